@@ -0,0 +1,42 @@
+//! Captures build metadata for `bento::build_info`.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    let git_sha = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|sha| sha.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // honour reproducible-build timestamps when set
+    let build_timestamp = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<i64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs() as i64)
+                .unwrap_or_default()
+        });
+
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR set by cargo");
+    fs::write(
+        Path::new(&out_dir).join("build_info.rs"),
+        format!(
+            "pub const GIT_SHA: &str = {git_sha:?};\npub const BUILD_TIMESTAMP: i64 = {build_timestamp};\n"
+        ),
+    )
+    .expect("write build_info.rs");
+}
@@ -1 +1,8 @@
 pub mod auth;
+pub mod config;
+pub mod metrics;
+pub mod openapi;
+pub mod projects;
+pub mod sessions;
+pub mod users;
+pub mod version;
@@ -2,16 +2,22 @@ use std::sync::Arc;
 
 use axum::{
     extract::{Json, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
 use axum_client_ip::ClientIp;
 use serde::{Deserialize, Serialize};
-use tracing::{debug, error};
+use time::OffsetDateTime;
+use tracing::{debug, error, warn};
 
 use crate::{
+    config::{CredentialLimits, LOCAL_CONF, Registration},
+    login_notice,
+    rate_limit::{IpLoginThrottle, RateLimited, too_many_requests},
+    registration::{self, RegistrationError},
+    security_log::{self, Outcome},
     storage::{AuthError, AuthStore},
-    types::{PasswordHash, Role, Session, SessionIp, Username},
+    types::{PasswordHash, PreviousLogin, Role, Session, SessionIp, UserAgentHash, Username},
 };
 
 #[derive(Debug, Deserialize)]
@@ -20,11 +26,71 @@ pub struct AuthRequest {
     password: String,
 }
 
+impl AuthRequest {
+    /// Checks the field lengths against the configured limits
+    pub fn validate(&self, limits: &CredentialLimits) -> Result<(), InvalidField> {
+        let username_len = self.username.0.chars().count();
+        let password_len = self.password.chars().count();
+
+        if username_len < limits.username_min_len {
+            Err(InvalidField::UsernameTooShort)
+        } else if username_len > limits.username_max_len {
+            Err(InvalidField::UsernameTooLong)
+        } else if password_len < limits.password_min_len {
+            Err(InvalidField::PasswordTooShort)
+        } else if password_len > limits.password_max_len {
+            Err(InvalidField::PasswordTooLong)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A rejected `AuthRequest` field, answered with 400 and `{"error": "<code>"}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidField {
+    UsernameTooShort,
+    UsernameTooLong,
+    PasswordTooShort,
+    PasswordTooLong,
+}
+
+impl InvalidField {
+    pub fn code(&self) -> &'static str {
+        match self {
+            InvalidField::UsernameTooShort => "username_too_short",
+            InvalidField::UsernameTooLong => "username_too_long",
+            InvalidField::PasswordTooShort => "password_too_short",
+            InvalidField::PasswordTooLong => "password_too_long",
+        }
+    }
+}
+
+impl IntoResponse for InvalidField {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": self.code() });
+        (StatusCode::BAD_REQUEST, Json(body)).into_response()
+    }
+}
+
+/// Body of `POST /api/v1/register`
+#[derive(Debug, Deserialize)]
+pub struct RegisterRequest {
+    #[serde(flatten)]
+    credentials: AuthRequest,
+    /// Redeemed for the role it was minted with; required with `[registration] require_invite`
+    #[serde(default)]
+    invite_code: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct AuthResponse {
     username: Username,
     role: Role,
     session: Session,
+    /// The login before this one; absent on registration and first login
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous_login: Option<PreviousLogin>,
 }
 
 impl IntoResponse for AuthError {
@@ -34,6 +100,17 @@ impl IntoResponse for AuthError {
     }
 }
 
+impl IntoResponse for RegistrationError {
+    fn into_response(self) -> Response {
+        match self {
+            RegistrationError::Disabled | RegistrationError::InviteRequired => {
+                StatusCode::FORBIDDEN.into_response()
+            }
+            RegistrationError::Store(err) => err.into_response(),
+        }
+    }
+}
+
 impl From<AuthError> for StatusCode {
     fn from(err: AuthError) -> Self {
         match err {
@@ -41,84 +118,203 @@ impl From<AuthError> for StatusCode {
             AuthError::NotFound => StatusCode::UNAUTHORIZED,
             AuthError::InvalidSession => StatusCode::FORBIDDEN,
             AuthError::SessionLimitReached => StatusCode::TOO_MANY_REQUESTS,
+            AuthError::InstanceSessionLimitReached => StatusCode::SERVICE_UNAVAILABLE,
+            AuthError::InvalidInvite => StatusCode::FORBIDDEN,
+            AuthError::AccountDisabled => StatusCode::FORBIDDEN,
+            AuthError::AccountLocked => StatusCode::LOCKED,
+            AuthError::PasswordReused => StatusCode::BAD_REQUEST,
+            AuthError::SessionDataTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
             AuthError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
 
-pub async fn register<S: AuthStore>(
+pub async fn register<S: AuthStore + ?Sized>(
     State(store): State<Arc<S>>,
     ClientIp(client_ip): ClientIp,
-    Json(req): Json<AuthRequest>,
+    headers: HeaderMap,
+    Json(req): Json<RegisterRequest>,
 ) -> Response {
     debug!("Registration attempt from IP: {}", client_ip);
 
-    if (store.get_user_by_username(&req.username).await).is_ok() {
-        debug!("Registration failed: username already exists");
-        StatusCode::BAD_REQUEST.into_response()
-    } else {
-        let AuthRequest { username, password } = req;
-        debug!("Creating new user");
-
-        match store
-            .create_standard_user(
-                &username,
-                PasswordHash::try_from(password.as_str()).unwrap(),
-            )
-            .await
-        {
-            Ok(user) => {
-                debug!(user_id = %user.id.0, "User created successfully");
-                // create token
-                debug!("Issuing session for new user");
-                match store.issue_session(&user.id, SessionIp(client_ip)).await {
-                    Ok(session) => {
-                        debug!(
-                            user_id = %user.id.0,
-                            expires_at = %session.expires_at,
-                            "Session created successfully"
-                        );
-                        let response = AuthResponse {
-                            username: user.username.clone(),
-                            role: user.role,
-                            session,
-                        };
-                        (StatusCode::CREATED, Json(response)).into_response()
-                    }
-                    Err(err) => {
-                        error!(user_id = %user.id.0, error = %err, "Failed to create session");
-                        err.into_response()
-                    }
+    if let Err(invalid) = req.credentials.validate(&LOCAL_CONF.credentials) {
+        debug!(code = invalid.code(), "Registration rejected");
+        return invalid.into_response();
+    }
+
+    register_account(
+        store.as_ref(),
+        req,
+        &LOCAL_CONF.registration,
+        LOCAL_CONF.privacy.ip_storage.session_ip(client_ip),
+        UserAgentHash::from_headers(&headers),
+    )
+    .await
+}
+
+/// Creates the account and signs it in, once the request passed validation.
+///
+/// Registrations `[registration]` doesn't allow are refused with 403 before the password is
+/// hashed. Duplicate usernames are left to the store, which checks and inserts in one step;
+/// checking here first would let two concurrent registrations of a name both pass.
+async fn register_account<S: AuthStore + ?Sized>(
+    store: &S,
+    req: RegisterRequest,
+    registration: &Registration,
+    ip: SessionIp,
+    user_agent: Option<UserAgentHash>,
+) -> Response {
+    let RegisterRequest {
+        credentials: AuthRequest { username, password },
+        invite_code,
+    } = req;
+    if let Err(err) = registration::check_allowed(registration, invite_code.as_deref()) {
+        debug!(error = %err, "Registration rejected");
+        return err.into_response();
+    }
+    debug!("Creating new user");
+
+    let pass_hash = match PasswordHash::try_from(password.as_str()) {
+        Ok(pass_hash) => pass_hash,
+        Err(err) => {
+            error!(error = %err, "Failed to hash password");
+            return AuthError::Internal(err.to_string()).into_response();
+        }
+    };
+    let created = registration::create_user(
+        store,
+        registration,
+        &username,
+        pass_hash,
+        invite_code.as_deref(),
+    )
+    .await;
+
+    match created {
+        Ok(user) if !user.is_active() => {
+            debug!(user_id = %user.id.0, "User created, awaiting approval");
+            let body = serde_json::json!({ "status": "pending_approval" });
+            (StatusCode::ACCEPTED, Json(body)).into_response()
+        }
+        Ok(user) => {
+            debug!(user_id = %user.id.0, "User created successfully");
+            // create token
+            debug!("Issuing session for new user");
+            match store.issue_session(&user.id, ip, user_agent).await {
+                Ok(session) => {
+                    debug!(
+                        user_id = %user.id.0,
+                        expires_at = %session.expires_at,
+                        "Session created successfully"
+                    );
+                    let response = AuthResponse {
+                        username: user.username.clone(),
+                        role: user.role,
+                        session,
+                        previous_login: None,
+                    };
+                    (StatusCode::CREATED, Json(response)).into_response()
+                }
+                Err(err) => {
+                    error!(user_id = %user.id.0, error = %err, "Failed to create session");
+                    err.into_response()
                 }
             }
-            Err(err) => {
-                error!(?err, "Failed to create user");
-                err.into_response()
-            }
+        }
+        Err(RegistrationError::Store(AuthError::UserExists)) => {
+            debug!("Registration failed: username already exists");
+            AuthError::UserExists.into_response()
+        }
+        Err(err) => {
+            error!(?err, "Failed to create user");
+            err.into_response()
         }
     }
 }
 
-pub async fn login<S: AuthStore>(
+pub async fn login<S: AuthStore + ?Sized>(
     State(store): State<Arc<S>>,
+    State(throttle): State<Arc<IpLoginThrottle>>,
     ClientIp(client_ip): ClientIp,
+    headers: HeaderMap,
     Json(req): Json<AuthRequest>,
 ) -> Response {
+    // the throttle sees the full address, anything kept only what `[privacy]` allows
+    let recorded_ip = LOCAL_CONF.privacy.ip_storage.apply(client_ip);
+    let log_event = |outcome| {
+        security_log::auth_event(
+            &LOCAL_CONF.logging,
+            "login",
+            &req.username.0,
+            recorded_ip,
+            outcome,
+        )
+    };
+
+    if let Err(invalid) = req.validate(&LOCAL_CONF.credentials) {
+        debug!(code = invalid.code(), "Login rejected");
+        log_event(Outcome::InvalidInput);
+        return invalid.into_response();
+    }
+    // refused before anything is checked, whichever account is targeted
+    if let Err(RateLimited { retry_after }) = throttle.check(&client_ip) {
+        debug!(ip = %client_ip, "Login rejected: address blocked");
+        log_event(Outcome::AddressBlocked);
+        return too_many_requests(retry_after);
+    }
+
     match store.get_user_by_username(&req.username).await {
         Ok(user) => {
+            // refused before the password check, so a lock can't be used to keep guessing
+            if user.is_locked(OffsetDateTime::now_utc()) {
+                debug!(user_id = %user.id.0, "Login rejected: account locked");
+                log_event(Outcome::AccountLocked);
+                return AuthError::AccountLocked.into_response();
+            }
             if user.password_hash.verify(&req.password) {
+                if !user.is_active() {
+                    debug!(user_id = %user.id.0, "Login rejected: account disabled");
+                    log_event(Outcome::AccountDisabled);
+                    return AuthError::AccountDisabled.into_response();
+                }
                 debug!(user_id = %user.id.0, "Password verified, issuing session");
-                match store.issue_session(&user.id, SessionIp(client_ip)).await {
+                // capture the prior login before it is overwritten
+                let recorded = store.record_login(&user.id, SessionIp(recorded_ip)).await;
+                let previous_login = match recorded {
+                    Ok(previous) => previous.last_login(),
+                    Err(err) => return err.into_response(),
+                };
+                match store
+                    .issue_session(
+                        &user.id,
+                        SessionIp(recorded_ip),
+                        UserAgentHash::from_headers(&headers),
+                    )
+                    .await
+                {
                     Ok(session) => {
                         debug!(
                             user_id = %user.id.0,
                             expires_at = %session.expires_at,
                             "Session created successfully"
                         );
+                        log_event(Outcome::Success);
+                        let notified = login_notice::notify_new_login(
+                            store.as_ref(),
+                            &LOCAL_CONF.security,
+                            previous_login.as_ref(),
+                            &session,
+                            &headers,
+                        )
+                        .await;
+                        if let Err(err) = notified {
+                            warn!(user_id = %user.id.0, error = %err, "Failed to leave new sign-in notices");
+                        }
                         let response = AuthResponse {
                             username: user.username,
                             role: user.role,
                             session,
+                            previous_login,
                         };
                         (StatusCode::OK, Json(response)).into_response()
                     }
@@ -129,9 +325,146 @@ pub async fn login<S: AuthStore>(
                 }
             } else {
                 debug!(user_id = %user.id.0, "Password verification failed");
+                log_event(Outcome::WrongPassword);
+                throttle.record_failure(client_ip, &req.username.0);
+                let lockout = &LOCAL_CONF.lockout;
+                if lockout.enabled() {
+                    let counted = store
+                        .record_failed_login(
+                            &user.id,
+                            lockout.max_failed_logins,
+                            lockout.duration(),
+                        )
+                        .await;
+                    if let Err(err) = counted {
+                        return err.into_response();
+                    }
+                }
                 StatusCode::UNAUTHORIZED.into_response()
             }
         }
-        Err(err) => err.into_response(),
+        Err(err) => {
+            if matches!(err, AuthError::NotFound) {
+                log_event(Outcome::UnknownUser);
+                throttle.record_failure(client_ip, &req.username.0);
+            } else {
+                log_event(Outcome::Error);
+            }
+            err.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(username: &str, password: &str) -> AuthRequest {
+        AuthRequest {
+            username: Username(username.to_string()),
+            password: password.to_string(),
+        }
+    }
+
+    #[test]
+    fn accepts_fields_within_limits() {
+        let limits = CredentialLimits::default();
+        assert_eq!(request("alice", "password").validate(&limits), Ok(()));
+        assert_eq!(
+            request(
+                &"a".repeat(limits.username_max_len),
+                &"p".repeat(limits.password_max_len)
+            )
+            .validate(&limits),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rejects_over_length_fields() {
+        let limits = CredentialLimits::default();
+        assert_eq!(
+            request(&"a".repeat(limits.username_max_len + 1), "password").validate(&limits),
+            Err(InvalidField::UsernameTooLong)
+        );
+        assert_eq!(
+            request("alice", &"p".repeat(1024 * 1024)).validate(&limits),
+            Err(InvalidField::PasswordTooLong)
+        );
+    }
+
+    #[test]
+    fn rejects_under_length_fields() {
+        let limits = CredentialLimits {
+            password_min_len: 8,
+            ..Default::default()
+        };
+        assert_eq!(
+            request("al", "password").validate(&limits),
+            Err(InvalidField::UsernameTooShort)
+        );
+        assert_eq!(
+            request("alice", "short").validate(&limits),
+            Err(InvalidField::PasswordTooShort)
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_field_is_a_bad_request_with_code() {
+        let response = InvalidField::PasswordTooLong.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            serde_json::json!({ "error": "password_too_long" })
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_registrations_of_a_name_create_one_user() {
+        use crate::storage::redb_authstore::RedbAuthStore;
+        use std::net::IpAddr;
+        use tokio::sync::Barrier;
+
+        let store = Arc::new(RedbAuthStore::in_memory(5).unwrap());
+        let registration: Arc<Registration> =
+            Arc::new(toml::from_str("allow_registration = true").unwrap());
+        // released together, so every attempt is in flight before any of them inserts
+        let start = Arc::new(Barrier::new(8));
+        let attempts: Vec<_> = (0..8)
+            .map(|_| {
+                let (store, registration, start) =
+                    (store.clone(), registration.clone(), start.clone());
+                tokio::spawn(async move {
+                    let req = RegisterRequest {
+                        credentials: request("alice", "password"),
+                        invite_code: None,
+                    };
+                    start.wait().await;
+                    register_account(
+                        store.as_ref(),
+                        req,
+                        &registration,
+                        SessionIp(IpAddr::from([127, 0, 0, 1])),
+                        None,
+                    )
+                    .await
+                    .status()
+                })
+            })
+            .collect();
+
+        let mut statuses = Vec::new();
+        for attempt in attempts {
+            statuses.push(attempt.await.unwrap());
+        }
+        statuses.sort();
+        let mut expected = vec![StatusCode::BAD_REQUEST; 7];
+        expected.insert(0, StatusCode::CREATED);
+        assert_eq!(statuses, expected);
+        assert_eq!(store.list_users().await.unwrap().len(), 1);
     }
 }
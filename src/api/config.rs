@@ -0,0 +1,22 @@
+use axum::{
+    Extension,
+    extract::Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
+use crate::{config::LOCAL_CONF, types::Role, webui::CurrentUser};
+
+/// `GET /api/v1/admin/config`, the configuration Bento is running with (admin only).
+///
+/// Every setting is included, defaults filled in for those `bento.toml` leaves out, with
+/// secrets redacted, see [crate::config::Config::redacted].
+pub async fn effective_config(Extension(current_user): Extension<Option<CurrentUser>>) -> Response {
+    match current_user {
+        Some(user) if user.role == Role::Admin => {}
+        Some(_) => return StatusCode::FORBIDDEN.into_response(),
+        None => return StatusCode::UNAUTHORIZED.into_response(),
+    }
+
+    (StatusCode::OK, Json(LOCAL_CONF.redacted())).into_response()
+}
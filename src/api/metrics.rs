@@ -0,0 +1,173 @@
+use std::fmt::Write;
+
+use axum::{
+    extract::State,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use tracing::error;
+
+use crate::{
+    server::AppState,
+    types::{DatabaseReport, MaintenanceReport},
+};
+
+/// `GET /metrics`, session cleanup counters, database sizes and blocking store operations in
+/// the Prometheus text format
+pub async fn metrics(State(state): State<AppState>) -> Response {
+    let databases = match (state.auth_db.stats().await, state.project_db.stats().await) {
+        (Ok(auth), Ok(projects)) => vec![auth, projects],
+        (Err(err), _) => {
+            error!(error = %err, "Failed to read auth database stats");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+        (_, Err(err)) => {
+            error!(error = %err, "Failed to read project database stats");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let blocking = BlockingReport {
+        in_flight: state.blocking.in_flight(),
+        queued: state.blocking.queue_depth(),
+    };
+    let body = render(&state.maintenance.report(), &databases, &blocking);
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response()
+}
+
+/// Reads one gauge off a database report; `None` leaves the database out
+type DatabaseGauge = fn(&DatabaseReport) -> Option<u64>;
+
+/// Snapshot of the blocking operation limiter
+struct BlockingReport {
+    in_flight: usize,
+    queued: usize,
+}
+
+fn render(
+    maintenance: &MaintenanceReport,
+    databases: &[DatabaseReport],
+    blocking: &BlockingReport,
+) -> String {
+    let mut out = String::new();
+
+    let counters = [
+        (
+            "bento_session_sweeps_total",
+            "Periodic expired session sweeps run",
+            maintenance.total_sweeps,
+        ),
+        (
+            "bento_sessions_swept_total",
+            "Expired sessions removed by sweeps",
+            maintenance.swept_sessions,
+        ),
+        (
+            "bento_sessions_lazily_purged_total",
+            "Expired sessions removed while being looked up",
+            maintenance.lazily_purged_sessions,
+        ),
+    ];
+    for (name, help, value) in counters {
+        let _ = writeln!(
+            out,
+            "# HELP {name} {help}\n# TYPE {name} counter\n{name} {value}"
+        );
+    }
+
+    let gauges: [(&str, &str, DatabaseGauge); 5] = [
+        (
+            "bento_db_file_size_bytes",
+            "Size of the database file",
+            |db| db.file_size_bytes,
+        ),
+        (
+            "bento_db_allocated_pages",
+            "Pages allocated in the database",
+            |db| Some(db.allocated_pages),
+        ),
+        ("bento_db_page_size_bytes", "Database page size", |db| {
+            Some(db.page_size)
+        }),
+        ("bento_db_stored_bytes", "Bytes of stored data", |db| {
+            Some(db.stored_bytes)
+        }),
+        (
+            "bento_db_fragmented_bytes",
+            "Bytes lost to partially filled pages",
+            |db| Some(db.fragmented_bytes),
+        ),
+    ];
+    for (name, help, value) in gauges {
+        let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} gauge");
+        for db in databases {
+            if let Some(value) = value(db) {
+                let _ = writeln!(out, "{name}{{db=\"{}\"}} {value}", db.name);
+            }
+        }
+    }
+
+    let blocking_gauges = [
+        (
+            "bento_storage_blocking_in_flight",
+            "Store operations running on the blocking thread pool",
+            blocking.in_flight,
+        ),
+        (
+            "bento_storage_blocking_queued",
+            "Store operations waiting for a blocking slot",
+            blocking.queued,
+        ),
+    ];
+    for (name, help, value) in blocking_gauges {
+        let _ = writeln!(
+            out,
+            "# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}"
+        );
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics_are_labelled_per_database() {
+        let maintenance = MaintenanceReport {
+            total_sweeps: 3,
+            swept_sessions: 7,
+            ..Default::default()
+        };
+        let auth = DatabaseReport {
+            name: "auth".to_string(),
+            file_size_bytes: Some(65536),
+            page_size: 4096,
+            allocated_pages: 16,
+            stored_bytes: 1000,
+            metadata_bytes: 200,
+            fragmented_bytes: 50,
+        };
+        let projects = DatabaseReport {
+            name: "projects".to_string(),
+            file_size_bytes: None,
+            ..auth.clone()
+        };
+
+        let blocking = BlockingReport {
+            in_flight: 4,
+            queued: 2,
+        };
+
+        let text = render(&maintenance, &[auth, projects], &blocking);
+        assert!(text.contains("bento_session_sweeps_total 3\n"));
+        assert!(text.contains("bento_sessions_swept_total 7\n"));
+        assert!(text.contains("bento_db_file_size_bytes{db=\"auth\"} 65536\n"));
+        assert!(text.contains("bento_db_stored_bytes{db=\"projects\"} 1000\n"));
+        // in-memory databases have no file to report
+        assert!(!text.contains("bento_db_file_size_bytes{db=\"projects\"}"));
+        assert!(text.contains("bento_storage_blocking_in_flight 4\n"));
+        assert!(text.contains("bento_storage_blocking_queued 2\n"));
+    }
+}
@@ -0,0 +1,413 @@
+//! Hand-written OpenAPI 3.1 description of the REST API, for generating clients.
+//!
+//! Keep this in sync with the routes registered in `main.rs` and the types they exchange.
+
+use axum::Json;
+use serde_json::{Value, json};
+
+/// `GET /api/v1/openapi.json`, available without authentication
+pub async fn openapi() -> Json<Value> {
+    Json(spec())
+}
+
+/// The OpenAPI document for every `/api/v1` endpoint
+pub fn spec() -> Value {
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "Bento REST API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": {
+            "/api/v1/register": {
+                "post": {
+                    "summary": "Create a new user account and sign it in",
+                    "requestBody": json_body("RegisterRequest"),
+                    "responses": {
+                        "201": json_response("Account created", "AuthResponse"),
+                        "202": json_response("Account created, but an admin has to enable it before it can sign in", "PendingApproval"),
+                        "400": json_response("Invalid credentials or username taken", "ErrorBody"),
+                        "403": { "description": "Registration is disabled, or an invite code is required and the one given is missing, invalid, expired or used up" },
+                        "429": { "description": "Session limit reached" },
+                        "500": { "description": "Internal error" },
+                    },
+                },
+            },
+            "/api/v1/login": {
+                "post": {
+                    "summary": "Authenticate and receive a session token",
+                    "requestBody": json_body("AuthRequest"),
+                    "responses": {
+                        "200": json_response("Signed in", "AuthResponse"),
+                        "400": json_response("Username or password outside the configured lengths", "ErrorBody"),
+                        "401": { "description": "Unknown user or wrong password" },
+                        "403": { "description": "Account disabled" },
+                        "423": { "description": "Account locked after too many wrong passwords, see `[lockout]`" },
+                        "429": { "description": "Session limit reached, or the address is blocked after failed logins to many accounts (with `Retry-After`)" },
+                        "500": { "description": "Internal error" },
+                    },
+                },
+            },
+            "/api/v1/users/bulk": {
+                "post": {
+                    "summary": "Create several accounts at once (admin only)",
+                    "description": "Every row is validated before any account is created. Rows that fail, e.g. on a taken username, are reported without stopping the others.",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "array",
+                                    "items": { "$ref": "#/components/schemas/NewUser" },
+                                },
+                            },
+                        },
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "One result per row, in order",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": { "$ref": "#/components/schemas/BulkUserResult" },
+                                    },
+                                },
+                            },
+                        },
+                        "401": { "description": "Not signed in" },
+                        "403": { "description": "Not an admin" },
+                    },
+                },
+            },
+            "/api/v1/admin/config": {
+                "get": {
+                    "summary": "Show the effective configuration (admin only)",
+                    "description": "Every setting Bento is running with, defaults included. The admin password, storage encryption key, webhook secret and webhook URLs are shown as `***`, or empty when unset.",
+                    "responses": {
+                        "200": {
+                            "description": "The configuration, one object per `bento.toml` section",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "type": "object" },
+                                },
+                            },
+                        },
+                        "401": { "description": "Not signed in" },
+                        "403": { "description": "Not an admin" },
+                    },
+                },
+            },
+            "/api/v1/sessions": {
+                "get": {
+                    "summary": "List your unexpired sessions",
+                    "description": "The session the request was made with is flagged `current`.",
+                    "responses": {
+                        "200": {
+                            "description": "Your sessions, most recently seen first",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": { "$ref": "#/components/schemas/ApiSession" },
+                                    },
+                                },
+                            },
+                        },
+                        "401": { "description": "Not signed in" },
+                    },
+                },
+            },
+            "/api/v1/sessions/{id}": {
+                "delete": {
+                    "summary": "Sign out one of your sessions",
+                    "parameters": [{
+                        "name": "id",
+                        "in": "path",
+                        "required": true,
+                        "description": "`id` from the session listing",
+                        "schema": { "type": "string" },
+                    }],
+                    "responses": {
+                        "204": { "description": "Session signed out" },
+                        "401": { "description": "Not signed in" },
+                        "403": { "description": "Not one of your sessions" },
+                    },
+                },
+            },
+            "/api/v1/version": {
+                "get": {
+                    "summary": "Server version, git commit and build time",
+                    "responses": {
+                        "200": json_response("Build information", "BuildInfo"),
+                    },
+                },
+            },
+            "/api/v1/openapi.json": {
+                "get": {
+                    "summary": "This document",
+                    "responses": {
+                        "200": { "description": "OpenAPI document" },
+                    },
+                },
+            },
+            "/api/v1/projects/{project_id}": {
+                "get": {
+                    "summary": "Read a project",
+                    "description": "Public and unlisted projects need no authentication; private ones only answer their owner.",
+                    "parameters": [{
+                        "name": "project_id",
+                        "in": "path",
+                        "required": true,
+                        "description": "The project's UUID or its `proj_<base62>` form",
+                        "schema": { "type": "string" },
+                    }],
+                    "responses": {
+                        "200": json_response("The project", "Project"),
+                        "400": { "description": "Malformed project ID" },
+                        "403": { "description": "Private project of another user" },
+                        "404": { "description": "No such project" },
+                        "429": { "description": "Project rate limit exceeded" },
+                    },
+                },
+            },
+        },
+        "components": {
+            "schemas": schemas(),
+        },
+    })
+}
+
+fn schemas() -> Value {
+    json!({
+        "AuthRequest": {
+            "type": "object",
+            "required": ["username", "password"],
+            "properties": {
+                "username": { "type": "string" },
+                "password": { "type": "string" },
+            },
+        },
+        "RegisterRequest": {
+            "type": "object",
+            "required": ["username", "password"],
+            "properties": {
+                "username": { "type": "string" },
+                "password": { "type": "string" },
+                "invite_code": { "type": "string", "description": "Required with `[registration] require_invite`" },
+            },
+        },
+        "AuthResponse": {
+            "type": "object",
+            "required": ["username", "role", "session"],
+            "properties": {
+                "username": { "type": "string" },
+                "role": { "$ref": "#/components/schemas/Role" },
+                "session": { "$ref": "#/components/schemas/Session" },
+                "previous_login": {
+                    "$ref": "#/components/schemas/PreviousLogin",
+                    "description": "The login before this one; absent on registration and first login",
+                },
+            },
+        },
+        "ErrorBody": {
+            "type": "object",
+            "required": ["error"],
+            "properties": {
+                "error": {
+                    "type": "string",
+                    "enum": [
+                        "username_too_short",
+                        "username_too_long",
+                        "password_too_short",
+                        "password_too_long",
+                    ],
+                },
+            },
+        },
+        "NewUser": {
+            "type": "object",
+            "required": ["username", "password", "role"],
+            "properties": {
+                "username": { "type": "string" },
+                "password": { "type": "string" },
+                "role": { "$ref": "#/components/schemas/Role" },
+            },
+        },
+        "BulkUserResult": {
+            "type": "object",
+            "required": ["username", "outcome"],
+            "properties": {
+                "username": { "type": "string" },
+                "outcome": {
+                    "description": "`{\"Ok\": <new user id>}` or `{\"Err\": <reason>}`",
+                    "oneOf": [
+                        {
+                            "type": "object",
+                            "required": ["Ok"],
+                            "properties": { "Ok": { "type": "string", "format": "uuid" } },
+                        },
+                        {
+                            "type": "object",
+                            "required": ["Err"],
+                            "properties": { "Err": { "type": "string" } },
+                        },
+                    ],
+                },
+            },
+        },
+        "PendingApproval": {
+            "type": "object",
+            "required": ["status"],
+            "properties": {
+                "status": { "type": "string", "enum": ["pending_approval"] },
+            },
+        },
+        "Role": {
+            "type": "string",
+            "enum": ["Admin", "User"],
+        },
+        "Session": {
+            "type": "object",
+            "required": ["id", "user_id", "ip", "created_at", "expires_at", "last_seen_at"],
+            "properties": {
+                "id": { "type": "string", "description": "Session token" },
+                "user_id": { "type": "string", "format": "uuid" },
+                "ip": { "type": "string" },
+                "created_at": { "$ref": "#/components/schemas/Timestamp" },
+                "expires_at": { "$ref": "#/components/schemas/Timestamp" },
+                "last_seen_at": { "$ref": "#/components/schemas/Timestamp" },
+            },
+        },
+        "PreviousLogin": {
+            "type": "object",
+            "required": ["at", "ip"],
+            "properties": {
+                "at": { "$ref": "#/components/schemas/Timestamp" },
+                "ip": { "type": "string" },
+            },
+        },
+        "Project": {
+            "type": "object",
+            "required": ["id", "owner_id", "name", "created_at", "updated_at", "visibility"],
+            "properties": {
+                "id": {
+                    "type": "string",
+                    "description": "A UUID, or `proj_<base62>` with `[projects] id_format = \"prefixed\"`",
+                },
+                "owner_id": { "type": "string", "format": "uuid" },
+                "name": { "type": "string" },
+                "description": { "type": ["string", "null"] },
+                "created_at": { "$ref": "#/components/schemas/Timestamp" },
+                "updated_at": { "$ref": "#/components/schemas/Timestamp" },
+                "rate_limit_rpm": {
+                    "type": ["integer", "null"],
+                    "minimum": 0,
+                    "description": "Requests per minute allowed on this project's endpoints; unlimited when null",
+                },
+                "visibility": { "$ref": "#/components/schemas/ProjectVisibility" },
+            },
+        },
+        "ProjectVisibility": {
+            "type": "string",
+            "enum": ["private", "unlisted", "public"],
+        },
+        "ApiSession": {
+            "type": "object",
+            "required": ["id", "ip", "created_at", "expires_at", "last_seen_at", "current"],
+            "properties": {
+                "id": { "type": "string", "description": "Public session id; never the session token" },
+                "ip": { "type": "string" },
+                "created_at": { "$ref": "#/components/schemas/Timestamp" },
+                "expires_at": { "$ref": "#/components/schemas/Timestamp" },
+                "last_seen_at": { "$ref": "#/components/schemas/Timestamp" },
+                "current": { "type": "boolean", "description": "Whether the request was made with this session" },
+            },
+        },
+        "BuildInfo": {
+            "type": "object",
+            "required": ["version", "git_sha", "built_at"],
+            "properties": {
+                "version": { "type": "string" },
+                "git_sha": { "type": "string" },
+                "built_at": { "type": "string", "format": "date-time" },
+            },
+        },
+        "Timestamp": {
+            "type": "array",
+            "items": { "type": "integer" },
+            "description": "UTC date and time as [year, day of year, hour, minute, second, nanosecond, offset hours, offset minutes, offset seconds]",
+        },
+    })
+}
+
+fn json_body(schema: &str) -> Value {
+    json!({
+        "required": true,
+        "content": {
+            "application/json": {
+                "schema": { "$ref": format!("#/components/schemas/{schema}") },
+            },
+        },
+    })
+}
+
+fn json_response(description: &str, schema: &str) -> Value {
+    json!({
+        "description": description,
+        "content": {
+            "application/json": {
+                "schema": { "$ref": format!("#/components/schemas/{schema}") },
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, http::Request, http::StatusCode, routing::get};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn served_spec_lists_every_endpoint() {
+        let app = Router::new().route("/api/v1/openapi.json", get(openapi));
+        let request = Request::builder()
+            .uri("/api/v1/openapi.json")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let spec: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(spec["openapi"], "3.1.0");
+        for path in [
+            "/api/v1/register",
+            "/api/v1/login",
+            "/api/v1/version",
+            "/api/v1/projects/{project_id}",
+            "/api/v1/users/bulk",
+            "/api/v1/admin/config",
+            "/api/v1/sessions",
+            "/api/v1/sessions/{id}",
+        ] {
+            assert!(spec["paths"][path].is_object(), "missing {path}");
+        }
+    }
+
+    #[test]
+    fn schema_references_resolve() {
+        let spec = spec();
+        let text = spec.to_string();
+        for reference in text.split("\"#/components/schemas/").skip(1) {
+            let name = reference.split('"').next().unwrap();
+            assert!(
+                spec["components"]["schemas"][name].is_object(),
+                "dangling reference to {name}"
+            );
+        }
+    }
+}
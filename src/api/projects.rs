@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use axum::{
+    Extension,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use crate::{
+    config::{LOCAL_CONF, ProjectIdFormat},
+    storage::{ProjectError, ProjectStore},
+    types::{Project, ProjectId, UserId},
+    webui::CurrentUser,
+};
+
+impl IntoResponse for ProjectError {
+    fn into_response(self) -> Response {
+        let status: StatusCode = self.into();
+        status.into_response()
+    }
+}
+
+impl From<ProjectError> for StatusCode {
+    fn from(err: ProjectError) -> Self {
+        match err {
+            ProjectError::NotFound => StatusCode::NOT_FOUND,
+            ProjectError::AlreadyExists | ProjectError::DeleteProtected => StatusCode::CONFLICT,
+            ProjectError::Unauthorized => StatusCode::FORBIDDEN,
+            ProjectError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// `GET /api/v1/projects/{project_id}`
+///
+/// Owners can always read their projects; anyone else, signed in or not, only gets
+/// unlisted and public ones. Private projects answer 403. The ID may be given as a UUID or
+/// in its `proj_` form; a malformed one answers 400.
+pub async fn get_project<S: ProjectStore + ?Sized>(
+    State(store): State<Arc<S>>,
+    Extension(viewer): Extension<Option<CurrentUser>>,
+    Path(project_id): Path<String>,
+) -> Response {
+    let project_id = match ProjectId::parse(&project_id) {
+        Ok(project_id) => project_id,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    };
+
+    let project = match store.get_project(&project_id).await {
+        Ok(project) => project,
+        Err(err) => {
+            if !matches!(err, ProjectError::NotFound) {
+                error!(project_id = %project_id.0, error = %err, "Failed to load project");
+            }
+            return err.into_response();
+        }
+    };
+
+    let viewer_id = viewer
+        .and_then(|user| Uuid::parse_str(&user.user_id).ok())
+        .map(UserId);
+    if !project.is_viewable_by(viewer_id.as_ref()) {
+        debug!(project_id = %project_id.0, "Project access denied");
+        return ProjectError::Unauthorized.into_response();
+    }
+
+    match project_json(&project, LOCAL_CONF.projects.id_format) {
+        Ok(json) => (StatusCode::OK, Json(json)).into_response(),
+        Err(err) => {
+            error!(project_id = %project_id.0, error = %err, "Failed to serialize project");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// `project` as JSON, with its `id` in the form `[projects] id_format` asks for
+fn project_json(
+    project: &Project,
+    format: ProjectIdFormat,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let mut json = serde_json::to_value(project)?;
+    json["id"] = format.render(&project.id).into();
+    Ok(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::redb_projectstore::RedbProjectStore;
+    use crate::types::{Project, ProjectVisibility, Role};
+    use axum::{Router, body::Body, http::Request, routing::get};
+    use tower::ServiceExt;
+
+    async fn call(
+        store: Arc<RedbProjectStore>,
+        viewer: Option<CurrentUser>,
+        project: &Project,
+    ) -> Response {
+        let app = Router::new()
+            .route(
+                "/projects/{project_id}",
+                get(get_project::<RedbProjectStore>),
+            )
+            .layer(Extension(viewer))
+            .with_state(store);
+        let request = Request::builder()
+            .uri(format!("/projects/{}", project.id.0))
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap()
+    }
+
+    async fn project_with(store: &RedbProjectStore, visibility: ProjectVisibility) -> Project {
+        let project = store
+            .create_project(&UserId::new(), visibility.as_str().to_string(), None, None)
+            .await
+            .unwrap()
+            .0;
+        store
+            .update_project(&project.id, None, None, None, Some(visibility))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn anonymous_access_follows_visibility() {
+        let store = Arc::new(RedbProjectStore::in_memory().unwrap());
+        let public = project_with(&store, ProjectVisibility::Public).await;
+        let unlisted = project_with(&store, ProjectVisibility::Unlisted).await;
+        let private = project_with(&store, ProjectVisibility::Private).await;
+
+        let response = call(store.clone(), None, &public).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let returned: Project = serde_json::from_slice(&body).unwrap();
+        assert_eq!(returned, public);
+
+        let response = call(store.clone(), None, &unlisted).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = call(store.clone(), None, &private).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn projects_are_found_by_their_prefixed_id() {
+        let store = Arc::new(RedbProjectStore::in_memory().unwrap());
+        let public = project_with(&store, ProjectVisibility::Public).await;
+        let app = Router::new()
+            .route(
+                "/projects/{project_id}",
+                get(get_project::<RedbProjectStore>),
+            )
+            .layer(Extension(None::<CurrentUser>))
+            .with_state(store);
+        let get = |id: String| {
+            let app = app.clone();
+            async move {
+                let request = Request::builder()
+                    .uri(format!("/projects/{id}"))
+                    .body(Body::empty())
+                    .unwrap();
+                app.oneshot(request).await.unwrap()
+            }
+        };
+
+        let response = get(public.id.prefixed()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = get("proj_tooshort".to_string()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, "Project ID must have 22 characters after \"proj_\"");
+
+        let json = project_json(&public, ProjectIdFormat::Prefixed).unwrap();
+        assert_eq!(json["id"], public.id.prefixed());
+        let json = project_json(&public, ProjectIdFormat::Uuid).unwrap();
+        assert_eq!(json["id"], public.id.0.to_string());
+    }
+
+    #[tokio::test]
+    async fn owner_can_read_private_project() {
+        let store = Arc::new(RedbProjectStore::in_memory().unwrap());
+        let private = project_with(&store, ProjectVisibility::Private).await;
+        let owner = CurrentUser {
+            username: "alice".to_string(),
+            role: Role::User,
+            user_id: private.owner_id.0.to_string(),
+            session_expires_at: None,
+            impersonated_by: None,
+            active_session_count: None,
+        };
+        let stranger = CurrentUser {
+            username: "mallory".to_string(),
+            role: Role::User,
+            user_id: UserId::new().0.to_string(),
+            session_expires_at: None,
+            impersonated_by: None,
+            active_session_count: None,
+        };
+
+        let response = call(store.clone(), Some(owner), &private).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = call(store, Some(stranger), &private).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
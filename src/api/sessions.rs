@@ -0,0 +1,221 @@
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use axum::{
+    Extension,
+    extract::{Json, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use crate::{
+    storage::AuthStore,
+    types::{SessionId, UserId},
+    webui::{CurrentUser, cookies::session_id_from_headers},
+};
+
+/// One of the caller's sessions, as listed by `GET /api/v1/sessions`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiSession {
+    /// [SessionId::public_id], for `DELETE /api/v1/sessions/{id}`; never the token itself
+    pub id: String,
+    pub ip: IpAddr,
+    pub created_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+    pub last_seen_at: OffsetDateTime,
+    /// Whether this is the session the request was made with
+    pub current: bool,
+}
+
+/// The signed-in caller and the session they called with
+fn caller(current_user: Option<CurrentUser>, headers: &HeaderMap) -> Option<(UserId, SessionId)> {
+    let user_id = Uuid::parse_str(&current_user?.user_id).ok().map(UserId)?;
+    let session_id = session_id_from_headers(headers)?;
+    Some((user_id, session_id))
+}
+
+/// `GET /api/v1/sessions`, the caller's unexpired sessions, most recently seen first
+pub async fn list_sessions<S: AuthStore + ?Sized>(
+    State(store): State<Arc<S>>,
+    Extension(current_user): Extension<Option<CurrentUser>>,
+    headers: HeaderMap,
+) -> Response {
+    let Some((user_id, current)) = caller(current_user, &headers) else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+
+    match store.list_user_sessions(&user_id).await {
+        Ok(sessions) => {
+            let sessions: Vec<ApiSession> = sessions
+                .into_iter()
+                .map(|session| ApiSession {
+                    id: session.id.public_id(),
+                    ip: session.ip.0,
+                    created_at: session.created_at,
+                    expires_at: session.expires_at,
+                    last_seen_at: session.last_seen_at,
+                    current: session.id == current,
+                })
+                .collect();
+            (StatusCode::OK, Json(sessions)).into_response()
+        }
+        Err(err) => {
+            error!(user_id = %user_id.0, error = %err, "Failed to list sessions");
+            err.into_response()
+        }
+    }
+}
+
+/// `DELETE /api/v1/sessions/{id}`, signs out one of the caller's sessions.
+///
+/// `id` is an id from the listing. Anything that isn't one of the caller's own sessions
+/// answers 403, whether or not it exists, so other users' sessions can't be probed.
+pub async fn revoke_session<S: AuthStore + ?Sized>(
+    State(store): State<Arc<S>>,
+    Extension(current_user): Extension<Option<CurrentUser>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    let Some((user_id, _)) = caller(current_user, &headers) else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+
+    let sessions = match store.list_user_sessions(&user_id).await {
+        Ok(sessions) => sessions,
+        Err(err) => {
+            error!(user_id = %user_id.0, error = %err, "Failed to list sessions");
+            return err.into_response();
+        }
+    };
+    let Some(session) = sessions
+        .into_iter()
+        .find(|session| session.id.public_id() == id)
+    else {
+        debug!(user_id = %user_id.0, "Refused to revoke a session the caller doesn't own");
+        return StatusCode::FORBIDDEN.into_response();
+    };
+
+    match store.revoke_session(&session.id).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(err) => {
+            error!(user_id = %user_id.0, error = %err, "Failed to revoke session");
+            err.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::mem_authstore::MemoryAuthStore;
+    use crate::types::{PasswordHash, Role, Session, SessionIp, User, Username};
+    use crate::webui::cookies::{SESSION_COOKIE_NAME, seal_session_id};
+    use axum::{
+        Router,
+        body::Body,
+        http::{Method, Request, header::COOKIE},
+        routing::{delete, get},
+    };
+    use tower::ServiceExt;
+
+    async fn user_with_sessions(store: &MemoryAuthStore, name: &str) -> (User, Session, Session) {
+        let user = store
+            .create_standard_user(
+                &Username(name.to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let first = store
+            .issue_session(&user.id, ip.clone(), None)
+            .await
+            .unwrap();
+        let second = store.issue_session(&user.id, ip, None).await.unwrap();
+        (user, first, second)
+    }
+
+    /// Calls the routes as `user`, signed in with `session`
+    async fn call(
+        store: Arc<MemoryAuthStore>,
+        user: &User,
+        session: &Session,
+        method: Method,
+        uri: &str,
+    ) -> Response {
+        let current_user = CurrentUser {
+            username: user.username.0.clone(),
+            role: Role::User,
+            user_id: user.id.0.to_string(),
+            session_expires_at: None,
+            impersonated_by: None,
+            active_session_count: None,
+        };
+        let app = Router::new()
+            .route("/sessions", get(list_sessions::<MemoryAuthStore>))
+            .route("/sessions/{id}", delete(revoke_session::<MemoryAuthStore>))
+            .layer(Extension(Some(current_user)))
+            .with_state(store);
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(
+                COOKIE,
+                format!("{SESSION_COOKIE_NAME}={}", seal_session_id(&session.id.0)),
+            )
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn listing_flags_the_current_session() {
+        let store = Arc::new(MemoryAuthStore::default());
+        let (alice, first, second) = user_with_sessions(&store, "alice").await;
+
+        let response = call(store, &alice, &first, Method::GET, "/sessions").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let sessions: Vec<ApiSession> = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(sessions.len(), 2);
+        let current: Vec<_> = sessions.iter().filter(|s| s.current).collect();
+        assert_eq!(current.len(), 1);
+        assert_eq!(current[0].id, first.id.public_id());
+        assert!(sessions.iter().any(|s| s.id == second.id.public_id()));
+        // the tokens themselves stay out of the response
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(!text.contains(&first.id.0) && !text.contains(&second.id.0));
+    }
+
+    #[tokio::test]
+    async fn revoking_an_own_session_signs_it_out() {
+        let store = Arc::new(MemoryAuthStore::default());
+        let (alice, first, second) = user_with_sessions(&store, "alice").await;
+
+        let uri = format!("/sessions/{}", second.id.public_id());
+        let response = call(store.clone(), &alice, &first, Method::DELETE, &uri).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        assert!(store.fetch_session(&second.id).await.is_err());
+        assert!(store.fetch_session(&first.id).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn revoking_someone_elses_session_is_forbidden() {
+        let store = Arc::new(MemoryAuthStore::default());
+        let (alice, alices_session, _) = user_with_sessions(&store, "alice").await;
+        let (_, bobs_session, _) = user_with_sessions(&store, "bob").await;
+
+        let uri = format!("/sessions/{}", bobs_session.id.public_id());
+        let response = call(store.clone(), &alice, &alices_session, Method::DELETE, &uri).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(store.fetch_session(&bobs_session.id).await.is_ok());
+    }
+}
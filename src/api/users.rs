@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use axum::{
+    Extension,
+    extract::{Json, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use tracing::debug;
+
+use crate::{
+    config::LOCAL_CONF,
+    storage::AuthStore,
+    types::{NewUser, Role},
+    webui::{CurrentUser, create_users},
+};
+
+/// `POST /api/v1/users/bulk`, the REST counterpart of `bulk_create_users` (admin only).
+///
+/// Answers 200 with one result per row, in order, even when some rows failed. Needs an
+/// admin session cookie; the JSON body can't be sent cross-site without a CORS preflight.
+pub async fn bulk_create_users<S: AuthStore + ?Sized>(
+    State(store): State<Arc<S>>,
+    Extension(current_user): Extension<Option<CurrentUser>>,
+    Json(users): Json<Vec<NewUser>>,
+) -> Response {
+    match current_user {
+        Some(user) if user.role == Role::Admin => {}
+        Some(_) => return StatusCode::FORBIDDEN.into_response(),
+        None => return StatusCode::UNAUTHORIZED.into_response(),
+    }
+
+    debug!(rows = users.len(), "Bulk user creation");
+    let results = create_users(store.as_ref(), &LOCAL_CONF.credentials, users).await;
+    (StatusCode::OK, Json(results)).into_response()
+}
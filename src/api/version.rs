@@ -0,0 +1,8 @@
+use axum::Json;
+
+use crate::build_info::BuildInfo;
+
+/// `GET /api/v1/version`, available without authentication
+pub async fn version() -> Json<BuildInfo> {
+    Json(BuildInfo::current())
+}
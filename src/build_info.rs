@@ -0,0 +1,47 @@
+//! Version and build metadata of the running server.
+//!
+//! The git SHA and build timestamp are generated by `build.rs` into `OUT_DIR`.
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/build_info.rs"));
+}
+
+pub use generated::{BUILD_TIMESTAMP, GIT_SHA};
+
+/// Crate version, e.g. "0.1.0"
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub version: String,
+    /// Short commit hash, or "unknown" when built outside a git checkout
+    pub git_sha: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub built_at: OffsetDateTime,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        Self {
+            version: VERSION.to_string(),
+            git_sha: GIT_SHA.to_string(),
+            built_at: OffsetDateTime::from_unix_timestamp(BUILD_TIMESTAMP)
+                .unwrap_or(OffsetDateTime::UNIX_EPOCH),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_matches_crate_version() {
+        let info = BuildInfo::current();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.git_sha.is_empty());
+    }
+}
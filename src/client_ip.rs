@@ -0,0 +1,202 @@
+//! Picks where the client IP of a request is read from.
+//!
+//! `X-Forwarded-For` is only believed when the connection comes from one of the
+//! `[server] trusted_proxies`; anyone else could put an arbitrary address in it. Handlers keep
+//! using `axum_client_ip::ClientIp`, which reads the source [select_client_ip_source] chose.
+
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use axum_client_ip::ClientIpSource;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+#[error("Invalid CIDR {0:?}, expected e.g. \"10.0.0.0/8\" or \"::1/128\"")]
+pub struct InvalidCidr(pub String);
+
+/// An IP network such as `10.0.0.0/8`; a bare address counts as a single host
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl Cidr {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = InvalidCidr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidCidr(s.to_string());
+        let (network, prefix_len) = match s.split_once('/') {
+            Some((network, prefix_len)) => (network, Some(prefix_len)),
+            None => (s, None),
+        };
+        let network = network
+            .trim()
+            .parse::<IpAddr>()
+            .map_err(|_| invalid())?
+            .to_canonical();
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len.trim().parse::<u8>().map_err(|_| invalid())?,
+            None => max_len,
+        };
+        if prefix_len > max_len {
+            return Err(invalid());
+        }
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+/// The proxies allowed to report the client IP in `X-Forwarded-For`
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Vec<Cidr>);
+
+impl TrustedProxies {
+    pub fn parse<S: AsRef<str>>(cidrs: &[S]) -> Result<Self, InvalidCidr> {
+        cidrs
+            .iter()
+            .map(|cidr| cidr.as_ref().parse())
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|cidr| cidr.contains(ip))
+    }
+}
+
+/// Inserts the `ClientIpSource` for this request: the rightmost `X-Forwarded-For` entry
+/// (the address the proxy saw) when the peer is a trusted proxy that sent one, otherwise
+/// the connection IP.
+///
+/// # Example
+/// ```ignore
+/// let app = Router::new()
+///     .merge(ssr)
+///     .layer(from_fn_with_state(Arc::new(trusted_proxies), select_client_ip_source));
+/// ```
+pub async fn select_client_ip_source(
+    State(trusted_proxies): State<Arc<TrustedProxies>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let forwarded = request.headers().contains_key("x-forwarded-for");
+    let source = if forwarded && trusted_proxies.contains(peer.ip()) {
+        ClientIpSource::RightmostXForwardedFor
+    } else {
+        ClientIpSource::ConnectInfo
+    };
+
+    request.extensions_mut().insert(source);
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, middleware::from_fn_with_state, routing::get};
+    use axum_client_ip::ClientIp;
+    use tower::ServiceExt;
+
+    fn app(trusted: &[&str]) -> Router {
+        let trusted_proxies = Arc::new(TrustedProxies::parse(trusted).unwrap());
+        Router::new()
+            .route(
+                "/",
+                get(|ClientIp(ip): ClientIp| async move { ip.to_string() }),
+            )
+            .layer(from_fn_with_state(trusted_proxies, select_client_ip_source))
+    }
+
+    async fn client_ip(app: Router, peer: [u8; 4], forwarded_for: Option<&str>) -> String {
+        let mut request = Request::builder().uri("/");
+        if let Some(forwarded_for) = forwarded_for {
+            request = request.header("x-forwarded-for", forwarded_for);
+        }
+        let mut request = request.body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from((peer, 40000))));
+
+        let response = app.oneshot(request).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn cidrs_match_their_network_only() {
+        let private: Cidr = "10.0.0.0/8".parse().unwrap();
+        assert!(private.contains("10.20.30.40".parse().unwrap()));
+        assert!(!private.contains("11.0.0.1".parse().unwrap()));
+        // IPv4-mapped peers from dual-stack listeners
+        assert!(private.contains("::ffff:10.0.0.1".parse().unwrap()));
+
+        let host: Cidr = "192.168.1.5".parse().unwrap();
+        assert!(host.contains("192.168.1.5".parse().unwrap()));
+        assert!(!host.contains("192.168.1.6".parse().unwrap()));
+
+        let loopback: Cidr = "::1/128".parse().unwrap();
+        assert!(loopback.contains("::1".parse().unwrap()));
+        assert!(!loopback.contains("127.0.0.1".parse().unwrap()));
+
+        let everything: Cidr = "0.0.0.0/0".parse().unwrap();
+        assert!(everything.contains("203.0.113.9".parse().unwrap()));
+
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("proxy.internal".parse::<Cidr>().is_err());
+    }
+
+    #[tokio::test]
+    async fn trusted_proxy_forwarded_for_is_honored() {
+        let app = app(&["10.0.0.0/8"]);
+        let ip = client_ip(app, [10, 0, 0, 2], Some("198.51.100.1, 203.0.113.7")).await;
+        assert_eq!(ip, "203.0.113.7");
+    }
+
+    #[tokio::test]
+    async fn untrusted_peer_forwarded_for_is_ignored() {
+        let app = app(&["10.0.0.0/8"]);
+        let ip = client_ip(app.clone(), [192, 0, 2, 50], Some("203.0.113.7")).await;
+        assert_eq!(ip, "192.0.2.50");
+
+        // a trusted proxy that didn't forward anything is the client itself
+        let ip = client_ip(app, [10, 0, 0, 2], None).await;
+        assert_eq!(ip, "10.0.0.2");
+    }
+}
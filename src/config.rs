@@ -1,10 +1,21 @@
 use std::sync::LazyLock;
 
-use crate::types::Username;
+use crate::client_ip::TrustedProxies;
+use crate::hashing::Argon2Params;
+use crate::storage::backend::{SessionStorage, StorageBackend};
+use crate::storage::codec::{CodecError, EncryptedCodec, ValueCodec};
+use crate::types::{Branding, ProjectId, Role, SessionId, SessionIp, UserStatus, Username};
+use crate::webhooks::ProjectEventKind;
+use axum::http::StatusCode;
 use axum_extra::extract::cookie::Key;
 use serde::{Deserialize, Serialize};
 use time::Duration;
 use toml::de;
+use tower_http::compression::{
+    CompressionLayer, CompressionLevel, Predicate,
+    predicate::{And, DefaultPredicate, SizeAbove},
+};
+use tower_http::timeout::TimeoutLayer;
 
 /*
  * Constants
@@ -18,11 +29,51 @@ pub const SESSION_DURATION: Duration = Duration::hours(1);
  */
 pub static LOCAL_CONF: LazyLock<Config> = LazyLock::new(|| grab_config().expect("bento.toml file"));
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct Config {
-    pub admin: Admin,
+    /// Account created at startup; optional with `[registration] first_user_is_admin`
+    #[serde(default)]
+    pub admin: Option<Admin>,
     #[serde(default)]
     pub server: Server,
+    #[serde(default)]
+    pub storage: Storage,
+    #[serde(default)]
+    pub registration: Registration,
+    #[serde(default)]
+    pub compression: Compression,
+    #[serde(default)]
+    pub passkeys: Passkeys,
+    #[serde(default)]
+    pub webhooks: Webhooks,
+    #[serde(default)]
+    pub security: Security,
+    #[serde(default)]
+    pub credentials: CredentialLimits,
+    #[serde(default)]
+    pub branding: Branding,
+    #[serde(default)]
+    pub argon2: Argon2Tuning,
+    #[serde(default)]
+    pub session: Session,
+    #[serde(default)]
+    pub projects: Projects,
+    #[serde(default)]
+    pub cookie: Cookie,
+    #[serde(default)]
+    pub lockout: Lockout,
+    #[serde(default)]
+    pub accounts: Accounts,
+    #[serde(default)]
+    pub logging: Logging,
+    #[serde(default)]
+    pub privacy: Privacy,
+    #[serde(default)]
+    pub ui: Ui,
+    #[serde(default)]
+    pub secrets_backup: SecretsBackup,
+    #[serde(default)]
+    pub auth: Auth,
 }
 
 impl AsRef<Config> for Config {
@@ -31,18 +82,71 @@ impl AsRef<Config> for Config {
     }
 }
 
-#[derive(Deserialize)]
+impl Config {
+    /// The effective configuration, defaults filled in, for `GET /api/v1/admin/config`.
+    ///
+    /// Serializing the config always redacts secrets (the admin password, the storage
+    /// encryption key, the webhook secret and endpoint URLs) to [REDACTED], or leaves them
+    /// empty when unset; the cookie key lives in `.bento_secrets` and isn't part of it.
+    pub fn redacted(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("config serializes to JSON")
+    }
+}
+
+/// Stands in for secrets in the serialized config
+pub const REDACTED: &str = "***";
+
+fn redact<S: serde::Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(if value.is_empty() { "" } else { REDACTED })
+}
+
+fn redact_option<S: serde::Serializer>(
+    value: &Option<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => redact(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Admin {
     pub username: Username,
+    #[serde(serialize_with = "redact")]
     pub password: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct Server {
     #[serde(default = "default_address")]
     pub address: String,
     #[serde(default = "default_port")]
     pub port: u16,
+    /// Requests taking longer than this are answered with 408 Request Timeout
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+    /// Idle time before TCP keep-alive probes are sent on client connections; 0 disables
+    /// keep-alive
+    #[serde(default = "default_tcp_keepalive_secs")]
+    pub tcp_keepalive_secs: u64,
+    /// How often expired sessions are swept from the auth store; 0 disables the sweeper
+    /// (expired sessions are then only removed when they are next looked up)
+    #[serde(default = "default_session_sweep_interval_secs")]
+    pub session_sweep_interval_secs: u64,
+    /// CIDRs of reverse proxies whose `X-Forwarded-For` header is believed; requests from
+    /// any other peer are attributed to the connection IP
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+    /// Have `login` answer with a redirect (`Location`) to where the user was headed, instead
+    /// of leaving it to the login page to reload. Also lets signing in work without
+    /// JavaScript.
+    #[serde(default)]
+    pub server_login_redirect: bool,
+    /// Requests one client IP may have in flight at once before it is answered with 503;
+    /// 0 disables the limit
+    #[serde(default)]
+    pub max_concurrent_per_ip: usize,
 }
 
 impl Default for Server {
@@ -50,6 +154,12 @@ impl Default for Server {
         Self {
             address: default_address(),
             port: default_port(),
+            request_timeout_secs: default_request_timeout_secs(),
+            tcp_keepalive_secs: default_tcp_keepalive_secs(),
+            session_sweep_interval_secs: default_session_sweep_interval_secs(),
+            trusted_proxies: Vec::new(),
+            server_login_redirect: false,
+            max_concurrent_per_ip: 0,
         }
     }
 }
@@ -59,6 +169,748 @@ impl Server {
     pub fn socket_addr(&self) -> String {
         format!("{}:{}", self.address, self.port)
     }
+
+    /// Builds the layer that aborts requests running past `request_timeout_secs`
+    pub fn timeout_layer(&self) -> TimeoutLayer {
+        TimeoutLayer::with_status_code(
+            StatusCode::REQUEST_TIMEOUT,
+            std::time::Duration::from_secs(self.request_timeout_secs),
+        )
+    }
+
+    /// TCP keep-alive idle time for accepted connections, if enabled
+    pub fn tcp_keepalive(&self) -> Option<std::time::Duration> {
+        (self.tcp_keepalive_secs > 0)
+            .then(|| std::time::Duration::from_secs(self.tcp_keepalive_secs))
+    }
+
+    /// Interval between expired session sweeps, if enabled
+    pub fn session_sweep_interval(&self) -> Option<std::time::Duration> {
+        (self.session_sweep_interval_secs > 0)
+            .then(|| std::time::Duration::from_secs(self.session_sweep_interval_secs))
+    }
+
+    /// Parses `trusted_proxies`, failing on the first invalid entry
+    pub fn trusted_proxies(&self) -> Result<TrustedProxies, String> {
+        TrustedProxies::parse(&self.trusted_proxies)
+            .map_err(|e| format!("[server] trusted_proxies: {e}"))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    /// Encrypt serialized records in the redb files (ChaCha20-Poly1305).
+    ///
+    /// Enabling this on an existing plaintext database requires a migration pass
+    /// that re-encodes every record; the stores do not convert data on the fly.
+    #[serde(default)]
+    pub encrypt_at_rest: bool,
+    /// Optional base64url-encoded 32-byte key. When absent, the key is derived
+    /// from the cookie key in `.bento_secrets`.
+    #[serde(default, serialize_with = "redact_option")]
+    pub encryption_key: Option<String>,
+    /// "redb" (database files), "memory" (lost on restart) or "auto" (files, falling back to
+    /// memory if they can't be opened)
+    #[serde(default)]
+    pub backend: StorageBackend,
+    /// Most store operations running on the blocking thread pool at once; further ones wait
+    /// for a slot. 0 removes the limit
+    #[serde(default = "default_max_blocking")]
+    pub max_blocking: usize,
+    /// Rebuild the project owner index from the projects at startup, repairing listings
+    /// after a crash left the two out of step
+    #[serde(default)]
+    pub reconcile_indexes: bool,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            encrypt_at_rest: false,
+            encryption_key: None,
+            backend: StorageBackend::default(),
+            max_blocking: default_max_blocking(),
+            reconcile_indexes: false,
+        }
+    }
+}
+
+impl Storage {
+    /// Builds the value codec for the stores according to this configuration
+    pub fn value_codec(&self, cookie_key: &Key) -> Result<ValueCodec, CodecError> {
+        if !self.encrypt_at_rest {
+            return Ok(ValueCodec::plaintext());
+        }
+
+        let codec = match &self.encryption_key {
+            Some(encoded) => {
+                let bytes = Base64Url
+                    .decode(encoded)
+                    .map_err(|e| CodecError::InvalidKey(e.to_string()))?;
+                EncryptedCodec::new(&bytes)?
+            }
+            // the zero key of `Secrets::default` is public, so nothing may be encrypted with it
+            None if cookie_key.master().iter().all(|&byte| byte == 0) => {
+                return Err(CodecError::InvalidKey(
+                    "the cookie key in .bento_secrets is all zeros, set [storage] encryption_key \
+                     or generate new secrets"
+                        .to_string(),
+                ));
+            }
+            None => EncryptedCodec::derive_from(cookie_key.master()),
+        };
+        Ok(ValueCodec::encrypted(codec))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Registration {
+    /// Whether visitors may create their own accounts (closed by default)
+    #[serde(default)]
+    pub allow_registration: bool,
+    /// Only allow registration with a valid invite code
+    #[serde(default)]
+    pub require_invite: bool,
+    /// Role of self-registered users; invited users get their invite's role instead
+    #[serde(default = "default_registration_role")]
+    pub default_role: Role,
+    /// Create self-registered users disabled, so an admin has to enable them first
+    #[serde(default)]
+    pub require_approval: bool,
+    /// Make whoever registers first an admin, instead of creating the `[admin]` account
+    #[serde(default)]
+    pub first_user_is_admin: bool,
+}
+
+impl Default for Registration {
+    fn default() -> Self {
+        Self {
+            allow_registration: false,
+            require_invite: false,
+            default_role: default_registration_role(),
+            require_approval: false,
+            first_user_is_admin: false,
+        }
+    }
+}
+
+impl Registration {
+    /// Status of self-registered users
+    pub fn initial_status(&self) -> UserStatus {
+        if self.require_approval {
+            UserStatus::Disabled
+        } else {
+            UserStatus::Active
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Security {
+    /// Tell users whether a failed login was due to an unknown username or a wrong
+    /// password. Off by default, as distinguishing them lets anyone probe for accounts.
+    #[serde(default)]
+    pub reveal_login_errors: bool,
+    /// Require a double-submit CSRF token on mutating server functions
+    #[serde(default)]
+    pub csrf: bool,
+    /// Sensitive actions, like renaming the account or resetting someone's password, ask
+    /// for the password again once it was last entered longer ago than this
+    #[serde(default = "default_reauth_max_age_secs")]
+    pub reauth_max_age_secs: u64,
+    /// How many recent passwords, the current one included, a new password may not match
+    /// when changing or resetting it. 0 allows any
+    #[serde(default)]
+    pub password_history: usize,
+    /// Tell a user's other sessions when someone signs in to their account, see
+    /// [crate::login_notice]
+    #[serde(default)]
+    pub notify_new_login: bool,
+}
+
+impl Default for Security {
+    fn default() -> Self {
+        Self {
+            reveal_login_errors: false,
+            csrf: false,
+            reauth_max_age_secs: default_reauth_max_age_secs(),
+            password_history: 0,
+            notify_new_login: false,
+        }
+    }
+}
+
+impl Security {
+    pub fn reauth_max_age(&self) -> Duration {
+        Duration::seconds(self.reauth_max_age_secs.try_into().unwrap_or(i64::MAX))
+    }
+}
+
+/// Length bounds (in characters) for usernames and passwords sent to the REST API,
+/// checked before any password hashing happens
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CredentialLimits {
+    #[serde(default = "default_username_min_len")]
+    pub username_min_len: usize,
+    #[serde(default = "default_username_max_len")]
+    pub username_max_len: usize,
+    #[serde(default = "default_password_min_len")]
+    pub password_min_len: usize,
+    #[serde(default = "default_password_max_len")]
+    pub password_max_len: usize,
+}
+
+impl Default for CredentialLimits {
+    fn default() -> Self {
+        Self {
+            username_min_len: default_username_min_len(),
+            username_max_len: default_username_max_len(),
+            password_min_len: default_password_min_len(),
+            password_max_len: default_password_max_len(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Session {
+    /// Random bytes per session token, at least 16
+    #[serde(default = "default_session_token_bytes")]
+    pub token_bytes: usize,
+    /// Only accept a session cookie from the browser (User-Agent) it was issued to, so a
+    /// stolen cookie replayed elsewhere is refused. Browser updates within the same major
+    /// version are tolerated, but a major update signs the user out.
+    #[serde(default)]
+    pub bind_user_agent: bool,
+    /// Where sessions are kept, apart from the users if wanted
+    #[serde(default)]
+    pub store: SessionStorage,
+    /// Active sessions allowed across all users, to protect a small server; unset for no cap
+    #[serde(default)]
+    pub max_total: Option<usize>,
+    /// When a login hits the per-user session limit, answer with the user's sessions so one
+    /// can be signed out to continue, instead of a generic error
+    #[serde(default)]
+    pub list_on_limit: bool,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            token_bytes: default_session_token_bytes(),
+            bind_user_agent: false,
+            store: SessionStorage::default(),
+            max_total: None,
+            list_on_limit: false,
+        }
+    }
+}
+
+impl Session {
+    /// Rejects settings that would weaken session tokens
+    pub fn validate(&self) -> Result<(), String> {
+        if self.token_bytes < SessionId::MIN_BYTES {
+            return Err(format!(
+                "[session] token_bytes must be at least {}, got {}",
+                SessionId::MIN_BYTES,
+                self.token_bytes
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Projects {
+    /// How long a `create_project` idempotency key replays the project it created
+    #[serde(default = "default_idempotency_window_secs")]
+    pub idempotency_window_secs: u64,
+    /// Derive a new slug from the new name on rename, instead of keeping `/p/{slug}` links
+    /// working
+    #[serde(default)]
+    pub regenerate_slug_on_rename: bool,
+    /// Seconds a user has to wait between creating two projects; 0 disables the cooldown.
+    /// Admins are exempt.
+    #[serde(default)]
+    pub creation_cooldown_secs: u64,
+    /// Most projects a user may own; 0 means no limit. Admins are exempt.
+    #[serde(default)]
+    pub max_per_user: u32,
+    /// How the REST API writes project IDs in its responses; either form is accepted
+    #[serde(default)]
+    pub id_format: ProjectIdFormat,
+    /// Seconds between refreshes of every project's usage metrics; 0 disables collection
+    #[serde(default = "default_metrics_interval_secs")]
+    pub metrics_interval_secs: u64,
+}
+
+impl Default for Projects {
+    fn default() -> Self {
+        Self {
+            idempotency_window_secs: default_idempotency_window_secs(),
+            regenerate_slug_on_rename: false,
+            creation_cooldown_secs: 0,
+            max_per_user: 0,
+            id_format: ProjectIdFormat::default(),
+            metrics_interval_secs: default_metrics_interval_secs(),
+        }
+    }
+}
+
+impl Projects {
+    pub fn idempotency_window(&self) -> Duration {
+        Duration::seconds(self.idempotency_window_secs.try_into().unwrap_or(i64::MAX))
+    }
+
+    pub fn creation_cooldown(&self) -> Duration {
+        Duration::seconds(self.creation_cooldown_secs.try_into().unwrap_or(i64::MAX))
+    }
+
+    /// Interval between metrics collection runs, if enabled
+    pub fn metrics_interval(&self) -> Option<std::time::Duration> {
+        (self.metrics_interval_secs > 0)
+            .then(|| std::time::Duration::from_secs(self.metrics_interval_secs))
+    }
+
+    /// The project limit for a user with `role`, `None` if they may own any number
+    pub fn max_projects_for(&self, role: Role) -> Option<u32> {
+        (self.max_per_user > 0 && role != Role::Admin).then_some(self.max_per_user)
+    }
+}
+
+/// Form of project IDs in REST responses
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectIdFormat {
+    /// The UUID, as stored
+    #[default]
+    Uuid,
+    /// Opaque `proj_<base62>`, see [ProjectId::prefixed]
+    Prefixed,
+}
+
+impl ProjectIdFormat {
+    pub fn render(self, id: &ProjectId) -> String {
+        match self {
+            Self::Uuid => id.0.to_string(),
+            Self::Prefixed => id.prefixed(),
+        }
+    }
+}
+
+/// Locking accounts after repeated wrong passwords
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Lockout {
+    /// Wrong passwords or refused passkeys in a row that lock an account; 0 disables locking
+    #[serde(default)]
+    pub max_failed_logins: u32,
+    /// How long a locked account refuses logins, unless an admin unlocks it
+    #[serde(default = "default_lock_minutes")]
+    pub lock_minutes: u64,
+    /// Different accounts one address may fail to log in to within `ip_window_minutes`
+    /// before it is blocked; 0 disables blocking addresses
+    #[serde(default)]
+    pub ip_max_accounts: u32,
+    #[serde(default = "default_lock_minutes")]
+    pub ip_window_minutes: u64,
+    /// How long a blocked address is refused logins to any account
+    #[serde(default = "default_lock_minutes")]
+    pub ip_block_minutes: u64,
+}
+
+impl Default for Lockout {
+    fn default() -> Self {
+        Self {
+            max_failed_logins: 0,
+            lock_minutes: default_lock_minutes(),
+            ip_max_accounts: 0,
+            ip_window_minutes: default_lock_minutes(),
+            ip_block_minutes: default_lock_minutes(),
+        }
+    }
+}
+
+impl Lockout {
+    pub fn enabled(&self) -> bool {
+        self.max_failed_logins > 0
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::minutes(self.lock_minutes.try_into().unwrap_or(i64::MAX))
+    }
+
+    pub fn ip_window(&self) -> Duration {
+        Duration::minutes(self.ip_window_minutes.try_into().unwrap_or(i64::MAX))
+    }
+
+    pub fn ip_block_duration(&self) -> Duration {
+        Duration::minutes(self.ip_block_minutes.try_into().unwrap_or(i64::MAX))
+    }
+}
+
+/// Disabling accounts nobody has used in a while
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Accounts {
+    /// Days without a login (or re-enabling) after which an account is disabled; 0 never
+    /// disables accounts
+    #[serde(default)]
+    pub inactivity_days: u64,
+    /// Leave admin accounts enabled however long they go unused
+    #[serde(default = "default_true")]
+    pub exempt_admins: bool,
+}
+
+impl Default for Accounts {
+    fn default() -> Self {
+        Self {
+            inactivity_days: 0,
+            exempt_admins: true,
+        }
+    }
+}
+
+impl Accounts {
+    /// How long an account may go unused, `None` when inactive accounts are kept
+    pub fn inactivity(&self) -> Option<Duration> {
+        let secs = self.inactivity_days.saturating_mul(24 * 60 * 60);
+        (secs > 0).then(|| Duration::seconds(secs.try_into().unwrap_or(i64::MAX)))
+    }
+}
+
+/// Security logging of authentication events, separate from the general log output (see
+/// `crate::security_log`)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Logging {
+    /// Log each login attempt with its address and outcome, successes at `info` and
+    /// failures at `warn`, under the `bento::security` target
+    #[serde(default)]
+    pub security_events: bool,
+    /// Log a hash of the username in those events instead of the username itself
+    #[serde(default)]
+    pub hash_usernames: bool,
+}
+
+/// How pages present user content
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Ui {
+    /// Render project descriptions as Markdown on the project page, see `crate::markdown`.
+    /// Dashboard cards show them as plain text either way.
+    #[serde(default)]
+    pub markdown_descriptions: bool,
+}
+
+/// How accounts are looked up
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Auth {
+    /// Treat usernames that differ only in case as the same account, so "Alice" signs in
+    /// as "alice" and both can't be registered. Users keep the spelling they chose.
+    #[serde(default)]
+    pub case_insensitive_usernames: bool,
+}
+
+/// What is kept of visitors' personal data
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Privacy {
+    #[serde(default)]
+    pub ip_storage: IpStorage,
+}
+
+/// How much of a client address is kept where it is recorded: on sessions, as a user's last
+/// login, and in security events. Rate limits always see the full address.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IpStorage {
+    /// The address as the client connected from
+    #[default]
+    Full,
+    /// The network part only: the last octet of IPv4 addresses and the last 80 bits of IPv6
+    /// addresses are zeroed
+    Masked,
+    /// Nothing; the unspecified address (`0.0.0.0` or `::`) stands in
+    None,
+}
+
+impl IpStorage {
+    /// What to record of `ip`
+    pub fn apply(self, ip: std::net::IpAddr) -> std::net::IpAddr {
+        use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+        match (self, ip) {
+            (IpStorage::Full, ip) => ip,
+            (IpStorage::None, IpAddr::V4(_)) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            (IpStorage::None, IpAddr::V6(_)) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            (IpStorage::Masked, IpAddr::V4(v4)) => {
+                let [a, b, c, _] = v4.octets();
+                IpAddr::V4(Ipv4Addr::new(a, b, c, 0))
+            }
+            (IpStorage::Masked, IpAddr::V6(v6)) => {
+                let [a, b, c, ..] = v6.segments();
+                IpAddr::V6(Ipv6Addr::new(a, b, c, 0, 0, 0, 0, 0))
+            }
+        }
+    }
+
+    /// [IpStorage::apply] for an address about to be stored with a session or login
+    pub fn session_ip(self, ip: std::net::IpAddr) -> SessionIp {
+        SessionIp(self.apply(ip))
+    }
+}
+
+/// Attributes shared by every cookie Bento sets
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Cookie {
+    /// `Path` of the cookies; set it to the prefix when a reverse proxy serves Bento under
+    /// a sub-path, so its cookies aren't sent to the rest of the site
+    #[serde(default = "default_cookie_path")]
+    pub path: String,
+    /// How the session cookie is protected with the cookie key from `.bento_secrets`
+    #[serde(default)]
+    pub session_protection: CookieProtection,
+}
+
+impl Default for Cookie {
+    fn default() -> Self {
+        Self {
+            path: default_cookie_path(),
+            session_protection: CookieProtection::default(),
+        }
+    }
+}
+
+/// Protection of the session cookie's value. Switching it signs everyone out, as cookies
+/// sealed the other way are then rejected.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CookieProtection {
+    /// The session ID is readable but carries an HMAC, so a tampered cookie is rejected
+    #[default]
+    Signed,
+    /// The session ID is encrypted and authenticated, so it's also hidden from the client
+    Encrypted,
+}
+
+impl Cookie {
+    /// Rejects paths browsers would ignore or that would break the `Set-Cookie` header
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.path.starts_with('/') {
+            return Err(format!(
+                "[cookie] path must start with '/', got {:?}",
+                self.path
+            ));
+        }
+        if self
+            .path
+            .chars()
+            .any(|c| c == ';' || c.is_whitespace() || c.is_control())
+        {
+            return Err(format!(
+                "[cookie] path can't contain ';', whitespace or control characters, got {:?}",
+                self.path
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Copies of `.bento_secrets` kept before it is overwritten, see [Secrets::backup]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SecretsBackup {
+    /// Backups to keep, oldest removed first; 0 turns backups off
+    #[serde(default)]
+    pub keep: usize,
+    /// Directory of the backups, created readable by Bento's user only
+    #[serde(default = "default_secrets_backup_dir")]
+    pub dir: String,
+}
+
+impl Default for SecretsBackup {
+    fn default() -> Self {
+        Self {
+            keep: 0,
+            dir: default_secrets_backup_dir(),
+        }
+    }
+}
+
+/// Password hashing algorithm and cost calibration
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Argon2Tuning {
+    /// Calibrate Argon2 on first boot so a password hash takes about `target_ms` on this
+    /// machine; the chosen parameters are saved to `.bento_secrets` and reused afterwards
+    #[serde(default)]
+    pub autotune: bool,
+    #[serde(default = "default_argon2_target_ms")]
+    pub target_ms: u64,
+    /// Variant for new hashes: "argon2id", "argon2i" or "argon2d"
+    #[serde(default = "default_argon2_variant")]
+    pub variant: String,
+    /// Version for new hashes: 19 (0x13) or 16 (0x10)
+    #[serde(default = "default_argon2_version")]
+    pub version: u32,
+}
+
+impl Default for Argon2Tuning {
+    fn default() -> Self {
+        Self {
+            autotune: false,
+            target_ms: default_argon2_target_ms(),
+            variant: default_argon2_variant(),
+            version: default_argon2_version(),
+        }
+    }
+}
+
+impl Argon2Tuning {
+    pub fn target(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.target_ms)
+    }
+
+    /// The configured variant and version, rejecting unknown ones
+    pub fn algorithm_version(&self) -> Result<(argon2::Algorithm, argon2::Version), String> {
+        let algorithm = self.variant.parse().map_err(|_| {
+            format!(
+                "[argon2] variant must be \"argon2id\", \"argon2i\" or \"argon2d\", got {:?}",
+                self.variant
+            )
+        })?;
+        let version = argon2::Version::try_from(self.version).map_err(|_| {
+            format!(
+                "[argon2] version must be 19 (0x13) or 16 (0x10), got {}",
+                self.version
+            )
+        })?;
+        Ok((algorithm, version))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Compression {
+    /// Set to false to disable response compression entirely
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Encoder-specific quality level (e.g., 0-11 for brotli, 0-9 for gzip).
+    /// Lower values trade ratio for CPU; defaults to each encoder's own default.
+    #[serde(default)]
+    pub level: Option<i32>,
+    /// Responses smaller than this many bytes are sent uncompressed
+    #[serde(default = "default_compression_min_size")]
+    pub min_size: u16,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            level: None,
+            min_size: default_compression_min_size(),
+        }
+    }
+}
+
+impl Compression {
+    /// Builds the response compression layer according to this configuration.
+    ///
+    /// Already-compressed content (images, gRPC, event streams) is skipped by the
+    /// default predicate; when disabled, no encodings are offered at all.
+    pub fn layer(&self) -> CompressionLayer<And<DefaultPredicate, SizeAbove>> {
+        let quality = match self.level {
+            Some(level) => CompressionLevel::Precise(level),
+            None => CompressionLevel::Default,
+        };
+
+        CompressionLayer::new()
+            .br(self.enabled)
+            .gzip(self.enabled)
+            .quality(quality)
+            .compress_when(DefaultPredicate::new().and(SizeAbove::new(self.min_size)))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Passkeys {
+    /// Allow users to register and sign in with WebAuthn passkeys
+    #[serde(default)]
+    pub enabled: bool,
+    /// Relying party id; the domain the site is served from (e.g., "bento.example.com")
+    #[serde(default = "default_rp_id")]
+    pub rp_id: String,
+    /// Full origin as seen by the browser (e.g., "https://bento.example.com")
+    #[serde(default = "default_rp_origin")]
+    pub rp_origin: String,
+    /// Name shown by the browser/authenticator during passkey prompts
+    #[serde(default = "default_rp_name")]
+    pub rp_name: String,
+}
+
+impl Default for Passkeys {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rp_id: default_rp_id(),
+            rp_origin: default_rp_origin(),
+            rp_name: default_rp_name(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Webhooks {
+    /// Shared secret for the `X-Bento-Signature` HMAC over each request body
+    #[serde(default, serialize_with = "redact")]
+    pub secret: String,
+    #[serde(default)]
+    pub endpoints: Vec<WebhookEndpoint>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WebhookEndpoint {
+    /// Redacted when serialized, as such URLs often carry an access token
+    #[serde(serialize_with = "redact")]
+    pub url: String,
+    /// Events to send to this endpoint; all events when empty
+    #[serde(default)]
+    pub events: Vec<ProjectEventKind>,
+}
+
+impl WebhookEndpoint {
+    pub fn wants(&self, event: ProjectEventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+fn default_rp_id() -> String {
+    "localhost".to_string()
+}
+
+fn default_rp_origin() -> String {
+    "http://localhost:8000".to_string()
+}
+
+fn default_rp_name() -> String {
+    "Bento".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_compression_min_size() -> u16 {
+    256
+}
+
+fn default_username_min_len() -> usize {
+    Username::MIN_LEN
+}
+
+fn default_username_max_len() -> usize {
+    Username::MAX_LEN
+}
+
+fn default_password_min_len() -> usize {
+    1
+}
+
+fn default_password_max_len() -> usize {
+    128
 }
 
 fn default_address() -> String {
@@ -69,6 +921,66 @@ fn default_port() -> u16 {
     8000
 }
 
+fn default_request_timeout_secs() -> u64 {
+    30
+}
+
+fn default_tcp_keepalive_secs() -> u64 {
+    60
+}
+
+fn default_max_blocking() -> usize {
+    64
+}
+
+fn default_session_sweep_interval_secs() -> u64 {
+    600
+}
+
+fn default_session_token_bytes() -> usize {
+    SessionId::DEFAULT_BYTES
+}
+
+fn default_reauth_max_age_secs() -> u64 {
+    15 * 60
+}
+
+fn default_idempotency_window_secs() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_metrics_interval_secs() -> u64 {
+    5 * 60
+}
+
+fn default_lock_minutes() -> u64 {
+    15
+}
+
+fn default_cookie_path() -> String {
+    "/".to_string()
+}
+
+fn default_secrets_backup_dir() -> String {
+    "secrets_backups".to_string()
+}
+
+fn default_registration_role() -> Role {
+    Role::User
+}
+
+fn default_argon2_target_ms() -> u64 {
+    250
+}
+
+fn default_argon2_variant() -> String {
+    "argon2id".to_string()
+}
+
+fn default_argon2_version() -> u32 {
+    0x13
+}
+
 pub fn grab_config() -> Result<Config, de::Error> {
     let config_str = std::fs::read_to_string("bento.toml").expect("a file called ./bento.toml");
     toml::from_str(&config_str)
@@ -78,10 +990,20 @@ pub fn grab_config() -> Result<Config, de::Error> {
  * Secrets Manager
  */
 use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the secrets live, next to `bento.toml`
+pub const SECRETS_PATH: &str = ".bento_secrets";
+
+/// File name prefix of the backups in `[secrets_backup] dir`; a nanosecond timestamp follows
+const SECRETS_BACKUP_PREFIX: &str = "bento_secrets.";
 
 #[derive(Deserialize, Serialize)]
 pub struct Secrets {
     pub cookie_key: CookieKey,
+    /// Parameters picked by `[argon2] autotune`, kept so calibration only runs once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub argon2: Option<Argon2Params>,
 }
 
 #[derive(Clone)]
@@ -95,36 +1017,159 @@ impl CookieKey {
 
 // TODO: replace Box<dyn Error> with anyhow::Error
 impl Secrets {
+    /// Fresh secrets with a random cookie key
+    pub fn generate() -> Self {
+        Secrets {
+            cookie_key: CookieKey::generate(),
+            argon2: None,
+        }
+    }
+
     pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
-        let secrets_path = ".bento_secrets";
-        let secrets_str = fs::read_to_string(secrets_path)?;
-        let secrets: Secrets = toml::from_str(&secrets_str)?;
-        Ok(secrets)
+        Self::load_from(Path::new(SECRETS_PATH))
+    }
+
+    fn load_from(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut secrets_str = fs::read_to_string(path)?;
+        let secrets = toml::from_str(&secrets_str);
+        wipe(&mut secrets_str);
+        Ok(secrets?)
     }
 
+    /// Loads `.bento_secrets`, generating new secrets only when the file doesn't exist yet.
+    ///
+    /// A file that can't be read or parsed fails startup rather than being replaced, as data
+    /// encrypted at rest is keyed from the cookie key and would be lost with it.
     pub fn load_or_init() -> Result<Self, Box<dyn std::error::Error>> {
         match Self::load() {
             Ok(secrets) => Ok(secrets),
+            Err(err) if !is_missing_file(err.as_ref()) => Err(err),
             Err(_) => {
-                tracing::info!("Generating default .bento_secrets file...");
-                let secrets = Self::default();
+                tracing::info!("Generating a new .bento_secrets file...");
+                let secrets = Self::generate();
                 secrets.save()?;
                 Ok(secrets)
             }
         }
     }
 
+    /// Writes the secrets to `.bento_secrets`, first backing up the ones it replaces when
+    /// `[secrets_backup] keep` is set
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let secrets_toml = toml::to_string(self)?;
-        fs::write(".bento_secrets", secrets_toml)?;
-        Ok(())
+        self.save_to(
+            Path::new(SECRETS_PATH),
+            &LOCAL_CONF.secrets_backup,
+            time::OffsetDateTime::now_utc(),
+        )
+    }
+
+    fn save_to(
+        &self,
+        path: &Path,
+        backup: &SecretsBackup,
+        now: time::OffsetDateTime,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if backup.keep > 0 && path.exists() {
+            let replaced = Self::load_from(path)?;
+            if let Some(written) = replaced.backup(backup, now)? {
+                tracing::info!(path = %written.display(), "Backed up the previous secrets");
+            }
+        }
+        write_private(path, self)
+    }
+
+    /// Writes these secrets to a new timestamped file in `[secrets_backup] dir`, then
+    /// removes the oldest backups beyond `keep`. Returns the new file, or `None` when
+    /// backups are off.
+    ///
+    /// The directory and files are only accessible to the user running Bento.
+    pub fn backup(
+        &self,
+        settings: &SecretsBackup,
+        now: time::OffsetDateTime,
+    ) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+        if settings.keep == 0 {
+            return Ok(None);
+        }
+        let dir = Path::new(&settings.dir);
+        create_private_dir(dir)?;
+        let path = dir.join(format!(
+            "{SECRETS_BACKUP_PREFIX}{}",
+            now.unix_timestamp_nanos()
+        ));
+        write_private(&path, self)?;
+
+        // oldest first; the timestamps all have the same number of digits
+        let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(SECRETS_BACKUP_PREFIX))
+            })
+            .collect();
+        backups.sort();
+        let excess = backups.len().saturating_sub(settings.keep);
+        for old in &backups[..excess] {
+            fs::remove_file(old)?;
+        }
+        Ok(Some(path))
     }
 }
 
+/// Writes `secrets` to `path` with owner-only permissions, wiping the serialized copy
+/// from memory afterwards
+fn write_private(path: &Path, secrets: &Secrets) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let mut secrets_toml = toml::to_string(secrets)?;
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options
+        .open(path)
+        .and_then(|mut file| file.write_all(secrets_toml.as_bytes()));
+    wipe(&mut secrets_toml);
+    Ok(written?)
+}
+
+/// Creates `dir` if missing, accessible to the owner only
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)
+}
+
+/// Overwrites the bytes of a string that held secrets, so they don't linger on the heap
+fn wipe(secret: &mut str) {
+    // SAFETY: zero bytes are valid UTF-8
+    for byte in unsafe { secret.as_bytes_mut() } {
+        // volatile, so the writes to a buffer about to be freed aren't optimized away
+        unsafe { std::ptr::write_volatile(byte, 0) };
+    }
+}
+
+fn is_missing_file(err: &(dyn std::error::Error + 'static)) -> bool {
+    err.downcast_ref::<std::io::Error>()
+        .is_some_and(|err| err.kind() == std::io::ErrorKind::NotFound)
+}
+
+/// All-zero placeholder the loaded secrets are overwritten with once they are in use. Never
+/// saved, use [Secrets::generate] for new secrets.
 impl Default for Secrets {
     fn default() -> Self {
         Secrets {
             cookie_key: CookieKey(Key::from(&[0u8; 64])),
+            argon2: None,
         }
     }
 }
@@ -152,3 +1197,368 @@ impl<'de> Deserialize<'de> for CookieKey {
         Ok(CookieKey(Key::from(&bytes)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode, header},
+        routing::get,
+    };
+    use tower::ServiceExt;
+
+    async fn content_encoding(compression: &Compression) -> Option<String> {
+        let app = Router::new()
+            .route("/", get(|| async { "bento ".repeat(1024) }))
+            .layer(compression.layer());
+
+        let request = Request::builder()
+            .uri("/")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        response
+            .headers()
+            .get(header::CONTENT_ENCODING)
+            .map(|v| v.to_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn compression_config_defaults() {
+        let compression: Compression = toml::from_str("").unwrap();
+        assert!(compression.enabled);
+        assert_eq!(compression.level, None);
+        assert_eq!(compression.min_size, 256);
+    }
+
+    #[tokio::test]
+    async fn compression_layer_follows_config() {
+        let enabled: Compression = toml::from_str("level = 1").unwrap();
+        assert_eq!(content_encoding(&enabled).await.as_deref(), Some("gzip"));
+
+        let disabled: Compression = toml::from_str("enabled = false").unwrap();
+        assert_eq!(content_encoding(&disabled).await, None);
+
+        let above_body_size: Compression = toml::from_str("min_size = 65535").unwrap();
+        assert_eq!(content_encoding(&above_body_size).await, None);
+    }
+
+    #[test]
+    fn server_config_defaults() {
+        let server: Server = toml::from_str("").unwrap();
+        assert_eq!(server.request_timeout_secs, 30);
+        assert_eq!(
+            server.tcp_keepalive(),
+            Some(std::time::Duration::from_secs(60))
+        );
+
+        assert_eq!(
+            server.session_sweep_interval(),
+            Some(std::time::Duration::from_secs(600))
+        );
+
+        let no_keepalive: Server = toml::from_str("tcp_keepalive_secs = 0").unwrap();
+        assert_eq!(no_keepalive.tcp_keepalive(), None);
+    }
+
+    #[test]
+    fn session_token_bytes_have_a_floor() {
+        let session: Session = toml::from_str("").unwrap();
+        assert_eq!(session.token_bytes, 32);
+        assert!(session.validate().is_ok());
+
+        let short: Session = toml::from_str("token_bytes = 8").unwrap();
+        assert!(short.validate().is_err());
+        let minimum: Session = toml::from_str("token_bytes = 16").unwrap();
+        assert!(minimum.validate().is_ok());
+    }
+
+    #[test]
+    fn argon2_variant_and_version_are_checked() {
+        let default: Argon2Tuning = toml::from_str("").unwrap();
+        assert_eq!(
+            default.algorithm_version(),
+            Ok((argon2::Algorithm::Argon2id, argon2::Version::V0x13))
+        );
+
+        let argon2i: Argon2Tuning = toml::from_str("variant = \"argon2i\"\nversion = 16").unwrap();
+        assert_eq!(
+            argon2i.algorithm_version(),
+            Ok((argon2::Algorithm::Argon2i, argon2::Version::V0x10))
+        );
+
+        let unknown: Argon2Tuning = toml::from_str("variant = \"argon3\"").unwrap();
+        assert!(unknown.algorithm_version().is_err());
+        let bad_version: Argon2Tuning = toml::from_str("version = 20").unwrap();
+        assert!(bad_version.algorithm_version().is_err());
+    }
+
+    #[tokio::test]
+    async fn slow_requests_time_out() {
+        let server: Server = toml::from_str("request_timeout_secs = 1").unwrap();
+        let app = Router::new()
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+                    "done"
+                }),
+            )
+            .route("/fast", get(|| async { "done" }))
+            .layer(server.timeout_layer());
+
+        let request = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let response = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            app.clone().oneshot(request("/slow")),
+        )
+        .await
+        .expect("timeout layer answered before the handler finished")
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+
+        let response = app.oneshot(request("/fast")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn branding_defaults_and_color_validation() {
+        let branding: Branding = toml::from_str("").unwrap();
+        assert_eq!(branding, Branding::default());
+
+        let custom: Branding =
+            toml::from_str("app_name = \"Acme\"\nprimary_color = \"#1d4ed8\"").unwrap();
+        assert_eq!(custom.app_name, "Acme");
+        assert_eq!(custom.logo_path, "/bento-dark.svg");
+        assert!(
+            custom
+                .css_variables()
+                .starts_with("--bento-primary: #1d4ed8;")
+        );
+
+        let injected: Branding = toml::from_str("primary_color = \"red; } body { x: y\"").unwrap();
+        assert_eq!(injected.primary_color(), "#e35b2d");
+    }
+
+    #[test]
+    fn default_theme_applies_without_a_theme_cookie() {
+        let branding: Branding = toml::from_str("").unwrap();
+        assert_eq!(branding.theme(None), "night");
+
+        let light: Branding =
+            toml::from_str("default_theme = \"light\"\naccent_color = \"#16a34a\"").unwrap();
+        assert_eq!(light.theme(None), "light");
+        assert!(light.css_variables().ends_with("--bento-accent: #16a34a;"));
+
+        // the visitor's own pick wins, unless it isn't a theme name
+        assert_eq!(light.theme(Some("cupcake")), "cupcake");
+        assert_eq!(light.theme(Some("\"><script>")), "light");
+
+        let broken: Branding =
+            toml::from_str("default_theme = \"Night Mode\"\naccent_color = \"blue\"").unwrap();
+        assert_eq!(broken.theme(None), "night");
+        assert_eq!(broken.accent_color(), "#2d9be3");
+    }
+
+    #[test]
+    fn cookie_path_validation() {
+        let cookie: Cookie = toml::from_str("").unwrap();
+        assert_eq!(cookie.path, "/");
+        assert!(cookie.validate().is_ok());
+
+        let prefixed: Cookie = toml::from_str("path = \"/bento\"").unwrap();
+        assert!(prefixed.validate().is_ok());
+        let relative: Cookie = toml::from_str("path = \"bento\"").unwrap();
+        assert!(relative.validate().is_err());
+        let injected: Cookie = toml::from_str("path = \"/; Domain=evil.example\"").unwrap();
+        assert!(injected.validate().is_err());
+    }
+
+    #[test]
+    fn trusted_proxies_are_parsed() {
+        let server: Server = toml::from_str("").unwrap();
+        assert!(server.trusted_proxies().unwrap().is_empty());
+
+        let server: Server = toml::from_str("trusted_proxies = [\"10.0.0.0/8\", \"::1\"]").unwrap();
+        let proxies = server.trusted_proxies().unwrap();
+        assert!(proxies.contains("10.1.2.3".parse().unwrap()));
+        assert!(proxies.contains("::1".parse().unwrap()));
+
+        let invalid: Server = toml::from_str("trusted_proxies = [\"10.0.0.0/40\"]").unwrap();
+        assert!(invalid.trusted_proxies().is_err());
+    }
+
+    #[test]
+    fn redacted_config_hides_secrets_only() {
+        let config: Config = toml::from_str(
+            r#"
+            [admin]
+            username = "root"
+            password = "hunter2"
+
+            [server]
+            port = 9000
+
+            [storage]
+            encryption_key = "c2VjcmV0LWtleQ"
+
+            [webhooks]
+            secret = "whsec"
+            endpoints = [{ url = "https://hooks.example.com/T0/abc", events = ["project_created"] }]
+            "#,
+        )
+        .unwrap();
+        let json = config.redacted();
+        let dump = json.to_string();
+        for secret in ["hunter2", "c2VjcmV0LWtleQ", "whsec", "hooks.example.com"] {
+            assert!(!dump.contains(secret), "{secret} leaked: {dump}");
+        }
+
+        assert_eq!(json["admin"]["password"], REDACTED);
+        assert_eq!(json["storage"]["encryption_key"], REDACTED);
+        assert_eq!(json["webhooks"]["secret"], REDACTED);
+        assert_eq!(json["webhooks"]["endpoints"][0]["url"], REDACTED);
+
+        // everything else is shown, defaults included
+        assert_eq!(json["admin"]["username"], "root");
+        assert_eq!(json["server"]["port"], 9000);
+        assert_eq!(json["server"]["request_timeout_secs"], 30);
+        assert_eq!(
+            json["webhooks"]["endpoints"][0]["events"][0],
+            "project_created"
+        );
+        assert_eq!(json["session"]["store"], "same");
+
+        // unset secrets stay visibly unset
+        let defaults: Config = toml::from_str("").unwrap();
+        let json = defaults.redacted();
+        assert!(json["admin"].is_null());
+        assert!(json["storage"]["encryption_key"].is_null());
+        assert_eq!(json["webhooks"]["secret"], "");
+    }
+
+    #[test]
+    fn ip_storage_keeps_only_what_it_should() {
+        let v4: std::net::IpAddr = "203.0.113.57".parse().unwrap();
+        let v6: std::net::IpAddr = "2001:db8:85a3:1234:5678:8a2e:370:7334".parse().unwrap();
+
+        let privacy: Privacy = toml::from_str("").unwrap();
+        assert_eq!(privacy.ip_storage, IpStorage::Full);
+        assert_eq!(IpStorage::Full.apply(v4), v4);
+        assert_eq!(IpStorage::Full.apply(v6), v6);
+
+        let privacy: Privacy = toml::from_str("ip_storage = \"masked\"").unwrap();
+        assert_eq!(
+            privacy.ip_storage.apply(v4),
+            "203.0.113.0".parse::<std::net::IpAddr>().unwrap()
+        );
+        assert_eq!(
+            privacy.ip_storage.apply(v6),
+            "2001:db8:85a3::".parse::<std::net::IpAddr>().unwrap()
+        );
+
+        let privacy: Privacy = toml::from_str("ip_storage = \"none\"").unwrap();
+        assert_eq!(
+            privacy.ip_storage.session_ip(v4),
+            SessionIp("0.0.0.0".parse().unwrap())
+        );
+        assert_eq!(
+            privacy.ip_storage.session_ip(v6),
+            SessionIp("::".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn replacing_secrets_keeps_capped_backups() {
+        let dir = std::env::temp_dir().join(format!("bento-secrets-{}", uuid::Uuid::now_v7()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".bento_secrets");
+        let backup = SecretsBackup {
+            keep: 2,
+            dir: dir.join("backups").to_string_lossy().into_owned(),
+        };
+        let backups = || -> Vec<Secrets> {
+            let mut paths: Vec<PathBuf> = fs::read_dir(&backup.dir)
+                .map(|entries| entries.map(|entry| entry.unwrap().path()).collect())
+                .unwrap_or_default();
+            paths.sort();
+            paths
+                .iter()
+                .map(|path| Secrets::load_from(path).unwrap())
+                .collect()
+        };
+
+        let keys: Vec<CookieKey> = (0..4).map(|_| CookieKey::generate()).collect();
+        let start = time::OffsetDateTime::now_utc();
+        for (i, key) in keys.iter().enumerate() {
+            let secrets = Secrets {
+                cookie_key: key.clone(),
+                argon2: None,
+            };
+            let now = start + time::Duration::seconds(i as i64);
+            secrets.save_to(&path, &backup, now).unwrap();
+        }
+
+        // the first save had nothing to back up, each later one kept the key it replaced
+        let kept = backups();
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0].cookie_key.0.master(), keys[1].0.master());
+        assert_eq!(kept[1].cookie_key.0.master(), keys[2].0.master());
+        let current = Secrets::load_from(&path).unwrap();
+        assert_eq!(current.cookie_key.0.master(), keys[3].0.master());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(Path::new(&backup.dir)), 0o700);
+            for entry in fs::read_dir(&backup.dir).unwrap() {
+                assert_eq!(mode(&entry.unwrap().path()), 0o600);
+            }
+        }
+
+        // with backups off, nothing is added
+        let off = SecretsBackup {
+            keep: 0,
+            ..backup.clone()
+        };
+        current
+            .save_to(&path, &off, time::OffsetDateTime::now_utc())
+            .unwrap();
+        assert_eq!(backups().len(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn encryption_is_never_keyed_by_the_zero_placeholder() {
+        let storage: Storage = toml::from_str("encrypt_at_rest = true").unwrap();
+        assert!(matches!(
+            storage.value_codec(&Secrets::default().cookie_key.0),
+            Err(CodecError::InvalidKey(_))
+        ));
+
+        let generated = Secrets::generate();
+        assert_ne!(
+            generated.cookie_key.0.master(),
+            Secrets::default().cookie_key.0.master()
+        );
+        assert!(storage.value_codec(&generated.cookie_key.0).is_ok());
+    }
+
+    #[test]
+    fn only_a_missing_secrets_file_is_regenerated() {
+        let missing = fs::read_to_string("no/such/dir/.bento_secrets").unwrap_err();
+        assert!(is_missing_file(&missing));
+
+        let Err(corrupt) = toml::from_str::<Secrets>("cookie_key = 1") else {
+            panic!("parsed a corrupt secrets file");
+        };
+        assert!(!is_missing_file(&corrupt));
+    }
+}
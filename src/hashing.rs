@@ -0,0 +1,176 @@
+//! Argon2 algorithm and parameters used for new password hashes.
+//!
+//! Hashes are created with Argon2id v19 and argon2's default costs unless others are installed
+//! at startup: the `[argon2] variant` and `version`, and e.g. the costs picked by
+//! [autotune_argon2] for `[argon2] autotune = true`. Verification always reads the variant,
+//! version and parameters stored in each PHC hash string, so changing them never invalidates
+//! existing passwords.
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use argon2::{
+    Algorithm, Argon2, Params, Version,
+    password_hash::{PasswordHasher, SaltString, rand_core::OsRng},
+};
+use serde::{Deserialize, Serialize};
+
+static HASH_PARAMS: OnceLock<Params> = OnceLock::new();
+static HASH_ALGORITHM: OnceLock<(Algorithm, Version)> = OnceLock::new();
+
+/// Largest memory cost the tuner will pick, in KiB (1 GiB)
+const MAX_M_COST: u32 = 1024 * 1024;
+/// Upper bound on calibration hashes, so tuning always terminates
+const MAX_TUNING_ROUNDS: usize = 24;
+
+/// Argon2 cost parameters as persisted in `.bento_secrets`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Argon2Params {
+    /// Memory cost in KiB
+    pub m_cost: u32,
+    /// Number of iterations
+    pub t_cost: u32,
+    /// Degree of parallelism
+    pub p_cost: u32,
+}
+
+impl Argon2Params {
+    pub fn to_params(self) -> Result<Params, argon2::Error> {
+        Params::new(self.m_cost, self.t_cost, self.p_cost, None)
+    }
+}
+
+impl From<&Params> for Argon2Params {
+    fn from(params: &Params) -> Self {
+        Self {
+            m_cost: params.m_cost(),
+            t_cost: params.t_cost(),
+            p_cost: params.p_cost(),
+        }
+    }
+}
+
+/// Sets the parameters for all hashes created from now on.
+///
+/// Only the first call has an effect; returns `false` if parameters were already installed.
+pub fn install_params(params: Params) -> bool {
+    HASH_PARAMS.set(params).is_ok()
+}
+
+/// Sets the variant and version for all hashes created (and calibrated) from now on.
+///
+/// Only the first call has an effect; returns `false` if they were already installed.
+pub fn install_algorithm(algorithm: Algorithm, version: Version) -> bool {
+    HASH_ALGORITHM.set((algorithm, version)).is_ok()
+}
+
+/// The hasher new password hashes are created with
+pub fn hasher() -> Argon2<'static> {
+    hasher_with(HASH_PARAMS.get().cloned().unwrap_or_default())
+}
+
+/// A hasher with the installed variant and version, and `params`
+fn hasher_with(params: Params) -> Argon2<'static> {
+    let (algorithm, version) = HASH_ALGORITHM
+        .get()
+        .copied()
+        .unwrap_or((Algorithm::Argon2id, Version::V0x13));
+    Argon2::new(algorithm, version, params)
+}
+
+/// Picks Argon2 parameters whose hash time on this machine reaches `target`.
+///
+/// Starts from argon2's defaults (never going below them) and raises the memory cost until
+/// a test hash takes at least `target`; once memory hits 1 GiB, iterations go up instead.
+pub fn autotune_argon2(target: Duration) -> Params {
+    let mut m_cost = Params::DEFAULT_M_COST;
+    let mut t_cost = Params::DEFAULT_T_COST;
+    let p_cost = Params::DEFAULT_P_COST;
+    let mut params = Params::default();
+
+    for _ in 0..MAX_TUNING_ROUNDS {
+        params = Params::new(m_cost, t_cost, p_cost, None).unwrap_or_default();
+        let elapsed = time_hash(&params);
+        tracing::debug!(m_cost, t_cost, ?elapsed, "Argon2 calibration hash");
+        if elapsed >= target {
+            break;
+        }
+
+        if m_cost < MAX_M_COST {
+            // grow towards the target, but at most double per round to avoid overshooting
+            let ratio = (target.as_secs_f64() / elapsed.as_secs_f64().max(1e-6)).min(2.0);
+            let next = (f64::from(m_cost) * ratio * 1.05) as u32;
+            m_cost = next.clamp(m_cost + 1, MAX_M_COST);
+        } else {
+            t_cost += 1;
+        }
+    }
+
+    params
+}
+
+/// How long hashing a throwaway password takes with `params`
+pub fn time_hash(params: &Params) -> Duration {
+    let argon2 = hasher_with(params.clone());
+    let salt = SaltString::generate(&mut OsRng);
+
+    let start = Instant::now();
+    let _ = argon2.hash_password(b"bento-calibration", &salt);
+    start.elapsed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn persisted_params_round_trip() {
+        let params = Params::new(32 * 1024, 3, 1, None).unwrap();
+        let saved = Argon2Params::from(&params);
+        let restored = saved.to_params().unwrap();
+        assert_eq!(restored.m_cost(), 32 * 1024);
+        assert_eq!(restored.t_cost(), 3);
+        assert_eq!(restored.p_cost(), 1);
+    }
+
+    #[test]
+    fn every_configured_variant_hashes_and_verifies() {
+        use crate::config::Argon2Tuning;
+        use crate::types::PasswordHash;
+
+        for (variant, version, prefix) in [
+            ("argon2id", 19, "$argon2id$v=19$"),
+            ("argon2i", 19, "$argon2i$v=19$"),
+            ("argon2d", 19, "$argon2d$v=19$"),
+            ("argon2id", 16, "$argon2id$v=16$"),
+        ] {
+            let config: Argon2Tuning =
+                toml::from_str(&format!("variant = \"{variant}\"\nversion = {version}")).unwrap();
+            let (algorithm, version) = config.algorithm_version().unwrap();
+            // cheap costs, this is about the variant
+            let argon2 = Argon2::new(algorithm, version, Params::new(1024, 1, 1, None).unwrap());
+
+            let hash = PasswordHash::hash_with(&argon2, b"hunter2").unwrap();
+            assert!(hash.as_str().starts_with(prefix), "{}", hash.as_str());
+            assert!(hash.verify("hunter2"));
+            assert!(!hash.verify("hunter3"));
+        }
+    }
+
+    #[test]
+    fn autotuned_params_hash_close_to_target() {
+        // aim a few times above the default hash time, so tuning actually has to work
+        let baseline = time_hash(&Params::default());
+        let target = (baseline * 3).max(Duration::from_millis(100));
+
+        let params = autotune_argon2(target);
+        assert!(params.m_cost() >= Params::DEFAULT_M_COST);
+        assert!(params.t_cost() >= Params::DEFAULT_T_COST);
+
+        let elapsed = time_hash(&params);
+        assert!(
+            elapsed >= target / 3 && elapsed <= target * 4,
+            "tuned hash took {elapsed:?}, target {target:?}"
+        );
+    }
+}
@@ -1,35 +1,83 @@
-#![feature(impl_trait_in_bindings, bool_to_result)]
+#![recursion_limit = "256"]
+
 #[cfg(feature = "ssr")]
 pub mod server {
+    use super::passkeys::PasskeyService;
+    use super::perf::PerfStats;
+    use super::project_metrics::MetricsCollector;
+    use super::rate_limit::{CreationCooldown, IpLoginThrottle, ProjectRateLimiter};
+    use super::storage::{
+        blocking::BlockingLimiter,
+        dyn_store::{DynAuthStore, DynProjectStore},
+        maintenance::MaintenanceStats,
+        redb_auditstore::RedbAuditStore,
+        redb_authstore::RedbAuthStore,
+        redb_projectstore::RedbProjectStore,
+    };
+    use super::webhooks::WebhookDispatcher;
     use axum::extract::FromRef;
     use axum_extra::extract::cookie::Key;
-    use std::sync::Arc;
-    // declare which implementation of AuthStore to use
-    use super::storage::{redb_authstore::RedbAuthStore, redb_projectstore::RedbProjectStore};
     use leptos::config::LeptosOptions;
+    use std::sync::Arc;
 
     // Unified AppState struct
     #[derive(Clone)]
     pub struct AppState {
         pub leptos_options: LeptosOptions,
-        pub auth_store: Arc<RedbAuthStore>,
-        pub project_store: Arc<RedbProjectStore>,
+        pub auth_store: Arc<dyn DynAuthStore>,
+        pub project_store: Arc<dyn DynProjectStore>,
+        /// The redb database behind `auth_store`, for its size and page usage
+        pub auth_db: Arc<RedbAuthStore>,
+        /// The redb database behind `project_store`, for its size and the project metrics
+        /// only it keeps
+        pub project_db: Arc<RedbProjectStore>,
+        pub audit_store: Arc<RedbAuditStore>,
         pub cookie_key: Key,
+        /// Present only when `[passkeys] enabled` is set
+        pub passkeys: Option<Arc<PasskeyService>>,
+        /// Present only when `[webhooks]` endpoints are configured
+        pub webhooks: Option<WebhookDispatcher>,
+        /// Enforces each project's `rate_limit_rpm`
+        pub rate_limiter: Arc<ProjectRateLimiter>,
+        /// Enforces `[projects] creation_cooldown_secs`
+        pub creation_cooldown: Arc<CreationCooldown>,
+        /// Blocks addresses failing logins across many accounts, see `[lockout] ip_max_accounts`
+        pub login_throttle: Arc<IpLoginThrottle>,
+        /// Expired session cleanup counters, shared with the auth store and the sweeper
+        pub maintenance: Arc<MaintenanceStats>,
+        /// Server function latency samples, see `perf::timer`
+        pub perf: Arc<PerfStats>,
+        /// Bounds concurrent blocking store operations, shared by the stores
+        pub blocking: Arc<BlockingLimiter>,
+        /// Measures project usage for `[projects] metrics_interval_secs`
+        pub metrics_collector: Arc<dyn MetricsCollector>,
     }
 
     // Axum uses FromRef impls to clone "sub-state" into routers
-    impl FromRef<AppState> for Arc<RedbAuthStore> {
+    impl FromRef<AppState> for Arc<dyn DynAuthStore> {
         fn from_ref(state: &AppState) -> Self {
             state.auth_store.clone()
         }
     }
 
-    impl FromRef<AppState> for Arc<RedbProjectStore> {
+    impl FromRef<AppState> for Arc<dyn DynProjectStore> {
         fn from_ref(state: &AppState) -> Self {
             state.project_store.clone()
         }
     }
 
+    impl FromRef<AppState> for Arc<ProjectRateLimiter> {
+        fn from_ref(state: &AppState) -> Self {
+            state.rate_limiter.clone()
+        }
+    }
+
+    impl FromRef<AppState> for Arc<IpLoginThrottle> {
+        fn from_ref(state: &AppState) -> Self {
+            state.login_throttle.clone()
+        }
+    }
+
     impl FromRef<AppState> for LeptosOptions {
         fn from_ref(state: &AppState) -> Self {
             state.leptos_options.clone()
@@ -45,12 +93,39 @@ pub mod server {
 
 #[cfg(all(feature = "ssr", feature = "rest-api"))]
 pub mod api;
+pub mod build_info;
+#[cfg(feature = "ssr")]
+pub mod client_ip;
 #[cfg(feature = "ssr")]
 pub mod config;
 #[cfg(feature = "ssr")]
+pub mod hashing;
+#[cfg(feature = "ssr")]
+pub mod login_notice;
+#[cfg(feature = "ssr")]
+pub mod markdown;
+#[cfg(feature = "ssr")]
+pub mod passkeys;
+#[cfg(feature = "ssr")]
+pub mod perf;
+#[cfg(feature = "ssr")]
+pub mod project_archive;
+#[cfg(feature = "ssr")]
+pub mod project_metrics;
+#[cfg(feature = "ssr")]
+pub mod rate_limit;
+#[cfg(feature = "ssr")]
+pub mod readiness;
+#[cfg(feature = "ssr")]
+pub mod registration;
+#[cfg(feature = "ssr")]
+pub mod security_log;
+#[cfg(feature = "ssr")]
 pub mod storage;
 
 pub mod types;
+#[cfg(feature = "ssr")]
+pub mod webhooks;
 pub mod webui;
 
 #[cfg(feature = "hydrate")]
@@ -60,3 +135,22 @@ pub fn hydrate() {
     leptos::leptos_dom::logging::console_log("Hydrating client...");
     leptos::mount::hydrate_body(webui::App);
 }
+
+#[cfg(test)]
+mod tests {
+    /// Guards stable toolchain support: unstable features may only be enabled behind
+    /// the `nightly` cargo feature, i.e. via `#![cfg_attr(feature = "nightly", ...)]`.
+    #[test]
+    fn crate_root_enables_no_unstable_features() {
+        let lib_rs = include_str!("lib.rs");
+        let offending: Vec<&str> = lib_rs
+            .lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with(concat!("#![", "feature(")))
+            .collect();
+        assert!(
+            offending.is_empty(),
+            "unconditional feature gates: {offending:?}"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Notices of new sign-ins, enabled with `[security] notify_new_login`, so users spot
+//! access they didn't make.
+//!
+//! Bento has no way of reaching users outside the app, so each sign-in leaves a
+//! [NewLoginNotice] in the data of the user's other sessions (under [DATA_KEY]), which their
+//! dashboard shows once. A later sign-in replaces a notice not yet seen. The first sign-in of
+//! an account, when it has no other sessions worth warning, leaves none.
+
+use axum::http::HeaderMap;
+use tracing::debug;
+
+use crate::config::Security;
+use crate::storage::{AuthError, AuthStore};
+use crate::types::{NewLoginNotice, PreviousLogin, Session, SessionId};
+
+/// Session data key holding the pending notice
+pub const DATA_KEY: &str = "bento.new_login";
+
+/// Longest `User-Agent` kept in a notice, in characters
+const MAX_USER_AGENT_CHARS: usize = 160;
+
+/// Leave a notice of `session`, just issued by a sign-in, on the user's other sessions.
+/// `previous` is the user's sign-in before this one, as returned by
+/// `AuthStore::record_login`.
+///
+/// Returns how many sessions were notified. Sessions whose data is full are skipped, as are
+/// admins' impersonation sessions.
+pub async fn notify_new_login<S: AuthStore + ?Sized>(
+    store: &S,
+    security: &Security,
+    previous: Option<&PreviousLogin>,
+    session: &Session,
+    headers: &HeaderMap,
+) -> Result<usize, AuthError> {
+    if !security.notify_new_login || previous.is_none() {
+        return Ok(0);
+    }
+
+    let notice = NewLoginNotice {
+        at: session.created_at,
+        ip: session.ip.0,
+        user_agent: headers
+            .get(axum::http::header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|agent| agent.chars().take(MAX_USER_AGENT_CHARS).collect()),
+    };
+    let encoded = serde_json::to_string(&notice).expect("notice serializes");
+
+    let mut notified = 0;
+    for other in store.list_user_sessions(&session.user_id).await? {
+        if other.id == session.id || other.impersonator.is_some() {
+            continue;
+        }
+        match store
+            .set_session_data(&other.id, DATA_KEY, Some(encoded.clone()))
+            .await
+        {
+            Ok(_) => notified += 1,
+            // ended in the meantime, or no room left
+            Err(AuthError::InvalidSession | AuthError::SessionDataTooLarge) => {
+                debug!("Skipped a session when leaving a new sign-in notice");
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(notified)
+}
+
+/// The notice left on session `token`, if any, removing it so it's only shown once
+pub async fn take_notice<S: AuthStore + ?Sized>(
+    store: &S,
+    token: &SessionId,
+) -> Result<Option<NewLoginNotice>, AuthError> {
+    let Some(encoded) = store.get_session_data(token, DATA_KEY).await? else {
+        return Ok(None);
+    };
+    store.set_session_data(token, DATA_KEY, None).await?;
+    // anything unreadable was written by the user through `set_my_session_data`
+    Ok(serde_json::from_str(&encoded).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::mem_authstore::MemoryAuthStore;
+    use crate::types::{PasswordHash, SessionIp, Username};
+    use std::net::IpAddr;
+
+    fn enabled() -> Security {
+        Security {
+            notify_new_login: true,
+            ..Security::default()
+        }
+    }
+
+    fn browser(user_agent: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(axum::http::header::USER_AGENT, user_agent.parse().unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn only_sign_ins_after_the_first_notify_other_sessions() {
+        let store = MemoryAuthStore::default();
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let home = SessionIp(IpAddr::from([192, 0, 2, 1]));
+        let elsewhere = SessionIp(IpAddr::from([198, 51, 100, 9]));
+
+        // the first sign-in has nobody to warn
+        let previous = store.record_login(&user.id, home.clone()).await.unwrap();
+        let first = store.issue_session(&user.id, home, None).await.unwrap();
+        let notified = notify_new_login(
+            &store,
+            &enabled(),
+            previous.last_login().as_ref(),
+            &first,
+            &browser("Firefox"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(notified, 0);
+
+        let previous = store
+            .record_login(&user.id, elsewhere.clone())
+            .await
+            .unwrap();
+        let second = store
+            .issue_session(&user.id, elsewhere.clone(), None)
+            .await
+            .unwrap();
+        let notified = notify_new_login(
+            &store,
+            &enabled(),
+            previous.last_login().as_ref(),
+            &second,
+            &browser("Chrome"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(notified, 1);
+
+        let notice = take_notice(&store, &first.id).await.unwrap().unwrap();
+        assert_eq!(notice.ip, elsewhere.0);
+        assert_eq!(notice.at, second.created_at);
+        assert_eq!(notice.user_agent.as_deref(), Some("Chrome"));
+        // shown once, and never to the session that signed in
+        assert_eq!(take_notice(&store, &first.id).await.unwrap(), None);
+        assert_eq!(take_notice(&store, &second.id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn nothing_is_left_unless_enabled() {
+        let store = MemoryAuthStore::default();
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([192, 0, 2, 1]));
+        store.record_login(&user.id, ip.clone()).await.unwrap();
+        let first = store
+            .issue_session(&user.id, ip.clone(), None)
+            .await
+            .unwrap();
+
+        let previous = store.record_login(&user.id, ip.clone()).await.unwrap();
+        let second = store.issue_session(&user.id, ip, None).await.unwrap();
+        let notified = notify_new_login(
+            &store,
+            &Security::default(),
+            previous.last_login().as_ref(),
+            &second,
+            &HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(notified, 0);
+        assert_eq!(take_notice(&store, &first.id).await.unwrap(), None);
+    }
+}
@@ -1,30 +1,60 @@
+#![recursion_limit = "256"]
+
 #[cfg(feature = "ssr")]
 #[tokio::main]
 async fn main() {
     /*
      * Static code (placed here to only be compiled in server binary)
      */
-    use std::{net::SocketAddr, sync::Arc};
+    use std::{net::SocketAddr, path::Path, sync::Arc};
 
     use axum::Router;
+    use axum::middleware::from_fn_with_state;
+    use axum::routing::get;
     #[cfg(feature = "rest-api")]
-    use axum::routing::post;
-    use axum_client_ip::ClientIpSource;
+    use axum::routing::{delete, post};
+    use axum::serve::ListenerExt;
+    use bento::client_ip::select_client_ip_source;
     use bento::config::{Admin, CookieKey, LOCAL_CONF};
+    use bento::hashing::{Argon2Params, autotune_argon2, install_algorithm, install_params};
+    use bento::passkeys::PasskeyService;
+    use bento::perf::PerfStats;
+    use bento::project_metrics::{NullCollector, spawn_metrics_collector};
     #[cfg(feature = "rest-api")]
-    use bento::server::ConcreteAuthStore;
+    use bento::rate_limit::limit_project_requests;
+    use bento::rate_limit::{
+        CreationCooldown, IpConcurrencyLimiter, IpLoginThrottle, ProjectRateLimiter,
+        limit_concurrent_requests,
+    };
+    use bento::readiness::{READYZ_PATH, Readiness, readyz};
     use bento::storage::AuthStore;
-    use bento::storage::redb_authstore::RedbAuthStore;
-    use bento::storage::redb_projectstore::RedbProjectStore;
+    use bento::storage::backend::Stores;
+    use bento::storage::blocking::BlockingLimiter;
+    use bento::storage::clock::SystemClock;
+    use bento::storage::dyn_store::DynAuthStore;
+    #[cfg(feature = "rest-api")]
+    use bento::storage::dyn_store::DynProjectStore;
+    use bento::storage::maintenance::{
+        INACTIVITY_SWEEP_INTERVAL, MaintenanceStats, spawn_inactivity_sweeper,
+        spawn_session_sweeper,
+    };
+    use bento::storage::redb_auditstore::RedbAuditStore;
     use bento::types::PasswordHash;
+    use bento::webhooks::WebhookDispatcher;
     use bento::webui;
+    use bento::webui::admin_events::admin_events;
+    use bento::webui::audit_log::ADMIN_EVENTS_PATH;
+    use bento::webui::cookies::install_session_key;
+    use bento::webui::csrf::issue_csrf_token;
+    use bento::webui::middleware::resolve_current_user;
     use bento::{
         config::{self, Secrets},
         server::AppState,
     };
     use leptos::prelude::*;
     use leptos_axum::{LeptosRoutes, file_and_error_handler, generate_route_list};
-    use tower_http::{compression::CompressionLayer, decompression::RequestDecompressionLayer};
+    use socket2::{SockRef, TcpKeepalive};
+    use tower_http::decompression::RequestDecompressionLayer;
     use tracing::{debug, error, info, warn};
 
     const MAX_SESSIONS_PER_USER: usize = 5;
@@ -49,35 +79,234 @@ async fn main() {
         .finish();
     tracing::subscriber::set_global_default(subscriber).expect("Failed to set tracing subscriber");
 
-    // initialize the auth store
-    // let auth_store = Arc::new(MemoryAuthStore::new(MAX_SESSIONS_PER_USER));
-    // create data directory if it doesn't exist
-    if let Err(e) = std::fs::create_dir_all("data") {
-        error!("Failed to create data directory: {e}");
+    let app_conf = LOCAL_CONF.as_ref();
+
+    let mut local_secrets = Secrets::load_or_init().unwrap_or_else(|e| {
+        error!("Failed to load or create the secrets file (.bento_secrets): {e}");
+        std::process::exit(1);
+    });
+    let CookieKey(cookie_key) = local_secrets.cookie_key.clone();
+    install_session_key(cookie_key.clone());
+
+    let (algorithm, version) = app_conf.argon2.algorithm_version().unwrap_or_else(|e| {
+        error!("Invalid configuration: {e}");
+        std::process::exit(1);
+    });
+    install_algorithm(algorithm, version);
+
+    // pick password hashing costs for this machine, once
+    if app_conf.argon2.autotune {
+        let saved = local_secrets
+            .argon2
+            .and_then(|saved| saved.to_params().ok());
+        let params = match saved {
+            Some(params) => params,
+            None => {
+                info!(target = ?app_conf.argon2.target(), "Calibrating Argon2 parameters...");
+                let params = autotune_argon2(app_conf.argon2.target());
+                local_secrets.argon2 = Some(Argon2Params::from(&params));
+                if let Err(e) = local_secrets.save() {
+                    warn!("Failed to save Argon2 parameters to .bento_secrets: {e}");
+                }
+                params
+            }
+        };
+        info!(
+            m_cost = params.m_cost(),
+            t_cost = params.t_cost(),
+            p_cost = params.p_cost(),
+            "Using tuned Argon2 parameters"
+        );
+        install_params(params);
+    }
+
+    let storage_codec = app_conf
+        .storage
+        .value_codec(&cookie_key)
+        .unwrap_or_else(|e| {
+            error!("Failed to set up storage encryption: {e}");
+            std::process::exit(1);
+        });
+    if storage_codec.is_encrypted() {
+        info!("Encryption at rest enabled for stored records");
+    }
+
+    if let Err(e) = app_conf.session.validate() {
+        error!("Invalid configuration: {e}");
+        std::process::exit(1);
+    }
+    if let Err(e) = app_conf.cookie.validate() {
+        error!("Invalid configuration: {e}");
+        std::process::exit(1);
+    }
+    let trusted_proxies = Arc::new(app_conf.server.trusted_proxies().unwrap_or_else(|e| {
+        error!("Invalid configuration: {e}");
+        std::process::exit(1);
+    }));
+    if !trusted_proxies.is_empty() {
+        info!(
+            proxies = app_conf.server.trusted_proxies.len(),
+            "Honoring X-Forwarded-For from trusted proxies"
+        );
+    }
+
+    // open the databases in the data directory (created if missing), or in memory
+    let stores = Stores::open_backend(
+        app_conf.storage.backend,
+        Path::new("data"),
+        MAX_SESSIONS_PER_USER,
+        app_conf.session.store,
+    )
+    .unwrap_or_else(|e| {
+        error!("{e}");
+        std::process::exit(1);
+    });
+    if stores.in_memory {
+        warn!("Using in-memory storage, all data will be lost when Bento stops");
+    }
+
+    let blocking = Arc::new(BlockingLimiter::new(app_conf.storage.max_blocking));
+    let maintenance = Arc::new(MaintenanceStats::new());
+    // every store migrates its database to the current schema before use, see
+    // storage::migrations
+    let auth_store = Arc::new(
+        stores
+            .auth
+            .with_codec(storage_codec.clone())
+            .with_blocking_limiter(blocking.clone())
+            .with_session_token_bytes(app_conf.session.token_bytes)
+            .with_max_total_sessions(app_conf.session.max_total)
+            .with_password_history(app_conf.security.password_history)
+            .with_case_insensitive_usernames(app_conf.auth.case_insensitive_usernames)
+            .with_maintenance_stats(maintenance.clone()),
+    );
+    if let Err(e) = auth_store.migrate().await {
+        error!("Migrating the auth database failed: {e}");
         std::process::exit(1);
     }
-    let auth_store = Arc::new(RedbAuthStore::new("data/auth.db", MAX_SESSIONS_PER_USER).unwrap());
     debug!("Authentication store initialized");
 
-    let project_store = Arc::new(RedbProjectStore::new("data/projects.db").unwrap());
+    if let Some(interval) = app_conf.server.session_sweep_interval() {
+        spawn_session_sweeper(
+            auth_store.clone(),
+            maintenance.clone(),
+            Arc::new(SystemClock),
+            interval,
+        );
+        debug!(?interval, "Session sweeper started");
+    }
+
+    let project_store = Arc::new(
+        stores
+            .projects
+            .with_codec(storage_codec.clone())
+            .with_blocking_limiter(blocking.clone())
+            .with_idempotency_window(app_conf.projects.idempotency_window())
+            .with_slug_regeneration(app_conf.projects.regenerate_slug_on_rename),
+    );
+    if let Err(e) = project_store.migrate().await {
+        error!("Migrating the projects database failed: {e}");
+        std::process::exit(1);
+    }
+    // the store logs what it repaired
+    if app_conf.storage.reconcile_indexes
+        && let Err(e) = project_store.reconcile_indexes().await
+    {
+        error!("Reconciling the project index failed: {e}");
+        std::process::exit(1);
+    }
     debug!("Project store initialized");
 
+    let audit_store = Arc::new(
+        stores
+            .audit
+            .with_codec(storage_codec)
+            .with_blocking_limiter(blocking.clone()),
+    );
+    if let Err(e) = audit_store.migrate().await {
+        error!("Migrating the audit database failed: {e}");
+        std::process::exit(1);
+    }
+    debug!("Audit store initialized");
+
+    if let Some(max_inactive) = app_conf.accounts.inactivity() {
+        spawn_inactivity_sweeper(
+            auth_store.clone(),
+            audit_store.clone(),
+            Arc::new(SystemClock),
+            max_inactive,
+            app_conf.accounts.exempt_admins,
+            INACTIVITY_SWEEP_INTERVAL,
+        );
+        debug!(%max_inactive, "Inactivity sweeper started");
+    }
+
     // set up leptos webui
     let leptos_conf = get_configuration(None).unwrap();
     let leptos_routes = generate_route_list(webui::App);
     let leptos_options = leptos_conf.leptos_options;
 
-    let mut local_secrets = Secrets::load_or_init().unwrap_or_else(|e| {
-        error!("Failed to create secrets file (.bento_secrets): {e}");
-        std::process::exit(1);
-    });
-    let CookieKey(cookie_key) = local_secrets.cookie_key.clone();
+    let passkeys = if app_conf.passkeys.enabled {
+        match PasskeyService::new(&app_conf.passkeys) {
+            Ok(service) => {
+                info!(rp_id = %app_conf.passkeys.rp_id, "Passkey login enabled");
+                Some(Arc::new(service))
+            }
+            Err(e) => {
+                error!("Invalid [passkeys] configuration: {e}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    let webhooks = if app_conf.webhooks.endpoints.is_empty() {
+        None
+    } else {
+        if app_conf.webhooks.secret.is_empty() {
+            warn!("[webhooks] secret is empty, webhook signatures can be forged");
+        }
+        info!(
+            endpoints = app_conf.webhooks.endpoints.len(),
+            "Project webhooks enabled"
+        );
+        Some(WebhookDispatcher::spawn(&app_conf.webhooks))
+    };
+
     let app_state = AppState {
         leptos_options,
         auth_store: auth_store.clone(),
         project_store: project_store.clone(),
+        auth_db: auth_store.clone(),
+        project_db: project_store.clone(),
+        audit_store: audit_store.clone(),
         cookie_key,
+        passkeys,
+        webhooks,
+        rate_limiter: Arc::new(ProjectRateLimiter::new()),
+        creation_cooldown: Arc::new(CreationCooldown::new(app_conf.projects.creation_cooldown())),
+        login_throttle: Arc::new(IpLoginThrottle::new(
+            app_conf.lockout.ip_max_accounts,
+            app_conf.lockout.ip_window(),
+            app_conf.lockout.ip_block_duration(),
+        )),
+        maintenance,
+        perf: Arc::new(PerfStats::new()),
+        blocking,
+        metrics_collector: Arc::new(NullCollector),
     };
+    log_startup_summary(app_conf, &app_state);
+
+    if let Some(interval) = app_conf.projects.metrics_interval() {
+        spawn_metrics_collector(
+            project_store.clone(),
+            app_state.metrics_collector.clone(),
+            Arc::new(SystemClock),
+            interval,
+        );
+        debug!(?interval, "Project metrics collector started");
+    }
     unsafe {
         // zero out [Secrets] struct so keys don't hang around in memory:
         // &raw mut local_secrets could also be used, but these kinds of pointer calls don't
@@ -86,68 +315,161 @@ async fn main() {
         std::ptr::write_volatile(&mut local_secrets as *mut _, config::Secrets::default());
     }
 
+    // resolves the signed-in user for the routes that read it; static files and the probe
+    // don't need it, so it isn't layered over the whole app
+    let current_user = from_fn_with_state(
+        (
+            app_state.auth_store.clone(),
+            app_conf.session.bind_user_agent,
+        ),
+        resolve_current_user::<dyn DynAuthStore>,
+    );
+
     // define api sub-router for the server
     #[cfg(feature = "rest-api")]
     let api = Router::new()
         .route(
             "/api/v1/register",
-            post(bento::api::auth::register::<ConcreteAuthStore>),
+            post(bento::api::auth::register::<dyn DynAuthStore>),
         )
         .route(
             "/api/v1/login",
-            post(bento::api::auth::login::<ConcreteAuthStore>),
-        );
+            post(bento::api::auth::login::<dyn DynAuthStore>),
+        )
+        .route(
+            "/api/v1/users/bulk",
+            post(bento::api::users::bulk_create_users::<dyn DynAuthStore>),
+        )
+        .route(
+            "/api/v1/admin/config",
+            get(bento::api::config::effective_config),
+        )
+        .route(
+            "/api/v1/sessions",
+            get(bento::api::sessions::list_sessions::<dyn DynAuthStore>),
+        )
+        .route(
+            "/api/v1/sessions/{id}",
+            delete(bento::api::sessions::revoke_session::<dyn DynAuthStore>),
+        )
+        .route("/api/v1/version", get(bento::api::version::version))
+        .route("/api/v1/openapi.json", get(bento::api::openapi::openapi))
+        .route("/metrics", get(bento::api::metrics::metrics))
+        .route(
+            "/api/v1/projects/{project_id}",
+            get(bento::api::projects::get_project::<dyn DynProjectStore>).route_layer(
+                from_fn_with_state(
+                    (
+                        app_state.rate_limiter.clone(),
+                        app_state.project_store.clone(),
+                    ),
+                    limit_project_requests::<dyn DynProjectStore>,
+                ),
+            ),
+        )
+        .route_layer(current_user.clone())
+        .layer(app_conf.server.timeout_layer());
 
     // define ssr'ed webui sub-router
-    let ssr = Router::new().leptos_routes_with_context(
-        &app_state,
-        leptos_routes,
-        {
-            let app_state = app_state.clone();
-            move || provide_context(app_state.clone())
-        },
-        {
-            let opts = app_state.clone();
-            move || webui::shell(opts.leptos_options.clone())
-        },
-    );
+    let ssr = Router::new()
+        .leptos_routes_with_context(
+            &app_state,
+            leptos_routes,
+            {
+                let app_state = app_state.clone();
+                move || provide_context(app_state.clone())
+            },
+            {
+                let opts = app_state.clone();
+                move || webui::shell(opts.leptos_options.clone())
+            },
+        )
+        .route_layer(current_user.clone())
+        .layer(app_conf.server.timeout_layer());
+
+    // live audit events for admins; the stream stays open, so it gets no request timeout
+    let live = Router::new()
+        .route(
+            ADMIN_EVENTS_PATH,
+            get(admin_events::<dyn DynAuthStore, RedbAuditStore>),
+        )
+        .route_layer(current_user)
+        .with_state((app_state.auth_store.clone(), audit_store));
+
+    // answers 503 until the bootstrap below is done
+    let readiness = Readiness::new();
+    let probe = Router::new()
+        .route(READYZ_PATH, get(readyz))
+        .with_state(readiness.clone());
 
     // Register initial auth account
-    let app_conf = LOCAL_CONF.as_ref();
-    let Admin { username, password } = &app_conf.admin;
-    let pass_hash: PasswordHash = match PasswordHash::try_from(password.as_str()) {
-        Ok(hash) => hash,
-        Err(e) => {
-            error!("Failed to create password hash for admin user: {e}");
-            return;
+    match &app_conf.admin {
+        _ if app_conf.registration.first_user_is_admin => {
+            info!("Not creating the [admin] account, the first user to register becomes admin");
         }
-    };
+        Some(Admin { username, password }) => {
+            let pass_hash: PasswordHash = match PasswordHash::try_from(password.as_str()) {
+                Ok(hash) => hash,
+                Err(e) => {
+                    error!("Failed to create password hash for admin user: {e}");
+                    return;
+                }
+            };
 
-    if let Ok(user) = auth_store.create_admin(username, pass_hash).await {
-        info!(username = %user.username.0, id = %user.id.0, "Admin user created successfully");
-    } else {
-        warn!("Admin user already exists, skipping creation");
+            if let Ok(user) = auth_store.create_admin(username, pass_hash).await {
+                info!(username = %user.username.0, id = %user.id.0, "Admin user created successfully");
+            } else {
+                warn!("Admin user already exists, skipping creation");
+            }
+        }
+        None => warn!("No [admin] account configured"),
     }
+    readiness.mark_ready();
+    debug!("Bootstrap finished, ready for traffic");
+
+    let concurrency_limiter = Arc::new(IpConcurrencyLimiter::new(
+        app_conf.server.max_concurrent_per_ip,
+    ));
 
     // Unify both sub-routers under one
     #[cfg(feature = "rest-api")]
     let app: Router = Router::new()
         .merge(api)
         .merge(ssr)
+        .merge(live)
+        .merge(probe)
         .fallback(file_and_error_handler::<AppState, _>(webui::shell)) // fallback for static files & 404s
+        .layer(from_fn_with_state(app_conf.security.csrf, issue_csrf_token))
         .layer(RequestDecompressionLayer::new().br(true).gzip(true))
-        .layer(CompressionLayer::new().br(true).gzip(true))
+        .layer(app_conf.compression.layer())
         .with_state(app_state)
-        .layer(ClientIpSource::ConnectInfo.into_extension());
+        .layer(from_fn_with_state(
+            concurrency_limiter.clone(),
+            limit_concurrent_requests,
+        ))
+        .layer(from_fn_with_state(
+            trusted_proxies.clone(),
+            select_client_ip_source,
+        ));
 
     #[cfg(not(feature = "rest-api"))]
     let app: Router = Router::new()
         .merge(ssr)
+        .merge(live)
+        .merge(probe)
         .fallback(file_and_error_handler::<AppState, _>(webui::shell)) // fallback for static files & 404s
+        .layer(from_fn_with_state(app_conf.security.csrf, issue_csrf_token))
         .layer(RequestDecompressionLayer::new().br(true).gzip(true))
-        .layer(CompressionLayer::new().br(true).gzip(true))
+        .layer(app_conf.compression.layer())
         .with_state(app_state)
-        .layer(ClientIpSource::ConnectInfo.into_extension());
+        .layer(from_fn_with_state(
+            concurrency_limiter.clone(),
+            limit_concurrent_requests,
+        ))
+        .layer(from_fn_with_state(
+            trusted_proxies.clone(),
+            select_client_ip_source,
+        ));
 
     // Start the server
     let server_addr = app_conf.server.socket_addr();
@@ -159,6 +481,16 @@ async fn main() {
             return;
         }
     };
+    // enable TCP keep-alive so dead peers are noticed instead of holding connections open
+    let keepalive = app_conf.server.tcp_keepalive();
+    let listener = listener.tap_io(move |tcp| {
+        if let Some(idle) = keepalive {
+            let params = TcpKeepalive::new().with_time(idle);
+            if let Err(e) = SockRef::from(&*tcp).set_tcp_keepalive(&params) {
+                warn!("Failed to enable TCP keep-alive: {e}");
+            }
+        }
+    });
     info!("Server started successfully!");
     if let Err(e) = axum::serve(
         listener,
@@ -171,9 +503,153 @@ async fn main() {
     }
 }
 
+/// The settings operators most often find did not take effect, gathered in one place
+#[cfg(feature = "ssr")]
+#[derive(Debug, PartialEq)]
+struct StartupSummary {
+    bind_address: String,
+    rest_api: bool,
+    session_duration: time::Duration,
+    max_sessions_per_user: usize,
+    max_total_sessions: Option<usize>,
+    storage: &'static str,
+    registration: &'static str,
+    /// Bento serves plain HTTP, so whether TLS is in use at all is up to what's in front of it
+    tls: &'static str,
+    secrets: &'static str,
+}
+
+#[cfg(feature = "ssr")]
+impl StartupSummary {
+    fn new(conf: &bento::config::Config, state: &bento::server::AppState) -> Self {
+        Self {
+            bind_address: conf.server.socket_addr(),
+            rest_api: cfg!(feature = "rest-api"),
+            session_duration: bento::config::SESSION_DURATION,
+            max_sessions_per_user: state.auth_store.max_sessions_per_user(),
+            max_total_sessions: conf.session.max_total,
+            // `auto` falls back to memory when the database files can't be opened
+            storage: if state.auth_db.is_in_memory() {
+                "memory"
+            } else {
+                "redb"
+            },
+            registration: match &conf.registration {
+                r if !r.allow_registration => "closed",
+                r if r.require_invite => "invite only",
+                _ => "open",
+            },
+            tls: "not terminated by Bento",
+            secrets: bento::config::SECRETS_PATH,
+        }
+    }
+}
+
+/// Logs a one-line summary of the effective configuration, so operators can check at a
+/// glance that theirs took effect
+#[cfg(feature = "ssr")]
+fn log_startup_summary(conf: &bento::config::Config, state: &bento::server::AppState) {
+    let summary = StartupSummary::new(conf, state);
+    tracing::info!(
+        bind = %summary.bind_address,
+        rest_api = summary.rest_api,
+        session_duration = %summary.session_duration,
+        max_sessions_per_user = summary.max_sessions_per_user,
+        max_total_sessions = ?summary.max_total_sessions,
+        storage = summary.storage,
+        registration = summary.registration,
+        tls = summary.tls,
+        secrets = summary.secrets,
+        "Startup summary"
+    );
+}
+
 #[cfg(not(feature = "ssr"))]
 fn main() {
     // no client-side main function
     // unless we want this to work with e.g., Trunk for pure client-side testing
     // see lib.rs for hydration function instead
 }
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use axum_extra::extract::cookie::Key;
+    use bento::config::Config;
+    use bento::perf::PerfStats;
+    use bento::project_metrics::NullCollector;
+    use bento::rate_limit::{CreationCooldown, IpLoginThrottle, ProjectRateLimiter};
+    use bento::server::AppState;
+    use bento::storage::blocking::BlockingLimiter;
+    use bento::storage::maintenance::MaintenanceStats;
+    use bento::storage::redb_auditstore::RedbAuditStore;
+    use bento::storage::redb_authstore::RedbAuthStore;
+    use bento::storage::redb_projectstore::RedbProjectStore;
+    use leptos::config::LeptosOptions;
+    use std::sync::Arc;
+    use time::Duration;
+
+    fn in_memory_state(max_sessions_per_user: usize) -> AppState {
+        let auth_store = Arc::new(RedbAuthStore::in_memory(max_sessions_per_user).unwrap());
+        let project_store = Arc::new(RedbProjectStore::in_memory().unwrap());
+        AppState {
+            leptos_options: LeptosOptions::builder().output_name("bento").build(),
+            auth_store: auth_store.clone(),
+            project_store: project_store.clone(),
+            auth_db: auth_store,
+            project_db: project_store,
+            audit_store: Arc::new(RedbAuditStore::in_memory().unwrap()),
+            cookie_key: Key::generate(),
+            passkeys: None,
+            webhooks: None,
+            rate_limiter: Arc::new(ProjectRateLimiter::new()),
+            creation_cooldown: Arc::new(CreationCooldown::new(Duration::ZERO)),
+            login_throttle: Arc::new(IpLoginThrottle::new(0, Duration::ZERO, Duration::ZERO)),
+            maintenance: Arc::new(MaintenanceStats::new()),
+            perf: Arc::new(PerfStats::new()),
+            blocking: Arc::new(BlockingLimiter::unbounded()),
+            metrics_collector: Arc::new(NullCollector),
+        }
+    }
+
+    #[test]
+    fn startup_summary_reflects_the_config() {
+        let conf: Config = toml::from_str(
+            r#"
+            [server]
+            address = "127.0.0.1"
+            port = 9000
+            trusted_proxies = ["10.0.0.0/8"]
+
+            [registration]
+            allow_registration = true
+            require_invite = true
+
+            [session]
+            max_total = 100
+            "#,
+        )
+        .unwrap();
+
+        let summary = StartupSummary::new(&conf, &in_memory_state(3));
+        assert_eq!(
+            summary,
+            StartupSummary {
+                bind_address: "127.0.0.1:9000".to_string(),
+                rest_api: cfg!(feature = "rest-api"),
+                session_duration: bento::config::SESSION_DURATION,
+                max_sessions_per_user: 3,
+                max_total_sessions: Some(100),
+                storage: "memory",
+                registration: "invite only",
+                tls: "not terminated by Bento",
+                secrets: ".bento_secrets",
+            }
+        );
+
+        let defaults: Config = toml::from_str("").unwrap();
+        let summary = StartupSummary::new(&defaults, &in_memory_state(5));
+        assert_eq!(summary.registration, "closed");
+        assert_eq!(summary.max_total_sessions, None);
+    }
+}
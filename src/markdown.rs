@@ -0,0 +1,88 @@
+//! Markdown rendering of project descriptions, for `[ui] markdown_descriptions`.
+//!
+//! Descriptions are written by any user, so the HTML is passed through an allowlist
+//! sanitizer: only basic formatting, lists, code and links survive, and links can only point
+//! to web or mail addresses.
+
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use pulldown_cmark::{Options, Parser};
+
+/// Tags kept in rendered descriptions; anything else is dropped, keeping its text
+const ALLOWED_TAGS: &[&str] = &[
+    "p",
+    "br",
+    "strong",
+    "em",
+    "del",
+    "code",
+    "pre",
+    "blockquote",
+    "ul",
+    "ol",
+    "li",
+    "a",
+];
+
+static SANITIZER: LazyLock<ammonia::Builder<'static>> = LazyLock::new(|| {
+    let mut builder = ammonia::Builder::empty();
+    builder
+        .tags(ALLOWED_TAGS.iter().copied().collect())
+        // dropped along with their contents, unlike other unknown tags
+        .clean_content_tags(["script", "style"].into_iter().collect())
+        .tag_attributes(
+            [("a", ["href"].into_iter().collect())]
+                .into_iter()
+                .collect(),
+        )
+        .url_schemes(
+            ["http", "https", "mailto"]
+                .into_iter()
+                .collect::<HashSet<_>>(),
+        )
+        .link_rel(Some("noopener noreferrer nofollow ugc"));
+    builder
+});
+
+/// `markdown` as sanitized HTML, safe to insert into a page
+pub fn render(markdown: &str) -> String {
+    let parser = Parser::new_ext(markdown, Options::ENABLE_STRIKETHROUGH);
+    let mut html = String::with_capacity(markdown.len() * 3 / 2);
+    pulldown_cmark::html::push_html(&mut html, parser);
+    SANITIZER.clean(&html).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_formatting_renders() {
+        let html =
+            render("Some **bold** and *slanted* text\n\n- one\n- [two](https://example.com)");
+        assert!(html.contains("<strong>bold</strong>"));
+        assert!(html.contains("<em>slanted</em>"));
+        assert!(html.contains("<ul>\n<li>one</li>"));
+        assert!(html.contains(
+            "<a href=\"https://example.com\" rel=\"noopener noreferrer nofollow ugc\">two</a>"
+        ));
+    }
+
+    #[test]
+    fn scripts_and_unsafe_links_are_stripped() {
+        let html = render(
+            "<script>alert(1)</script>\n\n<img src=x onerror=alert(2)>\n\n\
+             [click](javascript:alert(3)) <b onclick=\"alert(4)\">hi</b>",
+        );
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("alert(1)"));
+        assert!(!html.contains("<img"));
+        assert!(!html.contains("onerror"));
+        assert!(!html.contains("onclick"));
+        assert!(!html.contains("javascript:"));
+        // the link text and tag contents remain, just not as markup
+        assert!(html.contains("click"));
+        assert!(html.contains("hi"));
+    }
+}
@@ -0,0 +1,236 @@
+//! WebAuthn passkey ceremonies.
+//!
+//! Ceremony state (the challenge a browser must answer) is kept in memory for a few
+//! minutes between the `begin_*` and `finish_*` server functions; credentials themselves
+//! are persisted through the [AuthStore](crate::storage::AuthStore).
+
+use std::sync::Arc;
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use papaya::HashMap;
+use thiserror::Error;
+use time::{Duration, OffsetDateTime};
+use webauthn_rs::prelude::*;
+
+use crate::config;
+use crate::storage::clock::{Clock, SystemClock};
+use crate::types::{PasskeyCredential, SessionId, User, UserId};
+
+/// How long a started ceremony may take before it must be restarted
+const CEREMONY_TIMEOUT: Duration = Duration::minutes(5);
+
+/// Ceremonies of each kind one user may have pending at once. Logins can be started
+/// without signing in, so without a cap anyone could grow the pending maps without bound
+pub const MAX_PENDING_PER_USER: usize = 5;
+
+#[derive(Debug, Error)]
+pub enum PasskeyError {
+    #[error("Passkey ceremony expired or not found, please try again")]
+    CeremonyNotFound,
+    #[error("No passkeys are registered for this account")]
+    NoCredentials,
+    #[error("Too many passkey requests are in progress, please try again in a few minutes")]
+    TooManyCeremonies,
+    #[error("Passkey verification failed")]
+    Webauthn(#[from] WebauthnError),
+    #[error("Invalid passkey data: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+struct PendingCeremony<T> {
+    user_id: UserId,
+    state: T,
+    expires_at: OffsetDateTime,
+}
+
+/// Runs WebAuthn registration and authentication ceremonies
+pub struct PasskeyService {
+    webauthn: Webauthn,
+    registrations: HashMap<String, PendingCeremony<PasskeyRegistration>>,
+    authentications: HashMap<String, PendingCeremony<PasskeyAuthentication>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl PasskeyService {
+    pub fn new(conf: &config::Passkeys) -> Result<Self, PasskeyError> {
+        let origin = Url::parse(&conf.rp_origin).map_err(|_| WebauthnError::Configuration)?;
+        let webauthn = WebauthnBuilder::new(&conf.rp_id, &origin)?
+            .rp_name(&conf.rp_name)
+            .build()?;
+
+        Ok(Self {
+            webauthn,
+            registrations: HashMap::new(),
+            authentications: HashMap::new(),
+            clock: Arc::new(SystemClock),
+        })
+    }
+
+    /// Sets the clock used to expire ceremonies
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Starts registering a new passkey for `user`, excluding the ones they already have.
+    ///
+    /// Returns the ceremony id and the options to pass to `navigator.credentials.create()`.
+    pub fn start_registration(
+        &self,
+        user: &User,
+        existing: &[PasskeyCredential],
+    ) -> Result<(String, CreationChallengeResponse), PasskeyError> {
+        self.make_room(&self.registrations, &user.id)?;
+        let exclude = existing
+            .iter()
+            .map(Self::decode_passkey)
+            .map(|passkey| passkey.map(|p| p.cred_id().clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (challenge, state) = self.webauthn.start_passkey_registration(
+            user.id.0,
+            user.username.as_ref(),
+            user.username.as_ref(),
+            Some(exclude),
+        )?;
+
+        let ceremony_id = Self::ceremony_id();
+        self.registrations
+            .pin()
+            .insert(ceremony_id.clone(), self.pending(user.id, state));
+
+        Ok((ceremony_id, challenge))
+    }
+
+    /// Verifies the browser's response and returns the credential to store
+    pub fn finish_registration(
+        &self,
+        ceremony_id: &str,
+        user_id: &UserId,
+        response: &RegisterPublicKeyCredential,
+    ) -> Result<PasskeyCredential, PasskeyError> {
+        let registrations = self.registrations.pin();
+        let pending = registrations
+            .remove(ceremony_id)
+            .filter(|pending| pending.user_id == *user_id && pending.expires_at > self.clock.now())
+            .ok_or(PasskeyError::CeremonyNotFound)?;
+
+        let passkey = self
+            .webauthn
+            .finish_passkey_registration(response, &pending.state)?;
+
+        Ok(PasskeyCredential {
+            credential_id: URL_SAFE_NO_PAD.encode(passkey.cred_id()),
+            user_id: *user_id,
+            passkey_json: serde_json::to_string(&passkey)?,
+            created_at: self.clock.now(),
+        })
+    }
+
+    /// Starts a login for a user with the given registered passkeys.
+    ///
+    /// Returns the ceremony id and the options to pass to `navigator.credentials.get()`.
+    pub fn start_authentication(
+        &self,
+        user_id: &UserId,
+        credentials: &[PasskeyCredential],
+    ) -> Result<(String, RequestChallengeResponse), PasskeyError> {
+        if credentials.is_empty() {
+            return Err(PasskeyError::NoCredentials);
+        }
+        self.make_room(&self.authentications, user_id)?;
+
+        let passkeys = credentials
+            .iter()
+            .map(Self::decode_passkey)
+            .collect::<Result<Vec<_>, _>>()?;
+        let (challenge, state) = self.webauthn.start_passkey_authentication(&passkeys)?;
+
+        let ceremony_id = Self::ceremony_id();
+        self.authentications
+            .pin()
+            .insert(ceremony_id.clone(), self.pending(*user_id, state));
+
+        Ok((ceremony_id, challenge))
+    }
+
+    /// Verifies the browser's assertion.
+    ///
+    /// Returns the authenticated user and any credentials whose stored state (e.g., the
+    /// signature counter) changed and should be saved back.
+    pub fn finish_authentication(
+        &self,
+        ceremony_id: &str,
+        response: &PublicKeyCredential,
+        credentials: &[PasskeyCredential],
+    ) -> Result<(UserId, Vec<PasskeyCredential>), PasskeyError> {
+        let authentications = self.authentications.pin();
+        let pending = authentications
+            .remove(ceremony_id)
+            .filter(|pending| pending.expires_at > self.clock.now())
+            .ok_or(PasskeyError::CeremonyNotFound)?;
+
+        let result = self
+            .webauthn
+            .finish_passkey_authentication(response, &pending.state)?;
+
+        let mut updated = Vec::new();
+        for credential in credentials {
+            let mut passkey = Self::decode_passkey(credential)?;
+            if passkey.update_credential(&result) == Some(true) {
+                updated.push(PasskeyCredential {
+                    passkey_json: serde_json::to_string(&passkey)?,
+                    ..credential.clone()
+                });
+            }
+        }
+
+        Ok((pending.user_id, updated))
+    }
+
+    /// Returns the user a pending, unexpired login ceremony was started for
+    pub fn authentication_user(&self, ceremony_id: &str) -> Option<UserId> {
+        self.authentications
+            .pin()
+            .get(ceremony_id)
+            .filter(|pending| pending.expires_at > self.clock.now())
+            .map(|pending| pending.user_id)
+    }
+
+    fn decode_passkey(credential: &PasskeyCredential) -> Result<Passkey, PasskeyError> {
+        Ok(serde_json::from_str(&credential.passkey_json)?)
+    }
+
+    /// Drops expired ceremonies, then refuses another one for `user_id` if they already have
+    /// [MAX_PENDING_PER_USER] pending
+    fn make_room<T>(
+        &self,
+        ceremonies: &HashMap<String, PendingCeremony<T>>,
+        user_id: &UserId,
+    ) -> Result<(), PasskeyError> {
+        let now = self.clock.now();
+        let mut ceremonies = ceremonies.pin();
+        ceremonies.retain(|_, pending| pending.expires_at > now);
+        let pending = ceremonies
+            .values()
+            .filter(|pending| pending.user_id == *user_id)
+            .count();
+        if pending >= MAX_PENDING_PER_USER {
+            return Err(PasskeyError::TooManyCeremonies);
+        }
+        Ok(())
+    }
+
+    fn pending<T>(&self, user_id: UserId, state: T) -> PendingCeremony<T> {
+        PendingCeremony {
+            user_id,
+            state,
+            expires_at: self.clock.now() + CEREMONY_TIMEOUT,
+        }
+    }
+
+    fn ceremony_id() -> String {
+        // session ids are already unguessable random tokens, reuse their generator
+        SessionId::new().0
+    }
+}
@@ -0,0 +1,146 @@
+//! Server function latency sampling.
+//!
+//! Server functions start a [PerfTimer] with [timer]; when it is dropped, the elapsed time is
+//! recorded in the [PerfStats] shared through `AppState`. The most recent samples per function
+//! are kept, and admins read their percentiles through `get_perf_stats`.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use leptos::prelude::use_context;
+
+use crate::server::AppState;
+use crate::types::PerfReport;
+
+/// Samples kept per function; older ones are dropped first
+const SAMPLES_PER_FUNCTION: usize = 512;
+
+/// Rolling latency samples per server function
+#[derive(Debug, Default)]
+pub struct PerfStats {
+    samples: Mutex<HashMap<&'static str, VecDeque<Duration>>>,
+}
+
+impl PerfStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, function: &'static str, elapsed: Duration) {
+        let mut samples = self.samples.lock().expect("perf stats lock");
+        let samples = samples.entry(function).or_default();
+        if samples.len() == SAMPLES_PER_FUNCTION {
+            samples.pop_front();
+        }
+        samples.push_back(elapsed);
+    }
+
+    /// Starts timing `function`, recorded once the returned timer is dropped
+    pub fn start(self: &Arc<Self>, function: &'static str) -> PerfTimer {
+        PerfTimer {
+            stats: Some(self.clone()),
+            function,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Percentiles of the kept samples, by function name
+    pub fn report(&self) -> Vec<PerfReport> {
+        let samples = self.samples.lock().expect("perf stats lock");
+        let mut reports: Vec<_> = samples
+            .iter()
+            .filter(|(_, samples)| !samples.is_empty())
+            .map(|(function, samples)| {
+                let mut sorted: Vec<_> = samples.iter().copied().collect();
+                sorted.sort_unstable();
+                PerfReport {
+                    function: function.to_string(),
+                    samples: sorted.len(),
+                    p50_ms: percentile_ms(&sorted, 50),
+                    p95_ms: percentile_ms(&sorted, 95),
+                    p99_ms: percentile_ms(&sorted, 99),
+                }
+            })
+            .collect();
+        reports.sort_by(|a, b| a.function.cmp(&b.function));
+        reports
+    }
+}
+
+/// Nearest-rank percentile of sorted, non-empty samples, in milliseconds
+fn percentile_ms(sorted: &[Duration], percentile: usize) -> f64 {
+    let rank = (percentile * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1].as_nanos() as f64 / 1_000_000.0
+}
+
+/// Records the time since it was started when dropped, including on early returns
+#[must_use = "the timer records when dropped, so bind it to a variable"]
+pub struct PerfTimer {
+    stats: Option<Arc<PerfStats>>,
+    function: &'static str,
+    started_at: Instant,
+}
+
+impl Drop for PerfTimer {
+    fn drop(&mut self) {
+        if let Some(stats) = &self.stats {
+            stats.record(self.function, self.started_at.elapsed());
+        }
+    }
+}
+
+/// Times the calling server function into the `AppState` stats.
+///
+/// Outside of a request (no `AppState` in context) nothing is recorded.
+///
+/// # Example
+/// ```ignore
+/// let _timer = crate::perf::timer("create_project");
+/// ```
+pub fn timer(function: &'static str) -> PerfTimer {
+    match use_context::<AppState>() {
+        Some(app_state) => app_state.perf.start(function),
+        None => PerfTimer {
+            stats: None,
+            function,
+            started_at: Instant::now(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn timed_calls_are_recorded() {
+        let stats = Arc::new(PerfStats::new());
+        {
+            let _timer = stats.start("slow_function");
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let report = stats.report();
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].function, "slow_function");
+        assert_eq!(report[0].samples, 1);
+        assert!(report[0].p50_ms >= 5.0);
+    }
+
+    #[test]
+    fn percentiles_use_the_most_recent_samples() {
+        let stats = PerfStats::new();
+        // pushed out by the next SAMPLES_PER_FUNCTION samples
+        stats.record("login", Duration::from_secs(60));
+        for ms in 1..=SAMPLES_PER_FUNCTION as u64 {
+            stats.record("login", Duration::from_millis(ms));
+        }
+
+        let report = &stats.report()[0];
+        assert_eq!(report.samples, SAMPLES_PER_FUNCTION);
+        assert_eq!(report.p50_ms, 256.0);
+        assert_eq!(report.p95_ms, 487.0);
+        assert_eq!(report.p99_ms, 507.0);
+    }
+}
@@ -0,0 +1,279 @@
+//! Single-project archives: a zip holding a manifest, the project record and its
+//! settings, downloaded with `export_project_archive` and restored with
+//! `import_project_archive`.
+//!
+//! Entries are deflated, and each is read back through a cap of `MAX_ARCHIVE_BYTES` so
+//! a small archive can't unpack into an unbounded amount of memory.
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::types::{Project, ProjectVisibility};
+
+/// Layout of the files inside an archive; bump when they change incompatibly
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Largest archive accepted for import
+pub const MAX_ARCHIVE_BYTES: usize = 1024 * 1024;
+
+/// `format` of every manifest, telling our archives apart from any other zip
+const ARCHIVE_FORMAT: &str = "bento-project";
+
+const MANIFEST_FILE: &str = "manifest.json";
+const PROJECT_FILE: &str = "project.json";
+const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("This isn't a valid project archive: {0}")]
+    Malformed(String),
+    #[error("Unsupported archive version {0}; this server reads version {ARCHIVE_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("Archives can be at most {} KiB", MAX_ARCHIVE_BYTES / 1024)]
+    TooLarge,
+}
+
+/// First file of every archive, checked before anything else is read
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format: String,
+    pub version: u32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub exported_at: OffsetDateTime,
+    /// Server version that wrote the archive, for troubleshooting
+    pub app_version: String,
+}
+
+/// The project record, without the parts that belong to the instance it came from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedProject {
+    pub name: String,
+    pub description: Option<String>,
+    pub slug: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedSettings {
+    pub visibility: ProjectVisibility,
+    pub rate_limit_rpm: Option<u32>,
+    /// Missing from archives made before projects could be protected
+    #[serde(default)]
+    pub delete_protected: bool,
+}
+
+/// Everything an archive carries about one project
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectArchive {
+    pub manifest: ArchiveManifest,
+    pub project: ArchivedProject,
+    pub settings: ArchivedSettings,
+}
+
+impl ProjectArchive {
+    /// Snapshot `project` as of `exported_at`
+    pub fn of(project: &Project, exported_at: OffsetDateTime) -> Self {
+        Self {
+            manifest: ArchiveManifest {
+                format: ARCHIVE_FORMAT.to_string(),
+                version: ARCHIVE_VERSION,
+                exported_at,
+                app_version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            project: ArchivedProject {
+                name: project.name.clone(),
+                description: project.description.clone(),
+                slug: project.slug.clone(),
+                created_at: project.created_at,
+                updated_at: project.updated_at,
+            },
+            settings: ArchivedSettings {
+                visibility: project.visibility,
+                rate_limit_rpm: project.rate_limit_rpm,
+                delete_protected: project.delete_protected,
+            },
+        }
+    }
+
+    /// Encode as a zip, manifest first
+    pub fn to_zip(&self) -> Result<Vec<u8>, ArchiveError> {
+        use std::io::{Cursor, Write};
+        use zip::CompressionMethod;
+        use zip::write::{SimpleFileOptions, ZipWriter};
+
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in [
+            (MANIFEST_FILE, encode(&self.manifest)?),
+            (PROJECT_FILE, encode(&self.project)?),
+            (SETTINGS_FILE, encode(&self.settings)?),
+        ] {
+            zip.start_file(name, options).map_err(malformed)?;
+            zip.write_all(&data).map_err(malformed)?;
+        }
+        Ok(zip.finish().map_err(malformed)?.into_inner())
+    }
+
+    /// Decode a zip made by [`ProjectArchive::to_zip`], refusing other formats and
+    /// versions before looking at the project itself
+    pub fn from_zip(bytes: &[u8]) -> Result<Self, ArchiveError> {
+        if bytes.len() > MAX_ARCHIVE_BYTES {
+            return Err(ArchiveError::TooLarge);
+        }
+        let mut files = zip::ZipArchive::new(std::io::Cursor::new(bytes)).map_err(malformed)?;
+
+        let manifest: ArchiveManifest = decode(&mut files, MANIFEST_FILE)?;
+        if manifest.format != ARCHIVE_FORMAT {
+            return Err(ArchiveError::Malformed(format!(
+                "unknown format \"{}\"",
+                manifest.format
+            )));
+        }
+        if manifest.version != ARCHIVE_VERSION {
+            return Err(ArchiveError::UnsupportedVersion(manifest.version));
+        }
+
+        Ok(Self {
+            manifest,
+            project: decode(&mut files, PROJECT_FILE)?,
+            settings: decode(&mut files, SETTINGS_FILE)?,
+        })
+    }
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, ArchiveError> {
+    serde_json::to_vec_pretty(value).map_err(|err| ArchiveError::Malformed(err.to_string()))
+}
+
+fn decode<T: serde::de::DeserializeOwned>(
+    files: &mut zip::ZipArchive<std::io::Cursor<&[u8]>>,
+    name: &str,
+) -> Result<T, ArchiveError> {
+    use std::io::Read;
+    use zip::result::ZipError;
+
+    let file = files.by_name(name).map_err(|err| match err {
+        ZipError::FileNotFound => ArchiveError::Malformed(format!("{name} is missing")),
+        err => ArchiveError::Malformed(format!("{name}: {err}")),
+    })?;
+    // reading one byte past the limit tells a file that fits from one that was cut off
+    let mut data = Vec::new();
+    file.take(MAX_ARCHIVE_BYTES as u64 + 1)
+        .read_to_end(&mut data)
+        .map_err(|err| ArchiveError::Malformed(format!("{name}: {err}")))?;
+    if data.len() > MAX_ARCHIVE_BYTES {
+        return Err(ArchiveError::TooLarge);
+    }
+    serde_json::from_slice(&data).map_err(|err| ArchiveError::Malformed(format!("{name}: {err}")))
+}
+
+fn malformed(err: impl std::fmt::Display) -> ArchiveError {
+    ArchiveError::Malformed(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ProjectId, UserId};
+    use uuid::Uuid;
+
+    fn project() -> Project {
+        Project {
+            id: ProjectId(Uuid::now_v7()),
+            owner_id: UserId(Uuid::now_v7()),
+            name: "Lunch orders".to_string(),
+            description: Some("Who wants what".to_string()),
+            created_at: OffsetDateTime::UNIX_EPOCH,
+            updated_at: OffsetDateTime::UNIX_EPOCH + time::Duration::days(1),
+            rate_limit_rpm: Some(30),
+            visibility: ProjectVisibility::Unlisted,
+            slug: "lunch-orders".to_string(),
+            delete_protected: true,
+        }
+    }
+
+    #[test]
+    fn archives_round_trip_through_zip() {
+        let archive = ProjectArchive::of(&project(), OffsetDateTime::UNIX_EPOCH);
+        let bytes = archive.to_zip().unwrap();
+
+        assert_eq!(&bytes[..4], b"PK\x03\x04");
+        assert_eq!(ProjectArchive::from_zip(&bytes).unwrap(), archive);
+    }
+
+    #[test]
+    fn other_versions_and_formats_are_refused() {
+        let mut archive = ProjectArchive::of(&project(), OffsetDateTime::UNIX_EPOCH);
+        archive.manifest.version = ARCHIVE_VERSION + 1;
+        assert_eq!(
+            ProjectArchive::from_zip(&archive.to_zip().unwrap()),
+            Err(ArchiveError::UnsupportedVersion(ARCHIVE_VERSION + 1))
+        );
+
+        archive.manifest.version = ARCHIVE_VERSION;
+        archive.manifest.format = "something-else".to_string();
+        assert!(matches!(
+            ProjectArchive::from_zip(&archive.to_zip().unwrap()),
+            Err(ArchiveError::Malformed(_))
+        ));
+    }
+
+    /// A zip of `files` as another tool might write it
+    fn zip_of(files: &[(&str, &[u8])], method: zip::CompressionMethod) -> Vec<u8> {
+        use std::io::{Cursor, Write};
+        use zip::write::{SimpleFileOptions, ZipWriter};
+
+        let options = SimpleFileOptions::default().compression_method(method);
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn damaged_archives_are_refused() {
+        use zip::CompressionMethod;
+
+        assert!(ProjectArchive::from_zip(b"not a zip").is_err());
+
+        let without_manifest = zip_of(&[(PROJECT_FILE, b"{}")], CompressionMethod::Stored);
+        assert_eq!(
+            ProjectArchive::from_zip(&without_manifest),
+            Err(ArchiveError::Malformed(
+                "manifest.json is missing".to_string()
+            ))
+        );
+
+        let archive = ProjectArchive::of(&project(), OffsetDateTime::UNIX_EPOCH);
+        let manifest = encode(&archive.manifest).unwrap();
+        let mut corrupt = zip_of(&[(MANIFEST_FILE, &manifest)], CompressionMethod::Stored);
+        let at = corrupt
+            .windows(manifest.len())
+            .position(|window| window == manifest.as_slice())
+            .unwrap();
+        corrupt[at + manifest.len() / 2] ^= 0xff;
+        assert!(matches!(
+            ProjectArchive::from_zip(&corrupt),
+            Err(ArchiveError::Malformed(_))
+        ));
+
+        let oversized = vec![0; MAX_ARCHIVE_BYTES + 1];
+        assert_eq!(
+            ProjectArchive::from_zip(&oversized),
+            Err(ArchiveError::TooLarge)
+        );
+
+        // compresses to a few KiB, but mustn't be unpacked in full
+        let bomb = zip_of(
+            &[(MANIFEST_FILE, &vec![b' '; 4 * MAX_ARCHIVE_BYTES])],
+            CompressionMethod::Deflated,
+        );
+        assert!(bomb.len() < MAX_ARCHIVE_BYTES);
+        assert_eq!(ProjectArchive::from_zip(&bomb), Err(ArchiveError::TooLarge));
+    }
+}
@@ -0,0 +1,169 @@
+//! Usage metrics of projects (storage, users), refreshed in the background.
+//!
+//! A [MetricsCollector] measures one project at a time. Every `[projects]
+//! metrics_interval_secs`, [spawn_metrics_collector] runs it over all projects and caches the
+//! results in the project store, where `get_project_metrics` reads them, so showing a
+//! project never waits on a measurement. Bento has nothing to measure yet and ships the
+//! [NullCollector]; a real collector only has to implement the trait and be put in
+//! `AppState` in its place.
+
+use std::sync::Arc;
+
+use tracing::{debug, error, warn};
+
+use crate::storage::ProjectError;
+use crate::storage::clock::Clock;
+use crate::storage::dyn_store::BoxFuture;
+use crate::storage::redb_projectstore::RedbProjectStore;
+use crate::types::{ProjectId, ProjectMetrics};
+
+/// Measures a project's usage
+pub trait MetricsCollector: Send + Sync {
+    /// Current metrics of the project, `None` when they can't be gathered right now; the
+    /// ones cached before are then kept. `collected_at` is filled in by the caller.
+    fn collect<'a>(&'a self, project_id: &'a ProjectId) -> BoxFuture<'a, Option<ProjectMetrics>>;
+}
+
+/// Reports zeros for every project, until there is something to measure
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullCollector;
+
+impl MetricsCollector for NullCollector {
+    fn collect<'a>(&'a self, _project_id: &'a ProjectId) -> BoxFuture<'a, Option<ProjectMetrics>> {
+        Box::pin(async { Some(ProjectMetrics::default()) })
+    }
+}
+
+/// Collects and stores the metrics of every project once, returning how many were updated.
+///
+/// A project whose metrics can't be gathered or stored is logged and skipped.
+pub async fn collect_project_metrics(
+    store: &RedbProjectStore,
+    collector: &dyn MetricsCollector,
+    clock: &dyn Clock,
+) -> Result<usize, ProjectError> {
+    let mut updated = 0;
+    for project_id in store.project_ids().await? {
+        let Some(metrics) = collector.collect(&project_id).await else {
+            warn!(project_id = %project_id.0, "No metrics collected for project");
+            continue;
+        };
+        let metrics = ProjectMetrics {
+            collected_at: Some(clock.now()),
+            ..metrics
+        };
+        match store.store_project_metrics(&project_id, metrics).await {
+            Ok(()) => updated += 1,
+            Err(e) => error!(project_id = %project_id.0, "Failed to store project metrics: {e}"),
+        }
+    }
+    debug!(updated, "Project metrics collected");
+    Ok(updated)
+}
+
+/// Starts a background task running [collect_project_metrics] every `interval`, the first
+/// time right away.
+///
+/// Must be called from within a tokio runtime.
+pub fn spawn_metrics_collector(
+    store: Arc<RedbProjectStore>,
+    collector: Arc<dyn MetricsCollector>,
+    clock: Arc<dyn Clock>,
+    interval: std::time::Duration,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) =
+                collect_project_metrics(store.as_ref(), collector.as_ref(), clock.as_ref()).await
+            {
+                error!("Collecting project metrics failed: {e}");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::ProjectStore;
+    use crate::storage::clock::TestClock;
+    use crate::types::UserId;
+
+    /// Reports a fixed size for every project, but nothing for `skipped`
+    struct FakeCollector {
+        skipped: Option<ProjectId>,
+    }
+
+    impl MetricsCollector for FakeCollector {
+        fn collect<'a>(
+            &'a self,
+            project_id: &'a ProjectId,
+        ) -> BoxFuture<'a, Option<ProjectMetrics>> {
+            Box::pin(async move {
+                (self.skipped != Some(*project_id)).then_some(ProjectMetrics {
+                    storage_bytes: 2048,
+                    users: 3,
+                    collected_at: None,
+                })
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn collector_output_is_stored_per_project() {
+        let clock = Arc::new(TestClock::new());
+        let store = Arc::new(RedbProjectStore::in_memory().unwrap());
+        let owner = UserId::new();
+        let (measured, _) = store
+            .create_project(&owner, "Measured".to_string(), None, None)
+            .await
+            .unwrap();
+        let (skipped, _) = store
+            .create_project(&owner, "Skipped".to_string(), None, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            store.project_metrics(&measured.id).await.unwrap(),
+            ProjectMetrics::default()
+        );
+
+        spawn_metrics_collector(
+            store.clone(),
+            Arc::new(FakeCollector {
+                skipped: Some(skipped.id),
+            }),
+            clock.clone(),
+            std::time::Duration::from_secs(3600),
+        );
+        // the first run starts right away
+        let mut metrics = ProjectMetrics::default();
+        for _ in 0..100 {
+            metrics = store.project_metrics(&measured.id).await.unwrap();
+            if metrics.collected_at.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            metrics,
+            ProjectMetrics {
+                storage_bytes: 2048,
+                users: 3,
+                collected_at: Some(clock.now()),
+            }
+        );
+        assert_eq!(
+            store.project_metrics(&skipped.id).await.unwrap(),
+            ProjectMetrics::default()
+        );
+
+        // metrics go along with their project
+        store.delete_project(&measured.id).await.unwrap();
+        assert_eq!(
+            store.project_metrics(&measured.id).await.unwrap(),
+            ProjectMetrics::default()
+        );
+    }
+}
@@ -0,0 +1,591 @@
+//! Per-project request rate limiting.
+//!
+//! Each project may set `rate_limit_rpm`; requests tagged with a project id are counted in
+//! fixed one-minute windows by a [ProjectRateLimiter] shared through `AppState`. The
+//! [CreationCooldown] spaces out each user's project creations, the [IpLoginThrottle]
+//! blocks addresses guessing passwords across many accounts, and the [IpConcurrencyLimiter]
+//! caps how many requests one address may have in flight.
+
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    extract::{Path, Request, State},
+    http::{HeaderValue, StatusCode, header::RETRY_AFTER},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_client_ip::ClientIp;
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, warn};
+
+use crate::storage::clock::{Clock, SystemClock};
+use crate::storage::{ProjectError, ProjectStore};
+use crate::types::{ProjectId, UserId};
+
+/// Length of a rate limiting window
+const WINDOW: Duration = Duration::minutes(1);
+
+/// Returned when a project's request budget for the current window is used up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimited {
+    /// Time until the current window ends
+    pub retry_after: Duration,
+}
+
+struct Window {
+    started_at: OffsetDateTime,
+    count: u32,
+}
+
+/// Counts requests per project against each project's configured limit
+pub struct ProjectRateLimiter {
+    windows: Mutex<HashMap<ProjectId, Window>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl ProjectRateLimiter {
+    pub fn new() -> Self {
+        Self {
+            windows: Mutex::new(HashMap::new()),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sets the clock used to start and expire windows
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Counts one request for `project_id`, failing once `limit_rpm` requests were seen in
+    /// the current window. Projects without a limit are never throttled.
+    pub fn check(&self, project_id: &ProjectId, limit_rpm: Option<u32>) -> Result<(), RateLimited> {
+        let Some(limit) = limit_rpm else {
+            return Ok(());
+        };
+        let now = self.clock.now();
+
+        let mut windows = self.windows.lock().unwrap();
+        // drop finished windows so idle projects don't accumulate
+        windows.retain(|_, window| now - window.started_at < WINDOW);
+
+        let window = windows.entry(*project_id).or_insert(Window {
+            started_at: now,
+            count: 0,
+        });
+        if window.count >= limit {
+            return Err(RateLimited {
+                retry_after: WINDOW - (now - window.started_at),
+            });
+        }
+        window.count += 1;
+        Ok(())
+    }
+}
+
+impl Default for ProjectRateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Minimum time between two projects created by the same user, from
+/// `[projects] creation_cooldown_secs`. A zero cooldown never throttles.
+pub struct CreationCooldown {
+    cooldown: Duration,
+    last_created: Mutex<HashMap<UserId, OffsetDateTime>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl CreationCooldown {
+    pub fn new(cooldown: Duration) -> Self {
+        Self {
+            cooldown,
+            last_created: Mutex::new(HashMap::new()),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sets the clock creations are timed with
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Fails while `user_id` created a project less than the cooldown ago
+    pub fn check(&self, user_id: &UserId) -> Result<(), RateLimited> {
+        if self.cooldown <= Duration::ZERO {
+            return Ok(());
+        }
+        let now = self.clock.now();
+
+        let mut last_created = self.last_created.lock().unwrap();
+        // forget users whose cooldown ran out so the map stays small
+        last_created.retain(|_, at| now - *at < self.cooldown);
+
+        match last_created.get(user_id) {
+            Some(at) => Err(RateLimited {
+                retry_after: self.cooldown - (now - *at),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Starts the cooldown of `user_id`, after a project was created
+    pub fn record(&self, user_id: &UserId) {
+        if self.cooldown <= Duration::ZERO {
+            return;
+        }
+        let now = self.clock.now();
+        self.last_created.lock().unwrap().insert(*user_id, now);
+    }
+}
+
+/// Failed logins from one address in its current window
+struct IpFailures {
+    window_started_at: OffsetDateTime,
+    /// Usernames that failed, each counted once
+    usernames: HashSet<String>,
+    blocked_until: Option<OffsetDateTime>,
+}
+
+/// Blocks addresses whose logins failed against `[lockout] ip_max_accounts` different
+/// usernames within `ip_window_minutes`, whatever username they try next. A password spray
+/// guesses once per account and never trips the per-account lock. A zero threshold never
+/// blocks.
+pub struct IpLoginThrottle {
+    max_accounts: u32,
+    window: Duration,
+    block: Duration,
+    failures: Mutex<HashMap<IpAddr, IpFailures>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl IpLoginThrottle {
+    pub fn new(max_accounts: u32, window: Duration, block: Duration) -> Self {
+        Self {
+            max_accounts,
+            window,
+            block,
+            failures: Mutex::new(HashMap::new()),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sets the clock windows and blocks are timed with
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Fails while `ip` is blocked
+    pub fn check(&self, ip: &IpAddr) -> Result<(), RateLimited> {
+        if self.max_accounts == 0 {
+            return Ok(());
+        }
+        let now = self.clock.now();
+
+        let mut failures = self.failures.lock().unwrap();
+        // forget addresses that are neither blocked nor inside a window
+        failures.retain(|_, entry| {
+            entry.blocked_until.is_some_and(|until| until > now)
+                || now - entry.window_started_at < self.window
+        });
+
+        match failures.get(ip).and_then(|entry| entry.blocked_until) {
+            Some(until) if until > now => Err(RateLimited {
+                retry_after: until - now,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts a failed login for `username` from `ip`, blocking the address once it has
+    /// failed against enough different usernames in the window
+    pub fn record_failure(&self, ip: IpAddr, username: &str) {
+        if self.max_accounts == 0 {
+            return;
+        }
+        let now = self.clock.now();
+
+        let mut failures = self.failures.lock().unwrap();
+        let entry = failures.entry(ip).or_insert_with(|| IpFailures {
+            window_started_at: now,
+            usernames: HashSet::new(),
+            blocked_until: None,
+        });
+        if now - entry.window_started_at >= self.window {
+            entry.window_started_at = now;
+            entry.usernames.clear();
+        }
+        entry.usernames.insert(username.trim().to_string());
+
+        if entry.usernames.len() >= self.max_accounts as usize {
+            warn!(
+                %ip,
+                accounts = entry.usernames.len(),
+                "Blocking logins from an address failing against many accounts"
+            );
+            entry.blocked_until = Some(now + self.block);
+            entry.window_started_at = now;
+            entry.usernames.clear();
+        }
+    }
+}
+
+/// Requests in flight per client address
+type InFlight = Arc<Mutex<HashMap<IpAddr, usize>>>;
+
+/// Caps the requests each address may have in flight at once, from `[server]
+/// max_concurrent_per_ip`. Unlike the rate limiters this counts requests that are still
+/// running, so a client holding connections open with slow requests can't tie up the
+/// server. A zero limit never throttles.
+pub struct IpConcurrencyLimiter {
+    max_per_ip: usize,
+    in_flight: InFlight,
+}
+
+impl IpConcurrencyLimiter {
+    pub fn new(max_per_ip: usize) -> Self {
+        Self {
+            max_per_ip,
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Takes one of the slots of `ip`, `None` when all are in use. The slot is given back
+    /// when the permit is dropped.
+    pub fn try_acquire(&self, ip: IpAddr) -> Option<ConcurrencyPermit> {
+        if self.max_per_ip == 0 {
+            return Some(ConcurrencyPermit(None));
+        }
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let count = in_flight.entry(ip).or_insert(0);
+        if *count >= self.max_per_ip {
+            return None;
+        }
+        *count += 1;
+        Some(ConcurrencyPermit(Some((ip, self.in_flight.clone()))))
+    }
+
+    /// Requests of `ip` currently holding a slot
+    pub fn in_flight(&self, ip: &IpAddr) -> usize {
+        self.in_flight.lock().unwrap().get(ip).copied().unwrap_or(0)
+    }
+}
+
+/// A slot of an [IpConcurrencyLimiter], released on drop
+pub struct ConcurrencyPermit(Option<(IpAddr, InFlight)>);
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        let Some((ip, in_flight)) = self.0.take() else {
+            return;
+        };
+        let mut in_flight = in_flight.lock().unwrap();
+        if let Some(count) = in_flight.get_mut(&ip) {
+            *count -= 1;
+            // idle addresses don't keep an entry
+            if *count == 0 {
+                in_flight.remove(&ip);
+            }
+        }
+    }
+}
+
+/// Middleware answering `503 Service Unavailable` while the client IP already has `[server]
+/// max_concurrent_per_ip` requests in flight. The slot is held until the response is
+/// ready; a streamed body doesn't keep it.
+///
+/// Has to run inside `select_client_ip_source`, which decides where the client IP comes
+/// from.
+///
+/// # Example
+/// ```ignore
+/// let app = Router::new()
+///     .merge(ssr)
+///     .layer(from_fn_with_state(limiter, limit_concurrent_requests))
+///     .layer(from_fn_with_state(trusted_proxies, select_client_ip_source));
+/// ```
+pub async fn limit_concurrent_requests(
+    State(limiter): State<Arc<IpConcurrencyLimiter>>,
+    ClientIp(ip): ClientIp,
+    request: Request,
+    next: Next,
+) -> Response {
+    match limiter.try_acquire(ip) {
+        Some(_permit) => next.run(request).await,
+        None => {
+            debug!(%ip, "Too many concurrent requests from one address");
+            StatusCode::SERVICE_UNAVAILABLE.into_response()
+        }
+    }
+}
+
+/// `429 Too Many Requests` with a `Retry-After` of at least a second
+pub fn too_many_requests(retry_after: Duration) -> Response {
+    let seconds = retry_after.whole_seconds().max(1);
+    let mut response = StatusCode::TOO_MANY_REQUESTS.into_response();
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(seconds));
+    response
+}
+
+/// Middleware enforcing the rate limit of the project named by the `{project_id}` path
+/// parameter.
+///
+/// Responds with 400 for malformed IDs, 404 for unknown projects and 429 (with
+/// `Retry-After`) when throttled.
+///
+/// # Example
+/// ```ignore
+/// let project_routes = Router::new()
+///     .route("/api/v1/projects/{project_id}/events", get(events))
+///     .route_layer(from_fn_with_state(
+///         (limiter, project_store),
+///         limit_project_requests::<RedbProjectStore>,
+///     ));
+/// ```
+pub async fn limit_project_requests<S: ProjectStore + ?Sized + 'static>(
+    State((limiter, project_store)): State<(Arc<ProjectRateLimiter>, Arc<S>)>,
+    Path(project_id): Path<String>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let project_id = match ProjectId::parse(&project_id) {
+        Ok(project_id) => project_id,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    };
+
+    let project = match project_store.get_project(&project_id).await {
+        Ok(project) => project,
+        Err(ProjectError::NotFound) => return StatusCode::NOT_FOUND.into_response(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+
+    match limiter.check(&project.id, project.rate_limit_rpm) {
+        Ok(()) => next.run(request).await,
+        Err(RateLimited { retry_after }) => too_many_requests(retry_after),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::clock::TestClock;
+    use crate::storage::redb_projectstore::RedbProjectStore;
+    use crate::types::UserId;
+    use axum::{Router, body::Body, middleware::from_fn_with_state, routing::get};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn configured_limit_throttles_tagged_requests() {
+        let clock = Arc::new(TestClock::new());
+        let limiter = Arc::new(ProjectRateLimiter::new().with_clock(clock.clone()));
+        let store = Arc::new(RedbProjectStore::in_memory().unwrap());
+
+        let owner = UserId::new();
+        let limited = store
+            .create_project(&owner, "Limited".to_string(), None, None)
+            .await
+            .unwrap()
+            .0;
+        store
+            .update_project(&limited.id, None, None, Some(Some(2)), None)
+            .await
+            .unwrap();
+        let unlimited = store
+            .create_project(&owner, "Unlimited".to_string(), None, None)
+            .await
+            .unwrap()
+            .0;
+
+        let app = Router::new()
+            .route("/projects/{project_id}", get(|| async { StatusCode::OK }))
+            .route_layer(from_fn_with_state(
+                (limiter, store),
+                limit_project_requests::<RedbProjectStore>,
+            ));
+        let call = |project_id: ProjectId| {
+            let app = app.clone();
+            async move {
+                let request = Request::builder()
+                    .uri(format!("/projects/{}", project_id.0))
+                    .body(Body::empty())
+                    .unwrap();
+                app.oneshot(request).await.unwrap()
+            }
+        };
+
+        assert_eq!(call(limited.id).await.status(), StatusCode::OK);
+        assert_eq!(call(limited.id).await.status(), StatusCode::OK);
+        let throttled = call(limited.id).await;
+        assert_eq!(throttled.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(throttled.headers().contains_key(RETRY_AFTER));
+
+        // other projects have their own budget
+        for _ in 0..5 {
+            assert_eq!(call(unlimited.id).await.status(), StatusCode::OK);
+        }
+        assert_eq!(call(ProjectId::new()).await.status(), StatusCode::NOT_FOUND);
+
+        // the budget resets with the next window
+        clock.advance(WINDOW);
+        assert_eq!(call(limited.id).await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_beyond_the_limit_are_refused() {
+        use axum::extract::ConnectInfo;
+        use axum_client_ip::ClientIpSource;
+        use std::net::SocketAddr;
+        use tokio::sync::Semaphore;
+
+        let limiter = Arc::new(IpConcurrencyLimiter::new(2));
+        // requests to /slow wait until the gate lets them through
+        let gate = Arc::new(Semaphore::new(0));
+        let app = Router::new()
+            .route("/", get(|| async { StatusCode::OK }))
+            .route(
+                "/slow",
+                get({
+                    let gate = gate.clone();
+                    move || {
+                        let gate = gate.clone();
+                        async move {
+                            gate.acquire().await.unwrap().forget();
+                            StatusCode::OK
+                        }
+                    }
+                }),
+            )
+            .layer(from_fn_with_state(
+                limiter.clone(),
+                limit_concurrent_requests,
+            ));
+        let call = |path: &'static str, ip: [u8; 4]| {
+            let app = app.clone();
+            async move {
+                let mut request = Request::builder().uri(path).body(Body::empty()).unwrap();
+                request.extensions_mut().insert(ClientIpSource::ConnectInfo);
+                request
+                    .extensions_mut()
+                    .insert(ConnectInfo(SocketAddr::from((ip, 40000))));
+                app.oneshot(request).await.unwrap().status()
+            }
+        };
+        let client = IpAddr::from([203, 0, 113, 7]);
+
+        let held: Vec<_> = (0..2)
+            .map(|_| tokio::spawn(call("/slow", [203, 0, 113, 7])))
+            .collect();
+        while limiter.in_flight(&client) < 2 {
+            tokio::task::yield_now().await;
+        }
+
+        assert_eq!(
+            call("/", [203, 0, 113, 7]).await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        // other addresses have their own allowance
+        assert_eq!(call("/", [198, 51, 100, 1]).await, StatusCode::OK);
+
+        gate.add_permits(2);
+        for request in held {
+            assert_eq!(request.await.unwrap(), StatusCode::OK);
+        }
+        assert_eq!(limiter.in_flight(&client), 0);
+        assert_eq!(call("/", [203, 0, 113, 7]).await, StatusCode::OK);
+    }
+
+    #[test]
+    fn zero_concurrency_limit_never_throttles() {
+        let limiter = IpConcurrencyLimiter::new(0);
+        let ip = IpAddr::from([203, 0, 113, 7]);
+        let permits: Vec<_> = (0..100).map(|_| limiter.try_acquire(ip)).collect();
+        assert!(permits.iter().all(Option::is_some));
+        assert_eq!(limiter.in_flight(&ip), 0);
+    }
+
+    #[test]
+    fn creations_inside_the_cooldown_are_refused() {
+        let clock = Arc::new(TestClock::new());
+        let cooldown = CreationCooldown::new(Duration::seconds(30)).with_clock(clock.clone());
+        let user = UserId::new();
+
+        assert_eq!(cooldown.check(&user), Ok(()));
+        cooldown.record(&user);
+
+        clock.advance(Duration::seconds(10));
+        assert_eq!(
+            cooldown.check(&user),
+            Err(RateLimited {
+                retry_after: Duration::seconds(20)
+            })
+        );
+        // other users aren't held up
+        assert_eq!(cooldown.check(&UserId::new()), Ok(()));
+
+        clock.advance(Duration::seconds(20));
+        assert_eq!(cooldown.check(&user), Ok(()));
+    }
+
+    #[test]
+    fn zero_cooldown_never_throttles() {
+        let cooldown = CreationCooldown::new(Duration::ZERO);
+        let user = UserId::new();
+        cooldown.record(&user);
+        assert_eq!(cooldown.check(&user), Ok(()));
+    }
+
+    #[test]
+    fn addresses_failing_against_many_accounts_are_blocked() {
+        let clock = Arc::new(TestClock::new());
+        let throttle = IpLoginThrottle::new(3, Duration::minutes(10), Duration::minutes(15))
+            .with_clock(clock.clone());
+        let ip = IpAddr::from([203, 0, 113, 7]);
+
+        // guessing at one account over and over is the account lock's business
+        for _ in 0..5 {
+            throttle.record_failure(ip, "alice");
+        }
+        assert_eq!(throttle.check(&ip), Ok(()));
+
+        throttle.record_failure(ip, "bob");
+        assert_eq!(throttle.check(&ip), Ok(()));
+        throttle.record_failure(ip, "carol");
+        assert_eq!(
+            throttle.check(&ip),
+            Err(RateLimited {
+                retry_after: Duration::minutes(15)
+            })
+        );
+        assert_eq!(throttle.check(&IpAddr::from([203, 0, 113, 8])), Ok(()));
+
+        clock.advance(Duration::minutes(15));
+        assert_eq!(throttle.check(&ip), Ok(()));
+    }
+
+    #[test]
+    fn failures_outside_the_window_are_forgotten() {
+        let clock = Arc::new(TestClock::new());
+        let throttle = IpLoginThrottle::new(2, Duration::minutes(10), Duration::minutes(15))
+            .with_clock(clock.clone());
+        let ip = IpAddr::from([203, 0, 113, 7]);
+
+        throttle.record_failure(ip, "alice");
+        clock.advance(Duration::minutes(10));
+        throttle.record_failure(ip, "bob");
+        assert_eq!(throttle.check(&ip), Ok(()));
+
+        // a zero threshold never blocks
+        let disabled = IpLoginThrottle::new(0, Duration::minutes(10), Duration::minutes(15));
+        for name in ["alice", "bob", "carol"] {
+            disabled.record_failure(ip, name);
+        }
+        assert_eq!(disabled.check(&ip), Ok(()));
+    }
+}
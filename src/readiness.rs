@@ -0,0 +1,76 @@
+//! Readiness probe for load balancers and container orchestrators.
+//!
+//! `GET /readyz` answers 503 until startup has finished bootstrapping (database migrations
+//! and the `[admin]` account), then 200, so a rollout only sends traffic to an instance
+//! that can sign its admin in.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use axum::{extract::State, http::StatusCode};
+
+/// Path of the readiness probe
+pub const READYZ_PATH: &str = "/readyz";
+
+/// Whether bootstrap has finished; clones share the flag
+#[derive(Debug, Clone, Default)]
+pub struct Readiness(Arc<AtomicBool>);
+
+impl Readiness {
+    /// A flag that is not ready yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks bootstrap as finished; `/readyz` answers 200 from now on
+    pub fn mark_ready(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// `GET /readyz`, available without authentication
+///
+/// # Example
+/// ```ignore
+/// let probe = Router::new()
+///     .route(READYZ_PATH, get(readyz))
+///     .with_state(readiness.clone());
+/// ```
+pub async fn readyz(State(readiness): State<Readiness>) -> StatusCode {
+    if readiness.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, http::Request, routing::get};
+    use tower::ServiceExt;
+
+    async fn probe(app: &Router) -> StatusCode {
+        let request = Request::get(READYZ_PATH).body(Body::empty()).unwrap();
+        app.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn not_ready_until_bootstrap_finishes() {
+        let readiness = Readiness::new();
+        let app = Router::new()
+            .route(READYZ_PATH, get(readyz))
+            .with_state(readiness.clone());
+
+        assert!(!readiness.is_ready());
+        assert_eq!(probe(&app).await, StatusCode::SERVICE_UNAVAILABLE);
+
+        readiness.mark_ready();
+        assert!(readiness.is_ready());
+        assert_eq!(probe(&app).await, StatusCode::OK);
+    }
+}
@@ -0,0 +1,208 @@
+//! Self-registration under the `[registration]` settings, shared by the `register` server
+//! function and `POST /api/v1/register` so both refuse and create accounts alike.
+
+use thiserror::Error;
+
+use crate::config::Registration;
+use crate::storage::{AuthError, AuthStore};
+use crate::types::{PasswordHash, User, Username};
+
+/// Why a registration was refused, as a forbidden `AppError` unless the store refused it
+#[derive(Debug, Error)]
+pub enum RegistrationError {
+    #[error("Registration is disabled")]
+    Disabled,
+    #[error("An invite code is required to register")]
+    InviteRequired,
+    #[error(transparent)]
+    Store(#[from] AuthError),
+}
+
+/// Refuses a registration the settings don't allow.
+///
+/// Only looks at the request, so callers run it before the password is hashed and it can't
+/// be used to burn CPU.
+pub fn check_allowed(
+    registration: &Registration,
+    invite_code: Option<&str>,
+) -> Result<(), RegistrationError> {
+    if !registration.allow_registration {
+        return Err(RegistrationError::Disabled);
+    }
+    if registration.require_invite && given_invite(invite_code).is_none() {
+        return Err(RegistrationError::InviteRequired);
+    }
+    Ok(())
+}
+
+/// Creates the account per the settings, redeeming `invite_code` when one is given.
+///
+/// Invited users get the role the invite was minted with and skip approval; everyone else
+/// gets `default_role` and `initial_status`, or admin if `first_user_is_admin` applies.
+pub async fn create_user<S: AuthStore + ?Sized>(
+    auth_store: &S,
+    registration: &Registration,
+    username: &Username,
+    pass_hash: PasswordHash,
+    invite_code: Option<&str>,
+) -> Result<User, RegistrationError> {
+    check_allowed(registration, invite_code)?;
+
+    let (role, status) = (registration.default_role, registration.initial_status());
+    let user = match given_invite(invite_code) {
+        // invites are minted by admins, so they skip approval
+        Some(code) => {
+            auth_store
+                .create_user_with_invite(username, pass_hash, code)
+                .await?
+        }
+        None if registration.first_user_is_admin => {
+            auth_store
+                .create_user_or_first_admin(username, pass_hash, role, status)
+                .await?
+        }
+        None => {
+            auth_store
+                .create_user(username, pass_hash, role, status)
+                .await?
+        }
+    };
+    Ok(user)
+}
+
+/// The invite code, unless it was left blank
+fn given_invite(invite_code: Option<&str>) -> Option<&str> {
+    invite_code.map(str::trim).filter(|code| !code.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::mem_authstore::MemoryAuthStore;
+    use crate::types::{Role, SessionIp, UserStatus};
+
+    fn registration(toml: &str) -> Registration {
+        toml::from_str(toml).unwrap()
+    }
+
+    async fn register(
+        store: &MemoryAuthStore,
+        registration: &Registration,
+        username: &str,
+        invite_code: Option<&str>,
+    ) -> Result<User, RegistrationError> {
+        create_user(
+            store,
+            registration,
+            &Username(username.to_string()),
+            PasswordHash::try_from("password").unwrap(),
+            invite_code,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn registration_uses_the_configured_default_role() {
+        let store = MemoryAuthStore::default();
+        let user = register(
+            &store,
+            &registration("allow_registration = true"),
+            "alice",
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(user.role, Role::User);
+        assert!(user.is_active());
+
+        let store = MemoryAuthStore::default();
+        let user = register(
+            &store,
+            &registration("allow_registration = true\ndefault_role = \"Admin\""),
+            "alice",
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(user.role, Role::Admin);
+
+        assert!(toml::from_str::<Registration>("default_role = \"Moderator\"").is_err());
+    }
+
+    #[tokio::test]
+    async fn registration_can_require_approval() {
+        let store = MemoryAuthStore::default();
+        let registration = registration("allow_registration = true\nrequire_approval = true");
+
+        let user = register(&store, &registration, "alice", None)
+            .await
+            .unwrap();
+        assert_eq!(user.status, UserStatus::Disabled);
+        assert!(
+            store
+                .issue_session(&user.id, SessionIp([127, 0, 0, 1].into()), None)
+                .await
+                .is_err()
+        );
+
+        // invited users were already vetted by the admin who minted the invite
+        let invite = store.create_invite(Role::User, 1, None).await.unwrap();
+        let invited = register(&store, &registration, "bob", Some(&invite.code))
+            .await
+            .unwrap();
+        assert!(invited.is_active());
+    }
+
+    #[tokio::test]
+    async fn the_first_registrant_can_become_admin() {
+        let store = MemoryAuthStore::default();
+        let registration = registration(
+            "allow_registration = true\nrequire_approval = true\nfirst_user_is_admin = true",
+        );
+
+        let first = register(&store, &registration, "alice", None)
+            .await
+            .unwrap();
+        assert_eq!(first.role, Role::Admin);
+        assert!(first.is_active());
+
+        let second = register(&store, &registration, "bob", None).await.unwrap();
+        assert_eq!(second.role, Role::User);
+        assert_eq!(second.status, UserStatus::Disabled);
+    }
+
+    #[tokio::test]
+    async fn closed_registration_refuses_everyone() {
+        let store = MemoryAuthStore::default();
+        let invite = store.create_invite(Role::User, 1, None).await.unwrap();
+
+        for invite_code in [None, Some(invite.code.as_str())] {
+            assert!(matches!(
+                register(&store, &Registration::default(), "alice", invite_code).await,
+                Err(RegistrationError::Disabled)
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn invite_only_registration_needs_a_valid_invite() {
+        let store = MemoryAuthStore::default();
+        let registration = registration("allow_registration = true\nrequire_invite = true");
+
+        for blank in [None, Some(""), Some("  ")] {
+            assert!(matches!(
+                register(&store, &registration, "alice", blank).await,
+                Err(RegistrationError::InviteRequired)
+            ));
+        }
+        assert!(matches!(
+            register(&store, &registration, "alice", Some("not-an-invite")).await,
+            Err(RegistrationError::Store(AuthError::InvalidInvite))
+        ));
+
+        let invite = store.create_invite(Role::User, 1, None).await.unwrap();
+        register(&store, &registration, "alice", Some(&invite.code))
+            .await
+            .unwrap();
+    }
+}
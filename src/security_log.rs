@@ -0,0 +1,233 @@
+//! Structured logging of authentication events, enabled with `[logging] security_events`
+//! and kept apart from the general debug output.
+//!
+//! Every event goes to the [TARGET] target with the same fields: `event`, `outcome`, `ip`,
+//! and either `username` or, with `[logging] hash_usernames`, `username_hash`. Successes
+//! are logged at `info` and failures at `warn`, so monitoring can filter on level alone.
+
+use std::net::IpAddr;
+
+use crate::config::Logging;
+use crate::types::LoginError;
+
+/// Target of every security event, e.g. for routing them to their own sink
+pub const TARGET: &str = "bento::security";
+
+/// How an authentication attempt ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Success,
+    /// The request was malformed, e.g. a username that can't exist
+    InvalidInput,
+    UnknownUser,
+    WrongPassword,
+    /// Unknown user or wrong password, where the caller didn't tell them apart
+    InvalidCredentials,
+    AccountDisabled,
+    AccountLocked,
+    /// Refused because the address failed too many logins (`[lockout] ip_max_accounts`)
+    AddressBlocked,
+    /// The password was right, but the user is at the session limit
+    SessionLimit,
+    /// The attempt failed for reasons of our own, like a storage error
+    Error,
+}
+
+impl Outcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Success => "success",
+            Outcome::InvalidInput => "invalid_input",
+            Outcome::UnknownUser => "unknown_user",
+            Outcome::WrongPassword => "wrong_password",
+            Outcome::InvalidCredentials => "invalid_credentials",
+            Outcome::AccountDisabled => "account_disabled",
+            Outcome::AccountLocked => "account_locked",
+            Outcome::AddressBlocked => "address_blocked",
+            Outcome::SessionLimit => "session_limit",
+            Outcome::Error => "error",
+        }
+    }
+}
+
+impl From<&LoginError> for Outcome {
+    fn from(err: &LoginError) -> Self {
+        match err {
+            LoginError::InvalidUsername(_) | LoginError::MissingPassword => Outcome::InvalidInput,
+            LoginError::UnknownUser => Outcome::UnknownUser,
+            LoginError::WrongPassword => Outcome::WrongPassword,
+            LoginError::InvalidCredentials => Outcome::InvalidCredentials,
+            LoginError::AccountDisabled => Outcome::AccountDisabled,
+            LoginError::AccountLocked => Outcome::AccountLocked,
+            LoginError::TooManyAttempts => Outcome::AddressBlocked,
+            LoginError::SessionLimitReached(_) => Outcome::SessionLimit,
+            LoginError::Other(_) => Outcome::Error,
+        }
+    }
+}
+
+/// Log an authentication `event` (e.g. "login") by `username` from `ip`, unless security
+/// events are turned off
+pub fn auth_event(
+    logging: &Logging,
+    event: &'static str,
+    username: &str,
+    ip: IpAddr,
+    outcome: Outcome,
+) {
+    if !logging.security_events {
+        return;
+    }
+    let outcome_name = outcome.as_str();
+
+    match (outcome == Outcome::Success, logging.hash_usernames) {
+        (true, false) => tracing::info!(
+            target: TARGET, event, username, %ip, outcome = outcome_name, "Authentication event"
+        ),
+        (true, true) => tracing::info!(
+            target: TARGET,
+            event,
+            username_hash = %username_hash(username),
+            %ip,
+            outcome = outcome_name,
+            "Authentication event"
+        ),
+        (false, false) => tracing::warn!(
+            target: TARGET, event, username, %ip, outcome = outcome_name, "Authentication event"
+        ),
+        (false, true) => tracing::warn!(
+            target: TARGET,
+            event,
+            username_hash = %username_hash(username),
+            %ip,
+            outcome = outcome_name,
+            "Authentication event"
+        ),
+    }
+}
+
+/// First 16 hex digits of the SHA-256 of the trimmed `username`: enough to follow one
+/// account through the logs without writing its name down. Not a secret, as anyone can
+/// hash a guessed name and compare.
+pub fn username_hash(username: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    Sha256::digest(username.trim().as_bytes())
+        .iter()
+        .take(8)
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+    use tracing::Level;
+    use tracing::field::{Field, Visit};
+    use tracing_subscriber::Layer;
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+
+    type Fields = BTreeMap<String, String>;
+
+    /// Keeps the level and fields of every event it sees
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<(Level, Fields)>>>);
+
+    impl<S: tracing::Subscriber> Layer<S> for Capture {
+        fn on_event(&self, event: &tracing::Event<'_>, _: Context<'_, S>) {
+            struct Visitor<'a>(&'a mut Fields);
+
+            impl Visit for Visitor<'_> {
+                fn record_str(&mut self, field: &Field, value: &str) {
+                    self.0.insert(field.name().to_string(), value.to_string());
+                }
+
+                fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                    self.0
+                        .insert(field.name().to_string(), format!("{value:?}"));
+                }
+            }
+
+            let mut fields = Fields::new();
+            event.record(&mut Visitor(&mut fields));
+            self.0
+                .lock()
+                .unwrap()
+                .push((*event.metadata().level(), fields));
+        }
+    }
+
+    fn captured(log: impl FnOnce()) -> Vec<(Level, Fields)> {
+        let capture = Capture::default();
+        let subscriber = tracing_subscriber::registry().with(capture.clone());
+        tracing::subscriber::with_default(subscriber, log);
+        capture.0.lock().unwrap().clone()
+    }
+
+    fn ip() -> IpAddr {
+        "192.0.2.7".parse().unwrap()
+    }
+
+    #[test]
+    fn events_carry_the_same_fields_whatever_the_outcome() {
+        let logging = Logging {
+            security_events: true,
+            hash_usernames: false,
+        };
+        let events = captured(|| {
+            auth_event(&logging, "login", "alice", ip(), Outcome::Success);
+            auth_event(&logging, "login", "alice", ip(), Outcome::WrongPassword);
+        });
+
+        let expected = |outcome: &str| {
+            Fields::from([
+                ("event".to_string(), "login".to_string()),
+                ("username".to_string(), "alice".to_string()),
+                ("ip".to_string(), "192.0.2.7".to_string()),
+                ("outcome".to_string(), outcome.to_string()),
+                ("message".to_string(), "Authentication event".to_string()),
+            ])
+        };
+        assert_eq!(
+            events,
+            vec![
+                (Level::INFO, expected("success")),
+                (Level::WARN, expected("wrong_password")),
+            ]
+        );
+    }
+
+    #[test]
+    fn usernames_can_be_logged_as_hashes() {
+        let logging = Logging {
+            security_events: true,
+            hash_usernames: true,
+        };
+        let events = captured(|| {
+            auth_event(&logging, "login", "alice", ip(), Outcome::AccountLocked);
+        });
+
+        let (level, fields) = &events[0];
+        assert_eq!(*level, Level::WARN);
+        assert!(!fields.contains_key("username"));
+        assert_eq!(fields["username_hash"], username_hash("alice"));
+        assert_eq!(fields["username_hash"].len(), 16);
+        assert_ne!(username_hash("alice"), username_hash("bob"));
+    }
+
+    #[test]
+    fn nothing_is_logged_unless_enabled() {
+        let events = captured(|| {
+            auth_event(
+                &Logging::default(),
+                "login",
+                "alice",
+                ip(),
+                Outcome::Success,
+            );
+        });
+        assert!(events.is_empty());
+    }
+}
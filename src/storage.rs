@@ -3,27 +3,50 @@
 //! This module defines the `AuthStore` and `ProjectStore` traits that abstract
 //! over different storage backends (memory, redb, etc.).
 
+pub mod backend;
+pub mod blocking;
+pub mod clock;
+pub mod codec;
+pub mod data_dir;
+pub mod db_stats;
+pub mod dyn_store;
 pub mod error;
+pub mod maintenance;
 pub mod mem_authstore;
+pub mod migrations;
+#[cfg(test)]
+pub mod mock_authstore;
+pub mod redb_auditstore;
 pub mod redb_authstore;
 pub mod redb_projectstore;
+pub mod redb_sessionstore;
 
-pub use error::{AuthError, ProjectError};
+pub use error::{AuditError, AuthError, ProjectError};
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use time::OffsetDateTime;
+use tokio::sync::broadcast;
 
 use crate::types::{
-    PasswordHash, Project, ProjectId, ProjectSummary, Role, Session, SessionId, SessionIp, User,
-    UserId, Username,
+    AuditEvent, AuditQuery, InviteCode, PasskeyCredential, PasswordHash, Project, ProjectId,
+    ProjectPage, ProjectSummary, ProjectVisibility, Role, Session, SessionCriteria, SessionId,
+    SessionIp, SessionPage, User, UserAgentHash, UserId, UserStatus, Username,
 };
 
 /// Trait for authentication and user session storage.
 pub trait AuthStore: Send + Sync {
     fn max_sessions_per_user(&self) -> usize;
 
+    /// Create a user; `UserStatus::Disabled` creates an account that can't sign in until
+    /// an admin enables it.
     fn create_user(
         &self,
         username: &Username,
         pass_hash: PasswordHash,
         role: Role,
+        status: UserStatus,
     ) -> impl Future<Output = Result<User, AuthError>> + Send;
 
     fn create_standard_user(
@@ -31,7 +54,7 @@ pub trait AuthStore: Send + Sync {
         username: &Username,
         pass_hash: PasswordHash,
     ) -> impl Future<Output = Result<User, AuthError>> + Send {
-        self.create_user(username, pass_hash, Role::User)
+        self.create_user(username, pass_hash, Role::User, UserStatus::Active)
     }
 
     fn create_admin(
@@ -39,9 +62,43 @@ pub trait AuthStore: Send + Sync {
         username: &Username,
         pass_hash: PasswordHash,
     ) -> impl Future<Output = Result<User, AuthError>> + Send {
-        self.create_user(username, pass_hash, Role::Admin)
+        self.create_user(username, pass_hash, Role::Admin, UserStatus::Active)
     }
 
+    /// Create the first user as an active admin, or any later one like `create_user`.
+    ///
+    /// Whether the store is empty is checked atomically with the insert, so of two
+    /// simultaneous first registrations only one becomes admin.
+    fn create_user_or_first_admin(
+        &self,
+        username: &Username,
+        pass_hash: PasswordHash,
+        role: Role,
+        status: UserStatus,
+    ) -> impl Future<Output = Result<User, AuthError>> + Send;
+
+    /// Create a user by redeeming an invite code, which grants the user its role.
+    ///
+    /// The code is validated and its use counter incremented atomically with the
+    /// user's creation.
+    fn create_user_with_invite(
+        &self,
+        username: &Username,
+        pass_hash: PasswordHash,
+        code: &str,
+    ) -> impl Future<Output = Result<User, AuthError>> + Send;
+
+    fn create_invite(
+        &self,
+        role: Role,
+        max_uses: u32,
+        expires_at: Option<OffsetDateTime>,
+    ) -> impl Future<Output = Result<InviteCode, AuthError>> + Send;
+
+    fn list_invites(&self) -> impl Future<Output = Result<Vec<InviteCode>, AuthError>> + Send;
+
+    fn revoke_invite(&self, code: &str) -> impl Future<Output = Result<(), AuthError>> + Send;
+
     fn get_user_by_id(&self, id: &UserId) -> impl Future<Output = Result<User, AuthError>> + Send;
 
     fn get_user_by_username(
@@ -49,18 +106,107 @@ pub trait AuthStore: Send + Sync {
         username: &Username,
     ) -> impl Future<Output = Result<User, AuthError>> + Send;
 
+    /// All users, ordered by username
+    fn list_users(&self) -> impl Future<Output = Result<Vec<User>, AuthError>> + Send;
+
+    /// Enables or disables a user, returning the updated user.
+    ///
+    /// Disabling also revokes all of the user's sessions.
+    fn set_status(
+        &self,
+        id: &UserId,
+        status: UserStatus,
+    ) -> impl Future<Output = Result<User, AuthError>> + Send;
+
+    /// Changes a user's username, returning the updated user.
+    ///
+    /// Fails with `AuthError::UserExists` if another user already has the new name; the old
+    /// name is free for others to take afterwards.
+    fn rename_user(
+        &self,
+        id: &UserId,
+        new: &Username,
+    ) -> impl Future<Output = Result<User, AuthError>> + Send;
+
+    /// Replaces a user's password hash. The replaced hash is remembered for
+    /// `check_password_reuse` if the store keeps a password history; entries beyond its
+    /// length are pruned.
     fn set_password_hash(
         &self,
         id: &UserId,
         new_hash: PasswordHash,
     ) -> impl Future<Output = Result<PasswordHash, AuthError>> + Send;
 
+    /// Fails with `AuthError::PasswordReused` if `password` is the user's current password
+    /// or one of the previous ones in their history (`[security] password_history`).
+    ///
+    /// Always succeeds when the store keeps no history.
+    fn check_password_reuse(
+        &self,
+        id: &UserId,
+        password: &str,
+    ) -> impl Future<Output = Result<(), AuthError>> + Send;
+
+    /// Stamps the user's last login with the current time and `ip`.
+    ///
+    /// Returns the user as it was before, i.e. still carrying the previous login.
+    fn record_login(
+        &self,
+        id: &UserId,
+        ip: SessionIp,
+    ) -> impl Future<Output = Result<User, AuthError>> + Send;
+
+    /// Counts a wrong password for the user, returning the updated user.
+    ///
+    /// The `max_failed_logins`th wrong password in a row locks the account for `lock_for`
+    /// and starts the count over. `record_login` also resets the count.
+    fn record_failed_login(
+        &self,
+        id: &UserId,
+        max_failed_logins: u32,
+        lock_for: time::Duration,
+    ) -> impl Future<Output = Result<User, AuthError>> + Send;
+
+    /// Unlocks a user locked by failed logins and resets their count, returning the updated
+    /// user.
+    fn clear_lockout(&self, id: &UserId) -> impl Future<Output = Result<User, AuthError>> + Send;
+
     fn delete_user(&self, id: &UserId) -> impl Future<Output = Result<(), AuthError>> + Send;
 
+    /// Dry run of `delete_user`: returns the sessions that would be removed alongside
+    /// the user, without mutating anything.
+    fn preview_delete_user(
+        &self,
+        id: &UserId,
+    ) -> impl Future<Output = Result<Vec<SessionId>, AuthError>> + Send;
+
+    /// Insert or replace a passkey credential (e.g., after its signature counter changed)
+    fn save_passkey(
+        &self,
+        credential: PasskeyCredential,
+    ) -> impl Future<Output = Result<(), AuthError>> + Send;
+
+    fn list_passkeys(
+        &self,
+        user_id: &UserId,
+    ) -> impl Future<Output = Result<Vec<PasskeyCredential>, AuthError>> + Send;
+
+    /// Start a session for a user, remembering the browser's `user_agent` if known
     fn issue_session(
         &self,
         id: &UserId,
         ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
+    ) -> impl Future<Output = Result<Session, AuthError>> + Send;
+
+    /// Start a session for `target` on behalf of `admin`, see `Session::impersonator`.
+    /// Permission checks are up to the caller.
+    fn issue_impersonation_session(
+        &self,
+        admin: &UserId,
+        target: &UserId,
+        ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
     ) -> impl Future<Output = Result<Session, AuthError>> + Send;
 
     fn fetch_session(
@@ -73,21 +219,195 @@ pub trait AuthStore: Send + Sync {
         token: &SessionId,
     ) -> impl Future<Output = Result<Session, AuthError>> + Send;
 
+    /// Records that the owner of an unexpired session just entered their password again,
+    /// see `Session::authenticated_at`
+    fn mark_reauthenticated(
+        &self,
+        token: &SessionId,
+    ) -> impl Future<Output = Result<Session, AuthError>> + Send;
+
+    /// Sets `key` in the data of an unexpired session, or removes it when `value` is `None`.
+    /// Fails with `AuthError::SessionDataTooLarge` when the data would outgrow the
+    /// `Session::MAX_DATA_ENTRIES` and `Session::MAX_DATA_BYTES` limits.
+    fn set_session_data(
+        &self,
+        token: &SessionId,
+        key: &str,
+        value: Option<String>,
+    ) -> impl Future<Output = Result<Session, AuthError>> + Send;
+
+    /// The value of `key` in an unexpired session's data
+    fn get_session_data(
+        &self,
+        token: &SessionId,
+        key: &str,
+    ) -> impl Future<Output = Result<Option<String>, AuthError>> + Send;
+
     fn revoke_session(
         &self,
         token: &SessionId,
     ) -> impl Future<Output = Result<(), AuthError>> + Send;
+
+    /// Revokes every session of a user, returning how many were revoked
+    fn revoke_all_sessions(
+        &self,
+        id: &UserId,
+    ) -> impl Future<Output = Result<usize, AuthError>> + Send;
+
+    /// Removes every expired session, returning how many were removed
+    fn purge_expired_sessions(&self) -> impl Future<Output = Result<usize, AuthError>> + Send;
+
+    /// Number of unexpired sessions of a user, without loading them
+    fn count_user_sessions(
+        &self,
+        id: &UserId,
+    ) -> impl Future<Output = Result<usize, AuthError>> + Send;
+
+    /// Unexpired sessions of a user, most recently seen first
+    fn list_user_sessions(
+        &self,
+        id: &UserId,
+    ) -> impl Future<Output = Result<Vec<Session>, AuthError>> + Send;
+
+    /// Up to `limit` unexpired sessions of a user, continuing after the session `after`.
+    ///
+    /// Pages follow the order of the session tokens, which is stable but not chronological
+    /// (tokens are random), so sessions issued or revoked between two pages never make
+    /// another session show up twice or go missing.
+    fn list_user_sessions_page(
+        &self,
+        id: &UserId,
+        after: Option<&SessionId>,
+        limit: usize,
+    ) -> impl Future<Output = Result<SessionPage, AuthError>> + Send;
+
+    /// Number of unexpired sessions per client IP they were issued to
+    fn active_sessions_by_ip(
+        &self,
+    ) -> impl Future<Output = Result<BTreeMap<IpAddr, usize>, AuthError>> + Send;
+
+    /// Revokes every session, of any user, matching `criteria`, returning how many were
+    /// revoked
+    fn revoke_sessions_where(
+        &self,
+        criteria: &SessionCriteria,
+    ) -> impl Future<Output = Result<usize, AuthError>> + Send;
+}
+
+/// Trait for keeping sessions apart from the users they belong to.
+///
+/// It knows nothing about users: checks like "is this account disabled" stay with the
+/// `AuthStore` that delegates its session methods here, so the sessions can live in a
+/// database of their own (`[session] store`).
+pub trait SessionStore: Send + Sync {
+    /// Store a new session, unless its user already has `max_per_user` unexpired ones.
+    ///
+    /// The user's expired sessions are removed along the way.
+    fn insert_session(
+        &self,
+        session: Session,
+        max_per_user: usize,
+    ) -> impl Future<Output = Result<Session, AuthError>> + Send;
+
+    /// The unexpired session behind `token`; an expired one is removed and reported as
+    /// `InvalidSession`, just like a missing one
+    fn get_session(
+        &self,
+        token: &SessionId,
+    ) -> impl Future<Output = Result<Session, AuthError>> + Send;
+
+    /// Moves an unexpired session's expiry to `expires_at` and marks it as seen now
+    fn touch_session(
+        &self,
+        token: &SessionId,
+        expires_at: OffsetDateTime,
+    ) -> impl Future<Output = Result<Session, AuthError>> + Send;
+
+    /// Sets the `last_auth_at` of an unexpired session
+    fn stamp_auth(
+        &self,
+        token: &SessionId,
+        at: OffsetDateTime,
+    ) -> impl Future<Output = Result<Session, AuthError>> + Send;
+
+    /// Applies `Session::set_data` to a session unexpired at `now`
+    fn set_data(
+        &self,
+        token: &SessionId,
+        key: &str,
+        value: Option<String>,
+        now: OffsetDateTime,
+    ) -> impl Future<Output = Result<Session, AuthError>> + Send;
+
+    fn remove_session(
+        &self,
+        token: &SessionId,
+    ) -> impl Future<Output = Result<(), AuthError>> + Send;
+
+    /// Removes every session of a user, returning their ids
+    fn remove_user_sessions(
+        &self,
+        user_id: &UserId,
+    ) -> impl Future<Output = Result<Vec<SessionId>, AuthError>> + Send;
+
+    /// Ids of every session of a user, expired or not
+    fn user_session_ids(
+        &self,
+        user_id: &UserId,
+    ) -> impl Future<Output = Result<Vec<SessionId>, AuthError>> + Send;
+
+    /// Removes every expired session, returning how many were removed
+    fn purge_expired(&self) -> impl Future<Output = Result<usize, AuthError>> + Send;
+
+    /// Removes every session matching `criteria`, returning how many were removed
+    fn remove_where(
+        &self,
+        criteria: &SessionCriteria,
+    ) -> impl Future<Output = Result<usize, AuthError>> + Send;
+
+    /// Number of unexpired sessions of a user
+    fn count_user_sessions(
+        &self,
+        user_id: &UserId,
+    ) -> impl Future<Output = Result<usize, AuthError>> + Send;
+
+    /// Unexpired sessions of a user, most recently seen first
+    fn user_sessions(
+        &self,
+        user_id: &UserId,
+    ) -> impl Future<Output = Result<Vec<Session>, AuthError>> + Send;
+
+    /// See `AuthStore::list_user_sessions_page`
+    fn user_sessions_page(
+        &self,
+        user_id: &UserId,
+        after: Option<&SessionId>,
+        limit: usize,
+    ) -> impl Future<Output = Result<SessionPage, AuthError>> + Send;
+
+    /// Number of unexpired sessions per client IP they were issued to
+    fn active_by_ip(
+        &self,
+    ) -> impl Future<Output = Result<BTreeMap<IpAddr, usize>, AuthError>> + Send;
+
+    /// Removes every session, signing everyone out; returns how many there were
+    fn clear(&self) -> impl Future<Output = Result<usize, AuthError>> + Send;
 }
 
 /// Trait for project storage operations.
 pub trait ProjectStore: Send + Sync {
-    /// Create a new project for a user
+    /// Create a new project for a user.
+    ///
+    /// Repeating a call with the same `idempotency_key` (per owner) within the store's
+    /// idempotency window returns the project the first call created instead of creating
+    /// another. The flag is `false` for such replays.
     fn create_project(
         &self,
         owner_id: &UserId,
         name: String,
         description: Option<String>,
-    ) -> impl Future<Output = Result<Project, ProjectError>> + Send;
+        idempotency_key: Option<String>,
+    ) -> impl Future<Output = Result<(Project, bool), ProjectError>> + Send;
 
     /// Get a project by ID
     fn get_project(
@@ -95,23 +415,96 @@ pub trait ProjectStore: Send + Sync {
         project_id: &ProjectId,
     ) -> impl Future<Output = Result<Project, ProjectError>> + Send;
 
+    /// Get a project by its slug
+    fn get_by_slug(&self, slug: &str)
+    -> impl Future<Output = Result<Project, ProjectError>> + Send;
+
+    /// Get several projects by ID in one transaction, in the order requested.
+    ///
+    /// IDs without a project are skipped; compare the result against `ids` to find them.
+    fn get_projects(
+        &self,
+        project_ids: &[ProjectId],
+    ) -> impl Future<Output = Result<Vec<Project>, ProjectError>> + Send;
+
     /// Get all projects owned by a user
     fn get_user_projects(
         &self,
         owner_id: &UserId,
     ) -> impl Future<Output = Result<Vec<ProjectSummary>, ProjectError>> + Send;
 
-    /// Update a project's name and/or description
+    /// Up to `limit` of a user's projects, newest first, continuing after the project `after`.
+    ///
+    /// Unlike an offset, the cursor doesn't move when projects are created or deleted between
+    /// two pages, so none of the others is listed twice or skipped.
+    fn get_user_projects_page(
+        &self,
+        owner_id: &UserId,
+        after: Option<ProjectId>,
+        limit: usize,
+    ) -> impl Future<Output = Result<ProjectPage, ProjectError>> + Send;
+
+    /// Up to `limit` of a user's projects whose name contains `query`, ignoring case,
+    /// newest first
+    fn search_projects(
+        &self,
+        owner_id: &UserId,
+        query: &str,
+        limit: usize,
+    ) -> impl Future<Output = Result<Vec<ProjectSummary>, ProjectError>> + Send;
+
+    /// Update a project's name, description, rate limit and/or visibility
     fn update_project(
         &self,
         project_id: &ProjectId,
         name: Option<String>,
         description: Option<Option<String>>,
+        rate_limit_rpm: Option<Option<u32>>,
+        visibility: Option<ProjectVisibility>,
     ) -> impl Future<Output = Result<Project, ProjectError>> + Send;
 
-    /// Delete a project
+    /// Turn delete protection of a project on or off, see `Project::delete_protected`
+    fn set_delete_protection(
+        &self,
+        project_id: &ProjectId,
+        protected: bool,
+    ) -> impl Future<Output = Result<Project, ProjectError>> + Send;
+
+    /// Delete a project. Fails with `ProjectError::DeleteProtected` while it is protected.
     fn delete_project(
         &self,
         project_id: &ProjectId,
     ) -> impl Future<Output = Result<(), ProjectError>> + Send;
+
+    /// Dry run of `delete_project`: returns the project that would be removed,
+    /// without mutating anything.
+    fn preview_delete_project(
+        &self,
+        project_id: &ProjectId,
+    ) -> impl Future<Output = Result<Project, ProjectError>> + Send;
+
+    /// Copy a project under a fresh id, owned by `new_owner`.
+    ///
+    /// The copy is named "<name> (copy)", numbered if the owner already has a project by
+    /// that name (e.g. "<name> (copy 2)").
+    fn clone_project(
+        &self,
+        source: &ProjectId,
+        new_owner: &UserId,
+    ) -> impl Future<Output = Result<Project, ProjectError>> + Send;
+}
+
+/// Trait for the append-only audit log.
+pub trait AuditStore: Send + Sync {
+    /// Append an event to the log
+    fn record(&self, event: AuditEvent) -> impl Future<Output = Result<(), AuditError>> + Send;
+
+    /// Return the events matching `query`, newest first
+    fn query(
+        &self,
+        query: AuditQuery,
+    ) -> impl Future<Output = Result<Vec<AuditEvent>, AuditError>> + Send;
+
+    /// Receive events as they are recorded, starting now
+    fn subscribe(&self) -> broadcast::Receiver<AuditEvent>;
 }
@@ -0,0 +1,240 @@
+//! Choosing between the on-disk databases and throwaway in-memory ones.
+//!
+//! Both options use the redb stores; the in-memory one swaps the file for redb's
+//! `InMemoryBackend`, so `AppState` keeps naming the same concrete store types and nothing
+//! downstream has to know which backend was picked.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::warn;
+
+use super::data_dir::{DataDirError, prepare_data_dir};
+use super::redb_auditstore::RedbAuditStore;
+use super::redb_authstore::RedbAuthStore;
+use super::redb_projectstore::RedbProjectStore;
+use super::redb_sessionstore::RedbSessionStore;
+use super::{AuditError, AuthError, ProjectError};
+
+/// Where the stores keep their data (`[storage] backend`)
+///
+/// - Redb:
+///   Database files in the data directory; failing to open them is fatal
+/// - Memory:
+///   In memory only, everything is lost on restart
+/// - Auto:
+///   The database files, falling back to memory if they can't be opened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    Redb,
+    Memory,
+    Auto,
+}
+
+/// Where the auth store keeps sessions (`[session] store`)
+///
+/// - Same:
+///   In the auth database, next to the users
+/// - Separate:
+///   In `sessions.db` of their own, e.g. to move them onto faster storage
+/// - Memory:
+///   In memory only, so a restart signs everyone out but keeps every user
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStorage {
+    #[default]
+    Same,
+    Separate,
+    Memory,
+}
+
+#[derive(Debug, Error)]
+pub enum OpenStoresError {
+    #[error(transparent)]
+    DataDir(#[from] DataDirError),
+    #[error("Failed to open the auth database: {0}")]
+    Auth(#[from] AuthError),
+    #[error("Failed to open the project database: {0}")]
+    Project(#[from] ProjectError),
+    #[error("Failed to open the audit database: {0}")]
+    Audit(#[from] AuditError),
+}
+
+/// The three stores, opened together so they never mix persistent and in-memory data
+pub struct Stores {
+    pub auth: RedbAuthStore,
+    pub projects: RedbProjectStore,
+    pub audit: RedbAuditStore,
+    /// Whether the data only lives in memory
+    pub in_memory: bool,
+}
+
+impl Stores {
+    /// Opens (or creates) `auth.db`, `projects.db` and `audit.db` in `data_dir`, plus
+    /// `sessions.db` for [SessionStorage::Separate]
+    pub fn open(
+        data_dir: &Path,
+        max_sessions_per_user: usize,
+        sessions: SessionStorage,
+    ) -> Result<Self, OpenStoresError> {
+        prepare_data_dir(data_dir)?;
+        let auth = RedbAuthStore::new(data_dir.join("auth.db"), max_sessions_per_user)?;
+        let auth = match sessions {
+            SessionStorage::Same => auth,
+            SessionStorage::Separate => {
+                auth.with_session_store(RedbSessionStore::new(data_dir.join("sessions.db"))?)
+            }
+            SessionStorage::Memory => auth.with_session_store(RedbSessionStore::in_memory()?),
+        };
+        Ok(Self {
+            auth,
+            projects: RedbProjectStore::new(data_dir.join("projects.db"))?,
+            audit: RedbAuditStore::new(data_dir.join("audit.db"))?,
+            in_memory: false,
+        })
+    }
+
+    pub fn in_memory(max_sessions_per_user: usize) -> Result<Self, OpenStoresError> {
+        Ok(Self {
+            auth: RedbAuthStore::in_memory(max_sessions_per_user)?,
+            projects: RedbProjectStore::in_memory()?,
+            audit: RedbAuditStore::in_memory()?,
+            in_memory: true,
+        })
+    }
+
+    /// Opens the stores on `backend`, see [StorageBackend]; `sessions` only matters when
+    /// the database files are used
+    pub fn open_backend(
+        backend: StorageBackend,
+        data_dir: &Path,
+        max_sessions_per_user: usize,
+        sessions: SessionStorage,
+    ) -> Result<Self, OpenStoresError> {
+        let open = || Self::open(data_dir, max_sessions_per_user, sessions);
+        match backend {
+            StorageBackend::Redb => open(),
+            StorageBackend::Memory => Self::in_memory(max_sessions_per_user),
+            StorageBackend::Auto => open().or_else(|e| {
+                warn!(
+                    "{e}. Falling back to IN-MEMORY storage: users, sessions and projects \
+                     will be LOST when Bento stops"
+                );
+                Self::in_memory(max_sessions_per_user)
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// A path the data directory can't be created at, because a file is in the way
+    fn blocked_data_dir() -> (PathBuf, PathBuf) {
+        let file = std::env::temp_dir().join(format!("bento-blocked-{}", uuid::Uuid::now_v7()));
+        fs::write(&file, b"").unwrap();
+        (file.join("data"), file)
+    }
+
+    #[test]
+    fn auto_falls_back_to_memory_when_the_files_cant_be_opened() {
+        let (data_dir, file) = blocked_data_dir();
+
+        assert!(matches!(
+            Stores::open_backend(StorageBackend::Redb, &data_dir, 5, SessionStorage::Same),
+            Err(OpenStoresError::DataDir(_))
+        ));
+        let stores =
+            Stores::open_backend(StorageBackend::Auto, &data_dir, 5, SessionStorage::Same).unwrap();
+        assert!(stores.in_memory);
+
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn auto_uses_the_files_when_they_open() {
+        let data_dir = std::env::temp_dir().join(format!("bento-auto-{}", uuid::Uuid::now_v7()));
+
+        let stores =
+            Stores::open_backend(StorageBackend::Auto, &data_dir, 5, SessionStorage::Same).unwrap();
+        assert!(!stores.in_memory);
+        assert!(data_dir.join("auth.db").is_file());
+
+        drop(stores);
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn memory_backend_never_touches_the_data_directory() {
+        let data_dir = std::env::temp_dir().join(format!("bento-mem-{}", uuid::Uuid::now_v7()));
+
+        let stores =
+            Stores::open_backend(StorageBackend::Memory, &data_dir, 5, SessionStorage::Same)
+                .unwrap();
+        assert!(stores.in_memory);
+        assert!(!data_dir.exists());
+    }
+
+    #[tokio::test]
+    async fn sessions_in_memory_are_gone_after_a_restart_but_users_stay() {
+        use crate::storage::AuthStore;
+        use crate::types::{PasswordHash, SessionIp, Username};
+        use std::net::IpAddr;
+
+        let data_dir = std::env::temp_dir().join(format!("bento-sess-{}", uuid::Uuid::now_v7()));
+        let username = Username("alice".to_string());
+        let open = || {
+            Stores::open_backend(StorageBackend::Redb, &data_dir, 5, SessionStorage::Memory)
+                .unwrap()
+        };
+
+        let stores = open();
+        let user = stores
+            .auth
+            .create_standard_user(&username, PasswordHash::try_from("password").unwrap())
+            .await
+            .unwrap();
+        let session = stores
+            .auth
+            .issue_session(&user.id, SessionIp(IpAddr::from([127, 0, 0, 1])), None)
+            .await
+            .unwrap();
+        drop(stores);
+
+        let stores = open();
+        assert_eq!(
+            stores
+                .auth
+                .get_user_by_username(&username)
+                .await
+                .unwrap()
+                .id,
+            user.id
+        );
+        assert!(stores.auth.fetch_session(&session.id).await.is_err());
+        assert!(!data_dir.join("sessions.db").exists());
+
+        drop(stores);
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn separate_sessions_get_their_own_file() {
+        let data_dir = std::env::temp_dir().join(format!("bento-sep-{}", uuid::Uuid::now_v7()));
+
+        let stores =
+            Stores::open_backend(StorageBackend::Redb, &data_dir, 5, SessionStorage::Separate)
+                .unwrap();
+        assert!(data_dir.join("sessions.db").is_file());
+        assert!(stores.auth.session_stats().await.unwrap().is_some());
+
+        drop(stores);
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+}
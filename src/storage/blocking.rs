@@ -0,0 +1,160 @@
+//! Bounds how many store operations occupy the blocking thread pool at once.
+//!
+//! Every redb transaction runs in `spawn_blocking`. Under load that can take up the whole
+//! blocking pool, so the stores first take a permit from a shared [BlockingLimiter]
+//! (`[storage] max_blocking`); operations past the limit wait for a permit instead.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+#[derive(Debug)]
+pub struct BlockingLimiter {
+    permits: Arc<Semaphore>,
+    max_concurrent: usize,
+    waiting: AtomicUsize,
+}
+
+impl BlockingLimiter {
+    /// Allows `max_concurrent` operations at once; 0 means no limit
+    pub fn new(max_concurrent: usize) -> Self {
+        let max_concurrent = match max_concurrent {
+            0 => Semaphore::MAX_PERMITS,
+            n => n.min(Semaphore::MAX_PERMITS),
+        };
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+            waiting: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn unbounded() -> Self {
+        Self::new(0)
+    }
+
+    /// Waits for a free slot; the operation may run until the permit is dropped
+    pub async fn acquire(&self) -> OwnedSemaphorePermit {
+        if let Ok(permit) = self.permits.clone().try_acquire_owned() {
+            return permit;
+        }
+
+        // counted until a permit is handed out, or the caller gives up waiting
+        let _waiting = WaitingGuard::new(&self.waiting);
+        self.permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the blocking semaphore is never closed")
+    }
+
+    /// Operations waiting for a permit
+    pub fn queue_depth(&self) -> usize {
+        self.waiting.load(Ordering::Relaxed)
+    }
+
+    /// Operations holding a permit
+    pub fn in_flight(&self) -> usize {
+        self.max_concurrent - self.permits.available_permits()
+    }
+}
+
+impl Default for BlockingLimiter {
+    fn default() -> Self {
+        Self::unbounded()
+    }
+}
+
+struct WaitingGuard<'a>(&'a AtomicUsize);
+
+impl<'a> WaitingGuard<'a> {
+    fn new(waiting: &'a AtomicUsize) -> Self {
+        waiting.fetch_add(1, Ordering::Relaxed);
+        Self(waiting)
+    }
+}
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::ProjectStore;
+    use crate::storage::redb_projectstore::RedbProjectStore;
+    use crate::types::UserId;
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn operations_past_the_limit_wait_their_turn() {
+        let limiter = Arc::new(BlockingLimiter::new(2));
+        let running = Arc::new(AtomicUsize::new(0));
+        let most_running = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                let (limiter, running, most_running) =
+                    (limiter.clone(), running.clone(), most_running.clone());
+                tokio::spawn(async move {
+                    let permit = limiter.acquire().await;
+                    tokio::task::spawn_blocking(move || {
+                        let _permit = permit;
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        most_running.fetch_max(now, Ordering::SeqCst);
+                        std::thread::sleep(Duration::from_millis(20));
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        i
+                    })
+                    .await
+                    .unwrap()
+                })
+            })
+            .collect();
+
+        // give the tasks time to pile up behind the first two
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(limiter.in_flight(), 2);
+        assert!(limiter.queue_depth() > 0);
+
+        let mut finished = Vec::new();
+        for task in tasks {
+            finished.push(task.await.unwrap());
+        }
+        finished.sort_unstable();
+        assert_eq!(finished, (0..8).collect::<Vec<_>>());
+        assert_eq!(most_running.load(Ordering::SeqCst), 2);
+        assert_eq!((limiter.in_flight(), limiter.queue_depth()), (0, 0));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn a_store_limited_to_one_operation_completes_concurrent_calls() {
+        let limiter = Arc::new(BlockingLimiter::new(1));
+        let store = Arc::new(
+            RedbProjectStore::in_memory()
+                .unwrap()
+                .with_blocking_limiter(limiter.clone()),
+        );
+        let owner = UserId::new();
+
+        let tasks: Vec<_> = (0..16)
+            .map(|i| {
+                let store = store.clone();
+                tokio::spawn(async move {
+                    store
+                        .create_project(&owner, format!("Project {i}"), None, None)
+                        .await
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        assert_eq!(store.get_user_projects(&owner).await.unwrap().len(), 16);
+        assert_eq!(limiter.in_flight(), 0);
+    }
+}
@@ -0,0 +1,61 @@
+//! Time source abstraction for the stores.
+//!
+//! Stores read the current time through a [Clock] so that expiry logic can be tested
+//! deterministically with a [TestClock] instead of sleeping.
+
+use std::sync::Mutex;
+
+use time::{Duration, OffsetDateTime};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> OffsetDateTime;
+}
+
+/// The real wall clock (UTC)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+/// A manually controlled clock for tests
+#[derive(Debug)]
+pub struct TestClock {
+    now: Mutex<OffsetDateTime>,
+}
+
+impl TestClock {
+    /// Creates a clock frozen at the current wall-clock time
+    pub fn new() -> Self {
+        Self::at(OffsetDateTime::now_utc())
+    }
+
+    pub fn at(now: OffsetDateTime) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+
+    pub fn set(&self, now: OffsetDateTime) {
+        *self.now.lock().unwrap() = now;
+    }
+}
+
+impl Default for TestClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> OffsetDateTime {
+        *self.now.lock().unwrap()
+    }
+}
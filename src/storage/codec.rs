@@ -0,0 +1,202 @@
+//! Value codec shared by the redb-backed stores.
+//!
+//! Records are serialized with bincode. When `[storage] encrypt_at_rest` is enabled, the
+//! serialized bytes are additionally sealed with ChaCha20-Poly1305 using a fresh random
+//! nonce per record, stored as `nonce || ciphertext`.
+//!
+//! NOTE: the codec does not detect plaintext records. Enabling encryption on an existing
+//! plaintext database (or disabling it on an encrypted one) requires a migration pass that
+//! re-encodes every value, otherwise reads will fail with a decryption error.
+
+use std::sync::Arc;
+
+use chacha20poly1305::{
+    ChaCha20Poly1305, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Length of the per-record nonce prepended to each encrypted value
+const NONCE_LEN: usize = 12;
+
+/// Domain separation tag used when deriving the storage key from the cookie key
+const KEY_DERIVATION_CONTEXT: &[u8] = b"bento/storage/encrypt-at-rest/v1";
+
+#[derive(Debug, Error)]
+pub enum CodecError {
+    #[error("Serialization error: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+    #[error("Deserialization error: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+    #[error("Failed to encrypt record")]
+    Encrypt,
+    #[error("Failed to decrypt record (wrong key, tampered or plaintext data)")]
+    Decrypt,
+    #[error("Invalid encryption key: {0}")]
+    InvalidKey(String),
+}
+
+/// AEAD wrapper used to seal serialized records before they are written to disk.
+pub struct EncryptedCodec {
+    cipher: ChaCha20Poly1305,
+}
+
+impl EncryptedCodec {
+    /// Creates a codec from a raw 32-byte key
+    pub fn new(key: &[u8]) -> Result<Self, CodecError> {
+        let cipher = ChaCha20Poly1305::new_from_slice(key)
+            .map_err(|_| CodecError::InvalidKey(format!("expected 32 bytes, got {}", key.len())))?;
+        Ok(Self { cipher })
+    }
+
+    /// Derives a storage key from other key material (e.g., the cookie key's master bytes),
+    /// so the same bytes are never used directly for two purposes.
+    pub fn derive_from(material: &[u8]) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(KEY_DERIVATION_CONTEXT);
+        hasher.update(material);
+        let key = hasher.finalize();
+        Self {
+            cipher: ChaCha20Poly1305::new(&key),
+        }
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| CodecError::Encrypt)?;
+
+        let mut out = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    pub fn decrypt(&self, bytes: &[u8]) -> Result<Vec<u8>, CodecError> {
+        if bytes.len() < NONCE_LEN {
+            return Err(CodecError::Decrypt);
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| CodecError::Decrypt)
+    }
+}
+
+/// Serialization helper held by each store; cheap to clone into blocking tasks.
+#[derive(Clone, Default)]
+pub struct ValueCodec {
+    encryption: Option<Arc<EncryptedCodec>>,
+}
+
+impl ValueCodec {
+    /// Plain bincode encoding, no encryption
+    pub fn plaintext() -> Self {
+        Self { encryption: None }
+    }
+
+    /// Bincode encoding sealed with the given [EncryptedCodec]
+    pub fn encrypted(codec: EncryptedCodec) -> Self {
+        Self {
+            encryption: Some(Arc::new(codec)),
+        }
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption.is_some()
+    }
+
+    pub fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        let bytes = bincode::serde::encode_to_vec(value, bincode::config::standard())?;
+        match &self.encryption {
+            Some(codec) => codec.encrypt(&bytes),
+            None => Ok(bytes),
+        }
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CodecError> {
+        let (result, _) = match &self.encryption {
+            Some(codec) => {
+                let plaintext = codec.decrypt(bytes)?;
+                bincode::serde::decode_from_slice(&plaintext, bincode::config::standard())?
+            }
+            None => bincode::serde::decode_from_slice(bytes, bincode::config::standard())?,
+        };
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Record {
+        name: String,
+        count: u32,
+    }
+
+    fn sample() -> Record {
+        Record {
+            name: "bento".to_string(),
+            count: 42,
+        }
+    }
+
+    #[test]
+    fn plaintext_round_trip() {
+        let codec = ValueCodec::plaintext();
+        let bytes = codec.serialize(&sample()).unwrap();
+        assert_eq!(codec.deserialize::<Record>(&bytes).unwrap(), sample());
+    }
+
+    #[test]
+    fn encrypted_round_trip() {
+        let codec = ValueCodec::encrypted(EncryptedCodec::new(&[7u8; 32]).unwrap());
+        let bytes = codec.serialize(&sample()).unwrap();
+
+        // ciphertext must not contain the plaintext fields
+        assert!(!bytes.windows(5).any(|w| w == b"bento"));
+        assert_eq!(codec.deserialize::<Record>(&bytes).unwrap(), sample());
+    }
+
+    #[test]
+    fn nonces_are_unique_per_record() {
+        let codec = ValueCodec::encrypted(EncryptedCodec::derive_from(&[1u8; 64]));
+        let first = codec.serialize(&sample()).unwrap();
+        let second = codec.serialize(&sample()).unwrap();
+        assert_ne!(first[..NONCE_LEN], second[..NONCE_LEN]);
+    }
+
+    #[test]
+    fn wrong_key_and_tampering_are_rejected() {
+        let codec = ValueCodec::encrypted(EncryptedCodec::new(&[7u8; 32]).unwrap());
+        let other = ValueCodec::encrypted(EncryptedCodec::new(&[8u8; 32]).unwrap());
+        let mut bytes = codec.serialize(&sample()).unwrap();
+
+        assert!(matches!(
+            other.deserialize::<Record>(&bytes),
+            Err(CodecError::Decrypt)
+        ));
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        assert!(matches!(
+            codec.deserialize::<Record>(&bytes),
+            Err(CodecError::Decrypt)
+        ));
+    }
+
+    #[test]
+    fn rejects_short_keys() {
+        assert!(matches!(
+            EncryptedCodec::new(&[0u8; 16]),
+            Err(CodecError::InvalidKey(_))
+        ));
+    }
+}
@@ -0,0 +1,84 @@
+//! Start-up checks for the directory holding the databases.
+//!
+//! redb reports a read-only directory as an obscure I/O error from deep inside
+//! `Database::create`, so the directory is probed up front with [prepare_data_dir].
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+/// Why the data directory can't be used
+#[derive(Debug, Error)]
+pub enum DataDirError {
+    #[error("Failed to create the data directory {}: {source}", path.display())]
+    Create { path: PathBuf, source: io::Error },
+
+    #[error(
+        "The data directory {} is not writable ({source}). Make sure the user running Bento \
+         can create files in it, e.g. by changing its owner or permissions",
+        path.display()
+    )]
+    NotWritable { path: PathBuf, source: io::Error },
+}
+
+/// Creates `path` if missing and checks that files can be created and removed in it
+pub fn prepare_data_dir(path: &Path) -> Result<(), DataDirError> {
+    fs::create_dir_all(path).map_err(|source| DataDirError::Create {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let probe = path.join(format!(".bento-write-check-{}", std::process::id()));
+    fs::write(&probe, b"")
+        .and_then(|()| fs::remove_file(&probe))
+        .map_err(|source| DataDirError::NotWritable {
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("bento-{name}-{}", uuid::Uuid::now_v7()))
+    }
+
+    #[test]
+    fn missing_directory_is_created() {
+        let dir = temp_dir("data-dir");
+        prepare_data_dir(&dir).unwrap();
+        assert!(dir.is_dir());
+        // the probe file is cleaned up
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_only_directory_is_reported() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir("read-only");
+        fs::create_dir(&dir).unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o555)).unwrap();
+
+        // permissions don't apply to root, so there is nothing to observe
+        if fs::write(dir.join("probe"), b"").is_ok() {
+            fs::remove_dir_all(&dir).unwrap();
+            return;
+        }
+
+        let err = prepare_data_dir(&dir).unwrap_err();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_dir(&dir).unwrap();
+
+        assert!(matches!(err, DataDirError::NotWritable { .. }));
+        let message = err.to_string();
+        assert!(message.contains(&dir.display().to_string()));
+        assert!(message.contains("not writable"));
+    }
+}
@@ -0,0 +1,37 @@
+//! On-disk size and page usage of the redb databases, for deciding when to compact.
+
+use std::path::Path;
+
+use redb::Database;
+
+use crate::types::DatabaseReport;
+
+/// Reads the page statistics of `db`, plus the size of its file at `path` if it has one.
+///
+/// redb only reports statistics from a write transaction, so this briefly takes the write
+/// lock; the transaction is aborted, never committed.
+pub(crate) fn database_report<E>(
+    name: &str,
+    db: &Database,
+    path: Option<&Path>,
+) -> Result<DatabaseReport, E>
+where
+    E: From<redb::TransactionError> + From<redb::StorageError>,
+{
+    let txn = db.begin_write()?;
+    let stats = txn.stats()?;
+    txn.abort()?;
+
+    // a missing or unreadable file just leaves the size out
+    let file_size_bytes = path.and_then(|path| std::fs::metadata(path).ok().map(|m| m.len()));
+
+    Ok(DatabaseReport {
+        name: name.to_string(),
+        file_size_bytes,
+        page_size: stats.page_size() as u64,
+        allocated_pages: stats.allocated_pages(),
+        stored_bytes: stats.stored_bytes(),
+        metadata_bytes: stats.metadata_bytes(),
+        fragmented_bytes: stats.fragmented_bytes(),
+    })
+}
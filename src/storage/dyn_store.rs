@@ -0,0 +1,1074 @@
+//! Object-safe wrappers around the store traits.
+//!
+//! [AuthStore], [ProjectStore] and [AuditStore] return `impl Future`, so they can't be used
+//! as trait objects. Their `Dyn*` counterparts box the futures instead, which lets a store be
+//! picked at runtime (or replaced with a test double) behind e.g. `Arc<dyn DynAuthStore>`. That
+//! is how `AppState` holds the auth and project stores, so code generic over a store takes
+//! `S: ?Sized`.
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::pin::Pin;
+
+use time::OffsetDateTime;
+use tokio::sync::broadcast;
+
+use super::{AuditError, AuditStore, AuthError, AuthStore, ProjectError, ProjectStore};
+use crate::types::{
+    AuditEvent, AuditQuery, InviteCode, PasskeyCredential, PasswordHash, Project, ProjectId,
+    ProjectPage, ProjectSummary, ProjectVisibility, Role, Session, SessionCriteria, SessionId,
+    SessionIp, SessionPage, User, UserAgentHash, UserId, UserStatus, Username,
+};
+
+/// A boxed future, as returned by the `Dyn*` store traits
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Object-safe version of [AuthStore], implemented for every `AuthStore`.
+///
+/// `dyn DynAuthStore` implements `AuthStore` in turn, so it can be passed to code that is
+/// generic over the store.
+pub trait DynAuthStore: Send + Sync {
+    fn max_sessions_per_user(&self) -> usize;
+
+    fn create_user<'a>(
+        &'a self,
+        username: &'a Username,
+        pass_hash: PasswordHash,
+        role: Role,
+        status: UserStatus,
+    ) -> BoxFuture<'a, Result<User, AuthError>>;
+
+    fn create_user_or_first_admin<'a>(
+        &'a self,
+        username: &'a Username,
+        pass_hash: PasswordHash,
+        role: Role,
+        status: UserStatus,
+    ) -> BoxFuture<'a, Result<User, AuthError>>;
+
+    fn create_user_with_invite<'a>(
+        &'a self,
+        username: &'a Username,
+        pass_hash: PasswordHash,
+        code: &'a str,
+    ) -> BoxFuture<'a, Result<User, AuthError>>;
+
+    fn create_invite(
+        &self,
+        role: Role,
+        max_uses: u32,
+        expires_at: Option<OffsetDateTime>,
+    ) -> BoxFuture<'_, Result<InviteCode, AuthError>>;
+
+    fn list_invites(&self) -> BoxFuture<'_, Result<Vec<InviteCode>, AuthError>>;
+
+    fn revoke_invite<'a>(&'a self, code: &'a str) -> BoxFuture<'a, Result<(), AuthError>>;
+
+    fn get_user_by_id<'a>(&'a self, id: &'a UserId) -> BoxFuture<'a, Result<User, AuthError>>;
+
+    fn get_user_by_username<'a>(
+        &'a self,
+        username: &'a Username,
+    ) -> BoxFuture<'a, Result<User, AuthError>>;
+
+    fn list_users(&self) -> BoxFuture<'_, Result<Vec<User>, AuthError>>;
+
+    fn set_status<'a>(
+        &'a self,
+        id: &'a UserId,
+        status: UserStatus,
+    ) -> BoxFuture<'a, Result<User, AuthError>>;
+
+    fn rename_user<'a>(
+        &'a self,
+        id: &'a UserId,
+        new: &'a Username,
+    ) -> BoxFuture<'a, Result<User, AuthError>>;
+
+    fn set_password_hash<'a>(
+        &'a self,
+        id: &'a UserId,
+        new_hash: PasswordHash,
+    ) -> BoxFuture<'a, Result<PasswordHash, AuthError>>;
+
+    fn check_password_reuse<'a>(
+        &'a self,
+        id: &'a UserId,
+        password: &'a str,
+    ) -> BoxFuture<'a, Result<(), AuthError>>;
+
+    fn record_login<'a>(
+        &'a self,
+        id: &'a UserId,
+        ip: SessionIp,
+    ) -> BoxFuture<'a, Result<User, AuthError>>;
+
+    fn record_failed_login<'a>(
+        &'a self,
+        id: &'a UserId,
+        max_failed_logins: u32,
+        lock_for: time::Duration,
+    ) -> BoxFuture<'a, Result<User, AuthError>>;
+
+    fn clear_lockout<'a>(&'a self, id: &'a UserId) -> BoxFuture<'a, Result<User, AuthError>>;
+
+    fn delete_user<'a>(&'a self, id: &'a UserId) -> BoxFuture<'a, Result<(), AuthError>>;
+
+    fn preview_delete_user<'a>(
+        &'a self,
+        id: &'a UserId,
+    ) -> BoxFuture<'a, Result<Vec<SessionId>, AuthError>>;
+
+    fn save_passkey(&self, credential: PasskeyCredential) -> BoxFuture<'_, Result<(), AuthError>>;
+
+    fn list_passkeys<'a>(
+        &'a self,
+        user_id: &'a UserId,
+    ) -> BoxFuture<'a, Result<Vec<PasskeyCredential>, AuthError>>;
+
+    fn issue_session<'a>(
+        &'a self,
+        id: &'a UserId,
+        ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
+    ) -> BoxFuture<'a, Result<Session, AuthError>>;
+
+    fn issue_impersonation_session<'a>(
+        &'a self,
+        admin: &'a UserId,
+        target: &'a UserId,
+        ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
+    ) -> BoxFuture<'a, Result<Session, AuthError>>;
+
+    fn fetch_session<'a>(
+        &'a self,
+        token: &'a SessionId,
+    ) -> BoxFuture<'a, Result<Session, AuthError>>;
+
+    fn extend_session<'a>(
+        &'a self,
+        token: &'a SessionId,
+    ) -> BoxFuture<'a, Result<Session, AuthError>>;
+
+    fn mark_reauthenticated<'a>(
+        &'a self,
+        token: &'a SessionId,
+    ) -> BoxFuture<'a, Result<Session, AuthError>>;
+
+    fn set_session_data<'a>(
+        &'a self,
+        token: &'a SessionId,
+        key: &'a str,
+        value: Option<String>,
+    ) -> BoxFuture<'a, Result<Session, AuthError>>;
+
+    fn get_session_data<'a>(
+        &'a self,
+        token: &'a SessionId,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, AuthError>>;
+
+    fn revoke_session<'a>(&'a self, token: &'a SessionId) -> BoxFuture<'a, Result<(), AuthError>>;
+
+    fn revoke_all_sessions<'a>(&'a self, id: &'a UserId)
+    -> BoxFuture<'a, Result<usize, AuthError>>;
+
+    fn purge_expired_sessions(&self) -> BoxFuture<'_, Result<usize, AuthError>>;
+
+    fn count_user_sessions<'a>(&'a self, id: &'a UserId)
+    -> BoxFuture<'a, Result<usize, AuthError>>;
+
+    fn list_user_sessions<'a>(
+        &'a self,
+        id: &'a UserId,
+    ) -> BoxFuture<'a, Result<Vec<Session>, AuthError>>;
+
+    fn list_user_sessions_page<'a>(
+        &'a self,
+        id: &'a UserId,
+        after: Option<&'a SessionId>,
+        limit: usize,
+    ) -> BoxFuture<'a, Result<SessionPage, AuthError>>;
+
+    fn active_sessions_by_ip(&self) -> BoxFuture<'_, Result<BTreeMap<IpAddr, usize>, AuthError>>;
+
+    fn revoke_sessions_where<'a>(
+        &'a self,
+        criteria: &'a SessionCriteria,
+    ) -> BoxFuture<'a, Result<usize, AuthError>>;
+}
+
+impl<S: AuthStore> DynAuthStore for S {
+    fn max_sessions_per_user(&self) -> usize {
+        AuthStore::max_sessions_per_user(self)
+    }
+
+    fn create_user<'a>(
+        &'a self,
+        username: &'a Username,
+        pass_hash: PasswordHash,
+        role: Role,
+        status: UserStatus,
+    ) -> BoxFuture<'a, Result<User, AuthError>> {
+        Box::pin(AuthStore::create_user(
+            self, username, pass_hash, role, status,
+        ))
+    }
+
+    fn create_user_or_first_admin<'a>(
+        &'a self,
+        username: &'a Username,
+        pass_hash: PasswordHash,
+        role: Role,
+        status: UserStatus,
+    ) -> BoxFuture<'a, Result<User, AuthError>> {
+        Box::pin(AuthStore::create_user_or_first_admin(
+            self, username, pass_hash, role, status,
+        ))
+    }
+
+    fn create_user_with_invite<'a>(
+        &'a self,
+        username: &'a Username,
+        pass_hash: PasswordHash,
+        code: &'a str,
+    ) -> BoxFuture<'a, Result<User, AuthError>> {
+        Box::pin(AuthStore::create_user_with_invite(
+            self, username, pass_hash, code,
+        ))
+    }
+
+    fn create_invite(
+        &self,
+        role: Role,
+        max_uses: u32,
+        expires_at: Option<OffsetDateTime>,
+    ) -> BoxFuture<'_, Result<InviteCode, AuthError>> {
+        Box::pin(AuthStore::create_invite(self, role, max_uses, expires_at))
+    }
+
+    fn list_invites(&self) -> BoxFuture<'_, Result<Vec<InviteCode>, AuthError>> {
+        Box::pin(AuthStore::list_invites(self))
+    }
+
+    fn revoke_invite<'a>(&'a self, code: &'a str) -> BoxFuture<'a, Result<(), AuthError>> {
+        Box::pin(AuthStore::revoke_invite(self, code))
+    }
+
+    fn get_user_by_id<'a>(&'a self, id: &'a UserId) -> BoxFuture<'a, Result<User, AuthError>> {
+        Box::pin(AuthStore::get_user_by_id(self, id))
+    }
+
+    fn get_user_by_username<'a>(
+        &'a self,
+        username: &'a Username,
+    ) -> BoxFuture<'a, Result<User, AuthError>> {
+        Box::pin(AuthStore::get_user_by_username(self, username))
+    }
+
+    fn list_users(&self) -> BoxFuture<'_, Result<Vec<User>, AuthError>> {
+        Box::pin(AuthStore::list_users(self))
+    }
+
+    fn set_status<'a>(
+        &'a self,
+        id: &'a UserId,
+        status: UserStatus,
+    ) -> BoxFuture<'a, Result<User, AuthError>> {
+        Box::pin(AuthStore::set_status(self, id, status))
+    }
+
+    fn rename_user<'a>(
+        &'a self,
+        id: &'a UserId,
+        new: &'a Username,
+    ) -> BoxFuture<'a, Result<User, AuthError>> {
+        Box::pin(AuthStore::rename_user(self, id, new))
+    }
+
+    fn set_password_hash<'a>(
+        &'a self,
+        id: &'a UserId,
+        new_hash: PasswordHash,
+    ) -> BoxFuture<'a, Result<PasswordHash, AuthError>> {
+        Box::pin(AuthStore::set_password_hash(self, id, new_hash))
+    }
+
+    fn check_password_reuse<'a>(
+        &'a self,
+        id: &'a UserId,
+        password: &'a str,
+    ) -> BoxFuture<'a, Result<(), AuthError>> {
+        Box::pin(AuthStore::check_password_reuse(self, id, password))
+    }
+
+    fn record_login<'a>(
+        &'a self,
+        id: &'a UserId,
+        ip: SessionIp,
+    ) -> BoxFuture<'a, Result<User, AuthError>> {
+        Box::pin(AuthStore::record_login(self, id, ip))
+    }
+
+    fn record_failed_login<'a>(
+        &'a self,
+        id: &'a UserId,
+        max_failed_logins: u32,
+        lock_for: time::Duration,
+    ) -> BoxFuture<'a, Result<User, AuthError>> {
+        Box::pin(AuthStore::record_failed_login(
+            self,
+            id,
+            max_failed_logins,
+            lock_for,
+        ))
+    }
+
+    fn clear_lockout<'a>(&'a self, id: &'a UserId) -> BoxFuture<'a, Result<User, AuthError>> {
+        Box::pin(AuthStore::clear_lockout(self, id))
+    }
+
+    fn delete_user<'a>(&'a self, id: &'a UserId) -> BoxFuture<'a, Result<(), AuthError>> {
+        Box::pin(AuthStore::delete_user(self, id))
+    }
+
+    fn preview_delete_user<'a>(
+        &'a self,
+        id: &'a UserId,
+    ) -> BoxFuture<'a, Result<Vec<SessionId>, AuthError>> {
+        Box::pin(AuthStore::preview_delete_user(self, id))
+    }
+
+    fn save_passkey(&self, credential: PasskeyCredential) -> BoxFuture<'_, Result<(), AuthError>> {
+        Box::pin(AuthStore::save_passkey(self, credential))
+    }
+
+    fn list_passkeys<'a>(
+        &'a self,
+        user_id: &'a UserId,
+    ) -> BoxFuture<'a, Result<Vec<PasskeyCredential>, AuthError>> {
+        Box::pin(AuthStore::list_passkeys(self, user_id))
+    }
+
+    fn issue_session<'a>(
+        &'a self,
+        id: &'a UserId,
+        ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
+    ) -> BoxFuture<'a, Result<Session, AuthError>> {
+        Box::pin(AuthStore::issue_session(self, id, ip, user_agent))
+    }
+
+    fn issue_impersonation_session<'a>(
+        &'a self,
+        admin: &'a UserId,
+        target: &'a UserId,
+        ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
+    ) -> BoxFuture<'a, Result<Session, AuthError>> {
+        Box::pin(AuthStore::issue_impersonation_session(
+            self, admin, target, ip, user_agent,
+        ))
+    }
+
+    fn fetch_session<'a>(
+        &'a self,
+        token: &'a SessionId,
+    ) -> BoxFuture<'a, Result<Session, AuthError>> {
+        Box::pin(AuthStore::fetch_session(self, token))
+    }
+
+    fn extend_session<'a>(
+        &'a self,
+        token: &'a SessionId,
+    ) -> BoxFuture<'a, Result<Session, AuthError>> {
+        Box::pin(AuthStore::extend_session(self, token))
+    }
+
+    fn mark_reauthenticated<'a>(
+        &'a self,
+        token: &'a SessionId,
+    ) -> BoxFuture<'a, Result<Session, AuthError>> {
+        Box::pin(AuthStore::mark_reauthenticated(self, token))
+    }
+
+    fn set_session_data<'a>(
+        &'a self,
+        token: &'a SessionId,
+        key: &'a str,
+        value: Option<String>,
+    ) -> BoxFuture<'a, Result<Session, AuthError>> {
+        Box::pin(AuthStore::set_session_data(self, token, key, value))
+    }
+
+    fn get_session_data<'a>(
+        &'a self,
+        token: &'a SessionId,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, AuthError>> {
+        Box::pin(AuthStore::get_session_data(self, token, key))
+    }
+
+    fn revoke_session<'a>(&'a self, token: &'a SessionId) -> BoxFuture<'a, Result<(), AuthError>> {
+        Box::pin(AuthStore::revoke_session(self, token))
+    }
+
+    fn revoke_all_sessions<'a>(
+        &'a self,
+        id: &'a UserId,
+    ) -> BoxFuture<'a, Result<usize, AuthError>> {
+        Box::pin(AuthStore::revoke_all_sessions(self, id))
+    }
+
+    fn purge_expired_sessions(&self) -> BoxFuture<'_, Result<usize, AuthError>> {
+        Box::pin(AuthStore::purge_expired_sessions(self))
+    }
+
+    fn count_user_sessions<'a>(
+        &'a self,
+        id: &'a UserId,
+    ) -> BoxFuture<'a, Result<usize, AuthError>> {
+        Box::pin(AuthStore::count_user_sessions(self, id))
+    }
+
+    fn list_user_sessions<'a>(
+        &'a self,
+        id: &'a UserId,
+    ) -> BoxFuture<'a, Result<Vec<Session>, AuthError>> {
+        Box::pin(AuthStore::list_user_sessions(self, id))
+    }
+
+    fn list_user_sessions_page<'a>(
+        &'a self,
+        id: &'a UserId,
+        after: Option<&'a SessionId>,
+        limit: usize,
+    ) -> BoxFuture<'a, Result<SessionPage, AuthError>> {
+        Box::pin(AuthStore::list_user_sessions_page(self, id, after, limit))
+    }
+
+    fn active_sessions_by_ip(&self) -> BoxFuture<'_, Result<BTreeMap<IpAddr, usize>, AuthError>> {
+        Box::pin(AuthStore::active_sessions_by_ip(self))
+    }
+
+    fn revoke_sessions_where<'a>(
+        &'a self,
+        criteria: &'a SessionCriteria,
+    ) -> BoxFuture<'a, Result<usize, AuthError>> {
+        Box::pin(AuthStore::revoke_sessions_where(self, criteria))
+    }
+}
+
+impl<'a> AuthStore for dyn DynAuthStore + 'a {
+    fn max_sessions_per_user(&self) -> usize {
+        DynAuthStore::max_sessions_per_user(self)
+    }
+
+    async fn create_user(
+        &self,
+        username: &Username,
+        pass_hash: PasswordHash,
+        role: Role,
+        status: UserStatus,
+    ) -> Result<User, AuthError> {
+        DynAuthStore::create_user(self, username, pass_hash, role, status).await
+    }
+
+    async fn create_user_or_first_admin(
+        &self,
+        username: &Username,
+        pass_hash: PasswordHash,
+        role: Role,
+        status: UserStatus,
+    ) -> Result<User, AuthError> {
+        DynAuthStore::create_user_or_first_admin(self, username, pass_hash, role, status).await
+    }
+
+    async fn create_user_with_invite(
+        &self,
+        username: &Username,
+        pass_hash: PasswordHash,
+        code: &str,
+    ) -> Result<User, AuthError> {
+        DynAuthStore::create_user_with_invite(self, username, pass_hash, code).await
+    }
+
+    fn create_invite(
+        &self,
+        role: Role,
+        max_uses: u32,
+        expires_at: Option<OffsetDateTime>,
+    ) -> impl Future<Output = Result<InviteCode, AuthError>> + Send {
+        DynAuthStore::create_invite(self, role, max_uses, expires_at)
+    }
+
+    fn list_invites(&self) -> impl Future<Output = Result<Vec<InviteCode>, AuthError>> + Send {
+        DynAuthStore::list_invites(self)
+    }
+
+    async fn revoke_invite(&self, code: &str) -> Result<(), AuthError> {
+        DynAuthStore::revoke_invite(self, code).await
+    }
+
+    async fn get_user_by_id(&self, id: &UserId) -> Result<User, AuthError> {
+        DynAuthStore::get_user_by_id(self, id).await
+    }
+
+    async fn get_user_by_username(&self, username: &Username) -> Result<User, AuthError> {
+        DynAuthStore::get_user_by_username(self, username).await
+    }
+
+    fn list_users(&self) -> impl Future<Output = Result<Vec<User>, AuthError>> + Send {
+        DynAuthStore::list_users(self)
+    }
+
+    async fn set_status(&self, id: &UserId, status: UserStatus) -> Result<User, AuthError> {
+        DynAuthStore::set_status(self, id, status).await
+    }
+
+    async fn rename_user(&self, id: &UserId, new: &Username) -> Result<User, AuthError> {
+        DynAuthStore::rename_user(self, id, new).await
+    }
+
+    async fn set_password_hash(
+        &self,
+        id: &UserId,
+        new_hash: PasswordHash,
+    ) -> Result<PasswordHash, AuthError> {
+        DynAuthStore::set_password_hash(self, id, new_hash).await
+    }
+
+    async fn check_password_reuse(&self, id: &UserId, password: &str) -> Result<(), AuthError> {
+        DynAuthStore::check_password_reuse(self, id, password).await
+    }
+
+    async fn record_login(&self, id: &UserId, ip: SessionIp) -> Result<User, AuthError> {
+        DynAuthStore::record_login(self, id, ip).await
+    }
+
+    async fn record_failed_login(
+        &self,
+        id: &UserId,
+        max_failed_logins: u32,
+        lock_for: time::Duration,
+    ) -> Result<User, AuthError> {
+        DynAuthStore::record_failed_login(self, id, max_failed_logins, lock_for).await
+    }
+
+    async fn clear_lockout(&self, id: &UserId) -> Result<User, AuthError> {
+        DynAuthStore::clear_lockout(self, id).await
+    }
+
+    async fn delete_user(&self, id: &UserId) -> Result<(), AuthError> {
+        DynAuthStore::delete_user(self, id).await
+    }
+
+    async fn preview_delete_user(&self, id: &UserId) -> Result<Vec<SessionId>, AuthError> {
+        DynAuthStore::preview_delete_user(self, id).await
+    }
+
+    fn save_passkey(
+        &self,
+        credential: PasskeyCredential,
+    ) -> impl Future<Output = Result<(), AuthError>> + Send {
+        DynAuthStore::save_passkey(self, credential)
+    }
+
+    async fn list_passkeys(&self, user_id: &UserId) -> Result<Vec<PasskeyCredential>, AuthError> {
+        DynAuthStore::list_passkeys(self, user_id).await
+    }
+
+    async fn issue_session(
+        &self,
+        id: &UserId,
+        ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
+    ) -> Result<Session, AuthError> {
+        DynAuthStore::issue_session(self, id, ip, user_agent).await
+    }
+
+    async fn issue_impersonation_session(
+        &self,
+        admin: &UserId,
+        target: &UserId,
+        ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
+    ) -> Result<Session, AuthError> {
+        DynAuthStore::issue_impersonation_session(self, admin, target, ip, user_agent).await
+    }
+
+    async fn fetch_session(&self, token: &SessionId) -> Result<Session, AuthError> {
+        DynAuthStore::fetch_session(self, token).await
+    }
+
+    async fn extend_session(&self, token: &SessionId) -> Result<Session, AuthError> {
+        DynAuthStore::extend_session(self, token).await
+    }
+
+    async fn mark_reauthenticated(&self, token: &SessionId) -> Result<Session, AuthError> {
+        DynAuthStore::mark_reauthenticated(self, token).await
+    }
+
+    async fn set_session_data(
+        &self,
+        token: &SessionId,
+        key: &str,
+        value: Option<String>,
+    ) -> Result<Session, AuthError> {
+        DynAuthStore::set_session_data(self, token, key, value).await
+    }
+
+    async fn get_session_data(
+        &self,
+        token: &SessionId,
+        key: &str,
+    ) -> Result<Option<String>, AuthError> {
+        DynAuthStore::get_session_data(self, token, key).await
+    }
+
+    async fn revoke_session(&self, token: &SessionId) -> Result<(), AuthError> {
+        DynAuthStore::revoke_session(self, token).await
+    }
+
+    async fn revoke_all_sessions(&self, id: &UserId) -> Result<usize, AuthError> {
+        DynAuthStore::revoke_all_sessions(self, id).await
+    }
+
+    fn purge_expired_sessions(&self) -> impl Future<Output = Result<usize, AuthError>> + Send {
+        DynAuthStore::purge_expired_sessions(self)
+    }
+
+    async fn count_user_sessions(&self, id: &UserId) -> Result<usize, AuthError> {
+        DynAuthStore::count_user_sessions(self, id).await
+    }
+
+    async fn list_user_sessions(&self, id: &UserId) -> Result<Vec<Session>, AuthError> {
+        DynAuthStore::list_user_sessions(self, id).await
+    }
+
+    async fn list_user_sessions_page(
+        &self,
+        id: &UserId,
+        after: Option<&SessionId>,
+        limit: usize,
+    ) -> Result<SessionPage, AuthError> {
+        DynAuthStore::list_user_sessions_page(self, id, after, limit).await
+    }
+
+    fn active_sessions_by_ip(
+        &self,
+    ) -> impl Future<Output = Result<BTreeMap<IpAddr, usize>, AuthError>> + Send {
+        DynAuthStore::active_sessions_by_ip(self)
+    }
+
+    async fn revoke_sessions_where(&self, criteria: &SessionCriteria) -> Result<usize, AuthError> {
+        DynAuthStore::revoke_sessions_where(self, criteria).await
+    }
+}
+
+/// Object-safe version of [ProjectStore], implemented for every `ProjectStore`.
+///
+/// `dyn DynProjectStore` implements `ProjectStore` in turn, so it can be passed to code that is
+/// generic over the store.
+pub trait DynProjectStore: Send + Sync {
+    fn create_project<'a>(
+        &'a self,
+        owner_id: &'a UserId,
+        name: String,
+        description: Option<String>,
+        idempotency_key: Option<String>,
+    ) -> BoxFuture<'a, Result<(Project, bool), ProjectError>>;
+
+    fn get_project<'a>(
+        &'a self,
+        project_id: &'a ProjectId,
+    ) -> BoxFuture<'a, Result<Project, ProjectError>>;
+
+    fn get_projects<'a>(
+        &'a self,
+        project_ids: &'a [ProjectId],
+    ) -> BoxFuture<'a, Result<Vec<Project>, ProjectError>>;
+
+    fn get_user_projects<'a>(
+        &'a self,
+        owner_id: &'a UserId,
+    ) -> BoxFuture<'a, Result<Vec<ProjectSummary>, ProjectError>>;
+
+    fn get_user_projects_page<'a>(
+        &'a self,
+        owner_id: &'a UserId,
+        after: Option<ProjectId>,
+        limit: usize,
+    ) -> BoxFuture<'a, Result<ProjectPage, ProjectError>>;
+
+    fn get_by_slug<'a>(&'a self, slug: &'a str) -> BoxFuture<'a, Result<Project, ProjectError>>;
+
+    fn search_projects<'a>(
+        &'a self,
+        owner_id: &'a UserId,
+        query: &'a str,
+        limit: usize,
+    ) -> BoxFuture<'a, Result<Vec<ProjectSummary>, ProjectError>>;
+
+    fn update_project<'a>(
+        &'a self,
+        project_id: &'a ProjectId,
+        name: Option<String>,
+        description: Option<Option<String>>,
+        rate_limit_rpm: Option<Option<u32>>,
+        visibility: Option<ProjectVisibility>,
+    ) -> BoxFuture<'a, Result<Project, ProjectError>>;
+
+    fn set_delete_protection<'a>(
+        &'a self,
+        project_id: &'a ProjectId,
+        protected: bool,
+    ) -> BoxFuture<'a, Result<Project, ProjectError>>;
+
+    fn delete_project<'a>(
+        &'a self,
+        project_id: &'a ProjectId,
+    ) -> BoxFuture<'a, Result<(), ProjectError>>;
+
+    fn preview_delete_project<'a>(
+        &'a self,
+        project_id: &'a ProjectId,
+    ) -> BoxFuture<'a, Result<Project, ProjectError>>;
+
+    fn clone_project<'a>(
+        &'a self,
+        source: &'a ProjectId,
+        new_owner: &'a UserId,
+    ) -> BoxFuture<'a, Result<Project, ProjectError>>;
+}
+
+impl<S: ProjectStore> DynProjectStore for S {
+    fn create_project<'a>(
+        &'a self,
+        owner_id: &'a UserId,
+        name: String,
+        description: Option<String>,
+        idempotency_key: Option<String>,
+    ) -> BoxFuture<'a, Result<(Project, bool), ProjectError>> {
+        Box::pin(ProjectStore::create_project(
+            self,
+            owner_id,
+            name,
+            description,
+            idempotency_key,
+        ))
+    }
+
+    fn get_project<'a>(
+        &'a self,
+        project_id: &'a ProjectId,
+    ) -> BoxFuture<'a, Result<Project, ProjectError>> {
+        Box::pin(ProjectStore::get_project(self, project_id))
+    }
+
+    fn get_projects<'a>(
+        &'a self,
+        project_ids: &'a [ProjectId],
+    ) -> BoxFuture<'a, Result<Vec<Project>, ProjectError>> {
+        Box::pin(ProjectStore::get_projects(self, project_ids))
+    }
+
+    fn get_user_projects<'a>(
+        &'a self,
+        owner_id: &'a UserId,
+    ) -> BoxFuture<'a, Result<Vec<ProjectSummary>, ProjectError>> {
+        Box::pin(ProjectStore::get_user_projects(self, owner_id))
+    }
+
+    fn get_user_projects_page<'a>(
+        &'a self,
+        owner_id: &'a UserId,
+        after: Option<ProjectId>,
+        limit: usize,
+    ) -> BoxFuture<'a, Result<ProjectPage, ProjectError>> {
+        Box::pin(ProjectStore::get_user_projects_page(
+            self, owner_id, after, limit,
+        ))
+    }
+
+    fn get_by_slug<'a>(&'a self, slug: &'a str) -> BoxFuture<'a, Result<Project, ProjectError>> {
+        Box::pin(ProjectStore::get_by_slug(self, slug))
+    }
+
+    fn search_projects<'a>(
+        &'a self,
+        owner_id: &'a UserId,
+        query: &'a str,
+        limit: usize,
+    ) -> BoxFuture<'a, Result<Vec<ProjectSummary>, ProjectError>> {
+        Box::pin(ProjectStore::search_projects(self, owner_id, query, limit))
+    }
+
+    fn update_project<'a>(
+        &'a self,
+        project_id: &'a ProjectId,
+        name: Option<String>,
+        description: Option<Option<String>>,
+        rate_limit_rpm: Option<Option<u32>>,
+        visibility: Option<ProjectVisibility>,
+    ) -> BoxFuture<'a, Result<Project, ProjectError>> {
+        Box::pin(ProjectStore::update_project(
+            self,
+            project_id,
+            name,
+            description,
+            rate_limit_rpm,
+            visibility,
+        ))
+    }
+
+    fn set_delete_protection<'a>(
+        &'a self,
+        project_id: &'a ProjectId,
+        protected: bool,
+    ) -> BoxFuture<'a, Result<Project, ProjectError>> {
+        Box::pin(ProjectStore::set_delete_protection(
+            self, project_id, protected,
+        ))
+    }
+
+    fn delete_project<'a>(
+        &'a self,
+        project_id: &'a ProjectId,
+    ) -> BoxFuture<'a, Result<(), ProjectError>> {
+        Box::pin(ProjectStore::delete_project(self, project_id))
+    }
+
+    fn preview_delete_project<'a>(
+        &'a self,
+        project_id: &'a ProjectId,
+    ) -> BoxFuture<'a, Result<Project, ProjectError>> {
+        Box::pin(ProjectStore::preview_delete_project(self, project_id))
+    }
+
+    fn clone_project<'a>(
+        &'a self,
+        source: &'a ProjectId,
+        new_owner: &'a UserId,
+    ) -> BoxFuture<'a, Result<Project, ProjectError>> {
+        Box::pin(ProjectStore::clone_project(self, source, new_owner))
+    }
+}
+
+impl<'a> ProjectStore for dyn DynProjectStore + 'a {
+    async fn create_project(
+        &self,
+        owner_id: &UserId,
+        name: String,
+        description: Option<String>,
+        idempotency_key: Option<String>,
+    ) -> Result<(Project, bool), ProjectError> {
+        DynProjectStore::create_project(self, owner_id, name, description, idempotency_key).await
+    }
+
+    async fn get_project(&self, project_id: &ProjectId) -> Result<Project, ProjectError> {
+        DynProjectStore::get_project(self, project_id).await
+    }
+
+    async fn get_projects(&self, project_ids: &[ProjectId]) -> Result<Vec<Project>, ProjectError> {
+        DynProjectStore::get_projects(self, project_ids).await
+    }
+
+    async fn get_user_projects(
+        &self,
+        owner_id: &UserId,
+    ) -> Result<Vec<ProjectSummary>, ProjectError> {
+        DynProjectStore::get_user_projects(self, owner_id).await
+    }
+
+    async fn get_user_projects_page(
+        &self,
+        owner_id: &UserId,
+        after: Option<ProjectId>,
+        limit: usize,
+    ) -> Result<ProjectPage, ProjectError> {
+        DynProjectStore::get_user_projects_page(self, owner_id, after, limit).await
+    }
+
+    async fn get_by_slug(&self, slug: &str) -> Result<Project, ProjectError> {
+        DynProjectStore::get_by_slug(self, slug).await
+    }
+
+    async fn search_projects(
+        &self,
+        owner_id: &UserId,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<ProjectSummary>, ProjectError> {
+        DynProjectStore::search_projects(self, owner_id, query, limit).await
+    }
+
+    async fn update_project(
+        &self,
+        project_id: &ProjectId,
+        name: Option<String>,
+        description: Option<Option<String>>,
+        rate_limit_rpm: Option<Option<u32>>,
+        visibility: Option<ProjectVisibility>,
+    ) -> Result<Project, ProjectError> {
+        DynProjectStore::update_project(
+            self,
+            project_id,
+            name,
+            description,
+            rate_limit_rpm,
+            visibility,
+        )
+        .await
+    }
+
+    async fn set_delete_protection(
+        &self,
+        project_id: &ProjectId,
+        protected: bool,
+    ) -> Result<Project, ProjectError> {
+        DynProjectStore::set_delete_protection(self, project_id, protected).await
+    }
+
+    async fn delete_project(&self, project_id: &ProjectId) -> Result<(), ProjectError> {
+        DynProjectStore::delete_project(self, project_id).await
+    }
+
+    async fn preview_delete_project(
+        &self,
+        project_id: &ProjectId,
+    ) -> Result<Project, ProjectError> {
+        DynProjectStore::preview_delete_project(self, project_id).await
+    }
+
+    async fn clone_project(
+        &self,
+        source: &ProjectId,
+        new_owner: &UserId,
+    ) -> Result<Project, ProjectError> {
+        DynProjectStore::clone_project(self, source, new_owner).await
+    }
+}
+
+/// Object-safe version of [AuditStore], implemented for every `AuditStore`.
+///
+/// `dyn DynAuditStore` implements `AuditStore` in turn, so it can be passed to code that is
+/// generic over the store.
+pub trait DynAuditStore: Send + Sync {
+    fn record(&self, event: AuditEvent) -> BoxFuture<'_, Result<(), AuditError>>;
+
+    fn query(&self, query: AuditQuery) -> BoxFuture<'_, Result<Vec<AuditEvent>, AuditError>>;
+
+    fn subscribe(&self) -> broadcast::Receiver<AuditEvent>;
+}
+
+impl<S: AuditStore> DynAuditStore for S {
+    fn record(&self, event: AuditEvent) -> BoxFuture<'_, Result<(), AuditError>> {
+        Box::pin(AuditStore::record(self, event))
+    }
+
+    fn query(&self, query: AuditQuery) -> BoxFuture<'_, Result<Vec<AuditEvent>, AuditError>> {
+        Box::pin(AuditStore::query(self, query))
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<AuditEvent> {
+        AuditStore::subscribe(self)
+    }
+}
+
+impl<'a> AuditStore for dyn DynAuditStore + 'a {
+    fn record(&self, event: AuditEvent) -> impl Future<Output = Result<(), AuditError>> + Send {
+        DynAuditStore::record(self, event)
+    }
+
+    fn query(
+        &self,
+        query: AuditQuery,
+    ) -> impl Future<Output = Result<Vec<AuditEvent>, AuditError>> + Send {
+        DynAuditStore::query(self, query)
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<AuditEvent> {
+        DynAuditStore::subscribe(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::mem_authstore::MemoryAuthStore;
+    use crate::storage::redb_auditstore::RedbAuditStore;
+    use crate::storage::redb_authstore::RedbAuthStore;
+    use crate::storage::redb_projectstore::RedbProjectStore;
+    use crate::types::AuditKind;
+    use std::sync::Arc;
+
+    // Written against the static traits, unaware of which store they are given. (Calling the
+    // methods on a `dyn ...` directly would be ambiguous with both traits in scope.)
+
+    async fn sign_in_and_out<S: AuthStore + ?Sized>(store: &S) {
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let session = store
+            .issue_session(&user.id, SessionIp(IpAddr::from([127, 0, 0, 1])), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            store.fetch_session(&session.id).await.unwrap().user_id,
+            user.id
+        );
+
+        store.revoke_session(&session.id).await.unwrap();
+        assert!(matches!(
+            store.fetch_session(&session.id).await,
+            Err(AuthError::InvalidSession)
+        ));
+    }
+
+    async fn create_and_log<P: ProjectStore + ?Sized, A: AuditStore + ?Sized>(
+        projects: &P,
+        audit: &A,
+    ) {
+        let owner = UserId::new();
+        let (project, created) = projects
+            .create_project(&owner, "Bento".to_string(), None, None)
+            .await
+            .unwrap();
+        assert!(created);
+        assert_eq!(projects.get_user_projects(&owner).await.unwrap().len(), 1);
+
+        let event = AuditEvent::new(Some(owner), AuditKind::ProjectCreated, project.name)
+            .with_project(project.id);
+        audit.record(event.clone()).await.unwrap();
+        assert_eq!(
+            audit.query(AuditQuery::default()).await.unwrap(),
+            vec![event]
+        );
+    }
+
+    #[tokio::test]
+    async fn auth_stores_can_be_swapped_behind_the_dyn_interface() {
+        let stores: Vec<Arc<dyn DynAuthStore>> = vec![
+            Arc::new(RedbAuthStore::in_memory(3).unwrap()),
+            Arc::new(MemoryAuthStore::new(3)),
+        ];
+
+        for store in stores {
+            assert_eq!(AuthStore::max_sessions_per_user(store.as_ref()), 3);
+            sign_in_and_out(store.as_ref()).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn project_and_audit_stores_work_behind_the_dyn_interface() {
+        let projects: Arc<dyn DynProjectStore> = Arc::new(RedbProjectStore::in_memory().unwrap());
+        let audit: Arc<dyn DynAuditStore> = Arc::new(RedbAuditStore::in_memory().unwrap());
+        create_and_log(projects.as_ref(), audit.as_ref()).await;
+    }
+}
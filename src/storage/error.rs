@@ -1,4 +1,4 @@
-//! Storage error types for auth and project stores.
+//! Storage error types for auth, project and audit stores.
 //!
 //! This module consolidates error handling for storage backends,
 //! using a macro to reduce boilerplate for common error conversions.
@@ -8,7 +8,7 @@ use thiserror::Error;
 /// Macro to implement From traits for common storage backend errors.
 ///
 /// This reduces duplication when multiple error types need the same
-/// conversions from redb, bincode, and tokio errors.
+/// conversions from redb, bincode, codec, and tokio errors.
 macro_rules! impl_storage_error_conversions {
     ($error_type:ty) => {
         #[cfg(feature = "ssr")]
@@ -60,6 +60,13 @@ macro_rules! impl_storage_error_conversions {
             }
         }
 
+        #[cfg(feature = "ssr")]
+        impl From<super::codec::CodecError> for $error_type {
+            fn from(err: super::codec::CodecError) -> Self {
+                Self::Internal(err.to_string())
+            }
+        }
+
         #[cfg(feature = "ssr")]
         impl From<tokio::task::JoinError> for $error_type {
             fn from(err: tokio::task::JoinError) -> Self {
@@ -79,6 +86,22 @@ pub enum AuthError {
     InvalidSession,
     #[error("Maximum active sessions reached")]
     SessionLimitReached,
+    /// The instance-wide `[session] max_total` is reached, whoever signs in
+    #[error("Maximum active sessions of this instance reached")]
+    InstanceSessionLimitReached,
+    #[error("Invalid invite code")]
+    InvalidInvite,
+    #[error("Account disabled")]
+    AccountDisabled,
+    #[error("Account locked")]
+    AccountLocked,
+    /// The new password is one of the user's recent ones, see `check_password_reuse`
+    #[error("Password was used recently")]
+    PasswordReused,
+    /// Setting session data would go over `Session::MAX_DATA_ENTRIES` or
+    /// `Session::MAX_DATA_BYTES`
+    #[error("Session data too large")]
+    SessionDataTooLarge,
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -93,8 +116,19 @@ pub enum ProjectError {
     AlreadyExists,
     #[error("Unauthorized access to project")]
     Unauthorized,
+    /// The project has `delete_protected` set
+    #[error("Project is protected from deletion")]
+    DeleteProtected,
     #[error("Internal error: {0}")]
     Internal(String),
 }
 
 impl_storage_error_conversions!(ProjectError);
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl_storage_error_conversions!(AuditError);
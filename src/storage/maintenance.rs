@@ -0,0 +1,343 @@
+//! Expired session cleanup and its statistics, and the disabling of inactive accounts.
+//!
+//! Expired sessions are removed lazily whenever the auth store comes across one, and in bulk
+//! by a periodic sweep ([spawn_session_sweeper]). Both paths are counted in a shared
+//! [MaintenanceStats], reported to admins through `get_maintenance_stats`.
+//!
+//! With `[accounts] inactivity_days` set, [spawn_inactivity_sweeper] also periodically
+//! disables accounts that haven't been used for that long.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, error, info, warn};
+
+use super::clock::Clock;
+use super::{AuditStore, AuthError, AuthStore};
+use crate::types::{AuditEvent, AuditKind, MaintenanceReport, Role, User, UserStatus};
+
+/// How often [spawn_inactivity_sweeper] looks for inactive accounts
+pub const INACTIVITY_SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Running cleanup counters, shared between the auth store, the sweeper and `AppState`
+#[derive(Debug, Default)]
+pub struct MaintenanceStats {
+    sweeps: AtomicU64,
+    /// Unix timestamp of the last sweep; 0 before the first one
+    last_sweep_at: AtomicI64,
+    swept_sessions: AtomicU64,
+    lazily_purged_sessions: AtomicU64,
+}
+
+impl MaintenanceStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts sessions the store removed on its own while looking them up
+    pub fn record_lazy_purge(&self, sessions: usize) {
+        if sessions > 0 {
+            self.lazily_purged_sessions
+                .fetch_add(sessions as u64, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_sweep(&self, at: OffsetDateTime, sessions: usize) {
+        self.sweeps.fetch_add(1, Ordering::Relaxed);
+        self.swept_sessions
+            .fetch_add(sessions as u64, Ordering::Relaxed);
+        self.last_sweep_at
+            .store(at.unix_timestamp(), Ordering::Relaxed);
+    }
+
+    pub fn report(&self) -> MaintenanceReport {
+        let last_sweep_at = match self.last_sweep_at.load(Ordering::Relaxed) {
+            0 => None,
+            at => OffsetDateTime::from_unix_timestamp(at).ok(),
+        };
+        let swept_sessions = self.swept_sessions.load(Ordering::Relaxed);
+        let lazily_purged_sessions = self.lazily_purged_sessions.load(Ordering::Relaxed);
+
+        MaintenanceReport {
+            total_sweeps: self.sweeps.load(Ordering::Relaxed),
+            last_sweep_at,
+            swept_sessions,
+            lazily_purged_sessions,
+            sessions_purged: swept_sessions + lazily_purged_sessions,
+        }
+    }
+}
+
+/// Removes every expired session once and records the sweep, returning how many were removed
+pub async fn sweep_sessions<S: AuthStore + ?Sized>(
+    auth_store: &S,
+    stats: &MaintenanceStats,
+    clock: &dyn Clock,
+) -> Result<usize, AuthError> {
+    let purged = auth_store.purge_expired_sessions().await?;
+    stats.record_sweep(clock.now(), purged);
+    debug!(purged, "Session sweep finished");
+    Ok(purged)
+}
+
+/// Starts a background task sweeping expired sessions every `interval`.
+///
+/// Must be called from within a tokio runtime.
+pub fn spawn_session_sweeper<S: AuthStore + ?Sized + 'static>(
+    auth_store: Arc<S>,
+    stats: Arc<MaintenanceStats>,
+    clock: Arc<dyn Clock>,
+    interval: std::time::Duration,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // the first tick completes immediately; skip it so startup isn't slowed down
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            if let Err(e) = sweep_sessions(auth_store.as_ref(), &stats, clock.as_ref()).await {
+                error!("Session sweep failed: {e}");
+            }
+        }
+    });
+}
+
+/// Disables every active user whose [User::last_active_at] is more than `max_inactive` ago,
+/// auditing each, and returns them as disabled.
+///
+/// Users who never signed in have nothing to count from and are left alone, as are admins
+/// when `exempt_admins` is set. A user that fails to be disabled is logged and skipped.
+pub async fn disable_inactive_users<S: AuthStore + ?Sized, A: AuditStore + ?Sized>(
+    auth_store: &S,
+    audit_store: &A,
+    clock: &dyn Clock,
+    max_inactive: Duration,
+    exempt_admins: bool,
+) -> Result<Vec<User>, AuthError> {
+    let now = clock.now();
+    let cutoff = now - max_inactive;
+    let mut disabled = Vec::new();
+
+    for user in auth_store.list_users().await? {
+        if !user.is_active() || (exempt_admins && user.role == Role::Admin) {
+            continue;
+        }
+        let Some(last_active_at) = user.last_active_at() else {
+            continue;
+        };
+        if last_active_at >= cutoff {
+            continue;
+        }
+
+        match auth_store.set_status(&user.id, UserStatus::Disabled).await {
+            Ok(user) => {
+                info!(user_id = %user.id.0, %last_active_at, "Disabled inactive user");
+                let event = AuditEvent {
+                    at: now,
+                    ..AuditEvent::new(
+                        None,
+                        AuditKind::UserDisabledInactive,
+                        user.username.0.clone(),
+                    )
+                };
+                if let Err(e) = audit_store.record(event).await {
+                    warn!(user_id = %user.id.0, "Failed to record audit event: {e}");
+                }
+                disabled.push(user);
+            }
+            Err(e) => error!(user_id = %user.id.0, "Failed to disable inactive user: {e}"),
+        }
+    }
+    debug!(disabled = disabled.len(), "Inactivity sweep finished");
+    Ok(disabled)
+}
+
+/// Starts a background task running [disable_inactive_users] every `interval`.
+///
+/// Must be called from within a tokio runtime.
+pub fn spawn_inactivity_sweeper<
+    S: AuthStore + ?Sized + 'static,
+    A: AuditStore + ?Sized + 'static,
+>(
+    auth_store: Arc<S>,
+    audit_store: Arc<A>,
+    clock: Arc<dyn Clock>,
+    max_inactive: Duration,
+    exempt_admins: bool,
+    interval: std::time::Duration,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = disable_inactive_users(
+                auth_store.as_ref(),
+                audit_store.as_ref(),
+                clock.as_ref(),
+                max_inactive,
+                exempt_admins,
+            )
+            .await
+            {
+                error!("Inactivity sweep failed: {e}");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SESSION_DURATION;
+    use crate::storage::clock::TestClock;
+    use crate::storage::redb_auditstore::RedbAuditStore;
+    use crate::storage::redb_authstore::RedbAuthStore;
+    use crate::types::{AuditQuery, PasswordHash, SessionIp, Username};
+    use std::net::IpAddr;
+
+    #[tokio::test]
+    async fn sweep_stats_reflect_purged_sessions() {
+        let clock = Arc::new(TestClock::new());
+        let stats = Arc::new(MaintenanceStats::new());
+        let store = RedbAuthStore::in_memory(10)
+            .unwrap()
+            .with_clock(clock.clone())
+            .with_maintenance_stats(stats.clone());
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+
+        for _ in 0..3 {
+            store
+                .issue_session(&user.id, ip.clone(), None)
+                .await
+                .unwrap();
+        }
+        let lazily_expired = store
+            .issue_session(&user.id, ip.clone(), None)
+            .await
+            .unwrap();
+        assert_eq!(stats.report(), MaintenanceReport::default());
+
+        clock.advance(SESSION_DURATION + time::Duration::seconds(1));
+        let fresh = store
+            .issue_session(&user.id, ip.clone(), None)
+            .await
+            .unwrap();
+        assert!(store.fetch_session(&fresh.id).await.is_ok());
+
+        // issue_session cleaned up all four expired sessions of the user on its own
+        assert!(store.fetch_session(&lazily_expired.id).await.is_err());
+        assert_eq!(stats.report().lazily_purged_sessions, 4);
+
+        // the sweep purges what the lazy path hasn't seen yet
+        clock.advance(SESSION_DURATION + time::Duration::seconds(1));
+        let purged = sweep_sessions(&store, &stats, clock.as_ref())
+            .await
+            .unwrap();
+        assert_eq!(purged, 1);
+
+        let report = stats.report();
+        assert_eq!(report.total_sweeps, 1);
+        assert_eq!(report.swept_sessions, 1);
+        assert_eq!(report.sessions_purged, 5);
+        assert_eq!(
+            report.last_sweep_at.map(|at| at.unix_timestamp()),
+            Some(clock.now().unix_timestamp())
+        );
+
+        // nothing left to purge
+        assert_eq!(
+            sweep_sessions(&store, &stats, clock.as_ref())
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(stats.report().total_sweeps, 2);
+    }
+
+    #[tokio::test]
+    async fn inactive_users_are_disabled_and_audited() {
+        let clock = Arc::new(TestClock::new());
+        let store = RedbAuthStore::in_memory(10)
+            .unwrap()
+            .with_clock(clock.clone());
+        let audit = RedbAuditStore::in_memory().unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let max_inactive = time::Duration::days(90);
+
+        let mut users = Vec::new();
+        for (name, role) in [
+            ("stale", Role::User),
+            ("recent", Role::User),
+            ("never", Role::User),
+            ("admin", Role::Admin),
+        ] {
+            let user = store
+                .create_user(
+                    &Username(name.to_string()),
+                    PasswordHash::try_from("password").unwrap(),
+                    role,
+                    UserStatus::Active,
+                )
+                .await
+                .unwrap();
+            users.push(user);
+        }
+        let [stale, recent, never, admin] = users.try_into().unwrap();
+        // backdates the last logins of stale and admin by 100 days
+        store.record_login(&stale.id, ip.clone()).await.unwrap();
+        store.record_login(&admin.id, ip.clone()).await.unwrap();
+        clock.advance(time::Duration::days(100));
+        store.record_login(&recent.id, ip).await.unwrap();
+
+        let disabled = disable_inactive_users(&store, &audit, clock.as_ref(), max_inactive, true)
+            .await
+            .unwrap();
+        assert_eq!(disabled.len(), 1);
+        assert_eq!(disabled[0].id, stale.id);
+        for (user, active) in [
+            (&stale, false),
+            (&recent, true),
+            (&never, true),
+            (&admin, true),
+        ] {
+            let user = store.get_user_by_id(&user.id).await.unwrap();
+            assert_eq!(user.is_active(), active, "{}", user.username.0);
+        }
+
+        let events = audit
+            .query(AuditQuery {
+                kinds: vec![AuditKind::UserDisabledInactive],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].detail, "stale");
+        assert_eq!(events[0].actor, None);
+
+        // re-enabled users get a fresh period before they're disabled again
+        store
+            .set_status(&stale.id, UserStatus::Active)
+            .await
+            .unwrap();
+        let disabled = disable_inactive_users(&store, &audit, clock.as_ref(), max_inactive, true)
+            .await
+            .unwrap();
+        assert!(disabled.is_empty());
+
+        // unless admins aren't exempt
+        let disabled = disable_inactive_users(&store, &audit, clock.as_ref(), max_inactive, false)
+            .await
+            .unwrap();
+        assert_eq!(disabled.len(), 1);
+        assert_eq!(disabled[0].id, admin.id);
+    }
+}
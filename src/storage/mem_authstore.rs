@@ -1,17 +1,37 @@
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+
 use papaya::HashMap;
 use time::OffsetDateTime;
 use tracing::{debug, trace};
 
+use super::clock::{Clock, SystemClock};
+use super::maintenance::MaintenanceStats;
 use super::{AuthError, AuthStore};
 use crate::config::SESSION_DURATION;
-use crate::types::{PasswordHash, Role, Session, SessionId, SessionIp, User, UserId, Username};
+use crate::types::{
+    InviteCode, PasskeyCredential, PasswordHash, Role, Session, SessionCriteria, SessionId,
+    SessionIp, SessionPage, User, UserAgentHash, UserId, UserStatus, Username,
+};
 
 /// An in-memory auth store designed for non-persistent usage.
 #[derive(Clone)]
 pub struct MemoryAuthStore {
     pub(self) users: HashMap<UserId, User>,
     pub(self) sessions: HashMap<SessionId, Session>,
+    pub(self) invites: HashMap<String, InviteCode>,
+    pub(self) passkeys: HashMap<String, PasskeyCredential>,
+    /// Replaced password hashes per user, newest first
+    pub(self) password_history: HashMap<UserId, Vec<PasswordHash>>,
+    pub(self) clock: Arc<dyn Clock>,
+    pub(self) stats: Arc<MaintenanceStats>,
     pub(self) max_sessions_per_user: usize,
+    pub(self) session_token_bytes: usize,
+    /// Recent passwords, the current one included, a new password may not match
+    pub(self) password_history_len: usize,
+    /// Compare usernames by `Username::lookup_key`, regardless of case
+    pub(self) case_insensitive_usernames: bool,
 }
 
 impl MemoryAuthStore {
@@ -19,13 +39,118 @@ impl MemoryAuthStore {
         MemoryAuthStore {
             users: HashMap::new(),
             sessions: HashMap::new(),
+            invites: HashMap::new(),
+            passkeys: HashMap::new(),
+            password_history: HashMap::new(),
+            clock: Arc::new(SystemClock),
+            stats: Arc::new(MaintenanceStats::new()),
             max_sessions_per_user,
+            session_token_bytes: SessionId::DEFAULT_BYTES,
+            password_history_len: 0,
+            case_insensitive_usernames: false,
         }
     }
 
+    /// Sets the clock used for session and invite expiry checks
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Sets how many random bytes new session tokens are made of
+    pub fn with_session_token_bytes(mut self, bytes: usize) -> Self {
+        self.session_token_bytes = bytes;
+        self
+    }
+
+    /// Sets how many recent passwords, the current one included, a new password may not
+    /// match; 0 allows any
+    pub fn with_password_history(mut self, len: usize) -> Self {
+        self.password_history_len = len;
+        self
+    }
+
+    /// Looks usernames up regardless of case, so names differing only in case collide
+    pub fn with_case_insensitive_usernames(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive_usernames = case_insensitive;
+        self
+    }
+
+    /// Sets the counters that expired sessions cleaned up along the way are recorded in
+    pub fn with_maintenance_stats(mut self, stats: Arc<MaintenanceStats>) -> Self {
+        self.stats = stats;
+        self
+    }
+
     pub fn new_unbounded() -> Self {
         Self::new(usize::MAX)
     }
+
+    /// Whether `a` and `b` name the same account
+    fn same_username(&self, a: &Username, b: &Username) -> bool {
+        a.lookup_key(self.case_insensitive_usernames)
+            == b.lookup_key(self.case_insensitive_usernames)
+    }
+
+    /// Backs `issue_session` and `issue_impersonation_session`
+    async fn start_session(
+        &self,
+        id: &UserId,
+        ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
+        impersonator: Option<UserId>,
+    ) -> Result<Session, AuthError> {
+        debug!(user_id = %id.0, ip = %ip.0, "Issuing new session");
+        let now = self.clock.now();
+        let expires = now + SESSION_DURATION;
+
+        if let Some(user) = self.users.pin().get(id)
+            && !user.is_active()
+        {
+            debug!(user_id = %id.0, "Refusing to issue a session to a disabled user");
+            return Err(AuthError::AccountDisabled);
+        }
+
+        let session_map = self.sessions.pin();
+
+        if self.max_sessions_per_user != usize::MAX {
+            let active_sessions = session_map
+                .values()
+                .filter(|session| session.user_id == *id && session.expires_at > now)
+                .count();
+
+            if active_sessions >= self.max_sessions_per_user {
+                debug!(
+                    user_id = %id.0,
+                    max = self.max_sessions_per_user,
+                    "Session limit reached"
+                );
+                return Err(AuthError::SessionLimitReached);
+            }
+        }
+
+        let session = Session {
+            id: SessionId::new_with_len(self.session_token_bytes),
+            user_id: *id,
+            ip,
+            created_at: now,
+            expires_at: expires,
+            last_seen_at: now,
+            user_agent,
+            last_auth_at: None,
+            impersonator,
+            data: BTreeMap::new(),
+        };
+
+        session_map.insert(session.id.clone(), session.clone());
+        debug!(
+            user_id = %id.0,
+            token_len = session.id.0.len(),
+            expires_at = %expires,
+            "Session created successfully"
+        );
+        Ok(session)
+    }
 }
 
 impl Default for MemoryAuthStore {
@@ -44,10 +169,14 @@ impl AuthStore for MemoryAuthStore {
         username: &Username,
         password_hash: PasswordHash,
         role: Role,
+        status: UserStatus,
     ) -> Result<User, AuthError> {
         let user_map = self.users.pin();
 
-        if user_map.values().any(|u| &u.username == username) {
+        if user_map
+            .values()
+            .any(|u| self.same_username(&u.username, username))
+        {
             debug!("User creation failed: username already exists");
             Err(AuthError::UserExists)
         } else {
@@ -56,6 +185,12 @@ impl AuthStore for MemoryAuthStore {
                 role,
                 username: username.clone(),
                 password_hash,
+                status,
+                last_login_at: None,
+                last_login_ip: None,
+                failed_logins: 0,
+                locked_until: None,
+                enabled_at: None,
             };
             trace!(user_id = %user.id.0, "Creating new user");
             user_map.insert(user.id, user.clone());
@@ -66,6 +201,78 @@ impl AuthStore for MemoryAuthStore {
         }
     }
 
+    async fn create_user_or_first_admin(
+        &self,
+        username: &Username,
+        password_hash: PasswordHash,
+        role: Role,
+        status: UserStatus,
+    ) -> Result<User, AuthError> {
+        // NOTE: not atomic with the insert; fine for non-persistent usage
+        let (role, status) = if self.users.pin().is_empty() {
+            (Role::Admin, UserStatus::Active)
+        } else {
+            (role, status)
+        };
+        self.create_user(username, password_hash, role, status)
+            .await
+    }
+
+    async fn create_user_with_invite(
+        &self,
+        username: &Username,
+        password_hash: PasswordHash,
+        code: &str,
+    ) -> Result<User, AuthError> {
+        // NOTE: not atomic across the two maps; fine for non-persistent usage
+        let invite = self
+            .invites
+            .pin()
+            .get(code)
+            .cloned()
+            .filter(|invite| invite.is_usable(self.clock.now()))
+            .ok_or_else(|| {
+                debug!("Invite code invalid, expired or exhausted");
+                AuthError::InvalidInvite
+            })?;
+
+        let user = self
+            .create_user(username, password_hash, invite.role, UserStatus::Active)
+            .await?;
+        self.invites.pin().update(invite.code, |invite| InviteCode {
+            uses: invite.uses + 1,
+            ..invite.clone()
+        });
+        Ok(user)
+    }
+
+    async fn create_invite(
+        &self,
+        role: Role,
+        max_uses: u32,
+        expires_at: Option<OffsetDateTime>,
+    ) -> Result<InviteCode, AuthError> {
+        let invite = InviteCode::new(role, max_uses, expires_at);
+        self.invites
+            .pin()
+            .insert(invite.code.clone(), invite.clone());
+        debug!(?role, max_uses, "Invite code created");
+        Ok(invite)
+    }
+
+    async fn list_invites(&self) -> Result<Vec<InviteCode>, AuthError> {
+        let mut invites: Vec<InviteCode> = self.invites.pin().values().cloned().collect();
+        invites.sort_by_key(|invite| std::cmp::Reverse(invite.created_at));
+        Ok(invites)
+    }
+
+    async fn revoke_invite(&self, code: &str) -> Result<(), AuthError> {
+        match self.invites.pin().remove(code) {
+            Some(_) => Ok(()),
+            None => Err(AuthError::InvalidInvite),
+        }
+    }
+
     async fn get_user_by_id(&self, id: &UserId) -> Result<User, AuthError> {
         debug!(user_id = %id.0, "Looking up user by ID");
         let user_map = self.users.pin();
@@ -83,7 +290,7 @@ impl AuthStore for MemoryAuthStore {
         let user_map = self.users.pin();
         let result = user_map
             .values()
-            .find(|&u| &u.username == username)
+            .find(|&u| self.same_username(&u.username, username))
             .cloned()
             .ok_or(AuthError::NotFound);
 
@@ -95,6 +302,59 @@ impl AuthStore for MemoryAuthStore {
         result
     }
 
+    async fn list_users(&self) -> Result<Vec<User>, AuthError> {
+        let mut users: Vec<User> = self.users.pin().values().cloned().collect();
+        users.sort_by(|a, b| a.username.0.cmp(&b.username.0));
+        Ok(users)
+    }
+
+    async fn set_status(&self, id: &UserId, status: UserStatus) -> Result<User, AuthError> {
+        debug!(user_id = %id.0, ?status, "Updating user status");
+        let now = self.clock.now();
+        let user = self
+            .users
+            .pin()
+            .update(*id, |u| User {
+                status,
+                enabled_at: if status == UserStatus::Active && !u.is_active() {
+                    Some(now)
+                } else {
+                    u.enabled_at
+                },
+                ..u.clone()
+            })
+            .cloned()
+            .ok_or(AuthError::NotFound)?;
+
+        if status == UserStatus::Disabled {
+            self.sessions
+                .pin()
+                .retain(|_, session| session.user_id != *id);
+        }
+        Ok(user)
+    }
+
+    async fn rename_user(&self, id: &UserId, new: &Username) -> Result<User, AuthError> {
+        // NOTE: the check and the update aren't atomic; fine for non-persistent usage
+        let user_map = self.users.pin();
+        if user_map
+            .values()
+            .any(|u| self.same_username(&u.username, new) && u.id != *id)
+        {
+            debug!("User rename failed: username already exists");
+            return Err(AuthError::UserExists);
+        }
+
+        debug!(user_id = %id.0, "Renaming user");
+        user_map
+            .update(*id, |u| User {
+                username: new.clone(),
+                ..u.clone()
+            })
+            .cloned()
+            .ok_or(AuthError::NotFound)
+    }
+
     async fn set_password_hash(
         &self,
         id: &UserId,
@@ -102,16 +362,29 @@ impl AuthStore for MemoryAuthStore {
     ) -> Result<PasswordHash, AuthError> {
         debug!(user_id = %id.0, "Updating user password");
         let user_map = self.users.pin();
+        let old_hash = user_map.get(id).map(|u| u.password_hash.clone());
         let result = user_map
             .update(*id, |u| User {
-                id: *id,
-                username: u.username.clone(),
                 password_hash: new_hash.clone(),
-                role: u.role,
+                ..u.clone()
             })
             .map(|_| new_hash)
             .ok_or(AuthError::NotFound);
 
+        if let (Ok(_), Some(old_hash)) = (&result, old_hash) {
+            // the current password counts towards the length, so keep one fewer
+            let keep = self.password_history_len.saturating_sub(1);
+            let history_map = self.password_history.pin();
+            let mut history = history_map.get(id).cloned().unwrap_or_default();
+            history.insert(0, old_hash);
+            history.truncate(keep);
+            if history.is_empty() {
+                history_map.remove(id);
+            } else {
+                history_map.insert(*id, history);
+            }
+        }
+
         match &result {
             Ok(_) => debug!(user_id = %id.0, "Password updated successfully"),
             Err(_) => debug!(user_id = %id.0, "Password update failed: user not found"),
@@ -120,10 +393,102 @@ impl AuthStore for MemoryAuthStore {
         result
     }
 
+    async fn check_password_reuse(&self, id: &UserId, password: &str) -> Result<(), AuthError> {
+        if self.password_history_len == 0 {
+            return Ok(());
+        }
+        let user = self
+            .users
+            .pin()
+            .get(id)
+            .cloned()
+            .ok_or(AuthError::NotFound)?;
+        let history = self
+            .password_history
+            .pin()
+            .get(id)
+            .cloned()
+            .unwrap_or_default();
+
+        if std::iter::once(&user.password_hash)
+            .chain(&history)
+            .take(self.password_history_len)
+            .any(|hash| hash.verify(password))
+        {
+            debug!(user_id = %id.0, "New password matches a recent one");
+            return Err(AuthError::PasswordReused);
+        }
+        Ok(())
+    }
+
+    async fn record_login(&self, id: &UserId, ip: SessionIp) -> Result<User, AuthError> {
+        debug!(user_id = %id.0, ip = %ip.0, "Recording login");
+        let now = self.clock.now();
+        let user_map = self.users.pin();
+
+        let previous = user_map.get(id).cloned().ok_or(AuthError::NotFound)?;
+        user_map.update(*id, |u| User {
+            last_login_at: Some(now),
+            last_login_ip: Some(ip.clone()),
+            failed_logins: 0,
+            ..u.clone()
+        });
+        Ok(previous)
+    }
+
+    async fn record_failed_login(
+        &self,
+        id: &UserId,
+        max_failed_logins: u32,
+        lock_for: time::Duration,
+    ) -> Result<User, AuthError> {
+        let now = self.clock.now();
+        self.users
+            .pin()
+            .update(*id, |u| {
+                let failed_logins = u.failed_logins + 1;
+                if failed_logins >= max_failed_logins {
+                    User {
+                        failed_logins: 0,
+                        locked_until: Some(now + lock_for),
+                        ..u.clone()
+                    }
+                } else {
+                    User {
+                        failed_logins,
+                        ..u.clone()
+                    }
+                }
+            })
+            .cloned()
+            .ok_or(AuthError::NotFound)
+    }
+
+    async fn clear_lockout(&self, id: &UserId) -> Result<User, AuthError> {
+        debug!(user_id = %id.0, "Clearing user lockout");
+        self.users
+            .pin()
+            .update(*id, |u| User {
+                failed_logins: 0,
+                locked_until: None,
+                ..u.clone()
+            })
+            .cloned()
+            .ok_or(AuthError::NotFound)
+    }
+
     async fn delete_user(&self, id: &UserId) -> Result<(), AuthError> {
         debug!(user_id = %id.0, "Deleting user");
         let user_map = self.users.pin();
         if user_map.remove(id).is_some() {
+            // Clean up all sessions, passkeys and old passwords for this user
+            self.password_history.pin().remove(id);
+            self.passkeys
+                .pin()
+                .retain(|_, credential| credential.user_id != *id);
+            self.sessions
+                .pin()
+                .retain(|_, session| session.user_id != *id);
             debug!(user_id = %id.0, "User deleted successfully");
             Ok(())
         } else {
@@ -132,45 +497,62 @@ impl AuthStore for MemoryAuthStore {
         }
     }
 
-    async fn issue_session(&self, id: &UserId, ip: SessionIp) -> Result<Session, AuthError> {
-        debug!(user_id = %id.0, ip = %ip.0, "Issuing new session");
-        let now = OffsetDateTime::now_utc();
-        let expires = now + SESSION_DURATION;
-
-        let session_map = self.sessions.pin();
+    async fn preview_delete_user(&self, id: &UserId) -> Result<Vec<SessionId>, AuthError> {
+        if !self.users.pin().contains_key(id) {
+            return Err(AuthError::NotFound);
+        }
 
-        if self.max_sessions_per_user != usize::MAX {
-            let active_sessions = session_map
-                .values()
-                .filter(|session| session.user_id == *id && session.expires_at > now)
-                .count();
+        let session_ids: Vec<SessionId> = self
+            .sessions
+            .pin()
+            .values()
+            .filter(|session| session.user_id == *id)
+            .map(|session| session.id.clone())
+            .collect();
+        debug!(user_id = %id.0, sessions = session_ids.len(), "Previewed user deletion");
+        Ok(session_ids)
+    }
 
-            if active_sessions >= self.max_sessions_per_user {
-                debug!(
-                    user_id = %id.0,
-                    max = self.max_sessions_per_user,
-                    "Session limit reached"
-                );
-                return Err(AuthError::SessionLimitReached);
-            }
+    async fn save_passkey(&self, credential: PasskeyCredential) -> Result<(), AuthError> {
+        if !self.users.pin().contains_key(&credential.user_id) {
+            return Err(AuthError::NotFound);
         }
+        debug!(user_id = %credential.user_id.0, "Saving passkey");
+        self.passkeys
+            .pin()
+            .insert(credential.credential_id.clone(), credential);
+        Ok(())
+    }
 
-        let session = Session {
-            id: SessionId::new(),
-            user_id: *id,
-            ip,
-            created_at: now,
-            expires_at: expires,
-        };
+    async fn list_passkeys(&self, user_id: &UserId) -> Result<Vec<PasskeyCredential>, AuthError> {
+        Ok(self
+            .passkeys
+            .pin()
+            .values()
+            .filter(|credential| credential.user_id == *user_id)
+            .cloned()
+            .collect())
+    }
 
-        session_map.insert(session.id.clone(), session.clone());
-        debug!(
-            user_id = %id.0,
-            token_len = session.id.0.len(),
-            expires_at = %expires,
-            "Session created successfully"
-        );
-        Ok(session)
+    async fn issue_session(
+        &self,
+        id: &UserId,
+        ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
+    ) -> Result<Session, AuthError> {
+        self.start_session(id, ip, user_agent, None).await
+    }
+
+    async fn issue_impersonation_session(
+        &self,
+        admin: &UserId,
+        target: &UserId,
+        ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
+    ) -> Result<Session, AuthError> {
+        debug!(admin_id = %admin.0, user_id = %target.0, "Issuing impersonation session");
+        self.start_session(target, ip, user_agent, Some(*admin))
+            .await
     }
 
     async fn fetch_session(&self, token: &SessionId) -> Result<Session, AuthError> {
@@ -178,8 +560,17 @@ impl AuthStore for MemoryAuthStore {
         let session_map = self.sessions.pin();
 
         if let Some(session) = session_map.get(token) {
-            let now = OffsetDateTime::now_utc();
+            let now = self.clock.now();
             if session.expires_at > now {
+                let disabled = self
+                    .users
+                    .pin()
+                    .get(&session.user_id)
+                    .is_some_and(|user| !user.is_active());
+                if disabled {
+                    debug!(user_id = %session.user_id.0, "Session belongs to a disabled user");
+                    return Err(AuthError::AccountDisabled);
+                }
                 debug!(
                     user_id = %session.user_id.0,
                     expires_in_secs = (session.expires_at - now).whole_seconds(),
@@ -191,7 +582,9 @@ impl AuthStore for MemoryAuthStore {
                     user_id = %session.user_id.0,
                     "Session expired, removing"
                 );
-                session_map.remove(token);
+                if session_map.remove(token).is_some() {
+                    self.stats.record_lazy_purge(1);
+                }
                 Err(AuthError::InvalidSession)
             }
         } else {
@@ -205,7 +598,7 @@ impl AuthStore for MemoryAuthStore {
         let session_map = self.sessions.pin();
 
         if let Some(mut session) = session_map.get(token).cloned() {
-            let now = OffsetDateTime::now_utc();
+            let now = self.clock.now();
             if session.expires_at > now {
                 let new_expires = now + SESSION_DURATION;
                 debug!(
@@ -215,11 +608,14 @@ impl AuthStore for MemoryAuthStore {
                     "Extending session"
                 );
                 session.expires_at = new_expires;
+                session.last_seen_at = now;
                 session_map.insert(token.clone(), session.clone());
                 Ok(session)
             } else {
                 debug!(user_id = %session.user_id.0, "Cannot extend expired session, removing");
-                session_map.remove(token);
+                if session_map.remove(token).is_some() {
+                    self.stats.record_lazy_purge(1);
+                }
                 Err(AuthError::InvalidSession)
             }
         } else {
@@ -228,6 +624,54 @@ impl AuthStore for MemoryAuthStore {
         }
     }
 
+    async fn mark_reauthenticated(&self, token: &SessionId) -> Result<Session, AuthError> {
+        let session_map = self.sessions.pin();
+        let now = self.clock.now();
+
+        match session_map.get(token).cloned() {
+            Some(mut session) if session.expires_at > now => {
+                session.last_auth_at = Some(now);
+                session_map.insert(token.clone(), session.clone());
+                debug!(user_id = %session.user_id.0, "Session re-authenticated");
+                Ok(session)
+            }
+            _ => Err(AuthError::InvalidSession),
+        }
+    }
+
+    async fn set_session_data(
+        &self,
+        token: &SessionId,
+        key: &str,
+        value: Option<String>,
+    ) -> Result<Session, AuthError> {
+        let session_map = self.sessions.pin();
+        let now = self.clock.now();
+
+        match session_map.get(token).cloned() {
+            Some(mut session) if session.expires_at > now => {
+                session.set_data(key, value)?;
+                session_map.insert(token.clone(), session.clone());
+                trace!(user_id = %session.user_id.0, key, "Session data set");
+                Ok(session)
+            }
+            _ => Err(AuthError::InvalidSession),
+        }
+    }
+
+    async fn get_session_data(
+        &self,
+        token: &SessionId,
+        key: &str,
+    ) -> Result<Option<String>, AuthError> {
+        match self.sessions.pin().get(token) {
+            Some(session) if session.expires_at > self.clock.now() => {
+                Ok(session.data.get(key).cloned())
+            }
+            _ => Err(AuthError::InvalidSession),
+        }
+    }
+
     async fn revoke_session(&self, token: &SessionId) -> Result<(), AuthError> {
         debug!(token_len = token.0.len(), "Revoking session");
         let session_map = self.sessions.pin();
@@ -239,12 +683,131 @@ impl AuthStore for MemoryAuthStore {
             Err(AuthError::InvalidSession)
         }
     }
+
+    async fn revoke_all_sessions(&self, id: &UserId) -> Result<usize, AuthError> {
+        if !self.users.pin().contains_key(id) {
+            debug!(user_id = %id.0, "User not found for session revocation");
+            return Err(AuthError::NotFound);
+        }
+
+        let session_map = self.sessions.pin();
+        let session_ids: Vec<SessionId> = session_map
+            .iter()
+            .filter(|(_, session)| session.user_id == *id)
+            .map(|(session_id, _)| session_id.clone())
+            .collect();
+        let revoked = session_ids
+            .iter()
+            .filter(|session_id| session_map.remove(*session_id).is_some())
+            .count();
+
+        debug!(user_id = %id.0, revoked, "All user sessions revoked");
+        Ok(revoked)
+    }
+
+    async fn purge_expired_sessions(&self) -> Result<usize, AuthError> {
+        let now = self.clock.now();
+        let session_map = self.sessions.pin();
+
+        let expired: Vec<SessionId> = session_map
+            .iter()
+            .filter(|(_, session)| session.expires_at <= now)
+            .map(|(id, _)| id.clone())
+            .collect();
+        let purged = expired
+            .iter()
+            .filter(|id| session_map.remove(*id).is_some())
+            .count();
+
+        debug!(purged, "Purged expired sessions");
+        Ok(purged)
+    }
+
+    async fn count_user_sessions(&self, id: &UserId) -> Result<usize, AuthError> {
+        let now = self.clock.now();
+        Ok(self
+            .sessions
+            .pin()
+            .values()
+            .filter(|session| session.user_id == *id && session.expires_at > now)
+            .count())
+    }
+
+    async fn list_user_sessions(&self, id: &UserId) -> Result<Vec<Session>, AuthError> {
+        let now = self.clock.now();
+        let mut sessions: Vec<Session> = self
+            .sessions
+            .pin()
+            .values()
+            .filter(|session| session.user_id == *id && session.expires_at > now)
+            .cloned()
+            .collect();
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.last_seen_at));
+        Ok(sessions)
+    }
+
+    async fn list_user_sessions_page(
+        &self,
+        id: &UserId,
+        after: Option<&SessionId>,
+        limit: usize,
+    ) -> Result<SessionPage, AuthError> {
+        let now = self.clock.now();
+        let mut sessions: Vec<Session> = self
+            .sessions
+            .pin()
+            .values()
+            .filter(|session| session.user_id == *id && session.expires_at > now)
+            .filter(|session| after.is_none_or(|after| session.id.0 > after.0))
+            .cloned()
+            .collect();
+        sessions.sort_by(|a, b| a.id.0.cmp(&b.id.0));
+
+        let next_cursor = if sessions.len() > limit {
+            sessions.truncate(limit);
+            sessions.last().map(|session| session.id.clone())
+        } else {
+            None
+        };
+        Ok(SessionPage {
+            sessions,
+            next_cursor,
+        })
+    }
+
+    async fn active_sessions_by_ip(&self) -> Result<BTreeMap<IpAddr, usize>, AuthError> {
+        let now = self.clock.now();
+        let mut counts = BTreeMap::new();
+        for session in self.sessions.pin().values() {
+            if session.expires_at > now {
+                *counts.entry(session.ip.0).or_insert(0) += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn revoke_sessions_where(&self, criteria: &SessionCriteria) -> Result<usize, AuthError> {
+        let session_map = self.sessions.pin();
+
+        let matching: Vec<SessionId> = session_map
+            .iter()
+            .filter(|(_, session)| criteria.matches(session))
+            .map(|(id, _)| id.clone())
+            .collect();
+        let revoked = matching
+            .iter()
+            .filter(|id| session_map.remove(*id).is_some())
+            .count();
+
+        debug!(revoked, "Revoked sessions by criteria");
+        Ok(revoked)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::net::IpAddr;
+    use crate::storage::clock::TestClock;
 
     #[tokio::test]
     async fn enforces_session_limit() {
@@ -253,11 +816,11 @@ mod tests {
         let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
 
         let first = store
-            .issue_session(&user_id, ip.clone())
+            .issue_session(&user_id, ip.clone(), None)
             .await
             .expect("first session should succeed");
 
-        match store.issue_session(&user_id, ip.clone()).await {
+        match store.issue_session(&user_id, ip.clone(), None).await {
             Err(AuthError::SessionLimitReached) => {}
             other => panic!("expected session limit error, got {other:?}"),
         }
@@ -268,8 +831,96 @@ mod tests {
             .expect("revocation should succeed");
 
         store
-            .issue_session(&user_id, ip)
+            .issue_session(&user_id, ip, None)
             .await
             .expect("session after revocation should succeed");
     }
+
+    #[tokio::test]
+    async fn expired_session_is_rejected_and_removed() {
+        let clock = Arc::new(TestClock::new());
+        let store = MemoryAuthStore::new(1).with_clock(clock.clone());
+        let user_id = UserId::new();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let session = store
+            .issue_session(&user_id, ip.clone(), None)
+            .await
+            .unwrap();
+
+        clock.advance(SESSION_DURATION);
+        assert!(matches!(
+            store.fetch_session(&session.id).await,
+            Err(AuthError::InvalidSession)
+        ));
+        assert!(store.sessions.pin().get(&session.id).is_none());
+
+        store
+            .issue_session(&user_id, ip, None)
+            .await
+            .expect("expired session should not count toward the limit");
+    }
+
+    #[tokio::test]
+    async fn session_data_is_kept_until_the_session_expires() {
+        let clock = Arc::new(TestClock::new());
+        let store = MemoryAuthStore::new(1).with_clock(clock.clone());
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let session = store.issue_session(&UserId::new(), ip, None).await.unwrap();
+
+        store
+            .set_session_data(&session.id, "project", Some("lunch-box".to_string()))
+            .await
+            .unwrap();
+        let extended = store.extend_session(&session.id).await.unwrap();
+        assert_eq!(extended.data["project"], "lunch-box");
+        assert!(matches!(
+            store
+                .set_session_data(
+                    &session.id,
+                    "notes",
+                    Some("x".repeat(Session::MAX_DATA_BYTES))
+                )
+                .await,
+            Err(AuthError::SessionDataTooLarge)
+        ));
+
+        clock.advance(SESSION_DURATION);
+        assert!(matches!(
+            store.get_session_data(&session.id, "project").await,
+            Err(AuthError::InvalidSession)
+        ));
+    }
+
+    #[tokio::test]
+    async fn sessions_are_revoked_only_when_every_criterion_matches() {
+        let clock = Arc::new(TestClock::new());
+        let store = MemoryAuthStore::default().with_clock(clock.clone());
+        let user_id = UserId::new();
+        let home = IpAddr::from([192, 168, 1, 10]);
+        let attacker = IpAddr::from([203, 0, 113, 66]);
+
+        let old_attacker = store
+            .issue_session(&user_id, SessionIp(attacker), None)
+            .await
+            .unwrap();
+        let old_home = store
+            .issue_session(&user_id, SessionIp(home), None)
+            .await
+            .unwrap();
+        clock.advance(time::Duration::minutes(10));
+        let cutoff = clock.now();
+        let new_attacker = store
+            .issue_session(&user_id, SessionIp(attacker), None)
+            .await
+            .unwrap();
+
+        let criteria = SessionCriteria {
+            before: Some(cutoff),
+            from_ip: Some(attacker),
+        };
+        assert_eq!(store.revoke_sessions_where(&criteria).await.unwrap(), 1);
+        assert!(store.fetch_session(&old_attacker.id).await.is_err());
+        assert!(store.fetch_session(&old_home.id).await.is_ok());
+        assert!(store.fetch_session(&new_attacker.id).await.is_ok());
+    }
 }
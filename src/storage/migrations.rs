@@ -0,0 +1,203 @@
+//! Schema versions of the redb stores, and the migrations bringing older databases up to date.
+//!
+//! Each store keeps its version under its own key of a metadata table, so stores sharing a
+//! database (auth and sessions) are versioned apart. A brand-new database starts out at the
+//! latest version; one from before versioning starts at 0. A store's `migrate` applies the
+//! migrations newer than its version, once the codec is set.
+
+use redb::{Database, ReadableTable, TableDefinition, TableHandle, WriteTransaction};
+use tracing::{debug, info};
+
+use super::codec::ValueCodec;
+
+/// Schema name (e.g. "projects") -> version
+pub(crate) const METADATA_TABLE: TableDefinition<&str, u64> = TableDefinition::new("metadata");
+
+/// One step of a store's schema, from `version - 1` to `version`
+pub struct Migration<E> {
+    pub version: u64,
+    /// Logged when the migration is applied
+    pub description: &'static str,
+    pub apply: fn(&WriteTransaction, &ValueCodec) -> Result<(), E>,
+}
+
+/// The version a store with these migrations is at once all of them ran
+pub fn latest_version<E>(migrations: &[Migration<E>]) -> u64 {
+    migrations.last().map_or(0, |migration| migration.version)
+}
+
+/// Records a version for `schema` unless it has one: the latest for a new database, 0 for
+/// one predating versioning. Whether the database is new is told by the store's
+/// `main_table` existing, so this must run before the store creates its tables.
+pub(crate) fn init_schema_version<E>(
+    txn: &WriteTransaction,
+    schema: &str,
+    main_table: &str,
+    latest: u64,
+) -> Result<(), E>
+where
+    E: From<redb::TableError> + From<redb::StorageError>,
+{
+    let existing = txn.list_tables()?.any(|table| table.name() == main_table);
+    let mut metadata = txn.open_table(METADATA_TABLE)?;
+    if metadata.get(schema)?.is_none() {
+        let version = if existing { 0 } else { latest };
+        metadata.insert(schema, version)?;
+        debug!(schema, version, "Recorded schema version");
+    }
+    Ok(())
+}
+
+/// The recorded version of `schema`, 0 if there is none
+#[cfg(test)]
+pub(crate) fn schema_version<E>(db: &Database, schema: &str) -> Result<u64, E>
+where
+    E: From<redb::TransactionError> + From<redb::TableError> + From<redb::StorageError>,
+{
+    let txn = db.begin_write()?;
+    let version = txn
+        .open_table(METADATA_TABLE)?
+        .get(schema)?
+        .map_or(0, |version| version.value());
+    txn.abort()?;
+    Ok(version)
+}
+
+/// Applies the migrations of `schema` newer than its recorded version, in order and in one
+/// transaction: either all of them stick or none. Returns how many were applied.
+pub(crate) fn run_migrations<E>(
+    db: &Database,
+    schema: &str,
+    codec: &ValueCodec,
+    migrations: &[Migration<E>],
+) -> Result<usize, E>
+where
+    E: From<redb::TransactionError>
+        + From<redb::TableError>
+        + From<redb::StorageError>
+        + From<redb::CommitError>,
+{
+    let txn = db.begin_write()?;
+    let current = txn
+        .open_table(METADATA_TABLE)?
+        .get(schema)?
+        .map_or(0, |version| version.value());
+
+    let pending: Vec<_> = migrations
+        .iter()
+        .filter(|migration| migration.version > current)
+        .collect();
+    let Some(last) = pending.last() else {
+        txn.abort()?;
+        debug!(schema, version = current, "Schema is up to date");
+        return Ok(0);
+    };
+
+    for migration in &pending {
+        (migration.apply)(&txn, codec)?;
+        info!(
+            schema,
+            version = migration.version,
+            "Applied migration: {}",
+            migration.description
+        );
+    }
+    txn.open_table(METADATA_TABLE)?
+        .insert(schema, last.version)?;
+    txn.commit()?;
+    Ok(pending.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::ProjectError;
+    use redb::ReadableDatabase;
+    use redb::backends::InMemoryBackend;
+
+    const NUMBERS: TableDefinition<&str, u64> = TableDefinition::new("numbers");
+
+    fn double(txn: &WriteTransaction, _: &ValueCodec) -> Result<(), ProjectError> {
+        let mut numbers = txn.open_table(NUMBERS)?;
+        let value = numbers.get("n")?.map_or(0, |n| n.value());
+        numbers.insert("n", value * 2)?;
+        Ok(())
+    }
+
+    fn add_one(txn: &WriteTransaction, _: &ValueCodec) -> Result<(), ProjectError> {
+        let mut numbers = txn.open_table(NUMBERS)?;
+        let value = numbers.get("n")?.map_or(0, |n| n.value());
+        numbers.insert("n", value + 1)?;
+        Ok(())
+    }
+
+    const MIGRATIONS: &[Migration<ProjectError>] = &[
+        Migration {
+            version: 1,
+            description: "double",
+            apply: double,
+        },
+        Migration {
+            version: 2,
+            description: "add one",
+            apply: add_one,
+        },
+    ];
+
+    fn database_with(n: u64) -> Database {
+        let db = Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .unwrap();
+        let txn = db.begin_write().unwrap();
+        txn.open_table(NUMBERS).unwrap().insert("n", n).unwrap();
+        txn.commit().unwrap();
+        db
+    }
+
+    fn init(db: &Database) {
+        let txn = db.begin_write().unwrap();
+        init_schema_version::<ProjectError>(&txn, "numbers", "numbers", 2).unwrap();
+        txn.commit().unwrap();
+    }
+
+    #[test]
+    fn unversioned_databases_get_every_migration_in_order() {
+        let db = database_with(5);
+        init(&db);
+        assert_eq!(schema_version::<ProjectError>(&db, "numbers").unwrap(), 0);
+
+        let codec = ValueCodec::plaintext();
+        assert_eq!(
+            run_migrations(&db, "numbers", &codec, MIGRATIONS).unwrap(),
+            2
+        );
+        assert_eq!(schema_version::<ProjectError>(&db, "numbers").unwrap(), 2);
+
+        // (5 * 2) + 1, not (5 + 1) * 2
+        let txn = db.begin_read().unwrap();
+        let n = txn.open_table(NUMBERS).unwrap().get("n").unwrap().unwrap();
+        assert_eq!(n.value(), 11);
+
+        // nothing left to do the second time
+        assert_eq!(
+            run_migrations(&db, "numbers", &codec, MIGRATIONS).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn new_databases_start_out_current() {
+        let db = Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .unwrap();
+        init(&db);
+        assert_eq!(schema_version::<ProjectError>(&db, "numbers").unwrap(), 2);
+        assert_eq!(latest_version(MIGRATIONS), 2);
+
+        let codec = ValueCodec::plaintext();
+        assert_eq!(
+            run_migrations(&db, "numbers", &codec, MIGRATIONS).unwrap(),
+            0
+        );
+    }
+}
@@ -0,0 +1,412 @@
+//! A scripted [AuthStore] for tests.
+//!
+//! [MockAuthStore] behaves like a [MemoryAuthStore] it wraps, except for the calls it was told
+//! to fail, which makes the error branches of code generic over `AuthStore` reachable without
+//! corrupting a database.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+use time::OffsetDateTime;
+
+use super::mem_authstore::MemoryAuthStore;
+use super::{AuthError, AuthStore};
+use crate::types::{
+    InviteCode, PasskeyCredential, PasswordHash, Role, Session, SessionCriteria, SessionId,
+    SessionIp, SessionPage, User, UserAgentHash, UserId, UserStatus, Username,
+};
+
+/// The `AuthStore` methods failures can be scripted for
+const METHODS: &[&str] = &[
+    "create_user",
+    "create_user_or_first_admin",
+    "create_user_with_invite",
+    "create_invite",
+    "list_invites",
+    "revoke_invite",
+    "get_user_by_id",
+    "get_user_by_username",
+    "list_users",
+    "set_status",
+    "rename_user",
+    "set_password_hash",
+    "check_password_reuse",
+    "record_login",
+    "record_failed_login",
+    "clear_lockout",
+    "delete_user",
+    "preview_delete_user",
+    "save_passkey",
+    "list_passkeys",
+    "issue_session",
+    "issue_impersonation_session",
+    "fetch_session",
+    "extend_session",
+    "mark_reauthenticated",
+    "set_session_data",
+    "get_session_data",
+    "revoke_session",
+    "revoke_all_sessions",
+    "purge_expired_sessions",
+    "count_user_sessions",
+    "list_user_sessions",
+    "list_user_sessions_page",
+    "active_sessions_by_ip",
+    "revoke_sessions_where",
+];
+
+/// An [AuthStore] answering from a [MemoryAuthStore] unless a failure was scripted.
+///
+/// # Example
+/// ```ignore
+/// let store = MockAuthStore::default();
+/// store.fail_next("fetch_session", AuthError::Internal("disk gone".to_string()));
+/// assert!(load_current_user(&store, &session_id, None).await.is_none());
+/// ```
+#[derive(Default)]
+pub struct MockAuthStore {
+    inner: MemoryAuthStore,
+    failures: Mutex<HashMap<&'static str, VecDeque<AuthError>>>,
+    calls: Mutex<HashMap<&'static str, usize>>,
+}
+
+impl MockAuthStore {
+    /// Wraps `inner`, e.g. a store with a test clock or pre-created users
+    pub fn new(inner: MemoryAuthStore) -> Self {
+        Self {
+            inner,
+            failures: Mutex::default(),
+            calls: Mutex::default(),
+        }
+    }
+
+    /// The wrapped store, for setting up data without going through the script
+    pub fn inner(&self) -> &MemoryAuthStore {
+        &self.inner
+    }
+
+    /// Makes the next call of `method` fail with `error`; queued errors are returned in order
+    ///
+    /// # Panics
+    /// If `method` isn't an `AuthStore` method name.
+    pub fn fail_next(&self, method: &'static str, error: AuthError) {
+        assert!(
+            METHODS.contains(&method),
+            "no AuthStore method named {method}"
+        );
+        self.failures
+            .lock()
+            .unwrap()
+            .entry(method)
+            .or_default()
+            .push_back(error);
+    }
+
+    /// How often `method` was called, including failed calls
+    pub fn calls(&self, method: &'static str) -> usize {
+        self.calls.lock().unwrap().get(method).copied().unwrap_or(0)
+    }
+
+    /// Counts the call and pops the next scripted failure for `method`, if any
+    fn scripted(&self, method: &'static str) -> Result<(), AuthError> {
+        *self.calls.lock().unwrap().entry(method).or_default() += 1;
+        match self
+            .failures
+            .lock()
+            .unwrap()
+            .get_mut(method)
+            .and_then(VecDeque::pop_front)
+        {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+impl AuthStore for MockAuthStore {
+    fn max_sessions_per_user(&self) -> usize {
+        self.inner.max_sessions_per_user()
+    }
+
+    async fn create_user(
+        &self,
+        username: &Username,
+        pass_hash: PasswordHash,
+        role: Role,
+        status: UserStatus,
+    ) -> Result<User, AuthError> {
+        self.scripted("create_user")?;
+        self.inner
+            .create_user(username, pass_hash, role, status)
+            .await
+    }
+
+    async fn create_user_or_first_admin(
+        &self,
+        username: &Username,
+        pass_hash: PasswordHash,
+        role: Role,
+        status: UserStatus,
+    ) -> Result<User, AuthError> {
+        self.scripted("create_user_or_first_admin")?;
+        self.inner
+            .create_user_or_first_admin(username, pass_hash, role, status)
+            .await
+    }
+
+    async fn create_user_with_invite(
+        &self,
+        username: &Username,
+        pass_hash: PasswordHash,
+        code: &str,
+    ) -> Result<User, AuthError> {
+        self.scripted("create_user_with_invite")?;
+        self.inner
+            .create_user_with_invite(username, pass_hash, code)
+            .await
+    }
+
+    async fn create_invite(
+        &self,
+        role: Role,
+        max_uses: u32,
+        expires_at: Option<OffsetDateTime>,
+    ) -> Result<InviteCode, AuthError> {
+        self.scripted("create_invite")?;
+        self.inner.create_invite(role, max_uses, expires_at).await
+    }
+
+    async fn list_invites(&self) -> Result<Vec<InviteCode>, AuthError> {
+        self.scripted("list_invites")?;
+        self.inner.list_invites().await
+    }
+
+    async fn revoke_invite(&self, code: &str) -> Result<(), AuthError> {
+        self.scripted("revoke_invite")?;
+        self.inner.revoke_invite(code).await
+    }
+
+    async fn get_user_by_id(&self, id: &UserId) -> Result<User, AuthError> {
+        self.scripted("get_user_by_id")?;
+        self.inner.get_user_by_id(id).await
+    }
+
+    async fn get_user_by_username(&self, username: &Username) -> Result<User, AuthError> {
+        self.scripted("get_user_by_username")?;
+        self.inner.get_user_by_username(username).await
+    }
+
+    async fn list_users(&self) -> Result<Vec<User>, AuthError> {
+        self.scripted("list_users")?;
+        self.inner.list_users().await
+    }
+
+    async fn set_status(&self, id: &UserId, status: UserStatus) -> Result<User, AuthError> {
+        self.scripted("set_status")?;
+        self.inner.set_status(id, status).await
+    }
+
+    async fn rename_user(&self, id: &UserId, new: &Username) -> Result<User, AuthError> {
+        self.scripted("rename_user")?;
+        self.inner.rename_user(id, new).await
+    }
+
+    async fn set_password_hash(
+        &self,
+        id: &UserId,
+        new_hash: PasswordHash,
+    ) -> Result<PasswordHash, AuthError> {
+        self.scripted("set_password_hash")?;
+        self.inner.set_password_hash(id, new_hash).await
+    }
+
+    async fn check_password_reuse(&self, id: &UserId, password: &str) -> Result<(), AuthError> {
+        self.scripted("check_password_reuse")?;
+        self.inner.check_password_reuse(id, password).await
+    }
+
+    async fn record_login(&self, id: &UserId, ip: SessionIp) -> Result<User, AuthError> {
+        self.scripted("record_login")?;
+        self.inner.record_login(id, ip).await
+    }
+
+    async fn record_failed_login(
+        &self,
+        id: &UserId,
+        max_failed_logins: u32,
+        lock_for: time::Duration,
+    ) -> Result<User, AuthError> {
+        self.scripted("record_failed_login")?;
+        self.inner
+            .record_failed_login(id, max_failed_logins, lock_for)
+            .await
+    }
+
+    async fn clear_lockout(&self, id: &UserId) -> Result<User, AuthError> {
+        self.scripted("clear_lockout")?;
+        self.inner.clear_lockout(id).await
+    }
+
+    async fn delete_user(&self, id: &UserId) -> Result<(), AuthError> {
+        self.scripted("delete_user")?;
+        self.inner.delete_user(id).await
+    }
+
+    async fn preview_delete_user(&self, id: &UserId) -> Result<Vec<SessionId>, AuthError> {
+        self.scripted("preview_delete_user")?;
+        self.inner.preview_delete_user(id).await
+    }
+
+    async fn save_passkey(&self, credential: PasskeyCredential) -> Result<(), AuthError> {
+        self.scripted("save_passkey")?;
+        self.inner.save_passkey(credential).await
+    }
+
+    async fn list_passkeys(&self, user_id: &UserId) -> Result<Vec<PasskeyCredential>, AuthError> {
+        self.scripted("list_passkeys")?;
+        self.inner.list_passkeys(user_id).await
+    }
+
+    async fn issue_session(
+        &self,
+        id: &UserId,
+        ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
+    ) -> Result<Session, AuthError> {
+        self.scripted("issue_session")?;
+        self.inner.issue_session(id, ip, user_agent).await
+    }
+
+    async fn issue_impersonation_session(
+        &self,
+        admin: &UserId,
+        target: &UserId,
+        ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
+    ) -> Result<Session, AuthError> {
+        self.scripted("issue_impersonation_session")?;
+        self.inner
+            .issue_impersonation_session(admin, target, ip, user_agent)
+            .await
+    }
+
+    async fn fetch_session(&self, token: &SessionId) -> Result<Session, AuthError> {
+        self.scripted("fetch_session")?;
+        self.inner.fetch_session(token).await
+    }
+
+    async fn extend_session(&self, token: &SessionId) -> Result<Session, AuthError> {
+        self.scripted("extend_session")?;
+        self.inner.extend_session(token).await
+    }
+
+    async fn mark_reauthenticated(&self, token: &SessionId) -> Result<Session, AuthError> {
+        self.scripted("mark_reauthenticated")?;
+        self.inner.mark_reauthenticated(token).await
+    }
+
+    async fn set_session_data(
+        &self,
+        token: &SessionId,
+        key: &str,
+        value: Option<String>,
+    ) -> Result<Session, AuthError> {
+        self.scripted("set_session_data")?;
+        self.inner.set_session_data(token, key, value).await
+    }
+
+    async fn get_session_data(
+        &self,
+        token: &SessionId,
+        key: &str,
+    ) -> Result<Option<String>, AuthError> {
+        self.scripted("get_session_data")?;
+        self.inner.get_session_data(token, key).await
+    }
+
+    async fn revoke_session(&self, token: &SessionId) -> Result<(), AuthError> {
+        self.scripted("revoke_session")?;
+        self.inner.revoke_session(token).await
+    }
+
+    async fn revoke_all_sessions(&self, id: &UserId) -> Result<usize, AuthError> {
+        self.scripted("revoke_all_sessions")?;
+        self.inner.revoke_all_sessions(id).await
+    }
+
+    async fn purge_expired_sessions(&self) -> Result<usize, AuthError> {
+        self.scripted("purge_expired_sessions")?;
+        self.inner.purge_expired_sessions().await
+    }
+
+    async fn count_user_sessions(&self, id: &UserId) -> Result<usize, AuthError> {
+        self.scripted("count_user_sessions")?;
+        self.inner.count_user_sessions(id).await
+    }
+
+    async fn list_user_sessions(&self, id: &UserId) -> Result<Vec<Session>, AuthError> {
+        self.scripted("list_user_sessions")?;
+        self.inner.list_user_sessions(id).await
+    }
+
+    async fn list_user_sessions_page(
+        &self,
+        id: &UserId,
+        after: Option<&SessionId>,
+        limit: usize,
+    ) -> Result<SessionPage, AuthError> {
+        self.scripted("list_user_sessions_page")?;
+        self.inner.list_user_sessions_page(id, after, limit).await
+    }
+
+    async fn active_sessions_by_ip(&self) -> Result<BTreeMap<IpAddr, usize>, AuthError> {
+        self.scripted("active_sessions_by_ip")?;
+        self.inner.active_sessions_by_ip().await
+    }
+
+    async fn revoke_sessions_where(&self, criteria: &SessionCriteria) -> Result<usize, AuthError> {
+        self.scripted("revoke_sessions_where")?;
+        self.inner.revoke_sessions_where(criteria).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn scripted_failures_are_returned_once_in_order() {
+        let store = MockAuthStore::default();
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+
+        store.fail_next(
+            "get_user_by_id",
+            AuthError::Internal("disk gone".to_string()),
+        );
+        store.fail_next("get_user_by_id", AuthError::NotFound);
+
+        assert!(matches!(
+            store.get_user_by_id(&user.id).await,
+            Err(AuthError::Internal(_))
+        ));
+        assert!(matches!(
+            store.get_user_by_id(&user.id).await,
+            Err(AuthError::NotFound)
+        ));
+        assert_eq!(store.get_user_by_id(&user.id).await.unwrap().id, user.id);
+        assert_eq!(store.calls("get_user_by_id"), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "no AuthStore method named fetch_sessions")]
+    fn unknown_methods_are_rejected() {
+        MockAuthStore::default().fail_next("fetch_sessions", AuthError::NotFound);
+    }
+}
@@ -0,0 +1,378 @@
+use redb::{
+    Database, ReadTransaction, ReadableDatabase, TableDefinition, TableHandle, WriteTransaction,
+    backends::InMemoryBackend,
+};
+use std::path::Path;
+use std::sync::Arc;
+use time::OffsetDateTime;
+use tokio::sync::broadcast;
+use tokio::task::spawn_blocking;
+use tracing::trace;
+
+use super::blocking::BlockingLimiter;
+use super::codec::ValueCodec;
+use super::migrations::{Migration, init_schema_version, latest_version, run_migrations};
+use super::{AuditError, AuditStore};
+use crate::types::{AuditEvent, AuditQuery};
+
+// Table definitions
+/// Primary table: timestamp (u64 BE nanos) || event id (16 bytes) -> AuditEvent (serialized).
+///
+/// Keys sort chronologically, so time-range queries are a single range scan.
+const AUDIT_EVENTS_TABLE: TableDefinition<&[u8], Vec<u8>> = TableDefinition::new("audit_events");
+
+/// Key of this store's schema version in the metadata table
+const SCHEMA: &str = "audit";
+
+/// Applied in order by [`RedbAuditStore::migrate`]; append, never reorder
+const MIGRATIONS: &[Migration<AuditError>] = &[];
+
+/// Length of an event key: 8-byte timestamp followed by the 16-byte event id
+const KEY_LEN: usize = 24;
+
+/// Recorded events a slow subscriber may fall behind by before it misses some
+const SUBSCRIBER_BACKLOG: usize = 256;
+
+#[derive(Clone)]
+pub struct RedbAuditStore {
+    db: Arc<Database>,
+    codec: ValueCodec,
+    blocking: Arc<BlockingLimiter>,
+    /// Every recorded event is published here for `subscribe`rs
+    recorded: broadcast::Sender<AuditEvent>,
+}
+
+impl RedbAuditStore {
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, AuditError> {
+        Self::from_database(Database::create(path)?)
+    }
+
+    /// Creates a store backed by an in-memory redb database (nothing is persisted)
+    pub fn in_memory() -> Result<Self, AuditError> {
+        Self::from_database(Database::builder().create_with_backend(InMemoryBackend::new())?)
+    }
+
+    fn from_database(db: Database) -> Result<Self, AuditError> {
+        // Initialize tables
+        let write_txn = db.begin_write()?;
+        {
+            init_schema_version::<AuditError>(
+                &write_txn,
+                SCHEMA,
+                AUDIT_EVENTS_TABLE.name(),
+                latest_version(MIGRATIONS),
+            )?;
+            let _ = write_txn.open_table(AUDIT_EVENTS_TABLE)?;
+        }
+        write_txn.commit()?;
+
+        Ok(Self {
+            db: Arc::new(db),
+            codec: ValueCodec::plaintext(),
+            blocking: Arc::new(BlockingLimiter::unbounded()),
+            recorded: broadcast::channel(SUBSCRIBER_BACKLOG).0,
+        })
+    }
+
+    /// Sets the codec used to (de)serialize stored values, e.g. to enable encryption at rest
+    pub fn with_codec(mut self, codec: ValueCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Sets the limiter shared by the stores to bound concurrent blocking operations
+    pub fn with_blocking_limiter(mut self, blocking: Arc<BlockingLimiter>) -> Self {
+        self.blocking = blocking;
+        self
+    }
+
+    /// Brings events written by an older version up to date, returning how many migrations
+    /// ran. Run once at startup, after `with_codec`.
+    pub async fn migrate(&self) -> Result<usize, AuditError> {
+        let db = self.db.clone();
+        let codec = self.codec.clone();
+        spawn_blocking(move || run_migrations(&db, SCHEMA, &codec, MIGRATIONS)).await?
+    }
+
+    // ==================== Transaction Helpers ====================
+
+    /// Execute a read-only operation within a transaction
+    async fn with_read_txn<T, F>(&self, f: F) -> Result<T, AuditError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&ReadTransaction, &ValueCodec) -> Result<T, AuditError> + Send + 'static,
+    {
+        let db = self.db.clone();
+        let codec = self.codec.clone();
+        let permit = self.blocking.acquire().await;
+        spawn_blocking(move || {
+            let _permit = permit;
+            let txn = db.begin_read()?;
+            f(&txn, &codec)
+        })
+        .await?
+    }
+
+    /// Execute a write operation within a transaction
+    async fn with_write_txn<T, F>(&self, f: F) -> Result<T, AuditError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&WriteTransaction, &ValueCodec) -> Result<T, AuditError> + Send + 'static,
+    {
+        let db = self.db.clone();
+        let codec = self.codec.clone();
+        let permit = self.blocking.acquire().await;
+        spawn_blocking(move || {
+            let _permit = permit;
+            let txn = db.begin_write()?;
+            let result = f(&txn, &codec)?;
+            txn.commit()?;
+            Ok(result)
+        })
+        .await?
+    }
+
+    // ==================== Key Helpers ====================
+
+    /// Timestamp part of a key; times before the unix epoch clamp to 0
+    fn timestamp_bytes(at: OffsetDateTime) -> [u8; 8] {
+        let nanos = u64::try_from(at.unix_timestamp_nanos().max(0)).unwrap_or(u64::MAX);
+        nanos.to_be_bytes()
+    }
+
+    fn event_key(event: &AuditEvent) -> [u8; KEY_LEN] {
+        let mut key = [0u8; KEY_LEN];
+        key[..8].copy_from_slice(&Self::timestamp_bytes(event.at));
+        key[8..].copy_from_slice(event.id.as_bytes());
+        key
+    }
+
+    /// Inclusive key bounds covering every event in `[since, until]`
+    fn key_range(query: &AuditQuery) -> ([u8; KEY_LEN], [u8; KEY_LEN]) {
+        let mut start = [0u8; KEY_LEN];
+        let mut end = [0xffu8; KEY_LEN];
+        if let Some(since) = query.since {
+            start[..8].copy_from_slice(&Self::timestamp_bytes(since));
+        }
+        if let Some(until) = query.until {
+            end[..8].copy_from_slice(&Self::timestamp_bytes(until));
+        }
+        (start, end)
+    }
+}
+
+impl AuditStore for RedbAuditStore {
+    async fn record(&self, event: AuditEvent) -> Result<(), AuditError> {
+        let published = event.clone();
+        self.with_write_txn(move |txn, codec| {
+            let mut events_table = txn.open_table(AUDIT_EVENTS_TABLE)?;
+            events_table.insert(Self::event_key(&event).as_slice(), codec.serialize(&event)?)?;
+
+            trace!(kind = ?event.kind, "Audit event recorded");
+            Ok(())
+        })
+        .await?;
+
+        // only fails when nobody is subscribed
+        let _ = self.recorded.send(published);
+        Ok(())
+    }
+
+    async fn query(&self, query: AuditQuery) -> Result<Vec<AuditEvent>, AuditError> {
+        self.with_read_txn(move |txn, codec| {
+            let events_table = txn.open_table(AUDIT_EVENTS_TABLE)?;
+            let (start, end) = Self::key_range(&query);
+            let limit = if query.limit == 0 {
+                usize::MAX
+            } else {
+                query.limit
+            };
+
+            let mut events = Vec::new();
+            if start > end {
+                return Ok(events);
+            }
+
+            // Scan newest first, stopping as soon as the limit is reached
+            for entry in events_table.range(start.as_slice()..=end.as_slice())?.rev() {
+                let (_, event_bytes) = entry?;
+                let event: AuditEvent = codec.deserialize(&event_bytes.value())?;
+                if query.matches(&event) {
+                    events.push(event);
+                    if events.len() >= limit {
+                        break;
+                    }
+                }
+            }
+            Ok(events)
+        })
+        .await
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<AuditEvent> {
+        self.recorded.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AuditKind, ProjectId, UserId};
+    use time::Duration;
+    use uuid::Uuid;
+
+    fn event(actor: UserId, kind: AuditKind, at: OffsetDateTime) -> AuditEvent {
+        AuditEvent {
+            id: Uuid::now_v7(),
+            at,
+            actor: Some(actor),
+            kind,
+            detail: String::new(),
+            project_id: None,
+        }
+    }
+
+    /// Seeds six events an hour apart, alternating between two actors
+    async fn seeded() -> (RedbAuditStore, UserId, UserId, OffsetDateTime) {
+        let store = RedbAuditStore::in_memory().unwrap();
+        let (alice, bob) = (UserId::new(), UserId::new());
+        let start = OffsetDateTime::now_utc() - Duration::days(1);
+
+        let kinds = [
+            AuditKind::Login,
+            AuditKind::ProjectCreated,
+            AuditKind::Login,
+            AuditKind::ProjectDeleted,
+            AuditKind::Logout,
+            AuditKind::ProjectCreated,
+        ];
+        for (i, kind) in kinds.into_iter().enumerate() {
+            let actor = if i % 2 == 0 { alice } else { bob };
+            let at = start + Duration::hours(i as i64);
+            store.record(event(actor, kind, at)).await.unwrap();
+        }
+
+        (store, alice, bob, start)
+    }
+
+    #[tokio::test]
+    async fn unfiltered_query_returns_newest_first() {
+        let (store, ..) = seeded().await;
+        let events = store.query(AuditQuery::default()).await.unwrap();
+
+        assert_eq!(events.len(), 6);
+        assert!(events.windows(2).all(|pair| pair[0].at > pair[1].at));
+    }
+
+    #[tokio::test]
+    async fn filters_by_actor() {
+        let (store, alice, bob, _) = seeded().await;
+        let query = AuditQuery {
+            actor: Some(bob),
+            ..Default::default()
+        };
+        let events = store.query(query).await.unwrap();
+
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|e| e.actor == Some(bob)));
+        assert!(events.iter().all(|e| e.actor != Some(alice)));
+    }
+
+    #[tokio::test]
+    async fn filters_by_kind() {
+        let (store, ..) = seeded().await;
+        let query = AuditQuery {
+            kinds: vec![AuditKind::ProjectCreated, AuditKind::ProjectDeleted],
+            ..Default::default()
+        };
+        let events = store.query(query).await.unwrap();
+
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|e| e.kind != AuditKind::Login));
+    }
+
+    #[tokio::test]
+    async fn filters_by_time_range_inclusively() {
+        let (store, _, _, start) = seeded().await;
+        let query = AuditQuery {
+            since: Some(start + Duration::hours(1)),
+            until: Some(start + Duration::hours(3)),
+            ..Default::default()
+        };
+        let events = store.query(query).await.unwrap();
+
+        let kinds: Vec<_> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                AuditKind::ProjectDeleted,
+                AuditKind::Login,
+                AuditKind::ProjectCreated
+            ]
+        );
+
+        let only_since = AuditQuery {
+            since: Some(start + Duration::hours(5)),
+            ..Default::default()
+        };
+        assert_eq!(store.query(only_since).await.unwrap().len(), 1);
+
+        let inverted = AuditQuery {
+            since: Some(start + Duration::hours(3)),
+            until: Some(start),
+            ..Default::default()
+        };
+        assert!(store.query(inverted).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn combined_filters_and_limit() {
+        let (store, alice, _, start) = seeded().await;
+        let query = AuditQuery {
+            actor: Some(alice),
+            kinds: vec![AuditKind::Login],
+            since: Some(start),
+            until: Some(start + Duration::hours(4)),
+            limit: 0,
+            ..Default::default()
+        };
+        let events = store.query(query.clone()).await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].at, start + Duration::hours(2));
+
+        let limited = AuditQuery { limit: 1, ..query };
+        let events = store.query(limited).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].at, start + Duration::hours(2));
+    }
+
+    #[tokio::test]
+    async fn filters_by_project() {
+        let (store, alice, ..) = seeded().await;
+        let project_id = ProjectId::new();
+        let scoped = event(alice, AuditKind::ProjectUpdated, OffsetDateTime::now_utc())
+            .with_project(project_id);
+        store.record(scoped.clone()).await.unwrap();
+
+        let query = AuditQuery {
+            project_id: Some(project_id),
+            ..Default::default()
+        };
+        assert_eq!(store.query(query).await.unwrap(), vec![scoped]);
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_events_recorded_after_subscribing() {
+        let store = RedbAuditStore::in_memory().unwrap();
+        let actor = UserId::new();
+        let before = event(actor, AuditKind::Login, OffsetDateTime::now_utc());
+        store.record(before).await.unwrap();
+
+        let mut subscriber = store.subscribe();
+        let after = event(actor, AuditKind::Logout, OffsetDateTime::now_utc());
+        store.record(after.clone()).await.unwrap();
+
+        assert_eq!(subscriber.recv().await.unwrap(), after);
+        assert!(subscriber.try_recv().is_err());
+    }
+}
@@ -1,69 +1,328 @@
 use redb::{
     Database, MultimapTableDefinition, ReadTransaction, ReadableDatabase, ReadableMultimapTable,
-    ReadableTable, TableDefinition, WriteTransaction,
+    ReadableTable, ReadableTableMetadata, TableDefinition, TableHandle, WriteTransaction,
+    backends::InMemoryBackend,
 };
-use serde::{Serialize, de::DeserializeOwned};
-use std::path::Path;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use time::OffsetDateTime;
+use time::{Duration, OffsetDateTime};
 use tokio::task::spawn_blocking;
 use tracing::{debug, error, trace};
 
-use super::{AuthError, AuthStore};
+use super::blocking::BlockingLimiter;
+use super::clock::{Clock, SystemClock};
+use super::codec::ValueCodec;
+use super::db_stats::database_report;
+use super::maintenance::MaintenanceStats;
+use super::migrations::{Migration, init_schema_version, latest_version, run_migrations};
+use super::redb_sessionstore::RedbSessionStore;
+use super::{AuthError, AuthStore, SessionStore};
 use crate::config::SESSION_DURATION;
-use crate::types::{PasswordHash, Role, Session, SessionId, SessionIp, User, UserId, Username};
+use crate::types::{
+    DatabaseReport, InviteCode, PasskeyCredential, PasswordHash, Role, Session, SessionCriteria,
+    SessionId, SessionIp, SessionPage, User, UserAgentHash, UserId, UserStatus, Username,
+};
+
+/// Key of this store's schema version in the metadata table
+const SCHEMA: &str = "auth";
+
+/// Applied in order by [`RedbAuthStore::migrate`]; append, never reorder
+const MIGRATIONS: &[Migration<AuthError>] = &[Migration {
+    version: 1,
+    description: "record when users were last enabled",
+    apply: RedbAuthStore::add_enabled_at,
+}];
+
+/// `User` as stored before `enabled_at`
+#[derive(Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+struct UserV0 {
+    id: UserId,
+    username: Username,
+    password_hash: PasswordHash,
+    role: Role,
+    status: UserStatus,
+    last_login_at: Option<OffsetDateTime>,
+    last_login_ip: Option<SessionIp>,
+    failed_logins: u32,
+    locked_until: Option<OffsetDateTime>,
+}
 
 // Table definitions
 const USERS_TABLE: TableDefinition<u128, Vec<u8>> = TableDefinition::new("users");
 const USERNAMES_TABLE: TableDefinition<&str, u128> = TableDefinition::new("usernames");
-const SESSIONS_TABLE: TableDefinition<&str, Vec<u8>> = TableDefinition::new("sessions");
+const INVITES_TABLE: TableDefinition<&str, Vec<u8>> = TableDefinition::new("invite_codes");
+
+/// Passkeys: credential_id -> PasskeyCredential (serialized)
+const CREDENTIALS_TABLE: TableDefinition<&str, Vec<u8>> = TableDefinition::new("credentials");
 
-/// Multimap index: user_id -> session_id for O(1) add/remove operations
-const USER_SESSIONS_INDEX: MultimapTableDefinition<u128, &str> =
-    MultimapTableDefinition::new("user_sessions_v2");
+/// Multimap index: user_id -> credential_id
+const USER_CREDENTIALS_INDEX: MultimapTableDefinition<u128, &str> =
+    MultimapTableDefinition::new("user_credentials");
 
-/// Reverse index: session_id -> user_id for O(1) lookup without deserializing session
-const SESSION_USER_INDEX: TableDefinition<&str, u128> = TableDefinition::new("session_user");
+/// Replaced passwords: user_id -> Vec<PasswordHash> (serialized), newest first
+const PASSWORD_HISTORY_TABLE: TableDefinition<u128, Vec<u8>> =
+    TableDefinition::new("password_history");
 
 #[derive(Clone)]
 pub struct RedbAuthStore {
     db: Arc<Database>,
+    /// Database file, `None` when in memory
+    path: Option<PathBuf>,
+    codec: ValueCodec,
+    blocking: Arc<BlockingLimiter>,
+    clock: Arc<dyn Clock>,
+    stats: Arc<MaintenanceStats>,
     max_sessions_per_user: usize,
+    /// Cap on active sessions across all users, kept by the session store
+    max_total_sessions: Option<usize>,
+    session_token_bytes: usize,
+    /// Recent passwords, the current one included, a new password may not match
+    password_history_len: usize,
+    /// Key the usernames table by the lowercased name, see `Username::lookup_key`
+    case_insensitive_usernames: bool,
+    /// The session tables, in this store's database unless `with_session_store` moved them
+    sessions: RedbSessionStore,
 }
 
 impl RedbAuthStore {
     pub fn new(path: impl AsRef<Path>, max_sessions_per_user: usize) -> Result<Self, AuthError> {
-        let db = Database::create(path)?;
+        let store = Self::from_database(Database::create(&path)?, max_sessions_per_user)?;
+        Ok(Self {
+            path: Some(path.as_ref().to_path_buf()),
+            ..store
+        })
+    }
 
+    /// Creates a store backed by an in-memory redb database (nothing is persisted)
+    pub fn in_memory(max_sessions_per_user: usize) -> Result<Self, AuthError> {
+        let db = Database::builder().create_with_backend(InMemoryBackend::new())?;
+        Self::from_database(db, max_sessions_per_user)
+    }
+
+    fn from_database(db: Database, max_sessions_per_user: usize) -> Result<Self, AuthError> {
         // Initialize tables
         let write_txn = db.begin_write()?;
         {
+            init_schema_version::<AuthError>(
+                &write_txn,
+                SCHEMA,
+                USERS_TABLE.name(),
+                latest_version(MIGRATIONS),
+            )?;
             let _ = write_txn.open_table(USERS_TABLE)?;
             let _ = write_txn.open_table(USERNAMES_TABLE)?;
-            let _ = write_txn.open_table(SESSIONS_TABLE)?;
-            let _ = write_txn.open_multimap_table(USER_SESSIONS_INDEX)?;
-            let _ = write_txn.open_table(SESSION_USER_INDEX)?;
+            let _ = write_txn.open_table(INVITES_TABLE)?;
+            let _ = write_txn.open_table(CREDENTIALS_TABLE)?;
+            let _ = write_txn.open_multimap_table(USER_CREDENTIALS_INDEX)?;
+            let _ = write_txn.open_table(PASSWORD_HISTORY_TABLE)?;
         }
         write_txn.commit()?;
 
+        let db = Arc::new(db);
         Ok(Self {
-            db: Arc::new(db),
+            sessions: RedbSessionStore::in_database(db.clone())?,
+            db,
+            path: None,
+            codec: ValueCodec::plaintext(),
+            blocking: Arc::new(BlockingLimiter::unbounded()),
+            clock: Arc::new(SystemClock),
+            stats: Arc::new(MaintenanceStats::new()),
             max_sessions_per_user,
+            max_total_sessions: None,
+            password_history_len: 0,
+            case_insensitive_usernames: false,
+            session_token_bytes: SessionId::DEFAULT_BYTES,
         })
     }
 
-    // ==================== Serialization Helpers ====================
+    /// Sets the codec used to (de)serialize stored values, e.g. to enable encryption at rest
+    pub fn with_codec(mut self, codec: ValueCodec) -> Self {
+        self.sessions = self.sessions.with_codec(codec.clone());
+        self.codec = codec;
+        self
+    }
+
+    /// Sets the limiter shared by the stores to bound concurrent blocking operations
+    pub fn with_blocking_limiter(mut self, blocking: Arc<BlockingLimiter>) -> Self {
+        self.sessions = self.sessions.with_blocking_limiter(blocking.clone());
+        self.blocking = blocking;
+        self
+    }
+
+    /// Brings users and sessions written by an older version up to date, returning how
+    /// many migrations ran. Run once at startup, after `with_codec`,
+    /// `with_case_insensitive_usernames` and `with_session_store`.
+    pub async fn migrate(&self) -> Result<usize, AuthError> {
+        let db = self.db.clone();
+        let codec = self.codec.clone();
+        let applied =
+            spawn_blocking(move || run_migrations(&db, SCHEMA, &codec, MIGRATIONS)).await??;
+        let case_insensitive = self.case_insensitive_usernames;
+        self.with_write_txn(move |txn, codec| Self::rekey_usernames(txn, codec, case_insensitive))
+            .await?;
+        Ok(applied + self.sessions.migrate().await?)
+    }
+
+    /// Rebuilds the usernames table when it was keyed for the other
+    /// `case_insensitive_usernames` setting. Fails, changing nothing, if two users' names
+    /// differ only in case and usernames are to be case-insensitive.
+    fn rekey_usernames(
+        txn: &WriteTransaction,
+        codec: &ValueCodec,
+        case_insensitive: bool,
+    ) -> Result<(), AuthError> {
+        let users_table = txn.open_table(USERS_TABLE)?;
+        let mut usernames_table = txn.open_table(USERNAMES_TABLE)?;
+
+        let mut wanted = BTreeMap::new();
+        for entry in users_table.iter()? {
+            let (_, bytes) = entry?;
+            let user: User = codec.deserialize(&bytes.value())?;
+            let key = user.username.lookup_key(case_insensitive);
+            if wanted.insert(key, user.id.0.as_u128()).is_some() {
+                return Err(AuthError::Internal(format!(
+                    "another username differs from \"{}\" only in case; rename one of them \
+                     before enabling [auth] case_insensitive_usernames",
+                    user.username.0
+                )));
+            }
+        }
+
+        let current = usernames_table
+            .iter()?
+            .map(|entry| {
+                let (key, id) = entry?;
+                Ok((key.value().to_string(), id.value()))
+            })
+            .collect::<Result<BTreeMap<_, _>, AuthError>>()?;
+        if current == wanted {
+            return Ok(());
+        }
+
+        for key in current.keys() {
+            usernames_table.remove(key.as_str())?;
+        }
+        for (key, id) in &wanted {
+            usernames_table.insert(key.as_str(), *id)?;
+        }
+        debug!(case_insensitive, "Usernames re-keyed");
+        Ok(())
+    }
+
+    /// Migration 1: re-encodes users with an empty `enabled_at`
+    fn add_enabled_at(txn: &WriteTransaction, codec: &ValueCodec) -> Result<(), AuthError> {
+        let mut users_table = txn.open_table(USERS_TABLE)?;
+        let legacy = users_table
+            .iter()?
+            .map(|entry| {
+                let (_, bytes) = entry?;
+                Ok(codec.deserialize::<UserV0>(&bytes.value())?)
+            })
+            .collect::<Result<Vec<_>, AuthError>>()?;
+
+        for old in legacy {
+            let user = User {
+                id: old.id,
+                username: old.username,
+                password_hash: old.password_hash,
+                role: old.role,
+                status: old.status,
+                last_login_at: old.last_login_at,
+                last_login_ip: old.last_login_ip,
+                failed_logins: old.failed_logins,
+                locked_until: old.locked_until,
+                enabled_at: None,
+            };
+            users_table.insert(user.id.0.as_u128(), codec.serialize(&user)?)?;
+        }
+        Ok(())
+    }
+
+    /// Whether nothing is persisted, see `in_memory`
+    pub fn is_in_memory(&self) -> bool {
+        self.path.is_none()
+    }
+
+    /// Size and page usage of the database, e.g. to decide when to compact it
+    pub async fn stats(&self) -> Result<DatabaseReport, AuthError> {
+        let db = self.db.clone();
+        let path = self.path.clone();
+        spawn_blocking(move || database_report("auth", &db, path.as_deref())).await?
+    }
+
+    /// Sets the clock used for session and invite expiry checks
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.sessions = self.sessions.with_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
+    /// Caps the number of active sessions across all users (`[session] max_total`)
+    pub fn with_max_total_sessions(mut self, max_total: Option<usize>) -> Self {
+        self.sessions = self.sessions.with_max_total(max_total);
+        self.max_total_sessions = max_total;
+        self
+    }
+
+    /// Sessions counted against the `with_max_total_sessions` cap. Sessions that expired
+    /// unnoticed are included until the next purge.
+    pub fn active_session_count(&self) -> usize {
+        self.sessions.active_count()
+    }
+
+    /// Sets how many recent passwords, the current one included, a new password may not
+    /// match (`[security] password_history`); 0 allows any
+    pub fn with_password_history(mut self, len: usize) -> Self {
+        self.password_history_len = len;
+        self
+    }
+
+    /// Looks usernames up regardless of case, so names differing only in case collide
+    /// (`[auth] case_insensitive_usernames`). `migrate` re-keys existing users.
+    pub fn with_case_insensitive_usernames(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive_usernames = case_insensitive;
+        self
+    }
+
+    /// Sets how many random bytes new session tokens are made of
+    pub fn with_session_token_bytes(mut self, bytes: usize) -> Self {
+        self.session_token_bytes = bytes;
+        self
+    }
+
+    /// Sets the counters that expired sessions cleaned up along the way are recorded in
+    pub fn with_maintenance_stats(mut self, stats: Arc<MaintenanceStats>) -> Self {
+        self.sessions = self.sessions.with_maintenance_stats(stats.clone());
+        self.stats = stats;
+        self
+    }
+
+    /// Keeps sessions in `sessions` instead of this store's database, e.g. one that is wiped
+    /// on restart. The codec, clock and limits set on this store apply to it as well.
+    pub fn with_session_store(mut self, sessions: RedbSessionStore) -> Self {
+        self.sessions = sessions
+            .with_codec(self.codec.clone())
+            .with_blocking_limiter(self.blocking.clone())
+            .with_clock(self.clock.clone())
+            .with_maintenance_stats(self.stats.clone())
+            .with_max_total(self.max_total_sessions);
+        self
+    }
 
-    fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, AuthError> {
-        Ok(bincode::serde::encode_to_vec(
-            value,
-            bincode::config::standard(),
-        )?)
+    /// Signs everyone out by dropping every session; users are left as they are.
+    ///
+    /// Returns how many sessions there were.
+    pub async fn reset_sessions(&self) -> Result<usize, AuthError> {
+        self.sessions.clear().await
     }
 
-    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, AuthError> {
-        let (result, _) = bincode::serde::decode_from_slice(bytes, bincode::config::standard())?;
-        Ok(result)
+    /// Size and page usage of the session database, if it is a file of its own
+    pub async fn session_stats(&self) -> Result<Option<DatabaseReport>, AuthError> {
+        self.sessions.stats().await
     }
 
     // ==================== Transaction Helpers ====================
@@ -72,12 +331,15 @@ impl RedbAuthStore {
     async fn with_read_txn<T, F>(&self, f: F) -> Result<T, AuthError>
     where
         T: Send + 'static,
-        F: FnOnce(&ReadTransaction) -> Result<T, AuthError> + Send + 'static,
+        F: FnOnce(&ReadTransaction, &ValueCodec) -> Result<T, AuthError> + Send + 'static,
     {
         let db = self.db.clone();
+        let codec = self.codec.clone();
+        let permit = self.blocking.acquire().await;
         spawn_blocking(move || {
+            let _permit = permit;
             let txn = db.begin_read()?;
-            f(&txn)
+            f(&txn, &codec)
         })
         .await?
     }
@@ -86,86 +348,163 @@ impl RedbAuthStore {
     async fn with_write_txn<T, F>(&self, f: F) -> Result<T, AuthError>
     where
         T: Send + 'static,
-        F: FnOnce(&WriteTransaction) -> Result<T, AuthError> + Send + 'static,
+        F: FnOnce(&WriteTransaction, &ValueCodec) -> Result<T, AuthError> + Send + 'static,
     {
         let db = self.db.clone();
+        let codec = self.codec.clone();
+        let permit = self.blocking.acquire().await;
         spawn_blocking(move || {
+            let _permit = permit;
             let txn = db.begin_write()?;
-            let result = f(&txn)?;
+            let result = f(&txn, &codec)?;
             txn.commit()?;
             Ok(result)
         })
         .await?
     }
 
-    // ==================== Multimap Index Operations ====================
+    /// Execute a write operation within a transaction that is always aborted.
+    ///
+    /// Used to preview destructive operations: the closure runs the real mutation logic,
+    /// but nothing is ever committed.
+    async fn with_dry_run_txn<T, F>(&self, f: F) -> Result<T, AuthError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&WriteTransaction, &ValueCodec) -> Result<T, AuthError> + Send + 'static,
+    {
+        let db = self.db.clone();
+        let codec = self.codec.clone();
+        let permit = self.blocking.acquire().await;
+        spawn_blocking(move || {
+            let _permit = permit;
+            let txn = db.begin_write()?;
+            let result = f(&txn, &codec);
+            txn.abort()?;
+            result
+        })
+        .await?
+    }
+
+    /// Creates a user within the given transaction, enforcing username uniqueness
+    fn create_user_in(
+        txn: &WriteTransaction,
+        codec: &ValueCodec,
+        case_insensitive: bool,
+        username: Username,
+        password_hash: PasswordHash,
+        role: Role,
+        status: UserStatus,
+    ) -> Result<User, AuthError> {
+        let mut usernames_table = txn.open_table(USERNAMES_TABLE)?;
+        let mut users_table = txn.open_table(USERS_TABLE)?;
 
-    /// Gets all session IDs for a user using multimap table - O(n) where n = user's session count
-    fn get_user_session_ids(
-        table: &redb::MultimapTable<u128, &'static str>,
-        user_id: u128,
-    ) -> Result<Vec<String>, AuthError> {
-        let mut session_ids = Vec::new();
-        let values = table.get(user_id)?;
-        for value_result in values {
-            let value = value_result?;
-            session_ids.push(value.value().to_string());
+        let key = username.lookup_key(case_insensitive);
+        if usernames_table.get(key.as_str())?.is_some() {
+            debug!("User creation failed: username already exists");
+            return Err(AuthError::UserExists);
         }
-        Ok(session_ids)
-    }
 
-    /// Removes a session from all relevant tables and indexes - O(log N)
-    fn remove_session(
-        sessions_table: &mut redb::Table<&str, Vec<u8>>,
-        user_sessions_table: &mut redb::MultimapTable<u128, &'static str>,
-        session_user_table: &mut redb::Table<&str, u128>,
-        user_id: u128,
-        session_id: &str,
-    ) -> Result<(), AuthError> {
-        sessions_table.remove(session_id)?;
-        user_sessions_table.remove(user_id, session_id)?;
-        session_user_table.remove(session_id)?;
-        trace!(session_id = %session_id, "Session removed from all tables");
-        Ok(())
+        let user = User {
+            id: UserId::new(),
+            role,
+            username: username.clone(),
+            password_hash,
+            status,
+            last_login_at: None,
+            last_login_ip: None,
+            failed_logins: 0,
+            locked_until: None,
+            enabled_at: None,
+        };
+
+        let user_bytes = codec.serialize(&user)?;
+        users_table.insert(user.id.0.as_u128(), user_bytes)?;
+        usernames_table.insert(key.as_str(), user.id.0.as_u128())?;
+
+        trace!(user_id = %user.id.0, "User created successfully");
+        Ok(user)
     }
 
-    /// Batch removes multiple sessions - O(k log N) where k = number of sessions
-    fn remove_sessions_batch(
-        sessions_table: &mut redb::Table<&str, Vec<u8>>,
-        user_sessions_table: &mut redb::MultimapTable<u128, &'static str>,
-        session_user_table: &mut redb::Table<&str, u128>,
-        user_id: u128,
-        session_ids: &[String],
+    /// Deletes a user and their passkeys within the given transaction; their sessions are
+    /// left to the session store.
+    fn delete_user_in(
+        txn: &WriteTransaction,
+        codec: &ValueCodec,
+        case_insensitive: bool,
+        id: UserId,
     ) -> Result<(), AuthError> {
-        for session_id in session_ids {
-            sessions_table.remove(session_id.as_str())?;
-            user_sessions_table.remove(user_id, session_id.as_str())?;
-            session_user_table.remove(session_id.as_str())?;
+        let mut users_table = txn.open_table(USERS_TABLE)?;
+        let mut usernames_table = txn.open_table(USERNAMES_TABLE)?;
+
+        let user_bytes = users_table
+            .remove(id.0.as_u128())?
+            .ok_or(AuthError::NotFound)?;
+
+        let user: User = codec.deserialize(&user_bytes.value())?;
+        usernames_table.remove(user.username.lookup_key(case_insensitive).as_str())?;
+
+        // Clean up all passkeys for this user
+        let mut credentials_table = txn.open_table(CREDENTIALS_TABLE)?;
+        let mut user_credentials_table = txn.open_multimap_table(USER_CREDENTIALS_INDEX)?;
+        let mut credential_ids = Vec::new();
+        for credential_id in user_credentials_table.get(id.0.as_u128())? {
+            credential_ids.push(credential_id?.value().to_string());
         }
-        if !session_ids.is_empty() {
-            trace!(count = session_ids.len(), "Batch removed expired sessions");
+        for credential_id in &credential_ids {
+            credentials_table.remove(credential_id.as_str())?;
         }
+        user_credentials_table.remove_all(id.0.as_u128())?;
+
+        txn.open_table(PASSWORD_HISTORY_TABLE)?
+            .remove(id.0.as_u128())?;
+
         Ok(())
     }
 
-    /// Removes all sessions for a user - used during user deletion
-    fn remove_all_user_sessions(
-        sessions_table: &mut redb::Table<&str, Vec<u8>>,
-        user_sessions_table: &mut redb::MultimapTable<u128, &'static str>,
-        session_user_table: &mut redb::Table<&str, u128>,
-        user_id: u128,
-    ) -> Result<(), AuthError> {
-        let session_ids = Self::get_user_session_ids(user_sessions_table, user_id)?;
-
-        for session_id in &session_ids {
-            sessions_table.remove(session_id.as_str())?;
-            session_user_table.remove(session_id.as_str())?;
-        }
-        // Remove all entries for this user from the multimap
-        user_sessions_table.remove_all(user_id)?;
+    /// Backs `issue_session` and `issue_impersonation_session`
+    async fn start_session(
+        &self,
+        id: &UserId,
+        ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
+        impersonator: Option<UserId>,
+    ) -> Result<Session, AuthError> {
+        let id = *id;
+        let now = self.clock.now();
 
-        trace!(user_id = %user_id, count = session_ids.len(), "Removed all user sessions");
-        Ok(())
+        // Verify user exists and may sign in
+        self.with_read_txn(move |txn, codec| {
+            let users_table = txn.open_table(USERS_TABLE)?;
+            let user: User = match users_table.get(id.0.as_u128())? {
+                Some(user_bytes) => codec.deserialize(&user_bytes.value())?,
+                None => {
+                    debug!(user_id = %id.0, "User not found during session creation");
+                    return Err(AuthError::NotFound);
+                }
+            };
+            if !user.is_active() {
+                debug!(user_id = %id.0, "Refusing to issue a session to a disabled user");
+                return Err(AuthError::AccountDisabled);
+            }
+            Ok(())
+        })
+        .await?;
+
+        let session = Session {
+            id: SessionId::new_with_len(self.session_token_bytes),
+            user_id: id,
+            ip,
+            created_at: now,
+            expires_at: now + SESSION_DURATION,
+            last_seen_at: now,
+            user_agent,
+            last_auth_at: None,
+            impersonator,
+            data: BTreeMap::new(),
+        };
+        self.sessions
+            .insert_session(session, self.max_sessions_per_user)
+            .await
     }
 }
 
@@ -179,44 +518,165 @@ impl AuthStore for RedbAuthStore {
         username: &Username,
         password_hash: PasswordHash,
         role: Role,
+        status: UserStatus,
     ) -> Result<User, AuthError> {
         let username = username.clone();
+        let case_insensitive = self.case_insensitive_usernames;
+
+        self.with_write_txn(move |txn, codec| {
+            Self::create_user_in(
+                txn,
+                codec,
+                case_insensitive,
+                username,
+                password_hash,
+                role,
+                status,
+            )
+        })
+        .await
+    }
 
-        self.with_write_txn(move |txn| {
-            let mut usernames_table = txn.open_table(USERNAMES_TABLE)?;
-            let mut users_table = txn.open_table(USERS_TABLE)?;
+    async fn create_user_or_first_admin(
+        &self,
+        username: &Username,
+        password_hash: PasswordHash,
+        role: Role,
+        status: UserStatus,
+    ) -> Result<User, AuthError> {
+        let username = username.clone();
+        let case_insensitive = self.case_insensitive_usernames;
+
+        self.with_write_txn(move |txn, codec| {
+            // write transactions are serialized, so nobody can insert between check and insert
+            let (role, status) = if txn.open_table(USERS_TABLE)?.is_empty()? {
+                debug!("No users yet, creating the first one as admin");
+                (Role::Admin, UserStatus::Active)
+            } else {
+                (role, status)
+            };
+            Self::create_user_in(
+                txn,
+                codec,
+                case_insensitive,
+                username,
+                password_hash,
+                role,
+                status,
+            )
+        })
+        .await
+    }
 
-            if usernames_table.get(username.as_ref())?.is_some() {
-                debug!("User creation failed: username already exists");
-                return Err(AuthError::UserExists);
+    async fn create_user_with_invite(
+        &self,
+        username: &Username,
+        password_hash: PasswordHash,
+        code: &str,
+    ) -> Result<User, AuthError> {
+        let username = username.clone();
+        let code = code.to_string();
+        let now = self.clock.now();
+        let case_insensitive = self.case_insensitive_usernames;
+
+        self.with_write_txn(move |txn, codec| {
+            let mut invite: InviteCode = {
+                let invites_table = txn.open_table(INVITES_TABLE)?;
+                let invite_bytes = invites_table.get(code.as_str())?.ok_or_else(|| {
+                    debug!("Invite code not found");
+                    AuthError::InvalidInvite
+                })?;
+                codec.deserialize(&invite_bytes.value())?
+            };
+
+            if !invite.is_usable(now) {
+                debug!(
+                    uses = invite.uses,
+                    max_uses = invite.max_uses,
+                    "Invite code expired or exhausted"
+                );
+                return Err(AuthError::InvalidInvite);
             }
 
-            let user = User {
-                id: UserId::new(),
-                role,
-                username: username.clone(),
+            let user = Self::create_user_in(
+                txn,
+                codec,
+                case_insensitive,
+                username,
                 password_hash,
-            };
+                invite.role,
+                UserStatus::Active,
+            )?;
 
-            let user_bytes = Self::serialize(&user)?;
-            users_table.insert(user.id.0.as_u128(), user_bytes)?;
-            usernames_table.insert(username.as_ref(), user.id.0.as_u128())?;
+            // Count the use in the same transaction, so concurrent redemptions can't overshoot
+            invite.uses += 1;
+            let mut invites_table = txn.open_table(INVITES_TABLE)?;
+            invites_table.insert(code.as_str(), codec.serialize(&invite)?)?;
 
-            trace!(user_id = %user.id.0, "User created successfully");
             Ok(user)
         })
         .await
     }
 
+    async fn create_invite(
+        &self,
+        role: Role,
+        max_uses: u32,
+        expires_at: Option<OffsetDateTime>,
+    ) -> Result<InviteCode, AuthError> {
+        self.with_write_txn(move |txn, codec| {
+            let mut invites_table = txn.open_table(INVITES_TABLE)?;
+            let invite = InviteCode::new(role, max_uses, expires_at);
+            invites_table.insert(invite.code.as_str(), codec.serialize(&invite)?)?;
+
+            debug!(?role, max_uses, "Invite code created");
+            Ok(invite)
+        })
+        .await
+    }
+
+    async fn list_invites(&self) -> Result<Vec<InviteCode>, AuthError> {
+        self.with_read_txn(move |txn, codec| {
+            let invites_table = txn.open_table(INVITES_TABLE)?;
+
+            let mut invites = Vec::new();
+            for entry in invites_table.iter()? {
+                let (_, invite_bytes) = entry?;
+                let invite: InviteCode = codec.deserialize(&invite_bytes.value())?;
+                invites.push(invite);
+            }
+
+            invites.sort_by_key(|invite| std::cmp::Reverse(invite.created_at));
+            Ok(invites)
+        })
+        .await
+    }
+
+    async fn revoke_invite(&self, code: &str) -> Result<(), AuthError> {
+        let code = code.to_string();
+
+        self.with_write_txn(move |txn, _| {
+            let mut invites_table = txn.open_table(INVITES_TABLE)?;
+            match invites_table.remove(code.as_str())? {
+                Some(_) => {
+                    debug!("Invite code revoked");
+                    Ok(())
+                }
+                None => Err(AuthError::InvalidInvite),
+            }
+        })
+        .await
+    }
+
     async fn get_user_by_id(&self, id: &UserId) -> Result<User, AuthError> {
         let id = *id;
 
-        self.with_read_txn(move |txn| {
+        self.with_read_txn(move |txn, codec| {
             let users_table = txn.open_table(USERS_TABLE)?;
 
             match users_table.get(id.0.as_u128())? {
                 Some(user_bytes) => {
-                    let user = Self::deserialize(&user_bytes.value())?;
+                    let user = codec.deserialize(&user_bytes.value())?;
                     debug!(user_id = %id.0, "User found");
                     Ok(user)
                 }
@@ -230,13 +690,13 @@ impl AuthStore for RedbAuthStore {
     }
 
     async fn get_user_by_username(&self, username: &Username) -> Result<User, AuthError> {
-        let username = username.clone();
+        let key = username.lookup_key(self.case_insensitive_usernames);
 
-        self.with_read_txn(move |txn| {
+        self.with_read_txn(move |txn, codec| {
             let usernames_table = txn.open_table(USERNAMES_TABLE)?;
             let users_table = txn.open_table(USERS_TABLE)?;
 
-            let user_id = match usernames_table.get(username.as_ref())? {
+            let user_id = match usernames_table.get(key.as_str())? {
                 Some(id) => id.value(),
                 None => {
                     debug!("User not found");
@@ -246,7 +706,7 @@ impl AuthStore for RedbAuthStore {
 
             match users_table.get(user_id)? {
                 Some(user_bytes) => {
-                    let user: User = Self::deserialize(&user_bytes.value())?;
+                    let user: User = codec.deserialize(&user_bytes.value())?;
                     debug!(user_id = %user.id.0, "User found");
                     Ok(user)
                 }
@@ -259,14 +719,109 @@ impl AuthStore for RedbAuthStore {
         .await
     }
 
+    async fn list_users(&self) -> Result<Vec<User>, AuthError> {
+        self.with_read_txn(move |txn, codec| {
+            let usernames_table = txn.open_table(USERNAMES_TABLE)?;
+            let users_table = txn.open_table(USERS_TABLE)?;
+
+            // The usernames table is keyed by name, so this comes out sorted
+            let mut users = Vec::new();
+            for entry in usernames_table.iter()? {
+                let (_, user_id) = entry?;
+                match users_table.get(user_id.value())? {
+                    Some(user_bytes) => users.push(codec.deserialize(&user_bytes.value())?),
+                    None => error!("Inconsistency: Username found but User data missing"),
+                }
+            }
+            Ok(users)
+        })
+        .await
+    }
+
+    async fn set_status(&self, id: &UserId, status: UserStatus) -> Result<User, AuthError> {
+        let id = *id;
+        let now = self.clock.now();
+
+        let user = self
+            .with_write_txn(move |txn, codec| {
+                let mut users_table = txn.open_table(USERS_TABLE)?;
+
+                let user_bytes = users_table
+                    .get(id.0.as_u128())?
+                    .map(|bytes| bytes.value().to_vec())
+                    .ok_or(AuthError::NotFound)?;
+
+                let mut user: User = codec.deserialize(&user_bytes)?;
+                if status == UserStatus::Active && !user.is_active() {
+                    user.enabled_at = Some(now);
+                }
+                user.status = status;
+                users_table.insert(id.0.as_u128(), codec.serialize(&user)?)?;
+                Ok(user)
+            })
+            .await?;
+
+        if status == UserStatus::Disabled {
+            let session_ids = self.sessions.remove_user_sessions(&id).await?;
+            debug!(user_id = %id.0, revoked = session_ids.len(), "User disabled");
+        } else {
+            debug!(user_id = %id.0, ?status, "User status updated");
+        }
+        Ok(user)
+    }
+
+    async fn rename_user(&self, id: &UserId, new: &Username) -> Result<User, AuthError> {
+        let id = *id;
+        let new = new.clone();
+        let case_insensitive = self.case_insensitive_usernames;
+
+        self.with_write_txn(move |txn, codec| {
+            let mut users_table = txn.open_table(USERS_TABLE)?;
+            let mut usernames_table = txn.open_table(USERNAMES_TABLE)?;
+
+            let user_bytes = users_table
+                .get(id.0.as_u128())?
+                .map(|bytes| bytes.value().to_vec())
+                .ok_or(AuthError::NotFound)?;
+            let mut user: User = codec.deserialize(&user_bytes)?;
+
+            let new_key = new.lookup_key(case_insensitive);
+            let owner = usernames_table
+                .get(new_key.as_str())?
+                .map(|owner| owner.value());
+            match owner {
+                Some(owner) if owner == id.0.as_u128() && user.username == new => {
+                    return Ok(user);
+                }
+                // with case-insensitive usernames, only the case of the name changes
+                Some(owner) if owner == id.0.as_u128() => {}
+                Some(_) => {
+                    debug!("User rename failed: username already exists");
+                    return Err(AuthError::UserExists);
+                }
+                None => {}
+            }
+
+            usernames_table.remove(user.username.lookup_key(case_insensitive).as_str())?;
+            usernames_table.insert(new_key.as_str(), id.0.as_u128())?;
+            user.username = new;
+            users_table.insert(id.0.as_u128(), codec.serialize(&user)?)?;
+
+            debug!(user_id = %id.0, "User renamed");
+            Ok(user)
+        })
+        .await
+    }
+
     async fn set_password_hash(
         &self,
         id: &UserId,
         new_hash: PasswordHash,
     ) -> Result<PasswordHash, AuthError> {
         let id = *id;
+        let history_len = self.password_history_len;
 
-        self.with_write_txn(move |txn| {
+        self.with_write_txn(move |txn, codec| {
             let mut users_table = txn.open_table(USERS_TABLE)?;
 
             let user_bytes = users_table
@@ -274,325 +829,1427 @@ impl AuthStore for RedbAuthStore {
                 .map(|bytes| bytes.value().to_vec())
                 .ok_or(AuthError::NotFound)?;
 
-            let mut user: User = Self::deserialize(&user_bytes)?;
-            user.password_hash = new_hash.clone();
+            let mut user: User = codec.deserialize(&user_bytes)?;
+            let old_hash = std::mem::replace(&mut user.password_hash, new_hash.clone());
 
-            let new_user_bytes = Self::serialize(&user)?;
+            let new_user_bytes = codec.serialize(&user)?;
             users_table.insert(id.0.as_u128(), new_user_bytes)?;
 
+            // the current password counts towards the length, so keep one fewer
+            let keep = history_len.saturating_sub(1);
+            let mut history_table = txn.open_table(PASSWORD_HISTORY_TABLE)?;
+            let mut history: Vec<PasswordHash> = match history_table.get(id.0.as_u128())? {
+                Some(bytes) => codec.deserialize(&bytes.value())?,
+                None => Vec::new(),
+            };
+            history.insert(0, old_hash);
+            history.truncate(keep);
+            if history.is_empty() {
+                history_table.remove(id.0.as_u128())?;
+            } else {
+                history_table.insert(id.0.as_u128(), codec.serialize(&history)?)?;
+            }
+
             Ok(new_hash)
         })
         .await
     }
 
-    async fn delete_user(&self, id: &UserId) -> Result<(), AuthError> {
+    async fn check_password_reuse(&self, id: &UserId, password: &str) -> Result<(), AuthError> {
         let id = *id;
+        let password = password.to_string();
+        let history_len = self.password_history_len;
+        if history_len == 0 {
+            return Ok(());
+        }
+
+        // argon2 is slow on purpose, so the hashes are checked on the blocking pool too
+        self.with_read_txn(move |txn, codec| {
+            let users_table = txn.open_table(USERS_TABLE)?;
+            let history_table = txn.open_table(PASSWORD_HISTORY_TABLE)?;
+
+            let user: User = match users_table.get(id.0.as_u128())? {
+                Some(bytes) => codec.deserialize(&bytes.value())?,
+                None => return Err(AuthError::NotFound),
+            };
+            let history: Vec<PasswordHash> = match history_table.get(id.0.as_u128())? {
+                Some(bytes) => codec.deserialize(&bytes.value())?,
+                None => Vec::new(),
+            };
 
-        self.with_write_txn(move |txn| {
+            if std::iter::once(&user.password_hash)
+                .chain(&history)
+                .take(history_len)
+                .any(|hash| hash.verify(&password))
+            {
+                debug!(user_id = %id.0, "New password matches a recent one");
+                return Err(AuthError::PasswordReused);
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn record_login(&self, id: &UserId, ip: SessionIp) -> Result<User, AuthError> {
+        let id = *id;
+        let now = self.clock.now();
+
+        self.with_write_txn(move |txn, codec| {
             let mut users_table = txn.open_table(USERS_TABLE)?;
-            let mut usernames_table = txn.open_table(USERNAMES_TABLE)?;
-            let mut sessions_table = txn.open_table(SESSIONS_TABLE)?;
-            let mut user_sessions_table = txn.open_multimap_table(USER_SESSIONS_INDEX)?;
-            let mut session_user_table = txn.open_table(SESSION_USER_INDEX)?;
 
             let user_bytes = users_table
-                .remove(id.0.as_u128())?
+                .get(id.0.as_u128())?
+                .map(|bytes| bytes.value().to_vec())
                 .ok_or(AuthError::NotFound)?;
 
-            let user: User = Self::deserialize(&user_bytes.value())?;
-            usernames_table.remove(user.username.as_ref())?;
+            let previous: User = codec.deserialize(&user_bytes)?;
+            let user = User {
+                last_login_at: Some(now),
+                last_login_ip: Some(ip),
+                failed_logins: 0,
+                ..previous.clone()
+            };
+            users_table.insert(id.0.as_u128(), codec.serialize(&user)?)?;
 
-            // Clean up all sessions for this user
-            Self::remove_all_user_sessions(
-                &mut sessions_table,
-                &mut user_sessions_table,
-                &mut session_user_table,
-                id.0.as_u128(),
-            )?;
+            trace!(user_id = %id.0, "Login recorded");
+            Ok(previous)
+        })
+        .await
+    }
+
+    async fn record_failed_login(
+        &self,
+        id: &UserId,
+        max_failed_logins: u32,
+        lock_for: Duration,
+    ) -> Result<User, AuthError> {
+        let id = *id;
+        let now = self.clock.now();
+
+        self.with_write_txn(move |txn, codec| {
+            let mut users_table = txn.open_table(USERS_TABLE)?;
+
+            let user_bytes = users_table
+                .get(id.0.as_u128())?
+                .map(|bytes| bytes.value().to_vec())
+                .ok_or(AuthError::NotFound)?;
+
+            let mut user: User = codec.deserialize(&user_bytes)?;
+            user.failed_logins += 1;
+            if user.failed_logins >= max_failed_logins {
+                user.failed_logins = 0;
+                user.locked_until = Some(now + lock_for);
+                debug!(user_id = %id.0, "User locked after too many failed logins");
+            }
+            users_table.insert(id.0.as_u128(), codec.serialize(&user)?)?;
+
+            Ok(user)
+        })
+        .await
+    }
+
+    async fn clear_lockout(&self, id: &UserId) -> Result<User, AuthError> {
+        let id = *id;
+
+        self.with_write_txn(move |txn, codec| {
+            let mut users_table = txn.open_table(USERS_TABLE)?;
+
+            let user_bytes = users_table
+                .get(id.0.as_u128())?
+                .map(|bytes| bytes.value().to_vec())
+                .ok_or(AuthError::NotFound)?;
+
+            let mut user: User = codec.deserialize(&user_bytes)?;
+            user.failed_logins = 0;
+            user.locked_until = None;
+            users_table.insert(id.0.as_u128(), codec.serialize(&user)?)?;
 
+            debug!(user_id = %id.0, "User lockout cleared");
+            Ok(user)
+        })
+        .await
+    }
+
+    async fn delete_user(&self, id: &UserId) -> Result<(), AuthError> {
+        let id = *id;
+
+        // sessions go first: should deleting the user fail, they were only signed out
+        self.sessions.remove_user_sessions(&id).await?;
+        let case_insensitive = self.case_insensitive_usernames;
+        self.with_write_txn(move |txn, codec| {
+            Self::delete_user_in(txn, codec, case_insensitive, id)?;
             trace!(user_id = %id.0, "User deleted successfully");
             Ok(())
         })
         .await
     }
 
-    async fn issue_session(&self, id: &UserId, ip: SessionIp) -> Result<Session, AuthError> {
+    async fn preview_delete_user(&self, id: &UserId) -> Result<Vec<SessionId>, AuthError> {
         let id = *id;
-        let max_sessions = self.max_sessions_per_user;
 
-        self.with_write_txn(move |txn| {
-            let now = OffsetDateTime::now_utc();
-            let expires = now + SESSION_DURATION;
+        let case_insensitive = self.case_insensitive_usernames;
+        self.with_dry_run_txn(move |txn, codec| {
+            Self::delete_user_in(txn, codec, case_insensitive, id)
+        })
+        .await?;
+        let session_ids = self.sessions.user_session_ids(&id).await?;
+        debug!(user_id = %id.0, sessions = session_ids.len(), "Previewed user deletion");
+        Ok(session_ids)
+    }
 
-            let mut sessions_table = txn.open_table(SESSIONS_TABLE)?;
+    async fn save_passkey(&self, credential: PasskeyCredential) -> Result<(), AuthError> {
+        self.with_write_txn(move |txn, codec| {
             let users_table = txn.open_table(USERS_TABLE)?;
-            let mut user_sessions_table = txn.open_multimap_table(USER_SESSIONS_INDEX)?;
-            let mut session_user_table = txn.open_table(SESSION_USER_INDEX)?;
+            let mut credentials_table = txn.open_table(CREDENTIALS_TABLE)?;
+            let mut user_credentials_table = txn.open_multimap_table(USER_CREDENTIALS_INDEX)?;
 
-            // Verify user exists
-            if users_table.get(id.0.as_u128())?.is_none() {
-                debug!(user_id = %id.0, "User not found during session creation");
+            let user_id = credential.user_id.0.as_u128();
+            if users_table.get(user_id)?.is_none() {
+                debug!(user_id = %credential.user_id.0, "User not found while saving passkey");
                 return Err(AuthError::NotFound);
             }
 
-            // Get session IDs and partition into active/expired
-            let session_ids = Self::get_user_session_ids(&user_sessions_table, id.0.as_u128())?;
-            let mut active_count = 0;
-            let mut expired_session_ids = Vec::new();
-
-            for session_id in &session_ids {
-                match sessions_table.get(session_id.as_str())? {
-                    Some(session_bytes) => {
-                        let session: Session = Self::deserialize(&session_bytes.value())?;
-                        if session.expires_at > now {
-                            active_count += 1;
-                        } else {
-                            expired_session_ids.push(session_id.clone());
-                        }
-                    }
-                    None => {
-                        // Session in index but not in sessions table - orphaned entry
-                        expired_session_ids.push(session_id.clone());
-                    }
-                }
-            }
+            let credential_bytes = codec.serialize(&credential)?;
+            credentials_table.insert(credential.credential_id.as_str(), credential_bytes)?;
+            user_credentials_table.insert(user_id, credential.credential_id.as_str())?;
 
-            // Batch clean up expired/orphaned sessions
-            Self::remove_sessions_batch(
-                &mut sessions_table,
-                &mut user_sessions_table,
-                &mut session_user_table,
-                id.0.as_u128(),
-                &expired_session_ids,
-            )?;
+            trace!(user_id = %credential.user_id.0, "Passkey saved");
+            Ok(())
+        })
+        .await
+    }
 
-            if active_count >= max_sessions {
-                debug!(
-                    user_id = %id.0,
-                    active_count,
-                    max_sessions,
-                    "Maximum active sessions reached"
-                );
-                return Err(AuthError::SessionLimitReached);
+    async fn list_passkeys(&self, user_id: &UserId) -> Result<Vec<PasskeyCredential>, AuthError> {
+        let user_id = *user_id;
+
+        self.with_read_txn(move |txn, codec| {
+            let credentials_table = txn.open_table(CREDENTIALS_TABLE)?;
+            let user_credentials_table = txn.open_multimap_table(USER_CREDENTIALS_INDEX)?;
+
+            let mut credentials = Vec::new();
+            for credential_id in user_credentials_table.get(user_id.0.as_u128())? {
+                if let Some(bytes) = credentials_table.get(credential_id?.value())? {
+                    credentials.push(codec.deserialize(&bytes.value())?);
+                }
             }
+            Ok(credentials)
+        })
+        .await
+    }
 
-            // Create new session
-            let session = Session {
-                id: SessionId::new(),
-                user_id: id,
-                ip,
-                created_at: now,
-                expires_at: expires,
-            };
+    async fn issue_session(
+        &self,
+        id: &UserId,
+        ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
+    ) -> Result<Session, AuthError> {
+        self.start_session(id, ip, user_agent, None).await
+    }
 
-            let session_bytes = Self::serialize(&session)?;
-            sessions_table.insert(session.id.as_str(), session_bytes)?;
+    async fn issue_impersonation_session(
+        &self,
+        admin: &UserId,
+        target: &UserId,
+        ip: SessionIp,
+        user_agent: Option<UserAgentHash>,
+    ) -> Result<Session, AuthError> {
+        debug!(admin_id = %admin.0, user_id = %target.0, "Issuing impersonation session");
+        self.start_session(target, ip, user_agent, Some(*admin))
+            .await
+    }
 
-            // Add to indexes
-            user_sessions_table.insert(id.0.as_u128(), session.id.as_str())?;
-            session_user_table.insert(session.id.as_str(), id.0.as_u128())?;
+    async fn fetch_session(&self, token: &SessionId) -> Result<Session, AuthError> {
+        let session = self.sessions.get_session(token).await?;
+        let user_id = session.user_id;
 
-            trace!(
-                user_id = %id.0,
-                session_id = %session.id.0,
-                "Session created successfully"
-            );
+        // Disabling and deleting revoke sessions, but the sessions may live in another
+        // database, so don't rely on that alone
+        self.with_read_txn(move |txn, codec| {
+            let users_table = txn.open_table(USERS_TABLE)?;
+            let Some(user_bytes) = users_table.get(user_id.0.as_u128())? else {
+                debug!(session_id = %session.id.0, "Session belongs to a deleted user");
+                return Err(AuthError::InvalidSession);
+            };
+            let user: User = codec.deserialize(&user_bytes.value())?;
+            if !user.is_active() {
+                debug!(session_id = %session.id.0, "Session belongs to a disabled user");
+                return Err(AuthError::AccountDisabled);
+            }
             Ok(session)
         })
         .await
     }
 
-    async fn fetch_session(&self, token: &SessionId) -> Result<Session, AuthError> {
-        let db = self.db.clone();
-        let token = token.clone();
+    async fn extend_session(&self, token: &SessionId) -> Result<Session, AuthError> {
+        let expires_at = self.clock.now() + SESSION_DURATION;
+        self.sessions.touch_session(token, expires_at).await
+    }
 
-        // Use read-first approach: only acquire write lock if cleanup is needed
-        spawn_blocking(move || {
-            let now = OffsetDateTime::now_utc();
+    async fn mark_reauthenticated(&self, token: &SessionId) -> Result<Session, AuthError> {
+        self.sessions.stamp_auth(token, self.clock.now()).await
+    }
 
-            // First, try with a read transaction (common path)
-            {
-                let read_txn = db.begin_read()?;
-                let sessions_table = read_txn.open_table(SESSIONS_TABLE)?;
-
-                match sessions_table.get(token.as_str())? {
-                    Some(session_bytes) => {
-                        let session: Session = Self::deserialize(&session_bytes.value())?;
-                        if session.expires_at > now {
-                            debug!(session_id = %token.0, "Valid session found");
-                            return Ok(session);
-                        }
-                        // Session expired - fall through to cleanup with write transaction
-                        debug!(
-                            session_id = %token.0,
-                            expired_at = %session.expires_at,
-                            "Session expired, will clean up"
-                        );
-                    }
-                    None => {
-                        debug!(session_id = %token.0, "Session not found");
-                        return Err(AuthError::InvalidSession);
-                    }
-                }
-            }
+    async fn set_session_data(
+        &self,
+        token: &SessionId,
+        key: &str,
+        value: Option<String>,
+    ) -> Result<Session, AuthError> {
+        self.sessions
+            .set_data(token, key, value, self.clock.now())
+            .await
+    }
 
-            // Session was expired - acquire write transaction to clean up
-            let write_txn = db.begin_write()?;
-            {
-                let mut sessions_table = write_txn.open_table(SESSIONS_TABLE)?;
-                let mut user_sessions_table = write_txn.open_multimap_table(USER_SESSIONS_INDEX)?;
-                let mut session_user_table = write_txn.open_table(SESSION_USER_INDEX)?;
-
-                // Get user_id from reverse index (no deserialization needed)
-                let user_id = session_user_table.get(token.as_str())?.map(|v| v.value());
-
-                if let Some(user_id) = user_id {
-                    Self::remove_session(
-                        &mut sessions_table,
-                        &mut user_sessions_table,
-                        &mut session_user_table,
-                        user_id,
-                        token.as_str(),
-                    )?;
-                }
+    async fn get_session_data(
+        &self,
+        token: &SessionId,
+        key: &str,
+    ) -> Result<Option<String>, AuthError> {
+        let session = self.sessions.get_session(token).await?;
+        Ok(session.data.get(key).cloned())
+    }
+
+    async fn revoke_session(&self, token: &SessionId) -> Result<(), AuthError> {
+        self.sessions.remove_session(token).await
+    }
+
+    async fn revoke_all_sessions(&self, id: &UserId) -> Result<usize, AuthError> {
+        let id = *id;
+
+        self.with_read_txn(move |txn, _| {
+            let users_table = txn.open_table(USERS_TABLE)?;
+            if users_table.get(id.0.as_u128())?.is_none() {
+                debug!(user_id = %id.0, "User not found for session revocation");
+                return Err(AuthError::NotFound);
             }
-            write_txn.commit()?;
+            Ok(())
+        })
+        .await?;
+
+        let session_ids = self.sessions.remove_user_sessions(&id).await?;
+        debug!(user_id = %id.0, count = session_ids.len(), "All user sessions revoked");
+        Ok(session_ids.len())
+    }
+
+    async fn purge_expired_sessions(&self) -> Result<usize, AuthError> {
+        self.sessions.purge_expired().await
+    }
+
+    async fn count_user_sessions(&self, id: &UserId) -> Result<usize, AuthError> {
+        self.sessions.count_user_sessions(id).await
+    }
+
+    async fn list_user_sessions(&self, id: &UserId) -> Result<Vec<Session>, AuthError> {
+        self.sessions.user_sessions(id).await
+    }
 
+    async fn list_user_sessions_page(
+        &self,
+        id: &UserId,
+        after: Option<&SessionId>,
+        limit: usize,
+    ) -> Result<SessionPage, AuthError> {
+        self.sessions.user_sessions_page(id, after, limit).await
+    }
+
+    async fn active_sessions_by_ip(&self) -> Result<BTreeMap<IpAddr, usize>, AuthError> {
+        self.sessions.active_by_ip().await
+    }
+
+    async fn revoke_sessions_where(&self, criteria: &SessionCriteria) -> Result<usize, AuthError> {
+        self.sessions.remove_where(criteria).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::clock::TestClock;
+    use crate::storage::migrations::schema_version;
+
+    #[tokio::test]
+    async fn preview_delete_user_matches_real_delete() {
+        let store = RedbAuthStore::in_memory(5).unwrap();
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let first = store
+            .issue_session(&user.id, ip.clone(), None)
+            .await
+            .unwrap();
+        let second = store.issue_session(&user.id, ip, None).await.unwrap();
+
+        let mut preview = store.preview_delete_user(&user.id).await.unwrap();
+        preview.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut expected = vec![first.id.clone(), second.id.clone()];
+        expected.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(preview, expected);
+
+        // the dry run left everything in place
+        assert!(store.get_user_by_id(&user.id).await.is_ok());
+        assert!(store.fetch_session(&first.id).await.is_ok());
+        assert!(store.fetch_session(&second.id).await.is_ok());
+
+        store.delete_user(&user.id).await.unwrap();
+        assert!(store.fetch_session(&first.id).await.is_err());
+        assert!(store.fetch_session(&second.id).await.is_err());
+        assert!(matches!(
+            store.preview_delete_user(&user.id).await,
+            Err(AuthError::NotFound)
+        ));
+    }
+
+    fn password() -> PasswordHash {
+        PasswordHash::try_from("password").unwrap()
+    }
+
+    #[tokio::test]
+    async fn valid_invite_grants_role_and_counts_use() {
+        let store = RedbAuthStore::in_memory(5).unwrap();
+        let invite = store.create_invite(Role::Admin, 2, None).await.unwrap();
+
+        let user = store
+            .create_user_with_invite(&Username("alice".to_string()), password(), &invite.code)
+            .await
+            .unwrap();
+        assert_eq!(user.role, Role::Admin);
+
+        let invites = store.list_invites().await.unwrap();
+        assert_eq!(invites.len(), 1);
+        assert_eq!(invites[0].uses, 1);
+    }
+
+    #[tokio::test]
+    async fn exhausted_invite_is_rejected() {
+        let store = RedbAuthStore::in_memory(5).unwrap();
+        let invite = store.create_invite(Role::User, 1, None).await.unwrap();
+
+        store
+            .create_user_with_invite(&Username("alice".to_string()), password(), &invite.code)
+            .await
+            .unwrap();
+        assert!(matches!(
+            store
+                .create_user_with_invite(&Username("bob".to_string()), password(), &invite.code)
+                .await,
+            Err(AuthError::InvalidInvite)
+        ));
+        assert!(
+            store
+                .get_user_by_username(&Username("bob".to_string()))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_or_revoked_invite_is_rejected() {
+        let store = RedbAuthStore::in_memory(5).unwrap();
+        let expired = store
+            .create_invite(
+                Role::User,
+                5,
+                Some(OffsetDateTime::now_utc() - time::Duration::minutes(1)),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            store
+                .create_user_with_invite(&Username("alice".to_string()), password(), &expired.code)
+                .await,
+            Err(AuthError::InvalidInvite)
+        ));
+
+        let revoked = store.create_invite(Role::User, 5, None).await.unwrap();
+        store.revoke_invite(&revoked.code).await.unwrap();
+        assert!(matches!(
+            store
+                .create_user_with_invite(&Username("alice".to_string()), password(), &revoked.code)
+                .await,
+            Err(AuthError::InvalidInvite)
+        ));
+    }
+
+    #[tokio::test]
+    async fn passkeys_are_listed_per_user_and_removed_with_user() {
+        let store = RedbAuthStore::in_memory(5).unwrap();
+        let user = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let credential = PasskeyCredential {
+            credential_id: "cred-1".to_string(),
+            user_id: user.id,
+            passkey_json: "{}".to_string(),
+            created_at: OffsetDateTime::now_utc(),
+        };
+
+        store.save_passkey(credential.clone()).await.unwrap();
+        // saving again replaces rather than duplicates
+        store.save_passkey(credential.clone()).await.unwrap();
+        assert_eq!(
+            store.list_passkeys(&user.id).await.unwrap(),
+            vec![credential]
+        );
+        assert!(
+            store
+                .list_passkeys(&UserId::new())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        store.delete_user(&user.id).await.unwrap();
+        assert!(store.list_passkeys(&user.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_registration_does_not_consume_invite() {
+        let store = RedbAuthStore::in_memory(5).unwrap();
+        store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let invite = store.create_invite(Role::User, 1, None).await.unwrap();
+
+        assert!(matches!(
+            store
+                .create_user_with_invite(&Username("alice".to_string()), password(), &invite.code)
+                .await,
+            Err(AuthError::UserExists)
+        ));
+        assert_eq!(store.list_invites().await.unwrap()[0].uses, 0);
+    }
+
+    #[tokio::test]
+    async fn expired_session_is_rejected_and_cleaned_up() {
+        let clock = Arc::new(TestClock::new());
+        let store = RedbAuthStore::in_memory(1)
+            .unwrap()
+            .with_clock(clock.clone());
+        let user = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let session = store
+            .issue_session(&user.id, ip.clone(), None)
+            .await
+            .unwrap();
+
+        clock.advance(SESSION_DURATION - time::Duration::seconds(1));
+        assert!(store.fetch_session(&session.id).await.is_ok());
+
+        clock.advance(time::Duration::seconds(1));
+        assert!(matches!(
+            store.fetch_session(&session.id).await,
             Err(AuthError::InvalidSession)
-        })
-        .await?
+        ));
+        assert!(matches!(
+            store.extend_session(&session.id).await,
+            Err(AuthError::InvalidSession)
+        ));
+
+        // the expired session was removed and no longer counts toward the limit
+        assert!(
+            store
+                .preview_delete_user(&user.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(store.issue_session(&user.id, ip, None).await.is_ok());
     }
 
-    async fn extend_session(&self, token: &SessionId) -> Result<Session, AuthError> {
-        let db = self.db.clone();
-        let token = token.clone();
+    #[tokio::test]
+    async fn record_login_returns_the_previous_login() {
+        let clock = Arc::new(TestClock::new());
+        let store = RedbAuthStore::in_memory(5)
+            .unwrap()
+            .with_clock(clock.clone());
+        let user = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let first_ip = SessionIp(IpAddr::from([10, 0, 0, 1]));
+        let second_ip = SessionIp(IpAddr::from([10, 0, 0, 2]));
+
+        let first_login_at = clock.now();
+        let before_first = store
+            .record_login(&user.id, first_ip.clone())
+            .await
+            .unwrap();
+        assert_eq!(before_first.last_login(), None);
+
+        clock.advance(time::Duration::days(1));
+        let before_second = store
+            .record_login(&user.id, second_ip.clone())
+            .await
+            .unwrap();
+        assert_eq!(before_second.last_login_at, Some(first_login_at));
+        assert_eq!(before_second.last_login_ip, Some(first_ip));
+
+        let current = store.get_user_by_id(&user.id).await.unwrap();
+        assert_eq!(current.last_login_at, Some(clock.now()));
+        assert_eq!(current.last_login_ip, Some(second_ip));
+        assert!(current.password_hash.verify("password"));
+    }
 
-        // Read-first: check if session is valid before acquiring write lock
-        spawn_blocking(move || {
-            let now = OffsetDateTime::now_utc();
-            let new_expires = now + SESSION_DURATION;
-
-            // First, verify session exists and is not expired with read transaction
-            let session_valid = {
-                let read_txn = db.begin_read()?;
-                let sessions_table = read_txn.open_table(SESSIONS_TABLE)?;
-
-                match sessions_table.get(token.as_str())? {
-                    Some(session_bytes) => {
-                        let session: Session = Self::deserialize(&session_bytes.value())?;
-                        if session.expires_at <= now {
-                            debug!(
-                                session_id = %token.0,
-                                expired_at = %session.expires_at,
-                                "Session expired, cannot extend"
-                            );
-                            false
-                        } else {
-                            true
-                        }
-                    }
-                    None => {
-                        debug!(session_id = %token.0, "Session not found");
-                        return Err(AuthError::InvalidSession);
-                    }
-                }
-            };
+    #[tokio::test]
+    async fn session_tokens_have_the_configured_length() {
+        use base64::{Engine as _, engine::general_purpose::URL_SAFE};
+
+        let store = RedbAuthStore::in_memory(10)
+            .unwrap()
+            .with_session_token_bytes(48);
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let session = store
+            .issue_session(&user.id, SessionIp(IpAddr::from([127, 0, 0, 1])), None)
+            .await
+            .unwrap();
+
+        assert!(
+            session
+                .id
+                .0
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '='))
+        );
+        assert_eq!(URL_SAFE.decode(&session.id.0).unwrap().len(), 48);
+        assert!(store.fetch_session(&session.id).await.is_ok());
+    }
 
-            if !session_valid {
-                // Clean up expired session
-                let write_txn = db.begin_write()?;
-                {
-                    let mut sessions_table = write_txn.open_table(SESSIONS_TABLE)?;
-                    let mut user_sessions_table =
-                        write_txn.open_multimap_table(USER_SESSIONS_INDEX)?;
-                    let mut session_user_table = write_txn.open_table(SESSION_USER_INDEX)?;
-
-                    let user_id = session_user_table.get(token.as_str())?.map(|v| v.value());
-
-                    if let Some(user_id) = user_id {
-                        Self::remove_session(
-                            &mut sessions_table,
-                            &mut user_sessions_table,
-                            &mut session_user_table,
-                            user_id,
-                            token.as_str(),
-                        )?;
-                    }
-                }
-                write_txn.commit()?;
-                return Err(AuthError::InvalidSession);
-            }
+    #[tokio::test]
+    async fn disabling_a_user_revokes_their_sessions() {
+        let store = RedbAuthStore::in_memory(5).unwrap();
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let session = store
+            .issue_session(&user.id, ip.clone(), None)
+            .await
+            .unwrap();
+
+        let disabled = store
+            .set_status(&user.id, UserStatus::Disabled)
+            .await
+            .unwrap();
+        assert_eq!(disabled.status, UserStatus::Disabled);
+        assert!(matches!(
+            store.fetch_session(&session.id).await,
+            Err(AuthError::InvalidSession)
+        ));
+        assert!(matches!(
+            store.issue_session(&user.id, ip.clone(), None).await,
+            Err(AuthError::AccountDisabled)
+        ));
+
+        store
+            .set_status(&user.id, UserStatus::Active)
+            .await
+            .unwrap();
+        assert!(store.issue_session(&user.id, ip, None).await.is_ok());
+    }
 
-            // Session is valid - acquire write transaction to extend
-            let write_txn = db.begin_write()?;
-            let result = {
-                let mut sessions_table = write_txn.open_table(SESSIONS_TABLE)?;
-
-                // Re-fetch and update (session might have changed between transactions)
-                let session_data = sessions_table
-                    .get(token.as_str())?
-                    .map(|b| b.value().to_vec());
-
-                match session_data {
-                    Some(session_bytes) => {
-                        let mut session: Session = Self::deserialize(&session_bytes)?;
-
-                        // Re-check expiry (could have expired between read and write)
-                        if session.expires_at <= now {
-                            return Err(AuthError::InvalidSession);
-                        }
-
-                        session.expires_at = new_expires;
-                        let new_session_bytes = Self::serialize(&session)?;
-                        sessions_table.insert(token.as_str(), new_session_bytes)?;
-
-                        trace!(
-                            session_id = %token.0,
-                            new_expires = %new_expires,
-                            "Session extended successfully"
-                        );
-                        Ok(session)
-                    }
-                    None => Err(AuthError::InvalidSession),
-                }
-            };
+    #[tokio::test]
+    async fn active_sessions_are_counted_per_ip() {
+        let clock = Arc::new(TestClock::new());
+        let store = RedbAuthStore::in_memory(10)
+            .unwrap()
+            .with_clock(clock.clone());
+        let alice = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let bob = store
+            .create_standard_user(&Username("bob".to_string()), password())
+            .await
+            .unwrap();
+        let home = IpAddr::from([192, 168, 1, 10]);
+        let office = IpAddr::from([10, 0, 0, 7]);
+
+        // this one expires before the rest are issued
+        store
+            .issue_session(&bob.id, SessionIp(office), None)
+            .await
+            .unwrap();
+        clock.advance(SESSION_DURATION + time::Duration::seconds(1));
+
+        for _ in 0..3 {
+            store
+                .issue_session(&alice.id, SessionIp(home), None)
+                .await
+                .unwrap();
+        }
+        store
+            .issue_session(&bob.id, SessionIp(home), None)
+            .await
+            .unwrap();
+        store
+            .issue_session(&bob.id, SessionIp(office), None)
+            .await
+            .unwrap();
+
+        let counts = store.active_sessions_by_ip().await.unwrap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&home], 4);
+        assert_eq!(counts[&office], 1);
+    }
+
+    #[tokio::test]
+    async fn sessions_are_revoked_by_issue_time_or_address() {
+        let clock = Arc::new(TestClock::new());
+        let store = RedbAuthStore::in_memory(10)
+            .unwrap()
+            .with_clock(clock.clone());
+        let alice = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let bob = store
+            .create_standard_user(&Username("bob".to_string()), password())
+            .await
+            .unwrap();
+        let home = IpAddr::from([192, 168, 1, 10]);
+        let attacker = IpAddr::from([203, 0, 113, 66]);
+
+        let old_home = store
+            .issue_session(&alice.id, SessionIp(home), None)
+            .await
+            .unwrap();
+        let old_attacker = store
+            .issue_session(&bob.id, SessionIp(attacker), None)
+            .await
+            .unwrap();
+        clock.advance(time::Duration::minutes(10));
+        let cutoff = clock.now();
+        let new_home = store
+            .issue_session(&bob.id, SessionIp(home), None)
+            .await
+            .unwrap();
+        let new_attacker = store
+            .issue_session(&alice.id, SessionIp(attacker), None)
+            .await
+            .unwrap();
+
+        // no criteria match nothing rather than everything
+        let none = SessionCriteria::default();
+        assert_eq!(store.revoke_sessions_where(&none).await.unwrap(), 0);
+
+        let from_attacker = SessionCriteria {
+            from_ip: Some(attacker),
+            ..SessionCriteria::default()
+        };
+        assert_eq!(
+            store.revoke_sessions_where(&from_attacker).await.unwrap(),
+            2
+        );
+        assert!(store.fetch_session(&old_attacker.id).await.is_err());
+        assert!(store.fetch_session(&new_attacker.id).await.is_err());
+        assert!(store.fetch_session(&old_home.id).await.is_ok());
+
+        let issued_before = SessionCriteria {
+            before: Some(cutoff),
+            ..SessionCriteria::default()
+        };
+        assert_eq!(
+            store.revoke_sessions_where(&issued_before).await.unwrap(),
+            1
+        );
+        assert!(store.fetch_session(&old_home.id).await.is_err());
+        assert!(store.fetch_session(&new_home.id).await.is_ok());
+
+        // the user index forgets them too
+        assert!(
+            store
+                .list_user_sessions(&alice.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let bobs = store.list_user_sessions(&bob.id).await.unwrap();
+        assert_eq!(bobs.len(), 1);
+        assert_eq!(bobs[0].id, new_home.id);
+    }
+
+    #[tokio::test]
+    async fn renamed_users_are_found_by_their_new_name_only() {
+        let store = RedbAuthStore::in_memory(5).unwrap();
+        let alice = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+
+        let renamed = store
+            .rename_user(&alice.id, &Username("alicia".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(renamed.username.0, "alicia");
+
+        let found = store
+            .get_user_by_username(&Username("alicia".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(found.id, alice.id);
+        assert!(matches!(
+            store
+                .get_user_by_username(&Username("alice".to_string()))
+                .await,
+            Err(AuthError::NotFound)
+        ));
+        // the old name is free again
+        assert!(
+            store
+                .create_standard_user(&Username("alice".to_string()), password())
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn renaming_to_a_taken_username_fails() {
+        let store = RedbAuthStore::in_memory(5).unwrap();
+        let alice = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let bob = store
+            .create_standard_user(&Username("bob".to_string()), password())
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            store.rename_user(&alice.id, &bob.username).await,
+            Err(AuthError::UserExists)
+        ));
+        // nothing changed for either user
+        let found = store.get_user_by_username(&alice.username).await.unwrap();
+        assert_eq!(found.id, alice.id);
+        let found = store.get_user_by_username(&bob.username).await.unwrap();
+        assert_eq!(found.id, bob.id);
+
+        // keeping one's own name is a no-op
+        let same = store.rename_user(&alice.id, &alice.username).await.unwrap();
+        assert_eq!(same.username, alice.username);
+    }
+
+    #[tokio::test]
+    async fn failed_logins_lock_the_account_until_cleared() {
+        let clock = Arc::new(TestClock::new());
+        let store = RedbAuthStore::in_memory(5)
+            .unwrap()
+            .with_clock(clock.clone());
+        let user = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let lock_for = Duration::minutes(15);
+
+        let counted = store
+            .record_failed_login(&user.id, 2, lock_for)
+            .await
+            .unwrap();
+        assert_eq!(counted.failed_logins, 1);
+        assert!(!counted.is_locked(clock.now()));
+
+        let locked = store
+            .record_failed_login(&user.id, 2, lock_for)
+            .await
+            .unwrap();
+        assert_eq!(locked.failed_logins, 0);
+        assert!(locked.is_locked(clock.now()));
+        // the lock runs out on its own
+        assert!(!locked.is_locked(clock.now() + lock_for));
+
+        let cleared = store.clear_lockout(&user.id).await.unwrap();
+        assert!(!cleared.is_locked(clock.now()));
+        let stored = store.get_user_by_id(&user.id).await.unwrap();
+        assert_eq!(stored.locked_until, None);
+
+        // a successful login starts the count over
+        store
+            .record_failed_login(&user.id, 2, lock_for)
+            .await
+            .unwrap();
+        store
+            .record_login(&user.id, SessionIp(IpAddr::from([127, 0, 0, 1])))
+            .await
+            .unwrap();
+        let stored = store.get_user_by_id(&user.id).await.unwrap();
+        assert_eq!(stored.failed_logins, 0);
+    }
 
-            if result.is_ok() {
-                write_txn.commit()?;
+    #[tokio::test]
+    async fn user_sessions_are_listed_newest_first() {
+        let clock = Arc::new(TestClock::new());
+        let store = RedbAuthStore::in_memory(10)
+            .unwrap()
+            .with_clock(clock.clone());
+        let alice = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let bob = store
+            .create_standard_user(&Username("bob".to_string()), password())
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([10, 0, 0, 7]));
+
+        // expired by the time the others are issued
+        store
+            .issue_session(&alice.id, ip.clone(), None)
+            .await
+            .unwrap();
+        clock.advance(SESSION_DURATION + time::Duration::seconds(1));
+        let older = store
+            .issue_session(&alice.id, ip.clone(), None)
+            .await
+            .unwrap();
+        clock.advance(time::Duration::minutes(5));
+        let newer = store
+            .issue_session(&alice.id, ip.clone(), None)
+            .await
+            .unwrap();
+        store.issue_session(&bob.id, ip, None).await.unwrap();
+
+        let sessions = store.list_user_sessions(&alice.id).await.unwrap();
+        let ids: Vec<_> = sessions.iter().map(|session| &session.id).collect();
+        assert_eq!(ids, [&newer.id, &older.id]);
+    }
+
+    #[tokio::test]
+    async fn only_the_first_user_becomes_admin() {
+        let store = RedbAuthStore::in_memory(5).unwrap();
+        let first = store
+            .create_user_or_first_admin(
+                &Username("alice".to_string()),
+                password(),
+                Role::User,
+                UserStatus::Disabled,
+            )
+            .await
+            .unwrap();
+        assert_eq!(first.role, Role::Admin);
+        assert!(first.is_active());
+
+        let second = store
+            .create_user_or_first_admin(
+                &Username("bob".to_string()),
+                password(),
+                Role::User,
+                UserStatus::Disabled,
+            )
+            .await
+            .unwrap();
+        assert_eq!(second.role, Role::User);
+        assert_eq!(second.status, UserStatus::Disabled);
+    }
+
+    #[tokio::test]
+    async fn simultaneous_first_users_make_one_admin() {
+        let store = Arc::new(RedbAuthStore::in_memory(5).unwrap());
+        let registrations: Vec<_> = ["alice", "bob", "carol"]
+            .into_iter()
+            .map(|name| {
+                let store = store.clone();
+                tokio::spawn(async move {
+                    store
+                        .create_user_or_first_admin(
+                            &Username(name.to_string()),
+                            password(),
+                            Role::User,
+                            UserStatus::Active,
+                        )
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect();
+
+        let mut admins = 0;
+        for registration in registrations {
+            if registration.await.unwrap().role == Role::Admin {
+                admins += 1;
             }
-            result
-        })
-        .await?
+        }
+        assert_eq!(admins, 1);
     }
 
-    async fn revoke_session(&self, token: &SessionId) -> Result<(), AuthError> {
-        let token = token.clone();
-
-        self.with_write_txn(move |txn| {
-            let mut sessions_table = txn.open_table(SESSIONS_TABLE)?;
-            let mut user_sessions_table = txn.open_multimap_table(USER_SESSIONS_INDEX)?;
-            let mut session_user_table = txn.open_table(SESSION_USER_INDEX)?;
-
-            // Use reverse index to get user_id directly - O(log N), no deserialization
-            let user_id = session_user_table
-                .get(token.as_str())?
-                .map(|v| v.value())
-                .ok_or_else(|| {
-                    debug!(session_id = %token.0, "Session not found for revocation");
-                    AuthError::InvalidSession
-                })?;
+    #[tokio::test]
+    async fn session_pages_neither_duplicate_nor_skip_sessions_issued_in_between() {
+        let store = RedbAuthStore::in_memory(20).unwrap();
+        let user = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([10, 0, 0, 7]));
+        let mut issued = Vec::new();
+        for _ in 0..6 {
+            let session = store
+                .issue_session(&user.id, ip.clone(), None)
+                .await
+                .unwrap();
+            issued.push(session.id);
+        }
 
-            Self::remove_session(
-                &mut sessions_table,
-                &mut user_sessions_table,
-                &mut session_user_table,
-                user_id,
-                token.as_str(),
-            )?;
+        let first = store
+            .list_user_sessions_page(&user.id, None, 3)
+            .await
+            .unwrap();
+        assert_eq!(first.sessions.len(), 3);
+        for _ in 0..4 {
+            store
+                .issue_session(&user.id, ip.clone(), None)
+                .await
+                .unwrap();
+        }
 
-            debug!(session_id = %token.0, "Session revoked successfully");
-            Ok(())
-        })
-        .await
+        let mut listed: Vec<SessionId> = first.sessions.into_iter().map(|s| s.id).collect();
+        let mut cursor = first.next_cursor;
+        while let Some(after) = cursor {
+            let page = store
+                .list_user_sessions_page(&user.id, Some(&after), 3)
+                .await
+                .unwrap();
+            cursor = page.next_cursor;
+            listed.extend(page.sessions.into_iter().map(|s| s.id));
+        }
+
+        // the new sessions may or may not show up, depending on where their tokens sort
+        let mut unique = listed.clone();
+        unique.sort_by(|a, b| a.0.cmp(&b.0));
+        unique.dedup();
+        assert_eq!(unique.len(), listed.len());
+        for id in &issued {
+            assert!(listed.contains(id));
+        }
+    }
+
+    #[tokio::test]
+    async fn users_survive_a_session_store_reset() {
+        let store = RedbAuthStore::in_memory(5)
+            .unwrap()
+            .with_session_store(RedbSessionStore::in_memory().unwrap());
+        let user = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let session = store
+            .issue_session(&user.id, ip.clone(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(store.reset_sessions().await.unwrap(), 1);
+        assert!(matches!(
+            store.fetch_session(&session.id).await,
+            Err(AuthError::InvalidSession)
+        ));
+        assert!(store.list_user_sessions(&user.id).await.unwrap().is_empty());
+
+        // the account itself is untouched and can sign in again
+        let found = store
+            .get_user_by_username(&Username("alice".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(found.id, user.id);
+        assert!(found.password_hash.verify("password"));
+        let again = store.issue_session(&user.id, ip, None).await.unwrap();
+        assert_eq!(
+            store.fetch_session(&again.id).await.unwrap().user_id,
+            user.id
+        );
+    }
+
+    #[tokio::test]
+    async fn sessions_of_deleted_users_are_refused() {
+        let store = RedbAuthStore::in_memory(5)
+            .unwrap()
+            .with_session_store(RedbSessionStore::in_memory().unwrap());
+        let user = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let session = store
+            .issue_session(&user.id, SessionIp(IpAddr::from([127, 0, 0, 1])), None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.preview_delete_user(&user.id).await.unwrap(),
+            vec![session.id.clone()]
+        );
+        store.delete_user(&user.id).await.unwrap();
+        assert!(matches!(
+            store.fetch_session(&session.id).await,
+            Err(AuthError::InvalidSession)
+        ));
+    }
+
+    #[tokio::test]
+    async fn instance_session_cap_refuses_new_sessions() {
+        let store = RedbAuthStore::in_memory(5)
+            .unwrap()
+            .with_max_total_sessions(Some(2));
+        let alice = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let bob = store
+            .create_standard_user(&Username("bob".to_string()), password())
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+
+        let first = store
+            .issue_session(&alice.id, ip.clone(), None)
+            .await
+            .unwrap();
+        store
+            .issue_session(&bob.id, ip.clone(), None)
+            .await
+            .unwrap();
+        assert_eq!(store.active_session_count(), 2);
+
+        // bob is well below the per-user limit, but the instance is full
+        assert!(matches!(
+            store.issue_session(&bob.id, ip.clone(), None).await,
+            Err(AuthError::InstanceSessionLimitReached)
+        ));
+        assert_eq!(store.active_session_count(), 2);
+
+        store.revoke_session(&first.id).await.unwrap();
+        assert_eq!(store.active_session_count(), 1);
+        store.issue_session(&bob.id, ip, None).await.unwrap();
+        assert_eq!(store.active_session_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn purging_reconciles_the_active_session_count() {
+        let clock = Arc::new(TestClock::new());
+        let store = RedbAuthStore::in_memory(5)
+            .unwrap()
+            .with_clock(clock.clone())
+            .with_max_total_sessions(Some(2));
+        let user = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        for _ in 0..2 {
+            store
+                .issue_session(&user.id, ip.clone(), None)
+                .await
+                .unwrap();
+        }
+
+        // expired, but nothing noticed yet
+        clock.advance(crate::config::SESSION_DURATION + time::Duration::seconds(1));
+        assert_eq!(store.active_session_count(), 2);
+
+        assert_eq!(store.purge_expired_sessions().await.unwrap(), 2);
+        assert_eq!(store.active_session_count(), 0);
+        store.issue_session(&user.id, ip, None).await.unwrap();
+        assert_eq!(store.active_session_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn password_history_is_pruned_to_its_length() {
+        let store = RedbAuthStore::in_memory(5)
+            .unwrap()
+            .with_password_history(2);
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("one").unwrap(),
+            )
+            .await
+            .unwrap();
+        for next in ["two", "three"] {
+            store
+                .set_password_hash(&user.id, PasswordHash::try_from(next).unwrap())
+                .await
+                .unwrap();
+        }
+
+        // the current password and the one before it
+        for recent in ["three", "two"] {
+            assert!(matches!(
+                store.check_password_reuse(&user.id, recent).await,
+                Err(AuthError::PasswordReused)
+            ));
+        }
+        // pushed out of the history
+        store.check_password_reuse(&user.id, "one").await.unwrap();
+        store.check_password_reuse(&user.id, "four").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn migrating_a_v0_database_keeps_users() {
+        let db = Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .unwrap();
+        let id = UserId::new();
+        let old = UserV0 {
+            id,
+            username: Username("alice".to_string()),
+            password_hash: PasswordHash::try_from("password").unwrap(),
+            role: Role::User,
+            status: UserStatus::Active,
+            last_login_at: Some(OffsetDateTime::UNIX_EPOCH),
+            last_login_ip: None,
+            failed_logins: 2,
+            locked_until: None,
+        };
+        let txn = db.begin_write().unwrap();
+        txn.open_table(USERS_TABLE)
+            .unwrap()
+            .insert(
+                id.0.as_u128(),
+                ValueCodec::plaintext().serialize(&old).unwrap(),
+            )
+            .unwrap();
+        txn.commit().unwrap();
+
+        let store = RedbAuthStore::from_database(db, 5).unwrap();
+        assert_eq!(schema_version::<AuthError>(&store.db, SCHEMA).unwrap(), 0);
+        assert_eq!(store.migrate().await.unwrap(), 1);
+
+        let user = store.get_user_by_id(&id).await.unwrap();
+        assert_eq!(user.username.0, "alice");
+        assert_eq!(user.failed_logins, 2);
+        assert_eq!(user.last_login_at, Some(OffsetDateTime::UNIX_EPOCH));
+        assert_eq!(user.enabled_at, None);
+        assert_eq!(store.migrate().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn re_enabling_a_user_records_when() {
+        let clock = Arc::new(TestClock::new());
+        let store = RedbAuthStore::in_memory(5)
+            .unwrap()
+            .with_clock(clock.clone());
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // enabling an active user isn't a re-enabling
+        let user = store
+            .set_status(&user.id, UserStatus::Active)
+            .await
+            .unwrap();
+        assert_eq!(user.enabled_at, None);
+
+        store
+            .set_status(&user.id, UserStatus::Disabled)
+            .await
+            .unwrap();
+        clock.advance(Duration::days(3));
+        let user = store
+            .set_status(&user.id, UserStatus::Active)
+            .await
+            .unwrap();
+        assert_eq!(user.enabled_at, Some(clock.now()));
+        assert_eq!(user.last_active_at(), Some(clock.now()));
+    }
+
+    #[tokio::test]
+    async fn session_data_is_limited_and_survives_extension() {
+        let store = RedbAuthStore::in_memory(5).unwrap();
+        let user = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let session = store
+            .issue_session(&user.id, SessionIp(IpAddr::from([127, 0, 0, 1])), None)
+            .await
+            .unwrap();
+        assert!(session.data.is_empty());
+
+        let updated = store
+            .set_session_data(&session.id, "project", Some("lunch-box".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(updated.data["project"], "lunch-box");
+        assert_eq!(
+            store
+                .get_session_data(&session.id, "project")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("lunch-box")
+        );
+        assert_eq!(
+            store.get_session_data(&session.id, "theme").await.unwrap(),
+            None
+        );
+
+        // over the byte limit, leaving what was there
+        let huge = "x".repeat(Session::MAX_DATA_BYTES);
+        assert!(matches!(
+            store
+                .set_session_data(&session.id, "notes", Some(huge))
+                .await,
+            Err(AuthError::SessionDataTooLarge)
+        ));
+        // over the entry limit
+        for i in 1..Session::MAX_DATA_ENTRIES {
+            store
+                .set_session_data(&session.id, &format!("key{i}"), Some(String::new()))
+                .await
+                .unwrap();
+        }
+        assert!(matches!(
+            store
+                .set_session_data(&session.id, "one-too-many", Some(String::new()))
+                .await,
+            Err(AuthError::SessionDataTooLarge)
+        ));
+
+        let extended = store.extend_session(&session.id).await.unwrap();
+        assert_eq!(extended.data.len(), Session::MAX_DATA_ENTRIES);
+        assert_eq!(extended.data["project"], "lunch-box");
+
+        // removing a key makes room again
+        store
+            .set_session_data(&session.id, "project", None)
+            .await
+            .unwrap();
+        assert_eq!(
+            store
+                .get_session_data(&session.id, "project")
+                .await
+                .unwrap(),
+            None
+        );
+        store
+            .set_session_data(&session.id, "one-too-many", Some(String::new()))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn usernames_differing_in_case_collide_only_when_case_insensitive() {
+        let alice = Username("Alice".to_string());
+        let lower = Username("alice".to_string());
+
+        let store = RedbAuthStore::in_memory(5).unwrap();
+        store
+            .create_standard_user(&alice, password())
+            .await
+            .unwrap();
+        store
+            .create_standard_user(&lower, password())
+            .await
+            .unwrap();
+        assert_eq!(store.list_users().await.unwrap().len(), 2);
+
+        let store = RedbAuthStore::in_memory(5)
+            .unwrap()
+            .with_case_insensitive_usernames(true);
+        let user = store
+            .create_standard_user(&alice, password())
+            .await
+            .unwrap();
+        assert!(matches!(
+            store.create_standard_user(&lower, password()).await,
+            Err(AuthError::UserExists)
+        ));
+        // found by any spelling, but keeps the one it was created with
+        let found = store.get_user_by_username(&lower).await.unwrap();
+        assert_eq!(found.id, user.id);
+        assert_eq!(found.username, alice);
+
+        let renamed = store.rename_user(&user.id, &lower).await.unwrap();
+        assert_eq!(renamed.username, lower);
+        let found = store.get_user_by_username(&alice).await.unwrap();
+        assert_eq!(found.username, lower);
+    }
+
+    #[tokio::test]
+    async fn migrating_rekeys_usernames_for_case_insensitivity() {
+        let store = RedbAuthStore::in_memory(5).unwrap();
+        let alice = store
+            .create_standard_user(&Username("Alice".to_string()), password())
+            .await
+            .unwrap();
+        let lower = Username("alice".to_string());
+        assert!(store.get_user_by_username(&lower).await.is_err());
+
+        let store = store.with_case_insensitive_usernames(true);
+        store.migrate().await.unwrap();
+        let found = store.get_user_by_username(&lower).await.unwrap();
+        assert_eq!(found.id, alice.id);
+
+        // and back again
+        let store = store.with_case_insensitive_usernames(false);
+        store.migrate().await.unwrap();
+        assert!(store.get_user_by_username(&lower).await.is_err());
+
+        // names that would collide keep case-insensitivity from being enabled
+        store
+            .create_standard_user(&lower, password())
+            .await
+            .unwrap();
+        let store = store.with_case_insensitive_usernames(true);
+        assert!(matches!(store.migrate().await, Err(AuthError::Internal(_))));
+        let store = store.with_case_insensitive_usernames(false);
+        assert_eq!(
+            store
+                .get_user_by_username(&alice.username)
+                .await
+                .unwrap()
+                .id,
+            alice.id
+        );
+    }
+
+    #[tokio::test]
+    async fn session_count_follows_issues_revocations_and_expiry() {
+        let clock = Arc::new(TestClock::new());
+        let store = RedbAuthStore::in_memory(5)
+            .unwrap()
+            .with_clock(clock.clone());
+        let user = store
+            .create_standard_user(&Username("alice".to_string()), password())
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        assert_eq!(store.count_user_sessions(&user.id).await.unwrap(), 0);
+
+        let first = store
+            .issue_session(&user.id, ip.clone(), None)
+            .await
+            .unwrap();
+        clock.advance(time::Duration::minutes(10));
+        store.issue_session(&user.id, ip, None).await.unwrap();
+        assert_eq!(store.count_user_sessions(&user.id).await.unwrap(), 2);
+
+        store.revoke_session(&first.id).await.unwrap();
+        assert_eq!(store.count_user_sessions(&user.id).await.unwrap(), 1);
+
+        // expired sessions stop counting before anything cleans them up
+        clock.advance(SESSION_DURATION);
+        assert_eq!(store.count_user_sessions(&user.id).await.unwrap(), 0);
     }
 }
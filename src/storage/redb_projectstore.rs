@@ -1,16 +1,28 @@
 use redb::{
-    Database, MultimapTableDefinition, ReadTransaction, ReadableDatabase, ReadableTable,
-    TableDefinition, WriteTransaction,
+    Database, MultimapTableDefinition, ReadTransaction, ReadableDatabase, ReadableMultimapTable,
+    ReadableTable, TableDefinition, TableHandle, WriteTransaction, backends::InMemoryBackend,
 };
-use serde::{Serialize, de::DeserializeOwned};
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use time::OffsetDateTime;
+use time::{Duration, OffsetDateTime};
 use tokio::task::spawn_blocking;
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
 
+use super::blocking::BlockingLimiter;
+use super::clock::{Clock, SystemClock};
+use super::codec::ValueCodec;
+use super::db_stats::database_report;
+use super::migrations::{Migration, init_schema_version, latest_version, run_migrations};
 use super::{ProjectError, ProjectStore};
-use crate::types::{Project, ProjectId, ProjectSummary, UserId};
+use crate::types::{
+    DatabaseReport, Project, ProjectId, ProjectMetrics, ProjectPage, ProjectSummary,
+    ProjectVisibility, UserId,
+};
+
+/// How long an idempotency key replays its project unless configured otherwise
+pub const DEFAULT_IDEMPOTENCY_WINDOW: Duration = Duration::hours(24);
 
 // Table definitions
 /// Primary table: project_id (u128) -> Project (serialized)
@@ -20,38 +32,356 @@ const PROJECTS_TABLE: TableDefinition<u128, Vec<u8>> = TableDefinition::new("pro
 const USER_PROJECTS_INDEX: MultimapTableDefinition<u128, u128> =
     MultimapTableDefinition::new("user_projects");
 
+/// Index: slug -> project_id (u128) for `/p/{slug}` lookups
+const SLUGS_INDEX: TableDefinition<&str, u128> = TableDefinition::new("project_slugs");
+
+/// project_id (u128) -> ProjectMetrics (serialized), as last collected
+const METRICS_TABLE: TableDefinition<u128, Vec<u8>> = TableDefinition::new("project_metrics");
+
+/// Longest slug derived from a name, before any numeric suffix
+const MAX_SLUG_LEN: usize = 60;
+
+/// "<owner_id>:<idempotency key>" -> IdempotentCreation (serialized)
+const IDEMPOTENCY_KEYS_TABLE: TableDefinition<&str, Vec<u8>> =
+    TableDefinition::new("project_idempotency_keys");
+
+/// Key of this store's schema version in the metadata table
+const SCHEMA: &str = "projects";
+
+/// Applied in order by [`RedbProjectStore::migrate`]; append, never reorder
+const MIGRATIONS: &[Migration<ProjectError>] = &[
+    Migration {
+        version: 1,
+        description: "give every project a slug",
+        apply: RedbProjectStore::add_slugs,
+    },
+    Migration {
+        version: 2,
+        description: "add delete protection to projects",
+        apply: RedbProjectStore::add_delete_protection,
+    },
+];
+
+/// `Project` as stored before slugs
+#[derive(Deserialize)]
+#[cfg_attr(test, derive(Serialize))]
+struct ProjectV0 {
+    id: ProjectId,
+    owner_id: UserId,
+    name: String,
+    description: Option<String>,
+    created_at: OffsetDateTime,
+    updated_at: OffsetDateTime,
+    rate_limit_rpm: Option<u32>,
+    visibility: ProjectVisibility,
+}
+
+/// `Project` as stored before delete protection
+#[derive(Serialize, Deserialize)]
+struct ProjectV1 {
+    id: ProjectId,
+    owner_id: UserId,
+    name: String,
+    description: Option<String>,
+    created_at: OffsetDateTime,
+    updated_at: OffsetDateTime,
+    rate_limit_rpm: Option<u32>,
+    visibility: ProjectVisibility,
+    slug: String,
+}
+
+/// The project created under an idempotency key, and until when the key replays it
+#[derive(Serialize, Deserialize)]
+struct IdempotentCreation {
+    project_id: ProjectId,
+    expires_at: OffsetDateTime,
+}
+
+/// What [`RedbProjectStore::reconcile_indexes`] had to fix in `USER_PROJECTS_INDEX`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexRepair {
+    /// Projects their owner's listing skipped, now indexed
+    pub missing: usize,
+    /// Index entries for projects that no longer exist (or changed owner), now removed
+    pub stale: usize,
+}
+
+impl IndexRepair {
+    /// Whether the index already matched the projects
+    pub fn is_clean(&self) -> bool {
+        self.missing == 0 && self.stale == 0
+    }
+}
+
 #[derive(Clone)]
 pub struct RedbProjectStore {
     db: Arc<Database>,
+    /// Database file, `None` when in memory
+    path: Option<PathBuf>,
+    codec: ValueCodec,
+    blocking: Arc<BlockingLimiter>,
+    clock: Arc<dyn Clock>,
+    idempotency_window: Duration,
+    /// Whether a rename derives a new slug instead of keeping the old one
+    regenerate_slugs: bool,
 }
 
 impl RedbProjectStore {
     pub fn new(path: impl AsRef<Path>) -> Result<Self, ProjectError> {
-        let db = Database::create(path)?;
+        let store = Self::from_database(Database::create(&path)?)?;
+        Ok(Self {
+            path: Some(path.as_ref().to_path_buf()),
+            ..store
+        })
+    }
+
+    /// Creates a store backed by an in-memory redb database (nothing is persisted)
+    pub fn in_memory() -> Result<Self, ProjectError> {
+        Self::from_database(Database::builder().create_with_backend(InMemoryBackend::new())?)
+    }
 
+    fn from_database(db: Database) -> Result<Self, ProjectError> {
         // Initialize tables
         let write_txn = db.begin_write()?;
         {
+            init_schema_version::<ProjectError>(
+                &write_txn,
+                SCHEMA,
+                PROJECTS_TABLE.name(),
+                latest_version(MIGRATIONS),
+            )?;
             let _ = write_txn.open_table(PROJECTS_TABLE)?;
             let _ = write_txn.open_multimap_table(USER_PROJECTS_INDEX)?;
+            let _ = write_txn.open_table(IDEMPOTENCY_KEYS_TABLE)?;
+            let _ = write_txn.open_table(SLUGS_INDEX)?;
+            let _ = write_txn.open_table(METRICS_TABLE)?;
         }
         write_txn.commit()?;
 
-        Ok(Self { db: Arc::new(db) })
+        Ok(Self {
+            db: Arc::new(db),
+            path: None,
+            codec: ValueCodec::plaintext(),
+            blocking: Arc::new(BlockingLimiter::unbounded()),
+            clock: Arc::new(SystemClock),
+            idempotency_window: DEFAULT_IDEMPOTENCY_WINDOW,
+            regenerate_slugs: false,
+        })
+    }
+
+    /// IDs of every project, for the metrics collector to go through
+    pub async fn project_ids(&self) -> Result<Vec<ProjectId>, ProjectError> {
+        self.with_read_txn(move |txn, _| {
+            let projects_table = txn.open_table(PROJECTS_TABLE)?;
+            projects_table
+                .iter()?
+                .map(|entry| Ok(ProjectId(uuid::Uuid::from_u128(entry?.0.value()))))
+                .collect()
+        })
+        .await
     }
 
-    // ==================== Serialization Helpers ====================
+    /// The metrics last stored for a project, zeros if none were collected yet
+    pub async fn project_metrics(
+        &self,
+        project_id: &ProjectId,
+    ) -> Result<ProjectMetrics, ProjectError> {
+        let project_id = *project_id;
 
-    fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, ProjectError> {
-        Ok(bincode::serde::encode_to_vec(
-            value,
-            bincode::config::standard(),
-        )?)
+        self.with_read_txn(move |txn, codec| {
+            let metrics_table = txn.open_table(METRICS_TABLE)?;
+            match metrics_table.get(project_id.0.as_u128())? {
+                Some(bytes) => Ok(codec.deserialize(&bytes.value())?),
+                None => Ok(ProjectMetrics::default()),
+            }
+        })
+        .await
     }
 
-    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, ProjectError> {
-        let (result, _) = bincode::serde::decode_from_slice(bytes, bincode::config::standard())?;
-        Ok(result)
+    /// Caches freshly collected metrics of a project; ignored if the project is gone by now
+    pub async fn store_project_metrics(
+        &self,
+        project_id: &ProjectId,
+        metrics: ProjectMetrics,
+    ) -> Result<(), ProjectError> {
+        let project_id = *project_id;
+
+        self.with_write_txn(move |txn, codec| {
+            if txn
+                .open_table(PROJECTS_TABLE)?
+                .get(project_id.0.as_u128())?
+                .is_none()
+            {
+                trace!(project_id = %project_id.0, "Project deleted before its metrics were stored");
+                return Ok(());
+            }
+            let mut metrics_table = txn.open_table(METRICS_TABLE)?;
+            metrics_table.insert(project_id.0.as_u128(), codec.serialize(&metrics)?)?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Sets the codec used to (de)serialize stored values, e.g. to enable encryption at rest
+    pub fn with_codec(mut self, codec: ValueCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Sets the limiter shared by the stores to bound concurrent blocking operations
+    pub fn with_blocking_limiter(mut self, blocking: Arc<BlockingLimiter>) -> Self {
+        self.blocking = blocking;
+        self
+    }
+
+    /// Size and page usage of the database, e.g. to decide when to compact it
+    pub async fn stats(&self) -> Result<DatabaseReport, ProjectError> {
+        let db = self.db.clone();
+        let path = self.path.clone();
+        spawn_blocking(move || database_report("projects", &db, path.as_deref())).await?
+    }
+
+    /// Brings a database written by an older version up to date, returning how many
+    /// migrations ran. Run once at startup, after `with_codec`.
+    pub async fn migrate(&self) -> Result<usize, ProjectError> {
+        let db = self.db.clone();
+        let codec = self.codec.clone();
+        spawn_blocking(move || run_migrations(&db, SCHEMA, &codec, MIGRATIONS)).await?
+    }
+
+    /// Migration 1: re-encodes projects with a slug derived from their name, and indexes it
+    fn add_slugs(txn: &WriteTransaction, codec: &ValueCodec) -> Result<(), ProjectError> {
+        let mut projects_table = txn.open_table(PROJECTS_TABLE)?;
+        let mut slugs_table = txn.open_table(SLUGS_INDEX)?;
+
+        let legacy = projects_table
+            .iter()?
+            .map(|entry| {
+                let (_, bytes) = entry?;
+                Ok(codec.deserialize::<ProjectV0>(&bytes.value())?)
+            })
+            .collect::<Result<Vec<_>, ProjectError>>()?;
+
+        for old in legacy {
+            let slug = Self::unique_slug(&slugs_table, &old.name)?;
+            slugs_table.insert(slug.as_str(), old.id.0.as_u128())?;
+            let project = ProjectV1 {
+                id: old.id,
+                owner_id: old.owner_id,
+                name: old.name,
+                description: old.description,
+                created_at: old.created_at,
+                updated_at: old.updated_at,
+                rate_limit_rpm: old.rate_limit_rpm,
+                visibility: old.visibility,
+                slug,
+            };
+            projects_table.insert(project.id.0.as_u128(), codec.serialize(&project)?)?;
+        }
+        Ok(())
+    }
+
+    /// Migration 2: re-encodes projects as unprotected
+    fn add_delete_protection(
+        txn: &WriteTransaction,
+        codec: &ValueCodec,
+    ) -> Result<(), ProjectError> {
+        let mut projects_table = txn.open_table(PROJECTS_TABLE)?;
+        let legacy = projects_table
+            .iter()?
+            .map(|entry| {
+                let (_, bytes) = entry?;
+                Ok(codec.deserialize::<ProjectV1>(&bytes.value())?)
+            })
+            .collect::<Result<Vec<_>, ProjectError>>()?;
+
+        for old in legacy {
+            let project = Project {
+                id: old.id,
+                owner_id: old.owner_id,
+                name: old.name,
+                description: old.description,
+                created_at: old.created_at,
+                updated_at: old.updated_at,
+                rate_limit_rpm: old.rate_limit_rpm,
+                visibility: old.visibility,
+                slug: old.slug,
+                delete_protected: false,
+            };
+            projects_table.insert(project.id.0.as_u128(), codec.serialize(&project)?)?;
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the owner index from the projects themselves, e.g. after a crash left them
+    /// diverged (`[storage] reconcile_indexes`). Listings skip projects missing from the
+    /// index, and ids of deleted projects would linger in it.
+    pub async fn reconcile_indexes(&self) -> Result<IndexRepair, ProjectError> {
+        self.with_write_txn(|txn, codec| {
+            let projects_table = txn.open_table(PROJECTS_TABLE)?;
+            let mut user_projects_table = txn.open_multimap_table(USER_PROJECTS_INDEX)?;
+
+            let mut expected = BTreeSet::new();
+            for entry in projects_table.iter()? {
+                let (project_id, bytes) = entry?;
+                let project: Project = codec.deserialize(&bytes.value())?;
+                expected.insert((project.owner_id.0.as_u128(), project_id.value()));
+            }
+            let mut indexed = BTreeSet::new();
+            for entry in user_projects_table.iter()? {
+                let (owner_id, project_ids) = entry?;
+                let owner_id = owner_id.value();
+                for project_id in project_ids {
+                    indexed.insert((owner_id, project_id?.value()));
+                }
+            }
+
+            let stale: Vec<_> = indexed.difference(&expected).copied().collect();
+            let missing: Vec<_> = expected.difference(&indexed).copied().collect();
+            for (owner_id, project_id) in &stale {
+                user_projects_table.remove(owner_id, project_id)?;
+            }
+            for (owner_id, project_id) in &missing {
+                user_projects_table.insert(owner_id, project_id)?;
+            }
+
+            let repair = IndexRepair {
+                missing: missing.len(),
+                stale: stale.len(),
+            };
+            if repair.is_clean() {
+                debug!(
+                    projects = expected.len(),
+                    "Project owner index is consistent"
+                );
+            } else {
+                warn!(
+                    missing = repair.missing,
+                    stale = repair.stale,
+                    "Repaired the project owner index"
+                );
+            }
+            Ok(repair)
+        })
+        .await
+    }
+
+    /// Sets the clock used for timestamps and idempotency key expiry
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Sets how long `create_project` idempotency keys are remembered
+    pub fn with_idempotency_window(mut self, window: Duration) -> Self {
+        self.idempotency_window = window;
+        self
+    }
+
+    /// Sets whether renaming a project gives it a new slug; by default slugs stay stable
+    pub fn with_slug_regeneration(mut self, regenerate: bool) -> Self {
+        self.regenerate_slugs = regenerate;
+        self
     }
 
     // ==================== Transaction Helpers ====================
@@ -60,12 +390,15 @@ impl RedbProjectStore {
     async fn with_read_txn<T, F>(&self, f: F) -> Result<T, ProjectError>
     where
         T: Send + 'static,
-        F: FnOnce(&ReadTransaction) -> Result<T, ProjectError> + Send + 'static,
+        F: FnOnce(&ReadTransaction, &ValueCodec) -> Result<T, ProjectError> + Send + 'static,
     {
         let db = self.db.clone();
+        let codec = self.codec.clone();
+        let permit = self.blocking.acquire().await;
         spawn_blocking(move || {
+            let _permit = permit;
             let txn = db.begin_read()?;
-            f(&txn)
+            f(&txn, &codec)
         })
         .await?
     }
@@ -74,17 +407,123 @@ impl RedbProjectStore {
     async fn with_write_txn<T, F>(&self, f: F) -> Result<T, ProjectError>
     where
         T: Send + 'static,
-        F: FnOnce(&WriteTransaction) -> Result<T, ProjectError> + Send + 'static,
+        F: FnOnce(&WriteTransaction, &ValueCodec) -> Result<T, ProjectError> + Send + 'static,
     {
         let db = self.db.clone();
+        let codec = self.codec.clone();
+        let permit = self.blocking.acquire().await;
         spawn_blocking(move || {
+            let _permit = permit;
             let txn = db.begin_write()?;
-            let result = f(&txn)?;
+            let result = f(&txn, &codec)?;
             txn.commit()?;
             Ok(result)
         })
         .await?
     }
+
+    /// Execute a write operation within a transaction that is always aborted.
+    ///
+    /// Used to preview destructive operations: the closure runs the real mutation logic,
+    /// but nothing is ever committed.
+    async fn with_dry_run_txn<T, F>(&self, f: F) -> Result<T, ProjectError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&WriteTransaction, &ValueCodec) -> Result<T, ProjectError> + Send + 'static,
+    {
+        let db = self.db.clone();
+        let codec = self.codec.clone();
+        let permit = self.blocking.acquire().await;
+        spawn_blocking(move || {
+            let _permit = permit;
+            let txn = db.begin_write()?;
+            let result = f(&txn, &codec);
+            txn.abort()?;
+            result
+        })
+        .await?
+    }
+
+    /// Deletes a project and its index entry within the given transaction, unless it is
+    /// delete protected.
+    ///
+    /// Returns the removed project.
+    fn delete_project_in(
+        txn: &WriteTransaction,
+        codec: &ValueCodec,
+        project_id: ProjectId,
+    ) -> Result<Project, ProjectError> {
+        let mut projects_table = txn.open_table(PROJECTS_TABLE)?;
+        let mut user_projects_table = txn.open_multimap_table(USER_PROJECTS_INDEX)?;
+
+        // First get the project to find the owner_id for index cleanup
+        let project_bytes = projects_table
+            .remove(project_id.0.as_u128())?
+            .ok_or(ProjectError::NotFound)?;
+
+        let project: Project = codec.deserialize(&project_bytes.value())?;
+        // the removal above is rolled back along with the transaction
+        if project.delete_protected {
+            return Err(ProjectError::DeleteProtected);
+        }
+
+        // Remove from the user_projects and slugs indexes
+        user_projects_table.remove(project.owner_id.0.as_u128(), project_id.0.as_u128())?;
+        txn.open_table(SLUGS_INDEX)?.remove(project.slug.as_str())?;
+        txn.open_table(METRICS_TABLE)?
+            .remove(project_id.0.as_u128())?;
+
+        Ok(project)
+    }
+
+    /// Lowercase ASCII letters and digits of `name`, with every other run of characters
+    /// turned into a single hyphen, e.g. "My Awesome Project!" -> "my-awesome-project"
+    fn slug_base(name: &str) -> String {
+        let mut slug = String::new();
+        for c in name.chars() {
+            if c.is_ascii_alphanumeric() {
+                if slug.len() == MAX_SLUG_LEN {
+                    break;
+                }
+                slug.push(c.to_ascii_lowercase());
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        let slug = slug.trim_end_matches('-');
+
+        if slug.is_empty() {
+            "project".to_string()
+        } else {
+            slug.to_string()
+        }
+    }
+
+    /// Picks a slug for `name` that no project uses yet, numbering it from 2 when taken
+    fn unique_slug(
+        slugs_table: &redb::Table<&str, u128>,
+        name: &str,
+    ) -> Result<String, ProjectError> {
+        let base = Self::slug_base(name);
+        let mut candidate = base.clone();
+        let mut n = 2;
+        while slugs_table.get(candidate.as_str())?.is_some() {
+            candidate = format!("{base}-{n}");
+            n += 1;
+        }
+        Ok(candidate)
+    }
+
+    /// Picks a name for a copy of `name` that none of `taken` uses
+    fn copy_name(name: &str, taken: &[String]) -> String {
+        let mut candidate = format!("{name} (copy)");
+        let mut n = 2;
+        while taken.contains(&candidate) {
+            candidate = format!("{name} (copy {n})");
+            n += 1;
+        }
+        candidate
+    }
 }
 
 impl ProjectStore for RedbProjectStore {
@@ -93,32 +532,70 @@ impl ProjectStore for RedbProjectStore {
         owner_id: &UserId,
         name: String,
         description: Option<String>,
-    ) -> Result<Project, ProjectError> {
+        idempotency_key: Option<String>,
+    ) -> Result<(Project, bool), ProjectError> {
         let owner_id = *owner_id;
-        let now = OffsetDateTime::now_utc();
+        let now = self.clock.now();
+        let window = self.idempotency_window;
 
-        self.with_write_txn(move |txn| {
+        self.with_write_txn(move |txn, codec| {
             let mut projects_table = txn.open_table(PROJECTS_TABLE)?;
             let mut user_projects_table = txn.open_multimap_table(USER_PROJECTS_INDEX)?;
+            let mut keys_table = txn.open_table(IDEMPOTENCY_KEYS_TABLE)?;
+            let mut slugs_table = txn.open_table(SLUGS_INDEX)?;
+
+            // Keys only live for the window, so the table stays small enough to sweep here
+            keys_table.retain(|_, bytes| {
+                codec
+                    .deserialize::<IdempotentCreation>(&bytes)
+                    .is_ok_and(|creation| creation.expires_at > now)
+            })?;
+
+            let key = idempotency_key.map(|key| format!("{}:{key}", owner_id.0));
+            if let Some(key) = &key {
+                let earlier = match keys_table.get(key.as_str())? {
+                    Some(bytes) => Some(codec.deserialize::<IdempotentCreation>(&bytes.value())?),
+                    None => None,
+                };
+                // a project deleted in the meantime is simply created again
+                if let Some(earlier) = earlier
+                    && let Some(bytes) = projects_table.get(earlier.project_id.0.as_u128())? {
+                        let project: Project = codec.deserialize(&bytes.value())?;
+                        debug!(project_id = %project.id.0, "Idempotency key replayed");
+                        return Ok((project, false));
+                    }
+            }
 
             let project = Project {
                 id: ProjectId::new(),
                 owner_id,
+                slug: Self::unique_slug(&slugs_table, &name)?,
                 name,
                 description,
                 created_at: now,
                 updated_at: now,
+                rate_limit_rpm: None,
+                visibility: ProjectVisibility::Private,
+                delete_protected: false,
             };
 
-            let project_bytes = Self::serialize(&project)?;
+            let project_bytes = codec.serialize(&project)?;
             let project_id_u128 = project.id.0.as_u128();
             let owner_id_u128 = owner_id.0.as_u128();
 
             projects_table.insert(project_id_u128, project_bytes)?;
             user_projects_table.insert(owner_id_u128, project_id_u128)?;
+            slugs_table.insert(project.slug.as_str(), project_id_u128)?;
+            if let Some(key) = &key {
+                let creation = IdempotentCreation {
+                    project_id: project.id,
+                    expires_at: now + window,
+                };
+                keys_table.insert(key.as_str(), codec.serialize(&creation)?)?;
+            }
 
             trace!(project_id = %project.id.0, owner_id = %owner_id.0, "Project created successfully");
-            Ok(project)
+            Ok((project, true))
         })
         .await
     }
@@ -126,12 +603,12 @@ impl ProjectStore for RedbProjectStore {
     async fn get_project(&self, project_id: &ProjectId) -> Result<Project, ProjectError> {
         let project_id = *project_id;
 
-        self.with_read_txn(move |txn| {
+        self.with_read_txn(move |txn, codec| {
             let projects_table = txn.open_table(PROJECTS_TABLE)?;
 
             match projects_table.get(project_id.0.as_u128())? {
                 Some(project_bytes) => {
-                    let project: Project = Self::deserialize(&project_bytes.value())?;
+                    let project: Project = codec.deserialize(&project_bytes.value())?;
                     debug!(project_id = %project_id.0, "Project found");
                     Ok(project)
                 }
@@ -144,13 +621,52 @@ impl ProjectStore for RedbProjectStore {
         .await
     }
 
+    async fn get_by_slug(&self, slug: &str) -> Result<Project, ProjectError> {
+        let slug = slug.to_string();
+
+        self.with_read_txn(move |txn, codec| {
+            let slugs_table = txn.open_table(SLUGS_INDEX)?;
+            let projects_table = txn.open_table(PROJECTS_TABLE)?;
+
+            let Some(project_id) = slugs_table.get(slug.as_str())? else {
+                debug!(slug, "No project with this slug");
+                return Err(ProjectError::NotFound);
+            };
+            let project_bytes = projects_table
+                .get(project_id.value())?
+                .ok_or(ProjectError::NotFound)?;
+            Ok(codec.deserialize(&project_bytes.value())?)
+        })
+        .await
+    }
+
+    async fn get_projects(&self, project_ids: &[ProjectId]) -> Result<Vec<Project>, ProjectError> {
+        let project_ids = project_ids.to_vec();
+
+        self.with_read_txn(move |txn, codec| {
+            let projects_table = txn.open_table(PROJECTS_TABLE)?;
+
+            let mut projects = Vec::with_capacity(project_ids.len());
+            for project_id in &project_ids {
+                match projects_table.get(project_id.0.as_u128())? {
+                    Some(project_bytes) => {
+                        projects.push(codec.deserialize(&project_bytes.value())?)
+                    }
+                    None => debug!(project_id = %project_id.0, "Project not found, skipping"),
+                }
+            }
+            Ok(projects)
+        })
+        .await
+    }
+
     async fn get_user_projects(
         &self,
         owner_id: &UserId,
     ) -> Result<Vec<ProjectSummary>, ProjectError> {
         let owner_id = *owner_id;
 
-        self.with_read_txn(move |txn| {
+        self.with_read_txn(move |txn, codec| {
             let projects_table = txn.open_table(PROJECTS_TABLE)?;
             let user_projects_table = txn.open_multimap_table(USER_PROJECTS_INDEX)?;
 
@@ -163,13 +679,13 @@ impl ProjectStore for RedbProjectStore {
                 let project_id = project_id_result?.value();
 
                 if let Some(project_bytes) = projects_table.get(project_id)? {
-                    let project: Project = Self::deserialize(&project_bytes.value())?;
+                    let project: Project = codec.deserialize(&project_bytes.value())?;
                     summaries.push(ProjectSummary::from(&project));
                 }
             }
 
             // Sort by created_at descending (newest first)
-            summaries.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+            summaries.sort_by_key(|summary| std::cmp::Reverse(summary.created_at));
 
             debug!(owner_id = %owner_id.0, count = summaries.len(), "Retrieved user projects");
             Ok(summaries)
@@ -177,34 +693,115 @@ impl ProjectStore for RedbProjectStore {
         .await
     }
 
+    async fn get_user_projects_page(
+        &self,
+        owner_id: &UserId,
+        after: Option<ProjectId>,
+        limit: usize,
+    ) -> Result<ProjectPage, ProjectError> {
+        let owner_id = *owner_id;
+
+        self.with_read_txn(move |txn, codec| {
+            let projects_table = txn.open_table(PROJECTS_TABLE)?;
+            let user_projects_table = txn.open_multimap_table(USER_PROJECTS_INDEX)?;
+
+            let project_ids = user_projects_table.get(owner_id.0.as_u128())?;
+            let total = project_ids.len() as usize;
+
+            // The index keeps an owner's ids in ascending order and v7 ids sort by creation
+            // time, so walking it backwards lists newest first. redb can only seek by key in
+            // a multimap, not to a value under one key, so a page still walks the owner's
+            // ids from the newest down to the cursor: O(owned projects) index reads per
+            // page. Skipped ids are not loaded from the projects table, which keeps this
+            // cheap at dashboard sizes; a (owner, project) keyed table would let pages seek
+            // if owners start keeping thousands of projects.
+            let mut projects = Vec::new();
+            let mut next_cursor = None;
+            for project_id in project_ids.rev() {
+                let project_id = project_id?.value();
+                if after.is_some_and(|after| project_id >= after.0.as_u128()) {
+                    continue;
+                }
+                if projects.len() == limit {
+                    next_cursor = projects.last().map(|project: &ProjectSummary| project.id);
+                    break;
+                }
+                if let Some(project_bytes) = projects_table.get(project_id)? {
+                    let project: Project = codec.deserialize(&project_bytes.value())?;
+                    projects.push(ProjectSummary::from(&project));
+                }
+            }
+
+            debug!(owner_id = %owner_id.0, count = projects.len(), "Retrieved a page of user projects");
+            Ok(ProjectPage {
+                projects,
+                total,
+                next_cursor,
+            })
+        })
+        .await
+    }
+
+    async fn search_projects(
+        &self,
+        owner_id: &UserId,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<ProjectSummary>, ProjectError> {
+        // Linear scan over the owner's projects. Fine at dashboard sizes; an index of
+        // lowercased name prefixes per owner would avoid deserializing every project if
+        // owners start keeping thousands of them.
+        let query = query.to_lowercase();
+        let mut matches = self.get_user_projects(owner_id).await?;
+        matches.retain(|project| project.name.to_lowercase().contains(&query));
+        matches.truncate(limit);
+        Ok(matches)
+    }
+
     async fn update_project(
         &self,
         project_id: &ProjectId,
         name: Option<String>,
         description: Option<Option<String>>,
+        rate_limit_rpm: Option<Option<u32>>,
+        visibility: Option<ProjectVisibility>,
     ) -> Result<Project, ProjectError> {
         let project_id = *project_id;
+        let regenerate_slugs = self.regenerate_slugs;
 
-        self.with_write_txn(move |txn| {
+        self.with_write_txn(move |txn, codec| {
             let mut projects_table = txn.open_table(PROJECTS_TABLE)?;
+            let mut slugs_table = txn.open_table(SLUGS_INDEX)?;
 
             let project_bytes = projects_table
                 .get(project_id.0.as_u128())?
                 .map(|bytes| bytes.value().to_vec())
                 .ok_or(ProjectError::NotFound)?;
 
-            let mut project: Project = Self::deserialize(&project_bytes)?;
+            let mut project: Project = codec.deserialize(&project_bytes)?;
 
             // Update fields if provided
             if let Some(new_name) = name {
                 project.name = new_name;
+                // projects from before slugs existed get theirs on the next rename
+                if regenerate_slugs || project.slug.is_empty() {
+                    slugs_table.remove(project.slug.as_str())?;
+                    project.slug = Self::unique_slug(&slugs_table, &project.name)?;
+                    slugs_table.insert(project.slug.as_str(), project_id.0.as_u128())?;
+                }
             }
             if let Some(new_description) = description {
                 project.description = new_description;
             }
+            if let Some(new_rate_limit) = rate_limit_rpm {
+                project.rate_limit_rpm = new_rate_limit;
+            }
+            if let Some(new_visibility) = visibility {
+                project.visibility = new_visibility;
+            }
             project.updated_at = OffsetDateTime::now_utc();
 
-            let new_project_bytes = Self::serialize(&project)?;
+            let new_project_bytes = codec.serialize(&project)?;
             projects_table.insert(project_id.0.as_u128(), new_project_bytes)?;
 
             trace!(project_id = %project_id.0, "Project updated successfully");
@@ -213,26 +810,661 @@ impl ProjectStore for RedbProjectStore {
         .await
     }
 
-    async fn delete_project(&self, project_id: &ProjectId) -> Result<(), ProjectError> {
+    async fn set_delete_protection(
+        &self,
+        project_id: &ProjectId,
+        protected: bool,
+    ) -> Result<Project, ProjectError> {
         let project_id = *project_id;
 
-        self.with_write_txn(move |txn| {
+        self.with_write_txn(move |txn, codec| {
             let mut projects_table = txn.open_table(PROJECTS_TABLE)?;
-            let mut user_projects_table = txn.open_multimap_table(USER_PROJECTS_INDEX)?;
+            let mut project: Project = match projects_table.get(project_id.0.as_u128())? {
+                Some(bytes) => codec.deserialize(&bytes.value())?,
+                None => return Err(ProjectError::NotFound),
+            };
 
-            // First get the project to find the owner_id for index cleanup
-            let project_bytes = projects_table
-                .remove(project_id.0.as_u128())?
-                .ok_or(ProjectError::NotFound)?;
+            project.delete_protected = protected;
+            project.updated_at = OffsetDateTime::now_utc();
+            projects_table.insert(project_id.0.as_u128(), codec.serialize(&project)?)?;
 
-            let project: Project = Self::deserialize(&project_bytes.value())?;
+            trace!(project_id = %project_id.0, protected, "Project delete protection set");
+            Ok(project)
+        })
+        .await
+    }
 
-            // Remove from the user_projects index
-            user_projects_table.remove(project.owner_id.0.as_u128(), project_id.0.as_u128())?;
+    async fn delete_project(&self, project_id: &ProjectId) -> Result<(), ProjectError> {
+        let project_id = *project_id;
 
+        self.with_write_txn(move |txn, codec| {
+            let project = Self::delete_project_in(txn, codec, project_id)?;
             trace!(project_id = %project_id.0, owner_id = %project.owner_id.0, "Project deleted successfully");
             Ok(())
         })
         .await
     }
+
+    async fn preview_delete_project(
+        &self,
+        project_id: &ProjectId,
+    ) -> Result<Project, ProjectError> {
+        let project_id = *project_id;
+
+        self.with_dry_run_txn(move |txn, codec| {
+            let project = Self::delete_project_in(txn, codec, project_id)?;
+            debug!(project_id = %project_id.0, "Previewed project deletion");
+            Ok(project)
+        })
+        .await
+    }
+
+    async fn clone_project(
+        &self,
+        source: &ProjectId,
+        new_owner: &UserId,
+    ) -> Result<Project, ProjectError> {
+        let source = *source;
+        let new_owner = *new_owner;
+        let now = OffsetDateTime::now_utc();
+
+        self.with_write_txn(move |txn, codec| {
+            let mut projects_table = txn.open_table(PROJECTS_TABLE)?;
+            let mut user_projects_table = txn.open_multimap_table(USER_PROJECTS_INDEX)?;
+            let mut slugs_table = txn.open_table(SLUGS_INDEX)?;
+
+            let original: Project = {
+                let project_bytes = projects_table
+                    .get(source.0.as_u128())?
+                    .ok_or(ProjectError::NotFound)?;
+                codec.deserialize(&project_bytes.value())?
+            };
+
+            // Names already used by the new owner, read in the same transaction
+            let mut taken = Vec::new();
+            for project_id in user_projects_table.get(new_owner.0.as_u128())? {
+                if let Some(project_bytes) = projects_table.get(project_id?.value())? {
+                    let project: Project = codec.deserialize(&project_bytes.value())?;
+                    taken.push(project.name);
+                }
+            }
+
+            let name = Self::copy_name(&original.name, &taken);
+            let project = Project {
+                id: ProjectId::new(),
+                owner_id: new_owner,
+                slug: Self::unique_slug(&slugs_table, &name)?,
+                name,
+                description: original.description,
+                created_at: now,
+                updated_at: now,
+                rate_limit_rpm: original.rate_limit_rpm,
+                // a copy starts out private and unprotected, whatever the source's settings
+                visibility: ProjectVisibility::Private,
+                delete_protected: false,
+            };
+
+            projects_table.insert(project.id.0.as_u128(), codec.serialize(&project)?)?;
+            user_projects_table.insert(new_owner.0.as_u128(), project.id.0.as_u128())?;
+            slugs_table.insert(project.slug.as_str(), project.id.0.as_u128())?;
+
+            trace!(
+                source_id = %source.0,
+                project_id = %project.id.0,
+                owner_id = %new_owner.0,
+                "Project cloned successfully"
+            );
+            Ok(project)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::clock::TestClock;
+    use crate::storage::migrations::schema_version;
+
+    #[tokio::test]
+    async fn preview_delete_project_does_not_mutate() {
+        let store = RedbProjectStore::in_memory().unwrap();
+        let owner = UserId::new();
+        let project = store
+            .create_project(&owner, "Preview".to_string(), None, None)
+            .await
+            .unwrap()
+            .0;
+
+        let preview = store.preview_delete_project(&project.id).await.unwrap();
+        assert_eq!(preview, project);
+
+        // nothing was removed by the dry run
+        assert_eq!(store.get_project(&project.id).await.unwrap(), project);
+        assert_eq!(store.get_user_projects(&owner).await.unwrap().len(), 1);
+
+        store.delete_project(&project.id).await.unwrap();
+        assert!(matches!(
+            store.get_project(&project.id).await,
+            Err(ProjectError::NotFound)
+        ));
+        assert!(matches!(
+            store.preview_delete_project(&project.id).await,
+            Err(ProjectError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn cloned_project_is_independent() {
+        let store = RedbProjectStore::in_memory().unwrap();
+        let owner = UserId::new();
+        let original = store
+            .create_project(
+                &owner,
+                "Lunch".to_string(),
+                Some("Bento box".to_string()),
+                None,
+            )
+            .await
+            .unwrap()
+            .0;
+
+        let copy = store.clone_project(&original.id, &owner).await.unwrap();
+        assert_ne!(copy.id, original.id);
+        assert_eq!(copy.name, "Lunch (copy)");
+        assert_eq!(copy.description, original.description);
+        assert_eq!(store.get_user_projects(&owner).await.unwrap().len(), 2);
+
+        // a second copy gets a distinct name
+        let second = store.clone_project(&original.id, &owner).await.unwrap();
+        assert_eq!(second.name, "Lunch (copy 2)");
+
+        // changes to either side don't leak into the other
+        store
+            .update_project(&copy.id, Some("Dinner".to_string()), Some(None), None, None)
+            .await
+            .unwrap();
+        assert_eq!(store.get_project(&original.id).await.unwrap(), original);
+
+        store.delete_project(&original.id).await.unwrap();
+        assert_eq!(store.get_project(&copy.id).await.unwrap().name, "Dinner");
+    }
+
+    #[tokio::test]
+    async fn get_projects_skips_missing_ids() {
+        let store = RedbProjectStore::in_memory().unwrap();
+        let (alice, bob) = (UserId::new(), UserId::new());
+        let lunch = store
+            .create_project(&alice, "Lunch".to_string(), None, None)
+            .await
+            .unwrap()
+            .0;
+        let dinner = store
+            .create_project(&bob, "Dinner".to_string(), None, None)
+            .await
+            .unwrap()
+            .0;
+
+        let ids = [dinner.id, ProjectId::new(), lunch.id, ProjectId::new()];
+        let projects = store.get_projects(&ids).await.unwrap();
+
+        let names: Vec<_> = projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Dinner", "Lunch"]);
+        assert!(store.get_projects(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn idempotency_key_creates_one_project() {
+        let clock = Arc::new(TestClock::new());
+        let store = RedbProjectStore::in_memory()
+            .unwrap()
+            .with_clock(clock.clone())
+            .with_idempotency_window(Duration::minutes(10));
+        let owner = UserId::new();
+        let key = || Some("retry-me".to_string());
+
+        let (first, created) = store
+            .create_project(&owner, "Lunch".to_string(), None, key())
+            .await
+            .unwrap();
+        assert!(created);
+        let (second, created) = store
+            .create_project(&owner, "Lunch".to_string(), None, key())
+            .await
+            .unwrap();
+        assert!(!created);
+        assert_eq!(second, first);
+        assert_eq!(store.get_user_projects(&owner).await.unwrap().len(), 1);
+
+        // keys are scoped to their owner
+        let (other, created) = store
+            .create_project(&UserId::new(), "Lunch".to_string(), None, key())
+            .await
+            .unwrap();
+        assert!(created);
+        assert_ne!(other.id, first.id);
+
+        // and forgotten once the window has passed
+        clock.advance(Duration::minutes(11));
+        let (later, created) = store
+            .create_project(&owner, "Lunch".to_string(), None, key())
+            .await
+            .unwrap();
+        assert!(created);
+        assert_ne!(later.id, first.id);
+        assert_eq!(store.get_user_projects(&owner).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn stats_follow_stored_data() {
+        let path = std::env::temp_dir().join(format!("bento-stats-{}.db", uuid::Uuid::now_v7()));
+        let store = RedbProjectStore::new(&path).unwrap();
+        let owner = UserId::new();
+        let empty = store.stats().await.unwrap();
+        assert_eq!(empty.name, "projects");
+        assert!(empty.file_size_bytes.is_some());
+
+        let mut ids = Vec::new();
+        for i in 0..200 {
+            let (project, _) = store
+                .create_project(&owner, format!("Project {i}"), Some("x".repeat(400)), None)
+                .await
+                .unwrap();
+            ids.push(project.id);
+        }
+        let full = store.stats().await.unwrap();
+        assert!(full.stored_bytes > empty.stored_bytes + 200 * 400);
+        assert!(full.allocated_pages > empty.allocated_pages);
+        assert!(full.file_size_bytes.unwrap() >= full.stored_bytes);
+
+        for id in &ids {
+            store.delete_project(id).await.unwrap();
+        }
+        let emptied = store.stats().await.unwrap();
+        assert!(emptied.stored_bytes < full.stored_bytes);
+        assert!((0.0..=1.0).contains(&emptied.fragmentation()));
+
+        drop(store);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    async fn store_with_projects(owner: &UserId, names: &[&str]) -> RedbProjectStore {
+        let clock = Arc::new(TestClock::new());
+        let store = RedbProjectStore::in_memory()
+            .unwrap()
+            .with_clock(clock.clone());
+        for name in names {
+            clock.advance(Duration::seconds(1));
+            store
+                .create_project(owner, name.to_string(), None, None)
+                .await
+                .unwrap();
+        }
+        store
+    }
+
+    fn names(projects: &[ProjectSummary]) -> Vec<&str> {
+        projects.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn search_matches_substrings_of_the_owners_projects() {
+        let owner = UserId::new();
+        let store = store_with_projects(&owner, &["Lunch box", "Dinner", "Bento lunch"]).await;
+        store
+            .create_project(
+                &UserId::new(),
+                "Someone else's lunch".to_string(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let found = store.search_projects(&owner, "unch", 10).await.unwrap();
+        assert_eq!(names(&found), ["Bento lunch", "Lunch box"]);
+
+        let found = store.search_projects(&owner, "unch", 1).await.unwrap();
+        assert_eq!(names(&found), ["Bento lunch"]);
+    }
+
+    #[tokio::test]
+    async fn search_without_a_match_is_empty() {
+        let owner = UserId::new();
+        let store = store_with_projects(&owner, &["Lunch box", "Dinner"]).await;
+
+        assert!(
+            store
+                .search_projects(&owner, "breakfast", 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            store
+                .search_projects(&UserId::new(), "lunch", 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn search_ignores_case() {
+        let owner = UserId::new();
+        let store = store_with_projects(&owner, &["Bento BOX"]).await;
+
+        for query in ["bento box", "BENTO", "Box", "tO b"] {
+            let found = store.search_projects(&owner, query, 10).await.unwrap();
+            assert_eq!(names(&found), ["Bento BOX"], "query {query:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn pages_continue_from_the_cursor_newest_first() {
+        let owner = UserId::new();
+        let store = store_with_projects(&owner, &["a", "b", "c", "d", "e"]).await;
+
+        let first = store.get_user_projects_page(&owner, None, 2).await.unwrap();
+        assert_eq!(names(&first.projects), ["e", "d"]);
+        assert_eq!(first.total, 5);
+
+        let second = store
+            .get_user_projects_page(&owner, first.next_cursor, 2)
+            .await
+            .unwrap();
+        assert_eq!(names(&second.projects), ["c", "b"]);
+
+        let last = store
+            .get_user_projects_page(&owner, second.next_cursor, 2)
+            .await
+            .unwrap();
+        assert_eq!(names(&last.projects), ["a"]);
+        assert_eq!(last.next_cursor, None);
+    }
+
+    #[tokio::test]
+    async fn changes_mid_pagination_neither_duplicate_nor_skip_projects() {
+        let owner = UserId::new();
+        let store = store_with_projects(&owner, &["a", "b", "c", "d", "e"]).await;
+
+        let first = store.get_user_projects_page(&owner, None, 2).await.unwrap();
+        // a new project lands in front, and one already shown goes away
+        store
+            .create_project(&owner, "new".to_string(), None, None)
+            .await
+            .unwrap();
+        store.delete_project(&first.projects[0].id).await.unwrap();
+
+        let mut listed = names(&first.projects);
+        let mut cursor = first.next_cursor;
+        let mut rest = Vec::new();
+        while let Some(after) = cursor {
+            let page = store
+                .get_user_projects_page(&owner, Some(after), 2)
+                .await
+                .unwrap();
+            cursor = page.next_cursor;
+            rest.extend(page.projects);
+        }
+        listed.extend(names(&rest));
+        assert_eq!(listed, ["e", "d", "c", "b", "a"]);
+    }
+
+    #[test]
+    fn slugs_are_lowercase_and_hyphenated() {
+        for (name, slug) in [
+            ("Lunch", "lunch"),
+            ("My Awesome Project!", "my-awesome-project"),
+            ("  Bento -- Box  ", "bento-box"),
+            ("Café 2", "caf-2"),
+            ("!!!", "project"),
+        ] {
+            assert_eq!(RedbProjectStore::slug_base(name), slug, "name {name:?}");
+        }
+        let long = "a".repeat(MAX_SLUG_LEN * 2);
+        assert_eq!(RedbProjectStore::slug_base(&long).len(), MAX_SLUG_LEN);
+    }
+
+    #[tokio::test]
+    async fn colliding_slugs_get_a_numeric_suffix() {
+        let store = RedbProjectStore::in_memory().unwrap();
+        let (alice, bob) = (UserId::new(), UserId::new());
+
+        let mut projects = Vec::new();
+        for (owner, name) in [(&alice, "Lunch"), (&alice, "lunch!"), (&bob, "LUNCH")] {
+            let (project, _) = store
+                .create_project(owner, name.to_string(), None, None)
+                .await
+                .unwrap();
+            projects.push(project);
+        }
+        let slugs: Vec<_> = projects.iter().map(|p| p.slug.as_str()).collect();
+        assert_eq!(slugs, ["lunch", "lunch-2", "lunch-3"]);
+
+        // copies take their slug from the copy's name
+        let copy = store.clone_project(&projects[2].id, &bob).await.unwrap();
+        assert_eq!(copy.slug, "lunch-copy");
+    }
+
+    #[tokio::test]
+    async fn projects_are_found_by_slug_until_deleted() {
+        let store = RedbProjectStore::in_memory().unwrap();
+        let owner = UserId::new();
+        let (lunch, _) = store
+            .create_project(&owner, "Lunch box".to_string(), None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(store.get_by_slug("lunch-box").await.unwrap(), lunch);
+        assert!(matches!(
+            store.get_by_slug("dinner").await,
+            Err(ProjectError::NotFound)
+        ));
+
+        // deleting frees the slug for the next project with that name
+        store.delete_project(&lunch.id).await.unwrap();
+        assert!(matches!(
+            store.get_by_slug("lunch-box").await,
+            Err(ProjectError::NotFound)
+        ));
+        let (again, _) = store
+            .create_project(&owner, "Lunch box".to_string(), None, None)
+            .await
+            .unwrap();
+        assert_eq!(again.slug, "lunch-box");
+    }
+
+    #[tokio::test]
+    async fn renames_keep_the_slug_unless_regeneration_is_on() {
+        for regenerate in [false, true] {
+            let store = RedbProjectStore::in_memory()
+                .unwrap()
+                .with_slug_regeneration(regenerate);
+            let (project, _) = store
+                .create_project(&UserId::new(), "Lunch".to_string(), None, None)
+                .await
+                .unwrap();
+
+            let renamed = store
+                .update_project(&project.id, Some("Dinner".to_string()), None, None, None)
+                .await
+                .unwrap();
+
+            let expected = if regenerate { "dinner" } else { "lunch" };
+            assert_eq!(renamed.slug, expected, "regenerate {regenerate}");
+            assert_eq!(store.get_by_slug(expected).await.unwrap().id, project.id);
+            if regenerate {
+                assert!(store.get_by_slug("lunch").await.is_err());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn migrating_a_v0_database_gives_projects_slugs() {
+        // what a build from before schema versions left behind: no metadata, no slugs
+        let db = Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .unwrap();
+        let owner = UserId::new();
+        let id = ProjectId::new();
+        let old = ProjectV0 {
+            id,
+            owner_id: owner,
+            name: "Lunch Box".to_string(),
+            description: None,
+            created_at: OffsetDateTime::UNIX_EPOCH,
+            updated_at: OffsetDateTime::UNIX_EPOCH,
+            rate_limit_rpm: None,
+            visibility: ProjectVisibility::Public,
+        };
+        let txn = db.begin_write().unwrap();
+        txn.open_table(PROJECTS_TABLE)
+            .unwrap()
+            .insert(
+                id.0.as_u128(),
+                ValueCodec::plaintext().serialize(&old).unwrap(),
+            )
+            .unwrap();
+        txn.open_multimap_table(USER_PROJECTS_INDEX)
+            .unwrap()
+            .insert(owner.0.as_u128(), id.0.as_u128())
+            .unwrap();
+        txn.commit().unwrap();
+
+        let store = RedbProjectStore::from_database(db).unwrap();
+        assert_eq!(
+            schema_version::<ProjectError>(&store.db, SCHEMA).unwrap(),
+            0
+        );
+        assert_eq!(store.migrate().await.unwrap(), 2);
+        assert_eq!(
+            schema_version::<ProjectError>(&store.db, SCHEMA).unwrap(),
+            latest_version(MIGRATIONS)
+        );
+
+        let project = store.get_by_slug("lunch-box").await.unwrap();
+        assert!(!project.delete_protected);
+        assert_eq!(project.id, id);
+        assert_eq!(project.name, "Lunch Box");
+        assert_eq!(project.visibility, ProjectVisibility::Public);
+        assert_eq!(store.get_project(&id).await.unwrap(), project);
+
+        // already current, so nothing runs twice
+        assert_eq!(store.migrate().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn new_databases_need_no_migrations() {
+        let store = RedbProjectStore::in_memory().unwrap();
+        assert_eq!(
+            schema_version::<ProjectError>(&store.db, SCHEMA).unwrap(),
+            latest_version(MIGRATIONS)
+        );
+        assert_eq!(store.migrate().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn reconciling_restores_listings_after_index_corruption() {
+        let store = RedbProjectStore::in_memory().unwrap();
+        let owner = UserId::new();
+        let kept = store
+            .create_project(&owner, "Kept".to_string(), None, None)
+            .await
+            .unwrap()
+            .0;
+        let dropped = store
+            .create_project(&owner, "Dropped".to_string(), None, None)
+            .await
+            .unwrap()
+            .0;
+        assert!(store.reconcile_indexes().await.unwrap().is_clean());
+
+        // as if a crash lost one entry and another survived its project
+        let ghost = ProjectId::new();
+        let txn = store.db.begin_write().unwrap();
+        {
+            let mut index = txn.open_multimap_table(USER_PROJECTS_INDEX).unwrap();
+            index
+                .remove(owner.0.as_u128(), dropped.id.0.as_u128())
+                .unwrap();
+            index.insert(owner.0.as_u128(), ghost.0.as_u128()).unwrap();
+        }
+        txn.commit().unwrap();
+        let listed = store.get_user_projects(&owner).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(
+            store
+                .get_user_projects_page(&owner, None, 10)
+                .await
+                .unwrap()
+                .total,
+            2
+        );
+
+        assert_eq!(
+            store.reconcile_indexes().await.unwrap(),
+            IndexRepair {
+                missing: 1,
+                stale: 1
+            }
+        );
+        let mut ids: Vec<_> = store
+            .get_user_projects(&owner)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|project| project.id)
+            .collect();
+        ids.sort_by_key(|id| id.0);
+        let mut expected = vec![kept.id, dropped.id];
+        expected.sort_by_key(|id| id.0);
+        assert_eq!(ids, expected);
+        let page = store
+            .get_user_projects_page(&owner, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.projects.len(), 2);
+
+        assert!(store.reconcile_indexes().await.unwrap().is_clean());
+    }
+
+    #[tokio::test]
+    async fn protected_projects_cant_be_deleted_until_unprotected() {
+        let store = RedbProjectStore::in_memory().unwrap();
+        let owner = UserId::new();
+        let (project, _) = store
+            .create_project(&owner, "Keep me".to_string(), None, None)
+            .await
+            .unwrap();
+        assert!(!project.delete_protected);
+
+        let protected = store
+            .set_delete_protection(&project.id, true)
+            .await
+            .unwrap();
+        assert!(protected.delete_protected);
+        assert!(matches!(
+            store.delete_project(&project.id).await,
+            Err(ProjectError::DeleteProtected)
+        ));
+        assert!(matches!(
+            store.preview_delete_project(&project.id).await,
+            Err(ProjectError::DeleteProtected)
+        ));
+        // the refused deletion left the project and its indexes alone
+        assert_eq!(store.get_by_slug("keep-me").await.unwrap().id, project.id);
+        assert_eq!(store.get_user_projects(&owner).await.unwrap().len(), 1);
+
+        store
+            .set_delete_protection(&project.id, false)
+            .await
+            .unwrap();
+        store.delete_project(&project.id).await.unwrap();
+        assert!(matches!(
+            store.get_project(&project.id).await,
+            Err(ProjectError::NotFound)
+        ));
+    }
 }
@@ -0,0 +1,1007 @@
+//! Sessions in a redb database of their own, or in the auth database (`[session] store`).
+
+use redb::{
+    Database, MultimapTableDefinition, ReadTransaction, ReadableDatabase, ReadableMultimapTable,
+    ReadableTable, ReadableTableMetadata, TableDefinition, TableHandle, WriteTransaction,
+    backends::InMemoryBackend,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use time::OffsetDateTime;
+use tokio::task::spawn_blocking;
+use tracing::{debug, trace};
+
+use super::blocking::BlockingLimiter;
+use super::clock::{Clock, SystemClock};
+use super::codec::ValueCodec;
+use super::db_stats::database_report;
+use super::maintenance::MaintenanceStats;
+use super::migrations::{Migration, init_schema_version, latest_version, run_migrations};
+use super::{AuthError, SessionStore};
+use crate::types::{
+    DatabaseReport, Session, SessionCriteria, SessionId, SessionIp, SessionPage, UserAgentHash,
+    UserId,
+};
+
+const SESSIONS_TABLE: TableDefinition<&str, Vec<u8>> = TableDefinition::new("sessions");
+
+/// Multimap index: user_id -> session_id for O(1) add/remove operations
+const USER_SESSIONS_INDEX: MultimapTableDefinition<u128, &str> =
+    MultimapTableDefinition::new("user_sessions_v2");
+
+/// Reverse index: session_id -> user_id for O(1) lookup without deserializing session
+const SESSION_USER_INDEX: TableDefinition<&str, u128> = TableDefinition::new("session_user");
+
+/// Key of this store's schema version in the metadata table
+const SCHEMA: &str = "sessions";
+
+/// Applied in order by [`RedbSessionStore::migrate`]; append, never reorder
+const MIGRATIONS: &[Migration<AuthError>] = &[
+    Migration {
+        version: 1,
+        description: "add the re-authentication time to sessions",
+        apply: RedbSessionStore::add_last_auth_at,
+    },
+    Migration {
+        version: 2,
+        description: "add the impersonating admin to sessions",
+        apply: RedbSessionStore::add_impersonator,
+    },
+    Migration {
+        version: 3,
+        description: "add custom data to sessions",
+        apply: RedbSessionStore::add_session_data,
+    },
+];
+
+/// `Session` as stored before `last_auth_at`
+#[derive(Deserialize)]
+struct SessionV0 {
+    id: SessionId,
+    user_id: UserId,
+    ip: SessionIp,
+    created_at: OffsetDateTime,
+    expires_at: OffsetDateTime,
+    last_seen_at: OffsetDateTime,
+    user_agent: Option<UserAgentHash>,
+}
+
+/// `Session` as stored before `impersonator`
+#[derive(Serialize, Deserialize)]
+struct SessionV1 {
+    id: SessionId,
+    user_id: UserId,
+    ip: SessionIp,
+    created_at: OffsetDateTime,
+    expires_at: OffsetDateTime,
+    last_seen_at: OffsetDateTime,
+    user_agent: Option<UserAgentHash>,
+    last_auth_at: Option<OffsetDateTime>,
+}
+
+/// `Session` as stored before `data`
+#[derive(Serialize, Deserialize)]
+struct SessionV2 {
+    id: SessionId,
+    user_id: UserId,
+    ip: SessionIp,
+    created_at: OffsetDateTime,
+    expires_at: OffsetDateTime,
+    last_seen_at: OffsetDateTime,
+    user_agent: Option<UserAgentHash>,
+    last_auth_at: Option<OffsetDateTime>,
+    impersonator: Option<UserId>,
+}
+
+/// The leading fields of a stored `Session`, up to its expiry. bincode reads a prefix of the
+/// encoding, so sessions are told apart as active or expired without decoding the rest.
+#[derive(Deserialize)]
+struct SessionHead {
+    _id: SessionId,
+    _user_id: UserId,
+    _ip: SessionIp,
+    _created_at: OffsetDateTime,
+    expires_at: OffsetDateTime,
+}
+
+#[derive(Clone)]
+pub struct RedbSessionStore {
+    db: Arc<Database>,
+    /// Database file, `None` when in memory or shared with the auth store
+    path: Option<PathBuf>,
+    codec: ValueCodec,
+    blocking: Arc<BlockingLimiter>,
+    clock: Arc<dyn Clock>,
+    stats: Arc<MaintenanceStats>,
+    /// Sessions believed active, for `max_total`. Sessions expiring unnoticed keep being
+    /// counted until they are purged; `purge_expired` recounts.
+    active: Arc<AtomicUsize>,
+    /// Cap on `active` across all users, `None` for no cap
+    max_total: Option<usize>,
+}
+
+impl RedbSessionStore {
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, AuthError> {
+        let store = Self::in_database(Arc::new(Database::create(&path)?))?;
+        Ok(Self {
+            path: Some(path.as_ref().to_path_buf()),
+            ..store
+        })
+    }
+
+    /// Creates a store backed by an in-memory redb database, so sessions don't outlive the
+    /// process
+    pub fn in_memory() -> Result<Self, AuthError> {
+        let db = Database::builder().create_with_backend(InMemoryBackend::new())?;
+        Self::in_database(Arc::new(db))
+    }
+
+    /// Keeps the session tables in `db`, e.g. the auth store's own database
+    pub(super) fn in_database(db: Arc<Database>) -> Result<Self, AuthError> {
+        let write_txn = db.begin_write()?;
+        let stored = {
+            init_schema_version::<AuthError>(
+                &write_txn,
+                SCHEMA,
+                SESSIONS_TABLE.name(),
+                latest_version(MIGRATIONS),
+            )?;
+            let sessions_table = write_txn.open_table(SESSIONS_TABLE)?;
+            let _ = write_txn.open_multimap_table(USER_SESSIONS_INDEX)?;
+            let _ = write_txn.open_table(SESSION_USER_INDEX)?;
+            sessions_table.len()?
+        };
+        write_txn.commit()?;
+
+        Ok(Self {
+            db,
+            path: None,
+            codec: ValueCodec::plaintext(),
+            blocking: Arc::new(BlockingLimiter::unbounded()),
+            clock: Arc::new(SystemClock),
+            stats: Arc::new(MaintenanceStats::new()),
+            // expired leftovers included; the first sweep corrects it
+            active: Arc::new(AtomicUsize::new(stored as usize)),
+            max_total: None,
+        })
+    }
+
+    /// Sets the codec used to (de)serialize stored sessions
+    pub fn with_codec(mut self, codec: ValueCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Sets the limiter shared by the stores to bound concurrent blocking operations
+    pub fn with_blocking_limiter(mut self, blocking: Arc<BlockingLimiter>) -> Self {
+        self.blocking = blocking;
+        self
+    }
+
+    /// Sets the clock used for expiry checks
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Sets the counters that expired sessions cleaned up along the way are recorded in
+    pub fn with_maintenance_stats(mut self, stats: Arc<MaintenanceStats>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Caps the number of active sessions across all users (`[session] max_total`); new
+    /// sessions beyond it are refused with `AuthError::InstanceSessionLimitReached`
+    pub fn with_max_total(mut self, max_total: Option<usize>) -> Self {
+        self.max_total = max_total;
+        self
+    }
+
+    /// Sessions counted against `max_total`
+    pub fn active_count(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Brings sessions written by an older version up to date, returning how many
+    /// migrations ran. Run once at startup, after `with_codec`.
+    pub async fn migrate(&self) -> Result<usize, AuthError> {
+        let db = self.db.clone();
+        let codec = self.codec.clone();
+        spawn_blocking(move || run_migrations(&db, SCHEMA, &codec, MIGRATIONS)).await?
+    }
+
+    /// Migration 1: re-encodes sessions with no re-authentication time
+    fn add_last_auth_at(txn: &WriteTransaction, codec: &ValueCodec) -> Result<(), AuthError> {
+        let mut sessions_table = txn.open_table(SESSIONS_TABLE)?;
+        let legacy = sessions_table
+            .iter()?
+            .map(|entry| {
+                let (key, bytes) = entry?;
+                let old: SessionV0 = codec.deserialize(&bytes.value())?;
+                Ok((key.value().to_string(), old))
+            })
+            .collect::<Result<Vec<_>, AuthError>>()?;
+
+        for (key, old) in legacy {
+            let session = SessionV1 {
+                id: old.id,
+                user_id: old.user_id,
+                ip: old.ip,
+                created_at: old.created_at,
+                expires_at: old.expires_at,
+                last_seen_at: old.last_seen_at,
+                user_agent: old.user_agent,
+                last_auth_at: None,
+            };
+            sessions_table.insert(key.as_str(), codec.serialize(&session)?)?;
+        }
+        Ok(())
+    }
+
+    /// Migration 2: re-encodes sessions as not impersonating anyone
+    fn add_impersonator(txn: &WriteTransaction, codec: &ValueCodec) -> Result<(), AuthError> {
+        let mut sessions_table = txn.open_table(SESSIONS_TABLE)?;
+        let legacy = sessions_table
+            .iter()?
+            .map(|entry| {
+                let (key, bytes) = entry?;
+                let old: SessionV1 = codec.deserialize(&bytes.value())?;
+                Ok((key.value().to_string(), old))
+            })
+            .collect::<Result<Vec<_>, AuthError>>()?;
+
+        for (key, old) in legacy {
+            let session = SessionV2 {
+                id: old.id,
+                user_id: old.user_id,
+                ip: old.ip,
+                created_at: old.created_at,
+                expires_at: old.expires_at,
+                last_seen_at: old.last_seen_at,
+                user_agent: old.user_agent,
+                last_auth_at: old.last_auth_at,
+                impersonator: None,
+            };
+            sessions_table.insert(key.as_str(), codec.serialize(&session)?)?;
+        }
+        Ok(())
+    }
+
+    /// Migration 3: re-encodes sessions with no custom data
+    fn add_session_data(txn: &WriteTransaction, codec: &ValueCodec) -> Result<(), AuthError> {
+        let mut sessions_table = txn.open_table(SESSIONS_TABLE)?;
+        let legacy = sessions_table
+            .iter()?
+            .map(|entry| {
+                let (key, bytes) = entry?;
+                let old: SessionV2 = codec.deserialize(&bytes.value())?;
+                Ok((key.value().to_string(), old))
+            })
+            .collect::<Result<Vec<_>, AuthError>>()?;
+
+        for (key, old) in legacy {
+            let session = Session {
+                id: old.id,
+                user_id: old.user_id,
+                ip: old.ip,
+                created_at: old.created_at,
+                expires_at: old.expires_at,
+                last_seen_at: old.last_seen_at,
+                user_agent: old.user_agent,
+                last_auth_at: old.last_auth_at,
+                impersonator: old.impersonator,
+                data: BTreeMap::new(),
+            };
+            sessions_table.insert(key.as_str(), codec.serialize(&session)?)?;
+        }
+        Ok(())
+    }
+
+    /// Size and page usage of the database, if the sessions have a file of their own
+    pub async fn stats(&self) -> Result<Option<DatabaseReport>, AuthError> {
+        let Some(path) = self.path.clone() else {
+            return Ok(None);
+        };
+        let db = self.db.clone();
+        spawn_blocking(move || database_report("sessions", &db, Some(&path)))
+            .await?
+            .map(Some)
+    }
+
+    // ==================== Transaction Helpers ====================
+
+    /// Execute a read-only operation within a transaction
+    async fn with_read_txn<T, F>(&self, f: F) -> Result<T, AuthError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&ReadTransaction, &ValueCodec) -> Result<T, AuthError> + Send + 'static,
+    {
+        let db = self.db.clone();
+        let codec = self.codec.clone();
+        let permit = self.blocking.acquire().await;
+        spawn_blocking(move || {
+            let _permit = permit;
+            let txn = db.begin_read()?;
+            f(&txn, &codec)
+        })
+        .await?
+    }
+
+    /// Execute a write operation within a transaction
+    async fn with_write_txn<T, F>(&self, f: F) -> Result<T, AuthError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&WriteTransaction, &ValueCodec) -> Result<T, AuthError> + Send + 'static,
+    {
+        let db = self.db.clone();
+        let codec = self.codec.clone();
+        let permit = self.blocking.acquire().await;
+        spawn_blocking(move || {
+            let _permit = permit;
+            let txn = db.begin_write()?;
+            let result = f(&txn, &codec)?;
+            txn.commit()?;
+            Ok(result)
+        })
+        .await?
+    }
+
+    /// Takes `removed` sessions off the active count
+    fn forget_sessions(active: &AtomicUsize, removed: usize) {
+        if removed > 0 {
+            let _ = active.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                Some(count.saturating_sub(removed))
+            });
+        }
+    }
+
+    // ==================== Multimap Index Operations ====================
+
+    /// Gets all session IDs for a user using multimap table - O(n) where n = user's session count
+    fn get_user_session_ids<T: ReadableMultimapTable<u128, &'static str>>(
+        table: &T,
+        user_id: u128,
+    ) -> Result<Vec<String>, AuthError> {
+        let mut session_ids = Vec::new();
+        let values = table.get(user_id)?;
+        for value_result in values {
+            let value = value_result?;
+            session_ids.push(value.value().to_string());
+        }
+        Ok(session_ids)
+    }
+
+    /// Splits a user's sessions into how many are active at `now` and the IDs of the
+    /// expired ones, orphaned index entries included - O(n) where n = user's session count
+    fn partition_user_sessions<T: ReadableTable<&'static str, Vec<u8>>>(
+        sessions_table: &T,
+        codec: &ValueCodec,
+        session_ids: &[String],
+        now: OffsetDateTime,
+    ) -> Result<(usize, Vec<String>), AuthError> {
+        let mut active_count = 0;
+        let mut expired_session_ids = Vec::new();
+
+        for session_id in session_ids {
+            match sessions_table.get(session_id.as_str())? {
+                Some(session_bytes) => {
+                    let head: SessionHead = codec.deserialize(&session_bytes.value())?;
+                    if head.expires_at > now {
+                        active_count += 1;
+                    } else {
+                        expired_session_ids.push(session_id.clone());
+                    }
+                }
+                None => {
+                    // Session in index but not in sessions table - orphaned entry
+                    expired_session_ids.push(session_id.clone());
+                }
+            }
+        }
+        Ok((active_count, expired_session_ids))
+    }
+
+    /// Removes a session from all relevant tables and indexes - O(log N)
+    fn remove_session(
+        sessions_table: &mut redb::Table<&str, Vec<u8>>,
+        user_sessions_table: &mut redb::MultimapTable<u128, &'static str>,
+        session_user_table: &mut redb::Table<&str, u128>,
+        user_id: u128,
+        session_id: &str,
+    ) -> Result<(), AuthError> {
+        sessions_table.remove(session_id)?;
+        user_sessions_table.remove(user_id, session_id)?;
+        session_user_table.remove(session_id)?;
+        trace!(session_id = %session_id, "Session removed from all tables");
+        Ok(())
+    }
+
+    /// Batch removes multiple sessions - O(k log N) where k = number of sessions
+    fn remove_sessions_batch(
+        sessions_table: &mut redb::Table<&str, Vec<u8>>,
+        user_sessions_table: &mut redb::MultimapTable<u128, &'static str>,
+        session_user_table: &mut redb::Table<&str, u128>,
+        user_id: u128,
+        session_ids: &[String],
+    ) -> Result<(), AuthError> {
+        for session_id in session_ids {
+            sessions_table.remove(session_id.as_str())?;
+            user_sessions_table.remove(user_id, session_id.as_str())?;
+            session_user_table.remove(session_id.as_str())?;
+        }
+        if !session_ids.is_empty() {
+            trace!(count = session_ids.len(), "Batch removed expired sessions");
+        }
+        Ok(())
+    }
+
+    /// Removes a session looked up by its token, if it still exists.
+    ///
+    /// Returns whether it did.
+    fn remove_session_by_token(txn: &WriteTransaction, token: &str) -> Result<bool, AuthError> {
+        let mut sessions_table = txn.open_table(SESSIONS_TABLE)?;
+        let mut user_sessions_table = txn.open_multimap_table(USER_SESSIONS_INDEX)?;
+        let mut session_user_table = txn.open_table(SESSION_USER_INDEX)?;
+
+        // Get user_id from reverse index (no deserialization needed)
+        let user_id = session_user_table.get(token)?.map(|v| v.value());
+
+        if let Some(user_id) = user_id {
+            Self::remove_session(
+                &mut sessions_table,
+                &mut user_sessions_table,
+                &mut session_user_table,
+                user_id,
+                token,
+            )?;
+        }
+        Ok(user_id.is_some())
+    }
+}
+
+impl SessionStore for RedbSessionStore {
+    async fn insert_session(
+        &self,
+        session: Session,
+        max_per_user: usize,
+    ) -> Result<Session, AuthError> {
+        let now = self.clock.now();
+        let stats = self.stats.clone();
+        let active = self.active.clone();
+        let max_total = self.max_total;
+
+        self.with_write_txn(move |txn, codec| {
+            let mut sessions_table = txn.open_table(SESSIONS_TABLE)?;
+            let mut user_sessions_table = txn.open_multimap_table(USER_SESSIONS_INDEX)?;
+            let mut session_user_table = txn.open_table(SESSION_USER_INDEX)?;
+            let user_id = session.user_id.0.as_u128();
+
+            // Get session IDs and partition into active/expired
+            let session_ids = Self::get_user_session_ids(&user_sessions_table, user_id)?;
+            let (active_count, expired_session_ids) =
+                Self::partition_user_sessions(&sessions_table, codec, &session_ids, now)?;
+
+            // Batch clean up expired/orphaned sessions
+            Self::remove_sessions_batch(
+                &mut sessions_table,
+                &mut user_sessions_table,
+                &mut session_user_table,
+                user_id,
+                &expired_session_ids,
+            )?;
+            stats.record_lazy_purge(expired_session_ids.len());
+
+            if active_count >= max_per_user {
+                debug!(
+                    user_id = %session.user_id.0,
+                    active_count,
+                    max_sessions = max_per_user,
+                    "Maximum active sessions reached"
+                );
+                return Err(AuthError::SessionLimitReached);
+            }
+            // write transactions are serialized, so no other insert races this check
+            if let Some(max_total) = max_total {
+                let total = active
+                    .load(Ordering::Relaxed)
+                    .saturating_sub(expired_session_ids.len());
+                if total >= max_total {
+                    debug!(
+                        total,
+                        max_total, "Maximum active sessions of the instance reached"
+                    );
+                    return Err(AuthError::InstanceSessionLimitReached);
+                }
+            }
+
+            let session_bytes = codec.serialize(&session)?;
+            sessions_table.insert(session.id.as_str(), session_bytes)?;
+
+            // Add to indexes
+            user_sessions_table.insert(user_id, session.id.as_str())?;
+            session_user_table.insert(session.id.as_str(), user_id)?;
+            Self::forget_sessions(&active, expired_session_ids.len());
+            active.fetch_add(1, Ordering::Relaxed);
+
+            trace!(
+                user_id = %session.user_id.0,
+                session_id = %session.id.0,
+                "Session created successfully"
+            );
+            Ok(session)
+        })
+        .await
+    }
+
+    async fn get_session(&self, token: &SessionId) -> Result<Session, AuthError> {
+        let db = self.db.clone();
+        let codec = self.codec.clone();
+        let token = token.clone();
+        let now = self.clock.now();
+        let stats = self.stats.clone();
+        let active = self.active.clone();
+
+        // Use read-first approach: only acquire write lock if cleanup is needed
+        let permit = self.blocking.acquire().await;
+        spawn_blocking(move || {
+            let _permit = permit;
+            // First, try with a read transaction (common path)
+            {
+                let read_txn = db.begin_read()?;
+                let sessions_table = read_txn.open_table(SESSIONS_TABLE)?;
+
+                match sessions_table.get(token.as_str())? {
+                    Some(session_bytes) => {
+                        let session: Session = codec.deserialize(&session_bytes.value())?;
+                        if session.expires_at > now {
+                            debug!(session_id = %token.0, "Valid session found");
+                            return Ok(session);
+                        }
+                        // Session expired - fall through to cleanup with write transaction
+                        debug!(
+                            session_id = %token.0,
+                            expired_at = %session.expires_at,
+                            "Session expired, will clean up"
+                        );
+                    }
+                    None => {
+                        debug!(session_id = %token.0, "Session not found");
+                        return Err(AuthError::InvalidSession);
+                    }
+                }
+            }
+
+            // Session was expired - acquire write transaction to clean up
+            let write_txn = db.begin_write()?;
+            let removed = Self::remove_session_by_token(&write_txn, token.as_str())?;
+            write_txn.commit()?;
+            if removed {
+                stats.record_lazy_purge(1);
+                Self::forget_sessions(&active, 1);
+            }
+
+            Err(AuthError::InvalidSession)
+        })
+        .await?
+    }
+
+    async fn touch_session(
+        &self,
+        token: &SessionId,
+        expires_at: OffsetDateTime,
+    ) -> Result<Session, AuthError> {
+        let db = self.db.clone();
+        let codec = self.codec.clone();
+        let token = token.clone();
+        let now = self.clock.now();
+        let stats = self.stats.clone();
+        let active = self.active.clone();
+
+        // Read-first: check if session is valid before acquiring write lock
+        let permit = self.blocking.acquire().await;
+        spawn_blocking(move || {
+            let _permit = permit;
+
+            // First, verify session exists and is not expired with read transaction
+            let session_valid = {
+                let read_txn = db.begin_read()?;
+                let sessions_table = read_txn.open_table(SESSIONS_TABLE)?;
+
+                match sessions_table.get(token.as_str())? {
+                    Some(session_bytes) => {
+                        let session: Session = codec.deserialize(&session_bytes.value())?;
+                        if session.expires_at <= now {
+                            debug!(
+                                session_id = %token.0,
+                                expired_at = %session.expires_at,
+                                "Session expired, cannot extend"
+                            );
+                            false
+                        } else {
+                            true
+                        }
+                    }
+                    None => {
+                        debug!(session_id = %token.0, "Session not found");
+                        return Err(AuthError::InvalidSession);
+                    }
+                }
+            };
+
+            if !session_valid {
+                // Clean up expired session
+                let write_txn = db.begin_write()?;
+                let removed = Self::remove_session_by_token(&write_txn, token.as_str())?;
+                write_txn.commit()?;
+                if removed {
+                    stats.record_lazy_purge(1);
+                    Self::forget_sessions(&active, 1);
+                }
+                return Err(AuthError::InvalidSession);
+            }
+
+            // Session is valid - acquire write transaction to extend
+            let write_txn = db.begin_write()?;
+            let result = {
+                let mut sessions_table = write_txn.open_table(SESSIONS_TABLE)?;
+
+                // Re-fetch and update (session might have changed between transactions)
+                let session_data = sessions_table
+                    .get(token.as_str())?
+                    .map(|b| b.value().to_vec());
+
+                match session_data {
+                    Some(session_bytes) => {
+                        let mut session: Session = codec.deserialize(&session_bytes)?;
+
+                        // Re-check expiry (could have expired between read and write)
+                        if session.expires_at <= now {
+                            return Err(AuthError::InvalidSession);
+                        }
+
+                        session.expires_at = expires_at;
+                        session.last_seen_at = now;
+                        let new_session_bytes = codec.serialize(&session)?;
+                        sessions_table.insert(token.as_str(), new_session_bytes)?;
+
+                        trace!(
+                            session_id = %token.0,
+                            new_expires = %expires_at,
+                            "Session extended successfully"
+                        );
+                        Ok(session)
+                    }
+                    None => Err(AuthError::InvalidSession),
+                }
+            };
+
+            if result.is_ok() {
+                write_txn.commit()?;
+            }
+            result
+        })
+        .await?
+    }
+
+    async fn stamp_auth(
+        &self,
+        token: &SessionId,
+        at: OffsetDateTime,
+    ) -> Result<Session, AuthError> {
+        let token = token.clone();
+
+        self.with_write_txn(move |txn, codec| {
+            let mut sessions_table = txn.open_table(SESSIONS_TABLE)?;
+            let mut session: Session = match sessions_table.get(token.as_str())? {
+                Some(session_bytes) => codec.deserialize(&session_bytes.value())?,
+                None => return Err(AuthError::InvalidSession),
+            };
+            // expired ones are left for the lazy cleanup of `get_session`
+            if session.expires_at <= at {
+                return Err(AuthError::InvalidSession);
+            }
+
+            session.last_auth_at = Some(at);
+            sessions_table.insert(token.as_str(), codec.serialize(&session)?)?;
+            trace!(session_id = %token.0, "Session re-authenticated");
+            Ok(session)
+        })
+        .await
+    }
+
+    async fn set_data(
+        &self,
+        token: &SessionId,
+        key: &str,
+        value: Option<String>,
+        now: OffsetDateTime,
+    ) -> Result<Session, AuthError> {
+        let token = token.clone();
+        let key = key.to_string();
+
+        self.with_write_txn(move |txn, codec| {
+            let mut sessions_table = txn.open_table(SESSIONS_TABLE)?;
+            let mut session: Session = match sessions_table.get(token.as_str())? {
+                Some(session_bytes) => codec.deserialize(&session_bytes.value())?,
+                None => return Err(AuthError::InvalidSession),
+            };
+            // expired ones are left for the lazy cleanup of `get_session`
+            if session.expires_at <= now {
+                return Err(AuthError::InvalidSession);
+            }
+
+            session.set_data(&key, value)?;
+            sessions_table.insert(token.as_str(), codec.serialize(&session)?)?;
+            trace!(session_id = %token.0, key = key.as_str(), "Session data set");
+            Ok(session)
+        })
+        .await
+    }
+
+    async fn remove_session(&self, token: &SessionId) -> Result<(), AuthError> {
+        let token = token.clone();
+        let active = self.active.clone();
+
+        self.with_write_txn(move |txn, _| {
+            // Use reverse index to get user_id directly - O(log N), no deserialization
+            if !Self::remove_session_by_token(txn, token.as_str())? {
+                debug!(session_id = %token.0, "Session not found for revocation");
+                return Err(AuthError::InvalidSession);
+            }
+            Self::forget_sessions(&active, 1);
+
+            debug!(session_id = %token.0, "Session revoked successfully");
+            Ok(())
+        })
+        .await
+    }
+
+    async fn remove_user_sessions(&self, user_id: &UserId) -> Result<Vec<SessionId>, AuthError> {
+        let user_id = *user_id;
+        let active = self.active.clone();
+
+        self.with_write_txn(move |txn, _| {
+            let mut sessions_table = txn.open_table(SESSIONS_TABLE)?;
+            let mut user_sessions_table = txn.open_multimap_table(USER_SESSIONS_INDEX)?;
+            let mut session_user_table = txn.open_table(SESSION_USER_INDEX)?;
+
+            let session_ids =
+                Self::get_user_session_ids(&user_sessions_table, user_id.0.as_u128())?;
+            let mut removed = 0;
+            for session_id in &session_ids {
+                if sessions_table.remove(session_id.as_str())?.is_some() {
+                    removed += 1;
+                }
+                session_user_table.remove(session_id.as_str())?;
+            }
+            // Remove all entries for this user from the multimap
+            user_sessions_table.remove_all(user_id.0.as_u128())?;
+            Self::forget_sessions(&active, removed);
+
+            trace!(user_id = %user_id.0, count = session_ids.len(), "Removed all user sessions");
+            Ok(session_ids.into_iter().map(SessionId).collect())
+        })
+        .await
+    }
+
+    async fn user_session_ids(&self, user_id: &UserId) -> Result<Vec<SessionId>, AuthError> {
+        let user_id = *user_id;
+
+        self.with_read_txn(move |txn, _| {
+            let user_sessions_table = txn.open_multimap_table(USER_SESSIONS_INDEX)?;
+            let session_ids =
+                Self::get_user_session_ids(&user_sessions_table, user_id.0.as_u128())?;
+            Ok(session_ids.into_iter().map(SessionId).collect())
+        })
+        .await
+    }
+
+    async fn purge_expired(&self) -> Result<usize, AuthError> {
+        let now = self.clock.now();
+        let active = self.active.clone();
+
+        self.with_write_txn(move |txn, codec| {
+            let mut sessions_table = txn.open_table(SESSIONS_TABLE)?;
+            let mut user_sessions_table = txn.open_multimap_table(USER_SESSIONS_INDEX)?;
+            let mut session_user_table = txn.open_table(SESSION_USER_INDEX)?;
+
+            let mut expired = Vec::new();
+            for entry in sessions_table.iter()? {
+                let (session_id, session_bytes) = entry?;
+                let session: Session = codec.deserialize(&session_bytes.value())?;
+                if session.expires_at <= now {
+                    expired.push((session_id.value().to_string(), session.user_id));
+                }
+            }
+
+            for (session_id, user_id) in &expired {
+                Self::remove_session(
+                    &mut sessions_table,
+                    &mut user_sessions_table,
+                    &mut session_user_table,
+                    user_id.0.as_u128(),
+                    session_id,
+                )?;
+            }
+
+            // what's left is unexpired, so this also corrects the drift of the active count
+            let remaining = sessions_table.len()? as usize;
+            let counted = active.swap(remaining, Ordering::Relaxed);
+            if counted != remaining {
+                debug!(counted, remaining, "Reconciled the active session count");
+            }
+
+            debug!(count = expired.len(), "Purged expired sessions");
+            Ok(expired.len())
+        })
+        .await
+    }
+
+    async fn remove_where(&self, criteria: &SessionCriteria) -> Result<usize, AuthError> {
+        let criteria = criteria.clone();
+        let active = self.active.clone();
+
+        self.with_write_txn(move |txn, codec| {
+            let mut sessions_table = txn.open_table(SESSIONS_TABLE)?;
+            let mut user_sessions_table = txn.open_multimap_table(USER_SESSIONS_INDEX)?;
+            let mut session_user_table = txn.open_table(SESSION_USER_INDEX)?;
+
+            let mut matching = Vec::new();
+            for entry in sessions_table.iter()? {
+                let (session_id, session_bytes) = entry?;
+                let session: Session = codec.deserialize(&session_bytes.value())?;
+                if criteria.matches(&session) {
+                    matching.push((session_id.value().to_string(), session.user_id));
+                }
+            }
+
+            for (session_id, user_id) in &matching {
+                Self::remove_session(
+                    &mut sessions_table,
+                    &mut user_sessions_table,
+                    &mut session_user_table,
+                    user_id.0.as_u128(),
+                    session_id,
+                )?;
+            }
+            Self::forget_sessions(&active, matching.len());
+
+            debug!(count = matching.len(), "Removed sessions by criteria");
+            Ok(matching.len())
+        })
+        .await
+    }
+
+    async fn count_user_sessions(&self, user_id: &UserId) -> Result<usize, AuthError> {
+        let user_id = *user_id;
+        let now = self.clock.now();
+
+        // read-only: expired sessions are left for the next insert or purge to remove
+        self.with_read_txn(move |txn, codec| {
+            let sessions_table = txn.open_table(SESSIONS_TABLE)?;
+            let user_sessions_table = txn.open_multimap_table(USER_SESSIONS_INDEX)?;
+
+            let session_ids =
+                Self::get_user_session_ids(&user_sessions_table, user_id.0.as_u128())?;
+            let (active_count, _) =
+                Self::partition_user_sessions(&sessions_table, codec, &session_ids, now)?;
+            Ok(active_count)
+        })
+        .await
+    }
+
+    async fn user_sessions(&self, user_id: &UserId) -> Result<Vec<Session>, AuthError> {
+        let user_id = *user_id;
+        let now = self.clock.now();
+
+        self.with_read_txn(move |txn, codec| {
+            let sessions_table = txn.open_table(SESSIONS_TABLE)?;
+            let user_sessions_table = txn.open_multimap_table(USER_SESSIONS_INDEX)?;
+
+            let mut sessions = Vec::new();
+            for session_id in user_sessions_table.get(user_id.0.as_u128())? {
+                let session_id = session_id?;
+                if let Some(session_bytes) = sessions_table.get(session_id.value())? {
+                    let session: Session = codec.deserialize(&session_bytes.value())?;
+                    if session.expires_at > now {
+                        sessions.push(session);
+                    }
+                }
+            }
+            sessions.sort_by_key(|session| std::cmp::Reverse(session.last_seen_at));
+            Ok(sessions)
+        })
+        .await
+    }
+
+    async fn user_sessions_page(
+        &self,
+        user_id: &UserId,
+        after: Option<&SessionId>,
+        limit: usize,
+    ) -> Result<SessionPage, AuthError> {
+        let user_id = *user_id;
+        let after = after.cloned();
+        let now = self.clock.now();
+
+        self.with_read_txn(move |txn, codec| {
+            let sessions_table = txn.open_table(SESSIONS_TABLE)?;
+            let user_sessions_table = txn.open_multimap_table(USER_SESSIONS_INDEX)?;
+
+            // the index keeps a user's tokens in ascending order, which the pages follow
+            let mut sessions = Vec::new();
+            let mut next_cursor = None;
+            for session_id in user_sessions_table.get(user_id.0.as_u128())? {
+                let session_id = session_id?;
+                let session_id = session_id.value();
+                if after
+                    .as_ref()
+                    .is_some_and(|after| session_id <= after.as_str())
+                {
+                    continue;
+                }
+                let Some(session_bytes) = sessions_table.get(session_id)? else {
+                    continue;
+                };
+                let session: Session = codec.deserialize(&session_bytes.value())?;
+                if session.expires_at <= now {
+                    continue;
+                }
+                if sessions.len() == limit {
+                    next_cursor = sessions.last().map(|session: &Session| session.id.clone());
+                    break;
+                }
+                sessions.push(session);
+            }
+            Ok(SessionPage {
+                sessions,
+                next_cursor,
+            })
+        })
+        .await
+    }
+
+    async fn active_by_ip(&self) -> Result<BTreeMap<IpAddr, usize>, AuthError> {
+        let now = self.clock.now();
+
+        // A full scan, but only in a read transaction, so logins and lookups aren't blocked
+        self.with_read_txn(move |txn, codec| {
+            let sessions_table = txn.open_table(SESSIONS_TABLE)?;
+
+            let mut counts = BTreeMap::new();
+            for entry in sessions_table.iter()? {
+                let (_, session_bytes) = entry?;
+                let session: Session = codec.deserialize(&session_bytes.value())?;
+                if session.expires_at > now {
+                    *counts.entry(session.ip.0).or_insert(0) += 1;
+                }
+            }
+            Ok(counts)
+        })
+        .await
+    }
+
+    async fn clear(&self) -> Result<usize, AuthError> {
+        let active = self.active.clone();
+
+        self.with_write_txn(move |txn, _| {
+            let removed = txn.open_table(SESSIONS_TABLE)?.len()?;
+            txn.delete_table(SESSIONS_TABLE)?;
+            txn.delete_multimap_table(USER_SESSIONS_INDEX)?;
+            txn.delete_table(SESSION_USER_INDEX)?;
+            // recreated empty, so readers keep finding them
+            let _ = txn.open_table(SESSIONS_TABLE)?;
+            let _ = txn.open_multimap_table(USER_SESSIONS_INDEX)?;
+            let _ = txn.open_table(SESSION_USER_INDEX)?;
+
+            active.store(0, Ordering::Relaxed);
+            debug!(removed, "Cleared all sessions");
+            Ok(removed as usize)
+        })
+        .await
+    }
+}
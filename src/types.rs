@@ -2,6 +2,7 @@ use leptos::prelude::FromServerFnError;
 use leptos::server_fn::codec::JsonEncoding;
 use leptos::server_fn::error::ServerFnErrorErr;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::net::IpAddr;
 use time::OffsetDateTime;
 use uuid::Uuid;
@@ -54,6 +55,25 @@ pub enum Role {
     User,
 }
 
+/// Whether a user may sign in. Disabled users keep their record (and with it their audit
+/// history and projects) but can't log in or use existing sessions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserStatus {
+    #[default]
+    Active,
+    Disabled,
+}
+
+impl UserStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            UserStatus::Active => "Active",
+            UserStatus::Disabled => "Disabled",
+        }
+    }
+}
+
 /// Main user abstraction
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
@@ -61,6 +81,21 @@ pub struct User {
     pub username: Username,
     pub password_hash: PasswordHash,
     pub role: Role,
+    #[serde(default)]
+    pub status: UserStatus,
+    #[serde(default)]
+    pub last_login_at: Option<OffsetDateTime>,
+    #[serde(default)]
+    pub last_login_ip: Option<SessionIp>,
+    /// Wrong passwords since the last successful login or lock, see `[lockout]`
+    #[serde(default)]
+    pub failed_logins: u32,
+    /// Password logins are refused until then
+    #[serde(default)]
+    pub locked_until: Option<OffsetDateTime>,
+    /// When an admin last re-enabled the account, see [User::last_active_at]
+    #[serde(default)]
+    pub enabled_at: Option<OffsetDateTime>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,6 +105,415 @@ pub struct Session {
     pub ip: SessionIp,
     pub created_at: OffsetDateTime,
     pub expires_at: OffsetDateTime,
+    /// Last time the session was issued or extended
+    pub last_seen_at: OffsetDateTime,
+    /// The browser the session was issued to, if it sent a User-Agent
+    #[serde(default)]
+    pub user_agent: Option<UserAgentHash>,
+    /// Last time the password was re-entered for this session, see `authenticated_at`
+    #[serde(default)]
+    pub last_auth_at: Option<OffsetDateTime>,
+    /// The admin acting as `user_id`, for sessions started by `impersonate`
+    #[serde(default)]
+    pub impersonator: Option<UserId>,
+    /// Small values integrations keep on the session, see `AuthStore::set_session_data`
+    #[serde(default)]
+    pub data: BTreeMap<String, String>,
+}
+
+/// One page of a user's unexpired sessions, see `AuthStore::list_user_sessions_page`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionPage {
+    pub sessions: Vec<Session>,
+    /// Pass as `after` to get the next page; `None` on the last page
+    pub next_cursor: Option<SessionId>,
+}
+
+/// SHA-256 of a normalized User-Agent header, so sessions can be bound to the browser
+/// they were issued to (`[session] bind_user_agent`) without storing the full string
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct UserAgentHash(pub String);
+
+#[cfg(feature = "ssr")]
+impl UserAgentHash {
+    /// Hashes `user_agent` with every version number cut down to its major part, so
+    /// "Firefox/128.0" and "Firefox/128.5.1" hash alike but "Firefox/129.0" doesn't
+    pub fn of(user_agent: &str) -> Self {
+        use sha2::{Digest, Sha256};
+
+        let mut normalized = String::with_capacity(user_agent.len());
+        let mut chars = user_agent.trim().chars().peekable();
+        while let Some(c) = chars.next() {
+            normalized.push(c);
+            if c.is_ascii_digit() && chars.peek() == Some(&'.') {
+                while chars.next_if(|c| c.is_ascii_digit() || *c == '.').is_some() {}
+            }
+        }
+
+        let digest = Sha256::digest(normalized.as_bytes());
+        Self(digest.iter().map(|byte| format!("{byte:02x}")).collect())
+    }
+
+    /// Hash of a request's User-Agent header, if it sent a readable one
+    pub fn from_headers(headers: &axum::http::HeaderMap) -> Option<Self> {
+        headers
+            .get(axum::http::header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(Self::of)
+    }
+}
+
+/// When and where a user last signed in, shown on the dashboard after the next login
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreviousLogin {
+    pub at: OffsetDateTime,
+    pub ip: IpAddr,
+}
+
+/// A sign-in to the account from another session, shown once on the dashboard when
+/// `[security] notify_new_login` is on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewLoginNotice {
+    pub at: OffsetDateTime,
+    pub ip: IpAddr,
+    /// The `User-Agent` header of the signing-in browser, shortened
+    pub user_agent: Option<String>,
+}
+
+impl Session {
+    /// Most entries `data` may hold
+    pub const MAX_DATA_ENTRIES: usize = 16;
+    /// Most bytes the keys and values in `data` may take up together
+    pub const MAX_DATA_BYTES: usize = 4096;
+
+    /// Whether a request from a browser with `user_agent` may use this session. Sessions
+    /// issued without a User-Agent aren't tied to one.
+    pub fn matches_user_agent(&self, user_agent: Option<&UserAgentHash>) -> bool {
+        self.user_agent.is_none() || self.user_agent.as_ref() == user_agent
+    }
+
+    /// When the user last proved their password: signing in, or re-authenticating since
+    pub fn authenticated_at(&self) -> OffsetDateTime {
+        self.last_auth_at.unwrap_or(self.created_at)
+    }
+
+    /// Sets `key` in `data`, or removes it when `value` is `None`, leaving `data` as it was
+    /// if that would go over [Session::MAX_DATA_ENTRIES] or [Session::MAX_DATA_BYTES]
+    #[cfg(feature = "ssr")]
+    pub fn set_data(
+        &mut self,
+        key: &str,
+        value: Option<String>,
+    ) -> Result<(), crate::storage::AuthError> {
+        let Some(value) = value else {
+            self.data.remove(key);
+            return Ok(());
+        };
+
+        let mut data = self.data.clone();
+        data.insert(key.to_string(), value);
+        let bytes: usize = data.iter().map(|(k, v)| k.len() + v.len()).sum();
+        if data.len() > Self::MAX_DATA_ENTRIES || bytes > Self::MAX_DATA_BYTES {
+            return Err(crate::storage::AuthError::SessionDataTooLarge);
+        }
+        self.data = data;
+        Ok(())
+    }
+}
+
+impl User {
+    pub fn is_active(&self) -> bool {
+        self.status == UserStatus::Active
+    }
+
+    /// Whether too many failed logins have locked the account at `now`
+    pub fn is_locked(&self, now: OffsetDateTime) -> bool {
+        self.locked_until.is_some_and(|until| until > now)
+    }
+
+    /// The login recorded by `AuthStore::record_login`, if any
+    pub fn last_login(&self) -> Option<PreviousLogin> {
+        Some(PreviousLogin {
+            at: self.last_login_at?,
+            ip: self.last_login_ip.as_ref()?.0,
+        })
+    }
+
+    /// The later of the last login and the last re-enabling, which `[accounts]
+    /// inactivity_days` counts from; `None` if the user never signed in
+    pub fn last_active_at(&self) -> Option<OffsetDateTime> {
+        self.last_login_at.max(self.enabled_at)
+    }
+}
+
+/// One account to create with `bulk_create_users`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewUser {
+    pub username: String,
+    pub password: String,
+    pub role: Role,
+}
+
+/// What became of one `NewUser` row, in the order the rows were sent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BulkUserResult {
+    pub username: String,
+    /// The new user's id, or why the row was rejected
+    pub outcome: Result<UserId, String>,
+}
+
+/// A user as listed on the admin's Manage Users panel, without any credentials
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserSummary {
+    pub id: UserId,
+    pub username: String,
+    pub role: Role,
+    pub status: UserStatus,
+    pub last_login_at: Option<OffsetDateTime>,
+    /// Set while the account is locked after too many failed logins
+    pub locked_until: Option<OffsetDateTime>,
+}
+
+impl UserSummary {
+    /// Summarizes `user`, leaving out a lock that has already run out at `now`
+    pub fn new(user: &User, now: OffsetDateTime) -> Self {
+        Self {
+            id: user.id,
+            username: user.username.0.clone(),
+            role: user.role,
+            status: user.status,
+            last_login_at: user.last_login_at,
+            locked_until: user.locked_until.filter(|_| user.is_locked(now)),
+        }
+    }
+}
+
+/// White-label settings from `[branding]`, shared with the browser via `get_branding`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Branding {
+    pub app_name: String,
+    /// Logo and favicon URL
+    pub logo_path: String,
+    /// Hex color (e.g., "#e35b2d") for primary buttons
+    pub primary_color: String,
+    /// daisyUI theme (e.g., "night", "light") for visitors who haven't picked their own
+    pub default_theme: String,
+    /// Hex color for highlights, exposed as `--bento-accent`
+    pub accent_color: String,
+}
+
+impl Default for Branding {
+    fn default() -> Self {
+        Self {
+            app_name: "Bento".to_string(),
+            logo_path: "/bento-dark.svg".to_string(),
+            primary_color: "#e35b2d".to_string(),
+            default_theme: "night".to_string(),
+            accent_color: "#2d9be3".to_string(),
+        }
+    }
+}
+
+impl Branding {
+    pub fn page_title(&self) -> String {
+        format!("{}: Backend Toolbox", self.app_name)
+    }
+
+    /// The configured primary color, or the default one if it isn't a `#rgb`/`#rrggbb` hex
+    /// color (it ends up in a stylesheet, so nothing else is let through)
+    pub fn primary_color(&self) -> String {
+        if is_hex_color(&self.primary_color) {
+            self.primary_color.clone()
+        } else {
+            Self::default().primary_color
+        }
+    }
+
+    /// The configured accent color, validated like [`Branding::primary_color`]
+    pub fn accent_color(&self) -> String {
+        if is_hex_color(&self.accent_color) {
+            self.accent_color.clone()
+        } else {
+            Self::default().accent_color
+        }
+    }
+
+    /// The `data-theme` to render: the visitor's own pick (from the theme cookie) if it
+    /// looks like a theme name, otherwise the configured default
+    pub fn theme(&self, chosen: Option<&str>) -> String {
+        chosen
+            .filter(|theme| is_theme_name(theme))
+            .or(Some(self.default_theme.as_str()).filter(|theme| is_theme_name(theme)))
+            .map_or_else(|| Self::default().default_theme, str::to_string)
+    }
+
+    /// CSS custom properties for the theme, used as e.g. `bg-[var(--bento-primary)]`
+    pub fn css_variables(&self) -> String {
+        format!(
+            "--bento-primary: {0}; --bento-primary-dark: color-mix(in srgb, {0} 80%, black); \
+             --bento-accent: {1};",
+            self.primary_color(),
+            self.accent_color()
+        )
+    }
+}
+
+/// Whether `color` is a `#rgb`/`#rrggbb` hex color
+fn is_hex_color(color: &str) -> bool {
+    let hex = color.strip_prefix('#').unwrap_or_default();
+    matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Whether `theme` could name a daisyUI theme; anything else stays out of the markup
+fn is_theme_name(theme: &str) -> bool {
+    (1..=32).contains(&theme.len())
+        && theme
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Session details for support and debugging; deliberately omits the token itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub user_id: UserId,
+    pub username: String,
+    pub ip: IpAddr,
+    pub created_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+    pub last_seen_at: OffsetDateTime,
+}
+
+/// One of a user's sessions, offered to sign out when a login hits the session limit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActiveSession {
+    /// [SessionId::public_id], never the token itself
+    pub id: String,
+    pub ip: IpAddr,
+    pub created_at: OffsetDateTime,
+    pub last_seen_at: OffsetDateTime,
+}
+
+#[cfg(feature = "ssr")]
+impl From<&Session> for ActiveSession {
+    fn from(session: &Session) -> Self {
+        Self {
+            id: session.id.public_id(),
+            ip: session.ip.0,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+        }
+    }
+}
+
+/// Snapshot of the expired session cleanup counters since startup.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceReport {
+    pub total_sweeps: u64,
+    pub last_sweep_at: Option<OffsetDateTime>,
+    /// Sessions removed by periodic sweeps
+    pub swept_sessions: u64,
+    /// Sessions removed while issuing or looking up sessions
+    pub lazily_purged_sessions: u64,
+    /// Lifetime total of both
+    pub sessions_purged: u64,
+}
+
+/// Size and page usage of one database, see `RedbAuthStore::stats`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatabaseReport {
+    /// Which database this is, e.g. "auth"
+    pub name: String,
+    /// Size of the database file; `None` for in-memory databases
+    pub file_size_bytes: Option<u64>,
+    pub page_size: u64,
+    pub allocated_pages: u64,
+    /// Bytes of user data
+    pub stored_bytes: u64,
+    /// Bytes of b-tree metadata
+    pub metadata_bytes: u64,
+    /// Bytes lost to partially filled pages
+    pub fragmented_bytes: u64,
+}
+
+impl DatabaseReport {
+    pub fn allocated_bytes(&self) -> u64 {
+        self.allocated_pages * self.page_size
+    }
+
+    /// Share of the space in use lost to fragmentation, from 0 to 1. redb counts freed
+    /// pages as fragmented too, so this is taken over all the bytes it accounts for
+    /// rather than over the allocated pages.
+    pub fn fragmentation(&self) -> f64 {
+        match self.stored_bytes + self.metadata_bytes + self.fragmented_bytes {
+            0 => 0.0,
+            accounted => self.fragmented_bytes as f64 / accounted as f64,
+        }
+    }
+}
+
+/// Latency percentiles of one server function over its recent calls
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PerfReport {
+    pub function: String,
+    /// Calls the percentiles are taken over
+    pub samples: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+}
+
+/// Which sessions `AuthStore::revoke_sessions_where` ends: those matching every criterion
+/// given. With no criterion given, none match.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionCriteria {
+    /// Issued before this time
+    pub before: Option<OffsetDateTime>,
+    /// Issued to this client IP
+    pub from_ip: Option<IpAddr>,
+}
+
+impl SessionCriteria {
+    pub fn is_empty(&self) -> bool {
+        self.before.is_none() && self.from_ip.is_none()
+    }
+
+    pub fn matches(&self, session: &Session) -> bool {
+        !self.is_empty()
+            && self.before.is_none_or(|before| session.created_at < before)
+            && self.from_ip.is_none_or(|ip| session.ip.0 == ip)
+    }
+}
+
+/// How many unexpired sessions were issued to one client IP
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpSessionCount {
+    pub ip: IpAddr,
+    pub sessions: usize,
+}
+
+/// A WebAuthn passkey registered to a user.
+///
+/// The credential itself is kept as opaque JSON so storage doesn't depend on the
+/// WebAuthn library's types.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PasskeyCredential {
+    /// Base64url-encoded credential id, as reported by the authenticator
+    pub credential_id: String,
+    pub user_id: UserId,
+    pub passkey_json: String,
+    pub created_at: OffsetDateTime,
+}
+
+/// A registration invite; redeeming it creates an account with the given role
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InviteCode {
+    pub code: String,
+    pub role: Role,
+    pub max_uses: u32,
+    pub uses: u32,
+    pub created_at: OffsetDateTime,
+    pub expires_at: Option<OffsetDateTime>,
 }
 
 /*
@@ -89,10 +533,21 @@ impl Default for UserId {
 
 #[cfg(feature = "ssr")]
 impl SessionId {
+    /// Random bytes in a session token unless configured otherwise
+    pub const DEFAULT_BYTES: usize = 32;
+    /// Fewest random bytes a session token may have
+    pub const MIN_BYTES: usize = 16;
+
     pub fn new() -> Self {
+        Self::new_with_len(Self::DEFAULT_BYTES)
+    }
+
+    /// Generates a token from `bytes` random bytes (at least [Self::MIN_BYTES]), encoded as
+    /// URL-safe base64
+    pub fn new_with_len(bytes: usize) -> Self {
         use rand::TryRngCore as _;
 
-        let mut buf = [0_u8; 32];
+        let mut buf = vec![0_u8; bytes.max(Self::MIN_BYTES)];
         if OsRng.try_fill_bytes(&mut buf).is_ok() {
             SessionId(Base64Url.encode(buf))
         } else {
@@ -103,6 +558,18 @@ impl SessionId {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// A stable handle for the session that is safe to show and send around, unlike the
+    /// token: the first 16 hex digits of its SHA-256
+    pub fn public_id(&self) -> String {
+        use sha2::{Digest, Sha256};
+
+        Sha256::digest(self.0.as_bytes())
+            .iter()
+            .take(8)
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
 }
 
 #[cfg(feature = "ssr")]
@@ -112,6 +579,69 @@ impl Default for SessionId {
     }
 }
 
+impl Username {
+    pub const MIN_LEN: usize = 3;
+    pub const MAX_LEN: usize = 32;
+
+    /// Validates and wraps a username.
+    ///
+    /// Usernames are 3-32 characters of ASCII letters, digits, `_`, `-` or `.`.
+    pub fn parse(value: &str) -> Result<Self, UsernameError> {
+        let len = value.chars().count();
+        if len < Self::MIN_LEN {
+            Err(UsernameError::TooShort)
+        } else if len > Self::MAX_LEN {
+            Err(UsernameError::TooLong)
+        } else if !value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        {
+            Err(UsernameError::InvalidCharacter)
+        } else {
+            Ok(Username(value.to_string()))
+        }
+    }
+
+    /// What the username is looked up and kept unique by: the name as is, or lowercased
+    /// when usernames are case-insensitive (`[auth] case_insensitive_usernames`)
+    pub fn lookup_key(&self, case_insensitive: bool) -> String {
+        if case_insensitive {
+            self.0.to_ascii_lowercase()
+        } else {
+            self.0.clone()
+        }
+    }
+}
+
+impl InviteCode {
+    /// Whether the code can still be redeemed at the given time
+    pub fn is_usable(&self, now: OffsetDateTime) -> bool {
+        self.uses < self.max_uses && self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl InviteCode {
+    pub fn new(role: Role, max_uses: u32, expires_at: Option<OffsetDateTime>) -> Self {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use rand::TryRngCore as _;
+
+        let mut buf = [0_u8; 12];
+        if OsRng.try_fill_bytes(&mut buf).is_err() {
+            panic!("Failed to generate secure numbers from the operating system.");
+        }
+
+        Self {
+            code: URL_SAFE_NO_PAD.encode(buf),
+            role,
+            max_uses,
+            uses: 0,
+            created_at: OffsetDateTime::now_utc(),
+            expires_at,
+        }
+    }
+}
+
 impl AsRef<str> for Username {
     fn as_ref(&self) -> &str {
         &self.0
@@ -123,6 +653,7 @@ impl PasswordHash {
     pub fn verify<B: AsRef<[u8]>>(&self, password: B) -> bool {
         let pass_bytes: &[u8] = password.as_ref();
 
+        // the variant, version and costs come from the PHC string, not from this instance
         Argon2::default()
             .verify_password(pass_bytes, &self.0.password_hash())
             .is_ok()
@@ -131,6 +662,13 @@ impl PasswordHash {
     pub fn as_str(&self) -> &str {
         self.0.as_str()
     }
+
+    /// Hashes `password` with a specific hasher; `TryFrom` uses [crate::hashing::hasher]
+    pub fn hash_with(argon2: &Argon2, password: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let salt = SaltString::generate(&mut ArgonRng);
+        let password_hash = argon2.hash_password(password, &salt)?.to_string();
+        Ok(Self(PasswordHashString::new(&password_hash)?))
+    }
 }
 
 #[cfg(feature = "ssr")]
@@ -138,11 +676,7 @@ impl TryFrom<&[u8]> for PasswordHash {
     type Error = Box<dyn std::error::Error>;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        let pass_bytes: &[u8] = value;
-        let salt = SaltString::generate(&mut ArgonRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2.hash_password(pass_bytes, &salt)?.to_string();
-        Ok(Self(PasswordHashString::new(&password_hash)?))
+        Self::hash_with(&crate::hashing::hasher(), value)
     }
 }
 
@@ -151,14 +685,20 @@ impl TryFrom<&str> for PasswordHash {
     type Error = Box<dyn std::error::Error>;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let pass_bytes: &[u8] = value.as_bytes();
-        let salt = SaltString::generate(&mut ArgonRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2.hash_password(pass_bytes, &salt)?.to_string();
-        Ok(Self(PasswordHashString::new(&password_hash)?))
+        Self::hash_with(&crate::hashing::hasher(), value.as_bytes())
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum UsernameError {
+    #[error("Username must be at least {} characters", Username::MIN_LEN)]
+    TooShort,
+    #[error("Username must be at most {} characters", Username::MAX_LEN)]
+    TooLong,
+    #[error("Username may only contain letters, digits, '_', '-' and '.'")]
+    InvalidCharacter,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ServerError {
     #[error("Invalid credentials provided")]
@@ -179,7 +719,7 @@ pub enum ServerError {
 /// automatically converted using the `?` operator.
 ///
 /// ## Example
-/// ```rust
+/// ```ignore
 /// #[server]
 /// pub async fn my_function() -> Result<Data, AppError> {
 ///     let user = auth_store.get_user(&id).await?;  // AuthError → AppError
@@ -188,17 +728,114 @@ pub enum ServerError {
 /// }
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AppError(String);
+pub struct AppError {
+    message: String,
+    #[serde(default)]
+    kind: AppErrorKind,
+    /// The offending input field, for `Validation` errors
+    #[serde(default)]
+    field: Option<String>,
+}
+
+/// Broad category of an [AppError], so the UI can tell bad input apart from other failures.
+///
+/// Server functions answer with the kind's HTTP status (see [AppErrorKind::status]), so
+/// the browser's network tab and non-leptos callers see more than a 500.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AppErrorKind {
+    #[default]
+    General,
+    /// The request was rejected because of invalid input
+    Validation,
+    /// Nobody is signed in, or the session expired
+    Unauthenticated,
+    /// Signed in, but not allowed to do this
+    Forbidden,
+    NotFound,
+    /// Clashes with existing data, e.g. a taken username
+    Conflict,
+    /// Done too often; the message says how long to wait
+    RateLimited,
+    /// Signed in, but the password has to be entered again first (`reauthenticate`)
+    ReauthRequired,
+}
+
+#[cfg(feature = "ssr")]
+impl AppErrorKind {
+    /// The HTTP status a server function failing with this kind responds with
+    pub fn status(self) -> axum::http::StatusCode {
+        use axum::http::StatusCode;
+
+        match self {
+            AppErrorKind::General => StatusCode::INTERNAL_SERVER_ERROR,
+            AppErrorKind::Validation => StatusCode::BAD_REQUEST,
+            AppErrorKind::Unauthenticated => StatusCode::UNAUTHORIZED,
+            AppErrorKind::Forbidden => StatusCode::FORBIDDEN,
+            AppErrorKind::NotFound => StatusCode::NOT_FOUND,
+            AppErrorKind::Conflict => StatusCode::CONFLICT,
+            AppErrorKind::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            AppErrorKind::ReauthRequired => StatusCode::FORBIDDEN,
+        }
+    }
+}
 
 impl AppError {
     /// Create a new AppError with a custom message
     pub fn new(message: impl Into<String>) -> Self {
-        Self(message.into())
+        Self {
+            message: message.into(),
+            kind: AppErrorKind::General,
+            field: None,
+        }
+    }
+
+    /// Create a `Validation` error about the input `field`
+    pub fn validation(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            kind: AppErrorKind::Validation,
+            field: Some(field.into()),
+        }
+    }
+
+    /// Create an `Unauthenticated` error, for requests that need a signed-in user
+    pub fn unauthenticated(message: impl Into<String>) -> Self {
+        Self::new(message).with_kind(AppErrorKind::Unauthenticated)
+    }
+
+    /// Create a `Forbidden` error, for signed-in users lacking permission
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(message).with_kind(AppErrorKind::Forbidden)
+    }
+
+    /// Create a `NotFound` error
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(message).with_kind(AppErrorKind::NotFound)
+    }
+
+    /// Create a `RateLimited` error, for actions repeated too quickly
+    pub fn rate_limited(message: impl Into<String>) -> Self {
+        Self::new(message).with_kind(AppErrorKind::RateLimited)
+    }
+
+    /// Change the error's kind
+    pub fn with_kind(mut self, kind: AppErrorKind) -> Self {
+        self.kind = kind;
+        self
     }
 
     /// Get the error message
     pub fn message(&self) -> &str {
-        &self.0
+        &self.message
+    }
+
+    pub fn kind(&self) -> AppErrorKind {
+        self.kind
+    }
+
+    /// The input field a `Validation` error refers to
+    pub fn field(&self) -> Option<&str> {
+        self.field.as_deref()
     }
 }
 
@@ -208,11 +845,68 @@ impl FromServerFnError for AppError {
     fn from_server_fn_error(value: ServerFnErrorErr) -> Self {
         Self::new(format!("Server function error: {:?}", value))
     }
+
+    // Called when the error is sent as a server function response; also sets that
+    // response's status from the kind
+    #[cfg(feature = "ssr")]
+    fn ser(&self) -> axum::body::Bytes {
+        use leptos::server_fn::Encodes;
+
+        if let Some(response) = leptos::prelude::use_context::<leptos_axum::ResponseOptions>() {
+            response.set_status(self.kind.status());
+        }
+        JsonEncoding::encode(self).expect("AppError only holds strings and a unit enum")
+    }
 }
 
 impl std::fmt::Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.message)
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl AppError {
+    /// The user-facing message and kind for a store error
+    fn from_auth_error(auth_err: &crate::storage::AuthError) -> Self {
+        use crate::storage::AuthError;
+
+        let kind = match auth_err {
+            AuthError::NotFound => AppErrorKind::NotFound,
+            AuthError::InvalidSession => AppErrorKind::Unauthenticated,
+            AuthError::UserExists
+            | AuthError::SessionLimitReached
+            | AuthError::InstanceSessionLimitReached => AppErrorKind::Conflict,
+            AuthError::InvalidInvite
+            | AuthError::PasswordReused
+            | AuthError::SessionDataTooLarge => AppErrorKind::Validation,
+            AuthError::AccountDisabled | AuthError::AccountLocked => AppErrorKind::Forbidden,
+            AuthError::Internal(_) => AppErrorKind::General,
+        };
+        Self::new(match auth_err {
+            AuthError::NotFound => "User not found",
+            AuthError::InvalidSession => "Your session has expired. Please log in again.",
+            AuthError::UserExists => "A user with this username already exists",
+            AuthError::SessionLimitReached => {
+                "Maximum number of active sessions reached. Please log out of another device."
+            }
+            AuthError::InstanceSessionLimitReached => {
+                "Too many people are signed in right now. Please try again later."
+            }
+            AuthError::InvalidInvite => "This invite code is invalid, expired or used up",
+            AuthError::PasswordReused => {
+                "You used this password recently. Please choose a different one."
+            }
+            AuthError::AccountDisabled => "This account has been disabled",
+            AuthError::AccountLocked => {
+                "This account is locked after too many failed logins. Please try again later."
+            }
+            AuthError::SessionDataTooLarge => {
+                "Too much data on this session. Remove some entries first."
+            }
+            AuthError::Internal(_) => "An internal error occurred. Please try again later.",
+        })
+        .with_kind(kind)
     }
 }
 
@@ -223,7 +917,9 @@ where
     E: std::error::Error + Send + Sync + 'static,
 {
     fn from(err: E) -> Self {
-        use crate::storage::{AuthError, ProjectError};
+        use crate::passkeys::PasskeyError;
+        use crate::registration::RegistrationError;
+        use crate::storage::{AuditError, AuthError, ProjectError};
         use std::any::Any;
 
         // Try to downcast to known error types for better messages
@@ -231,34 +927,63 @@ where
 
         // Check for AuthError
         if let Some(auth_err) = err_any.downcast_ref::<AuthError>() {
-            return Self::new(match auth_err {
-                AuthError::NotFound => "User not found",
-                AuthError::InvalidSession => "Your session has expired. Please log in again.",
-                AuthError::UserExists => "A user with this username already exists",
-                AuthError::SessionLimitReached => {
-                    "Maximum number of active sessions reached. Please log out of another device."
+            return Self::from_auth_error(auth_err);
+        }
+
+        // Check for RegistrationError
+        if let Some(registration_err) = err_any.downcast_ref::<RegistrationError>() {
+            return match registration_err {
+                RegistrationError::Store(auth_err) => Self::from_auth_error(auth_err),
+                RegistrationError::Disabled | RegistrationError::InviteRequired => {
+                    Self::forbidden(registration_err.to_string())
                 }
-                AuthError::Internal(_) => "An internal error occurred. Please try again later.",
-            });
+            };
+        }
+
+        // Check for PasskeyError
+        if let Some(passkey_err @ PasskeyError::TooManyCeremonies) =
+            err_any.downcast_ref::<PasskeyError>()
+        {
+            return Self::rate_limited(passkey_err.to_string());
         }
 
         // Check for ProjectError
         if let Some(project_err) = err_any.downcast_ref::<ProjectError>() {
+            let kind = match project_err {
+                ProjectError::NotFound => AppErrorKind::NotFound,
+                ProjectError::AlreadyExists | ProjectError::DeleteProtected => {
+                    AppErrorKind::Conflict
+                }
+                ProjectError::Unauthorized => AppErrorKind::Forbidden,
+                ProjectError::Internal(_) => AppErrorKind::General,
+            };
             return Self::new(match project_err {
                 ProjectError::NotFound => "Project not found",
                 ProjectError::AlreadyExists => "A project with this name already exists",
                 ProjectError::Unauthorized => "You don't have permission to access this project",
+                ProjectError::DeleteProtected => {
+                    "This project is protected from deletion. Turn off delete protection first."
+                }
                 ProjectError::Internal(_) => "An internal error occurred. Please try again later.",
+            })
+            .with_kind(kind);
+        }
+
+        // Check for AuditError
+        if let Some(audit_err) = err_any.downcast_ref::<AuditError>() {
+            return Self::new(match audit_err {
+                AuditError::Internal(_) => "An internal error occurred. Please try again later.",
             });
         }
 
         // Check for ServerError
         if let Some(server_err) = err_any.downcast_ref::<ServerError>() {
-            return Self::new(match server_err {
-                ServerError::InvalidCreds => "Invalid username or password",
-                ServerError::RequestError => "Request error occurred",
-                ServerError::Unknown => "An unknown error occurred",
-            });
+            return match server_err {
+                ServerError::InvalidCreds => Self::new("Invalid username or password")
+                    .with_kind(AppErrorKind::Unauthenticated),
+                ServerError::RequestError => Self::new("Request error occurred"),
+                ServerError::Unknown => Self::new("An unknown error occurred"),
+            };
         }
 
         // Default: convert to string
@@ -274,6 +999,79 @@ impl From<ServerFnErrorErr> for AppError {
     }
 }
 
+/// The login form field an error concerns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginField {
+    Username,
+    Password,
+}
+
+/// Structured login failure, so the form can show errors next to the relevant field.
+///
+/// `UnknownUser` and `WrongPassword` are only produced when `[security] reveal_login_errors`
+/// is enabled; otherwise both collapse into `InvalidCredentials`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+pub enum LoginError {
+    #[error("{0}")]
+    InvalidUsername(String),
+    #[error("Please enter your password")]
+    MissingPassword,
+    #[error("No account exists with this username")]
+    UnknownUser,
+    #[error("Incorrect password")]
+    WrongPassword,
+    #[error("Invalid username or password")]
+    InvalidCredentials,
+    #[error("This account has been disabled")]
+    AccountDisabled,
+    #[error("Too many failed logins. Try again later or ask an admin to unlock this account")]
+    AccountLocked,
+    #[error("Too many failed logins from your network. Try again later")]
+    TooManyAttempts,
+    /// The user is at the session limit; signing out one of these lets the login through.
+    /// Only produced with `[session] list_on_limit` set.
+    #[error("You're signed in on {} devices. Sign one of them out to continue", .0.len())]
+    SessionLimitReached(Vec<ActiveSession>),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl LoginError {
+    /// The field to show this error next to, or `None` for form-wide errors
+    pub fn field(&self) -> Option<LoginField> {
+        match self {
+            LoginError::InvalidUsername(_) | LoginError::UnknownUser => Some(LoginField::Username),
+            LoginError::MissingPassword | LoginError::WrongPassword => Some(LoginField::Password),
+            LoginError::InvalidCredentials
+            | LoginError::AccountDisabled
+            | LoginError::AccountLocked
+            | LoginError::TooManyAttempts
+            | LoginError::SessionLimitReached(_)
+            | LoginError::Other(_) => None,
+        }
+    }
+}
+
+impl FromServerFnError for LoginError {
+    type Encoder = JsonEncoding;
+
+    fn from_server_fn_error(value: ServerFnErrorErr) -> Self {
+        Self::Other(format!("Server function error: {:?}", value))
+    }
+}
+
+impl From<ServerFnErrorErr> for LoginError {
+    fn from(err: ServerFnErrorErr) -> Self {
+        Self::from_server_fn_error(err)
+    }
+}
+
+impl From<AppError> for LoginError {
+    fn from(err: AppError) -> Self {
+        Self::Other(err.message)
+    }
+}
+
 impl serde::Serialize for PasswordHash {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -314,9 +1112,78 @@ impl<'de> serde::Deserialize<'de> for PasswordHash {
 pub struct ProjectId(pub Uuid);
 
 impl ProjectId {
+    /// Prefix of the opaque form, see [ProjectId::prefixed]
+    pub const PREFIX: &str = "proj_";
+
+    /// Characters of the opaque form's base62 digits, in ascending (ASCII) order
+    const BASE62: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+    /// Digits of the largest UUID in base62; shorter ones are zero-padded, so opaque IDs
+    /// sort like the UUIDs they encode
+    const BASE62_LEN: usize = 22;
+
     pub fn new() -> Self {
         ProjectId(Uuid::now_v7())
     }
+
+    /// The opaque form shown to users, e.g. `proj_0BoXsLO5vB0dy1e2Y7Wq3F`; the UUID stays
+    /// what is stored
+    pub fn prefixed(&self) -> String {
+        let mut value = self.0.as_u128();
+        let mut digits = [b'0'; Self::BASE62_LEN];
+        for digit in digits.iter_mut().rev() {
+            *digit = Self::BASE62[(value % 62) as usize];
+            value /= 62;
+        }
+        let mut id = String::with_capacity(Self::PREFIX.len() + Self::BASE62_LEN);
+        id.push_str(Self::PREFIX);
+        id.extend(digits.iter().map(|&digit| digit as char));
+        id
+    }
+
+    /// Reads a project ID in its opaque `proj_` form or as a plain UUID
+    pub fn parse(value: &str) -> Result<Self, ProjectIdError> {
+        let Some(digits) = value.strip_prefix(Self::PREFIX) else {
+            return Uuid::parse_str(value)
+                .map(ProjectId)
+                .map_err(|_| ProjectIdError::Malformed);
+        };
+        if digits.len() != Self::BASE62_LEN {
+            return Err(ProjectIdError::InvalidLength);
+        }
+
+        let mut value: u128 = 0;
+        for byte in digits.bytes() {
+            let digit = Self::BASE62
+                .iter()
+                .position(|&c| c == byte)
+                .ok_or(ProjectIdError::InvalidCharacter)?;
+            value = value
+                .checked_mul(62)
+                .and_then(|value| value.checked_add(digit as u128))
+                .ok_or(ProjectIdError::OutOfRange)?;
+        }
+        Ok(ProjectId(Uuid::from_u128(value)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProjectIdError {
+    #[error("Project ID must be a UUID or start with \"{}\"", ProjectId::PREFIX)]
+    Malformed,
+    #[error(
+        "Project ID must have {} characters after \"{}\"",
+        ProjectId::BASE62_LEN,
+        ProjectId::PREFIX
+    )]
+    InvalidLength,
+    #[error(
+        "Project ID may only contain letters and digits after \"{}\"",
+        ProjectId::PREFIX
+    )]
+    InvalidCharacter,
+    #[error("Project ID is out of range")]
+    OutOfRange,
 }
 
 impl Default for ProjectId {
@@ -334,6 +1201,102 @@ pub struct Project {
     pub description: Option<String>,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
+    /// Requests per minute allowed on this project's endpoints; unlimited when `None`
+    pub rate_limit_rpm: Option<u32>,
+    #[serde(default)]
+    pub visibility: ProjectVisibility,
+    /// URL-friendly form of the name, unique across all projects, for `/p/{slug}`
+    #[serde(default)]
+    pub slug: String,
+    /// Refuse to delete the project until this is turned off again
+    #[serde(default)]
+    pub delete_protected: bool,
+}
+
+impl Project {
+    /// Whether `viewer` (`None` when anonymous) may read this project.
+    ///
+    /// Owners always can; anyone else only if the project isn't private.
+    pub fn is_viewable_by(&self, viewer: Option<&UserId>) -> bool {
+        viewer == Some(&self.owner_id) || self.visibility != ProjectVisibility::Private
+    }
+}
+
+/// Who can read a project without owning it
+///
+/// - Private:
+///   Only the owner
+/// - Unlisted:
+///   Anyone with the project id, but never shown in public listings
+/// - Public:
+///   Anyone, read-only
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectVisibility {
+    #[default]
+    Private,
+    Unlisted,
+    Public,
+}
+
+impl ProjectVisibility {
+    pub const ALL: [ProjectVisibility; 3] = [
+        ProjectVisibility::Private,
+        ProjectVisibility::Unlisted,
+        ProjectVisibility::Public,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProjectVisibility::Private => "private",
+            ProjectVisibility::Unlisted => "unlisted",
+            ProjectVisibility::Public => "public",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|visibility| visibility.as_str() == value)
+    }
+}
+
+/// Limits of this instance that apply to the signed-in user, see `get_instance_limits`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceLimits {
+    /// Most projects the user may own (`[projects] max_per_user`); `None` if unlimited
+    pub max_projects_per_user: Option<u32>,
+}
+
+impl InstanceLimits {
+    /// "Project limit reached (N/N)" once a user owning `count` projects can't create
+    /// another, `None` while they can
+    pub fn project_limit_reached(&self, count: usize) -> Option<String> {
+        let max = self.max_projects_per_user?;
+        (count >= max as usize).then(|| format!("Project limit reached ({count}/{max})"))
+    }
+}
+
+/// Usage figures of a project, as last gathered by the metrics collector (see
+/// `project_metrics`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectMetrics {
+    /// Bytes of data the project keeps
+    pub storage_bytes: u64,
+    /// Users of the project
+    pub users: u64,
+    /// When the figures were gathered; `None` until the collector first ran for the project
+    pub collected_at: Option<OffsetDateTime>,
+}
+
+/// One page of a user's projects, newest first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectPage {
+    pub projects: Vec<ProjectSummary>,
+    /// Number of projects across all pages
+    pub total: usize,
+    /// Pass as `after` to get the next page; `None` on the last page
+    pub next_cursor: Option<ProjectId>,
 }
 
 /// Lightweight project summary for listing/display purposes
@@ -343,6 +1306,10 @@ pub struct ProjectSummary {
     pub name: String,
     pub description: Option<String>,
     pub created_at: OffsetDateTime,
+    pub rate_limit_rpm: Option<u32>,
+    pub visibility: ProjectVisibility,
+    pub slug: String,
+    pub delete_protected: bool,
 }
 
 impl From<Project> for ProjectSummary {
@@ -352,6 +1319,10 @@ impl From<Project> for ProjectSummary {
             name: project.name,
             description: project.description,
             created_at: project.created_at,
+            rate_limit_rpm: project.rate_limit_rpm,
+            visibility: project.visibility,
+            slug: project.slug,
+            delete_protected: project.delete_protected,
         }
     }
 }
@@ -363,6 +1334,10 @@ impl From<&Project> for ProjectSummary {
             name: project.name.clone(),
             description: project.description.clone(),
             created_at: project.created_at,
+            rate_limit_rpm: project.rate_limit_rpm,
+            visibility: project.visibility,
+            slug: project.slug.clone(),
+            delete_protected: project.delete_protected,
         }
     }
 }
@@ -377,3 +1352,216 @@ pub struct ProjectData {
     pub users_count: String,
     pub active_connections: String,
 }
+
+/*
+ * Audit log
+ */
+
+/// Kinds of events recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AuditKind {
+    Login,
+    LoginFailed,
+    Logout,
+    UserRegistered,
+    ProjectCreated,
+    ProjectUpdated,
+    ProjectDeleted,
+    InviteCreated,
+    InviteRevoked,
+    PasskeyRegistrationStarted,
+    PasskeyAdded,
+    PasswordReset,
+    UserDisabled,
+    UserEnabled,
+    UsernameChanged,
+    UserUnlocked,
+    ProjectRenamed,
+    PasswordChanged,
+    /// Disabled by the `[accounts] inactivity_days` job; recorded without an actor
+    UserDisabledInactive,
+    ImpersonationStarted,
+    ImpersonationEnded,
+    /// Sessions of any users ended by `admin_revoke_sessions`
+    SessionsRevoked,
+}
+
+impl AuditKind {
+    pub const ALL: [AuditKind; 22] = [
+        AuditKind::Login,
+        AuditKind::LoginFailed,
+        AuditKind::Logout,
+        AuditKind::UserRegistered,
+        AuditKind::ProjectCreated,
+        AuditKind::ProjectUpdated,
+        AuditKind::ProjectDeleted,
+        AuditKind::InviteCreated,
+        AuditKind::InviteRevoked,
+        AuditKind::PasskeyRegistrationStarted,
+        AuditKind::PasskeyAdded,
+        AuditKind::PasswordReset,
+        AuditKind::UserDisabled,
+        AuditKind::UserEnabled,
+        AuditKind::UsernameChanged,
+        AuditKind::UserUnlocked,
+        AuditKind::ProjectRenamed,
+        AuditKind::PasswordChanged,
+        AuditKind::UserDisabledInactive,
+        AuditKind::ImpersonationStarted,
+        AuditKind::ImpersonationEnded,
+        AuditKind::SessionsRevoked,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuditKind::Login => "Login",
+            AuditKind::LoginFailed => "Failed login",
+            AuditKind::Logout => "Logout",
+            AuditKind::UserRegistered => "User registered",
+            AuditKind::ProjectCreated => "Project created",
+            AuditKind::ProjectUpdated => "Project updated",
+            AuditKind::ProjectDeleted => "Project deleted",
+            AuditKind::InviteCreated => "Invite created",
+            AuditKind::InviteRevoked => "Invite revoked",
+            AuditKind::PasskeyRegistrationStarted => "Passkey registration started",
+            AuditKind::PasskeyAdded => "Passkey added",
+            AuditKind::PasswordReset => "Password reset",
+            AuditKind::UserDisabled => "User disabled",
+            AuditKind::UserEnabled => "User enabled",
+            AuditKind::UsernameChanged => "Username changed",
+            AuditKind::UserUnlocked => "User unlocked",
+            AuditKind::ProjectRenamed => "Project renamed",
+            AuditKind::PasswordChanged => "Password changed",
+            AuditKind::UserDisabledInactive => "User disabled for inactivity",
+            AuditKind::ImpersonationStarted => "Impersonation started",
+            AuditKind::ImpersonationEnded => "Impersonation ended",
+            AuditKind::SessionsRevoked => "Sessions revoked",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub id: Uuid,
+    pub at: OffsetDateTime,
+    /// The user who performed the action, if known
+    pub actor: Option<UserId>,
+    pub kind: AuditKind,
+    /// Free-form context, e.g. the affected project's name
+    pub detail: String,
+    /// The project the event concerns, shown in its activity feed
+    #[serde(default)]
+    pub project_id: Option<ProjectId>,
+}
+
+#[cfg(feature = "ssr")]
+impl AuditEvent {
+    pub fn new(actor: Option<UserId>, kind: AuditKind, detail: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            at: OffsetDateTime::now_utc(),
+            actor,
+            kind,
+            detail: detail.into(),
+            project_id: None,
+        }
+    }
+
+    /// Scopes the event to `project_id`
+    pub fn with_project(mut self, project_id: ProjectId) -> Self {
+        self.project_id = Some(project_id);
+        self
+    }
+
+    /// Attributes the event to `admin`, who did it while impersonating `username`
+    pub fn impersonated_by(mut self, admin: UserId, username: &str) -> Self {
+        self.actor = Some(admin);
+        self.detail = if self.detail.is_empty() {
+            format!("as {username}")
+        } else {
+            format!("{} (as {username})", self.detail)
+        };
+        self
+    }
+}
+
+/// Filter for audit log queries; every set field must match.
+///
+/// Results are returned newest first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditQuery {
+    pub actor: Option<UserId>,
+    /// Only events concerning this project
+    pub project_id: Option<ProjectId>,
+    /// Only these kinds; all kinds when empty
+    pub kinds: Vec<AuditKind>,
+    /// Inclusive lower bound on the event time
+    pub since: Option<OffsetDateTime>,
+    /// Inclusive upper bound on the event time
+    pub until: Option<OffsetDateTime>,
+    /// Maximum number of events to return; unlimited when 0
+    pub limit: usize,
+}
+
+impl AuditQuery {
+    /// Checks the actor and kind filters (the time range is handled by the store's key scan)
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        self.actor.is_none_or(|actor| event.actor == Some(actor))
+            && self
+                .project_id
+                .is_none_or(|project_id| event.project_id == Some(project_id))
+            && (self.kinds.is_empty() || self.kinds.contains(&event.kind))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixed_project_ids_round_trip() {
+        for id in [
+            ProjectId::new(),
+            ProjectId(Uuid::nil()),
+            ProjectId(Uuid::from_u128(u128::MAX)),
+        ] {
+            let prefixed = id.prefixed();
+            assert!(prefixed.starts_with(ProjectId::PREFIX));
+            assert_eq!(prefixed.len(), ProjectId::PREFIX.len() + 22);
+            assert_eq!(ProjectId::parse(&prefixed), Ok(id));
+            // plain UUIDs are still accepted
+            assert_eq!(ProjectId::parse(&id.0.to_string()), Ok(id));
+        }
+
+        // opaque IDs sort like the UUIDs they encode
+        let (low, high) = (
+            ProjectId(Uuid::from_u128(61)),
+            ProjectId(Uuid::from_u128(62)),
+        );
+        assert!(low.prefixed() < high.prefixed());
+    }
+
+    #[test]
+    fn invalid_project_ids_are_rejected() {
+        assert_eq!(
+            ProjectId::parse("prj_0BoXsLO5vB0dy1e2Y7Wq3F"),
+            Err(ProjectIdError::Malformed)
+        );
+        assert_eq!(
+            ProjectId::parse("proj_0BoXsLO5"),
+            Err(ProjectIdError::InvalidLength)
+        );
+        assert_eq!(
+            ProjectId::parse("proj_0BoXsLO5vB0dy1e2Y7Wq3-"),
+            Err(ProjectIdError::InvalidCharacter)
+        );
+        assert_eq!(
+            ProjectId::parse("proj_zzzzzzzzzzzzzzzzzzzzzz"),
+            Err(ProjectIdError::OutOfRange)
+        );
+        assert_eq!(
+            ProjectId::parse("proj_!").unwrap_err().to_string(),
+            "Project ID must have 22 characters after \"proj_\""
+        );
+    }
+}
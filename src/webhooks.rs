@@ -0,0 +1,263 @@
+//! Outgoing webhooks for project events.
+//!
+//! Events are pushed onto a bounded queue and delivered by a background task, so a slow or
+//! unreachable endpoint never delays the request that triggered the event. Each delivery is
+//! a JSON `POST` signed with HMAC-SHA256 over the raw body:
+//!
+//! ```text
+//! X-Bento-Event: project_created
+//! X-Bento-Signature: sha256=<hex digest>
+//! ```
+
+use std::fmt::Write as _;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use time::OffsetDateTime;
+use tokio::sync::mpsc;
+use tokio::time::{Duration, sleep};
+use tracing::{debug, error, warn};
+
+use crate::config::{self, WebhookEndpoint};
+use crate::types::{Project, ProjectId, UserId};
+
+pub const EVENT_HEADER: &str = "X-Bento-Event";
+pub const SIGNATURE_HEADER: &str = "X-Bento-Signature";
+
+/// Events waiting for delivery; further events are dropped (and logged) while full
+const QUEUE_CAPACITY: usize = 256;
+/// Delivery attempts per endpoint before an event is given up on
+const MAX_ATTEMPTS: u32 = 5;
+/// Delay before the first retry, doubled after every failed attempt
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectEventKind {
+    ProjectCreated,
+    ProjectUpdated,
+    ProjectDeleted,
+}
+
+impl ProjectEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProjectEventKind::ProjectCreated => "project_created",
+            ProjectEventKind::ProjectUpdated => "project_updated",
+            ProjectEventKind::ProjectDeleted => "project_deleted",
+        }
+    }
+}
+
+/// Webhook payload describing a project mutation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectEvent {
+    pub event: ProjectEventKind,
+    pub project_id: ProjectId,
+    pub owner_id: UserId,
+    pub name: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub occurred_at: OffsetDateTime,
+}
+
+impl ProjectEvent {
+    pub fn new(event: ProjectEventKind, project: &Project) -> Self {
+        Self {
+            event,
+            project_id: project.id,
+            owner_id: project.owner_id,
+            name: project.name.clone(),
+            occurred_at: OffsetDateTime::now_utc(),
+        }
+    }
+}
+
+/// Computes the `X-Bento-Signature` header value for a request body
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(body);
+
+    let digest = mac.finalize().into_bytes();
+    let mut signature = String::with_capacity(7 + digest.len() * 2);
+    signature.push_str("sha256=");
+    for byte in digest {
+        let _ = write!(signature, "{byte:02x}");
+    }
+    signature
+}
+
+/// Handle for queueing webhook events; cheap to clone into request handlers.
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    queue: mpsc::Sender<ProjectEvent>,
+}
+
+impl WebhookDispatcher {
+    /// Starts the background delivery task for the configured endpoints.
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn spawn(conf: &config::Webhooks) -> Self {
+        let (queue, events) = mpsc::channel(QUEUE_CAPACITY);
+        let worker = WebhookWorker {
+            client: reqwest::Client::new(),
+            secret: conf.secret.clone().into_bytes(),
+            endpoints: conf.endpoints.clone(),
+        };
+        tokio::spawn(worker.run(events));
+
+        Self { queue }
+    }
+
+    /// Queues an event for delivery without waiting for it to be sent
+    pub fn notify(&self, event: ProjectEvent) {
+        if let Err(e) = self.queue.try_send(event) {
+            warn!("Dropping webhook event, delivery queue unavailable: {e}");
+        }
+    }
+}
+
+struct WebhookWorker {
+    client: reqwest::Client,
+    secret: Vec<u8>,
+    endpoints: Vec<WebhookEndpoint>,
+}
+
+impl WebhookWorker {
+    async fn run(self, mut events: mpsc::Receiver<ProjectEvent>) {
+        while let Some(event) = events.recv().await {
+            let body = match serde_json::to_vec(&event) {
+                Ok(body) => body,
+                Err(e) => {
+                    error!("Failed to serialize webhook payload: {e}");
+                    continue;
+                }
+            };
+            let signature = sign(&self.secret, &body);
+
+            for endpoint in self.endpoints.iter().filter(|e| e.wants(event.event)) {
+                self.deliver(&endpoint.url, event.event, &body, &signature)
+                    .await;
+            }
+        }
+    }
+
+    async fn deliver(&self, url: &str, event: ProjectEventKind, body: &[u8], signature: &str) {
+        let mut backoff = INITIAL_BACKOFF;
+
+        for attempt in 1..=MAX_ATTEMPTS {
+            let result = self
+                .client
+                .post(url)
+                .timeout(REQUEST_TIMEOUT)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, event.as_str())
+                .header(SIGNATURE_HEADER, signature)
+                .body(body.to_vec())
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+
+            match result {
+                Ok(_) => {
+                    debug!(url, event = event.as_str(), attempt, "Webhook delivered");
+                    return;
+                }
+                Err(e) if attempt < MAX_ATTEMPTS => {
+                    warn!(url, attempt, "Webhook delivery failed, retrying: {e}");
+                    sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(e) => {
+                    error!(url, attempt, "Webhook delivery failed, giving up: {e}");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ProjectVisibility;
+    use axum::{Router, body::Bytes, extract::State, http::HeaderMap, http::StatusCode};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Clone)]
+    struct MockEndpoint {
+        attempts: Arc<AtomicUsize>,
+        received: mpsc::Sender<(HeaderMap, Bytes)>,
+    }
+
+    async fn receive(
+        State(mock): State<MockEndpoint>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> StatusCode {
+        // fail the first attempt to exercise the retry path
+        if mock.attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+            return StatusCode::SERVICE_UNAVAILABLE;
+        }
+        mock.received.send((headers, body)).await.unwrap();
+        StatusCode::OK
+    }
+
+    #[tokio::test]
+    async fn delivers_signed_payload_with_retry() {
+        let (received, mut deliveries) = mpsc::channel(1);
+        let mock = MockEndpoint {
+            attempts: Arc::new(AtomicUsize::new(0)),
+            received,
+        };
+        let app = Router::new()
+            .route("/hook", axum::routing::post(receive))
+            .with_state(mock.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let dispatcher = WebhookDispatcher::spawn(&config::Webhooks {
+            secret: "hunter2".to_string(),
+            endpoints: vec![WebhookEndpoint {
+                url: format!("http://{addr}/hook"),
+                events: vec![ProjectEventKind::ProjectCreated],
+            }],
+        });
+
+        let project = Project {
+            id: ProjectId::new(),
+            owner_id: UserId::new(),
+            name: "lunchbox".to_string(),
+            description: None,
+            created_at: OffsetDateTime::now_utc(),
+            updated_at: OffsetDateTime::now_utc(),
+            rate_limit_rpm: None,
+            visibility: ProjectVisibility::Private,
+            slug: "lunchbox".to_string(),
+            delete_protected: false,
+        };
+        // not subscribed, must not be delivered
+        dispatcher.notify(ProjectEvent::new(
+            ProjectEventKind::ProjectDeleted,
+            &project,
+        ));
+        let event = ProjectEvent::new(ProjectEventKind::ProjectCreated, &project);
+        dispatcher.notify(event.clone());
+
+        let (headers, body) = tokio::time::timeout(Duration::from_secs(10), deliveries.recv())
+            .await
+            .expect("webhook delivered in time")
+            .unwrap();
+
+        assert_eq!(mock.attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(headers[EVENT_HEADER], "project_created");
+        assert_eq!(headers[SIGNATURE_HEADER], sign(b"hunter2", &body).as_str());
+        assert_ne!(headers[SIGNATURE_HEADER], sign(b"wrong", &body).as_str());
+        assert_eq!(
+            serde_json::from_slice::<ProjectEvent>(&body).unwrap(),
+            event
+        );
+    }
+}
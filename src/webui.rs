@@ -1,10 +1,29 @@
+pub mod account;
+#[cfg(feature = "ssr")]
+pub mod admin_events;
+pub mod audit_log;
+pub mod components;
 #[cfg(feature = "ssr")]
 pub mod cookies;
+pub mod csrf;
 pub mod icons;
+pub mod impersonation;
+pub mod maintenance;
+#[cfg(feature = "ssr")]
+pub mod middleware;
+pub mod passkeys;
+pub mod project_activity;
+pub mod project_slug;
 pub mod screen_home;
 pub mod screen_login;
+pub mod screen_settings;
+pub mod session_expiry;
+pub mod user_admin;
 
+use components::{ToastContext, ToastHost};
+use project_slug::ProjectBySlug;
 use screen_home::HomeScreen;
+use screen_settings::SettingsScreen;
 
 use leptos::prelude::*;
 use leptos_meta::{MetaTags, Stylesheet, Title, provide_meta_context};
@@ -14,20 +33,44 @@ use leptos_router::{
 };
 
 use crate::{
-    types::{AppError, Project, ProjectSummary, Session},
+    build_info::BuildInfo,
+    types::{
+        AppError, AuditEvent, AuditQuery, Branding, BulkUserResult, DatabaseReport, InstanceLimits,
+        InviteCode, IpSessionCount, MaintenanceReport, NewLoginNotice, NewUser, PerfReport,
+        PreviousLogin, Project, ProjectMetrics, ProjectPage, ProjectSummary, ProjectVisibility,
+        Role, Session, SessionCriteria, SessionInfo, UserId, UserStatus, UserSummary,
+    },
     webui::screen_login::LoginScreen,
 };
 
 pub fn shell(options: LeptosOptions) -> impl IntoView {
+    // the shell is only rendered on the server, where the config is at hand
+    #[cfg(feature = "ssr")]
+    let branding = crate::config::LOCAL_CONF.branding.clone();
+    #[cfg(not(feature = "ssr"))]
+    let branding = Branding::default();
+
+    #[cfg(feature = "ssr")]
+    let theme_cookie = use_context::<axum::http::request::Parts>().and_then(|parts| {
+        axum_extra::extract::CookieJar::from_headers(&parts.headers)
+            .get(cookies::THEME_COOKIE_NAME)
+            .map(|cookie| cookie.value().to_string())
+    });
+    #[cfg(not(feature = "ssr"))]
+    let theme_cookie: Option<String> = None;
+    let theme = branding.theme(theme_cookie.as_deref());
+
     view! {
         <!DOCTYPE html>
-        <html lang="en" data-theme="night">
+        <html lang="en" data-theme=theme>
             <head>
                 <meta charset="utf-8"/>
                 <meta name="viewport" content="width=device-width, initial-scale=1"/>
                 <link href="https://cdn.jsdelivr.net/npm/daisyui@5" rel="stylesheet" type="text/css" />
                 <link href="https://cdn.jsdelivr.net/npm/daisyui@5/themes.css" rel="stylesheet" type="text/css" />
                 <script src="https://cdn.jsdelivr.net/npm/@tailwindcss/browser@4" />
+                <link rel="icon" href=branding.logo_path.clone() />
+                <style>{format!(":root {{ {} }}", branding.css_variables())}</style>
                 <AutoReload options=options.clone() />
                 <HydrationScripts options/>
                 <MetaTags/>
@@ -43,6 +86,13 @@ pub fn shell(options: LeptosOptions) -> impl IntoView {
 pub fn App() -> impl IntoView {
     // provides context that manages stylesheets, titles, meta tags, etc.
     provide_meta_context();
+    csrf::CsrfContext::provide();
+    ToastContext::provide();
+
+    // blocking, so the title is known before the <head> is streamed
+    let branding = Resource::new_blocking(|| (), |_| get_branding());
+    provide_context(BrandingContext(branding));
+    let branding = use_branding();
 
     view! {
         // injects a stylesheet into the document <head>
@@ -50,13 +100,16 @@ pub fn App() -> impl IntoView {
         <Stylesheet id="leptos" href="/pkg/bento.css" />
 
         // sets the document title
-        <Title text="Bento: Backend Toolbox" />
+        <Title text=move || branding.get().page_title() />
 
         <Router>
             <Routes fallback=|| "Page not found.".into_view()>
                 <Route path=path!("/") view=RootView />
+                <Route path=path!("/p/:slug") view=ProjectBySlug />
+                <Route path=path!("/settings") view=SettingsScreen />
             </Routes>
         </Router>
+        <ToastHost />
     }
 }
 
@@ -84,84 +137,624 @@ pub fn RootView() -> impl IntoView {
     }
 }
 
+#[derive(Clone, Copy)]
+struct BrandingContext(Resource<Result<Branding, AppError>>);
+
+/// The configured branding, falling back to the defaults until it has loaded
+pub fn use_branding() -> Signal<Branding> {
+    let branding = use_context::<BrandingContext>();
+    Signal::derive(move || {
+        branding
+            .and_then(|BrandingContext(resource)| resource.get())
+            .and_then(Result::ok)
+            .unwrap_or_default()
+    })
+}
+
 #[component]
 pub fn LogoSvg(size: i32, #[prop(optional)] class: Option<&'static str>) -> impl IntoView {
+    let branding = use_branding();
+
     view! {
         <img
             class={format!("h-{size} w-{size} {}", class.unwrap_or_default())}
-            src="/bento-dark.svg"
-            alt="Bento logo"
+            src=move || branding.get().logo_path
+            alt=move || format!("{} logo", branding.get().app_name)
         />
     }
 }
 
 /// Helper function to authenticate a user and issue a session.
 ///
-/// Returns the issued session if authentication succeeds.
+/// Returns the issued session and the user's previous login if authentication succeeds.
+/// `sign_out` is one of the user's sessions to end first, picked from a
+/// `LoginError::SessionLimitReached`.
 #[cfg(feature = "ssr")]
 async fn authenticate_user(
     username: &str,
     password: &str,
     client_ip: std::net::IpAddr,
-) -> Result<Session, AppError> {
+    user_agent: Option<crate::types::UserAgentHash>,
+    sign_out: Option<&str>,
+) -> Result<(Session, Option<crate::types::PreviousLogin>), crate::types::LoginError> {
+    use crate::config::LOCAL_CONF;
     use crate::server::AppState;
-    use crate::storage::AuthStore;
-    use crate::types::{SessionIp, Username};
+
+    // timed apart from `login` as it includes the password hash check
+    let _timer = crate::perf::timer("authenticate_user");
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let auth_store = app_state.auth_store.clone();
+    let reveal_errors = LOCAL_CONF.security.reveal_login_errors;
+
+    let attempt = check_login_with_lockout(
+        auth_store.as_ref(),
+        &LOCAL_CONF.lockout,
+        username,
+        password,
+        reveal_errors,
+        time::OffsetDateTime::now_utc(),
+    );
+    let user = match throttle_login(&app_state.login_throttle, client_ip, username, attempt).await {
+        Ok(user) => user,
+        Err(e) => {
+            log_failed_login("login", username, client_ip, &e).await;
+            return Err(e);
+        }
+    };
+
+    complete_login("login", &user, client_ip, user_agent, sign_out).await
+}
+
+/// Helper function backing `authenticate_user` and passkey login: signs in a user whose
+/// credentials checked out.
+///
+/// Records the login, issues the session under the `[session]` limits and logs the outcome
+/// as `event`. Returns the session and the user's previous login.
+#[cfg(feature = "ssr")]
+async fn complete_login(
+    event: &'static str,
+    user: &crate::types::User,
+    client_ip: std::net::IpAddr,
+    user_agent: Option<crate::types::UserAgentHash>,
+    sign_out: Option<&str>,
+) -> Result<(Session, Option<crate::types::PreviousLogin>), crate::types::LoginError> {
+    use crate::config::LOCAL_CONF;
+    use crate::security_log::{self, Outcome};
+    use crate::server::AppState;
+    use crate::types::AuditKind;
 
     let app_state: AppState = use_context().expect("Axum state in leptos context");
     let auth_store = app_state.auth_store.clone();
+    // the throttle sees the full address, anything kept only what `[privacy]` allows
+    let recorded_ip = LOCAL_CONF.privacy.ip_storage.apply(client_ip);
+
+    let session_ip = crate::types::SessionIp(recorded_ip);
+    let previous = auth_store
+        .record_login(&user.id, session_ip.clone())
+        .await
+        .map_err(AppError::from)?;
+    let issued = issue_login_session(
+        auth_store.as_ref(),
+        &user.id,
+        session_ip,
+        user_agent,
+        sign_out,
+        LOCAL_CONF.session.list_on_limit,
+    )
+    .await;
+    let session = match issued {
+        Ok(session) => session,
+        Err(e) => {
+            security_log::auth_event(
+                &LOCAL_CONF.logging,
+                event,
+                &user.username.0,
+                recorded_ip,
+                Outcome::from(&e),
+            );
+            return Err(e);
+        }
+    };
+    security_log::auth_event(
+        &LOCAL_CONF.logging,
+        event,
+        &user.username.0,
+        recorded_ip,
+        Outcome::Success,
+    );
+    record_audit(Some(user.id), AuditKind::Login, user.username.0.clone()).await;
+    Ok((session, previous.last_login()))
+}
+
+/// Helper function to log and audit a refused sign-in as `event`.
+#[cfg(feature = "ssr")]
+async fn log_failed_login(
+    event: &'static str,
+    username: &str,
+    client_ip: std::net::IpAddr,
+    error: &crate::types::LoginError,
+) {
+    use crate::config::LOCAL_CONF;
+    use crate::security_log::{self, Outcome};
+    use crate::types::AuditKind;
+
+    security_log::auth_event(
+        &LOCAL_CONF.logging,
+        event,
+        username,
+        LOCAL_CONF.privacy.ip_storage.apply(client_ip),
+        Outcome::from(error),
+    );
+    record_audit(None, AuditKind::LoginFailed, username).await;
+}
+
+/// Helper function backing [complete_login]: issues the session of a user whose
+/// credentials checked out.
+///
+/// `sign_out` (a [SessionId::public_id](crate::types::SessionId::public_id)) is revoked
+/// first if it's one of the user's sessions. With `list_on_limit` set, a user at the session
+/// limit gets their sessions back in `LoginError::SessionLimitReached` to pick from.
+#[cfg(feature = "ssr")]
+async fn issue_login_session<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    user_id: &UserId,
+    session_ip: crate::types::SessionIp,
+    user_agent: Option<crate::types::UserAgentHash>,
+    sign_out: Option<&str>,
+    list_on_limit: bool,
+) -> Result<Session, crate::types::LoginError> {
+    use crate::storage::AuthError;
+    use crate::types::{ActiveSession, LoginError};
+
+    if let Some(public_id) = sign_out {
+        let sessions = auth_store
+            .list_user_sessions(user_id)
+            .await
+            .map_err(AppError::from)?;
+        if let Some(session) = sessions.iter().find(|s| s.id.public_id() == public_id) {
+            auth_store
+                .revoke_session(&session.id)
+                .await
+                .map_err(AppError::from)?;
+        }
+    }
+
+    match auth_store
+        .issue_session(user_id, session_ip, user_agent)
+        .await
+    {
+        Ok(session) => Ok(session),
+        Err(AuthError::SessionLimitReached) if list_on_limit => {
+            let sessions = auth_store
+                .list_user_sessions(user_id)
+                .await
+                .map_err(AppError::from)?;
+            Err(LoginError::SessionLimitReached(
+                sessions.iter().map(ActiveSession::from).collect(),
+            ))
+        }
+        Err(e) => Err(AppError::from(e).into()),
+    }
+}
+
+/// Helper function backing `authenticate_user`: validates the form input and checks
+/// the credentials, returning the user on success.
+///
+/// Unless `reveal_errors` is set, unknown users and wrong passwords produce the same
+/// `LoginError::InvalidCredentials`. Disabled accounts are only reported once the password
+/// checks out.
+#[cfg(feature = "ssr")]
+async fn check_login<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    username: &str,
+    password: &str,
+    reveal_errors: bool,
+) -> Result<crate::types::User, crate::types::LoginError> {
+    use crate::storage::AuthError;
+    use crate::types::{LoginError, Username};
+
+    let username =
+        Username::parse(username.trim()).map_err(|e| LoginError::InvalidUsername(e.to_string()))?;
+    if password.is_empty() {
+        return Err(LoginError::MissingPassword);
+    }
+
+    let user = match auth_store.get_user_by_username(&username).await {
+        Ok(user) => user,
+        Err(AuthError::NotFound) if reveal_errors => return Err(LoginError::UnknownUser),
+        Err(AuthError::NotFound) => return Err(LoginError::InvalidCredentials),
+        Err(e) => return Err(AppError::from(e).into()),
+    };
+
+    if !user.password_hash.verify(password) {
+        if reveal_errors {
+            Err(LoginError::WrongPassword)
+        } else {
+            Err(LoginError::InvalidCredentials)
+        }
+    } else if !user.is_active() {
+        Err(LoginError::AccountDisabled)
+    } else {
+        Ok(user)
+    }
+}
+
+/// Helper function wrapping [check_login] with the `[lockout]` policy.
+///
+/// Locked accounts are refused before their password is checked, so a lock can't be used to
+/// keep guessing. With locking enabled, wrong passwords count towards a lock.
+#[cfg(feature = "ssr")]
+async fn check_login_with_lockout<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    lockout: &crate::config::Lockout,
+    username: &str,
+    password: &str,
+    reveal_errors: bool,
+    now: time::OffsetDateTime,
+) -> Result<crate::types::User, crate::types::LoginError> {
+    use crate::types::{LoginError, Username};
+
+    let existing = match Username::parse(username.trim()) {
+        Ok(username) => auth_store.get_user_by_username(&username).await.ok(),
+        Err(_) => None,
+    };
+    if existing.as_ref().is_some_and(|user| user.is_locked(now)) {
+        return Err(LoginError::AccountLocked);
+    }
+
+    let result = check_login(auth_store, username, password, reveal_errors).await;
+    if let (Some(user), Err(LoginError::WrongPassword | LoginError::InvalidCredentials)) =
+        (&existing, &result)
+        && lockout.enabled()
+    {
+        auth_store
+            .record_failed_login(&user.id, lockout.max_failed_logins, lockout.duration())
+            .await
+            .map_err(AppError::from)?;
+    }
+    result
+}
+
+/// Helper function wrapping a login `attempt` with the address-wide `[lockout]` policy.
+///
+/// Blocked addresses are refused without running the attempt, so they can't keep guessing
+/// across accounts. Unknown users and wrong passwords count against the address, whichever
+/// account they targeted.
+#[cfg(feature = "ssr")]
+async fn throttle_login<T>(
+    throttle: &crate::rate_limit::IpLoginThrottle,
+    client_ip: std::net::IpAddr,
+    username: &str,
+    attempt: impl Future<Output = Result<T, crate::types::LoginError>>,
+) -> Result<T, crate::types::LoginError> {
+    use crate::types::LoginError;
+
+    if throttle.check(&client_ip).is_err() {
+        tracing::debug!(ip = %client_ip, "Login refused: address blocked");
+        return Err(LoginError::TooManyAttempts);
+    }
+    let result = attempt.await;
+    if matches!(
+        result,
+        Err(LoginError::WrongPassword | LoginError::InvalidCredentials | LoginError::UnknownUser)
+    ) {
+        throttle.record_failure(client_ip, username);
+    }
+    result
+}
 
-    // strong type for username
-    let username = Username(username.to_string());
+/// Helper function to load the current user, failing unless they are an admin.
+#[cfg(feature = "ssr")]
+async fn require_admin() -> Result<crate::types::User, AppError> {
+    use crate::types::Role;
 
-    // Get user by username
-    let user = auth_store.get_user_by_username(&username).await?;
+    let user = signed_in()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?
+        .user;
 
-    // Verify password
-    if user.password_hash.verify(password) {
-        let session_ip = SessionIp(client_ip);
-        let session = auth_store.issue_session(&user.id, session_ip).await?;
-        Ok(session)
+    if user.role == Role::Admin {
+        Ok(user)
     } else {
-        Err(AppError::new("Invalid username or password"))
+        Err(AppError::forbidden("This action requires admin privileges"))
+    }
+}
+
+/// Get the current session, failing with `ReauthRequired` unless its user entered their
+/// password within `max_age`. Guards sensitive actions; the client answers the error by
+/// asking for the password and calling `reauthenticate`.
+///
+/// Impersonation sessions never pass, see `refuse_impersonated`.
+#[cfg(feature = "ssr")]
+async fn require_recent_auth(max_age: time::Duration) -> Result<Session, AppError> {
+    let session = fetch_session()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+    check_recent_auth(&session, max_age, time::OffsetDateTime::now_utc())?;
+    Ok(session)
+}
+
+/// Helper function backing `require_recent_auth`.
+#[cfg(feature = "ssr")]
+fn check_recent_auth(
+    session: &Session,
+    max_age: time::Duration,
+    now: time::OffsetDateTime,
+) -> Result<(), AppError> {
+    use crate::types::AppErrorKind;
+
+    refuse_impersonated(session)?;
+    if now - session.authenticated_at() > max_age {
+        return Err(
+            AppError::new("Please enter your password again to continue")
+                .with_kind(AppErrorKind::ReauthRequired),
+        );
+    }
+    Ok(())
+}
+
+/// Helper function refusing `session` if an admin started it with `impersonate`.
+///
+/// Credentials and identity stay with the account's owner: an impersonating admin could
+/// otherwise rename the account or leave themselves a password or passkey to come back with.
+#[cfg(feature = "ssr")]
+fn refuse_impersonated(session: &Session) -> Result<(), AppError> {
+    if session.impersonator.is_some() {
+        return Err(AppError::forbidden(
+            "This isn't available while impersonating a user",
+        ));
+    }
+    Ok(())
+}
+
+/// Confirm the signed-in user's password, so sensitive actions refused with
+/// `ReauthRequired` go through for a while.
+#[server]
+pub async fn reauthenticate(password: String, csrf_token: String) -> Result<(), AppError> {
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webui::csrf::verify_csrf;
+
+    let _timer = crate::perf::timer("reauthenticate");
+
+    verify_csrf(&csrf_token).await?;
+    let session = fetch_session()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    if let Err(e) = confirm_password(app_state.auth_store.as_ref(), &session, &password).await {
+        if e.field() == Some("password") {
+            record_audit(
+                Some(session.user_id),
+                AuditKind::LoginFailed,
+                "Re-authentication",
+            )
+            .await;
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Helper function backing `reauthenticate`, stamping the session once the password
+/// checks out.
+#[cfg(feature = "ssr")]
+async fn confirm_password<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    session: &Session,
+    password: &str,
+) -> Result<Session, AppError> {
+    let user = auth_store.get_user_by_id(&session.user_id).await?;
+    if !user.password_hash.verify(password) {
+        return Err(AppError::validation("password", "Incorrect password"));
+    }
+    Ok(auth_store.mark_reauthenticated(&session.id).await?)
+}
+
+/// Helper function to append an event to the audit log.
+///
+/// Failures are logged rather than returned, so auditing never breaks the audited action.
+#[cfg(feature = "ssr")]
+pub(crate) async fn record_audit(
+    actor: Option<crate::types::UserId>,
+    kind: crate::types::AuditKind,
+    detail: impl Into<String>,
+) {
+    record_audit_event(crate::types::AuditEvent::new(actor, kind, detail)).await;
+}
+
+/// Like `record_audit`, for events that also show up in the project's activity feed
+#[cfg(feature = "ssr")]
+pub(crate) async fn record_project_audit(
+    actor: Option<crate::types::UserId>,
+    project_id: crate::types::ProjectId,
+    kind: crate::types::AuditKind,
+    detail: impl Into<String>,
+) {
+    let event = crate::types::AuditEvent::new(actor, kind, detail).with_project(project_id);
+    record_audit_event(event).await;
+}
+
+#[cfg(feature = "ssr")]
+async fn record_audit_event(event: crate::types::AuditEvent) {
+    // whatever an admin does while impersonating someone is theirs
+    let mut event = event;
+    if let Ok(Some(signed_in)) = signed_in().await
+        && let Some(admin) = signed_in.session.impersonator
+    {
+        event = event.impersonated_by(admin, &signed_in.user.username.0);
+    }
+    save_audit_event(event).await;
+}
+
+/// Appends `event` to the audit log as is, unlike `record_audit` which attributes it to an
+/// impersonating admin
+#[cfg(feature = "ssr")]
+async fn save_audit_event(event: crate::types::AuditEvent) {
+    use crate::server::AppState;
+    use crate::storage::AuditStore;
+
+    let kind = event.kind;
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    if let Err(e) = app_state.audit_store.record(event).await {
+        tracing::warn!(kind = ?kind, "Failed to record audit event: {e}");
     }
 }
 
 /// Server function to fetch the current user's session from the cookie.
 ///
-/// Returns `Some(Session)` if a valid session exists, `None` otherwise.
+/// Returns `Some(Session)` if a valid session exists, `None` otherwise, and fails if the
+/// session belongs to a disabled user.
 /// This is a low-level function - consider using `get_current_user()` for user info.
 #[server]
 pub async fn fetch_session() -> Result<Option<Session>, AppError> {
+    use crate::config::LOCAL_CONF;
     use crate::server::AppState;
-    use crate::storage::AuthStore;
-    use crate::types::SessionId;
-    use axum_extra::extract::CookieJar;
+    use crate::storage::AuthError;
+    use crate::types::UserAgentHash;
+    use crate::webui::cookies::session_id_from_headers;
+    use crate::webui::middleware::SignedIn;
+    use axum::Extension;
+    use axum::http::HeaderMap;
     use leptos_axum::extract;
 
-    // extract the cookie jar from the request
-    let jar: CookieJar = extract().await?;
+    let _timer = crate::perf::timer("fetch_session");
+
+    // Prefer the session already resolved by the session middleware for this request
+    if let Ok(Extension(signed_in)) = extract::<Extension<Option<SignedIn>>>().await {
+        return Ok(signed_in.map(|signed_in| signed_in.session));
+    }
+
+    let headers: HeaderMap = extract().await?;
 
-    if let Some(cookie) = jar.get("session_id") {
+    if let Some(session_id) = session_id_from_headers(&headers) {
         let app_state: AppState = use_context().expect("Axum state in leptos context");
         let auth_store = app_state.auth_store.clone();
-        let session_id = SessionId(cookie.value().to_string());
 
-        match auth_store.fetch_session(&session_id).await {
-            Ok(session) => Ok(Some(session)),
-            Err(_) => Ok(None),
+        let session = match auth_store.fetch_session(&session_id).await {
+            Ok(session) => session,
+            Err(AuthError::AccountDisabled) => return Err(AuthError::AccountDisabled.into()),
+            Err(_) => return Ok(None),
+        };
+
+        if LOCAL_CONF.session.bind_user_agent
+            && !session.matches_user_agent(UserAgentHash::from_headers(&headers).as_ref())
+        {
+            tracing::warn!(
+                user_id = %session.user_id.0,
+                "Refused a session cookie sent from a different browser"
+            );
+            return Ok(None);
         }
+        Ok(Some(session))
     } else {
         Ok(None)
     }
 }
 
+/// Helper function to get the current session along with its user, `None` if signed out.
+///
+/// Reads what the session middleware resolved for this request, so callers needing the
+/// user don't look it up again; only routes without the middleware go to the store.
+#[cfg(feature = "ssr")]
+async fn signed_in() -> Result<Option<middleware::SignedIn>, AppError> {
+    use crate::server::AppState;
+    use crate::webui::middleware::SignedIn;
+    use axum::Extension;
+
+    if let Ok(Extension(signed_in)) = leptos_axum::extract::<Extension<Option<SignedIn>>>().await {
+        return Ok(signed_in);
+    }
+    let Some(session) = fetch_session().await? else {
+        return Ok(None);
+    };
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let user = app_state
+        .auth_store
+        .get_user_by_id(&session.user_id)
+        .await?;
+    Ok(Some(SignedIn { session, user }))
+}
+
 /// User information returned by `get_current_user`
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct CurrentUser {
     pub username: String,
     pub role: crate::types::Role,
     pub user_id: String,
+    /// When the session this user was resolved from runs out, if it was resolved from one
+    #[serde(default)]
+    pub session_expires_at: Option<time::OffsetDateTime>,
+    /// Username of the admin impersonating this user, see `impersonate`
+    #[serde(default)]
+    pub impersonated_by: Option<String>,
+    /// The user's unexpired sessions, for badges; only counted by `get_current_user` and
+    /// `get_account_overview`, not for every request
+    #[serde(default)]
+    pub active_session_count: Option<usize>,
+}
+
+impl From<&crate::types::User> for CurrentUser {
+    fn from(user: &crate::types::User) -> Self {
+        Self {
+            username: user.username.0.clone(),
+            role: user.role,
+            user_id: user.id.0.to_string(),
+            session_expires_at: None,
+            impersonated_by: None,
+            active_session_count: None,
+        }
+    }
+}
+
+impl CurrentUser {
+    /// The owner of `session`, along with when the session expires
+    pub fn with_session(user: &crate::types::User, session: &Session) -> Self {
+        Self {
+            session_expires_at: Some(session.expires_at),
+            ..Self::from(user)
+        }
+    }
+
+    /// Resolves the user behind `session`, and the admin impersonating them if any
+    #[cfg(feature = "ssr")]
+    pub async fn load<S: crate::storage::AuthStore + ?Sized>(
+        auth_store: &S,
+        session: &Session,
+    ) -> Result<Self, crate::storage::AuthError> {
+        let user = auth_store.get_user_by_id(&session.user_id).await?;
+        Self::for_user(auth_store, &user, session).await
+    }
+
+    /// Like `load`, for a `user` already loaded for `session`
+    #[cfg(feature = "ssr")]
+    pub async fn for_user<S: crate::storage::AuthStore + ?Sized>(
+        auth_store: &S,
+        user: &crate::types::User,
+        session: &Session,
+    ) -> Result<Self, crate::storage::AuthError> {
+        let mut current_user = Self::with_session(user, session);
+        if let Some(admin) = session.impersonator {
+            current_user.impersonated_by =
+                Some(auth_store.get_user_by_id(&admin).await?.username.0);
+        }
+        Ok(current_user)
+    }
+
+    /// Fills in `active_session_count`
+    #[cfg(feature = "ssr")]
+    pub async fn with_session_count<S: crate::storage::AuthStore + ?Sized>(
+        mut self,
+        auth_store: &S,
+    ) -> Result<Self, crate::storage::AuthError> {
+        let user_id = uuid::Uuid::parse_str(&self.user_id)
+            .map(UserId)
+            .map_err(|_| crate::storage::AuthError::NotFound)?;
+        self.active_session_count = Some(auth_store.count_user_sessions(&user_id).await?);
+        Ok(self)
+    }
 }
 
 /// Server function to get the current authenticated user's information.
@@ -171,48 +764,57 @@ pub struct CurrentUser {
 #[server]
 pub async fn get_current_user() -> Result<Option<CurrentUser>, AppError> {
     use crate::server::AppState;
-    use crate::storage::AuthStore;
+    use axum::Extension;
+    use leptos_axum::extract;
 
-    // Use the fetch_session function to get the session
-    let session = fetch_session().await?;
+    let _timer = crate::perf::timer("get_current_user");
 
-    if let Some(session) = session {
-        let app_state: AppState = use_context().expect("Axum state in leptos context");
-        let auth_store = app_state.auth_store.clone();
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let auth_store = app_state.auth_store.clone();
 
-        // Fetch the user details
-        match auth_store.get_user_by_id(&session.user_id).await {
-            Ok(user) => Ok(Some(CurrentUser {
-                username: user.username.0,
-                role: user.role,
-                user_id: user.id.0.to_string(),
-            })),
-            Err(_) => Ok(None),
-        }
-    } else {
-        Ok(None)
+    // Prefer the user already resolved by the session middleware for this request
+    let current_user =
+        if let Ok(Extension(current_user)) = extract::<Extension<Option<CurrentUser>>>().await {
+            current_user
+        } else if let Some(session) = fetch_session().await? {
+            // Fetch the user details
+            CurrentUser::load(auth_store.as_ref(), &session).await.ok()
+        } else {
+            None
+        };
+
+    match current_user {
+        Some(current_user) => Ok(Some(
+            current_user.with_session_count(auth_store.as_ref()).await?,
+        )),
+        None => Ok(None),
     }
 }
 
 #[server]
-pub async fn logout() -> Result<(), AppError> {
+pub async fn logout(csrf_token: String) -> Result<(), AppError> {
     use crate::server::AppState;
-    use crate::storage::AuthStore;
-    use crate::types::SessionId;
-    use crate::webui::cookies::{SESSION_COOKIE_NAME, clear_session_cookie};
-    use axum_extra::extract::CookieJar;
+    use crate::types::AuditKind;
+    use crate::webui::cookies::{clear_session_cookie, session_id_from_headers};
+    use crate::webui::csrf::verify_csrf;
+    use axum::http::HeaderMap;
     use leptos_axum::ResponseOptions;
     use leptos_axum::extract;
 
-    // extract the cookie jar from the request
-    let jar: CookieJar = extract().await?;
+    let _timer = crate::perf::timer("logout");
+
+    verify_csrf(&csrf_token).await?;
 
-    if let Some(cookie) = jar.get(SESSION_COOKIE_NAME) {
+    let headers: HeaderMap = extract().await?;
+
+    if let Some(session_id) = session_id_from_headers(&headers) {
         let app_state: AppState = use_context().expect("Axum state in leptos context");
         let auth_store = app_state.auth_store.clone();
-        let session_id = SessionId(cookie.value().to_string());
 
         // Revoke the session in the store
+        if let Ok(session) = auth_store.fetch_session(&session_id).await {
+            record_audit(Some(session.user_id), AuditKind::Logout, "").await;
+        }
         let _ = auth_store.revoke_session(&session_id).await;
     }
 
@@ -223,156 +825,3539 @@ pub async fn logout() -> Result<(), AppError> {
     Ok(())
 }
 
-// ==================== Project Server Functions ====================
-
-/// Create a new project for the current authenticated user.
-///
-/// Returns the created project summary on success.
+/// Pushes the current session's expiry a full session length out, for the "stay signed
+/// in" button of the expiry warning. Returns the new expiry.
 #[server]
-pub async fn create_project(
-    name: String,
-    description: Option<String>,
-) -> Result<ProjectSummary, AppError> {
+pub async fn extend_my_session(csrf_token: String) -> Result<time::OffsetDateTime, AppError> {
     use crate::server::AppState;
-    use crate::storage::ProjectStore;
+    use crate::webui::csrf::verify_csrf;
 
-    // Get current user session first
+    let _timer = crate::perf::timer("extend_my_session");
+
+    verify_csrf(&csrf_token).await?;
     let session = fetch_session()
         .await?
-        .ok_or_else(|| AppError::new("Not authenticated"))?;
+        .ok_or_else(|| AppError::unauthenticated("Not signed in"))?;
 
     let app_state: AppState = use_context().expect("Axum state in leptos context");
-    let project_store = app_state.project_store.clone();
-
-    let project = project_store
-        .create_project(&session.user_id, name, description)
-        .await?;
-
-    Ok(ProjectSummary::from(project))
+    let session = app_state.auth_store.extend_session(&session.id).await?;
+    Ok(session.expires_at)
 }
 
-/// Get all projects owned by the current authenticated user.
-///
-/// Returns a list of project summaries sorted by creation date (newest first).
+/// The value stored under `key` on the current session, see `set_my_session_data`
 #[server]
-pub async fn get_my_projects() -> Result<Vec<ProjectSummary>, AppError> {
+pub async fn get_my_session_data(key: String) -> Result<Option<String>, AppError> {
     use crate::server::AppState;
-    use crate::storage::ProjectStore;
 
-    // Get current user session
+    let _timer = crate::perf::timer("get_my_session_data");
+
     let session = fetch_session()
         .await?
-        .ok_or_else(|| AppError::new("Not authenticated"))?;
+        .ok_or_else(|| AppError::unauthenticated("Not signed in"))?;
 
     let app_state: AppState = use_context().expect("Axum state in leptos context");
-    let project_store = app_state.project_store.clone();
-
-    let projects = project_store.get_user_projects(&session.user_id).await?;
-    Ok(projects)
+    Ok(app_state
+        .auth_store
+        .get_session_data(&session.id, &key)
+        .await?)
 }
 
-/// Get a specific project by ID.
+/// Stores a small value under `key` on the current session, e.g. the project being worked
+/// on, or removes it when `value` is `None`. It lasts as long as the session does.
 ///
-/// Returns the full project if the current user owns it.
+/// A session holds at most `Session::MAX_DATA_ENTRIES` entries and
+/// `Session::MAX_DATA_BYTES` bytes of keys and values.
 #[server]
-pub async fn get_project(project_id: String) -> Result<Project, AppError> {
+pub async fn set_my_session_data(
+    key: String,
+    value: Option<String>,
+    csrf_token: String,
+) -> Result<(), AppError> {
     use crate::server::AppState;
-    use crate::storage::ProjectStore;
-    use crate::types::ProjectId;
-    use uuid::Uuid;
+    use crate::webui::csrf::verify_csrf;
 
-    // Get current user session
+    let _timer = crate::perf::timer("set_my_session_data");
+
+    verify_csrf(&csrf_token).await?;
+    if key.trim().is_empty() {
+        return Err(AppError::validation("key", "Key cannot be empty"));
+    }
     let session = fetch_session()
         .await?
-        .ok_or_else(|| AppError::new("Not authenticated"))?;
+        .ok_or_else(|| AppError::unauthenticated("Not signed in"))?;
 
     let app_state: AppState = use_context().expect("Axum state in leptos context");
-    let project_store = app_state.project_store.clone();
-
-    let project_id =
-        ProjectId(Uuid::parse_str(&project_id).map_err(|_| AppError::new("Invalid project ID"))?);
-
-    let project = project_store.get_project(&project_id).await?;
-
-    // Verify ownership
-    if project.owner_id != session.user_id {
-        return Err(AppError::new(
-            "You don't have permission to access this project",
-        ));
-    }
-
-    Ok(project)
+    app_state
+        .auth_store
+        .set_session_data(&session.id, &key, value)
+        .await?;
+    Ok(())
 }
 
-/// Update a project's name and/or description.
-///
-/// Only the project owner can update it.
+/// Ends every session of the signed-in user, this one included, for when they suspect
+/// someone else has access. Returns how many sessions were ended.
 #[server]
-pub async fn update_project(
-    project_id: String,
-    name: Option<String>,
-    description: Option<Option<String>>,
-) -> Result<Project, AppError> {
+pub async fn logout_all(csrf_token: String) -> Result<usize, AppError> {
     use crate::server::AppState;
-    use crate::storage::ProjectStore;
-    use crate::types::ProjectId;
-    use uuid::Uuid;
+    use crate::types::AuditKind;
+    use crate::webui::cookies::{clear_session_cookie, session_id_from_headers};
+    use crate::webui::csrf::verify_csrf;
+    use axum::http::HeaderMap;
+    use leptos_axum::ResponseOptions;
+    use leptos_axum::extract;
 
-    // Get current user session
-    let session = fetch_session()
-        .await?
-        .ok_or_else(|| AppError::new("Not authenticated"))?;
+    let _timer = crate::perf::timer("logout_all");
 
-    let app_state: AppState = use_context().expect("Axum state in leptos context");
-    let project_store = app_state.project_store.clone();
+    verify_csrf(&csrf_token).await?;
 
-    let project_id =
-        ProjectId(Uuid::parse_str(&project_id).map_err(|_| AppError::new("Invalid project ID"))?);
+    let headers: HeaderMap = extract().await?;
+    let Some(session_id) = session_id_from_headers(&headers) else {
+        return Err(AppError::unauthenticated("Not signed in"));
+    };
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
 
-    // Verify ownership before updating
-    let existing = project_store.get_project(&project_id).await?;
-    if existing.owner_id != session.user_id {
-        return Err(AppError::new(
-            "You don't have permission to update this project",
-        ));
-    }
+    let (user_id, revoked) = end_all_sessions(app_state.auth_store.as_ref(), &session_id).await?;
+    record_audit(
+        Some(user_id),
+        AuditKind::Logout,
+        format!("All devices ({revoked} sessions)"),
+    )
+    .await;
 
-    let updated = project_store
-        .update_project(&project_id, name, description)
-        .await?;
+    let response = expect_context::<ResponseOptions>();
+    clear_session_cookie(&response);
 
-    Ok(updated)
+    Ok(revoked)
 }
 
-/// Delete a project by ID.
+/// Revokes every session of the user owning `current`
+#[cfg(feature = "ssr")]
+async fn end_all_sessions<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    current: &crate::types::SessionId,
+) -> Result<(UserId, usize), AppError> {
+    let session = auth_store.fetch_session(current).await?;
+    let revoked = auth_store.revoke_all_sessions(&session.user_id).await?;
+    tracing::info!(user_id = %session.user_id.0, revoked, "Signed out of all devices");
+    Ok((session.user_id, revoked))
+}
+
+/// Returns the login before the current one, once: only the first call after signing in
+/// sees it, as the cookie carrying it is cleared.
+#[server]
+pub async fn take_previous_login() -> Result<Option<PreviousLogin>, AppError> {
+    use crate::webui::cookies::{
+        PREVIOUS_LOGIN_COOKIE_NAME, clear_previous_login_cookie, parse_previous_login,
+    };
+    use axum_extra::extract::CookieJar;
+    use leptos_axum::ResponseOptions;
+    use leptos_axum::extract;
+
+    let jar: CookieJar = extract().await?;
+    let Some(cookie) = jar.get(PREVIOUS_LOGIN_COOKIE_NAME) else {
+        return Ok(None);
+    };
+
+    let response = expect_context::<ResponseOptions>();
+    clear_previous_login_cookie(&response);
+    Ok(parse_previous_login(cookie.value()))
+}
+
+/// Returns the notice of a sign-in from elsewhere left on this session, once, see
+/// `[security] notify_new_login`
+#[server]
+pub async fn take_new_login_notice() -> Result<Option<NewLoginNotice>, AppError> {
+    use crate::login_notice::take_notice;
+    use crate::server::AppState;
+
+    let _timer = crate::perf::timer("take_new_login_notice");
+
+    let Some(session) = fetch_session().await? else {
+        return Ok(None);
+    };
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    Ok(take_notice(app_state.auth_store.as_ref(), &session.id).await?)
+}
+
+/// Server function returning the `[branding]` settings.
 ///
-/// Only the project owner can delete it.
+/// Available without authentication.
+#[server]
+pub async fn get_branding() -> Result<Branding, AppError> {
+    use crate::config::LOCAL_CONF;
+
+    Ok(LOCAL_CONF.branding.clone())
+}
+
+/// Server function returning the running server's version and build metadata.
+///
+/// Available without authentication.
+#[server]
+pub async fn get_build_info() -> Result<BuildInfo, AppError> {
+    Ok(BuildInfo::current())
+}
+
+/// Server function returning the limits that apply to the signed-in user, for the dashboard
+/// to show before a creation would be refused.
+#[server]
+pub async fn get_instance_limits() -> Result<InstanceLimits, AppError> {
+    let user = signed_in()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?
+        .user;
+    Ok(instance_limits(user.role))
+}
+
+/// Server function to check whether a username can still be registered.
+///
+/// Returns `Ok(true)` if the name is valid and free, `Ok(false)` if it's taken.
+/// Only available while registration is open, so it can't be used to probe for accounts.
 #[server]
-pub async fn delete_project(project_id: String) -> Result<(), AppError> {
+pub async fn username_available(username: String) -> Result<bool, AppError> {
+    use crate::config::LOCAL_CONF;
     use crate::server::AppState;
-    use crate::storage::ProjectStore;
-    use crate::types::ProjectId;
-    use uuid::Uuid;
 
-    // Get current user session
+    if !LOCAL_CONF.registration.allow_registration {
+        return Err(AppError::forbidden("Registration is disabled"));
+    }
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    check_username_available(app_state.auth_store.as_ref(), &username).await
+}
+
+/// Change the signed-in user's username.
+///
+/// Returns the new username.
+#[server]
+pub async fn change_username(new_username: String, csrf_token: String) -> Result<String, AppError> {
+    use crate::config::LOCAL_CONF;
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webui::csrf::verify_csrf;
+
+    verify_csrf(&csrf_token).await?;
+    let session = require_recent_auth(LOCAL_CONF.security.reauth_max_age()).await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let auth_store = app_state.auth_store.as_ref();
+    let old = auth_store.get_user_by_id(&session.user_id).await?;
+    let user = rename_user(auth_store, &session.user_id, &new_username).await?;
+
+    if user.username != old.username {
+        record_audit(
+            Some(user.id),
+            AuditKind::UsernameChanged,
+            format!("{} -> {}", old.username.0, user.username.0),
+        )
+        .await;
+    }
+    Ok(user.username.0)
+}
+
+/// Helper function backing `change_username`, reporting problems with the new name on the
+/// `new_username` field.
+#[cfg(feature = "ssr")]
+async fn rename_user<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    id: &UserId,
+    new_username: &str,
+) -> Result<crate::types::User, AppError> {
+    use crate::storage::AuthError;
+    use crate::types::Username;
+
+    let username = Username::parse(new_username)
+        .map_err(|e| AppError::validation("new_username", e.to_string()))?;
+
+    match auth_store.rename_user(id, &username).await {
+        Err(AuthError::UserExists) => Err(AppError::validation(
+            "new_username",
+            "That username is already taken",
+        )),
+        result => Ok(result?),
+    }
+}
+
+/// Helper function backing `username_available`, validating the name before lookup.
+#[cfg(feature = "ssr")]
+async fn check_username_available<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    username: &str,
+) -> Result<bool, AppError> {
+    use crate::storage::AuthError;
+    use crate::types::Username;
+
+    let username = Username::parse(username)?;
+
+    match auth_store.get_user_by_username(&username).await {
+        Ok(_) => Ok(false),
+        Err(AuthError::NotFound) => Ok(true),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// ==================== Invite Server Functions ====================
+
+/// Mint a new registration invite code (admin only).
+///
+/// The code can be redeemed `max_uses` times, optionally expiring after `expires_in_hours`.
+#[server]
+pub async fn create_invite_code(
+    role: Role,
+    max_uses: u32,
+    expires_in_hours: Option<i64>,
+    csrf_token: String,
+) -> Result<InviteCode, AppError> {
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webui::csrf::verify_csrf;
+    use time::{Duration, OffsetDateTime};
+
+    verify_csrf(&csrf_token).await?;
+    let admin = require_admin().await?;
+
+    if max_uses == 0 {
+        return Err(AppError::new("An invite code must allow at least one use"));
+    }
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let expires_at =
+        expires_in_hours.map(|hours| OffsetDateTime::now_utc() + Duration::hours(hours));
+    let invite = app_state
+        .auth_store
+        .create_invite(role, max_uses, expires_at)
+        .await?;
+
+    record_audit(
+        Some(admin.id),
+        AuditKind::InviteCreated,
+        format!("{role:?} invite, {max_uses} use(s)"),
+    )
+    .await;
+
+    Ok(invite)
+}
+
+/// List all invite codes, newest first (admin only).
+#[server]
+pub async fn list_invite_codes() -> Result<Vec<InviteCode>, AppError> {
+    use crate::server::AppState;
+
+    require_admin().await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    Ok(app_state.auth_store.list_invites().await?)
+}
+
+/// Revoke an invite code so it can no longer be redeemed (admin only).
+#[server]
+pub async fn revoke_invite_code(code: String, csrf_token: String) -> Result<(), AppError> {
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webui::csrf::verify_csrf;
+
+    verify_csrf(&csrf_token).await?;
+    let admin = require_admin().await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    app_state.auth_store.revoke_invite(&code).await?;
+
+    record_audit(Some(admin.id), AuditKind::InviteRevoked, code).await;
+    Ok(())
+}
+
+// ==================== User Admin Server Functions ====================
+
+/// Set a new password for another user and sign them out everywhere (admin only).
+///
+/// The password must satisfy the configured `[credentials]` length limits.
+#[server]
+pub async fn admin_reset_password(
+    target: UserId,
+    new_password: String,
+    csrf_token: String,
+) -> Result<(), AppError> {
+    use crate::config::LOCAL_CONF;
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webui::csrf::verify_csrf;
+
+    verify_csrf(&csrf_token).await?;
+    let admin = require_admin().await?;
+    require_recent_auth(LOCAL_CONF.security.reauth_max_age()).await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let user = reset_user_password(
+        app_state.auth_store.as_ref(),
+        &LOCAL_CONF.credentials,
+        &target,
+        &new_password,
+    )
+    .await?;
+
+    record_audit(
+        Some(admin.id),
+        AuditKind::PasswordReset,
+        format!("Reset password of {}", user.username.0),
+    )
+    .await;
+    Ok(())
+}
+
+/// Checks the password in the `field` input against the configured `[credentials]` length
+/// limits, so an oversized one is refused before it reaches argon2
+#[cfg(feature = "ssr")]
+fn check_password_len(
+    limits: &crate::config::CredentialLimits,
+    field: &str,
+    password: &str,
+) -> Result<(), AppError> {
+    let len = password.chars().count();
+    if len < limits.password_min_len {
+        return Err(AppError::validation(
+            field,
+            format!(
+                "Password must be at least {} characters",
+                limits.password_min_len
+            ),
+        ));
+    }
+    if len > limits.password_max_len {
+        return Err(AppError::validation(
+            field,
+            format!(
+                "Password can be at most {} characters",
+                limits.password_max_len
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Helper function backing `admin_reset_password`.
+///
+/// Returns the target user; all of their sessions are revoked.
+#[cfg(feature = "ssr")]
+async fn reset_user_password<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    limits: &crate::config::CredentialLimits,
+    target: &UserId,
+    new_password: &str,
+) -> Result<crate::types::User, AppError> {
+    use crate::types::PasswordHash;
+
+    check_password_len(limits, "new_password", new_password)?;
+
+    let user = auth_store.get_user_by_id(target).await?;
+    auth_store
+        .check_password_reuse(&user.id, new_password)
+        .await?;
+    let password_hash = PasswordHash::try_from(new_password)
+        .map_err(|_| AppError::new("Failed to hash the new password"))?;
+    auth_store
+        .set_password_hash(&user.id, password_hash)
+        .await?;
+    let revoked = auth_store.revoke_all_sessions(&user.id).await?;
+    tracing::info!(user_id = %user.id.0, revoked, "Password reset by an admin");
+
+    Ok(user)
+}
+
+/// Change the signed-in user's password, given their current one.
+///
+/// Every other session of the account is ended; this one stays signed in. Returns how
+/// many sessions were ended.
+#[server]
+pub async fn change_password(
+    current_password: String,
+    new_password: String,
+    csrf_token: String,
+) -> Result<usize, AppError> {
+    use crate::config::LOCAL_CONF;
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webui::csrf::verify_csrf;
+
+    let _timer = crate::perf::timer("change_password");
+
+    verify_csrf(&csrf_token).await?;
     let session = fetch_session()
         .await?
-        .ok_or_else(|| AppError::new("Not authenticated"))?;
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
 
     let app_state: AppState = use_context().expect("Axum state in leptos context");
-    let project_store = app_state.project_store.clone();
+    let revoked = change_own_password(
+        app_state.auth_store.as_ref(),
+        &LOCAL_CONF.credentials,
+        &session,
+        &current_password,
+        &new_password,
+    )
+    .await?;
 
-    let project_id =
-        ProjectId(Uuid::parse_str(&project_id).map_err(|_| AppError::new("Invalid project ID"))?);
+    record_audit(
+        Some(session.user_id),
+        AuditKind::PasswordChanged,
+        format!("Ended {revoked} other session(s)"),
+    )
+    .await;
+    Ok(revoked)
+}
 
-    // Verify ownership before deleting
-    let project = project_store.get_project(&project_id).await?;
-    if project.owner_id != session.user_id {
-        return Err(AppError::new(
-            "You don't have permission to delete this project",
+/// Helper function backing `change_password`.
+///
+/// A wrong `current_password` is a validation error on that field, so the form can show
+/// it next to the input.
+#[cfg(feature = "ssr")]
+async fn change_own_password<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    limits: &crate::config::CredentialLimits,
+    session: &Session,
+    current_password: &str,
+    new_password: &str,
+) -> Result<usize, AppError> {
+    use crate::types::PasswordHash;
+
+    refuse_impersonated(session)?;
+    let user = auth_store.get_user_by_id(&session.user_id).await?;
+    if !user.password_hash.verify(current_password) {
+        return Err(AppError::validation(
+            "current_password",
+            "Current password is incorrect",
         ));
     }
+    check_password_len(limits, "new_password", new_password)?;
+    auth_store
+        .check_password_reuse(&user.id, new_password)
+        .await?;
 
-    project_store.delete_project(&project_id).await?;
+    let password_hash = PasswordHash::try_from(new_password)
+        .map_err(|_| AppError::new("Failed to hash the new password"))?;
+    auth_store
+        .set_password_hash(&user.id, password_hash)
+        .await?;
+
+    let mut revoked = 0;
+    for other in auth_store.list_user_sessions(&user.id).await? {
+        if other.id != session.id {
+            auth_store.revoke_session(&other.id).await?;
+            revoked += 1;
+        }
+    }
+    tracing::info!(user_id = %user.id.0, revoked, "Password changed");
+
+    Ok(revoked)
+}
+
+/// Create several accounts at once (admin only), e.g. to onboard a team.
+///
+/// Every row is checked before any account is created; a row that fails (say, a taken
+/// username) is reported in its result without stopping the others. Each password is
+/// hashed on the blocking pool, so large batches take a while.
+#[server]
+pub async fn bulk_create_users(
+    users: Vec<NewUser>,
+    csrf_token: String,
+) -> Result<Vec<BulkUserResult>, AppError> {
+    use crate::config::LOCAL_CONF;
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webui::csrf::verify_csrf;
+
+    let _timer = crate::perf::timer("bulk_create_users");
+
+    verify_csrf(&csrf_token).await?;
+    let admin = require_admin().await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let results = create_users(
+        app_state.auth_store.as_ref(),
+        &LOCAL_CONF.credentials,
+        users,
+    )
+    .await;
+
+    for result in results.iter().filter(|result| result.outcome.is_ok()) {
+        record_audit(
+            Some(admin.id),
+            AuditKind::UserRegistered,
+            format!("Created {} in bulk", result.username),
+        )
+        .await;
+    }
+    Ok(results)
+}
+
+/// Helper function backing `bulk_create_users` and its REST counterpart.
+///
+/// Creates the valid rows as active accounts and returns one result per row, in order.
+#[cfg(feature = "ssr")]
+pub(crate) async fn create_users<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    limits: &crate::config::CredentialLimits,
+    users: Vec<NewUser>,
+) -> Vec<BulkUserResult> {
+    use crate::storage::AuthError;
+    use crate::types::{PasswordHash, Username};
+    use std::collections::HashSet;
+
+    // validate the whole batch up front, including names repeated within it
+    let mut seen = HashSet::new();
+    let checked: Vec<_> = users
+        .into_iter()
+        .map(|user| {
+            let checked = Username::parse(&user.username)
+                .map_err(|e| e.to_string())
+                .and_then(|username| {
+                    let len = user.password.chars().count();
+                    if len < limits.password_min_len {
+                        Err(format!(
+                            "Password must be at least {} characters",
+                            limits.password_min_len
+                        ))
+                    } else if len > limits.password_max_len {
+                        Err(format!(
+                            "Password can be at most {} characters",
+                            limits.password_max_len
+                        ))
+                    } else if !seen.insert(username.0.clone()) {
+                        Err("Username appears more than once in this batch".to_string())
+                    } else {
+                        Ok(username)
+                    }
+                });
+            (user, checked)
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(checked.len());
+    for (user, checked) in checked {
+        let outcome = match checked {
+            Ok(username) => {
+                let password = user.password;
+                let hashed = tokio::task::spawn_blocking(move || {
+                    PasswordHash::try_from(password.as_str()).map_err(|e| e.to_string())
+                })
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|hashed| hashed);
+
+                match hashed {
+                    Ok(pass_hash) => match auth_store
+                        .create_user(&username, pass_hash, user.role, UserStatus::Active)
+                        .await
+                    {
+                        Ok(created) => Ok(created.id),
+                        Err(AuthError::UserExists) => {
+                            Err("That username is already taken".to_string())
+                        }
+                        Err(e) => Err(e.to_string()),
+                    },
+                    Err(e) => {
+                        tracing::error!(error = %e, "Failed to hash a bulk-created password");
+                        Err("Failed to hash the password".to_string())
+                    }
+                }
+            }
+            Err(reason) => Err(reason),
+        };
+        results.push(BulkUserResult {
+            username: user.username,
+            outcome,
+        });
+    }
+    results
+}
+
+/// All users, for the Manage Users panel (admin only).
+#[server]
+pub async fn list_users() -> Result<Vec<UserSummary>, AppError> {
+    use crate::server::AppState;
+
+    require_admin().await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let users = app_state.auth_store.list_users().await?;
+    let now = time::OffsetDateTime::now_utc();
+    Ok(users
+        .iter()
+        .map(|user| UserSummary::new(user, now))
+        .collect())
+}
+
+/// Disable or re-enable a user (admin only).
+///
+/// Disabled users keep their projects and audit history, but can't sign in and are signed
+/// out everywhere.
+#[server]
+pub async fn set_user_status(
+    target: UserId,
+    status: UserStatus,
+    csrf_token: String,
+) -> Result<UserSummary, AppError> {
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webui::csrf::verify_csrf;
+
+    verify_csrf(&csrf_token).await?;
+    let admin = require_admin().await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let user =
+        change_user_status(app_state.auth_store.as_ref(), &admin.id, &target, status).await?;
+
+    let kind = match status {
+        UserStatus::Active => AuditKind::UserEnabled,
+        UserStatus::Disabled => AuditKind::UserDisabled,
+    };
+    record_audit(Some(admin.id), kind, user.username.0.clone()).await;
+    Ok(UserSummary::new(&user, time::OffsetDateTime::now_utc()))
+}
+
+/// Unlock a user locked after too many failed logins (admin only).
+#[server]
+pub async fn admin_unlock_user(
+    target: UserId,
+    csrf_token: String,
+) -> Result<UserSummary, AppError> {
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webui::csrf::verify_csrf;
+
+    verify_csrf(&csrf_token).await?;
+    let admin = require_admin().await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let user = app_state.auth_store.clear_lockout(&target).await?;
+
+    record_audit(
+        Some(admin.id),
+        AuditKind::UserUnlocked,
+        user.username.0.clone(),
+    )
+    .await;
+    Ok(UserSummary::new(&user, time::OffsetDateTime::now_utc()))
+}
+
+/// Helper function backing `set_user_status`; admins can't disable themselves.
+#[cfg(feature = "ssr")]
+async fn change_user_status<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    admin: &UserId,
+    target: &UserId,
+    status: UserStatus,
+) -> Result<crate::types::User, AppError> {
+    if admin == target && status == UserStatus::Disabled {
+        return Err(AppError::forbidden("You can't disable your own account"));
+    }
+    Ok(auth_store.set_status(target, status).await?)
+}
+
+/// Sign in as `target` to see Bento the way they do (admin only). Admins can't be
+/// impersonated.
+///
+/// The admin's session is swapped for one acting as `target` until `end_impersonation`,
+/// and everything done meanwhile is audited under the admin.
+#[server]
+pub async fn impersonate(target: UserId, csrf_token: String) -> Result<(), AppError> {
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webui::cookies::set_session_cookie;
+    use crate::webui::csrf::verify_csrf;
+    use leptos_axum::ResponseOptions;
+
+    let _timer = crate::perf::timer("impersonate");
+
+    verify_csrf(&csrf_token).await?;
+    let admin = require_admin().await?;
+    let admin_session = fetch_session()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let (user, session) = start_impersonation(
+        app_state.auth_store.as_ref(),
+        &admin.id,
+        &admin_session,
+        &target,
+    )
+    .await?;
+
+    record_audit(
+        Some(admin.id),
+        AuditKind::ImpersonationStarted,
+        user.username.0.clone(),
+    )
+    .await;
+    let response = expect_context::<ResponseOptions>();
+    set_session_cookie(&response, session.id.as_str());
+    Ok(())
+}
+
+/// Stop impersonating, signing the admin back in as themselves.
+#[server]
+pub async fn end_impersonation(csrf_token: String) -> Result<(), AppError> {
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webui::cookies::set_session_cookie;
+    use crate::webui::csrf::verify_csrf;
+    use crate::webui::middleware::SignedIn;
+    use leptos_axum::ResponseOptions;
+
+    let _timer = crate::perf::timer("end_impersonation");
+
+    verify_csrf(&csrf_token).await?;
+    let SignedIn { session, user } = signed_in()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let admin_session = stop_impersonation(app_state.auth_store.as_ref(), &session).await?;
+
+    // saved as is: this request still carries the impersonation session, which would
+    // otherwise rewrite it as one of its actions
+    save_audit_event(AuditEvent::new(
+        Some(admin_session.user_id),
+        AuditKind::ImpersonationEnded,
+        user.username.0,
+    ))
+    .await;
+    let response = expect_context::<ResponseOptions>();
+    set_session_cookie(&response, admin_session.id.as_str());
     Ok(())
 }
+
+/// Helper function backing `impersonate`: swaps `admin_session` for a session acting as
+/// `target`, returning the impersonated user and the new session.
+#[cfg(feature = "ssr")]
+async fn start_impersonation<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    admin: &UserId,
+    admin_session: &Session,
+    target: &UserId,
+) -> Result<(crate::types::User, Session), AppError> {
+    let user = auth_store.get_user_by_id(target).await?;
+    if user.role == Role::Admin {
+        return Err(AppError::forbidden("Admins can't be impersonated"));
+    }
+    let session = auth_store
+        .issue_impersonation_session(
+            admin,
+            target,
+            admin_session.ip.clone(),
+            admin_session.user_agent.clone(),
+        )
+        .await?;
+    auth_store.revoke_session(&admin_session.id).await?;
+    tracing::info!(admin_id = %admin.0, user_id = %target.0, "Impersonation started");
+    Ok((user, session))
+}
+
+/// Helper function backing `end_impersonation`: swaps an impersonation session for a new
+/// session of the admin behind it, returning that.
+#[cfg(feature = "ssr")]
+async fn stop_impersonation<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    session: &Session,
+) -> Result<Session, AppError> {
+    use crate::types::AppErrorKind;
+
+    let admin = session.impersonator.ok_or_else(|| {
+        AppError::new("You aren't impersonating anyone").with_kind(AppErrorKind::Conflict)
+    })?;
+    // issued first, so the admin isn't left signed out of both if it's refused
+    let admin_session = auth_store
+        .issue_session(&admin, session.ip.clone(), session.user_agent.clone())
+        .await?;
+    auth_store.revoke_session(&session.id).await?;
+    tracing::info!(admin_id = %admin.0, user_id = %session.user_id.0, "Impersonation ended");
+    Ok(admin_session)
+}
+
+// ==================== Audit Server Functions ====================
+
+/// Upper bound on the number of audit events returned by one query
+#[cfg(feature = "ssr")]
+const MAX_AUDIT_EVENTS: usize = 500;
+
+/// Search the audit log (admin only).
+///
+/// Returns matching events newest first, capped at 500 per query.
+#[server(input = leptos::server_fn::codec::Json)]
+pub async fn list_audit_events(query: AuditQuery) -> Result<Vec<AuditEvent>, AppError> {
+    use crate::server::AppState;
+    use crate::storage::AuditStore;
+
+    require_admin().await?;
+
+    let mut query = query;
+    if query.limit == 0 || query.limit > MAX_AUDIT_EVENTS {
+        query.limit = MAX_AUDIT_EVENTS;
+    }
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    Ok(app_state.audit_store.query(query).await?)
+}
+
+// ==================== Session Server Functions ====================
+
+/// Look up a session's owner, timestamps and IP for debugging (admin only).
+///
+/// Unknown or expired sessions return a not found error; expired ones are cleaned up.
+#[server]
+pub async fn introspect_session(session_id: String) -> Result<SessionInfo, AppError> {
+    use crate::server::AppState;
+    use crate::types::SessionId;
+
+    require_admin().await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    load_session_info(app_state.auth_store.as_ref(), &SessionId(session_id)).await
+}
+
+/// Helper function backing `introspect_session`.
+#[cfg(feature = "ssr")]
+async fn load_session_info<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    session_id: &crate::types::SessionId,
+) -> Result<SessionInfo, AppError> {
+    use crate::storage::AuthError;
+
+    // fetch_session removes the session if it has expired
+    let session = match auth_store.fetch_session(session_id).await {
+        Ok(session) => session,
+        Err(AuthError::InvalidSession) => return Err(AppError::not_found("Session not found")),
+        Err(e) => return Err(e.into()),
+    };
+    let user = auth_store.get_user_by_id(&session.user_id).await?;
+
+    Ok(SessionInfo {
+        user_id: user.id,
+        username: user.username.0,
+        ip: session.ip.0,
+        created_at: session.created_at,
+        expires_at: session.expires_at,
+        last_seen_at: session.last_seen_at,
+    })
+}
+
+/// The current user's unexpired sessions, most recently seen first.
+#[server]
+pub async fn get_my_sessions() -> Result<Vec<SessionInfo>, AppError> {
+    use crate::server::AppState;
+
+    let _timer = crate::perf::timer("get_my_sessions");
+
+    let user = signed_in()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?
+        .user;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    list_session_infos(app_state.auth_store.as_ref(), &user).await
+}
+
+/// Helper function backing `get_my_sessions`.
+#[cfg(feature = "ssr")]
+async fn list_session_infos<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    user: &crate::types::User,
+) -> Result<Vec<SessionInfo>, AppError> {
+    let sessions = auth_store.list_user_sessions(&user.id).await?;
+    Ok(sessions
+        .into_iter()
+        .map(|session| SessionInfo {
+            user_id: user.id,
+            username: user.username.0.clone(),
+            ip: session.ip.0,
+            created_at: session.created_at,
+            expires_at: session.expires_at,
+            last_seen_at: session.last_seen_at,
+        })
+        .collect())
+}
+
+/// Everything the account page shows, returned by `get_account_overview`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AccountOverview {
+    pub user: CurrentUser,
+    pub sessions: Vec<SessionInfo>,
+    pub project_count: usize,
+}
+
+/// The current user, their sessions and project count in one round-trip.
+///
+/// Combines `get_current_user`, `get_my_sessions` and `get_my_projects` behind a single
+/// session lookup; use those to refetch one part after a change.
+#[server]
+pub async fn get_account_overview() -> Result<AccountOverview, AppError> {
+    use crate::server::AppState;
+
+    let _timer = crate::perf::timer("get_account_overview");
+
+    let session = fetch_session()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    account_overview(
+        app_state.auth_store.as_ref(),
+        app_state.project_store.as_ref(),
+        &session.user_id,
+    )
+    .await
+}
+
+/// Helper function backing `get_account_overview`.
+#[cfg(feature = "ssr")]
+async fn account_overview<
+    A: crate::storage::AuthStore + ?Sized,
+    P: crate::storage::ProjectStore + ?Sized,
+>(
+    auth_store: &A,
+    project_store: &P,
+    user_id: &UserId,
+) -> Result<AccountOverview, AppError> {
+    let user = auth_store.get_user_by_id(user_id).await?;
+    let sessions = list_session_infos(auth_store, &user).await?;
+    let project_count = project_store.get_user_projects(user_id).await?.len();
+
+    Ok(AccountOverview {
+        user: CurrentUser {
+            active_session_count: Some(sessions.len()),
+            ..CurrentUser::from(&user)
+        },
+        sessions,
+        project_count,
+    })
+}
+
+/// Expired session cleanup counters since startup (admin only).
+#[server]
+pub async fn get_maintenance_stats() -> Result<MaintenanceReport, AppError> {
+    use crate::server::AppState;
+
+    require_admin().await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    Ok(app_state.maintenance.report())
+}
+
+/// The configuration Bento is running with, secrets redacted (admin only).
+///
+/// Same as `GET /api/v1/admin/config`.
+#[server]
+pub async fn get_effective_config() -> Result<serde_json::Value, AppError> {
+    require_admin().await?;
+    Ok(crate::config::LOCAL_CONF.redacted())
+}
+
+/// Latency percentiles of the timed server functions since startup (admin only).
+#[server]
+pub async fn get_perf_stats() -> Result<Vec<PerfReport>, AppError> {
+    use crate::server::AppState;
+
+    require_admin().await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    Ok(app_state.perf.report())
+}
+
+/// Size and page usage of the auth, session and project databases (admin only).
+#[server]
+pub async fn get_storage_stats() -> Result<Vec<DatabaseReport>, AppError> {
+    use crate::server::AppState;
+
+    require_admin().await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let mut reports = vec![app_state.auth_db.stats().await?];
+    // only when `[session] store = "separate"`
+    reports.extend(app_state.auth_db.session_stats().await?);
+    reports.push(app_state.project_db.stats().await?);
+    Ok(reports)
+}
+
+/// The client IPs holding the most active sessions, busiest first (admin only).
+#[server]
+pub async fn get_sessions_by_ip(limit: usize) -> Result<Vec<IpSessionCount>, AppError> {
+    use crate::server::AppState;
+
+    require_admin().await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let counts = app_state.auth_store.active_sessions_by_ip().await?;
+    Ok(top_session_ips(counts, limit))
+}
+
+/// Helper function backing `get_sessions_by_ip`: orders IPs by session count, descending,
+/// and keeps the first `limit`.
+#[cfg(feature = "ssr")]
+fn top_session_ips(
+    counts: std::collections::BTreeMap<std::net::IpAddr, usize>,
+    limit: usize,
+) -> Vec<IpSessionCount> {
+    let mut top: Vec<IpSessionCount> = counts
+        .into_iter()
+        .map(|(ip, sessions)| IpSessionCount { ip, sessions })
+        .collect();
+    // stable sort, so ties stay in address order
+    top.sort_by_key(|count| std::cmp::Reverse(count.sessions));
+    top.truncate(limit);
+    top
+}
+
+/// Sign out every user's sessions matching `criteria`, e.g. all issued before a breach was
+/// fixed or from an attacker's address (admin only). Returns how many sessions were ended.
+#[server(input = leptos::server_fn::codec::Json)]
+pub async fn admin_revoke_sessions(
+    criteria: SessionCriteria,
+    csrf_token: String,
+) -> Result<usize, AppError> {
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webui::csrf::verify_csrf;
+
+    let _timer = crate::perf::timer("admin_revoke_sessions");
+
+    verify_csrf(&csrf_token).await?;
+    let admin = require_admin().await?;
+    if criteria.is_empty() {
+        return Err(AppError::validation(
+            "criteria",
+            "Give a time, an address or both",
+        ));
+    }
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let revoked = app_state
+        .auth_store
+        .revoke_sessions_where(&criteria)
+        .await?;
+
+    let mut detail = vec![format!("{revoked} sessions")];
+    if let Some(before) = criteria.before {
+        detail.push(format!("issued before {before}"));
+    }
+    if let Some(ip) = criteria.from_ip {
+        detail.push(format!("from {ip}"));
+    }
+    record_audit(
+        Some(admin.id),
+        AuditKind::SessionsRevoked,
+        detail.join(", "),
+    )
+    .await;
+    Ok(revoked)
+}
+
+// ==================== Project Server Functions ====================
+
+/// Longest accepted project name, in grapheme clusters
+pub const PROJECT_NAME_MAX_LEN: usize = 64;
+/// Longest accepted project description, in grapheme clusters
+pub const PROJECT_DESCRIPTION_MAX_LEN: usize = 500;
+
+/// Checks project names and descriptions before `create_project`/`update_project` touch the
+/// store; `None` skips a field.
+///
+/// Surrounding whitespace doesn't count (the functions store trimmed values), and lengths
+/// are measured in grapheme clusters, so an emoji counts as one character however many
+/// bytes or code points it takes.
+pub fn validate_project_input(
+    name: Option<&str>,
+    description: Option<&str>,
+) -> Result<(), AppError> {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    if let Some(name) = name {
+        let len = name.trim().graphemes(true).count();
+        if len == 0 {
+            return Err(AppError::validation("name", "Project name can't be empty"));
+        }
+        if len > PROJECT_NAME_MAX_LEN {
+            return Err(AppError::validation(
+                "name",
+                format!("Project name can be at most {PROJECT_NAME_MAX_LEN} characters"),
+            ));
+        }
+    }
+
+    if let Some(description) = description
+        && description.trim().graphemes(true).count() > PROJECT_DESCRIPTION_MAX_LEN
+    {
+        return Err(AppError::validation(
+            "description",
+            format!("Description can be at most {PROJECT_DESCRIPTION_MAX_LEN} characters"),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Trims a validated description, dropping it entirely if nothing is left
+#[cfg(feature = "ssr")]
+fn normalize_description(description: String) -> Option<String> {
+    Some(description.trim().to_string()).filter(|description| !description.is_empty())
+}
+
+/// Create a new project for the current authenticated user.
+///
+/// Returns the created project summary on success. Sending an `idempotency_key` makes the
+/// call safe to retry: a repeat with the same key returns the project the first call
+/// created (see `[projects] idempotency_window_secs`).
+#[server]
+pub async fn create_project(
+    name: String,
+    description: Option<String>,
+    idempotency_key: Option<String>,
+    csrf_token: String,
+) -> Result<ProjectSummary, AppError> {
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webhooks::{ProjectEvent, ProjectEventKind};
+    use crate::webui::csrf::verify_csrf;
+    use crate::webui::middleware::SignedIn;
+
+    let _timer = crate::perf::timer("create_project");
+
+    verify_csrf(&csrf_token).await?;
+    validate_project_input(Some(&name), description.as_deref())?;
+    let name = name.trim().to_string();
+    let description = description.and_then(normalize_description);
+
+    // Get current user session first
+    let SignedIn { session, user } = signed_in()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let project_store = app_state.project_store.clone();
+
+    check_creation_cooldown(&app_state.creation_cooldown, &user.id, user.role)?;
+    check_project_limit(project_store.as_ref(), &user.id, instance_limits(user.role)).await?;
+
+    let (project, created) = project_store
+        .create_project(&session.user_id, name, description, idempotency_key)
+        .await?;
+    if !created {
+        // a retry of a request that already went through; it was announced back then
+        return Ok(ProjectSummary::from(project));
+    }
+    app_state.creation_cooldown.record(&user.id);
+
+    if let Some(webhooks) = &app_state.webhooks {
+        webhooks.notify(ProjectEvent::new(
+            ProjectEventKind::ProjectCreated,
+            &project,
+        ));
+    }
+    record_project_audit(
+        Some(session.user_id),
+        project.id,
+        AuditKind::ProjectCreated,
+        project.name.clone(),
+    )
+    .await;
+
+    Ok(ProjectSummary::from(project))
+}
+
+/// Refuses to create a project while `user_id` is still in their creation cooldown, saying
+/// how many seconds are left. Admins are exempt.
+#[cfg(feature = "ssr")]
+fn check_creation_cooldown(
+    cooldown: &crate::rate_limit::CreationCooldown,
+    user_id: &UserId,
+    role: Role,
+) -> Result<(), AppError> {
+    if role == Role::Admin {
+        return Ok(());
+    }
+    cooldown.check(user_id).map_err(|limited| {
+        let seconds = limited.retry_after.whole_seconds().max(1);
+        AppError::rate_limited(format!(
+            "Please wait {seconds} more second{} before creating another project",
+            if seconds == 1 { "" } else { "s" }
+        ))
+    })
+}
+
+/// The [InstanceLimits] of a user with `role`
+#[cfg(feature = "ssr")]
+fn instance_limits(role: Role) -> InstanceLimits {
+    InstanceLimits {
+        max_projects_per_user: crate::config::LOCAL_CONF.projects.max_projects_for(role),
+    }
+}
+
+/// Refuses to create a project once `owner` has as many as `limits` allow them
+#[cfg(feature = "ssr")]
+async fn check_project_limit<P: crate::storage::ProjectStore + ?Sized>(
+    project_store: &P,
+    owner: &UserId,
+    limits: InstanceLimits,
+) -> Result<(), AppError> {
+    if limits.max_projects_per_user.is_none() {
+        return Ok(());
+    }
+    let count = project_store
+        .get_user_projects_page(owner, None, 0)
+        .await?
+        .total;
+    match limits.project_limit_reached(count) {
+        Some(reached) => Err(AppError::forbidden(reached)),
+        None => Ok(()),
+    }
+}
+
+/// Get all projects owned by the current authenticated user.
+///
+/// Returns a list of project summaries sorted by creation date (newest first).
+#[server]
+pub async fn get_my_projects() -> Result<Vec<ProjectSummary>, AppError> {
+    use crate::server::AppState;
+
+    let _timer = crate::perf::timer("get_my_projects");
+
+    // Get current user session
+    let session = fetch_session()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let project_store = app_state.project_store.clone();
+
+    let projects = project_store.get_user_projects(&session.user_id).await?;
+    Ok(projects)
+}
+
+/// Largest page `get_my_projects_page` returns
+#[cfg(feature = "ssr")]
+const MAX_PROJECT_PAGE_SIZE: usize = 100;
+
+/// Get up to `limit` of the current user's projects, newest first, continuing after the
+/// project `after` (the previous page's `next_cursor`).
+///
+/// `limit` is capped at 100.
+#[server]
+pub async fn get_my_projects_page(
+    after: Option<String>,
+    limit: usize,
+) -> Result<ProjectPage, AppError> {
+    use crate::server::AppState;
+
+    let _timer = crate::perf::timer("get_my_projects_page");
+
+    let session = fetch_session()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    project_page(
+        app_state.project_store.as_ref(),
+        &session.user_id,
+        after.as_deref(),
+        limit,
+    )
+    .await
+}
+
+/// Helper function backing `get_my_projects_page`: parses the cursor and caps the limit.
+#[cfg(feature = "ssr")]
+async fn project_page<P: crate::storage::ProjectStore + ?Sized>(
+    project_store: &P,
+    owner_id: &crate::types::UserId,
+    after: Option<&str>,
+    limit: usize,
+) -> Result<ProjectPage, AppError> {
+    use crate::types::ProjectId;
+
+    let after = after
+        .map(|after| {
+            ProjectId::parse(after).map_err(|e| AppError::validation("after", e.to_string()))
+        })
+        .transpose()?;
+
+    Ok(project_store
+        .get_user_projects_page(owner_id, after, limit.min(MAX_PROJECT_PAGE_SIZE))
+        .await?)
+}
+
+/// Get up to `limit` of the current user's projects whose name contains `query`, ignoring
+/// case, newest first.
+///
+/// `limit` is capped at 100.
+#[server]
+pub async fn search_my_projects(
+    query: String,
+    limit: usize,
+) -> Result<Vec<ProjectSummary>, AppError> {
+    use crate::server::AppState;
+
+    let _timer = crate::perf::timer("search_my_projects");
+
+    let session = fetch_session()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let projects = app_state
+        .project_store
+        .search_projects(
+            &session.user_id,
+            query.trim(),
+            limit.min(MAX_PROJECT_PAGE_SIZE),
+        )
+        .await?;
+
+    Ok(projects)
+}
+
+/// Get a specific project by ID.
+///
+/// Returns the full project if the current user owns it, or if it is unlisted or public
+/// (no sign-in needed).
+#[server]
+pub async fn get_project(project_id: String) -> Result<Project, AppError> {
+    use crate::server::AppState;
+    use crate::types::ProjectId;
+
+    let _timer = crate::perf::timer("get_project");
+
+    // Anonymous visitors may still read public projects
+    let viewer = fetch_session().await?.map(|session| session.user_id);
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let project_store = app_state.project_store.clone();
+
+    let project_id = ProjectId::parse(&project_id)
+        .map_err(|e| AppError::validation("project_id", e.to_string()))?;
+
+    let project = project_store.get_project(&project_id).await?;
+
+    if !project.is_viewable_by(viewer.as_ref()) {
+        return Err(AppError::new(
+            "You don't have permission to access this project",
+        ));
+    }
+
+    Ok(project)
+}
+
+/// Latest usage metrics of a project, see `[projects] metrics_interval_secs`.
+///
+/// Same access rules as [`get_project`]. Served from the cache the background collector
+/// fills, so `collected_at` is `None` until its first run.
+#[server]
+pub async fn get_project_metrics(project_id: String) -> Result<ProjectMetrics, AppError> {
+    use crate::server::AppState;
+    use crate::types::ProjectId;
+
+    let _timer = crate::perf::timer("get_project_metrics");
+
+    let viewer = fetch_session().await?.map(|session| session.user_id);
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let project_store = app_state.project_store.clone();
+
+    let project_id = ProjectId::parse(&project_id)
+        .map_err(|e| AppError::validation("project_id", e.to_string()))?;
+
+    let project = project_store.get_project(&project_id).await?;
+    if !project.is_viewable_by(viewer.as_ref()) {
+        return Err(AppError::new(
+            "You don't have permission to access this project",
+        ));
+    }
+
+    Ok(app_state.project_db.project_metrics(&project_id).await?)
+}
+
+/// Get a project by its slug, as linked from `/p/{slug}`.
+///
+/// Same access rules as [`get_project`], except a project the viewer may not see is
+/// reported as not found, so slugs don't reveal private projects.
+#[server]
+pub async fn get_project_by_slug(slug: String) -> Result<Project, AppError> {
+    use crate::server::AppState;
+
+    let _timer = crate::perf::timer("get_project_by_slug");
+
+    let viewer = fetch_session().await?.map(|session| session.user_id);
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    project_by_slug(app_state.project_store.as_ref(), viewer.as_ref(), &slug).await
+}
+
+/// The description of the project at `slug` as sanitized HTML, when `[ui]
+/// markdown_descriptions` is on; `None` when it's off or the project has no description.
+///
+/// Same access rules as [`get_project_by_slug`].
+#[server]
+pub async fn get_project_description_html(slug: String) -> Result<Option<String>, AppError> {
+    use crate::config::LOCAL_CONF;
+    use crate::server::AppState;
+
+    let _timer = crate::perf::timer("get_project_description_html");
+
+    if !LOCAL_CONF.ui.markdown_descriptions {
+        return Ok(None);
+    }
+    let viewer = fetch_session().await?.map(|session| session.user_id);
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let project = project_by_slug(app_state.project_store.as_ref(), viewer.as_ref(), &slug).await?;
+    Ok(project.description.as_deref().map(crate::markdown::render))
+}
+
+#[cfg(feature = "ssr")]
+async fn project_by_slug<P: crate::storage::ProjectStore + ?Sized>(
+    project_store: &P,
+    viewer: Option<&UserId>,
+    slug: &str,
+) -> Result<Project, AppError> {
+    match project_store.get_by_slug(slug).await {
+        Ok(project) if project.is_viewable_by(viewer) => Ok(project),
+        Ok(_) | Err(crate::storage::ProjectError::NotFound) => {
+            Err(AppError::not_found("Project not found"))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Update a project's name, description, rate limit and/or visibility.
+///
+/// Only the project owner can update it.
+#[server]
+pub async fn update_project(
+    project_id: String,
+    name: Option<String>,
+    description: Option<Option<String>>,
+    rate_limit_rpm: Option<Option<u32>>,
+    visibility: Option<ProjectVisibility>,
+    csrf_token: String,
+) -> Result<Project, AppError> {
+    use crate::server::AppState;
+    use crate::types::ProjectId;
+    use crate::webhooks::{ProjectEvent, ProjectEventKind};
+    use crate::webui::csrf::verify_csrf;
+
+    let _timer = crate::perf::timer("update_project");
+
+    verify_csrf(&csrf_token).await?;
+    validate_project_input(
+        name.as_deref(),
+        description.as_ref().and_then(Option::as_deref),
+    )?;
+    let name = name.map(|name| name.trim().to_string());
+    let description = description.map(|description| description.and_then(normalize_description));
+
+    // Get current user session
+    let session = fetch_session()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let project_store = app_state.project_store.clone();
+
+    let project_id = ProjectId::parse(&project_id)
+        .map_err(|e| AppError::validation("project_id", e.to_string()))?;
+
+    // Verify ownership before updating
+    let existing = project_store.get_project(&project_id).await?;
+    if existing.owner_id != session.user_id {
+        return Err(AppError::new(
+            "You don't have permission to update this project",
+        ));
+    }
+
+    let updated = project_store
+        .update_project(&project_id, name, description, rate_limit_rpm, visibility)
+        .await?;
+
+    if let Some(webhooks) = &app_state.webhooks {
+        webhooks.notify(ProjectEvent::new(
+            ProjectEventKind::ProjectUpdated,
+            &updated,
+        ));
+    }
+    for (kind, detail) in project_changes(&existing, &updated) {
+        record_project_audit(Some(session.user_id), updated.id, kind, detail).await;
+    }
+
+    Ok(updated)
+}
+
+/// Turn delete protection of one of the current user's projects on or off. While it is on,
+/// `delete_project` refuses to delete the project.
+#[server]
+pub async fn set_project_delete_protection(
+    project_id: String,
+    protected: bool,
+    csrf_token: String,
+) -> Result<Project, AppError> {
+    use crate::server::AppState;
+    use crate::types::ProjectId;
+    use crate::webhooks::{ProjectEvent, ProjectEventKind};
+    use crate::webui::csrf::verify_csrf;
+
+    let _timer = crate::perf::timer("set_project_delete_protection");
+
+    verify_csrf(&csrf_token).await?;
+
+    let session = fetch_session()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let project_store = app_state.project_store.clone();
+
+    let project_id = ProjectId::parse(&project_id)
+        .map_err(|e| AppError::validation("project_id", e.to_string()))?;
+
+    let existing = project_store.get_project(&project_id).await?;
+    if existing.owner_id != session.user_id {
+        return Err(AppError::new(
+            "You don't have permission to update this project",
+        ));
+    }
+
+    let updated = project_store
+        .set_delete_protection(&project_id, protected)
+        .await?;
+
+    if let Some(webhooks) = &app_state.webhooks {
+        webhooks.notify(ProjectEvent::new(
+            ProjectEventKind::ProjectUpdated,
+            &updated,
+        ));
+    }
+    for (kind, detail) in project_changes(&existing, &updated) {
+        record_project_audit(Some(session.user_id), updated.id, kind, detail).await;
+    }
+
+    Ok(updated)
+}
+
+/// Helper function describing what an update changed, as activity feed entries.
+///
+/// A rename is its own entry; description, rate limit and visibility changes are combined
+/// into one "settings changed" entry.
+#[cfg(feature = "ssr")]
+fn project_changes(before: &Project, after: &Project) -> Vec<(crate::types::AuditKind, String)> {
+    use crate::types::AuditKind;
+
+    let mut changes = Vec::new();
+    if before.name != after.name {
+        changes.push((
+            AuditKind::ProjectRenamed,
+            format!("{} -> {}", before.name, after.name),
+        ));
+    }
+
+    let rate_limit = |rpm: Option<u32>| {
+        rpm.map(|rpm| format!("{rpm}/min"))
+            .unwrap_or_else(|| "unlimited".to_string())
+    };
+    let mut settings = Vec::new();
+    if before.description != after.description {
+        settings.push("description changed".to_string());
+    }
+    if before.rate_limit_rpm != after.rate_limit_rpm {
+        settings.push(format!(
+            "rate limit {} -> {}",
+            rate_limit(before.rate_limit_rpm),
+            rate_limit(after.rate_limit_rpm)
+        ));
+    }
+    if before.visibility != after.visibility {
+        settings.push(format!(
+            "visibility {} -> {}",
+            before.visibility.as_str(),
+            after.visibility.as_str()
+        ));
+    }
+    if before.delete_protected != after.delete_protected {
+        let state = |protected| if protected { "on" } else { "off" };
+        settings.push(format!(
+            "delete protection {} -> {}",
+            state(before.delete_protected),
+            state(after.delete_protected)
+        ));
+    }
+    if !settings.is_empty() {
+        changes.push((
+            AuditKind::ProjectUpdated,
+            format!("{}: {}", after.name, settings.join(", ")),
+        ));
+    }
+
+    changes
+}
+
+/// Most events `get_project_activity` returns
+#[cfg(feature = "ssr")]
+const MAX_PROJECT_ACTIVITY: usize = 100;
+
+/// The activity feed of a project the current user owns, newest first.
+#[server]
+pub async fn get_project_activity(project_id: String) -> Result<Vec<AuditEvent>, AppError> {
+    use crate::server::AppState;
+    use crate::types::ProjectId;
+
+    let _timer = crate::perf::timer("get_project_activity");
+
+    let session = fetch_session()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let project_id = ProjectId::parse(&project_id)
+        .map_err(|e| AppError::validation("project_id", e.to_string()))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    project_activity(
+        app_state.project_store.as_ref(),
+        app_state.audit_store.as_ref(),
+        &session.user_id,
+        &project_id,
+    )
+    .await
+}
+
+/// Helper function backing `get_project_activity`.
+#[cfg(feature = "ssr")]
+async fn project_activity<
+    P: crate::storage::ProjectStore + ?Sized,
+    A: crate::storage::AuditStore + ?Sized,
+>(
+    project_store: &P,
+    audit_store: &A,
+    viewer: &UserId,
+    project_id: &crate::types::ProjectId,
+) -> Result<Vec<AuditEvent>, AppError> {
+    // the feed names who did what, so it stays with the owner even for public projects
+    let project = project_store.get_project(project_id).await?;
+    if project.owner_id != *viewer {
+        return Err(AppError::new(
+            "You don't have permission to access this project",
+        ));
+    }
+
+    let query = AuditQuery {
+        project_id: Some(*project_id),
+        limit: MAX_PROJECT_ACTIVITY,
+        ..Default::default()
+    };
+    Ok(audit_store.query(query).await?)
+}
+
+/// Delete a project by ID.
+///
+/// Only the project owner can delete it.
+#[server]
+pub async fn delete_project(project_id: String, csrf_token: String) -> Result<(), AppError> {
+    use crate::server::AppState;
+    use crate::types::{AuditKind, ProjectId};
+    use crate::webhooks::{ProjectEvent, ProjectEventKind};
+    use crate::webui::csrf::verify_csrf;
+
+    let _timer = crate::perf::timer("delete_project");
+
+    verify_csrf(&csrf_token).await?;
+
+    // Get current user session
+    let session = fetch_session()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let project_store = app_state.project_store.clone();
+
+    let project_id = ProjectId::parse(&project_id)
+        .map_err(|e| AppError::validation("project_id", e.to_string()))?;
+
+    // Verify ownership before deleting
+    let project = project_store.get_project(&project_id).await?;
+    if project.owner_id != session.user_id {
+        return Err(AppError::new(
+            "You don't have permission to delete this project",
+        ));
+    }
+
+    project_store.delete_project(&project_id).await?;
+
+    if let Some(webhooks) = &app_state.webhooks {
+        webhooks.notify(ProjectEvent::new(
+            ProjectEventKind::ProjectDeleted,
+            &project,
+        ));
+    }
+    record_project_audit(
+        Some(session.user_id),
+        project.id,
+        AuditKind::ProjectDeleted,
+        project.name.clone(),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Duplicate a project the current user can read, under a new id owned by them.
+///
+/// Returns the summary of the copy.
+#[server]
+pub async fn duplicate_project(
+    project_id: String,
+    csrf_token: String,
+) -> Result<ProjectSummary, AppError> {
+    use crate::server::AppState;
+    use crate::types::{AuditKind, ProjectId};
+    use crate::webhooks::{ProjectEvent, ProjectEventKind};
+    use crate::webui::csrf::verify_csrf;
+    use crate::webui::middleware::SignedIn;
+
+    let _timer = crate::perf::timer("duplicate_project");
+
+    verify_csrf(&csrf_token).await?;
+
+    // Get current user session
+    let SignedIn { session, user } = signed_in()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let project_store = app_state.project_store.clone();
+
+    let project_id = ProjectId::parse(&project_id)
+        .map_err(|e| AppError::validation("project_id", e.to_string()))?;
+
+    // Verify the caller can read the source
+    let source = project_store.get_project(&project_id).await?;
+    if source.owner_id != session.user_id {
+        return Err(AppError::new(
+            "You don't have permission to access this project",
+        ));
+    }
+    check_project_limit(project_store.as_ref(), &user.id, instance_limits(user.role)).await?;
+
+    let project = project_store
+        .clone_project(&project_id, &session.user_id)
+        .await?;
+
+    if let Some(webhooks) = &app_state.webhooks {
+        webhooks.notify(ProjectEvent::new(
+            ProjectEventKind::ProjectCreated,
+            &project,
+        ));
+    }
+    record_project_audit(
+        Some(session.user_id),
+        project.id,
+        AuditKind::ProjectCreated,
+        format!("{} (copied from {})", project.name, source.name),
+    )
+    .await;
+
+    Ok(ProjectSummary::from(project))
+}
+
+/// Download one of the current user's projects as a zip archive (see
+/// `crate::project_archive`), restorable with `import_project_archive`.
+#[server(
+    input = leptos::server_fn::codec::GetUrl,
+    output = leptos::server_fn::codec::Streaming
+)]
+pub async fn export_project_archive(
+    project_id: String,
+) -> Result<leptos::server_fn::codec::ByteStream<AppError>, AppError> {
+    use crate::server::AppState;
+    use crate::types::ProjectId;
+    use axum::http::{HeaderValue, header::CONTENT_DISPOSITION};
+    use leptos::server_fn::codec::ByteStream;
+    use leptos_axum::ResponseOptions;
+
+    let _timer = crate::perf::timer("export_project_archive");
+
+    let session = fetch_session()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+
+    let project_id = ProjectId::parse(&project_id)
+        .map_err(|e| AppError::validation("project_id", e.to_string()))?;
+
+    let (project, archive) = project_archive(
+        app_state.project_store.as_ref(),
+        &session.user_id,
+        &project_id,
+        time::OffsetDateTime::now_utc(),
+    )
+    .await?;
+
+    // slugs are plain ASCII, so this only fails for projects from before slugs existed
+    if let Ok(disposition) =
+        HeaderValue::from_str(&format!("attachment; filename=\"{}.zip\"", project.slug))
+    {
+        expect_context::<ResponseOptions>().insert_header(CONTENT_DISPOSITION, disposition);
+    }
+
+    // `Streaming` is the only codec that sends raw bytes; archives are at most
+    // `MAX_ARCHIVE_BYTES`, so the whole zip goes out as the one and only piece
+    Ok(ByteStream::new(futures_util::stream::once(async move {
+        Ok::<_, AppError>(archive)
+    })))
+}
+
+/// Helper function backing `export_project_archive`: only the owner may export a project.
+#[cfg(feature = "ssr")]
+async fn project_archive<P: crate::storage::ProjectStore + ?Sized>(
+    project_store: &P,
+    viewer: &UserId,
+    project_id: &crate::types::ProjectId,
+    now: time::OffsetDateTime,
+) -> Result<(Project, Vec<u8>), AppError> {
+    use crate::project_archive::ProjectArchive;
+
+    let project = project_store.get_project(project_id).await?;
+    if project.owner_id != *viewer {
+        return Err(AppError::forbidden(
+            "You don't have permission to access this project",
+        ));
+    }
+
+    let archive = ProjectArchive::of(&project, now)
+        .to_zip()
+        .map_err(|err| AppError::new(err.to_string()))?;
+    Ok((project, archive))
+}
+
+/// Create a project owned by the current user from an archive made by
+/// `export_project_archive`, sent base64-encoded.
+///
+/// Returns the summary of the new project. Archives of another format version are refused.
+#[server]
+pub async fn import_project_archive(
+    archive: String,
+    csrf_token: String,
+) -> Result<ProjectSummary, AppError> {
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webhooks::{ProjectEvent, ProjectEventKind};
+    use crate::webui::csrf::verify_csrf;
+    use base64::Engine;
+
+    let _timer = crate::perf::timer("import_project_archive");
+
+    verify_csrf(&csrf_token).await?;
+
+    let user = signed_in()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?
+        .user;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+
+    let archive = base64::engine::general_purpose::STANDARD
+        .decode(archive.trim())
+        .map_err(|_| AppError::validation("archive", "The archive couldn't be read"))?;
+
+    check_creation_cooldown(&app_state.creation_cooldown, &user.id, user.role)?;
+    check_project_limit(
+        app_state.project_store.as_ref(),
+        &user.id,
+        instance_limits(user.role),
+    )
+    .await?;
+
+    let project = import_archive(app_state.project_store.as_ref(), &user.id, &archive).await?;
+    app_state.creation_cooldown.record(&user.id);
+
+    if let Some(webhooks) = &app_state.webhooks {
+        webhooks.notify(ProjectEvent::new(
+            ProjectEventKind::ProjectCreated,
+            &project,
+        ));
+    }
+    record_project_audit(
+        Some(user.id),
+        project.id,
+        AuditKind::ProjectCreated,
+        format!("{} (imported)", project.name),
+    )
+    .await;
+
+    Ok(ProjectSummary::from(project))
+}
+
+/// Helper function backing `import_project_archive`: validates the archive like any new
+/// project's input, then creates it with the archived settings.
+///
+/// The project gets a fresh id, slug and timestamps; the archived ones belong to the original.
+/// If the settings can't be applied, the new project is deleted again rather than left
+/// half-imported.
+#[cfg(feature = "ssr")]
+async fn import_archive<P: crate::storage::ProjectStore + ?Sized>(
+    project_store: &P,
+    owner_id: &UserId,
+    archive: &[u8],
+) -> Result<Project, AppError> {
+    use crate::project_archive::ProjectArchive;
+
+    let archive = ProjectArchive::from_zip(archive)
+        .map_err(|err| AppError::validation("archive", err.to_string()))?;
+    let project = archive.project;
+    validate_project_input(Some(&project.name), project.description.as_deref())?;
+
+    let (created, _) = project_store
+        .create_project(
+            owner_id,
+            project.name.trim().to_string(),
+            project.description.and_then(normalize_description),
+            None,
+        )
+        .await?;
+    match apply_archived_settings(project_store, &created.id, &archive.settings).await {
+        Ok(project) => Ok(project),
+        Err(err) => {
+            if let Err(cleanup_err) = project_store.delete_project(&created.id).await {
+                tracing::warn!(
+                    project_id = %created.id.0,
+                    "Failed to delete a half-imported project: {cleanup_err}"
+                );
+            }
+            Err(err.into())
+        }
+    }
+}
+
+/// Applies the archived settings to a freshly imported project, protection last so a
+/// failure leaves it deletable
+#[cfg(feature = "ssr")]
+async fn apply_archived_settings<P: crate::storage::ProjectStore + ?Sized>(
+    project_store: &P,
+    project_id: &crate::types::ProjectId,
+    settings: &crate::project_archive::ArchivedSettings,
+) -> Result<Project, crate::storage::ProjectError> {
+    let project = project_store
+        .update_project(
+            project_id,
+            None,
+            None,
+            Some(settings.rate_limit_rpm),
+            Some(settings.visibility),
+        )
+        .await?;
+    if settings.delete_protected {
+        return project_store.set_delete_protection(project_id, true).await;
+    }
+    Ok(project)
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use crate::config::SESSION_DURATION;
+    use crate::storage::AuthStore;
+    use crate::storage::clock::TestClock;
+    use crate::storage::mem_authstore::MemoryAuthStore;
+    use crate::types::{LoginError, LoginField, PasswordHash, SessionId, SessionIp, Username};
+    use std::net::IpAddr;
+    use std::sync::Arc;
+    use time::Duration;
+
+    #[tokio::test]
+    async fn username_availability() {
+        let store = MemoryAuthStore::default();
+        store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert!(check_username_available(&store, "bob").await.unwrap());
+        assert!(!check_username_available(&store, "alice").await.unwrap());
+        assert!(check_username_available(&store, "a").await.is_err());
+        assert!(check_username_available(&store, "no spaces").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn login_errors_are_generic_by_default() {
+        let store = MemoryAuthStore::default();
+        store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            check_login(&store, "bob", "password", false)
+                .await
+                .unwrap_err(),
+            LoginError::InvalidCredentials
+        );
+        assert_eq!(
+            check_login(&store, "alice", "wrong", false)
+                .await
+                .unwrap_err(),
+            LoginError::InvalidCredentials
+        );
+        // input validation errors are field-level in both modes
+        assert!(matches!(
+            check_login(&store, "al", "password", false).await,
+            Err(LoginError::InvalidUsername(_))
+        ));
+        assert_eq!(
+            check_login(&store, "alice", "", false).await.unwrap_err(),
+            LoginError::MissingPassword
+        );
+        assert!(
+            check_login(&store, "alice", "password", false)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn login_errors_are_distinguished_when_revealed() {
+        let store = MemoryAuthStore::default();
+        store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let unknown = check_login(&store, "bob", "password", true)
+            .await
+            .unwrap_err();
+        assert_eq!(unknown, LoginError::UnknownUser);
+        assert_eq!(unknown.field(), Some(LoginField::Username));
+
+        let wrong = check_login(&store, "alice", "wrong", true)
+            .await
+            .unwrap_err();
+        assert_eq!(wrong, LoginError::WrongPassword);
+        assert_eq!(wrong.field(), Some(LoginField::Password));
+
+        let user = check_login(&store, "alice", "password", true)
+            .await
+            .unwrap();
+        assert_eq!(user.username.0, "alice");
+    }
+
+    #[tokio::test]
+    async fn session_introspection() {
+        let clock = Arc::new(TestClock::new());
+        let store = MemoryAuthStore::default().with_clock(clock.clone());
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let session = store
+            .issue_session(&user.id, SessionIp(ip), None)
+            .await
+            .unwrap();
+
+        let info = load_session_info(&store, &session.id).await.unwrap();
+        assert_eq!(info.user_id, user.id);
+        assert_eq!(info.username, "alice");
+        assert_eq!(info.ip, ip);
+        assert_eq!(info.created_at, session.created_at);
+        assert_eq!(info.expires_at, session.expires_at);
+        assert_eq!(info.last_seen_at, session.created_at);
+
+        let unknown = SessionId("not-a-session".to_string());
+        assert_eq!(
+            load_session_info(&store, &unknown)
+                .await
+                .unwrap_err()
+                .to_string(),
+            "Session not found"
+        );
+
+        // expired sessions are reported as missing and removed
+        clock.advance(SESSION_DURATION + Duration::seconds(1));
+        assert_eq!(
+            load_session_info(&store, &session.id)
+                .await
+                .unwrap_err()
+                .to_string(),
+            "Session not found"
+        );
+        clock.set(session.created_at);
+        assert!(store.fetch_session(&session.id).await.is_err());
+    }
+
+    #[test]
+    fn configured_app_name_is_in_page_title() {
+        let branding = Branding {
+            app_name: "Acme Cloud".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(branding.page_title(), "Acme Cloud: Backend Toolbox");
+        assert_eq!(Branding::default().page_title(), "Bento: Backend Toolbox");
+    }
+
+    fn validation_field(result: Result<(), AppError>) -> Option<String> {
+        let err = result.unwrap_err();
+        assert_eq!(err.kind(), crate::types::AppErrorKind::Validation);
+        err.field().map(str::to_string)
+    }
+
+    #[test]
+    fn project_input_within_limits_passes() {
+        let name = "n".repeat(PROJECT_NAME_MAX_LEN);
+        let description = "d".repeat(PROJECT_DESCRIPTION_MAX_LEN);
+        assert!(validate_project_input(Some(&name), Some(&description)).is_ok());
+        // unchanged fields aren't checked
+        assert!(validate_project_input(None, None).is_ok());
+    }
+
+    #[test]
+    fn over_long_project_input_names_the_field() {
+        let name = "n".repeat(PROJECT_NAME_MAX_LEN + 1);
+        assert_eq!(
+            validation_field(validate_project_input(Some(&name), None)).as_deref(),
+            Some("name")
+        );
+
+        let description = "d".repeat(PROJECT_DESCRIPTION_MAX_LEN + 1);
+        assert_eq!(
+            validation_field(validate_project_input(Some("Lunch"), Some(&description))).as_deref(),
+            Some("description")
+        );
+    }
+
+    #[test]
+    fn blank_project_name_is_rejected() {
+        assert_eq!(
+            validation_field(validate_project_input(Some(""), None)).as_deref(),
+            Some("name")
+        );
+        assert_eq!(
+            validation_field(validate_project_input(Some(" \t\n"), None)).as_deref(),
+            Some("name")
+        );
+    }
+
+    #[test]
+    fn trailing_whitespace_is_trimmed_before_length_check() {
+        let name = format!("{}   \n", "n".repeat(PROJECT_NAME_MAX_LEN));
+        assert!(validate_project_input(Some(&name), None).is_ok());
+
+        let description = format!("{}\t\t", "d".repeat(PROJECT_DESCRIPTION_MAX_LEN));
+        assert!(validate_project_input(None, Some(&description)).is_ok());
+    }
+
+    #[test]
+    fn emoji_names_are_counted_by_grapheme() {
+        // a family emoji: one grapheme, 7 code points, 25 bytes
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}";
+        let name = family.repeat(PROJECT_NAME_MAX_LEN);
+        assert!(name.len() > PROJECT_NAME_MAX_LEN * 20);
+        assert!(validate_project_input(Some(&name), None).is_ok());
+
+        let name = family.repeat(PROJECT_NAME_MAX_LEN + 1);
+        assert_eq!(
+            validation_field(validate_project_input(Some(&name), None)).as_deref(),
+            Some("name")
+        );
+    }
+
+    #[tokio::test]
+    async fn admin_password_reset_replaces_password_and_revokes_sessions() {
+        let store = MemoryAuthStore::default();
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("old-password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let first = store
+            .issue_session(&user.id, ip.clone(), None)
+            .await
+            .unwrap();
+        let second = store.issue_session(&user.id, ip, None).await.unwrap();
+        let limits = crate::config::CredentialLimits::default();
+
+        reset_user_password(&store, &limits, &user.id, "new-password")
+            .await
+            .unwrap();
+
+        let user = store.get_user_by_id(&user.id).await.unwrap();
+        assert!(!user.password_hash.verify("old-password"));
+        assert!(user.password_hash.verify("new-password"));
+        assert!(store.fetch_session(&first.id).await.is_err());
+        assert!(store.fetch_session(&second.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn admin_password_reset_enforces_password_policy() {
+        let store = MemoryAuthStore::default();
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("old-password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let limits = crate::config::CredentialLimits {
+            password_min_len: 8,
+            ..Default::default()
+        };
+
+        let err = reset_user_password(&store, &limits, &user.id, "short")
+            .await
+            .unwrap_err();
+        assert_eq!(err.field(), Some("new_password"));
+
+        let user = store.get_user_by_id(&user.id).await.unwrap();
+        assert!(user.password_hash.verify("old-password"));
+
+        // unknown users can't be targeted
+        assert!(
+            reset_user_password(&store, &limits, &UserId::new(), "new-password")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn invite_code_changes_refuse_forged_calls() {
+        use crate::webui::csrf::forged_csrf_call;
+
+        let refused =
+            forged_csrf_call(|csrf_token| create_invite_code(Role::User, 1, None, csrf_token))
+                .await;
+        assert_eq!(refused, "Invalid CSRF token, please reload the page");
+
+        let refused = forged_csrf_call(|csrf_token| {
+            revoke_invite_code("invite-code".to_string(), csrf_token)
+        })
+        .await;
+        assert_eq!(refused, "Invalid CSRF token, please reload the page");
+    }
+
+    #[test]
+    fn password_length_errors_name_the_checked_input() {
+        let limits = crate::config::CredentialLimits::default();
+        let oversized = "p".repeat(limits.password_max_len + 1);
+
+        let err = check_password_len(&limits, "password", &oversized).unwrap_err();
+        assert_eq!(err.field(), Some("password"));
+        assert!(check_password_len(&limits, "password", "password").is_ok());
+    }
+
+    #[tokio::test]
+    async fn project_pages_follow_the_cursor() {
+        use crate::storage::ProjectStore;
+        use crate::storage::redb_projectstore::RedbProjectStore;
+
+        let store = RedbProjectStore::in_memory().unwrap();
+        let owner = UserId::new();
+        for i in 0..5 {
+            store
+                .create_project(&owner, format!("project-{i}"), None, None)
+                .await
+                .unwrap();
+        }
+        let names = |page: &ProjectPage| -> Vec<String> {
+            page.projects.iter().map(|p| p.name.clone()).collect()
+        };
+
+        let first = project_page(&store, &owner, None, 2).await.unwrap();
+        assert_eq!(first.total, 5);
+        assert_eq!(names(&first), ["project-4", "project-3"]);
+
+        let cursor = first.next_cursor.unwrap().0.to_string();
+        let second = project_page(&store, &owner, Some(&cursor), 2)
+            .await
+            .unwrap();
+        assert_eq!(names(&second), ["project-2", "project-1"]);
+
+        let cursor = second.next_cursor.unwrap().0.to_string();
+        let last = project_page(&store, &owner, Some(&cursor), 2)
+            .await
+            .unwrap();
+        assert_eq!(names(&last), ["project-0"]);
+        assert_eq!(last.next_cursor, None);
+
+        let capped = project_page(&store, &owner, None, usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(capped.projects.len(), 5);
+
+        assert!(
+            project_page(&store, &owner, Some("not-an-id"), 2)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn disabled_user_cannot_log_in_until_re_enabled() {
+        let store = MemoryAuthStore::default();
+        let admin = store
+            .create_admin(
+                &Username("admin".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let session = store
+            .issue_session(&user.id, SessionIp(IpAddr::from([127, 0, 0, 1])), None)
+            .await
+            .unwrap();
+
+        change_user_status(&store, &admin.id, &user.id, UserStatus::Disabled)
+            .await
+            .unwrap();
+        assert_eq!(
+            check_login(&store, "alice", "password", false)
+                .await
+                .unwrap_err(),
+            LoginError::AccountDisabled
+        );
+        // a wrong password still gets the usual answer, so the status isn't revealed
+        assert_eq!(
+            check_login(&store, "alice", "wrong", false)
+                .await
+                .unwrap_err(),
+            LoginError::InvalidCredentials
+        );
+        assert!(store.fetch_session(&session.id).await.is_err());
+        // the record itself is kept
+        assert_eq!(
+            store.get_user_by_id(&user.id).await.unwrap().status,
+            UserStatus::Disabled
+        );
+
+        change_user_status(&store, &admin.id, &user.id, UserStatus::Active)
+            .await
+            .unwrap();
+        assert!(
+            check_login(&store, "alice", "password", false)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn admins_cannot_disable_themselves() {
+        let store = MemoryAuthStore::default();
+        let admin = store
+            .create_admin(
+                &Username("admin".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert!(
+            change_user_status(&store, &admin.id, &admin.id, UserStatus::Disabled)
+                .await
+                .is_err()
+        );
+        assert!(store.get_user_by_id(&admin.id).await.unwrap().is_active());
+    }
+
+    #[test]
+    fn busiest_session_ips_come_first() {
+        let ip = |last: u8| IpAddr::from([10, 0, 0, last]);
+        let counts = [(ip(1), 1), (ip(2), 5), (ip(3), 2), (ip(4), 5)]
+            .into_iter()
+            .collect();
+
+        let top = top_session_ips(counts, 3);
+        let expected = [(ip(2), 5), (ip(4), 5), (ip(3), 2)];
+        assert_eq!(
+            top.iter().map(|c| (c.ip, c.sessions)).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn logout_all_ends_every_session_of_the_user() {
+        let store = MemoryAuthStore::default();
+        let alice = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let bob = store
+            .create_standard_user(
+                &Username("bob".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let mut tokens = Vec::new();
+        for _ in 0..3 {
+            tokens.push(
+                store
+                    .issue_session(&alice.id, ip.clone(), None)
+                    .await
+                    .unwrap()
+                    .id,
+            );
+        }
+        let other = store.issue_session(&bob.id, ip, None).await.unwrap();
+
+        let (user_id, revoked) = end_all_sessions(&store, &tokens[1]).await.unwrap();
+        assert_eq!(user_id, alice.id);
+        assert_eq!(revoked, 3);
+        for token in &tokens {
+            assert!(store.fetch_session(token).await.is_err());
+        }
+        // other users stay signed in
+        assert!(store.fetch_session(&other.id).await.is_ok());
+        // the ended session can't be used to try again
+        assert!(end_all_sessions(&store, &tokens[1]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn renaming_reports_taken_and_invalid_names_on_the_field() {
+        let store = MemoryAuthStore::default();
+        let alice = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        store
+            .create_standard_user(
+                &Username("bob".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let taken = rename_user(&store, &alice.id, "bob").await.unwrap_err();
+        assert_eq!(taken.field(), Some("new_username"));
+        let invalid = rename_user(&store, &alice.id, "no spaces")
+            .await
+            .unwrap_err();
+        assert_eq!(invalid.field(), Some("new_username"));
+
+        let renamed = rename_user(&store, &alice.id, "alicia").await.unwrap();
+        assert_eq!(renamed.username.0, "alicia");
+        assert!(check_username_available(&store, "alice").await.unwrap());
+        assert!(!check_username_available(&store, "alicia").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn locked_accounts_can_log_in_after_an_admin_unlocks_them() {
+        let store = MemoryAuthStore::default();
+        store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let lockout = crate::config::Lockout {
+            max_failed_logins: 3,
+            lock_minutes: 15,
+            ..Default::default()
+        };
+        let now = time::OffsetDateTime::now_utc();
+        let attempt = |password: &'static str| {
+            check_login_with_lockout(&store, &lockout, "alice", password, false, now)
+        };
+
+        for _ in 0..3 {
+            assert_eq!(
+                attempt("wrong").await.unwrap_err(),
+                LoginError::InvalidCredentials
+            );
+        }
+        // even the right password is refused while locked
+        assert_eq!(
+            attempt("password").await.unwrap_err(),
+            LoginError::AccountLocked
+        );
+
+        let alice = store
+            .get_user_by_username(&Username("alice".to_string()))
+            .await
+            .unwrap();
+        assert!(alice.is_locked(now));
+        let unlocked = store.clear_lockout(&alice.id).await.unwrap();
+        assert!(!unlocked.is_locked(now));
+
+        assert!(attempt("password").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn account_overview_matches_the_individual_calls() {
+        use crate::storage::ProjectStore;
+        use crate::storage::redb_projectstore::RedbProjectStore;
+
+        let clock = Arc::new(TestClock::new());
+        let store = MemoryAuthStore::default().with_clock(clock.clone());
+        let projects = RedbProjectStore::in_memory().unwrap();
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        store
+            .issue_session(&user.id, SessionIp(ip), None)
+            .await
+            .unwrap();
+        clock.advance(Duration::minutes(1));
+        let newer = store
+            .issue_session(&user.id, SessionIp(ip), None)
+            .await
+            .unwrap();
+        for name in ["one", "two"] {
+            projects
+                .create_project(&user.id, name.to_string(), None, None)
+                .await
+                .unwrap();
+        }
+
+        let overview = account_overview(&store, &projects, &user.id).await.unwrap();
+
+        let user = store.get_user_by_id(&user.id).await.unwrap();
+        assert_eq!(
+            overview.user,
+            CurrentUser::from(&user)
+                .with_session_count(&store)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            overview.sessions,
+            list_session_infos(&store, &user).await.unwrap()
+        );
+        assert_eq!(
+            overview.project_count,
+            projects.get_user_projects(&user.id).await.unwrap().len()
+        );
+        assert_eq!(overview.sessions.len(), 2);
+        assert_eq!(overview.sessions[0].created_at, newer.created_at);
+        assert_eq!(overview.project_count, 2);
+    }
+
+    #[tokio::test]
+    async fn renames_show_up_in_the_owners_project_activity_only() {
+        use crate::storage::redb_auditstore::RedbAuditStore;
+        use crate::storage::redb_projectstore::RedbProjectStore;
+        use crate::storage::{AuditStore, ProjectStore};
+        use crate::types::{AuditKind, UserId};
+
+        let projects = RedbProjectStore::in_memory().unwrap();
+        let audit = RedbAuditStore::in_memory().unwrap();
+        let (owner, stranger) = (UserId::new(), UserId::new());
+        let (before, _) = projects
+            .create_project(&owner, "Draft".to_string(), None, None)
+            .await
+            .unwrap();
+        let after = projects
+            .update_project(&before.id, Some("Launch".to_string()), None, None, None)
+            .await
+            .unwrap();
+
+        for (kind, detail) in project_changes(&before, &after) {
+            let event = AuditEvent::new(Some(owner), kind, detail).with_project(after.id);
+            audit.record(event).await.unwrap();
+        }
+        // events about other projects stay out of the feed
+        let other = AuditEvent::new(Some(owner), AuditKind::ProjectCreated, "Other")
+            .with_project(crate::types::ProjectId::new());
+        audit.record(other).await.unwrap();
+
+        let activity = project_activity(&projects, &audit, &owner, &after.id)
+            .await
+            .unwrap();
+        assert_eq!(activity.len(), 1);
+        assert_eq!(activity[0].kind, AuditKind::ProjectRenamed);
+        assert_eq!(activity[0].detail, "Draft -> Launch");
+
+        assert!(
+            project_activity(&projects, &audit, &stranger, &after.id)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn store_errors_surface_as_generic_messages() {
+        use crate::storage::AuthError;
+        use crate::storage::mock_authstore::MockAuthStore;
+
+        let store = MockAuthStore::default();
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let session = store
+            .issue_session(&user.id, SessionIp("10.0.0.1".parse().unwrap()), None)
+            .await
+            .unwrap();
+
+        // a lost session reads as missing, anything else as an internal error
+        store.fail_next("fetch_session", AuthError::InvalidSession);
+        store.fail_next(
+            "fetch_session",
+            AuthError::Internal("disk gone".to_string()),
+        );
+        assert_eq!(
+            load_session_info(&store, &session.id)
+                .await
+                .unwrap_err()
+                .to_string(),
+            "Session not found"
+        );
+        assert_eq!(
+            load_session_info(&store, &session.id)
+                .await
+                .unwrap_err()
+                .to_string(),
+            "An internal error occurred. Please try again later."
+        );
+
+        // a failing lookup isn't mistaken for a wrong password, even when errors are hidden
+        store.fail_next(
+            "get_user_by_username",
+            AuthError::Internal("disk gone".to_string()),
+        );
+        assert_eq!(
+            check_login(&store, "alice", "password", false)
+                .await
+                .unwrap_err(),
+            LoginError::Other("An internal error occurred. Please try again later.".to_string())
+        );
+        assert!(
+            check_login(&store, "alice", "password", false)
+                .await
+                .is_ok()
+        );
+        assert_eq!(store.calls("get_user_by_username"), 2);
+    }
+
+    #[tokio::test]
+    async fn failing_server_fns_respond_with_their_error_kinds_status() {
+        use axum::{
+            Router,
+            body::Body,
+            http::{Request, StatusCode},
+            routing::post,
+        };
+        use leptos::server_fn::ServerFn;
+        use tower::ServiceExt;
+
+        let app = Router::new().route("/api/{*fn_name}", post(leptos_axum::handle_server_fns));
+        // no session cookie, so the server function bails out before touching any state
+        let request = Request::post(<GetMyProjects as ServerFn>::PATH)
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let err: AppError = serde_json::from_slice(&body).unwrap();
+        assert_eq!(err.kind(), crate::types::AppErrorKind::Unauthenticated);
+        assert_eq!(err.message(), "Not authenticated");
+    }
+
+    #[tokio::test]
+    async fn private_projects_are_hidden_behind_their_slug() {
+        use crate::storage::ProjectStore;
+        use crate::storage::redb_projectstore::RedbProjectStore;
+        use crate::types::AppErrorKind;
+
+        let store = RedbProjectStore::in_memory().unwrap();
+        let (owner, stranger) = (UserId::new(), UserId::new());
+        let (project, _) = store
+            .create_project(&owner, "Lunch box".to_string(), None, None)
+            .await
+            .unwrap();
+
+        let found = project_by_slug(&store, Some(&owner), "lunch-box").await;
+        assert_eq!(found.unwrap().id, project.id);
+        for viewer in [Some(&stranger), None] {
+            let err = project_by_slug(&store, viewer, "lunch-box")
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind(), AppErrorKind::NotFound);
+        }
+
+        store
+            .update_project(
+                &project.id,
+                None,
+                None,
+                None,
+                Some(ProjectVisibility::Public),
+            )
+            .await
+            .unwrap();
+        assert!(project_by_slug(&store, None, "lunch-box").await.is_ok());
+        let missing = project_by_slug(&store, None, "dinner").await.unwrap_err();
+        assert_eq!(missing.kind(), AppErrorKind::NotFound);
+    }
+
+    #[tokio::test]
+    async fn bulk_creation_reports_each_row() {
+        let store = MemoryAuthStore::default();
+        store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let row = |username: &str, password: &str, role| NewUser {
+            username: username.to_string(),
+            password: password.to_string(),
+            role,
+        };
+        let limits = crate::config::CredentialLimits {
+            password_min_len: 8,
+            ..Default::default()
+        };
+
+        let results = create_users(
+            &store,
+            &limits,
+            vec![
+                row("bob", "password", Role::User),
+                row("alice", "password", Role::User),
+                row("carol", "password", Role::Admin),
+                row("bob", "password", Role::User),
+                row("dave", "pw", Role::User),
+            ],
+        )
+        .await;
+
+        let usernames: Vec<_> = results.iter().map(|r| r.username.as_str()).collect();
+        assert_eq!(usernames, ["bob", "alice", "carol", "bob", "dave"]);
+        assert!(results[0].outcome.is_ok());
+        assert_eq!(
+            results[1].outcome.as_ref().unwrap_err(),
+            "That username is already taken"
+        );
+        assert!(results[2].outcome.is_ok());
+        assert!(results[3].outcome.is_err(), "repeated within the batch");
+        assert!(results[4].outcome.is_err(), "password too short");
+
+        let carol = store
+            .get_user_by_username(&Username("carol".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(Ok(carol.id), results[2].outcome);
+        assert_eq!(carol.role, Role::Admin);
+        assert!(carol.password_hash.verify("password"));
+        assert!(
+            store
+                .get_user_by_username(&Username("dave".to_string()))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn session_limit_lists_the_sessions_to_sign_out() {
+        let store = MemoryAuthStore::new(2);
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let ip = || SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let mut existing = Vec::new();
+        for _ in 0..2 {
+            existing.push(store.issue_session(&user.id, ip(), None).await.unwrap());
+        }
+
+        // without `list_on_limit` the limit stays a generic error
+        let err = issue_login_session(&store, &user.id, ip(), None, None, false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LoginError::Other(_)), "{err:?}");
+
+        let err = issue_login_session(&store, &user.id, ip(), None, None, true)
+            .await
+            .unwrap_err();
+        let LoginError::SessionLimitReached(listed) = &err else {
+            panic!("expected the session list, got {err:?}");
+        };
+        let mut listed: Vec<&str> = listed.iter().map(|s| s.id.as_str()).collect();
+        let mut expected: Vec<String> = existing.iter().map(|s| s.id.public_id()).collect();
+        listed.sort();
+        expected.sort();
+        assert_eq!(listed, expected);
+        assert!(err.to_string().contains("signed in on 2 devices"));
+
+        // an id that isn't one of the user's sessions frees nothing up
+        let err = issue_login_session(&store, &user.id, ip(), None, Some("0123456789abcdef"), true)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LoginError::SessionLimitReached(_)));
+
+        // picking one signs it out and lets the login through
+        let picked = existing[0].id.public_id();
+        let session = issue_login_session(&store, &user.id, ip(), None, Some(&picked), true)
+            .await
+            .unwrap();
+        assert!(store.fetch_session(&existing[0].id).await.is_err());
+        assert!(store.fetch_session(&existing[1].id).await.is_ok());
+        assert!(store.fetch_session(&session.id).await.is_ok());
+    }
+
+    #[test]
+    fn rapid_project_creation_waits_out_the_cooldown() {
+        use crate::rate_limit::CreationCooldown;
+        use crate::types::AppErrorKind;
+
+        let clock = Arc::new(TestClock::new());
+        let cooldown = CreationCooldown::new(Duration::seconds(60)).with_clock(clock.clone());
+        let user = UserId::new();
+
+        check_creation_cooldown(&cooldown, &user, Role::User).unwrap();
+        cooldown.record(&user);
+
+        clock.advance(Duration::seconds(15));
+        let err = check_creation_cooldown(&cooldown, &user, Role::User).unwrap_err();
+        assert_eq!(err.kind(), AppErrorKind::RateLimited);
+        assert!(
+            err.message().contains("45 more seconds"),
+            "{}",
+            err.message()
+        );
+
+        // admins are never held up
+        check_creation_cooldown(&cooldown, &user, Role::Admin).unwrap();
+
+        clock.advance(Duration::seconds(45));
+        check_creation_cooldown(&cooldown, &user, Role::User).unwrap();
+    }
+
+    #[tokio::test]
+    async fn project_creation_stops_at_the_limit() {
+        use crate::storage::ProjectStore;
+        use crate::storage::redb_projectstore::RedbProjectStore;
+        use crate::types::AppErrorKind;
+
+        let store = RedbProjectStore::in_memory().unwrap();
+        let owner = UserId::new();
+        let limits = InstanceLimits {
+            max_projects_per_user: Some(2),
+        };
+
+        let mut created = Vec::new();
+        for name in ["Bento", "Lunch box"] {
+            check_project_limit(&store, &owner, limits).await.unwrap();
+            let (project, _) = store
+                .create_project(&owner, name.to_string(), None, None)
+                .await
+                .unwrap();
+            created.push(project.id);
+        }
+        let err = check_project_limit(&store, &owner, limits)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), AppErrorKind::Forbidden);
+        assert_eq!(err.message(), "Project limit reached (2/2)");
+
+        // other owners and unlimited users aren't affected
+        check_project_limit(&store, &UserId::new(), limits)
+            .await
+            .unwrap();
+        check_project_limit(&store, &owner, InstanceLimits::default())
+            .await
+            .unwrap();
+
+        // deleting a project makes room again
+        store.delete_project(&created[0]).await.unwrap();
+        check_project_limit(&store, &owner, limits).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn changing_the_password_keeps_only_this_session() {
+        let store = MemoryAuthStore::default();
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("old-password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let current = store
+            .issue_session(&user.id, ip.clone(), None)
+            .await
+            .unwrap();
+        let other = store.issue_session(&user.id, ip, None).await.unwrap();
+        let limits = crate::config::CredentialLimits::default();
+
+        let err = change_own_password(&store, &limits, &current, "wrong", "new-password")
+            .await
+            .unwrap_err();
+        assert_eq!(err.field(), Some("current_password"));
+        assert!(store.fetch_session(&other.id).await.is_ok());
+
+        let revoked =
+            change_own_password(&store, &limits, &current, "old-password", "new-password")
+                .await
+                .unwrap();
+        assert_eq!(revoked, 1);
+
+        let user = store.get_user_by_id(&user.id).await.unwrap();
+        assert!(user.password_hash.verify("new-password"));
+        assert!(store.fetch_session(&current.id).await.is_ok());
+        assert!(store.fetch_session(&other.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn recent_passwords_cannot_be_reused() {
+        use crate::types::AppErrorKind;
+
+        let store = MemoryAuthStore::default().with_password_history(3);
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("first-password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let session = store
+            .issue_session(&user.id, SessionIp(IpAddr::from([127, 0, 0, 1])), None)
+            .await
+            .unwrap();
+        let limits = crate::config::CredentialLimits::default();
+
+        change_own_password(
+            &store,
+            &limits,
+            &session,
+            "first-password",
+            "second-password",
+        )
+        .await
+        .unwrap();
+
+        // the immediately previous password
+        let err = change_own_password(
+            &store,
+            &limits,
+            &session,
+            "second-password",
+            "first-password",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind(), AppErrorKind::Validation);
+        // an admin reset is held to the same rule
+        assert!(
+            reset_user_password(&store, &limits, &user.id, "first-password")
+                .await
+                .is_err()
+        );
+        let current = store.get_user_by_id(&user.id).await.unwrap();
+        assert!(current.password_hash.verify("second-password"));
+
+        change_own_password(
+            &store,
+            &limits,
+            &session,
+            "second-password",
+            "third-password",
+        )
+        .await
+        .unwrap();
+        let current = store.get_user_by_id(&user.id).await.unwrap();
+        assert!(current.password_hash.verify("third-password"));
+    }
+
+    #[tokio::test]
+    async fn sensitive_actions_need_a_recent_password() {
+        use crate::storage::clock::Clock;
+        use crate::types::AppErrorKind;
+
+        let clock = Arc::new(TestClock::new());
+        let store = MemoryAuthStore::default().with_clock(clock.clone());
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let session = store.issue_session(&user.id, ip, None).await.unwrap();
+        let max_age = Duration::minutes(15);
+
+        // fresh from signing in
+        check_recent_auth(&session, max_age, clock.now()).unwrap();
+
+        clock.advance(Duration::minutes(20));
+        let err = check_recent_auth(&session, max_age, clock.now()).unwrap_err();
+        assert_eq!(err.kind(), AppErrorKind::ReauthRequired);
+
+        let err = confirm_password(&store, &session, "wrong")
+            .await
+            .unwrap_err();
+        assert_eq!(err.field(), Some("password"));
+        let session = store.fetch_session(&session.id).await.unwrap();
+        assert!(check_recent_auth(&session, max_age, clock.now()).is_err());
+
+        confirm_password(&store, &session, "password")
+            .await
+            .unwrap();
+        let session = store.fetch_session(&session.id).await.unwrap();
+        check_recent_auth(&session, max_age, clock.now()).unwrap();
+    }
+
+    async fn admin_and_user(store: &MemoryAuthStore) -> (crate::types::User, crate::types::User) {
+        let admin = store
+            .create_user(
+                &Username("admin".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+                Role::Admin,
+                UserStatus::Active,
+            )
+            .await
+            .unwrap();
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        (admin, user)
+    }
+
+    #[tokio::test]
+    async fn impersonation_acts_as_the_user_until_ended() {
+        use crate::webui::middleware::load_current_user;
+
+        let store = MemoryAuthStore::default();
+        let (admin, alice) = admin_and_user(&store).await;
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let admin_session = store.issue_session(&admin.id, ip, None).await.unwrap();
+
+        // starting swaps the admin's session for one acting as alice
+        let (user, session) = start_impersonation(&store, &admin.id, &admin_session, &alice.id)
+            .await
+            .unwrap();
+        assert_eq!(user.id, alice.id);
+        assert_eq!(session.user_id, alice.id);
+        assert_eq!(session.impersonator, Some(admin.id));
+        assert!(store.fetch_session(&admin_session.id).await.is_err());
+
+        // acting as alice, with the admin shown behind her
+        let current = load_current_user(&store, &session.id, None).await.unwrap();
+        assert_eq!(current.username, "alice");
+        assert_eq!(current.role, Role::User);
+        assert_eq!(current.impersonated_by.as_deref(), Some("admin"));
+
+        // ending signs the admin back in as themselves
+        let restored = stop_impersonation(&store, &session).await.unwrap();
+        assert_eq!(restored.user_id, admin.id);
+        assert_eq!(restored.impersonator, None);
+        assert!(store.fetch_session(&session.id).await.is_err());
+        let current = load_current_user(&store, &restored.id, None).await.unwrap();
+        assert_eq!(current.username, "admin");
+        assert_eq!(current.impersonated_by, None);
+
+        // and a regular session has nothing to end
+        let err = stop_impersonation(&store, &restored).await.unwrap_err();
+        assert_eq!(err.kind(), crate::types::AppErrorKind::Conflict);
+    }
+
+    #[tokio::test]
+    async fn admins_cannot_be_impersonated() {
+        let store = MemoryAuthStore::default();
+        let (admin, _) = admin_and_user(&store).await;
+        let other_admin = store
+            .create_user(
+                &Username("root".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+                Role::Admin,
+                UserStatus::Active,
+            )
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let admin_session = store.issue_session(&admin.id, ip, None).await.unwrap();
+
+        for target in [&other_admin.id, &admin.id] {
+            let err = start_impersonation(&store, &admin.id, &admin_session, target)
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind(), crate::types::AppErrorKind::Forbidden);
+        }
+        // the admin stays signed in
+        assert!(store.fetch_session(&admin_session.id).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn impersonation_sessions_cannot_change_credentials() {
+        use crate::types::AppErrorKind;
+
+        let store = MemoryAuthStore::default();
+        let (admin, alice) = admin_and_user(&store).await;
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let session = store
+            .issue_impersonation_session(&admin.id, &alice.id, ip, None)
+            .await
+            .unwrap();
+
+        // `change_username` and other sensitive actions go through `require_recent_auth`,
+        // which impersonation sessions never pass, however fresh
+        let err =
+            check_recent_auth(&session, Duration::minutes(15), session.created_at).unwrap_err();
+        assert_eq!(err.kind(), AppErrorKind::Forbidden);
+
+        let limits = crate::config::CredentialLimits::default();
+        let err = change_own_password(&store, &limits, &session, "password", "new-password")
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), AppErrorKind::Forbidden);
+        let alice = store.get_user_by_id(&alice.id).await.unwrap();
+        assert!(alice.password_hash.verify("password"));
+    }
+
+    #[tokio::test]
+    async fn ending_impersonation_keeps_it_if_the_admin_session_is_refused() {
+        use crate::storage::AuthError;
+        use crate::storage::mock_authstore::MockAuthStore;
+
+        let store = MockAuthStore::default();
+        let admin = store
+            .create_admin(
+                &Username("admin".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let alice = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let session = store
+            .issue_impersonation_session(&admin.id, &alice.id, ip, None)
+            .await
+            .unwrap();
+
+        store.fail_next("issue_session", AuthError::SessionLimitReached);
+        stop_impersonation(&store, &session).await.unwrap_err();
+        // still signed in, just as alice
+        assert!(store.fetch_session(&session.id).await.is_ok());
+    }
+
+    #[test]
+    fn impersonated_actions_are_attributed_to_the_admin() {
+        use crate::types::AuditKind;
+
+        let admin = UserId::new();
+        let alice = UserId::new();
+        let event = AuditEvent::new(Some(alice), AuditKind::ProjectCreated, "Lunch")
+            .impersonated_by(admin, "alice");
+        assert_eq!(event.actor, Some(admin));
+        assert_eq!(event.detail, "Lunch (as alice)");
+
+        let event =
+            AuditEvent::new(Some(alice), AuditKind::Logout, "").impersonated_by(admin, "alice");
+        assert_eq!(event.detail, "as alice");
+    }
+
+    #[tokio::test]
+    async fn spraying_from_one_address_blocks_it_before_accounts_lock() {
+        use crate::rate_limit::IpLoginThrottle;
+
+        let store = MemoryAuthStore::default();
+        let names = ["alice", "bob", "carol", "dave", "erin"];
+        for name in names {
+            store
+                .create_standard_user(
+                    &Username(name.to_string()),
+                    PasswordHash::try_from("password").unwrap(),
+                )
+                .await
+                .unwrap();
+        }
+        let lockout = crate::config::Lockout {
+            max_failed_logins: 3,
+            ip_max_accounts: 4,
+            ..Default::default()
+        };
+        let throttle = IpLoginThrottle::new(4, lockout.ip_window(), lockout.ip_block_duration());
+        let now = time::OffsetDateTime::now_utc();
+        let sprayer = IpAddr::from([203, 0, 113, 7]);
+        let attempt = |ip: IpAddr, username: &'static str, password: &'static str| {
+            let store = &store;
+            let lockout = &lockout;
+            let throttle = &throttle;
+            async move {
+                let login =
+                    check_login_with_lockout(store, lockout, username, password, false, now);
+                throttle_login(throttle, ip, username, login).await
+            }
+        };
+
+        // one guess per account, as a spray goes, plus one at an account that doesn't exist
+        for username in ["alice", "bob", "nobody", "carol"] {
+            assert_eq!(
+                attempt(sprayer, username, "guess").await.unwrap_err(),
+                LoginError::InvalidCredentials
+            );
+        }
+        // the address is now blocked, even with the right password for an untouched account
+        assert_eq!(
+            attempt(sprayer, "erin", "password").await.unwrap_err(),
+            LoginError::TooManyAttempts
+        );
+
+        // while no account got anywhere near its own lock
+        for name in names {
+            let user = store
+                .get_user_by_username(&Username(name.to_string()))
+                .await
+                .unwrap();
+            assert!(!user.is_locked(now), "{name}");
+            assert!(user.failed_logins <= 1, "{name}");
+        }
+        // and other addresses sign in as usual
+        let elsewhere = IpAddr::from([198, 51, 100, 1]);
+        assert!(attempt(elsewhere, "alice", "password").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn exported_projects_import_into_a_fresh_project() {
+        use crate::storage::ProjectStore;
+        use crate::storage::redb_projectstore::RedbProjectStore;
+        use crate::types::{ProjectVisibility, UserId};
+        use uuid::Uuid;
+
+        let store = RedbProjectStore::in_memory().unwrap();
+        let owner = UserId(Uuid::now_v7());
+        let (original, _) = store
+            .create_project(
+                &owner,
+                "Lunch orders".to_string(),
+                Some("Who wants what".to_string()),
+                None,
+            )
+            .await
+            .unwrap();
+        let original = store
+            .update_project(
+                &original.id,
+                None,
+                None,
+                Some(Some(30)),
+                Some(ProjectVisibility::Unlisted),
+            )
+            .await
+            .unwrap();
+
+        let stranger = UserId(Uuid::now_v7());
+        let refused = project_archive(
+            &store,
+            &stranger,
+            &original.id,
+            time::OffsetDateTime::now_utc(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(refused.kind(), crate::types::AppErrorKind::Forbidden);
+
+        let (_, archive) = project_archive(
+            &store,
+            &owner,
+            &original.id,
+            time::OffsetDateTime::now_utc(),
+        )
+        .await
+        .unwrap();
+        let imported = import_archive(&store, &stranger, &archive).await.unwrap();
+
+        assert_ne!(imported.id, original.id);
+        assert_ne!(imported.slug, original.slug);
+        assert_eq!(imported.owner_id, stranger);
+        assert_eq!(imported.name, original.name);
+        assert_eq!(imported.description, original.description);
+        assert_eq!(imported.rate_limit_rpm, Some(30));
+        assert_eq!(imported.visibility, ProjectVisibility::Unlisted);
+
+        let garbage = import_archive(&store, &stranger, b"not an archive")
+            .await
+            .unwrap_err();
+        assert_eq!(garbage.kind(), crate::types::AppErrorKind::Validation);
+    }
+
+    /// A project store whose `set_delete_protection` always fails
+    struct RefusesProtection(crate::storage::redb_projectstore::RedbProjectStore);
+
+    impl crate::storage::ProjectStore for RefusesProtection {
+        async fn create_project(
+            &self,
+            owner_id: &UserId,
+            name: String,
+            description: Option<String>,
+            idempotency_key: Option<String>,
+        ) -> Result<(Project, bool), crate::storage::ProjectError> {
+            self.0
+                .create_project(owner_id, name, description, idempotency_key)
+                .await
+        }
+
+        async fn get_project(
+            &self,
+            project_id: &crate::types::ProjectId,
+        ) -> Result<Project, crate::storage::ProjectError> {
+            self.0.get_project(project_id).await
+        }
+
+        async fn get_by_slug(&self, slug: &str) -> Result<Project, crate::storage::ProjectError> {
+            self.0.get_by_slug(slug).await
+        }
+
+        async fn get_projects(
+            &self,
+            project_ids: &[crate::types::ProjectId],
+        ) -> Result<Vec<Project>, crate::storage::ProjectError> {
+            self.0.get_projects(project_ids).await
+        }
+
+        async fn get_user_projects(
+            &self,
+            owner_id: &UserId,
+        ) -> Result<Vec<ProjectSummary>, crate::storage::ProjectError> {
+            self.0.get_user_projects(owner_id).await
+        }
+
+        async fn get_user_projects_page(
+            &self,
+            owner_id: &UserId,
+            after: Option<crate::types::ProjectId>,
+            limit: usize,
+        ) -> Result<ProjectPage, crate::storage::ProjectError> {
+            self.0.get_user_projects_page(owner_id, after, limit).await
+        }
+
+        async fn search_projects(
+            &self,
+            owner_id: &UserId,
+            query: &str,
+            limit: usize,
+        ) -> Result<Vec<ProjectSummary>, crate::storage::ProjectError> {
+            self.0.search_projects(owner_id, query, limit).await
+        }
+
+        async fn update_project(
+            &self,
+            project_id: &crate::types::ProjectId,
+            name: Option<String>,
+            description: Option<Option<String>>,
+            rate_limit_rpm: Option<Option<u32>>,
+            visibility: Option<ProjectVisibility>,
+        ) -> Result<Project, crate::storage::ProjectError> {
+            self.0
+                .update_project(project_id, name, description, rate_limit_rpm, visibility)
+                .await
+        }
+
+        async fn set_delete_protection(
+            &self,
+            _project_id: &crate::types::ProjectId,
+            _protected: bool,
+        ) -> Result<Project, crate::storage::ProjectError> {
+            Err(crate::storage::ProjectError::Internal(
+                "disk gone".to_string(),
+            ))
+        }
+
+        async fn delete_project(
+            &self,
+            project_id: &crate::types::ProjectId,
+        ) -> Result<(), crate::storage::ProjectError> {
+            self.0.delete_project(project_id).await
+        }
+
+        async fn preview_delete_project(
+            &self,
+            project_id: &crate::types::ProjectId,
+        ) -> Result<Project, crate::storage::ProjectError> {
+            self.0.preview_delete_project(project_id).await
+        }
+
+        async fn clone_project(
+            &self,
+            source: &crate::types::ProjectId,
+            new_owner: &UserId,
+        ) -> Result<Project, crate::storage::ProjectError> {
+            self.0.clone_project(source, new_owner).await
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_imports_leave_no_project_behind() {
+        use crate::project_archive::ProjectArchive;
+        use crate::storage::ProjectStore;
+        use crate::storage::redb_projectstore::RedbProjectStore;
+        use uuid::Uuid;
+
+        let store = RefusesProtection(RedbProjectStore::in_memory().unwrap());
+        let owner = UserId(Uuid::now_v7());
+        let (original, _) = store
+            .0
+            .create_project(&owner, "Lunch orders".to_string(), None, None)
+            .await
+            .unwrap();
+        let mut archive = ProjectArchive::of(&original, time::OffsetDateTime::now_utc());
+        archive.settings.delete_protected = true;
+
+        let importer = UserId(Uuid::now_v7());
+        let err = import_archive(&store, &importer, &archive.to_zip().unwrap())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), crate::types::AppErrorKind::General);
+        assert!(store.get_user_projects(&importer).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,229 @@
+use leptos::prelude::*;
+
+use crate::types::{AppErrorKind, Username};
+use crate::webui::components::EscapeListener;
+use crate::webui::csrf::CsrfContext;
+use crate::webui::{change_username, reauthenticate};
+
+/// Dialog for changing the signed-in user's username, reloading the page once it is saved.
+#[component]
+pub fn ChangeUsernameModal(
+    #[prop(into)] open: Signal<bool>,
+    current: String,
+    #[prop(into)] on_close: Callback<()>,
+) -> impl IntoView {
+    let csrf = expect_context::<CsrfContext>();
+    let (new_username, set_new_username) = signal(current.clone());
+
+    let rename_action = Action::new(move |new_username: &String| {
+        let new_username = new_username.clone();
+        let csrf_token = csrf.token();
+        async move { change_username(new_username, csrf_token).await }
+    });
+    let pending = rename_action.pending();
+    let (show_reauth, set_show_reauth) = signal(false);
+
+    // the navbar and dashboard show the username, so reload to pick up the new one
+    Effect::watch(
+        move || rename_action.value().get(),
+        move |result, _, _| match result {
+            Some(Ok(_)) => {
+                let _ = window().location().reload();
+            }
+            Some(Err(e)) if e.kind() == AppErrorKind::ReauthRequired => set_show_reauth.set(true),
+            _ => {}
+        },
+        false,
+    );
+    let error = move || {
+        rename_action
+            .value()
+            .get()
+            .and_then(Result::err)
+            .filter(|e| e.kind() != AppErrorKind::ReauthRequired)
+            .map(|e| e.to_string())
+    };
+    let unchanged = {
+        let current = current.clone();
+        move || new_username.get() == current
+    };
+    let length_hint = format!("{}-{} characters", Username::MIN_LEN, Username::MAX_LEN);
+
+    let close = move || {
+        if !pending.get_untracked() {
+            set_new_username.set(current.clone());
+            on_close.run(());
+        }
+    };
+    let close = StoredValue::new(close);
+
+    view! {
+        <Show when=move || open.get()>
+            <EscapeListener on_escape=Callback::new(move |_| close.with_value(|close| close())) />
+            <div
+                class="fixed inset-0 z-50 bg-black/60 flex items-center justify-center p-6"
+                on:click=move |_| close.with_value(|close| close())
+            >
+                <form
+                    role="dialog"
+                    aria-modal="true"
+                    class="w-full max-w-sm bg-[#1e1f25] border border-gray-700/60 rounded-2xl p-6 shadow-xl shadow-black/40 space-y-4"
+                    on:click=|ev| ev.stop_propagation()
+                    on:submit=move |ev| {
+                        ev.prevent_default();
+                        rename_action.dispatch(new_username.get());
+                    }
+                >
+                    <p class="text-gray-200 text-sm font-medium">"Change username"</p>
+
+                    <div>
+                        <input
+                            type="text"
+                            required
+                            minlength=Username::MIN_LEN.to_string()
+                            maxlength=Username::MAX_LEN.to_string()
+                            class="w-full bg-[#252630] border border-gray-700 rounded-lg px-3 py-2 text-white text-sm focus:outline-none focus:border-orange-500 transition"
+                            prop:value=move || new_username.get()
+                            on:input=move |ev| set_new_username.set(event_target_value(&ev))
+                        />
+                        <p class="text-gray-500 text-xs mt-1">
+                            {length_hint.clone()}" of letters, digits, '_', '-' or '.'"
+                        </p>
+                        <Show when=move || error().is_some()>
+                            <p class="text-red-400 text-xs mt-1">{move || error().unwrap_or_default()}</p>
+                        </Show>
+                    </div>
+
+                    <div class="flex gap-2">
+                        <button
+                            type="button"
+                            class="flex-1 bg-gray-700 hover:bg-gray-600 text-white text-sm font-medium py-2 px-4 rounded-lg transition"
+                            disabled=move || pending.get()
+                            on:click=move |_| close.with_value(|close| close())
+                        >
+                            "Cancel"
+                        </button>
+                        <button
+                            type="submit"
+                            class="flex-1 bg-[var(--bento-primary)] hover:brightness-110 text-white text-sm font-semibold py-2 px-4 rounded-lg transition disabled:opacity-50"
+                            disabled={
+                                let unchanged = unchanged.clone();
+                                move || pending.get() || unchanged()
+                            }
+                        >
+                            {move || if pending.get() { "Saving..." } else { "Save" }}
+                        </button>
+                    </div>
+                </form>
+            </div>
+        </Show>
+
+        <ReauthModal
+            open=show_reauth
+            on_success=move || {
+                set_show_reauth.set(false);
+                rename_action.dispatch(new_username.get_untracked());
+            }
+            on_cancel=move || set_show_reauth.set(false)
+        />
+    }
+}
+
+/// Asks for the password again when a sensitive action was refused with
+/// `ReauthRequired`; `on_success` can then retry the action.
+#[component]
+pub fn ReauthModal(
+    #[prop(into)] open: Signal<bool>,
+    #[prop(into)] on_success: Callback<()>,
+    #[prop(into)] on_cancel: Callback<()>,
+) -> impl IntoView {
+    let csrf = expect_context::<CsrfContext>();
+    let (password, set_password) = signal(String::new());
+
+    let reauth_action = Action::new(move |password: &String| {
+        let password = password.clone();
+        let csrf_token = csrf.token();
+        async move { reauthenticate(password, csrf_token).await }
+    });
+    let pending = reauth_action.pending();
+    Effect::watch(
+        move || reauth_action.value().get(),
+        move |result, _, _| {
+            if matches!(result.as_ref(), Some(Ok(()))) {
+                set_password.set(String::new());
+                on_success.run(());
+            }
+        },
+        false,
+    );
+    let error = move || {
+        reauth_action
+            .value()
+            .get()
+            .and_then(Result::err)
+            .map(|e| e.to_string())
+    };
+
+    let close = move || {
+        if !pending.get_untracked() {
+            set_password.set(String::new());
+            on_cancel.run(());
+        }
+    };
+
+    view! {
+        <Show when=move || open.get()>
+            <EscapeListener on_escape=Callback::new(move |_| close()) />
+            <div
+                class="fixed inset-0 z-50 bg-black/60 flex items-center justify-center p-6"
+                on:click=move |_| close()
+            >
+                <form
+                    role="dialog"
+                    aria-modal="true"
+                    class="w-full max-w-sm bg-[#1e1f25] border border-gray-700/60 rounded-2xl p-6 shadow-xl shadow-black/40 space-y-4"
+                    on:click=|ev| ev.stop_propagation()
+                    on:submit=move |ev| {
+                        ev.prevent_default();
+                        reauth_action.dispatch(password.get());
+                    }
+                >
+                    <p class="text-gray-200 text-sm font-medium">"Confirm your password"</p>
+                    <p class="text-gray-400 text-xs">"For your security, enter your password again to continue."</p>
+
+                    <div>
+                        <input
+                            type="password"
+                            required
+                            autocomplete="current-password"
+                            class="w-full bg-[#252630] border border-gray-700 rounded-lg px-3 py-2 text-white text-sm focus:outline-none focus:border-orange-500 transition"
+                            prop:value=move || password.get()
+                            on:input=move |ev| set_password.set(event_target_value(&ev))
+                        />
+                        <Show when=move || error().is_some()>
+                            <p class="text-red-400 text-xs mt-1">{move || error().unwrap_or_default()}</p>
+                        </Show>
+                    </div>
+
+                    <div class="flex gap-2">
+                        <button
+                            type="button"
+                            class="flex-1 bg-gray-700 hover:bg-gray-600 text-white text-sm font-medium py-2 px-4 rounded-lg transition"
+                            disabled=move || pending.get()
+                            on:click=move |_| close()
+                        >
+                            "Cancel"
+                        </button>
+                        <button
+                            type="submit"
+                            class="flex-1 bg-[var(--bento-primary)] hover:brightness-110 text-white text-sm font-semibold py-2 px-4 rounded-lg transition disabled:opacity-50"
+                            disabled=move || pending.get()
+                        >
+                            {move || if pending.get() { "Checking..." } else { "Continue" }}
+                        </button>
+                    </div>
+                </form>
+            </div>
+        </Show>
+    }
+}
@@ -0,0 +1,225 @@
+//! Live audit events for admins, as server-sent events
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    Extension,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use futures_util::stream::{self, Stream};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::storage::{AuditStore, AuthStore};
+use crate::types::{AuditEvent, Role, SessionId};
+use crate::webui::CurrentUser;
+use crate::webui::cookies::session_id_from_headers;
+use crate::webui::middleware::load_current_user;
+
+/// How often an open stream re-checks the admin's session while no events arrive
+const SESSION_RECHECK: Duration = Duration::from_secs(15);
+
+/// `GET /admin/events`, every audit event from now on as an `audit` server-sent event
+/// carrying the event as JSON.
+///
+/// Needs an admin session cookie (resolved by `resolve_current_user`). The stream ends
+/// once that session is revoked or expires, or the account stops being an admin.
+///
+/// # Example
+/// ```ignore
+/// let live = Router::new()
+///     .route(audit_log::ADMIN_EVENTS_PATH, get(admin_events::<RedbAuthStore, RedbAuditStore>))
+///     .with_state((auth_store, audit_store));
+/// ```
+pub async fn admin_events<A, S>(
+    State((auth_store, audit_store)): State<(Arc<A>, Arc<S>)>,
+    Extension(current_user): Extension<Option<CurrentUser>>,
+    headers: HeaderMap,
+) -> Response
+where
+    A: AuthStore + ?Sized + 'static,
+    S: AuditStore + ?Sized + 'static,
+{
+    match current_user {
+        Some(user) if user.role == Role::Admin => {}
+        Some(_) => return StatusCode::FORBIDDEN.into_response(),
+        None => return StatusCode::UNAUTHORIZED.into_response(),
+    }
+    let Some(session_id) = session_id_from_headers(&headers) else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+
+    let events = audit_event_stream(auth_store, session_id, audit_store.subscribe());
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// The subscribed events, for as long as `session_id` belongs to an admin
+fn audit_event_stream<A: AuthStore + ?Sized + 'static>(
+    auth_store: Arc<A>,
+    session_id: SessionId,
+    events: broadcast::Receiver<AuditEvent>,
+) -> impl Stream<Item = Result<Event, axum::Error>> + Send + 'static {
+    let recheck = tokio::time::interval(SESSION_RECHECK);
+
+    stream::unfold(
+        (auth_store, session_id, events, recheck),
+        |(auth_store, session_id, mut events, mut recheck)| async move {
+            loop {
+                let received = tokio::select! {
+                    received = events.recv() => Some(received),
+                    _ = recheck.tick() => None,
+                };
+
+                // checked before every event, so a revoked session doesn't see another one
+                let current = load_current_user(auth_store.as_ref(), &session_id, None).await;
+                if !current.is_some_and(|user| user.role == Role::Admin) {
+                    return None;
+                }
+
+                match received {
+                    Some(Ok(event)) => {
+                        let sse = Event::default().event("audit").json_data(&event);
+                        return Some((sse, (auth_store, session_id, events, recheck)));
+                    }
+                    Some(Err(RecvError::Lagged(missed))) => {
+                        tracing::warn!(missed, "Live audit stream fell behind, events skipped");
+                    }
+                    Some(Err(RecvError::Closed)) => return None,
+                    None => {}
+                }
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::mem_authstore::MemoryAuthStore;
+    use crate::storage::redb_auditstore::RedbAuditStore;
+    use crate::types::{AuditKind, PasswordHash, SessionIp, Username};
+    use crate::webui::audit_log::ADMIN_EVENTS_PATH;
+    use crate::webui::cookies::{SESSION_COOKIE_NAME, seal_session_id};
+    use crate::webui::middleware::resolve_current_user;
+    use axum::{
+        Router,
+        body::{Body, BodyDataStream},
+        http::{Request, header::COOKIE},
+        middleware::from_fn_with_state,
+        routing::get,
+    };
+    use futures_util::StreamExt;
+    use std::net::IpAddr;
+    use tower::ServiceExt;
+
+    struct Fixture {
+        app: Router,
+        auth: Arc<MemoryAuthStore>,
+        audit: Arc<RedbAuditStore>,
+    }
+
+    fn fixture() -> Fixture {
+        let auth = Arc::new(MemoryAuthStore::default());
+        let audit = Arc::new(RedbAuditStore::in_memory().unwrap());
+        let app = Router::new()
+            .route(
+                ADMIN_EVENTS_PATH,
+                get(admin_events::<MemoryAuthStore, RedbAuditStore>),
+            )
+            .with_state((auth.clone(), audit.clone()))
+            .layer(from_fn_with_state(
+                (auth.clone(), false),
+                resolve_current_user::<MemoryAuthStore>,
+            ));
+        Fixture { app, auth, audit }
+    }
+
+    async fn sign_in(auth: &MemoryAuthStore, username: &str, admin: bool) -> SessionId {
+        let username = Username(username.to_string());
+        let password = PasswordHash::try_from("password").unwrap();
+        let user = if admin {
+            auth.create_admin(&username, password).await.unwrap()
+        } else {
+            auth.create_standard_user(&username, password)
+                .await
+                .unwrap()
+        };
+        auth.issue_session(&user.id, SessionIp(IpAddr::from([127, 0, 0, 1])), None)
+            .await
+            .unwrap()
+            .id
+    }
+
+    async fn subscribe(app: &Router, session: Option<&SessionId>) -> Response {
+        let mut request = Request::get(ADMIN_EVENTS_PATH);
+        if let Some(session) = session {
+            request = request.header(
+                COOKIE,
+                format!(
+                    "{SESSION_COOKIE_NAME}={}",
+                    seal_session_id(session.as_str())
+                ),
+            );
+        }
+        app.clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    /// The next chunk of the stream, or `None` once it ended
+    async fn next_chunk(body: &mut BodyDataStream) -> Option<String> {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), body.next())
+            .await
+            .expect("the stream to yield or end in time")?;
+        Some(String::from_utf8(chunk.unwrap().to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn admins_receive_events_recorded_after_subscribing() {
+        let Fixture { app, auth, audit } = fixture();
+        let session = sign_in(&auth, "admin", true).await;
+
+        let response = subscribe(&app, Some(&session)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut body = response.into_body().into_data_stream();
+
+        audit
+            .record(AuditEvent::new(None, AuditKind::Login, "bob"))
+            .await
+            .unwrap();
+        let chunk = next_chunk(&mut body).await.unwrap();
+        assert!(chunk.starts_with("event: audit\n"), "{chunk}");
+        assert!(chunk.contains(r#""detail":"bob""#), "{chunk}");
+
+        // revoking the admin's session ends the stream
+        auth.revoke_session(&session).await.unwrap();
+        audit
+            .record(AuditEvent::new(None, AuditKind::Logout, "admin"))
+            .await
+            .unwrap();
+        assert_eq!(next_chunk(&mut body).await, None);
+    }
+
+    #[tokio::test]
+    async fn non_admins_are_rejected() {
+        let Fixture { app, auth, .. } = fixture();
+        let session = sign_in(&auth, "alice", false).await;
+
+        assert_eq!(
+            subscribe(&app, Some(&session)).await.status(),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            subscribe(&app, None).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
@@ -0,0 +1,295 @@
+use leptos::prelude::*;
+use time::{OffsetDateTime, PrimitiveDateTime, format_description};
+use uuid::Uuid;
+
+use crate::types::{AuditEvent, AuditKind, AuditQuery, UserId};
+use crate::webui::list_audit_events;
+
+/// Number of events shown unless the admin asks for more
+const DEFAULT_LIMIT: usize = 100;
+
+/// Where admins subscribe to audit events as they are recorded
+pub const ADMIN_EVENTS_PATH: &str = "/admin/events";
+
+/// Number of live events kept on screen
+#[cfg(feature = "hydrate")]
+const LIVE_LIMIT: usize = 50;
+
+/// Audit log viewer with actor, kind and time range filters (admin only).
+#[component]
+pub fn AuditLogPanel() -> impl IntoView {
+    let (query, set_query) = signal(AuditQuery {
+        limit: DEFAULT_LIMIT,
+        ..Default::default()
+    });
+    let events = Resource::new(move || query.get(), list_audit_events);
+
+    // Filter form state
+    let (actor, set_actor) = signal(String::new());
+    let kinds = RwSignal::new(Vec::<AuditKind>::new());
+    let (since, set_since) = signal(String::new());
+    let (until, set_until) = signal(String::new());
+    let (limit, set_limit) = signal(DEFAULT_LIMIT.to_string());
+    let (form_error, set_form_error) = signal(None::<String>);
+
+    let on_submit = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+        match build_query(
+            &actor.get(),
+            kinds.get(),
+            &since.get(),
+            &until.get(),
+            &limit.get(),
+        ) {
+            Ok(new_query) => {
+                set_form_error.set(None);
+                set_query.set(new_query);
+            }
+            Err(message) => set_form_error.set(Some(message)),
+        }
+    };
+
+    let input_class = "w-full bg-[#252630] border border-gray-700 rounded-lg px-3 py-2 text-white text-sm focus:outline-none focus:border-orange-500 transition";
+
+    view! {
+        <section class="mt-12">
+            <h2 class="text-xl font-bold mb-4 tracking-tight">"Audit Log"</h2>
+
+            <LiveAuditEvents />
+
+            <form
+                class="bg-[#1e1f25] border border-gray-800/60 rounded-2xl p-6 mb-6 space-y-4"
+                on:submit=on_submit
+            >
+                <div class="grid grid-cols-1 md:grid-cols-4 gap-4">
+                    <div>
+                        <label class="block text-sm font-medium text-gray-300 mb-1">"Actor (user ID)"</label>
+                        <input
+                            type="text"
+                            class=input_class
+                            placeholder="Any user"
+                            prop:value=move || actor.get()
+                            on:input=move |ev| set_actor.set(event_target_value(&ev))
+                        />
+                    </div>
+                    <div>
+                        <label class="block text-sm font-medium text-gray-300 mb-1">"Since (UTC)"</label>
+                        <input
+                            type="datetime-local"
+                            class=input_class
+                            prop:value=move || since.get()
+                            on:input=move |ev| set_since.set(event_target_value(&ev))
+                        />
+                    </div>
+                    <div>
+                        <label class="block text-sm font-medium text-gray-300 mb-1">"Until (UTC)"</label>
+                        <input
+                            type="datetime-local"
+                            class=input_class
+                            prop:value=move || until.get()
+                            on:input=move |ev| set_until.set(event_target_value(&ev))
+                        />
+                    </div>
+                    <div>
+                        <label class="block text-sm font-medium text-gray-300 mb-1">"Limit"</label>
+                        <input
+                            type="number"
+                            min="1"
+                            class=input_class
+                            prop:value=move || limit.get()
+                            on:input=move |ev| set_limit.set(event_target_value(&ev))
+                        />
+                    </div>
+                </div>
+
+                <div class="flex flex-wrap gap-x-5 gap-y-2">
+                    {AuditKind::ALL.into_iter().map(|kind| {
+                        view! {
+                            <label class="flex items-center text-sm text-gray-400">
+                                <input
+                                    type="checkbox"
+                                    class="mr-2 accent-orange-500"
+                                    prop:checked=move || kinds.get().contains(&kind)
+                                    on:change=move |ev| {
+                                        let checked = event_target_checked(&ev);
+                                        kinds.update(|kinds| {
+                                            kinds.retain(|k| *k != kind);
+                                            if checked {
+                                                kinds.push(kind);
+                                            }
+                                        });
+                                    }
+                                />
+                                {kind.label()}
+                            </label>
+                        }
+                    }).collect_view()}
+                </div>
+
+                <div class="flex items-center gap-4">
+                    <button
+                        type="submit"
+                        class="bg-[var(--bento-primary)] hover:brightness-110 text-white text-sm font-semibold py-2 px-6 rounded-lg transition"
+                    >
+                        "Apply Filters"
+                    </button>
+                    <Show when=move || form_error.get().is_some()>
+                        <span class="text-sm text-red-400">{move || form_error.get().unwrap_or_default()}</span>
+                    </Show>
+                </div>
+            </form>
+
+            <Suspense fallback=|| view! { <p class="text-gray-500 text-sm">"Loading events..."</p> }>
+                {move || {
+                    events.get().map(|result| match result {
+                        Ok(events) if events.is_empty() => view! {
+                            <p class="text-gray-500 text-sm">"No events match these filters."</p>
+                        }.into_any(),
+                        Ok(events) => view! {
+                            <div class="bg-[#1e1f25] border border-gray-800/60 rounded-2xl overflow-hidden">
+                                <table class="w-full text-sm text-left">
+                                    <thead class="text-gray-500 border-b border-gray-800/60">
+                                        <tr>
+                                            <th class="px-4 py-3 font-medium">"Time (UTC)"</th>
+                                            <th class="px-4 py-3 font-medium">"Event"</th>
+                                            <th class="px-4 py-3 font-medium">"Actor"</th>
+                                            <th class="px-4 py-3 font-medium">"Detail"</th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {events.into_iter().map(|event| view! { <AuditRow event=event /> }).collect_view()}
+                                    </tbody>
+                                </table>
+                            </div>
+                        }.into_any(),
+                        Err(e) => view! {
+                            <div class="bg-red-900/20 border border-red-800 rounded-2xl p-6 text-red-400">
+                                <p class="font-medium">"Failed to load audit events"</p>
+                                <p class="text-sm mt-1">{e.to_string()}</p>
+                            </div>
+                        }.into_any(),
+                    })
+                }}
+            </Suspense>
+        </section>
+    }
+}
+
+/// Events recorded while the dashboard is open, newest first
+#[component]
+fn LiveAuditEvents() -> impl IntoView {
+    let live = RwSignal::new(Vec::<AuditEvent>::new());
+
+    // the browser only; the stream ends by itself once the admin's session is revoked
+    #[cfg(feature = "hydrate")]
+    Effect::new(move |_| {
+        use wasm_bindgen::{JsCast, closure::Closure};
+        use web_sys::{EventSource, MessageEvent};
+
+        let Ok(source) = EventSource::new(ADMIN_EVENTS_PATH) else {
+            return;
+        };
+        let on_audit = Closure::<dyn FnMut(MessageEvent)>::new(move |message: MessageEvent| {
+            let Some(data) = message.data().as_string() else {
+                return;
+            };
+            if let Ok(event) = serde_json::from_str::<AuditEvent>(&data) {
+                live.update(|events| {
+                    events.insert(0, event);
+                    events.truncate(LIVE_LIMIT);
+                });
+            }
+        });
+        let _ = source.add_event_listener_with_callback("audit", on_audit.as_ref().unchecked_ref());
+
+        let subscription = send_wrapper::SendWrapper::new((source, on_audit));
+        on_cleanup(move || subscription.0.close());
+    });
+
+    view! {
+        <div class="bg-[#1e1f25] border border-gray-800/60 rounded-2xl overflow-hidden mb-6">
+            <p class="px-4 py-3 text-sm font-medium text-gray-300 border-b border-gray-800/60">"Live"</p>
+            <Show
+                when=move || !live.get().is_empty()
+                fallback=|| view! { <p class="px-4 py-3 text-gray-500 text-sm">"Waiting for new events..."</p> }
+            >
+                <table class="w-full text-sm text-left">
+                    <tbody>
+                        {move || live.get().into_iter().map(|event| view! { <AuditRow event=event /> }).collect_view()}
+                    </tbody>
+                </table>
+            </Show>
+        </div>
+    }
+}
+
+#[component]
+fn AuditRow(event: AuditEvent) -> impl IntoView {
+    let at = event.at;
+    let time_str = format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        at.year(),
+        at.month() as u8,
+        at.day(),
+        at.hour(),
+        at.minute(),
+        at.second()
+    );
+    let actor = event
+        .actor
+        .map(|actor| actor.0.to_string())
+        .unwrap_or_else(|| "-".to_string());
+
+    view! {
+        <tr class="border-b border-gray-800/40 last:border-0">
+            <td class="px-4 py-2 text-gray-400 whitespace-nowrap">{time_str}</td>
+            <td class="px-4 py-2 text-gray-200">{event.kind.label()}</td>
+            <td class="px-4 py-2 text-gray-500 font-mono text-xs">{actor}</td>
+            <td class="px-4 py-2 text-gray-400">{event.detail}</td>
+        </tr>
+    }
+}
+
+/// Builds a query from the raw filter form values
+fn build_query(
+    actor: &str,
+    kinds: Vec<AuditKind>,
+    since: &str,
+    until: &str,
+    limit: &str,
+) -> Result<AuditQuery, String> {
+    let actor = match actor.trim() {
+        "" => None,
+        id => Some(UserId(
+            Uuid::parse_str(id).map_err(|_| "Actor must be a user ID".to_string())?,
+        )),
+    };
+    let limit = match limit.trim() {
+        "" => DEFAULT_LIMIT,
+        n => n
+            .parse()
+            .map_err(|_| "Limit must be a positive number".to_string())?,
+    };
+
+    Ok(AuditQuery {
+        actor,
+        kinds,
+        since: parse_datetime(since)?,
+        until: parse_datetime(until)?,
+        limit,
+        ..Default::default()
+    })
+}
+
+/// Parses a `datetime-local` input value (e.g., "2025-01-31T13:45") as UTC
+fn parse_datetime(value: &str) -> Result<Option<OffsetDateTime>, String> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    let format = format_description::parse("[year]-[month]-[day]T[hour]:[minute]")
+        .expect("valid format description");
+    PrimitiveDateTime::parse(value, &format)
+        .map(|datetime| Some(datetime.assume_utc()))
+        .map_err(|_| format!("Invalid date: {value}"))
+}
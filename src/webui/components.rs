@@ -0,0 +1,308 @@
+//! Small UI building blocks shared between screens.
+
+use leptos::prelude::*;
+
+/// Confirmation dialog shown over the whole page while `open` is true.
+///
+/// Cancelling (the cancel button, Escape, or a click on the backdrop) runs `on_cancel`; none
+/// of these work while `pending` is true, so an in-flight action can't be dismissed halfway.
+///
+/// # Example
+/// ```ignore
+/// <ConfirmModal
+///     open=show_confirm
+///     title="Delete this project?"
+///     body="This action cannot be undone."
+///     confirm_label="Delete"
+///     pending_label="Deleting..."
+///     pending=delete_action.pending()
+///     danger=true
+///     on_confirm=move || { delete_action.dispatch(id.clone()); }
+///     on_cancel=move || set_show_confirm.set(false)
+/// />
+/// ```
+#[component]
+pub fn ConfirmModal(
+    #[prop(into)] open: Signal<bool>,
+    #[prop(into)] title: String,
+    #[prop(into)] body: String,
+    #[prop(into, default = "Confirm".to_string())] confirm_label: String,
+    /// Confirm button text while `pending`
+    #[prop(into, default = "Working...".to_string())]
+    pending_label: String,
+    #[prop(into, optional)] pending: Signal<bool>,
+    /// Style the confirm button as a destructive action
+    #[prop(optional)]
+    danger: bool,
+    #[prop(into)] on_confirm: Callback<()>,
+    #[prop(into)] on_cancel: Callback<()>,
+) -> impl IntoView {
+    let cancel = move || {
+        if !pending.get_untracked() {
+            on_cancel.run(());
+        }
+    };
+    let confirm_class = if danger {
+        "flex-1 bg-red-600 hover:bg-red-500 text-white text-sm font-semibold py-2 px-4 rounded-lg transition disabled:opacity-50"
+    } else {
+        "flex-1 bg-[var(--bento-primary)] hover:brightness-110 text-white text-sm font-semibold py-2 px-4 rounded-lg transition disabled:opacity-50"
+    };
+
+    view! {
+        <Show when=move || open.get()>
+            <EscapeListener on_escape=Callback::new(move |_| cancel()) />
+            // Backdrop, closes the dialog when clicked
+            <div
+                class="fixed inset-0 z-50 bg-black/60 flex items-center justify-center p-6"
+                on:click=move |_| cancel()
+            >
+                <div
+                    role="dialog"
+                    aria-modal="true"
+                    class="w-full max-w-sm bg-[#1e1f25] border border-gray-700/60 rounded-2xl p-6 shadow-xl shadow-black/40 flex flex-col items-center text-center"
+                    on:click=|ev| ev.stop_propagation()
+                >
+                    <p class="text-gray-200 text-sm font-medium mb-1">{title.clone()}</p>
+                    <p class="text-gray-500 text-xs mb-4">{body.clone()}</p>
+
+                    <div class="flex gap-2 w-full">
+                        <button
+                            type="button"
+                            class="flex-1 bg-gray-700 hover:bg-gray-600 text-white text-sm font-medium py-2 px-4 rounded-lg transition"
+                            disabled=move || pending.get()
+                            on:click=move |_| cancel()
+                        >
+                            "Cancel"
+                        </button>
+                        <button
+                            type="button"
+                            class=confirm_class
+                            disabled=move || pending.get()
+                            on:click=move |_| on_confirm.run(())
+                        >
+                            {
+                                let confirm_label = confirm_label.clone();
+                                let pending_label = pending_label.clone();
+                                move || {
+                                    if pending.get() {
+                                        pending_label.clone()
+                                    } else {
+                                        confirm_label.clone()
+                                    }
+                                }
+                            }
+                        </button>
+                    </div>
+                </div>
+            </div>
+        </Show>
+    }
+}
+
+/// How long a [Toast] stays up
+const TOAST_DURATION: std::time::Duration = std::time::Duration::from_secs(6);
+
+/// Short notice in the bottom corner of the page that dismisses itself after a few seconds
+#[component]
+pub fn Toast(#[prop(into)] message: String) -> impl IntoView {
+    let (visible, set_visible) = signal(true);
+    // effects only run in the browser, so no timer is started while rendering on the server
+    Effect::new(move |_| {
+        set_timeout(move || set_visible.set(false), TOAST_DURATION);
+    });
+
+    view! {
+        <Show when=move || visible.get()>
+            <div
+                role="status"
+                class="fixed bottom-6 right-6 z-50 flex items-center gap-4 bg-[#1f2029] border border-gray-700/50 rounded-xl shadow-xl shadow-black/30 px-4 py-3 text-sm text-gray-200"
+            >
+                <span>{message.clone()}</span>
+                <button
+                    type="button"
+                    aria-label="Dismiss"
+                    class="text-gray-500 hover:text-white transition"
+                    on:click=move |_| set_visible.set(false)
+                >
+                    "\u{00d7}"
+                </button>
+            </div>
+        </Show>
+    }
+}
+
+/// What a toast reports, which picks its accent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToastKind {
+    Success,
+    Error,
+    Info,
+}
+
+/// A toast waiting in a [ToastQueue]
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueuedToast {
+    pub id: u64,
+    pub kind: ToastKind,
+    pub message: String,
+}
+
+/// Toasts currently shown, oldest first
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ToastQueue {
+    toasts: Vec<QueuedToast>,
+    next_id: u64,
+}
+
+impl ToastQueue {
+    /// Most toasts shown at once; pushing another drops the oldest
+    pub const MAX_SHOWN: usize = 4;
+
+    /// Queues a toast and returns its id
+    pub fn push(&mut self, kind: ToastKind, message: String) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.toasts.push(QueuedToast { id, kind, message });
+        if self.toasts.len() > Self::MAX_SHOWN {
+            self.toasts.remove(0);
+        }
+        id
+    }
+
+    /// Removes a toast; ids that are already gone are ignored
+    pub fn dismiss(&mut self, id: u64) {
+        self.toasts.retain(|toast| toast.id != id);
+    }
+
+    pub fn toasts(&self) -> &[QueuedToast] {
+        &self.toasts
+    }
+}
+
+/// Page-wide toasts, provided by `App` and shown by its [ToastHost]
+///
+/// # Example
+/// ```ignore
+/// let toasts = expect_context::<ToastContext>();
+/// toasts.push_toast(ToastKind::Success, "Project created");
+/// ```
+#[derive(Clone, Copy)]
+pub struct ToastContext(RwSignal<ToastQueue>);
+
+impl ToastContext {
+    pub fn provide() {
+        provide_context(Self(RwSignal::new(ToastQueue::default())));
+    }
+
+    pub fn push_toast(&self, kind: ToastKind, message: impl Into<String>) {
+        let message = message.into();
+        self.0.update(|queue| {
+            queue.push(kind, message);
+        });
+    }
+
+    fn dismiss(&self, id: u64) {
+        self.0.update(|queue| queue.dismiss(id));
+    }
+}
+
+/// Stack of the [ToastContext]'s toasts in the bottom corner of the page
+#[component]
+pub fn ToastHost() -> impl IntoView {
+    let context = expect_context::<ToastContext>();
+
+    view! {
+        <div aria-live="polite" class="fixed bottom-6 right-6 z-50 flex flex-col items-end gap-2">
+            <For
+                each=move || context.0.with(|queue| queue.toasts().to_vec())
+                key=|toast| toast.id
+                let:toast
+            >
+                <HostedToast toast=toast context=context />
+            </For>
+        </div>
+    }
+}
+
+#[component]
+fn HostedToast(toast: QueuedToast, context: ToastContext) -> impl IntoView {
+    let id = toast.id;
+    // toasts are only pushed in the browser, so this timer never starts on the server
+    Effect::new(move |_| {
+        set_timeout(move || context.dismiss(id), TOAST_DURATION);
+    });
+    let accent = match toast.kind {
+        ToastKind::Success => "border-green-700/60",
+        ToastKind::Error => "border-red-800/70 text-red-300",
+        ToastKind::Info => "border-gray-700/50",
+    };
+
+    view! {
+        <div
+            role=if toast.kind == ToastKind::Error { "alert" } else { "status" }
+            class=format!("flex items-center gap-4 bg-[#1f2029] border rounded-xl shadow-xl shadow-black/30 px-4 py-3 text-sm text-gray-200 {accent}")
+        >
+            <span>{toast.message}</span>
+            <button
+                type="button"
+                aria-label="Dismiss"
+                class="text-gray-500 hover:text-white transition"
+                on:click=move |_| context.dismiss(id)
+            >
+                "\u{00d7}"
+            </button>
+        </div>
+    }
+}
+
+/// Runs `on_escape` when Escape is pressed, for as long as it is mounted
+#[component]
+pub(crate) fn EscapeListener(on_escape: Callback<()>) -> impl IntoView {
+    let handle = window_event_listener(leptos::ev::keydown, move |ev| {
+        if ev.key() == "Escape" {
+            on_escape.run(());
+        }
+    });
+    on_cleanup(move || handle.remove());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(queue: &ToastQueue) -> Vec<&str> {
+        queue
+            .toasts()
+            .iter()
+            .map(|toast| toast.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn toasts_are_dismissed_by_id() {
+        let mut queue = ToastQueue::default();
+        let created = queue.push(ToastKind::Success, "Project created".to_string());
+        let failed = queue.push(ToastKind::Error, "Failed to delete".to_string());
+        assert_ne!(created, failed);
+
+        queue.dismiss(created);
+        assert_eq!(messages(&queue), ["Failed to delete"]);
+        // dismissing twice, e.g. by click and then by timeout, is harmless
+        queue.dismiss(created);
+        assert_eq!(messages(&queue), ["Failed to delete"]);
+    }
+
+    #[test]
+    fn pushing_past_the_limit_drops_the_oldest() {
+        let mut queue = ToastQueue::default();
+        for n in 0..=ToastQueue::MAX_SHOWN {
+            queue.push(ToastKind::Info, n.to_string());
+        }
+
+        assert_eq!(queue.toasts().len(), ToastQueue::MAX_SHOWN);
+        assert_eq!(queue.toasts()[0].message, "1");
+        // ids keep counting, so a dropped toast's timeout can't dismiss a newer one
+        let next = queue.push(ToastKind::Info, "next".to_string());
+        assert_eq!(next, ToastQueue::MAX_SHOWN as u64 + 1);
+    }
+}
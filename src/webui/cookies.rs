@@ -1,23 +1,57 @@
 //! Cookie helper functions for session management
+//!
+//! The session cookie's value is signed or encrypted with the cookie key from
+//! `.bento_secrets` (see `[cookie] session_protection`), so a tampered or forged cookie is
+//! rejected before its session ID reaches the auth store.
 
+use std::sync::OnceLock;
+
+use axum::http::HeaderMap;
 use axum::http::header::{HeaderValue, SET_COOKIE};
-use axum_extra::extract::cookie::{Cookie, SameSite};
+use axum_extra::extract::cookie::{Cookie, Key, PrivateCookieJar, SameSite, SignedCookieJar};
 use leptos_axum::ResponseOptions;
-use time::Duration;
+use time::{Duration, OffsetDateTime};
+
+use crate::config::{CookieProtection, LOCAL_CONF};
+use crate::types::{PreviousLogin, SessionId};
+
+static SESSION_KEY: OnceLock<Key> = OnceLock::new();
 
 /// Cookie name for session identification
 pub const SESSION_COOKIE_NAME: &str = "session_id";
 
+/// Cookie holding the double-submit CSRF token (see `webui::csrf`)
+pub const CSRF_COOKIE_NAME: &str = "csrf";
+
+/// Cookie holding the theme a visitor picked, overriding `[branding] default_theme`
+pub const THEME_COOKIE_NAME: &str = "theme";
+
+/// Cookie carrying the previous login to the dashboard once, right after signing in
+pub const PREVIOUS_LOGIN_COOKIE_NAME: &str = "previous_login";
+
 /// Builds a session cookie with the given value.
 ///
 /// The cookie is configured with:
 /// - `HttpOnly`: true (not accessible via JavaScript)
 /// - `SameSite`: Lax (sent with top-level navigations)
 /// - `Secure`: true in release builds only
-/// - `Path`: "/" (available site-wide)
-fn build_session_cookie(value: &str, max_age: Option<Duration>) -> Cookie<'static> {
-    let builder = Cookie::build((SESSION_COOKIE_NAME, value.to_string()))
-        .path("/")
+/// - `Path`: `path`, the `[cookie] path` setting outside of tests
+///
+/// Browsers only replace a cookie with one of the same name and path, so the cookie that
+/// clears a session has to be built with the same `path` as the one that set it.
+fn build_session_cookie(value: &str, max_age: Option<Duration>, path: &str) -> Cookie<'static> {
+    build_cookie(SESSION_COOKIE_NAME, value, max_age, path)
+}
+
+/// Builds an `HttpOnly`, `SameSite=Lax` cookie; see [build_session_cookie]
+fn build_cookie(
+    name: &'static str,
+    value: &str,
+    max_age: Option<Duration>,
+    path: &str,
+) -> Cookie<'static> {
+    let builder = Cookie::build((name, value.to_string()))
+        .path(path.to_string())
         .http_only(true)
         .same_site(SameSite::Lax);
 
@@ -43,7 +77,7 @@ fn build_session_cookie(value: &str, max_age: Option<Duration>) -> Cookie<'stati
 /// set_session_cookie(&response, &session.id.0);
 /// ```
 pub fn set_session_cookie(response: &ResponseOptions, session_id: &str) {
-    let cookie = build_session_cookie(session_id, None);
+    let cookie = build_session_cookie(&seal_session_id(session_id), None, cookie_path());
 
     if let Ok(header_value) = HeaderValue::from_str(&cookie.to_string()) {
         response.insert_header(SET_COOKIE, header_value);
@@ -58,9 +92,217 @@ pub fn set_session_cookie(response: &ResponseOptions, session_id: &str) {
 /// clear_session_cookie(&response);
 /// ```
 pub fn clear_session_cookie(response: &ResponseOptions) {
-    let cookie = build_session_cookie("", Some(Duration::seconds(0)));
+    let cookie = build_session_cookie("", Some(Duration::seconds(0)), cookie_path());
 
     if let Ok(header_value) = HeaderValue::from_str(&cookie.to_string()) {
         response.insert_header(SET_COOKIE, header_value);
     }
 }
+
+/// Sets the key session cookies are signed or encrypted with, the cookie key from
+/// `.bento_secrets`.
+///
+/// Only the first call has an effect; returns `false` if a key was already installed.
+/// Without one, a random key is generated, which holds for the life of the process.
+pub fn install_session_key(key: Key) -> bool {
+    SESSION_KEY.set(key).is_ok()
+}
+
+fn session_key() -> &'static Key {
+    SESSION_KEY.get_or_init(Key::generate)
+}
+
+/// The session cookie value carrying `session_id`, protected per `[cookie] session_protection`
+pub fn seal_session_id(session_id: &str) -> String {
+    seal(
+        session_id,
+        LOCAL_CONF.cookie.session_protection,
+        session_key(),
+    )
+}
+
+/// The session ID of the request's session cookie, if it has one that verifies against the
+/// session key. Cookies that were tampered with, or sealed another way, count as missing.
+pub fn session_id_from_headers(headers: &HeaderMap) -> Option<SessionId> {
+    open(
+        headers,
+        LOCAL_CONF.cookie.session_protection,
+        session_key().clone(),
+    )
+}
+
+fn seal(value: &str, protection: CookieProtection, key: &Key) -> String {
+    let mut jar = cookie::CookieJar::new();
+    let plain = Cookie::new(SESSION_COOKIE_NAME, value.to_string());
+    match protection {
+        CookieProtection::Signed => jar.signed_mut(key).add(plain),
+        CookieProtection::Encrypted => jar.private_mut(key).add(plain),
+    }
+    jar.get(SESSION_COOKIE_NAME)
+        .map(|sealed| sealed.value().to_string())
+        .unwrap_or_default()
+}
+
+fn open(headers: &HeaderMap, protection: CookieProtection, key: Key) -> Option<SessionId> {
+    let cookie = match protection {
+        CookieProtection::Signed => {
+            SignedCookieJar::from_headers(headers, key).get(SESSION_COOKIE_NAME)
+        }
+        CookieProtection::Encrypted => {
+            PrivateCookieJar::from_headers(headers, key).get(SESSION_COOKIE_NAME)
+        }
+    }?;
+    Some(SessionId(cookie.value().to_string()))
+}
+
+/// Sets the previous login cookie, to be read once by the dashboard.
+pub fn set_previous_login_cookie(response: &ResponseOptions, previous: &PreviousLogin) {
+    let value = format!("{}|{}", previous.at.unix_timestamp(), previous.ip);
+    let cookie = build_cookie(PREVIOUS_LOGIN_COOKIE_NAME, &value, None, cookie_path());
+
+    if let Ok(header_value) = HeaderValue::from_str(&cookie.to_string()) {
+        response.append_header(SET_COOKIE, header_value);
+    }
+}
+
+/// Clears the previous login cookie once it has been shown.
+pub fn clear_previous_login_cookie(response: &ResponseOptions) {
+    let cookie = build_cookie(
+        PREVIOUS_LOGIN_COOKIE_NAME,
+        "",
+        Some(Duration::seconds(0)),
+        cookie_path(),
+    );
+
+    if let Ok(header_value) = HeaderValue::from_str(&cookie.to_string()) {
+        response.append_header(SET_COOKIE, header_value);
+    }
+}
+
+/// Parses a previous login cookie value written by [set_previous_login_cookie]
+pub fn parse_previous_login(value: &str) -> Option<PreviousLogin> {
+    let (at, ip) = value.split_once('|')?;
+    Some(PreviousLogin {
+        at: OffsetDateTime::from_unix_timestamp(at.parse().ok()?).ok()?,
+        ip: ip.parse().ok()?,
+    })
+}
+
+/// Builds the CSRF token cookie; it lives as long as the browser session.
+pub fn build_csrf_cookie(token: &str) -> Cookie<'static> {
+    build_cookie(CSRF_COOKIE_NAME, token, None, cookie_path())
+}
+
+/// The configured `[cookie] path`
+fn cookie_path() -> &'static str {
+    &LOCAL_CONF.cookie.path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Applies a `Set-Cookie` the way a browser does: cookies are keyed by name and path,
+    /// and an expired one removes the cookie it matches
+    fn apply(jar: &mut HashMap<(String, String), String>, set_cookie: &str) {
+        let cookie = Cookie::parse(set_cookie.to_string()).unwrap();
+        let key = (
+            cookie.name().to_string(),
+            cookie.path().unwrap_or("/").to_string(),
+        );
+        if cookie.max_age() == Some(Duration::ZERO) {
+            jar.remove(&key);
+        } else {
+            jar.insert(key, cookie.value().to_string());
+        }
+    }
+
+    #[test]
+    fn session_cookie_is_set_and_cleared_on_the_configured_path() {
+        let set = build_session_cookie("token", None, "/bento");
+        let clear = build_session_cookie("", Some(Duration::seconds(0)), "/bento");
+        assert_eq!(set.path(), Some("/bento"));
+        assert_eq!(clear.path(), Some("/bento"));
+
+        let mut jar = HashMap::new();
+        apply(&mut jar, &set.to_string());
+        assert_eq!(jar.len(), 1);
+        apply(&mut jar, &clear.to_string());
+        assert!(jar.is_empty());
+    }
+
+    fn request_with(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::COOKIE,
+            HeaderValue::from_str(&format!("{SESSION_COOKIE_NAME}={value}")).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn sealed_session_ids_open_with_the_same_key() {
+        let key = Key::generate();
+        for protection in [CookieProtection::Signed, CookieProtection::Encrypted] {
+            let sealed = seal("token", protection, &key);
+            assert_ne!(sealed, "token");
+            let opened = open(&request_with(&sealed), protection, key.clone());
+            assert_eq!(opened, Some(SessionId("token".to_string())));
+        }
+        let encrypted = seal("token", CookieProtection::Encrypted, &key);
+        assert!(!encrypted.contains("token"));
+    }
+
+    #[test]
+    fn tampered_session_cookies_are_rejected() {
+        let key = Key::generate();
+        for protection in [CookieProtection::Signed, CookieProtection::Encrypted] {
+            let sealed = seal("token", protection, &key);
+            let mut tampered = sealed.clone().into_bytes();
+            let last = tampered.len() - 1;
+            tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+            let tampered = String::from_utf8(tampered).unwrap();
+
+            assert_eq!(
+                open(&request_with(&tampered), protection, key.clone()),
+                None
+            );
+            assert_eq!(open(&request_with("token"), protection, key.clone()), None);
+            assert_eq!(
+                open(&request_with(&sealed), protection, Key::generate()),
+                None
+            );
+        }
+
+        // a signed cookie's ID is readable, but swapping it breaks the signature
+        let signed = seal("token", CookieProtection::Signed, &key);
+        let forged = signed.replace("token", "other");
+        assert_eq!(
+            open(
+                &request_with(&forged),
+                CookieProtection::Signed,
+                key.clone()
+            ),
+            None
+        );
+        assert_eq!(
+            open(&request_with(&signed), CookieProtection::Encrypted, key),
+            None
+        );
+    }
+
+    #[test]
+    fn clearing_on_another_path_keeps_the_cookie() {
+        let set = build_session_cookie("token", None, "/bento");
+        let clear = build_session_cookie("", Some(Duration::seconds(0)), "/");
+
+        let mut jar = HashMap::new();
+        apply(&mut jar, &set.to_string());
+        apply(&mut jar, &clear.to_string());
+        assert_eq!(
+            jar.get(&(SESSION_COOKIE_NAME.to_string(), "/bento".to_string())),
+            Some(&"token".to_string())
+        );
+    }
+}
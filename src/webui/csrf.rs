@@ -0,0 +1,297 @@
+//! Double-submit CSRF protection for mutating server functions.
+//!
+//! Opt-in via `[security] csrf = true`. [issue_csrf_token] gives every visitor a random
+//! token in the `csrf` cookie; forms echo it back in a hidden `csrf_token` field (see
+//! [CsrfField]) and server functions compare the two with [verify_csrf]. A cross-site form
+//! can't read the cookie, so it can't supply a matching field.
+
+use leptos::prelude::*;
+
+use crate::types::AppError;
+
+/// Name of the form field (and server function argument) carrying the token
+pub const CSRF_FIELD: &str = "csrf_token";
+
+/// The current visitor's token, loaded once and shared through context
+#[derive(Clone, Copy)]
+pub struct CsrfContext(Resource<Result<String, AppError>>);
+
+impl CsrfContext {
+    pub fn provide() {
+        provide_context(Self(Resource::new(|| (), |_| csrf_token())));
+    }
+
+    /// The token to submit; empty while loading or when protection is disabled
+    pub fn token(&self) -> String {
+        self.0
+            .get_untracked()
+            .and_then(Result::ok)
+            .unwrap_or_default()
+    }
+}
+
+/// Hidden form input submitting the CSRF token with an `ActionForm`
+#[component]
+pub fn CsrfField() -> impl IntoView {
+    let token = use_context::<CsrfContext>().map(|context| context.0);
+
+    view! {
+        <input
+            type="hidden"
+            name=CSRF_FIELD
+            prop:value=move || {
+                token
+                    .and_then(|token| token.get())
+                    .and_then(Result::ok)
+                    .unwrap_or_default()
+            }
+        />
+    }
+}
+
+/// Server function returning the visitor's CSRF token, or an empty string when CSRF
+/// protection is disabled.
+#[server]
+pub async fn csrf_token() -> Result<String, AppError> {
+    use axum::Extension;
+
+    let Extension(token): Extension<Option<IssuedCsrfToken>> = leptos_axum::extract().await?;
+    Ok(token.map(|token| token.0).unwrap_or_default())
+}
+
+#[cfg(feature = "ssr")]
+pub use server::{IssuedCsrfToken, check_csrf, issue_csrf_token, verify_csrf};
+
+#[cfg(feature = "ssr")]
+mod server {
+    use axum::{
+        extract::{Request, State},
+        http::header::{HeaderValue, SET_COOKIE},
+        middleware::Next,
+        response::Response,
+    };
+    use axum_extra::extract::CookieJar;
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use rand::{TryRngCore as _, rngs::OsRng};
+
+    use crate::types::AppError;
+    use crate::webui::cookies::{CSRF_COOKIE_NAME, build_csrf_cookie};
+
+    /// Request extension holding the token issued by [issue_csrf_token]
+    #[derive(Debug, Clone)]
+    pub struct IssuedCsrfToken(pub String);
+
+    impl IssuedCsrfToken {
+        fn generate() -> Self {
+            let mut buf = [0_u8; 32];
+            if OsRng.try_fill_bytes(&mut buf).is_err() {
+                panic!("Failed to generate secure numbers from the operating system.");
+            }
+            Self(URL_SAFE_NO_PAD.encode(buf))
+        }
+    }
+
+    /// Makes sure every visitor has a CSRF cookie and exposes its value to server functions
+    /// as an `Option<IssuedCsrfToken>` request extension (`None` when disabled).
+    ///
+    /// # Example
+    /// ```ignore
+    /// let app = Router::new()
+    ///     .merge(ssr)
+    ///     .layer(from_fn_with_state(conf.security.csrf, issue_csrf_token));
+    /// ```
+    pub async fn issue_csrf_token(
+        State(enabled): State<bool>,
+        jar: CookieJar,
+        mut request: Request,
+        next: Next,
+    ) -> Response {
+        if !enabled {
+            request.extensions_mut().insert(None::<IssuedCsrfToken>);
+            return next.run(request).await;
+        }
+
+        let existing = jar
+            .get(CSRF_COOKIE_NAME)
+            .map(|cookie| cookie.value().to_string())
+            .filter(|token| !token.is_empty());
+        let token = existing
+            .clone()
+            .map(IssuedCsrfToken)
+            .unwrap_or_else(IssuedCsrfToken::generate);
+        request.extensions_mut().insert(Some(token.clone()));
+
+        let mut response = next.run(request).await;
+        if existing.is_none() {
+            let cookie = build_csrf_cookie(&token.0);
+            if let Ok(header_value) = HeaderValue::from_str(&cookie.to_string()) {
+                response.headers_mut().append(SET_COOKIE, header_value);
+            }
+        }
+        response
+    }
+
+    /// Rejects the current server function call unless `submitted` matches the CSRF cookie.
+    ///
+    /// Always passes when `[security] csrf` is off, which [issue_csrf_token] marks by
+    /// attaching no token to the request.
+    pub async fn verify_csrf(submitted: &str) -> Result<(), AppError> {
+        use axum::Extension;
+
+        let Extension(issued): Extension<Option<IssuedCsrfToken>> = leptos_axum::extract().await?;
+        if issued.is_none() {
+            return Ok(());
+        }
+
+        let jar: CookieJar = leptos_axum::extract().await?;
+        check_csrf(
+            jar.get(CSRF_COOKIE_NAME).map(|cookie| cookie.value()),
+            submitted,
+        )
+    }
+
+    /// Compares the cookie and submitted tokens in constant time
+    pub fn check_csrf(cookie: Option<&str>, submitted: &str) -> Result<(), AppError> {
+        let Some(cookie) = cookie.filter(|token| !token.is_empty()) else {
+            return Err(AppError::forbidden(
+                "Missing CSRF token, please reload the page",
+            ));
+        };
+        if submitted.is_empty() {
+            return Err(AppError::forbidden(
+                "Missing CSRF token, please reload the page",
+            ));
+        }
+
+        let matches = cookie.len() == submitted.len()
+            && cookie
+                .bytes()
+                .zip(submitted.bytes())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0;
+        if matches {
+            Ok(())
+        } else {
+            Err(AppError::forbidden(
+                "Invalid CSRF token, please reload the page",
+            ))
+        }
+    }
+}
+
+/// Makes the server function call `call` builds as a request whose `csrf_token` doesn't
+/// match its CSRF cookie, returning the message it was refused with
+#[cfg(all(test, feature = "ssr"))]
+pub(crate) async fn forged_csrf_call<T, E, F>(call: impl FnOnce(String) -> F) -> String
+where
+    F: Future<Output = Result<T, E>>,
+    E: std::fmt::Display,
+{
+    use axum::http::{Request, header::COOKIE};
+
+    let (mut parts, ()) = Request::builder()
+        .header(COOKIE, "csrf=issued-token")
+        .body(())
+        .unwrap()
+        .into_parts();
+    parts
+        .extensions
+        .insert(Some(IssuedCsrfToken("issued-token".to_string())));
+
+    // server functions find the request in the context of the current reactive owner
+    let owner = Owner::new();
+    owner.set();
+    provide_context(parts);
+    match call("forged-token".to_string()).await {
+        Ok(_) => panic!("a forged call went through"),
+        Err(err) => err.to_string(),
+    }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use axum::{
+        Extension, Router,
+        body::Body,
+        http::{StatusCode, header::COOKIE, header::SET_COOKIE},
+        middleware::from_fn_with_state,
+        routing::get,
+    };
+    use tower::ServiceExt;
+
+    #[test]
+    fn matching_token_passes() {
+        assert!(check_csrf(Some("token-123"), "token-123").is_ok());
+    }
+
+    #[test]
+    fn missing_or_mismatched_token_is_rejected() {
+        assert!(check_csrf(None, "token-123").is_err());
+        assert!(check_csrf(Some(""), "").is_err());
+        assert!(check_csrf(Some("token-123"), "").is_err());
+        assert!(check_csrf(Some("token-123"), "token-124").is_err());
+        assert!(check_csrf(Some("token-123"), "token-1234").is_err());
+    }
+
+    async fn echo_token(Extension(token): Extension<Option<IssuedCsrfToken>>) -> String {
+        token.map(|token| token.0).unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn issues_cookie_once_when_enabled() {
+        let app = Router::new()
+            .route("/", get(echo_token))
+            .layer(from_fn_with_state(true, issue_csrf_token));
+
+        let response = app
+            .clone()
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let cookie = response.headers()[SET_COOKIE].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let token = String::from_utf8(body.to_vec()).unwrap();
+        assert!(!token.is_empty());
+        assert!(cookie.starts_with(&format!("csrf={token}")));
+
+        // an existing cookie is reused, not replaced
+        let response = app
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/")
+                    .header(COOKIE, format!("csrf={token}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(response.headers().get(SET_COOKIE).is_none());
+    }
+
+    #[tokio::test]
+    async fn issues_nothing_when_disabled() {
+        let app = Router::new()
+            .route("/", get(echo_token))
+            .layer(from_fn_with_state(false, issue_csrf_token));
+
+        let response = app
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(response.headers().get(SET_COOKIE).is_none());
+    }
+}
@@ -0,0 +1,45 @@
+use leptos::prelude::*;
+
+use crate::webui::components::{ToastContext, ToastKind};
+use crate::webui::csrf::CsrfContext;
+use crate::webui::end_impersonation;
+
+/// Strip across the top of the page while an admin is impersonating `username`, with a
+/// button that signs the admin back in as themselves.
+#[component]
+pub fn ImpersonationBanner(username: String, admin: String) -> impl IntoView {
+    let csrf = expect_context::<CsrfContext>();
+    let toasts = expect_context::<ToastContext>();
+
+    let stop_action: Action<(), Result<(), crate::types::AppError>> =
+        Action::new(move |_: &()| end_impersonation(csrf.token()));
+    Effect::watch(
+        move || stop_action.value().get(),
+        move |result, _, _| match result {
+            // full reload so the whole page renders for the admin again
+            Some(Ok(())) => {
+                let _ = window().location().set_href("/");
+            }
+            Some(Err(e)) => toasts.push_toast(ToastKind::Error, e.to_string()),
+            None => {}
+        },
+        false,
+    );
+
+    view! {
+        <div class="bg-fuchsia-600 text-white text-sm font-medium">
+            <div class="max-w-7xl mx-auto px-6 py-2 flex items-center justify-between gap-4">
+                <span>{format!("Impersonating {username} (you are signed in as {admin})")}</span>
+                <button
+                    class="px-3 py-1 rounded bg-white/20 hover:bg-white/30 disabled:opacity-50"
+                    disabled=move || stop_action.pending().get()
+                    on:click=move |_| {
+                        stop_action.dispatch(());
+                    }
+                >
+                    "Stop impersonating"
+                </button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,198 @@
+use leptos::prelude::*;
+
+use crate::types::{DatabaseReport, MaintenanceReport, PerfReport};
+use crate::webui::{get_maintenance_stats, get_perf_stats, get_storage_stats};
+
+/// Session cleanup counters, database sizes and server function latency (admin only).
+#[component]
+pub fn MaintenancePanel() -> impl IntoView {
+    let cleanup = Resource::new(|| (), |_| get_maintenance_stats());
+    let databases = Resource::new(|| (), |_| get_storage_stats());
+    let perf = Resource::new(|| (), |_| get_perf_stats());
+
+    view! {
+        <section class="mt-12">
+            <div class="flex items-center justify-between mb-4">
+                <h2 class="text-xl font-bold tracking-tight">"Maintenance"</h2>
+                <button
+                    type="button"
+                    class="text-xs font-medium text-gray-300 hover:text-white bg-gray-700 hover:bg-gray-600 py-1 px-3 rounded-lg transition"
+                    on:click=move |_| {
+                        cleanup.refetch();
+                        databases.refetch();
+                        perf.refetch();
+                    }
+                >
+                    "Refresh"
+                </button>
+            </div>
+
+            <Suspense fallback=|| view! { <p class="text-gray-500 text-sm">"Loading statistics..."</p> }>
+                {move || {
+                    cleanup.get().map(|result| match result {
+                        Ok(report) => view! { <CleanupStats report=report /> }.into_any(),
+                        Err(e) => view! { <LoadError what="session cleanup statistics" error=e.to_string() /> }.into_any(),
+                    })
+                }}
+                {move || {
+                    databases.get().map(|result| match result {
+                        Ok(reports) => view! {
+                            <div class="bg-[#1e1f25] border border-gray-800/60 rounded-2xl overflow-hidden">
+                                <table class="w-full text-sm text-left">
+                                    <thead class="text-gray-500 border-b border-gray-800/60">
+                                        <tr>
+                                            <th class="px-4 py-3 font-medium">"Database"</th>
+                                            <th class="px-4 py-3 font-medium">"File size"</th>
+                                            <th class="px-4 py-3 font-medium">"Allocated"</th>
+                                            <th class="px-4 py-3 font-medium">"Stored"</th>
+                                            <th class="px-4 py-3 font-medium">"Fragmentation"</th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {reports.into_iter().map(|report| view! { <DatabaseRow report=report /> }).collect_view()}
+                                    </tbody>
+                                </table>
+                            </div>
+                        }.into_any(),
+                        Err(e) => view! { <LoadError what="database statistics" error=e.to_string() /> }.into_any(),
+                    })
+                }}
+                {move || {
+                    perf.get().map(|result| match result {
+                        Ok(reports) if reports.is_empty() => ().into_any(),
+                        Ok(reports) => view! {
+                            <div class="bg-[#1e1f25] border border-gray-800/60 rounded-2xl overflow-hidden mt-6">
+                                <table class="w-full text-sm text-left">
+                                    <thead class="text-gray-500 border-b border-gray-800/60">
+                                        <tr>
+                                            <th class="px-4 py-3 font-medium">"Server function"</th>
+                                            <th class="px-4 py-3 font-medium">"Calls"</th>
+                                            <th class="px-4 py-3 font-medium">"p50"</th>
+                                            <th class="px-4 py-3 font-medium">"p95"</th>
+                                            <th class="px-4 py-3 font-medium">"p99"</th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {reports.into_iter().map(|report| view! { <PerfRow report=report /> }).collect_view()}
+                                    </tbody>
+                                </table>
+                            </div>
+                        }.into_any(),
+                        Err(e) => view! { <LoadError what="server function latency" error=e.to_string() /> }.into_any(),
+                    })
+                }}
+            </Suspense>
+        </section>
+    }
+}
+
+#[component]
+fn CleanupStats(report: MaintenanceReport) -> impl IntoView {
+    let last_sweep = report
+        .last_sweep_at
+        .map(|at| {
+            format!(
+                "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+                at.year(),
+                at.month() as u8,
+                at.day(),
+                at.hour(),
+                at.minute()
+            )
+        })
+        .unwrap_or_else(|| "Never".to_string());
+    let stats = [
+        ("Sweeps", report.total_sweeps.to_string()),
+        ("Last sweep", last_sweep),
+        ("Swept sessions", report.swept_sessions.to_string()),
+        (
+            "Purged on access",
+            report.lazily_purged_sessions.to_string(),
+        ),
+    ];
+
+    view! {
+        <div class="grid grid-cols-2 md:grid-cols-4 gap-4 mb-6">
+            {stats.into_iter().map(|(label, value)| view! {
+                <div class="bg-[#1e1f25] border border-gray-800/60 rounded-2xl p-4">
+                    <p class="text-gray-500 text-xs mb-1">{label}</p>
+                    <p class="text-gray-200 text-lg font-semibold">{value}</p>
+                </div>
+            }).collect_view()}
+        </div>
+    }
+}
+
+#[component]
+fn DatabaseRow(report: DatabaseReport) -> impl IntoView {
+    let file_size = report
+        .file_size_bytes
+        .map(format_bytes)
+        .unwrap_or_else(|| "In memory".to_string());
+    let allocated = format!(
+        "{} ({} pages)",
+        format_bytes(report.allocated_bytes()),
+        report.allocated_pages
+    );
+    let fragmentation = format!("{:.1}%", report.fragmentation() * 100.0);
+
+    view! {
+        <tr class="border-b border-gray-800/40 last:border-0">
+            <td class="px-4 py-2 text-gray-200">{report.name}</td>
+            <td class="px-4 py-2 text-gray-400">{file_size}</td>
+            <td class="px-4 py-2 text-gray-400">{allocated}</td>
+            <td class="px-4 py-2 text-gray-400">{format_bytes(report.stored_bytes)}</td>
+            <td class="px-4 py-2 text-gray-400">{fragmentation}</td>
+        </tr>
+    }
+}
+
+#[component]
+fn PerfRow(report: PerfReport) -> impl IntoView {
+    view! {
+        <tr class="border-b border-gray-800/40 last:border-0">
+            <td class="px-4 py-2 text-gray-200 font-mono">{report.function}</td>
+            <td class="px-4 py-2 text-gray-400">{report.samples}</td>
+            <td class="px-4 py-2 text-gray-400">{format!("{:.1} ms", report.p50_ms)}</td>
+            <td class="px-4 py-2 text-gray-400">{format!("{:.1} ms", report.p95_ms)}</td>
+            <td class="px-4 py-2 text-gray-400">{format!("{:.1} ms", report.p99_ms)}</td>
+        </tr>
+    }
+}
+
+#[component]
+fn LoadError(what: &'static str, error: String) -> impl IntoView {
+    view! {
+        <div class="bg-red-900/20 border border-red-800 rounded-2xl p-6 mb-6 text-red-400">
+            <p class="font-medium">{format!("Failed to load {what}")}</p>
+            <p class="text-sm mt-1">{error}</p>
+        </div>
+    }
+}
+
+/// Formats a byte count with a binary unit, e.g. "1.5 MiB"
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_counts_use_binary_units() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MiB");
+    }
+}
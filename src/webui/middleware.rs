@@ -0,0 +1,321 @@
+//! Request middleware for session resolution
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::storage::{AuthError, AuthStore};
+use crate::types::{Session, SessionId, User, UserAgentHash};
+use crate::webui::CurrentUser;
+use crate::webui::cookies::session_id_from_headers;
+
+/// The session a request was made with, and its owner
+#[derive(Debug, Clone)]
+pub struct SignedIn {
+    pub session: Session,
+    pub user: User,
+}
+
+/// Resolves the session cookie once per request and inserts the result into the
+/// request extensions as `Option<SignedIn>` and `Option<CurrentUser>`.
+///
+/// Server functions then find them through `fetch_session` and `signed_in`, and REST
+/// handlers with `Extension<Option<CurrentUser>>`, instead of repeating the session and
+/// user lookups. Layer it onto the routes that read the user, not static files.
+///
+/// `Option<SignedIn>` is left out when the lookup failed, for example because the account
+/// is disabled, so `fetch_session` repeats it and reports why.
+///
+/// # Example
+/// ```ignore
+/// let ssr = Router::new()
+///     .leptos_routes(&state, routes, shell)
+///     .route_layer(from_fn_with_state(
+///         (auth_store, bind_user_agent),
+///         resolve_current_user::<RedbAuthStore>,
+///     ));
+/// ```
+pub async fn resolve_current_user<S: AuthStore + ?Sized + 'static>(
+    State((auth_store, bind_user_agent)): State<(Arc<S>, bool)>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let signed_in = match session_id_from_headers(request.headers()) {
+        Some(session_id) => {
+            let user_agent =
+                bind_user_agent.then(|| UserAgentHash::from_headers(request.headers()));
+            load_signed_in(auth_store.as_ref(), &session_id, user_agent.as_ref()).await
+        }
+        None => Ok(None),
+    };
+    let current_user = match &signed_in {
+        Ok(Some(SignedIn { session, user })) => {
+            CurrentUser::for_user(auth_store.as_ref(), user, session)
+                .await
+                .ok()
+        }
+        _ => None,
+    };
+
+    request.extensions_mut().insert(current_user);
+    if let Ok(signed_in) = signed_in {
+        request.extensions_mut().insert(signed_in);
+    }
+    next.run(request).await
+}
+
+/// Looks up the session and its owner.
+///
+/// A missing, expired or revoked session is `Ok(None)`, as is one issued to another
+/// browser: `bound_to` is the requesting browser when `[session] bind_user_agent` is set.
+/// Fails if the session's account is disabled or its owner can't be loaded.
+pub async fn load_signed_in<S: AuthStore + ?Sized>(
+    auth_store: &S,
+    session_id: &SessionId,
+    bound_to: Option<&Option<UserAgentHash>>,
+) -> Result<Option<SignedIn>, AuthError> {
+    let session = match auth_store.fetch_session(session_id).await {
+        Ok(session) => session,
+        Err(AuthError::AccountDisabled) => return Err(AuthError::AccountDisabled),
+        Err(_) => return Ok(None),
+    };
+    if let Some(user_agent) = bound_to
+        && !session.matches_user_agent(user_agent.as_ref())
+    {
+        tracing::warn!(
+            user_id = %session.user_id.0,
+            "Refused a session cookie sent from a different browser"
+        );
+        return Ok(None);
+    }
+    let user = auth_store.get_user_by_id(&session.user_id).await?;
+    Ok(Some(SignedIn { session, user }))
+}
+
+/// Looks up the session and its owner, returning `None` if either is missing or invalid.
+///
+/// `bound_to` is as for [load_signed_in].
+pub async fn load_current_user<S: AuthStore + ?Sized>(
+    auth_store: &S,
+    session_id: &SessionId,
+    bound_to: Option<&Option<UserAgentHash>>,
+) -> Option<CurrentUser> {
+    let SignedIn { session, user } = load_signed_in(auth_store, session_id, bound_to)
+        .await
+        .ok()??;
+    CurrentUser::for_user(auth_store, &user, &session)
+        .await
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::mem_authstore::MemoryAuthStore;
+    use crate::types::{PasswordHash, SessionIp, Username};
+    use crate::webui::cookies::{SESSION_COOKIE_NAME, seal_session_id};
+    use axum::{
+        Extension, Router,
+        body::Body,
+        http::{StatusCode, header::COOKIE},
+        middleware::from_fn_with_state,
+        routing::get,
+    };
+    use std::net::IpAddr;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn valid_cookie_attaches_user() {
+        let store = Arc::new(MemoryAuthStore::default());
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let session = store
+            .issue_session(&user.id, SessionIp(IpAddr::from([127, 0, 0, 1])), None)
+            .await
+            .unwrap();
+
+        let app = Router::new()
+            .route(
+                "/",
+                get(
+                    |Extension(user): Extension<Option<CurrentUser>>,
+                     Extension(signed_in): Extension<Option<SignedIn>>| async move {
+                        match (user, signed_in) {
+                            (Some(user), Some(signed_in))
+                                if user.username == "alice"
+                                    && signed_in.user.username.0 == "alice" =>
+                            {
+                                StatusCode::OK
+                            }
+                            _ => StatusCode::UNAUTHORIZED,
+                        }
+                    },
+                ),
+            )
+            .layer(from_fn_with_state(
+                (store.clone(), false),
+                resolve_current_user::<MemoryAuthStore>,
+            ));
+
+        let with_cookie = Request::builder()
+            .uri("/")
+            .header(
+                COOKIE,
+                format!(
+                    "{SESSION_COOKIE_NAME}={}",
+                    seal_session_id(session.id.as_str())
+                ),
+            )
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(with_cookie).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let without_cookie = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let response = app.oneshot(without_cookie).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn store_errors_resolve_to_no_user() {
+        use crate::storage::mock_authstore::MockAuthStore;
+
+        let store = MockAuthStore::default();
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let session = store
+            .issue_session(&user.id, SessionIp(IpAddr::from([127, 0, 0, 1])), None)
+            .await
+            .unwrap();
+
+        store.fail_next(
+            "fetch_session",
+            AuthError::Internal("disk gone".to_string()),
+        );
+        assert!(load_current_user(&store, &session.id, None).await.is_none());
+
+        // the session is fine, but its user can't be loaded
+        store.fail_next("get_user_by_id", AuthError::NotFound);
+        assert!(load_current_user(&store, &session.id, None).await.is_none());
+
+        let current = load_current_user(&store, &session.id, None).await.unwrap();
+        assert_eq!(current.username, "alice");
+    }
+
+    #[tokio::test]
+    async fn disabled_accounts_fail_to_resolve() {
+        use crate::storage::mock_authstore::MockAuthStore;
+
+        let store = MockAuthStore::default();
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let session = store
+            .issue_session(&user.id, SessionIp(IpAddr::from([127, 0, 0, 1])), None)
+            .await
+            .unwrap();
+
+        // left for `fetch_session` to report, rather than looking signed out
+        store.fail_next("fetch_session", AuthError::AccountDisabled);
+        assert!(matches!(
+            load_signed_in(&store, &session.id, None).await,
+            Err(AuthError::AccountDisabled)
+        ));
+
+        store.fail_next("fetch_session", AuthError::InvalidSession);
+        assert!(matches!(
+            load_signed_in(&store, &session.id, None).await,
+            Ok(None)
+        ));
+
+        let signed_in = load_signed_in(&store, &session.id, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(signed_in.session.id, session.id);
+        assert_eq!(signed_in.user.id, user.id);
+    }
+
+    #[tokio::test]
+    async fn bound_sessions_only_resolve_for_their_browser() {
+        let firefox = "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0";
+        let store = MemoryAuthStore::default();
+        let user = store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let bound = store
+            .issue_session(&user.id, ip.clone(), Some(UserAgentHash::of(firefox)))
+            .await
+            .unwrap();
+        let unbound = store.issue_session(&user.id, ip, None).await.unwrap();
+
+        let same = Some(UserAgentHash::of(firefox));
+        // a point release keeps the major versions, and with them the hash
+        let updated = Some(UserAgentHash::of(
+            "Mozilla/5.0 (X11; Linux x86_64; rv:128.3.1) Gecko/20100101 Firefox/128.3.1",
+        ));
+        assert!(
+            load_current_user(&store, &bound.id, Some(&same))
+                .await
+                .is_some()
+        );
+        assert!(
+            load_current_user(&store, &bound.id, Some(&updated))
+                .await
+                .is_some()
+        );
+
+        let chrome = Some(UserAgentHash::of(
+            "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 Chrome/126.0.0.0 Safari/537.36",
+        ));
+        let major_update = Some(UserAgentHash::of(
+            "Mozilla/5.0 (X11; Linux x86_64; rv:129.0) Gecko/20100101 Firefox/129.0",
+        ));
+        assert!(
+            load_current_user(&store, &bound.id, Some(&chrome))
+                .await
+                .is_none()
+        );
+        assert!(
+            load_current_user(&store, &bound.id, Some(&major_update))
+                .await
+                .is_none()
+        );
+        assert!(
+            load_current_user(&store, &bound.id, Some(&None))
+                .await
+                .is_none()
+        );
+
+        // without `bind_user_agent`, or for sessions issued without a User-Agent, anything goes
+        assert!(load_current_user(&store, &bound.id, None).await.is_some());
+        assert!(
+            load_current_user(&store, &unbound.id, Some(&chrome))
+                .await
+                .is_some()
+        );
+    }
+}
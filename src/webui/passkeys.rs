@@ -0,0 +1,769 @@
+//! Passkey (WebAuthn) server functions.
+//!
+//! Challenges and browser responses are exchanged as JSON strings, which map directly
+//! onto `navigator.credentials.create()`/`get()` options and results on the client.
+
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{AppError, LoginError};
+
+/// A started passkey ceremony, to be answered by the browser
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasskeyChallenge {
+    pub ceremony_id: String,
+    /// WebAuthn options for the browser credential API, as JSON
+    pub options_json: String,
+}
+
+/// Helper function to get the passkey service, failing if passkeys are disabled.
+#[cfg(feature = "ssr")]
+fn passkey_service() -> Result<std::sync::Arc<crate::passkeys::PasskeyService>, AppError> {
+    use crate::server::AppState;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    app_state
+        .passkeys
+        .ok_or_else(|| AppError::forbidden("Passkey login is not enabled"))
+}
+
+/// Start registering a passkey for the current authenticated user.
+///
+/// Not available while impersonating, see `refuse_impersonated`.
+#[server]
+pub async fn begin_passkey_registration(csrf_token: String) -> Result<PasskeyChallenge, AppError> {
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webui::csrf::verify_csrf;
+    use crate::webui::{fetch_session, record_audit};
+
+    verify_csrf(&csrf_token).await?;
+    let passkeys = passkey_service()?;
+    let session = fetch_session()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let challenge =
+        start_passkey_registration(app_state.auth_store.as_ref(), &passkeys, &session).await?;
+
+    record_audit(
+        Some(session.user_id),
+        AuditKind::PasskeyRegistrationStarted,
+        "",
+    )
+    .await;
+    Ok(challenge)
+}
+
+/// Finish registering a passkey with the browser's `navigator.credentials.create()` result.
+#[server]
+pub async fn finish_passkey_registration(
+    ceremony_id: String,
+    credential_json: String,
+    csrf_token: String,
+) -> Result<(), AppError> {
+    use crate::server::AppState;
+    use crate::types::AuditKind;
+    use crate::webui::csrf::verify_csrf;
+    use crate::webui::{fetch_session, record_audit};
+
+    verify_csrf(&csrf_token).await?;
+    let passkeys = passkey_service()?;
+    let session = fetch_session()
+        .await?
+        .ok_or_else(|| AppError::unauthenticated("Not authenticated"))?;
+
+    let response = serde_json::from_str(&credential_json)?;
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let credential = save_passkey_registration(
+        app_state.auth_store.as_ref(),
+        &passkeys,
+        &session,
+        &ceremony_id,
+        &response,
+    )
+    .await?;
+
+    record_audit(
+        Some(session.user_id),
+        AuditKind::PasskeyAdded,
+        credential.credential_id,
+    )
+    .await;
+    Ok(())
+}
+
+/// Start a passkey login for the given username.
+///
+/// Unknown users and users without passkeys get the same `LoginError::InvalidCredentials`,
+/// and count against the client address like wrong passwords do.
+#[server]
+pub async fn begin_passkey_login(username: String) -> Result<PasskeyChallenge, LoginError> {
+    use crate::server::AppState;
+    use crate::webui::throttle_login;
+    use axum_client_ip::ClientIp;
+
+    let passkeys = passkey_service()?;
+    let ClientIp(client_ip) = leptos_axum::extract().await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let attempt = start_passkey_login(app_state.auth_store.as_ref(), &passkeys, &username);
+    throttle_login(&app_state.login_throttle, client_ip, &username, attempt).await
+}
+
+/// Finish a passkey login with the browser's `navigator.credentials.get()` result.
+///
+/// Goes through the same `[lockout]` and `[session]` policies as password login, and on
+/// success sets the session cookie like it. `sign_out_session` is a session picked from
+/// `LoginError::SessionLimitReached` to end, making room for this one.
+#[server]
+pub async fn finish_passkey_login(
+    ceremony_id: String,
+    credential_json: String,
+    csrf_token: String,
+    #[server(default)] sign_out_session: Option<String>,
+) -> Result<(), LoginError> {
+    use crate::config::LOCAL_CONF;
+    use crate::server::AppState;
+    use crate::types::UserAgentHash;
+    use crate::webui::cookies::{set_previous_login_cookie, set_session_cookie};
+    use crate::webui::csrf::verify_csrf;
+    use crate::webui::{complete_login, log_failed_login, throttle_login};
+    use axum::http::HeaderMap;
+    use axum_client_ip::ClientIp;
+    use leptos_axum::ResponseOptions;
+
+    let _timer = crate::perf::timer("finish_passkey_login");
+
+    verify_csrf(&csrf_token).await?;
+    let passkeys = passkey_service()?;
+    let response = expect_context::<ResponseOptions>();
+    let ClientIp(client_ip) = leptos_axum::extract().await?;
+    let headers: HeaderMap = leptos_axum::extract().await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let auth_store = app_state.auth_store.clone();
+
+    let ceremony_user = passkey_login_user(auth_store.as_ref(), &passkeys, &ceremony_id).await?;
+    let username = ceremony_user.username.0.clone();
+    let assertion = serde_json::from_str(&credential_json).map_err(AppError::from)?;
+    let attempt = verify_passkey_login(
+        auth_store.as_ref(),
+        &passkeys,
+        ceremony_user,
+        &ceremony_id,
+        &assertion,
+        &LOCAL_CONF.lockout,
+        time::OffsetDateTime::now_utc(),
+    );
+    let user = match throttle_login(&app_state.login_throttle, client_ip, &username, attempt).await
+    {
+        Ok(user) => user,
+        Err(e) => {
+            log_failed_login("passkey_login", &username, client_ip, &e).await;
+            return Err(e);
+        }
+    };
+
+    let (session, previous_login) = complete_login(
+        "passkey_login",
+        &user,
+        client_ip,
+        UserAgentHash::from_headers(&headers),
+        sign_out_session.as_deref(),
+    )
+    .await?;
+
+    let notified = crate::login_notice::notify_new_login(
+        auth_store.as_ref(),
+        &LOCAL_CONF.security,
+        previous_login.as_ref(),
+        &session,
+        &headers,
+    )
+    .await;
+    if let Err(e) = notified {
+        tracing::warn!("Failed to leave new sign-in notices: {e}");
+    }
+
+    set_session_cookie(&response, session.id.as_str());
+    if let Some(previous_login) = previous_login {
+        set_previous_login_cookie(&response, &previous_login);
+    }
+
+    Ok(())
+}
+
+/// Helper function backing `begin_passkey_registration`.
+#[cfg(feature = "ssr")]
+async fn start_passkey_registration<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    passkeys: &crate::passkeys::PasskeyService,
+    session: &crate::types::Session,
+) -> Result<PasskeyChallenge, AppError> {
+    use crate::webui::refuse_impersonated;
+
+    refuse_impersonated(session)?;
+    let user = auth_store.get_user_by_id(&session.user_id).await?;
+    let existing = auth_store.list_passkeys(&user.id).await?;
+    let (ceremony_id, options) = passkeys.start_registration(&user, &existing)?;
+
+    Ok(PasskeyChallenge {
+        ceremony_id,
+        options_json: serde_json::to_string(&options)?,
+    })
+}
+
+/// Helper function backing `finish_passkey_registration`, returning the saved passkey.
+#[cfg(feature = "ssr")]
+async fn save_passkey_registration<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    passkeys: &crate::passkeys::PasskeyService,
+    session: &crate::types::Session,
+    ceremony_id: &str,
+    response: &webauthn_rs::prelude::RegisterPublicKeyCredential,
+) -> Result<crate::types::PasskeyCredential, AppError> {
+    use crate::webui::refuse_impersonated;
+
+    refuse_impersonated(session)?;
+    let credential = passkeys.finish_registration(ceremony_id, &session.user_id, response)?;
+    auth_store.save_passkey(credential.clone()).await?;
+    Ok(credential)
+}
+
+/// Helper function backing `begin_passkey_login`: starts a login ceremony for `username`.
+///
+/// Unknown users and users without passkeys both produce `LoginError::InvalidCredentials`.
+#[cfg(feature = "ssr")]
+async fn start_passkey_login<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    passkeys: &crate::passkeys::PasskeyService,
+    username: &str,
+) -> Result<PasskeyChallenge, LoginError> {
+    use crate::passkeys::PasskeyError;
+    use crate::storage::AuthError;
+    use crate::types::Username;
+
+    let user = match Username::parse(username.trim()) {
+        Ok(username) => auth_store.get_user_by_username(&username).await,
+        Err(_) => Err(AuthError::NotFound),
+    };
+    let user = match user {
+        Ok(user) => user,
+        Err(AuthError::NotFound) => return Err(LoginError::InvalidCredentials),
+        Err(e) => return Err(AppError::from(e).into()),
+    };
+
+    let credentials = auth_store
+        .list_passkeys(&user.id)
+        .await
+        .map_err(AppError::from)?;
+    let (ceremony_id, options) = match passkeys.start_authentication(&user.id, &credentials) {
+        Ok(started) => started,
+        Err(PasskeyError::NoCredentials) => return Err(LoginError::InvalidCredentials),
+        Err(e) => return Err(AppError::from(e).into()),
+    };
+
+    Ok(PasskeyChallenge {
+        ceremony_id,
+        options_json: serde_json::to_string(&options).map_err(AppError::from)?,
+    })
+}
+
+/// Helper function backing `finish_passkey_login`: loads the user a pending login ceremony
+/// was started for.
+#[cfg(feature = "ssr")]
+async fn passkey_login_user<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    passkeys: &crate::passkeys::PasskeyService,
+    ceremony_id: &str,
+) -> Result<crate::types::User, LoginError> {
+    use crate::passkeys::PasskeyError;
+
+    let user_id = passkeys
+        .authentication_user(ceremony_id)
+        .ok_or_else(|| LoginError::Other(PasskeyError::CeremonyNotFound.to_string()))?;
+    Ok(auth_store
+        .get_user_by_id(&user_id)
+        .await
+        .map_err(AppError::from)?)
+}
+
+/// Helper function backing `finish_passkey_login`: checks the browser's assertion against
+/// the passkeys of `user`, the user the ceremony was started for.
+///
+/// Handles `[lockout]` like password login: locked accounts are refused before the assertion
+/// is checked, and refused assertions count towards a lock. Disabled accounts are refused
+/// only once it checks out. Passkeys whose stored state changed (e.g., the signature
+/// counter) are saved back.
+#[cfg(feature = "ssr")]
+async fn verify_passkey_login<S: crate::storage::AuthStore + ?Sized>(
+    auth_store: &S,
+    passkeys: &crate::passkeys::PasskeyService,
+    user: crate::types::User,
+    ceremony_id: &str,
+    assertion: &webauthn_rs::prelude::PublicKeyCredential,
+    lockout: &crate::config::Lockout,
+    now: time::OffsetDateTime,
+) -> Result<crate::types::User, LoginError> {
+    use crate::passkeys::PasskeyError;
+
+    if user.is_locked(now) {
+        return Err(LoginError::AccountLocked);
+    }
+
+    let credentials = auth_store
+        .list_passkeys(&user.id)
+        .await
+        .map_err(AppError::from)?;
+    let updated = match passkeys.finish_authentication(ceremony_id, assertion, &credentials) {
+        Ok((_, updated)) => updated,
+        Err(PasskeyError::Webauthn(e)) => {
+            tracing::debug!(user = %user.username.0, "Passkey assertion refused: {e}");
+            if lockout.enabled() {
+                auth_store
+                    .record_failed_login(&user.id, lockout.max_failed_logins, lockout.duration())
+                    .await
+                    .map_err(AppError::from)?;
+            }
+            return Err(LoginError::InvalidCredentials);
+        }
+        Err(e @ PasskeyError::CeremonyNotFound) => return Err(LoginError::Other(e.to_string())),
+        Err(e) => return Err(AppError::from(e).into()),
+    };
+
+    // persist signature counter changes so cloned authenticators can be detected
+    for credential in updated {
+        auth_store
+            .save_passkey(credential)
+            .await
+            .map_err(AppError::from)?;
+    }
+
+    if !user.is_active() {
+        return Err(LoginError::AccountDisabled);
+    }
+    Ok(user)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+    use crate::passkeys::PasskeyService;
+    use crate::storage::AuthStore;
+    use crate::storage::clock::TestClock;
+    use crate::storage::mem_authstore::MemoryAuthStore;
+    use crate::types::{PasskeyCredential, PasswordHash, User, UserId, UserStatus, Username};
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use openssl::bn::{BigNum, BigNumContext};
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::{PKey, Private};
+    use openssl::sha::sha256;
+    use openssl::sign::Signer;
+    use serde_json::json;
+    use std::sync::Arc;
+    use time::{Duration, OffsetDateTime};
+    use webauthn_rs::prelude::PublicKeyCredential;
+
+    /// A software authenticator holding a single P-256 passkey
+    struct SoftPasskey {
+        key: EcKey<Private>,
+        credential_id: Vec<u8>,
+    }
+
+    impl SoftPasskey {
+        fn new() -> Self {
+            let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+            let mut credential_id = vec![0; 16];
+            openssl::rand::rand_bytes(&mut credential_id).unwrap();
+            Self {
+                key: EcKey::generate(&group).unwrap(),
+                credential_id,
+            }
+        }
+
+        /// The credential registration would have stored for `user_id`
+        fn credential(&self, user_id: UserId) -> PasskeyCredential {
+            let (mut x, mut y) = (BigNum::new().unwrap(), BigNum::new().unwrap());
+            self.key
+                .public_key()
+                .affine_coordinates(
+                    self.key.group(),
+                    &mut x,
+                    &mut y,
+                    &mut BigNumContext::new().unwrap(),
+                )
+                .unwrap();
+            let passkey = json!({ "cred": {
+                "cred_id": URL_SAFE_NO_PAD.encode(&self.credential_id),
+                "cred": { "type_": "ES256", "key": { "EC_EC2": {
+                    "curve": "SECP256R1",
+                    "x": URL_SAFE_NO_PAD.encode(x.to_vec_padded(32).unwrap()),
+                    "y": URL_SAFE_NO_PAD.encode(y.to_vec_padded(32).unwrap()),
+                } } },
+                "counter": 0,
+                "transports": null,
+                "user_verified": true,
+                "backup_eligible": false,
+                "backup_state": false,
+                "registration_policy": "required",
+                "extensions": {},
+                "attestation": { "data": "None", "metadata": "None" },
+                "attestation_format": "none",
+            } });
+
+            PasskeyCredential {
+                credential_id: URL_SAFE_NO_PAD.encode(&self.credential_id),
+                user_id,
+                passkey_json: passkey.to_string(),
+                created_at: OffsetDateTime::now_utc(),
+            }
+        }
+
+        /// Answers `challenge` like `navigator.credentials.get()`, having signed `counter`
+        /// times so far
+        fn assert(&self, challenge: &PasskeyChallenge, counter: u32) -> PublicKeyCredential {
+            let options: serde_json::Value = serde_json::from_str(&challenge.options_json).unwrap();
+            let client_data = json!({
+                "type": "webauthn.get",
+                "challenge": options["publicKey"]["challenge"],
+                "origin": config::Passkeys::default().rp_origin,
+            })
+            .to_string();
+
+            // rp id hash, user present and verified flags, signature counter
+            let mut authenticator_data = sha256(b"localhost").to_vec();
+            authenticator_data.push(0x05);
+            authenticator_data.extend(counter.to_be_bytes());
+
+            let key = PKey::from_ec_key(self.key.clone()).unwrap();
+            let mut signer = Signer::new(MessageDigest::sha256(), &key).unwrap();
+            signer.update(&authenticator_data).unwrap();
+            signer.update(&sha256(client_data.as_bytes())).unwrap();
+            let signature = signer.sign_to_vec().unwrap();
+
+            serde_json::from_value(json!({
+                "id": URL_SAFE_NO_PAD.encode(&self.credential_id),
+                "rawId": URL_SAFE_NO_PAD.encode(&self.credential_id),
+                "response": {
+                    "authenticatorData": URL_SAFE_NO_PAD.encode(&authenticator_data),
+                    "clientDataJSON": URL_SAFE_NO_PAD.encode(&client_data),
+                    "signature": URL_SAFE_NO_PAD.encode(&signature),
+                    "userHandle": null,
+                },
+                "type": "public-key",
+            }))
+            .unwrap()
+        }
+    }
+
+    fn service(clock: Arc<TestClock>) -> PasskeyService {
+        PasskeyService::new(&config::Passkeys::default())
+            .unwrap()
+            .with_clock(clock)
+    }
+
+    async fn user_with_passkey(store: &MemoryAuthStore, name: &str) -> (User, SoftPasskey) {
+        let user = store
+            .create_standard_user(
+                &Username(name.to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+        let passkey = SoftPasskey::new();
+        store
+            .save_passkey(passkey.credential(user.id))
+            .await
+            .unwrap();
+        (user, passkey)
+    }
+
+    /// Runs a whole login ceremony for `username`, answered by `passkey`
+    async fn login(
+        store: &MemoryAuthStore,
+        passkeys: &PasskeyService,
+        username: &str,
+        passkey: &SoftPasskey,
+    ) -> Result<User, LoginError> {
+        login_with_lockout(
+            store,
+            passkeys,
+            username,
+            passkey,
+            &config::Lockout::default(),
+        )
+        .await
+    }
+
+    async fn login_with_lockout(
+        store: &MemoryAuthStore,
+        passkeys: &PasskeyService,
+        username: &str,
+        passkey: &SoftPasskey,
+        lockout: &config::Lockout,
+    ) -> Result<User, LoginError> {
+        let challenge = start_passkey_login(store, passkeys, username).await?;
+        let user = passkey_login_user(store, passkeys, &challenge.ceremony_id).await?;
+        let assertion = passkey.assert(&challenge, 1);
+        verify_passkey_login(
+            store,
+            passkeys,
+            user,
+            &challenge.ceremony_id,
+            &assertion,
+            lockout,
+            OffsetDateTime::now_utc(),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn unknown_users_look_like_users_without_passkeys() {
+        let store = MemoryAuthStore::default();
+        let passkeys = service(Arc::new(TestClock::new()));
+        store
+            .create_standard_user(
+                &Username("alice".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+            )
+            .await
+            .unwrap();
+
+        for username in ["alice", "bob", "not a username"] {
+            assert_eq!(
+                start_passkey_login(&store, &passkeys, username)
+                    .await
+                    .unwrap_err(),
+                LoginError::InvalidCredentials,
+                "{username}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_or_expired_ceremonies_are_refused() {
+        let store = MemoryAuthStore::default();
+        let clock = Arc::new(TestClock::new());
+        let passkeys = service(clock.clone());
+        user_with_passkey(&store, "alice").await;
+
+        assert!(matches!(
+            passkey_login_user(&store, &passkeys, "unknown").await,
+            Err(LoginError::Other(_))
+        ));
+
+        let challenge = start_passkey_login(&store, &passkeys, "alice")
+            .await
+            .unwrap();
+        clock.advance(Duration::minutes(6));
+        assert!(matches!(
+            passkey_login_user(&store, &passkeys, &challenge.ceremony_id).await,
+            Err(LoginError::Other(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn passkey_login_checks_the_assertion() {
+        let store = MemoryAuthStore::default();
+        let passkeys = service(Arc::new(TestClock::new()));
+        let (alice, alice_passkey) = user_with_passkey(&store, "alice").await;
+        let (_, bob_passkey) = user_with_passkey(&store, "bob").await;
+
+        // bob's passkey can't answer a ceremony started for alice
+        assert_eq!(
+            login(&store, &passkeys, "alice", &bob_passkey)
+                .await
+                .unwrap_err(),
+            LoginError::InvalidCredentials
+        );
+        assert_eq!(
+            login(&store, &passkeys, "alice", &alice_passkey)
+                .await
+                .unwrap()
+                .id,
+            alice.id
+        );
+    }
+
+    #[tokio::test]
+    async fn passkey_login_saves_the_signature_counter() {
+        let store = MemoryAuthStore::default();
+        let passkeys = service(Arc::new(TestClock::new()));
+        let (alice, passkey) = user_with_passkey(&store, "alice").await;
+
+        login(&store, &passkeys, "alice", &passkey).await.unwrap();
+
+        let stored = store.list_passkeys(&alice.id).await.unwrap();
+        let stored: serde_json::Value = serde_json::from_str(&stored[0].passkey_json).unwrap();
+        assert_eq!(stored["cred"]["counter"], 1);
+    }
+
+    #[tokio::test]
+    async fn passkey_login_refuses_disabled_and_locked_users() {
+        let store = MemoryAuthStore::default();
+        let passkeys = service(Arc::new(TestClock::new()));
+        let (alice, passkey) = user_with_passkey(&store, "alice").await;
+
+        store
+            .set_status(&alice.id, UserStatus::Disabled)
+            .await
+            .unwrap();
+        assert_eq!(
+            login(&store, &passkeys, "alice", &passkey)
+                .await
+                .unwrap_err(),
+            LoginError::AccountDisabled
+        );
+
+        store
+            .set_status(&alice.id, UserStatus::Active)
+            .await
+            .unwrap();
+        store
+            .record_failed_login(&alice.id, 1, Duration::minutes(15))
+            .await
+            .unwrap();
+        assert_eq!(
+            login(&store, &passkeys, "alice", &passkey)
+                .await
+                .unwrap_err(),
+            LoginError::AccountLocked
+        );
+    }
+
+    #[tokio::test]
+    async fn refused_passkeys_count_towards_a_lock() {
+        let store = MemoryAuthStore::default();
+        let passkeys = service(Arc::new(TestClock::new()));
+        let (_, alice_passkey) = user_with_passkey(&store, "alice").await;
+        let (_, bob_passkey) = user_with_passkey(&store, "bob").await;
+        let lockout = config::Lockout {
+            max_failed_logins: 2,
+            ..Default::default()
+        };
+
+        for _ in 0..2 {
+            assert_eq!(
+                login_with_lockout(&store, &passkeys, "alice", &bob_passkey, &lockout)
+                    .await
+                    .unwrap_err(),
+                LoginError::InvalidCredentials
+            );
+        }
+        assert_eq!(
+            login_with_lockout(&store, &passkeys, "alice", &alice_passkey, &lockout)
+                .await
+                .unwrap_err(),
+            LoginError::AccountLocked
+        );
+    }
+
+    #[tokio::test]
+    async fn pending_ceremonies_are_capped_per_user() {
+        use crate::passkeys::MAX_PENDING_PER_USER;
+
+        let store = MemoryAuthStore::default();
+        let clock = Arc::new(TestClock::new());
+        let passkeys = service(clock.clone());
+        user_with_passkey(&store, "alice").await;
+        user_with_passkey(&store, "bob").await;
+
+        for _ in 0..MAX_PENDING_PER_USER {
+            start_passkey_login(&store, &passkeys, "alice")
+                .await
+                .unwrap();
+        }
+        assert!(matches!(
+            start_passkey_login(&store, &passkeys, "alice").await,
+            Err(LoginError::Other(_))
+        ));
+
+        // other users aren't held up, and expired ceremonies make room again
+        start_passkey_login(&store, &passkeys, "bob").await.unwrap();
+        clock.advance(Duration::minutes(6));
+        start_passkey_login(&store, &passkeys, "alice")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn impersonation_sessions_cannot_register_passkeys() {
+        use crate::types::{AppErrorKind, Role, SessionIp};
+        use std::net::IpAddr;
+
+        let store = MemoryAuthStore::default();
+        let passkeys = service(Arc::new(TestClock::new()));
+        let admin = store
+            .create_user(
+                &Username("admin".to_string()),
+                PasswordHash::try_from("password").unwrap(),
+                Role::Admin,
+                UserStatus::Active,
+            )
+            .await
+            .unwrap();
+        let (alice, _) = user_with_passkey(&store, "alice").await;
+        let ip = SessionIp(IpAddr::from([127, 0, 0, 1]));
+        let own = store
+            .issue_session(&alice.id, ip.clone(), None)
+            .await
+            .unwrap();
+        let impersonated = store
+            .issue_impersonation_session(&admin.id, &alice.id, ip, None)
+            .await
+            .unwrap();
+
+        let err = start_passkey_registration(&store, &passkeys, &impersonated)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), AppErrorKind::Forbidden);
+
+        // nor finish one alice started herself
+        let challenge = start_passkey_registration(&store, &passkeys, &own)
+            .await
+            .unwrap();
+        let response = serde_json::from_value(json!({
+            "id": "AA",
+            "rawId": "AA",
+            "response": { "attestationObject": "AA", "clientDataJSON": "AA" },
+            "type": "public-key",
+        }))
+        .unwrap();
+        let err = save_passkey_registration(
+            &store,
+            &passkeys,
+            &impersonated,
+            &challenge.ceremony_id,
+            &response,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind(), AppErrorKind::Forbidden);
+        assert_eq!(store.list_passkeys(&alice.id).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn passkey_changes_refuse_forged_calls() {
+        use crate::webui::csrf::forged_csrf_call;
+
+        let refused = forged_csrf_call(begin_passkey_registration).await;
+        assert_eq!(refused, "Invalid CSRF token, please reload the page");
+
+        let refused = forged_csrf_call(|csrf_token| {
+            finish_passkey_registration("ceremony".to_string(), "{}".to_string(), csrf_token)
+        })
+        .await;
+        assert_eq!(refused, "Invalid CSRF token, please reload the page");
+
+        let refused = forged_csrf_call(|csrf_token| {
+            finish_passkey_login("ceremony".to_string(), "{}".to_string(), csrf_token, None)
+        })
+        .await;
+        assert_eq!(refused, "Invalid CSRF token, please reload the page");
+    }
+}
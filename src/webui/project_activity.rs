@@ -0,0 +1,59 @@
+use leptos::prelude::*;
+
+use crate::types::{AuditEvent, AuditKind};
+use crate::webui::get_project_activity;
+
+/// Timeline of a project's creation, renames and settings changes, newest first.
+#[component]
+pub fn ProjectActivityFeed(project_id: String) -> impl IntoView {
+    let activity = Resource::new(move || project_id.clone(), get_project_activity);
+
+    view! {
+        <Suspense fallback=|| view! { <p class="text-gray-500 text-xs">"Loading activity..."</p> }>
+            {move || {
+                activity.get().map(|result| match result {
+                    Ok(events) if events.is_empty() => view! {
+                        <p class="text-gray-500 text-xs">"No activity yet."</p>
+                    }.into_any(),
+                    Ok(events) => view! {
+                        <ol class="border-l border-gray-700/60 ml-1 space-y-3">
+                            {events.into_iter().map(|event| view! { <ActivityEntry event=event /> }).collect_view()}
+                        </ol>
+                    }.into_any(),
+                    Err(e) => view! {
+                        <p class="text-red-400 text-xs">{e.to_string()}</p>
+                    }.into_any(),
+                })
+            }}
+        </Suspense>
+    }
+}
+
+#[component]
+fn ActivityEntry(event: AuditEvent) -> impl IntoView {
+    let at = event.at;
+    let time_str = format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        at.year(),
+        at.month() as u8,
+        at.day(),
+        at.hour(),
+        at.minute()
+    );
+    let summary = match event.kind {
+        AuditKind::ProjectCreated => "Created",
+        AuditKind::ProjectRenamed => "Renamed",
+        AuditKind::ProjectUpdated => "Settings changed",
+        kind => kind.label(),
+    };
+
+    view! {
+        <li class="relative pl-4">
+            <span class="absolute -left-[5px] top-1.5 w-2 h-2 rounded-full bg-gray-500"></span>
+            <p class="text-gray-200 text-xs font-medium">
+                {summary}<span class="text-gray-500 font-normal ml-2">{time_str}</span>
+            </p>
+            <p class="text-gray-400 text-xs break-words">{event.detail}</p>
+        </li>
+    }
+}
@@ -0,0 +1,138 @@
+use leptos::prelude::*;
+use leptos_router::hooks::use_params_map;
+
+use crate::types::Project;
+use crate::webui::components::{ToastContext, ToastKind};
+use crate::webui::csrf::CsrfContext;
+use crate::webui::{
+    get_current_user, get_project_by_slug, get_project_description_html,
+    set_project_delete_protection,
+};
+
+/// View of the project at `/p/{slug}`, for anyone allowed to see it. Its owner also gets
+/// the project's settings that don't fit on the dashboard card.
+#[component]
+pub fn ProjectBySlug() -> impl IntoView {
+    let params = use_params_map();
+    let project = Resource::new(
+        move || params.read().get("slug").unwrap_or_default(),
+        get_project_by_slug,
+    );
+    let fallback =
+        || view! { <div class="min-h-screen flex items-center justify-center">"Loading..."</div> };
+
+    view! {
+        <Suspense fallback=fallback>
+            {move || {
+                project.get().map(|result| match result {
+                    Ok(project) => view! { <ProjectDetails project=project /> }.into_any(),
+                    Err(e) => view! {
+                        <div class="min-h-screen flex items-center justify-center">
+                            <p class="text-gray-400">{e.to_string()}</p>
+                        </div>
+                    }.into_any(),
+                })
+            }}
+        </Suspense>
+    }
+}
+
+#[component]
+fn ProjectDetails(project: Project) -> impl IntoView {
+    let owner_id = project.owner_id.0.to_string();
+    let current_user = Resource::new(|| (), |_| get_current_user());
+    let slug = project.slug.clone();
+    // rendered on the server, and only with `[ui] markdown_descriptions`
+    let description_html = Resource::new(move || slug.clone(), get_project_description_html);
+    let name = project.name.clone();
+    let path = format!("/p/{}", project.slug);
+    let description = project.description.clone();
+    let is_owner = move || {
+        current_user
+            .get()
+            .and_then(Result::ok)
+            .flatten()
+            .is_some_and(|user| user.user_id == owner_id)
+    };
+
+    view! {
+        <main class="max-w-2xl mx-auto px-4 py-12">
+            <h1 class="text-2xl font-semibold text-gray-100 break-words">{name}</h1>
+            <p class="text-gray-500 text-xs mt-1">{path}</p>
+            {move || match description_html.get().and_then(Result::ok).flatten() {
+                Some(html) => view! {
+                    <div class="markdown text-gray-300 mt-4 break-words" inner_html=html></div>
+                }
+                .into_any(),
+                None => description
+                    .clone()
+                    .map(|description| view! {
+                        <p class="text-gray-300 mt-4 whitespace-pre-line break-words">
+                            {description}
+                        </p>
+                    })
+                    .into_any(),
+            }}
+            <Show when=is_owner>
+                <DeleteProtectionToggle project=project.clone() />
+            </Show>
+            <a href="/" class="inline-block mt-8 text-sm text-blue-400 hover:text-blue-300">
+                "Back to Bento"
+            </a>
+        </main>
+    }
+}
+
+/// Owner's switch for `Project::delete_protected`, saved as soon as it changes
+#[component]
+fn DeleteProtectionToggle(project: Project) -> impl IntoView {
+    let csrf = expect_context::<CsrfContext>();
+    let toasts = expect_context::<ToastContext>();
+    let project_id = project.id.0.to_string();
+    let (protected, set_protected) = signal(project.delete_protected);
+    // the value last sent to the server; the checkbox shows it until the server answers
+    let requested = StoredValue::new(project.delete_protected);
+
+    let toggle = Action::new(move |protected: &bool| {
+        let project_id = project_id.clone();
+        let protected = *protected;
+        let csrf_token = csrf.token();
+        async move { set_project_delete_protection(project_id, protected, csrf_token).await }
+    });
+    Effect::watch(
+        move || toggle.value().get(),
+        move |result, _, _| match result {
+            Some(Ok(project)) => set_protected.set(project.delete_protected),
+            Some(Err(e)) => {
+                // puts the checkbox back
+                set_protected.set(!requested.get_value());
+                toasts.push_toast(ToastKind::Error, e.to_string());
+            }
+            None => {}
+        },
+        false,
+    );
+
+    view! {
+        <section id="settings" class="mt-8 border-t border-gray-800 pt-6">
+            <label class="flex items-center text-sm text-gray-300">
+                <input
+                    type="checkbox"
+                    class="mr-2 accent-orange-500"
+                    prop:checked=move || protected.get()
+                    disabled=move || toggle.pending().get()
+                    on:change=move |ev| {
+                        let checked = event_target_checked(&ev);
+                        requested.set_value(checked);
+                        set_protected.set(checked);
+                        toggle.dispatch(checked);
+                    }
+                />
+                "Delete protection"
+            </label>
+            <p class="text-gray-500 text-xs mt-1 ml-6">
+                "While on, the project can't be deleted."
+            </p>
+        </section>
+    }
+}
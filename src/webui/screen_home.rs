@@ -1,77 +1,260 @@
-use crate::types::{AppError, ProjectSummary};
+use crate::types::{
+    AppError, InstanceLimits, ProjectMetrics, ProjectPage, ProjectSummary, ProjectVisibility, Role,
+};
+use crate::webui::account::ChangeUsernameModal;
+use crate::webui::audit_log::AuditLogPanel;
+use crate::webui::components::{ConfirmModal, ToastContext, ToastKind};
+use crate::webui::csrf::CsrfContext;
 use crate::webui::icons::*;
-use crate::webui::{CurrentUser, LogoSvg, Logout, create_project, delete_project, get_my_projects};
+use crate::webui::impersonation::ImpersonationBanner;
+use crate::webui::maintenance::{MaintenancePanel, format_bytes};
+use crate::webui::project_activity::ProjectActivityFeed;
+use crate::webui::session_expiry::SessionExpiryBanner;
+use crate::webui::user_admin::UserAdminPanel;
+use crate::webui::{
+    CurrentUser, ExportProjectArchive, LogoSvg, Logout, LogoutAll, create_project, delete_project,
+    duplicate_project, get_build_info, get_instance_limits, get_my_projects_page,
+    get_project_metrics, search_my_projects, take_new_login_notice, take_previous_login,
+    update_project, use_branding,
+};
 use leptos::prelude::*;
+use leptos::server_fn::ServerFn;
+use uuid::Uuid;
 
-type CreateProjectInput = (String, Option<String>);
+/// Projects fetched per page
+const PAGE_SIZE: usize = 12;
+
+/// Most search results shown at once
+const SEARCH_LIMIT: usize = 48;
+
+/// How long typing has to pause before the search runs
+const SEARCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Name, description and idempotency key
+type CreateProjectInput = (String, Option<String>, String);
 type CreateProjectOutput = Result<ProjectSummary, AppError>;
 type CreateProjectAction = Action<CreateProjectInput, CreateProjectOutput>;
 
 type DeleteProjectOutput = Result<(), AppError>;
 type DeleteProjectAction = Action<String, DeleteProjectOutput>;
 
+type DuplicateProjectOutput = Result<ProjectSummary, AppError>;
+type DuplicateProjectAction = Action<String, DuplicateProjectOutput>;
+
 // Context type to avoid prop drilling
 #[derive(Clone)]
 struct HomeContext {
     user: CurrentUser,
-    projects_resource: Resource<Result<Vec<ProjectSummary>, AppError>>,
+    projects_resource: Resource<Result<ProjectPage, AppError>>,
+    search_results: Resource<Result<Vec<ProjectSummary>, AppError>>,
     create_action: CreateProjectAction,
     delete_action: DeleteProjectAction,
+    duplicate_action: DuplicateProjectAction,
+    /// Why `NewProjectCard` can't create a project right now, if it can't
+    project_limit: Memo<Option<String>>,
+}
+
+/// "Project limit reached (N/N)" while a user owning `count` projects is at their limit.
+///
+/// Follows both signals, so the create button re-enables as soon as a deletion brings the
+/// count back under the limit.
+fn project_limit_notice(
+    limits: Signal<InstanceLimits>,
+    count: Signal<usize>,
+) -> Memo<Option<String>> {
+    Memo::new(move |_| limits.get().project_limit_reached(count.get()))
 }
 
 #[component]
 pub fn HomeScreen(user: CurrentUser) -> impl IntoView {
-    // Resource to fetch projects from the server
-    let projects_resource = Resource::new(|| (), |_| get_my_projects());
+    // Pages loaded with "Load more", on top of the first page from `projects_resource`
+    let more_projects = RwSignal::new(Vec::<ProjectSummary>::new());
+    // The page "Load more" fetched last, which knows where the list continues
+    let latest_page = RwSignal::new(None::<ProjectPage>);
 
-    // Action to create a new project
-    let create_action = Action::new(|(name, description): &CreateProjectInput| {
-        let name = name.clone();
-        let description = description.clone();
-        async move { create_project(name, description).await }
+    // Resource to fetch the first page; refetches cover the extra pages too, so nothing
+    // already in view disappears
+    let projects_resource = Resource::new(
+        || (),
+        move |_| get_my_projects_page(None, PAGE_SIZE + more_projects.with_untracked(Vec::len)),
+    );
+
+    // All loaded projects, newest first
+    let projects = Memo::new(move |_| {
+        let mut projects = projects_resource
+            .get()
+            .and_then(Result::ok)
+            .map(|page| page.projects)
+            .unwrap_or_default();
+        append_page(&mut projects, more_projects.get());
+        projects
+    });
+    let latest = move || {
+        latest_page
+            .get()
+            .or_else(|| projects_resource.get().and_then(Result::ok))
+    };
+    let total = Memo::new(move |_| latest().map(|page| page.total).unwrap_or(0));
+    let next_cursor = Memo::new(move |_| latest().and_then(|page| page.next_cursor));
+
+    // The server refuses creations past the limit anyway; this only disables the button early
+    let limits = Resource::new(|| (), |_| get_instance_limits());
+    let project_limit = project_limit_notice(
+        Signal::derive(move || limits.get().and_then(Result::ok).unwrap_or_default()),
+        total.into(),
+    );
+
+    // Action to fetch the next page and append it
+    let load_more_action = Action::new(move |_: &()| {
+        let after = next_cursor.get_untracked().map(|id| id.0.to_string());
+        async move { get_my_projects_page(after, PAGE_SIZE).await }
     });
+    Effect::watch(
+        move || load_more_action.value().get(),
+        move |result, _, _| {
+            if let Some(Ok(page)) = result {
+                more_projects.update(|more| append_page(more, page.projects.clone()));
+                latest_page.set(Some(page.clone()));
+            }
+        },
+        false,
+    );
+
+    // A fresh first page already includes everything loaded before
+    Effect::watch(
+        move || projects_resource.get(),
+        move |result, _, _| {
+            if matches!(result, Some(Ok(_))) {
+                more_projects.set(Vec::new());
+                latest_page.set(None);
+            }
+        },
+        false,
+    );
+
+    // What's typed in the search box, and the query last searched for once typing paused
+    let (search_input, set_search_input) = signal(String::new());
+    let (search_query, set_search_query) = signal(String::new());
+    let searching = move || !search_query.with(|query| query.trim().is_empty());
+    let search_results = Resource::new(
+        move || search_query.get(),
+        |query| async move {
+            if query.trim().is_empty() {
+                return Ok(Vec::new());
+            }
+            search_my_projects(query, SEARCH_LIMIT).await
+        },
+    );
+    let on_search_input = move |ev: leptos::ev::Event| {
+        let value = event_target_value(&ev);
+        set_search_input.set(value.clone());
+        set_timeout(
+            move || {
+                // only the last keystroke before the pause gets through
+                if search_input.with_untracked(|input| *input == value) {
+                    set_search_query.set(value);
+                }
+            },
+            SEARCH_DEBOUNCE,
+        );
+    };
+
+    let csrf = expect_context::<CsrfContext>();
+
+    // Action to create a new project
+    let create_action = Action::new(
+        move |(name, description, idempotency_key): &CreateProjectInput| {
+            let name = name.clone();
+            let description = description.clone();
+            let idempotency_key = Some(idempotency_key.clone());
+            let csrf_token = csrf.token();
+            async move { create_project(name, description, idempotency_key, csrf_token).await }
+        },
+    );
 
     // Action to delete a project
-    let delete_action = Action::new(|project_id: &String| {
+    let delete_action = Action::new(move |project_id: &String| {
         let project_id = project_id.clone();
-        async move { delete_project(project_id).await }
+        let csrf_token = csrf.token();
+        async move { delete_project(project_id, csrf_token).await }
     });
 
-    // Refetch projects when create or delete action completes successfully
+    // Action to duplicate a project
+    let duplicate_action = Action::new(move |project_id: &String| {
+        let project_id = project_id.clone();
+        let csrf_token = csrf.token();
+        async move { duplicate_project(project_id, csrf_token).await }
+    });
+
+    // Refetch projects (and search results) when create, delete or duplicate completes
+    // successfully; creating and deleting also report back with a toast
+    let toasts = expect_context::<ToastContext>();
     Effect::watch(
         move || create_action.value().get(),
-        move |result, _, _| {
-            if matches!(result.as_ref(), Some(Ok(_))) {
+        move |result, _, _| match result {
+            Some(Ok(project)) => {
                 projects_resource.refetch();
+                search_results.refetch();
+                toasts.push_toast(
+                    ToastKind::Success,
+                    format!("Created project \"{}\"", project.name),
+                );
             }
+            Some(Err(e)) => toasts.push_toast(ToastKind::Error, e.to_string()),
+            None => {}
         },
         false,
     );
 
     Effect::watch(
         move || delete_action.value().get(),
+        move |result, _, _| match result {
+            Some(Ok(())) => {
+                projects_resource.refetch();
+                search_results.refetch();
+                toasts.push_toast(ToastKind::Success, "Project deleted");
+            }
+            Some(Err(e)) => toasts.push_toast(ToastKind::Error, e.to_string()),
+            None => {}
+        },
+        false,
+    );
+
+    Effect::watch(
+        move || duplicate_action.value().get(),
         move |result, _, _| {
             if matches!(result.as_ref(), Some(Ok(_))) {
                 projects_resource.refetch();
+                search_results.refetch();
             }
         },
         false,
     );
 
+    // Fetched from the browser so the one-time cookie is cleared by a regular response
+    let previous_login = LocalResource::new(take_previous_login);
+    let new_login = LocalResource::new(take_new_login_notice);
+
     let user_name = user.username.clone();
+    let is_admin = user.role == Role::Admin;
 
     // Provide context to child components
     let context = HomeContext {
         user: user.clone(),
         projects_resource,
+        search_results,
         create_action,
         delete_action,
+        duplicate_action,
+        project_limit,
     };
     provide_context(context);
 
     view! {
         <div class="min-h-screen bg-[#13141c] text-white font-sans selection:bg-orange-500/30">
             <NavBar />
+            {user.impersonated_by.clone().map(|admin| view! { <ImpersonationBanner username=user.username.clone() admin=admin /> })}
+            {user.session_expires_at.map(|expires_at| view! { <SessionExpiryBanner expires_at=expires_at /> })}
 
             <main class="max-w-7xl mx-auto px-6 py-10">
                 // header section
@@ -80,20 +263,125 @@ pub fn HomeScreen(user: CurrentUser) -> impl IntoView {
                     <p class="text-gray-400">
                         {format!("Welcome back, {}. Manage your projects or create a new one.", user_name)}
                     </p>
+                    {move || {
+                        previous_login.get().and_then(Result::ok).flatten().map(|previous| {
+                            let at = previous.at;
+                            view! {
+                                <p class="text-gray-500 text-sm mt-1">
+                                    {format!(
+                                        "Last login: {:04}-{:02}-{:02} {:02}:{:02} UTC from {}",
+                                        at.year(),
+                                        at.month() as u8,
+                                        at.day(),
+                                        at.hour(),
+                                        at.minute(),
+                                        previous.ip
+                                    )}
+                                </p>
+                            }
+                        })
+                    }}
+                    {move || {
+                        new_login.get().and_then(Result::ok).flatten().map(|notice| {
+                            let at = notice.at;
+                            let browser = notice
+                                .user_agent
+                                .map(|agent| format!(" using {agent}"))
+                                .unwrap_or_default();
+                            view! {
+                                <p class="mt-3 rounded-lg border border-amber-500/40 bg-amber-500/10 px-4 py-2 text-sm text-amber-200">
+                                    {format!(
+                                        "New sign-in to your account: {:04}-{:02}-{:02} {:02}:{:02} UTC from {}{}. Not you? Change your password and sign out everywhere.",
+                                        at.year(),
+                                        at.month() as u8,
+                                        at.day(),
+                                        at.hour(),
+                                        at.minute(),
+                                        notice.ip,
+                                        browser
+                                    )}
+                                </p>
+                            }
+                        })
+                    }}
+                </div>
+
+                <div class="mb-6">
+                    <input
+                        type="search"
+                        class="w-full md:w-80 bg-gray-800 border border-gray-700 rounded-lg px-4 py-2 text-sm text-white placeholder-gray-500 focus:outline-none focus:border-orange-500"
+                        placeholder="Search projects by name"
+                        prop:value=move || search_input.get()
+                        on:input=on_search_input
+                    />
                 </div>
 
-                // Grid Layout
+                <Show when=searching>
+                    <Transition fallback=ProjectsPlaceholder>
+                        {move || {
+                            search_results.get().map(|result| match result {
+                                Ok(matches) if matches.is_empty() => view! {
+                                    <p class="text-gray-500 text-sm">
+                                        {move || format!("No projects match \"{}\".", search_query.get().trim())}
+                                    </p>
+                                }.into_any(),
+                                Ok(matches) => view! {
+                                    <div class="grid grid-cols-1 md:grid-cols-2 lg:grid-cols-4 gap-6">
+                                        {matches.into_iter().map(|project| {
+                                            view! { <ProjectCard project=project /> }
+                                        }).collect_view()}
+                                    </div>
+                                }.into_any(),
+                                Err(e) => view! {
+                                    <div class="bg-red-900/20 border border-red-800 rounded-2xl p-6 text-red-400">
+                                        <p class="font-medium">"Search failed"</p>
+                                        <p class="text-sm mt-1">{e.to_string()}</p>
+                                    </div>
+                                }.into_any(),
+                            })
+                        }}
+                    </Transition>
+                </Show>
+
+                // Grid Layout, kept (hidden) while searching so loaded pages survive
+                <div class:hidden=searching>
                 <Suspense fallback=ProjectsPlaceholder>
                     {move || {
                         projects_resource.get().map(|result| {
                             match result {
-                                Ok(projects) => view! {
+                                Ok(_) => view! {
                                     <div class="grid grid-cols-1 md:grid-cols-2 lg:grid-cols-4 gap-6">
                                         <NewProjectCard />
-                                        {projects.into_iter().map(|project| {
+                                        {move || projects.get().into_iter().map(|project| {
                                             view! { <ProjectCard project=project /> }
                                         }).collect_view()}
                                     </div>
+
+                                    // Count, "Load more" and end-of-list states
+                                    <div class="mt-8 flex flex-col items-center gap-3 text-sm text-gray-500">
+                                        <p>
+                                            {move || match (projects.with(Vec::len), total.get()) {
+                                                (_, 0) => "No projects yet. Create your first one to get started.".to_string(),
+                                                (shown, total) if shown < total => format!("Showing {shown} of {total} projects"),
+                                                (_, 1) => "1 project".to_string(),
+                                                (_, total) => format!("All {total} projects loaded"),
+                                            }}
+                                        </p>
+                                        <Show when=move || next_cursor.get().is_some()>
+                                            <button
+                                                class="bg-gray-700 hover:bg-gray-600 text-white text-sm font-medium py-2 px-6 rounded-lg transition disabled:opacity-50"
+                                                disabled=move || load_more_action.pending().get()
+                                                on:click=move |_| {
+                                                    load_more_action.dispatch(());
+                                                }
+                                            >
+                                                {move || if load_more_action.pending().get() { "Loading..." } else { "Load more" }}
+                                            </button>
+                                        </Show>
+                                        {move || load_more_action.value().get().and_then(Result::err).map(|e| view! {
+                                            <p class="text-red-400 text-xs">{e.to_string()}</p>
+                                        })}
+                                    </div>
                                 }.into_any(),
 
                                 Err(e) => view! {
@@ -109,11 +397,39 @@ pub fn HomeScreen(user: CurrentUser) -> impl IntoView {
                         })
                     }}
                 </Suspense>
+                </div>
+
+                {is_admin.then(|| view! { <UserAdminPanel /> })}
+                {is_admin.then(|| view! { <MaintenancePanel /> })}
+                {is_admin.then(|| view! { <AuditLogPanel /> })}
             </main>
+
+            <Footer />
         </div>
     }
 }
 
+#[component]
+fn Footer() -> impl IntoView {
+    let build_info = Resource::new(|| (), |_| get_build_info());
+
+    view! {
+        <footer class="max-w-7xl mx-auto px-6 py-6 text-xs text-gray-600">
+            <Suspense fallback=|| ()>
+                {move || {
+                    build_info.get().and_then(Result::ok).map(|info| {
+                        view! {
+                            <span title=format!("Built {}", info.built_at.date())>
+                                {format!("Bento v{} ({})", info.version, info.git_sha)}
+                            </span>
+                        }
+                    })
+                }}
+            </Suspense>
+        </footer>
+    }
+}
+
 #[component]
 fn ProjectsPlaceholder() -> impl IntoView {
     view! {
@@ -131,12 +447,18 @@ fn ProjectsPlaceholder() -> impl IntoView {
 fn NavBar() -> impl IntoView {
     let logout_action = ServerAction::<Logout>::new();
     let pending = logout_action.pending();
+    let logout_all_action = ServerAction::<LogoutAll>::new();
 
     // Get context
     let context = expect_context::<HomeContext>();
 
     // Dropdown open/closed state
     let (dropdown_open, set_dropdown_open) = signal(false);
+    let (show_logout_confirm, set_show_logout_confirm) = signal(false);
+    let (show_logout_all_confirm, set_show_logout_all_confirm) = signal(false);
+    let (show_rename, set_show_rename) = signal(false);
+    let csrf = expect_context::<CsrfContext>();
+    let toasts = expect_context::<ToastContext>();
 
     // Handle redirect after successful logout
     Effect::watch(
@@ -148,15 +470,35 @@ fn NavBar() -> impl IntoView {
         },
         false,
     );
+    // The login screen reports how many sessions were ended
+    Effect::watch(
+        move || logout_all_action.value().get(),
+        move |result, _, _| match result {
+            Some(Ok(count)) => {
+                let _ = window()
+                    .location()
+                    .set_href(&format!("/?signed_out={count}"));
+            }
+            Some(Err(e)) => {
+                set_show_logout_all_confirm.set(false);
+                toasts.push_toast(ToastKind::Error, e.to_string());
+            }
+            None => {}
+        },
+        false,
+    );
 
     let username = context.user.username.clone();
+    let is_admin = context.user.role == Role::Admin;
+    let session_count = context.user.active_session_count;
+    let branding = use_branding();
 
     view! {
         <nav class="flex items-center justify-between px-6 py-4 border-b border-gray-800/60 bg-[#16171f]">
             // Left side: Logo
             <div class="flex items-center space-x-3">
                 <LogoSvg size=8 />
-                <span class="text-xl font-bold text-white tracking-tight">"Bento"</span>
+                <span class="text-xl font-bold text-white tracking-tight">{move || branding.get().app_name}</span>
             </div>
 
             // Right side: Icons and User Info
@@ -205,34 +547,109 @@ fn NavBar() -> impl IntoView {
                         />
 
                         // Dropdown content
-                        <div class="absolute right-0 mt-2 w-48 bg-[#1f2029] border border-gray-700/50 rounded-xl shadow-xl shadow-black/30 z-20 overflow-hidden">
-                            // Manage users option
+                        <div class="absolute right-0 mt-2 w-56 bg-[#1f2029] border border-gray-700/50 rounded-xl shadow-xl shadow-black/30 z-20 overflow-hidden">
+                            // Manage users option, jumps to the panel below the projects
+                            {is_admin.then(|| view! {
+                                <a
+                                    href="#manage-users"
+                                    class="flex items-center w-full px-4 py-3 text-sm text-gray-300 hover:bg-[#252630] hover:text-white transition"
+                                    on:click=move |_| set_dropdown_open.set(false)
+                                >
+                                    <UserIcon class="w-4 h-4 mr-3" />
+                                    "Manage Users"
+                                </a>
+
+                                // Divider
+                                <div class="border-t border-gray-700/50" />
+                            })}
+
+                            <a
+                                href="/settings"
+                                class="flex items-center w-full px-4 py-3 text-sm text-gray-300 hover:bg-[#252630] hover:text-white transition"
+                            >
+                                <UserIcon class="w-4 h-4 mr-3" />
+                                <span>"Settings"</span>
+                            </a>
+
                             <button
                                 class="flex items-center w-full px-4 py-3 text-sm text-gray-300 hover:bg-[#252630] hover:text-white transition"
-                                on:click=move |_| set_dropdown_open.set(false)
+                                on:click=move |_| {
+                                    set_dropdown_open.set(false);
+                                    set_show_rename.set(true);
+                                }
                             >
                                 <UserIcon class="w-4 h-4 mr-3" />
-                                "Manage Users"
+                                <span>"Change username"</span>
                             </button>
 
-                            // Divider
-                            <div class="border-t border-gray-700/50" />
+                            // Logout option, confirmed first
+                            <button
+                                class="flex items-center w-full px-4 py-3 text-sm text-gray-300 hover:bg-[#252630] hover:text-white transition"
+                                on:click=move |_| {
+                                    set_dropdown_open.set(false);
+                                    set_show_logout_confirm.set(true);
+                                }
+                            >
+                                <LogoutIcon class="w-4 h-4 mr-3" />
+                                <span>"Logout"</span>
+                            </button>
 
-                            // Logout option
-                            <ActionForm action=logout_action>
-                                <button
-                                    type="submit"
-                                    class="flex items-center w-full px-4 py-3 text-sm text-gray-300 hover:bg-[#252630] hover:text-white transition"
-                                    disabled=move || pending.get()
-                                >
-                                    <LogoutIcon class="w-4 h-4 mr-3" />
-                                    <span>{move || if pending.get() { "Logging out..." } else { "Logout" }}</span>
-                                </button>
-                            </ActionForm>
+                            // Ends every session, not just this one
+                            <button
+                                class="flex items-center w-full px-4 py-3 text-sm text-red-400 hover:bg-[#252630] hover:text-red-300 transition"
+                                on:click=move |_| {
+                                    set_dropdown_open.set(false);
+                                    set_show_logout_all_confirm.set(true);
+                                }
+                            >
+                                <LogoutIcon class="w-4 h-4 mr-3" />
+                                <span>"Log out of all devices"</span>
+                                {session_count.map(|count| view! {
+                                    <span
+                                        class="ml-auto px-2 py-0.5 rounded-full bg-red-900/40 text-xs text-red-300"
+                                        title="Active sessions"
+                                    >
+                                        {count}
+                                    </span>
+                                })}
+                            </button>
                         </div>
                     </Show>
                 </div>
             </div>
+
+            <ConfirmModal
+                open=show_logout_confirm
+                title="Log out?"
+                body="You'll need to sign in again to manage your projects."
+                confirm_label="Logout"
+                pending_label="Logging out..."
+                pending=pending
+                on_confirm=move || {
+                    logout_action.dispatch(Logout { csrf_token: csrf.token() });
+                }
+                on_cancel=move || set_show_logout_confirm.set(false)
+            />
+
+            <ConfirmModal
+                open=show_logout_all_confirm
+                title="Log out of all devices?"
+                body="Every session of your account ends, including this one. Anyone else using it will have to sign in again."
+                confirm_label="Log out everywhere"
+                pending_label="Logging out..."
+                pending=logout_all_action.pending()
+                danger=true
+                on_confirm=move || {
+                    logout_all_action.dispatch(LogoutAll { csrf_token: csrf.token() });
+                }
+                on_cancel=move || set_show_logout_all_confirm.set(false)
+            />
+
+            <ChangeUsernameModal
+                open=show_rename
+                current=context.user.username.clone()
+                on_close=move || set_show_rename.set(false)
+            />
         </nav>
     }
 }
@@ -242,10 +659,14 @@ fn NewProjectCard() -> impl IntoView {
     // Get context
     let context = expect_context::<HomeContext>();
     let create_action = context.create_action;
+    let project_limit = context.project_limit;
 
     let (show_form, set_show_form) = signal(false);
     let (name, set_name) = signal(String::new());
     let (description, set_description) = signal(String::new());
+    // Resubmitting after a failed or lost response reuses the key, so the project can't be
+    // created twice; a fresh one is drawn once creation succeeds
+    let (idempotency_key, set_idempotency_key) = signal(Uuid::now_v7().to_string());
 
     let pending = create_action.pending();
 
@@ -257,6 +678,7 @@ fn NewProjectCard() -> impl IntoView {
                 set_show_form.set(false);
                 set_name.set(String::new());
                 set_description.set(String::new());
+                set_idempotency_key.set(Uuid::now_v7().to_string());
             }
         },
         false,
@@ -279,10 +701,11 @@ fn NewProjectCard() -> impl IntoView {
                     <p class="relative z-10 text-gray-500 text-sm mb-8">"Set up a new backend in seconds."</p>
 
                     <button
-                        class="relative z-10 bg-[#e35b2d] hover:bg-[#ff6b3d] text-white text-sm font-semibold py-2.5 px-6 rounded-lg w-full transition-all duration-300 shadow-lg shadow-orange-900/30 hover:shadow-orange-600/40 transform hover:-translate-y-0.5"
+                        class="relative z-10 bg-[var(--bento-primary)] hover:brightness-110 text-white text-sm font-semibold py-2.5 px-6 rounded-lg w-full transition-all duration-300 shadow-lg shadow-orange-900/30 hover:shadow-orange-600/40 transform hover:-translate-y-0.5 disabled:opacity-50 disabled:cursor-not-allowed disabled:transform-none"
+                        disabled=move || project_limit.with(Option::is_some)
                         on:click=move |_| set_show_form.set(true)
                     >
-                        "Create Project"
+                        {move || project_limit.get().unwrap_or_else(|| "Create Project".to_string())}
                     </button>
                 }
             >
@@ -294,7 +717,7 @@ fn NewProjectCard() -> impl IntoView {
                         let name_val = name.get();
                         let desc_val = description.get();
                         let desc = if desc_val.trim().is_empty() { None } else { Some(desc_val) };
-                        create_action.dispatch((name_val, desc));
+                        create_action.dispatch((name_val, desc, idempotency_key.get()));
                     }
                 >
                     <div class="text-left">
@@ -335,7 +758,7 @@ fn NewProjectCard() -> impl IntoView {
                         </button>
                         <button
                             type="submit"
-                            class="flex-1 bg-[#e35b2d] hover:bg-[#ff6b3d] text-white text-sm font-semibold py-2 px-4 rounded-lg transition disabled:opacity-50 disabled:cursor-not-allowed"
+                            class="flex-1 bg-[var(--bento-primary)] hover:brightness-110 text-white text-sm font-semibold py-2 px-4 rounded-lg transition disabled:opacity-50 disabled:cursor-not-allowed"
                             disabled=move || pending.get() || name.get().trim().is_empty()
                         >
                             {move || if pending.get() { "Creating..." } else { "Create" }}
@@ -352,10 +775,36 @@ fn ProjectCard(project: ProjectSummary) -> impl IntoView {
     // Get context
     let context = expect_context::<HomeContext>();
     let delete_action = context.delete_action;
+    let duplicate_action = context.duplicate_action;
 
     let icon_class = "w-4 h-4 text-gray-600 mr-2.5";
-    let project_id = project.id.0.to_string();
+    // the opaque form is shown, and accepted by the server functions like the UUID
+    let project_id = project.id.prefixed();
     let project_id_for_delete = project_id.clone();
+    let project_id_for_duplicate = project_id.clone();
+    let project_id_for_rate_limit = project_id.clone();
+    let project_id_for_visibility = project_id.clone();
+    let project_id_for_activity = project_id.clone();
+    let metrics = Resource::new(
+        {
+            let project_id = project_id.clone();
+            move || project_id.clone()
+        },
+        get_project_metrics,
+    );
+    // "—" until the collector has measured the project
+    let metric = move |render: fn(&ProjectMetrics) -> String| {
+        move || {
+            metrics
+                .get()
+                .and_then(Result::ok)
+                .filter(|metrics| metrics.collected_at.is_some())
+                .map(|metrics| render(&metrics))
+                .unwrap_or_else(|| "—".to_string())
+        }
+    };
+    let export_url = format!("{}?project_id={project_id}", ExportProjectArchive::PATH);
+    let (show_activity, set_show_activity) = signal(false);
 
     let (show_delete_confirm, set_show_delete_confirm) = signal(false);
     let pending = delete_action.pending();
@@ -371,6 +820,68 @@ fn ProjectCard(project: ProjectSummary) -> impl IntoView {
         false,
     );
 
+    // Inline rate limit editor; an empty value removes the limit
+    let csrf = expect_context::<CsrfContext>();
+    let projects_resource = context.projects_resource;
+    let search_results = context.search_results;
+    let (editing_rate_limit, set_editing_rate_limit) = signal(false);
+    let (rate_limit_input, set_rate_limit_input) = signal(
+        project
+            .rate_limit_rpm
+            .map(|rpm| rpm.to_string())
+            .unwrap_or_default(),
+    );
+    let rate_limit_action = Action::new(move |rate_limit_rpm: &Option<u32>| {
+        let project_id = project_id_for_rate_limit.clone();
+        let rate_limit_rpm = *rate_limit_rpm;
+        let csrf_token = csrf.token();
+        async move {
+            update_project(
+                project_id,
+                None,
+                None,
+                Some(rate_limit_rpm),
+                None,
+                csrf_token,
+            )
+            .await
+        }
+    });
+    Effect::watch(
+        move || rate_limit_action.value().get(),
+        move |result, _, _| {
+            if matches!(result.as_ref(), Some(Ok(_))) {
+                set_editing_rate_limit.set(false);
+                projects_resource.refetch();
+                search_results.refetch();
+            }
+        },
+        false,
+    );
+    // Visibility selector, saved as soon as it changes
+    let visibility_action = Action::new(move |visibility: &ProjectVisibility| {
+        let project_id = project_id_for_visibility.clone();
+        let visibility = *visibility;
+        let csrf_token = csrf.token();
+        async move { update_project(project_id, None, None, None, Some(visibility), csrf_token).await }
+    });
+    Effect::watch(
+        move || visibility_action.value().get(),
+        move |result, _, _| {
+            if matches!(result.as_ref(), Some(Ok(_))) {
+                projects_resource.refetch();
+                search_results.refetch();
+            }
+        },
+        false,
+    );
+    let visibility = project.visibility;
+
+    let rate_limit_label = project
+        .rate_limit_rpm
+        .map(|rpm| format!("{rpm}/min"))
+        .unwrap_or_else(|| "Unlimited".to_string());
+
     // Format the created_at date
     let created_at = project.created_at;
     let date_str = format!(
@@ -382,6 +893,28 @@ fn ProjectCard(project: ProjectSummary) -> impl IntoView {
 
     view! {
         <div class="bg-[#1e1f25] border border-gray-800/60 rounded-2xl p-6 flex flex-col h-full justify-between shadow-xl shadow-black/20 hover:border-gray-700 transition-all duration-200 relative group">
+            // Duplicate button (shown on hover)
+            <button
+                class="absolute top-3 right-12 w-8 h-8 rounded-lg hover:bg-gray-700/50 flex items-center justify-center text-gray-500 hover:text-gray-200 transition opacity-0 group-hover:opacity-100 disabled:opacity-50"
+                title="Duplicate project"
+                disabled=move || duplicate_action.pending().get()
+                on:click=move |_| {
+                    duplicate_action.dispatch(project_id_for_duplicate.clone());
+                }
+            >
+                <DuplicateIcon class="w-4 h-4" />
+            </button>
+
+            // Export button (shown on hover)
+            <a
+                class="absolute top-3 right-[5.25rem] w-8 h-8 rounded-lg hover:bg-gray-700/50 flex items-center justify-center text-gray-500 hover:text-gray-200 transition opacity-0 group-hover:opacity-100"
+                title="Export project"
+                href=export_url
+                download
+            >
+                <DownloadIcon class="w-4 h-4" />
+            </a>
+
             // Delete button (shown on hover)
             <button
                 class="absolute top-3 right-3 w-8 h-8 rounded-lg bg-red-900/0 hover:bg-red-900/50 flex items-center justify-center text-gray-500 hover:text-red-400 transition opacity-0 group-hover:opacity-100"
@@ -416,47 +949,144 @@ fn ProjectCard(project: ProjectSummary) -> impl IntoView {
 
                     <div class="flex items-center">
                         <DatabaseIcon class=icon_class />
-                        <span class="text-gray-400 text-sm"><strong class="text-gray-200 font-medium mr-1">"—"</strong> "Storage"</span>
+                        <span class="text-gray-400 text-sm"><strong class="text-gray-200 font-medium mr-1"><Transition fallback=|| "—">{metric(|m| format_bytes(m.storage_bytes))}</Transition></strong> "Storage"</span>
                     </div>
 
                     <div class="flex items-center">
                         <LockIcon class=icon_class />
-                        <span class="text-gray-400 text-sm"><strong class="text-gray-200 font-medium mr-1">"—"</strong> "Users"</span>
+                        <span class="text-gray-400 text-sm"><strong class="text-gray-200 font-medium mr-1"><Transition fallback=|| "—">{metric(|m| m.users.to_string())}</Transition></strong> "Users"</span>
                     </div>
-                </div>
-            </div>
-
-            // Delete confirmation modal
-            <Show when=move || show_delete_confirm.get()>
-                <div class="absolute inset-0 bg-[#1e1f25]/95 rounded-2xl flex flex-col items-center justify-center p-6 z-10">
-                    <TrashIcon class="w-8 h-8 text-red-400 mb-3" />
-                    <p class="text-gray-200 text-sm font-medium mb-1">"Delete this project?"</p>
-                    <p class="text-gray-500 text-xs mb-4 text-center">"This action cannot be undone."</p>
 
-                    <div class="flex gap-2 w-full">
-                        <button
-                            class="flex-1 bg-gray-700 hover:bg-gray-600 text-white text-sm font-medium py-2 px-4 rounded-lg transition"
-                            on:click=move |_| set_show_delete_confirm.set(false)
-                            disabled=move || pending.get()
+                    <div class="flex items-center">
+                        <ChartBarIcon class=icon_class />
+                        <Show
+                            when=move || editing_rate_limit.get()
+                            fallback=move || view! {
+                                <button
+                                    class="text-gray-400 text-sm hover:text-gray-200 transition"
+                                    title="Edit rate limit"
+                                    on:click=move |_| set_editing_rate_limit.set(true)
+                                >
+                                    "Rate limit: "<strong class="text-gray-200 font-medium">{rate_limit_label.clone()}</strong>
+                                </button>
+                            }
                         >
-                            "Cancel"
-                        </button>
-                        <button
-                            type="button"
-                            class="flex-1 bg-red-600 hover:bg-red-500 text-white text-sm font-semibold py-2 px-4 rounded-lg transition disabled:opacity-50"
-                            disabled=move || pending.get()
-                            on:click={
-                                let project_id = project_id_for_delete.clone();
-                                move |_| {
-                                    delete_action.dispatch(project_id.clone());
+                            <form
+                                class="flex items-center gap-2"
+                                on:submit=move |ev: leptos::ev::SubmitEvent| {
+                                    ev.prevent_default();
+                                    let value = rate_limit_input.get();
+                                    match value.trim() {
+                                        "" => {
+                                            rate_limit_action.dispatch(None);
+                                        }
+                                        rpm => {
+                                            if let Ok(rpm) = rpm.parse::<u32>() {
+                                                rate_limit_action.dispatch(Some(rpm));
+                                            }
+                                        }
+                                    }
+                                }
+                            >
+                                <input
+                                    type="number"
+                                    min="1"
+                                    placeholder="Unlimited"
+                                    class="w-24 bg-[#252630] border border-gray-700 rounded-lg px-2 py-1 text-white text-sm focus:outline-none focus:border-orange-500 transition"
+                                    prop:value=move || rate_limit_input.get()
+                                    on:input=move |ev| set_rate_limit_input.set(event_target_value(&ev))
+                                />
+                                <span class="text-gray-500 text-xs">"/min"</span>
+                                <button
+                                    type="submit"
+                                    class="text-orange-400 hover:text-orange-300 text-sm font-medium transition disabled:opacity-50"
+                                    disabled=move || rate_limit_action.pending().get()
+                                >
+                                    "Save"
+                                </button>
+                            </form>
+                        </Show>
+                    </div>
+                    {move || rate_limit_action.value().get().and_then(Result::err).map(|e| view! {
+                        <p class="text-red-400 text-xs">{e.to_string()}</p>
+                    })}
+
+                    <div class="flex items-center">
+                        <UsersIcon class=icon_class />
+                        <label class="text-gray-400 text-sm mr-2">"Visibility"</label>
+                        <select
+                            class="bg-[#252630] border border-gray-700 rounded-lg px-2 py-1 text-white text-sm focus:outline-none focus:border-orange-500 transition disabled:opacity-50"
+                            disabled=move || visibility_action.pending().get()
+                            on:change=move |ev| {
+                                if let Some(visibility) = ProjectVisibility::parse(&event_target_value(&ev)) {
+                                    visibility_action.dispatch(visibility);
                                 }
                             }
                         >
-                            {move || if pending.get() { "Deleting..." } else { "Delete" }}
-                        </button>
+                            {ProjectVisibility::ALL
+                                .into_iter()
+                                .map(|option| view! {
+                                    <option value=option.as_str() selected=option == visibility>
+                                        {option.as_str()}
+                                    </option>
+                                })
+                                .collect_view()}
+                        </select>
                     </div>
+                    {move || visibility_action.value().get().and_then(Result::err).map(|e| view! {
+                        <p class="text-red-400 text-xs">{e.to_string()}</p>
+                    })}
                 </div>
-            </Show>
+
+                // Activity feed, fetched when first expanded
+                <button
+                    class="mt-4 text-gray-500 hover:text-gray-300 text-xs font-medium transition"
+                    on:click=move |_| set_show_activity.update(|show| *show = !*show)
+                >
+                    {move || if show_activity.get() { "Hide activity" } else { "Show activity" }}
+                </button>
+                <Show when=move || show_activity.get()>
+                    <div class="mt-3">
+                        <ProjectActivityFeed project_id=project_id_for_activity.clone() />
+                    </div>
+                </Show>
+            </div>
+
+            // Delete confirmation modal
+            {if project.delete_protected {
+                // deleting would be refused anyway; point to where protection is turned off
+                let settings_url = format!("/p/{}#settings", project.slug);
+                view! {
+                    <ConfirmModal
+                        open=show_delete_confirm
+                        title=format!("{} is protected from deletion", project.name)
+                        body="Turn off delete protection in the project's settings to delete it."
+                        confirm_label="Open settings"
+                        on_confirm=move || {
+                            let _ = window().location().set_href(&settings_url);
+                        }
+                        on_cancel=move || set_show_delete_confirm.set(false)
+                    />
+                }
+                    .into_any()
+            } else {
+                view! {
+                    <ConfirmModal
+                        open=show_delete_confirm
+                        title=format!("Delete {}?", project.name)
+                        body="This action cannot be undone."
+                        confirm_label="Delete"
+                        pending_label="Deleting..."
+                        pending=pending
+                        danger=true
+                        on_confirm=move || {
+                            delete_action.dispatch(project_id_for_delete.clone());
+                        }
+                        on_cancel=move || set_show_delete_confirm.set(false)
+                    />
+                }
+                    .into_any()
+            }}
         </div>
     }
 }
@@ -482,3 +1112,90 @@ fn ProjectCardSkeleton() -> impl IntoView {
         </div>
     }
 }
+
+/// Appends `page` to `projects`, skipping projects already listed.
+///
+/// Pages continue from a cursor and don't overlap, but a page that does (e.g. one loaded
+/// before a refetch of the first page settled) must not list a project twice.
+fn append_page(projects: &mut Vec<ProjectSummary>, page: Vec<ProjectSummary>) {
+    for project in page {
+        if !projects.iter().any(|existing| existing.id == project.id) {
+            projects.push(project);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ProjectId;
+    use time::OffsetDateTime;
+
+    fn summary(name: &str) -> ProjectSummary {
+        ProjectSummary {
+            id: ProjectId::new(),
+            name: name.to_string(),
+            description: None,
+            created_at: OffsetDateTime::now_utc(),
+            rate_limit_rpm: None,
+            visibility: ProjectVisibility::Private,
+            slug: name.to_string(),
+            delete_protected: false,
+        }
+    }
+
+    #[test]
+    fn create_button_follows_the_project_count() {
+        // signals live in the arena of a reactive owner
+        Owner::new().with(|| {
+            let limits = RwSignal::new(InstanceLimits {
+                max_projects_per_user: Some(3),
+            });
+            let count = RwSignal::new(2);
+            let notice = project_limit_notice(limits.into(), count.into());
+            assert_eq!(notice.get_untracked(), None);
+
+            count.set(3);
+            assert_eq!(
+                notice.get_untracked().as_deref(),
+                Some("Project limit reached (3/3)")
+            );
+
+            // deleting a project re-enables it
+            count.set(2);
+            assert_eq!(notice.get_untracked(), None);
+
+            // as does lifting the limit
+            count.set(5);
+            assert!(notice.get_untracked().is_some());
+            limits.set(InstanceLimits::default());
+            assert_eq!(notice.get_untracked(), None);
+        });
+    }
+
+    #[test]
+    fn appended_pages_keep_order() {
+        let (a, b, c) = (summary("a"), summary("b"), summary("c"));
+        let mut projects = vec![a.clone()];
+
+        append_page(&mut projects, vec![b.clone(), c.clone()]);
+        assert_eq!(projects, vec![a, b, c]);
+
+        append_page(&mut projects, Vec::new());
+        assert_eq!(projects.len(), 3);
+    }
+
+    #[test]
+    fn appended_pages_skip_already_listed_projects() {
+        let (a, b, c) = (summary("a"), summary("b"), summary("c"));
+        let mut projects = vec![a.clone(), b.clone()];
+
+        // a project was deleted before "Load more", so the next page starts one item early
+        append_page(&mut projects, vec![b.clone(), c.clone()]);
+        assert_eq!(projects, vec![a.clone(), b.clone(), c.clone()]);
+
+        // loading the same page twice adds nothing
+        append_page(&mut projects, vec![b, c]);
+        assert_eq!(projects.len(), 3);
+    }
+}
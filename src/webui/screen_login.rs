@@ -1,20 +1,45 @@
-use crate::types::AppError;
-use crate::webui::LogoSvg;
+use crate::types::{AppError, LoginError, LoginField, UserStatus};
+use crate::webui::components::Toast;
+use crate::webui::csrf::CsrfField;
+use crate::webui::{LogoSvg, use_branding};
 use leptos::{form::ActionForm, prelude::*};
+use leptos_router::hooks::use_query_map;
 
 #[component]
 pub fn LoginScreen() -> impl IntoView {
     let login_action = ServerAction::<Login>::new();
     let pending = login_action.pending();
+    let branding = use_branding();
     let action_value = login_action.value();
+    // set after "Log out of all devices", to the number of sessions that were ended
+    let query = use_query_map();
+    // where to go once signed in, e.g. set when a signed-out visitor opened /settings
+    let redirect_to = move || query.with(|query| query.get("redirect_to"));
+    let signed_out = move || {
+        query.with(|query| {
+            query
+                .get("signed_out")
+                .and_then(|n| n.parse::<usize>().ok())
+        })
+    };
 
     let has_success = move || matches!(action_value.get().as_ref(), Some(Ok(_)));
+    let login_error = move || action_value.get().and_then(|res| res.err());
+    // errors about a specific input are shown next to it, the rest below the form
+    let field_error = move |field: LoginField| {
+        login_error()
+            .filter(|err| err.field() == Some(field))
+            .map(|err| err.to_string())
+    };
     let error_message = move || {
-        action_value
-            .get()
-            .as_ref()
-            .and_then(|res| res.as_ref().err())
-            .map(|err| err.message().to_string())
+        login_error()
+            .filter(|err| err.field().is_none())
+            .map(|err| err.to_string())
+    };
+    // at the session limit, the sessions one of which can be signed out to get in
+    let limit_sessions = move || match login_error() {
+        Some(LoginError::SessionLimitReached(sessions)) => Some(sessions),
+        _ => None,
     };
 
     // handle client-side refresh after successful login
@@ -22,8 +47,12 @@ pub fn LoginScreen() -> impl IntoView {
         move || action_value.get(),
         move |result, _, _| {
             if matches!(result.as_ref(), Some(Ok(_))) {
-                // force a full page reload to ensure session is properly loaded
-                let _ = window().location().set_href("/");
+                // force a full page reload to ensure session is properly loaded; with
+                // `[server] server_login_redirect` the response already navigated there
+                let target = redirect_to();
+                let _ = window()
+                    .location()
+                    .set_href(login_redirect_target(target.as_deref()));
             }
         },
         false,
@@ -43,12 +72,16 @@ pub fn LoginScreen() -> impl IntoView {
                 <div class="text-center">
                     <LogoSvg size=16 class="mx-auto mb-4 opacity-90 drop-shadow-lg" />
                     <h1 class="text-3xl font-bold tracking-tight mb-2 text-white">
-                        "Sign in to Bento"
+                        {move || format!("Sign in to {}", branding.get().app_name)}
                     </h1>
                     <p class="text-stone-400">"Enter your username and password to continue."</p>
                 </div>
 
                 <ActionForm action=login_action>
+                    <CsrfField />
+                    {move || redirect_to().map(|target| view! {
+                        <input type="hidden" name="redirect_to" value=target />
+                    })}
                     <div class="bg-[#18181b] border-t border-white/10 border-b border-black/50 border-x border-white/5 rounded-2xl p-8 shadow-xl shadow-black/60 backdrop-blur-sm">
                         <div class="space-y-6">
                             <div class="space-y-1.5">
@@ -68,6 +101,11 @@ pub fn LoginScreen() -> impl IntoView {
                                     autocomplete="username"
                                     placeholder="Username"
                                 />
+                                <Show when=move || field_error(LoginField::Username).is_some() fallback=|| ()>
+                                    <p class="text-sm text-red-300 ml-1">
+                                        {move || field_error(LoginField::Username).unwrap_or_default()}
+                                    </p>
+                                </Show>
                             </div>
 
                             <div class="space-y-1.5">
@@ -87,15 +125,56 @@ pub fn LoginScreen() -> impl IntoView {
                                     autocomplete="current-password"
                                     placeholder="••••••••"
                                 />
+                                <Show when=move || field_error(LoginField::Password).is_some() fallback=|| ()>
+                                    <p class="text-sm text-red-300 ml-1">
+                                        {move || field_error(LoginField::Password).unwrap_or_default()}
+                                    </p>
+                                </Show>
                             </div>
 
                             <button
-                                class="w-full relative group overflow-hidden bg-gradient-to-b from-[#e35b2d] to-[#c2411c] hover:to-[#d64c23] border-t border-white/20 border-b border-black/20 text-white font-semibold py-3 rounded-lg transition-all duration-200 shadow-lg shadow-orange-900/40 active:scale-[0.98] active:shadow-none mt-4"
+                                class="w-full relative group overflow-hidden bg-gradient-to-b from-[var(--bento-primary)] to-[var(--bento-primary-dark)] hover:brightness-110 border-t border-white/20 border-b border-black/20 text-white font-semibold py-3 rounded-lg transition-all duration-200 shadow-lg shadow-orange-900/40 active:scale-[0.98] active:shadow-none mt-4"
                                 type="submit"
                                 disabled=move || pending.get()
                             >
                                 {move || if pending.get() { "Signing in..." } else { "Sign In" }}
                             </button>
+
+                            {move || limit_sessions().map(|sessions| view! {
+                                <div class="space-y-2">
+                                    <p class="text-sm text-stone-400 ml-1">"Sign out one of your sessions to continue:"</p>
+                                    {sessions.into_iter().map(|session| {
+                                        let seen = session.last_seen_at;
+                                        view! {
+                                            <div class="flex items-center justify-between gap-3 bg-[#232326] border border-white/5 rounded-lg px-4 py-2 text-sm">
+                                                <div>
+                                                    <p class="text-stone-200">{session.ip.to_string()}</p>
+                                                    <p class="text-xs text-stone-500">
+                                                        {format!(
+                                                            "Last seen {:04}-{:02}-{:02} {:02}:{:02} UTC",
+                                                            seen.year(),
+                                                            seen.month() as u8,
+                                                            seen.day(),
+                                                            seen.hour(),
+                                                            seen.minute()
+                                                        )}
+                                                    </p>
+                                                </div>
+                                                // submits the credentials again, naming the session to end
+                                                <button
+                                                    class="text-xs font-medium text-red-300 hover:text-red-200 border border-red-500/20 rounded-md px-3 py-1.5 transition"
+                                                    type="submit"
+                                                    name="sign_out_session"
+                                                    value=session.id
+                                                    disabled=move || pending.get()
+                                                >
+                                                    "Sign out & continue"
+                                                </button>
+                                            </div>
+                                        }
+                                    }).collect_view()}
+                                </div>
+                            })}
                         </div>
                     </div>
                 </ActionForm>
@@ -112,28 +191,264 @@ pub fn LoginScreen() -> impl IntoView {
                     </div>
                 </Show>
             </div>
+
+            {move || signed_out().map(|count| view! { <Toast message=signed_out_message(count) /> })}
         </div>
     }
 }
 
+fn signed_out_message(count: usize) -> String {
+    match count {
+        1 => "Signed out of 1 session".to_string(),
+        n => format!("Signed out of {n} sessions on all devices"),
+    }
+}
+
+/// Where to land after signing in: `redirect_to` when it is a path on this site, else "/".
+///
+/// Anything that could lead off the site (`https://…`, `//host`, `/\host`) falls back to
+/// "/", so the parameter can't be used to send people elsewhere.
+pub fn login_redirect_target(redirect_to: Option<&str>) -> &str {
+    match redirect_to {
+        Some(path)
+            if path.starts_with('/')
+                && !path.starts_with("//")
+                && !path.contains('\\')
+                && !path.chars().any(char::is_control) =>
+        {
+            path
+        }
+        _ => "/",
+    }
+}
+
+/// Adds the headers redirecting a successful login to `target`, returning the status to
+/// answer with (`[server] server_login_redirect`).
+///
+/// Plain form posts (`accepts_html`, i.e. no JavaScript) get a `303 See Other`. Calls from
+/// the hydrated page keep their 200; leptos' redirect header makes the client navigate.
+#[cfg(feature = "ssr")]
+fn login_redirect(
+    headers: &mut axum::http::HeaderMap,
+    target: &str,
+    accepts_html: bool,
+) -> Option<axum::http::StatusCode> {
+    use axum::http::{HeaderName, HeaderValue, StatusCode, header::LOCATION};
+    use leptos::server_fn::redirect::REDIRECT_HEADER;
+
+    // the target was checked to be a plain path, so this only fails on odd bytes
+    let location = HeaderValue::from_str(target).unwrap_or(HeaderValue::from_static("/"));
+    headers.insert(LOCATION, location.clone());
+    headers.insert(HeaderName::from_static(REDIRECT_HEADER), location);
+    accepts_html.then_some(StatusCode::SEE_OTHER)
+}
+
+/// Server function to sign in with a username and password.
+///
+/// Errors are returned as a structured [LoginError] so the form can point at the field
+/// that needs fixing. `redirect_to` is where the user was headed, see
+/// [login_redirect_target]. `sign_out_session` is a session picked from
+/// `LoginError::SessionLimitReached` to end, making room for this one.
 #[server]
-pub async fn login(username: String, password: String) -> Result<(), AppError> {
+pub async fn login(
+    username: String,
+    password: String,
+    csrf_token: String,
+    #[server(default)] redirect_to: Option<String>,
+    #[server(default)] sign_out_session: Option<String>,
+) -> Result<(), LoginError> {
+    use crate::config::LOCAL_CONF;
+    use crate::server::AppState;
+    use crate::types::UserAgentHash;
     use crate::webui::authenticate_user;
-    use crate::webui::cookies::set_session_cookie;
+    use crate::webui::cookies::{set_previous_login_cookie, set_session_cookie};
+    use crate::webui::csrf::verify_csrf;
+
+    let _timer = crate::perf::timer("login");
+
+    verify_csrf(&csrf_token).await?;
+    use axum::http::HeaderMap;
     use axum_client_ip::ClientIp;
     use leptos_axum::ResponseOptions;
 
-    // Extract client IP and response context
+    // Extract client IP, browser and response context
     let response = expect_context::<ResponseOptions>();
     let ClientIp(client_ip) = leptos_axum::extract().await?;
+    let headers: HeaderMap = leptos_axum::extract().await?;
 
     // Authenticate user and issue session
-    let session = authenticate_user(&username, &password, client_ip).await?;
+    let (session, previous_login) = authenticate_user(
+        &username,
+        &password,
+        client_ip,
+        UserAgentHash::from_headers(&headers),
+        sign_out_session.as_deref(),
+    )
+    .await?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let notified = crate::login_notice::notify_new_login(
+        app_state.auth_store.as_ref(),
+        &LOCAL_CONF.security,
+        previous_login.as_ref(),
+        &session,
+        &headers,
+    )
+    .await;
+    if let Err(e) = notified {
+        tracing::warn!("Failed to leave new sign-in notices: {e}");
+    }
 
     // Set the session cookie
     set_session_cookie(&response, session.id.as_str());
+    if let Some(previous_login) = previous_login {
+        set_previous_login_cookie(&response, &previous_login);
+    }
 
-    // note: server-side redirect doesn't work with streaming SSR.
-    // client-side redirect is handled in the [LoginScreen] component via an Effect.
+    // a redirect can't be issued from the streamed page itself, only from this response;
+    // without `server_login_redirect` the [LoginScreen] Effect reloads the page instead
+    if LOCAL_CONF.server.server_login_redirect {
+        let accepts_html = headers
+            .get(axum::http::header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains("text/html"));
+        let mut redirect_headers = HeaderMap::new();
+        let status = login_redirect(
+            &mut redirect_headers,
+            login_redirect_target(redirect_to.as_deref()),
+            accepts_html,
+        );
+        for (name, value) in redirect_headers.iter() {
+            response.insert_header(name.clone(), value.clone());
+        }
+        if let Some(status) = status {
+            response.set_status(status);
+        }
+    }
     Ok(())
 }
+
+/// Server function to create an account and sign in.
+///
+/// Requires `[registration] allow_registration`. An invite code, when given, grants the
+/// role it was minted with; with `require_invite` set, registering without one fails.
+///
+/// Returns the new account's status: with `require_approval` set, self-registered accounts
+/// start out disabled and aren't signed in.
+#[server]
+pub async fn register(
+    username: String,
+    password: String,
+    csrf_token: String,
+    invite_code: Option<String>,
+) -> Result<UserStatus, AppError> {
+    use crate::config::LOCAL_CONF;
+    use crate::registration;
+    use crate::server::AppState;
+    use crate::types::{AuditKind, PasswordHash, UserAgentHash, Username};
+    use crate::webui::cookies::set_session_cookie;
+    use crate::webui::csrf::verify_csrf;
+    use crate::webui::{check_password_len, record_audit};
+    use axum::http::HeaderMap;
+    use axum_client_ip::ClientIp;
+    use leptos_axum::ResponseOptions;
+
+    let _timer = crate::perf::timer("register");
+
+    verify_csrf(&csrf_token).await?;
+    registration::check_allowed(&LOCAL_CONF.registration, invite_code.as_deref())?;
+
+    let response = expect_context::<ResponseOptions>();
+    let ClientIp(client_ip) = leptos_axum::extract().await?;
+    let headers: HeaderMap = leptos_axum::extract().await?;
+
+    let username = Username::parse(&username)?;
+    check_password_len(&LOCAL_CONF.credentials, "password", &password)?;
+    let pass_hash = PasswordHash::try_from(password.as_str())
+        .map_err(|_| AppError::new("Failed to process password"))?;
+
+    let app_state: AppState = use_context().expect("Axum state in leptos context");
+    let auth_store = app_state.auth_store.clone();
+
+    let user = registration::create_user(
+        auth_store.as_ref(),
+        &LOCAL_CONF.registration,
+        &username,
+        pass_hash,
+        invite_code.as_deref(),
+    )
+    .await?;
+
+    record_audit(Some(user.id), AuditKind::UserRegistered, username.0).await;
+    if !user.is_active() {
+        return Ok(user.status);
+    }
+
+    let session = auth_store
+        .issue_session(
+            &user.id,
+            LOCAL_CONF.privacy.ip_storage.session_ip(client_ip),
+            UserAgentHash::from_headers(&headers),
+        )
+        .await?;
+    set_session_cookie(&response, session.id.as_str());
+
+    Ok(user.status)
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn login_only_redirects_within_the_site() {
+        assert_eq!(login_redirect_target(None), "/");
+        assert_eq!(
+            login_redirect_target(Some("/settings?tab=security")),
+            "/settings?tab=security"
+        );
+        for outside in [
+            "https://example.com",
+            "//example.com",
+            "/\\example.com",
+            "settings",
+            "/\nLocation: x",
+        ] {
+            assert_eq!(login_redirect_target(Some(outside)), "/", "{outside}");
+        }
+    }
+
+    #[test]
+    fn server_side_login_redirect_sets_the_location() {
+        use axum::http::{HeaderMap, StatusCode, header::LOCATION};
+        use leptos::server_fn::redirect::REDIRECT_HEADER;
+
+        // a plain form post, without JavaScript
+        let mut headers = HeaderMap::new();
+        let status = login_redirect(&mut headers, "/settings", true);
+        assert_eq!(status, Some(StatusCode::SEE_OTHER));
+        assert_eq!(headers.get(LOCATION).unwrap(), "/settings");
+
+        // from the hydrated page, which navigates on leptos' header
+        let mut headers = HeaderMap::new();
+        assert_eq!(login_redirect(&mut headers, "/settings", false), None);
+        assert_eq!(headers.get(LOCATION).unwrap(), "/settings");
+        assert_eq!(headers.get(REDIRECT_HEADER).unwrap(), "/settings");
+    }
+
+    #[tokio::test]
+    async fn register_refuses_forged_calls() {
+        use crate::webui::csrf::forged_csrf_call;
+
+        let refused = forged_csrf_call(|csrf_token| {
+            register(
+                "alice".to_string(),
+                "password".to_string(),
+                csrf_token,
+                None,
+            )
+        })
+        .await;
+        assert_eq!(refused, "Invalid CSRF token, please reload the page");
+    }
+}
@@ -0,0 +1,421 @@
+use leptos::prelude::*;
+use leptos_router::components::Redirect;
+use leptos_router::hooks::use_query_map;
+
+use crate::types::{Role, SessionInfo};
+use crate::webui::account::ChangeUsernameModal;
+use crate::webui::audit_log::AuditLogPanel;
+use crate::webui::components::{ConfirmModal, ToastContext, ToastKind};
+use crate::webui::csrf::CsrfContext;
+use crate::webui::maintenance::MaintenancePanel;
+use crate::webui::user_admin::UserAdminPanel;
+use crate::webui::{
+    CurrentUser, LogoutAll, change_password, get_account_overview, get_current_user,
+    get_my_sessions,
+};
+
+/// A section of the settings screen, picked with `?tab=`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsTab {
+    Profile,
+    Security,
+    /// Instance administration, only offered to admins
+    Instance,
+}
+
+impl SettingsTab {
+    /// Value of the `tab` query parameter
+    pub fn slug(self) -> &'static str {
+        match self {
+            SettingsTab::Profile => "profile",
+            SettingsTab::Security => "security",
+            SettingsTab::Instance => "instance",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SettingsTab::Profile => "Profile",
+            SettingsTab::Security => "Security",
+            SettingsTab::Instance => "Instance",
+        }
+    }
+
+    /// The tabs `role` may open, in display order
+    pub fn visible_to(role: Role) -> &'static [SettingsTab] {
+        match role {
+            Role::Admin => &[
+                SettingsTab::Profile,
+                SettingsTab::Security,
+                SettingsTab::Instance,
+            ],
+            Role::User => &[SettingsTab::Profile, SettingsTab::Security],
+        }
+    }
+
+    /// The tab named by `slug`, falling back to the profile for unknown names and tabs
+    /// `role` may not open
+    pub fn from_query(slug: Option<&str>, role: Role) -> Self {
+        Self::visible_to(role)
+            .iter()
+            .copied()
+            .find(|tab| Some(tab.slug()) == slug)
+            .unwrap_or(SettingsTab::Profile)
+    }
+}
+
+/// `/settings`: account management for the signed-in user. Visitors who aren't signed in
+/// are sent to the login screen.
+#[component]
+pub fn SettingsScreen() -> impl IntoView {
+    let auth_user = Resource::new(|| (), |_| get_current_user());
+    let fallback =
+        || view! { <div class="min-h-screen flex items-center justify-center">"Loading..."</div> };
+
+    view! {
+        <Suspense fallback=fallback>
+            {move || {
+                auth_user.get().map(|result| match result {
+                    Ok(Some(user)) => view! { <SettingsPage user=user /> }.into_any(),
+                    // back here once signed in
+                    _ => view! { <Redirect path="/?redirect_to=/settings" /> }.into_any(),
+                })
+            }}
+        </Suspense>
+    }
+}
+
+#[component]
+fn SettingsPage(user: CurrentUser) -> impl IntoView {
+    let query = use_query_map();
+    let role = user.role;
+    let active = Memo::new(move |_| {
+        query.with(|query| SettingsTab::from_query(query.get("tab").as_deref(), role))
+    });
+
+    view! {
+        <div class="min-h-screen bg-[#13141c] text-white font-sans selection:bg-orange-500/30">
+            <main class="max-w-4xl mx-auto px-6 py-10">
+                <div class="flex items-center justify-between mb-8">
+                    <h1 class="text-3xl font-bold tracking-tight">"Settings"</h1>
+                    <a href="/" class="text-sm text-gray-400 hover:text-white transition">"Back to projects"</a>
+                </div>
+
+                <nav class="flex gap-1 border-b border-gray-800/60 mb-8">
+                    {SettingsTab::visible_to(role).iter().copied().map(|tab| view! {
+                        <a
+                            href=format!("/settings?tab={}", tab.slug())
+                            class="px-4 py-2 text-sm font-medium border-b-2 -mb-px transition"
+                            class=("border-orange-500", move || active.get() == tab)
+                            class=("text-white", move || active.get() == tab)
+                            class=("border-transparent", move || active.get() != tab)
+                            class=("text-gray-400", move || active.get() != tab)
+                        >
+                            {tab.label()}
+                        </a>
+                    }).collect_view()}
+                </nav>
+
+                // only the open tab is mounted, so each loads its data when first shown
+                {move || match active.get() {
+                    SettingsTab::Profile => view! { <ProfileTab user=user.clone() /> }.into_any(),
+                    SettingsTab::Security => view! { <SecurityTab /> }.into_any(),
+                    SettingsTab::Instance => view! { <InstanceTab /> }.into_any(),
+                }}
+            </main>
+        </div>
+    }
+}
+
+#[component]
+fn ProfileTab(user: CurrentUser) -> impl IntoView {
+    let overview = Resource::new(|| (), |_| get_account_overview());
+    let (show_rename, set_show_rename) = signal(false);
+    let role = match user.role {
+        Role::Admin => "Administrator",
+        Role::User => "User",
+    };
+
+    view! {
+        <section class="bg-[#1e1f25] border border-gray-800/60 rounded-2xl p-6 space-y-4">
+            <div class="flex items-center justify-between">
+                <div>
+                    <p class="text-gray-500 text-xs">"Username"</p>
+                    <p class="text-gray-100 font-medium">{user.username.clone()}</p>
+                </div>
+                <button
+                    type="button"
+                    class="text-xs font-medium text-gray-300 hover:text-white bg-gray-700 hover:bg-gray-600 py-1 px-3 rounded-lg transition"
+                    on:click=move |_| set_show_rename.set(true)
+                >
+                    "Change"
+                </button>
+            </div>
+            <div>
+                <p class="text-gray-500 text-xs">"Role"</p>
+                <p class="text-gray-100">{role}</p>
+            </div>
+            <Suspense fallback=|| view! { <p class="text-gray-500 text-xs">"Loading..."</p> }>
+                {move || overview.get().map(|result| match result {
+                    Ok(overview) => view! {
+                        <div>
+                            <p class="text-gray-500 text-xs">"Projects"</p>
+                            <p class="text-gray-100">{overview.project_count}</p>
+                        </div>
+                    }.into_any(),
+                    Err(e) => view! { <p class="text-red-400 text-xs">{e.to_string()}</p> }.into_any(),
+                })}
+            </Suspense>
+        </section>
+
+        <ChangeUsernameModal
+            open=show_rename
+            current=user.username
+            on_close=move || set_show_rename.set(false)
+        />
+    }
+}
+
+#[component]
+fn SecurityTab() -> impl IntoView {
+    let sessions = Resource::new(|| (), |_| get_my_sessions());
+    let logout_all_action = ServerAction::<LogoutAll>::new();
+    let (show_logout_all_confirm, set_show_logout_all_confirm) = signal(false);
+    let csrf = expect_context::<CsrfContext>();
+    let toasts = expect_context::<ToastContext>();
+
+    // The login screen reports how many sessions were ended
+    Effect::watch(
+        move || logout_all_action.value().get(),
+        move |result, _, _| match result {
+            Some(Ok(count)) => {
+                let _ = window()
+                    .location()
+                    .set_href(&format!("/?signed_out={count}"));
+            }
+            Some(Err(e)) => {
+                set_show_logout_all_confirm.set(false);
+                toasts.push_toast(ToastKind::Error, e.to_string());
+            }
+            None => {}
+        },
+        false,
+    );
+
+    view! {
+        <ChangePasswordForm on_changed=move || sessions.refetch() />
+
+        <section class="mt-8">
+            <div class="flex items-center justify-between mb-4">
+                <h2 class="text-xl font-bold tracking-tight">"Sessions"</h2>
+                <button
+                    type="button"
+                    class="text-xs font-medium text-red-400 hover:text-red-300 bg-gray-700 hover:bg-gray-600 py-1 px-3 rounded-lg transition"
+                    on:click=move |_| set_show_logout_all_confirm.set(true)
+                >
+                    "Log out of all devices"
+                </button>
+            </div>
+            <Suspense fallback=|| view! { <p class="text-gray-500 text-sm">"Loading sessions..."</p> }>
+                {move || sessions.get().map(|result| match result {
+                    Ok(sessions) => view! {
+                        <ul class="bg-[#1e1f25] border border-gray-800/60 rounded-2xl divide-y divide-gray-800/60">
+                            {sessions.into_iter().map(|session| view! { <SessionRow session=session /> }).collect_view()}
+                        </ul>
+                    }.into_any(),
+                    Err(e) => view! { <p class="text-red-400 text-sm">{e.to_string()}</p> }.into_any(),
+                })}
+            </Suspense>
+        </section>
+
+        <ConfirmModal
+            open=show_logout_all_confirm
+            title="Log out of all devices?"
+            body="Every session of your account ends, including this one. Anyone else using it will have to sign in again."
+            confirm_label="Log out everywhere"
+            pending_label="Logging out..."
+            pending=logout_all_action.pending()
+            danger=true
+            on_confirm=move || {
+                logout_all_action.dispatch(LogoutAll { csrf_token: csrf.token() });
+            }
+            on_cancel=move || set_show_logout_all_confirm.set(false)
+        />
+    }
+}
+
+#[component]
+fn SessionRow(session: SessionInfo) -> impl IntoView {
+    let seen = session.last_seen_at;
+    let last_seen = format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        seen.year(),
+        seen.month() as u8,
+        seen.day(),
+        seen.hour(),
+        seen.minute()
+    );
+
+    view! {
+        <li class="flex items-center justify-between px-4 py-3 text-sm">
+            <span class="text-gray-200 font-mono">{session.ip.to_string()}</span>
+            <span class="text-gray-500 text-xs">"Last seen "{last_seen}</span>
+        </li>
+    }
+}
+
+#[component]
+fn ChangePasswordForm(#[prop(into)] on_changed: Callback<()>) -> impl IntoView {
+    let csrf = expect_context::<CsrfContext>();
+    let toasts = expect_context::<ToastContext>();
+    let (current, set_current) = signal(String::new());
+    let (new_password, set_new_password) = signal(String::new());
+    let (confirm, set_confirm) = signal(String::new());
+
+    let change_action = Action::new(move |(current, new_password): &(String, String)| {
+        change_password(current.clone(), new_password.clone(), csrf.token())
+    });
+    let pending = change_action.pending();
+    Effect::watch(
+        move || change_action.value().get(),
+        move |result, _, _| {
+            if let Some(Ok(revoked)) = result {
+                set_current.set(String::new());
+                set_new_password.set(String::new());
+                set_confirm.set(String::new());
+                toasts.push_toast(
+                    ToastKind::Success,
+                    format!("Password changed, {revoked} other session(s) signed out"),
+                );
+                on_changed.run(());
+            }
+        },
+        false,
+    );
+
+    // errors about one input are shown next to it, the rest below the form
+    let field_error = move |field: &'static str| {
+        change_action
+            .value()
+            .get()
+            .and_then(Result::err)
+            .filter(|e| e.field() == Some(field))
+            .map(|e| e.to_string())
+    };
+    let other_error = move || {
+        change_action
+            .value()
+            .get()
+            .and_then(Result::err)
+            .filter(|e| e.field().is_none())
+            .map(|e| e.to_string())
+    };
+    let mismatch = move || !confirm.get().is_empty() && confirm.get() != new_password.get();
+
+    let input_class = "w-full bg-[#252630] border border-gray-700 rounded-lg px-3 py-2 text-white text-sm focus:outline-none focus:border-orange-500 transition";
+
+    view! {
+        <section>
+            <h2 class="text-xl font-bold tracking-tight mb-4">"Change password"</h2>
+            <form
+                class="bg-[#1e1f25] border border-gray-800/60 rounded-2xl p-6 space-y-4 max-w-md"
+                on:submit=move |ev| {
+                    ev.prevent_default();
+                    if !mismatch() {
+                        change_action.dispatch((current.get(), new_password.get()));
+                    }
+                }
+            >
+                <div>
+                    <label class="text-gray-400 text-xs">"Current password"</label>
+                    <input
+                        type="password"
+                        required
+                        autocomplete="current-password"
+                        class=input_class
+                        prop:value=move || current.get()
+                        on:input=move |ev| set_current.set(event_target_value(&ev))
+                    />
+                    {move || field_error("current_password").map(|e| view! { <p class="text-red-400 text-xs mt-1">{e}</p> })}
+                </div>
+                <div>
+                    <label class="text-gray-400 text-xs">"New password"</label>
+                    <input
+                        type="password"
+                        required
+                        autocomplete="new-password"
+                        class=input_class
+                        prop:value=move || new_password.get()
+                        on:input=move |ev| set_new_password.set(event_target_value(&ev))
+                    />
+                    {move || field_error("new_password").map(|e| view! { <p class="text-red-400 text-xs mt-1">{e}</p> })}
+                </div>
+                <div>
+                    <label class="text-gray-400 text-xs">"Repeat new password"</label>
+                    <input
+                        type="password"
+                        required
+                        autocomplete="new-password"
+                        class=input_class
+                        prop:value=move || confirm.get()
+                        on:input=move |ev| set_confirm.set(event_target_value(&ev))
+                    />
+                    <Show when=mismatch>
+                        <p class="text-red-400 text-xs mt-1">"The passwords don't match"</p>
+                    </Show>
+                </div>
+                {move || other_error().map(|e| view! { <p class="text-red-400 text-xs">{e}</p> })}
+                <button
+                    type="submit"
+                    class="bg-[var(--bento-primary)] hover:brightness-110 text-white text-sm font-semibold py-2 px-4 rounded-lg transition disabled:opacity-50"
+                    disabled=move || pending.get() || mismatch()
+                >
+                    {move || if pending.get() { "Saving..." } else { "Change password" }}
+                </button>
+            </form>
+        </section>
+    }
+}
+
+#[component]
+fn InstanceTab() -> impl IntoView {
+    view! {
+        <UserAdminPanel />
+        <MaintenancePanel />
+        <AuditLogPanel />
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tabs_follow_the_query_parameter() {
+        assert_eq!(
+            SettingsTab::from_query(Some("security"), Role::User),
+            SettingsTab::Security
+        );
+        assert_eq!(
+            SettingsTab::from_query(None, Role::User),
+            SettingsTab::Profile
+        );
+        assert_eq!(
+            SettingsTab::from_query(Some("nonsense"), Role::Admin),
+            SettingsTab::Profile
+        );
+    }
+
+    #[test]
+    fn instance_tab_is_for_admins_only() {
+        assert_eq!(
+            SettingsTab::from_query(Some("instance"), Role::Admin),
+            SettingsTab::Instance
+        );
+        assert_eq!(
+            SettingsTab::from_query(Some("instance"), Role::User),
+            SettingsTab::Profile
+        );
+        assert!(!SettingsTab::visible_to(Role::User).contains(&SettingsTab::Instance));
+    }
+}
@@ -0,0 +1,163 @@
+use std::time::Duration;
+
+use leptos::prelude::*;
+use time::OffsetDateTime;
+
+use crate::types::AppError;
+use crate::webui::components::{ToastContext, ToastKind};
+use crate::webui::csrf::CsrfContext;
+use crate::webui::extend_my_session;
+
+/// How long before the session runs out the warning shows up
+pub const SESSION_WARNING_LEAD: Duration = Duration::from_secs(5 * 60);
+
+/// How often the banner re-reads the clock
+const TICK: Duration = Duration::from_secs(15);
+
+/// Where a session stands relative to its expiry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionExpiry {
+    /// More than [`SESSION_WARNING_LEAD`] left, nothing to show
+    Fine,
+    /// Close enough to the end that the user should be asked to stay signed in
+    ExpiresSoon { remaining: Duration },
+    /// Already over; the next request will be refused
+    Expired,
+}
+
+/// Time left until `expires_at`, zero once it has passed.
+pub fn time_remaining(expires_at: OffsetDateTime, now: OffsetDateTime) -> Duration {
+    Duration::try_from(expires_at - now).unwrap_or(Duration::ZERO)
+}
+
+pub fn session_expiry(expires_at: OffsetDateTime, now: OffsetDateTime) -> SessionExpiry {
+    match time_remaining(expires_at, now) {
+        Duration::ZERO => SessionExpiry::Expired,
+        remaining if remaining <= SESSION_WARNING_LEAD => SessionExpiry::ExpiresSoon { remaining },
+        _ => SessionExpiry::Fine,
+    }
+}
+
+/// "in 3 minutes", rounded up so the banner never claims zero minutes while time is left
+fn minutes_phrase(remaining: Duration) -> String {
+    match remaining.as_secs().div_ceil(60) {
+        1 => "in 1 minute".to_string(),
+        minutes => format!("in {minutes} minutes"),
+    }
+}
+
+/// Banner warning that the session is about to run out, with a button that extends it.
+/// Once the session is gone it offers to sign in again instead.
+#[component]
+pub fn SessionExpiryBanner(expires_at: OffsetDateTime) -> impl IntoView {
+    let csrf = expect_context::<CsrfContext>();
+    let toasts = expect_context::<ToastContext>();
+    let expires_at = RwSignal::new(expires_at);
+
+    // Unknown until hydrated, so the server render and the first client render agree
+    let now = RwSignal::new(None::<OffsetDateTime>);
+    Effect::new(move |_| {
+        now.set(Some(OffsetDateTime::now_utc()));
+        if let Ok(handle) =
+            set_interval_with_handle(move || now.set(Some(OffsetDateTime::now_utc())), TICK)
+        {
+            on_cleanup(move || handle.clear());
+        }
+    });
+
+    let extend_action: Action<(), Result<OffsetDateTime, AppError>> =
+        Action::new(move |_: &()| extend_my_session(csrf.token()));
+    Effect::watch(
+        move || extend_action.value().get(),
+        move |result, _, _| match result {
+            Some(Ok(new_expiry)) => {
+                expires_at.set(*new_expiry);
+                toasts.push_toast(ToastKind::Success, "You're still signed in");
+            }
+            Some(Err(e)) => toasts.push_toast(ToastKind::Error, e.to_string()),
+            None => {}
+        },
+        false,
+    );
+
+    let state = move || {
+        now.get()
+            .map(|now| session_expiry(expires_at.get(), now))
+            .unwrap_or(SessionExpiry::Fine)
+    };
+
+    view! {
+        {move || match state() {
+            SessionExpiry::Fine => None,
+            SessionExpiry::ExpiresSoon { remaining } => Some(view! {
+                <div class="bg-amber-500/10 border-b border-amber-500/30 text-amber-200 text-sm">
+                    <div class="max-w-7xl mx-auto px-6 py-2 flex items-center justify-between gap-4">
+                        <span>{format!("Your session expires {}.", minutes_phrase(remaining))}</span>
+                        <button
+                            class="px-3 py-1 rounded bg-amber-500/20 hover:bg-amber-500/30 text-amber-100 disabled:opacity-50"
+                            disabled=move || extend_action.pending().get()
+                            on:click=move |_| {
+                                extend_action.dispatch(());
+                            }
+                        >
+                            "Stay signed in"
+                        </button>
+                    </div>
+                </div>
+            }.into_any()),
+            SessionExpiry::Expired => Some(view! {
+                <div class="bg-red-500/10 border-b border-red-500/30 text-red-200 text-sm">
+                    <div class="max-w-7xl mx-auto px-6 py-2 flex items-center justify-between gap-4">
+                        <span>"Your session has expired."</span>
+                        <a href="/" rel="external" class="underline hover:text-red-100">"Sign in again"</a>
+                    </div>
+                </div>
+            }.into_any()),
+        }}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: OffsetDateTime = OffsetDateTime::UNIX_EPOCH;
+
+    #[test]
+    fn time_remaining_counts_down_to_zero() {
+        let expires_at = NOW + time::Duration::minutes(3);
+        assert_eq!(time_remaining(expires_at, NOW), Duration::from_secs(180));
+        assert_eq!(time_remaining(expires_at, expires_at), Duration::ZERO);
+    }
+
+    #[test]
+    fn already_expired_sessions_have_no_time_left() {
+        let expires_at = NOW - time::Duration::seconds(1);
+        assert_eq!(time_remaining(expires_at, NOW), Duration::ZERO);
+        assert_eq!(session_expiry(expires_at, NOW), SessionExpiry::Expired);
+    }
+
+    #[test]
+    fn warning_starts_five_minutes_before_expiry() {
+        let far = NOW + time::Duration::minutes(30);
+        assert_eq!(session_expiry(far, NOW), SessionExpiry::Fine);
+
+        let edge = NOW + time::Duration::minutes(5);
+        assert_eq!(
+            session_expiry(edge, NOW),
+            SessionExpiry::ExpiresSoon {
+                remaining: SESSION_WARNING_LEAD
+            }
+        );
+
+        let just_outside = edge + time::Duration::seconds(1);
+        assert_eq!(session_expiry(just_outside, NOW), SessionExpiry::Fine);
+    }
+
+    #[test]
+    fn minutes_round_up() {
+        assert_eq!(minutes_phrase(Duration::from_secs(1)), "in 1 minute");
+        assert_eq!(minutes_phrase(Duration::from_secs(61)), "in 2 minutes");
+        assert_eq!(minutes_phrase(SESSION_WARNING_LEAD), "in 5 minutes");
+    }
+}
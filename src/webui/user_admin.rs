@@ -0,0 +1,228 @@
+use leptos::prelude::*;
+
+use crate::types::{Role, UserId, UserStatus, UserSummary};
+use crate::webui::components::ConfirmModal;
+use crate::webui::csrf::CsrfContext;
+use crate::webui::{admin_unlock_user, impersonate, list_users, set_user_status};
+
+/// User list with enable/disable toggles (admin only).
+#[component]
+pub fn UserAdminPanel() -> impl IntoView {
+    let users = Resource::new(|| (), |_| list_users());
+    let csrf = expect_context::<CsrfContext>();
+
+    let status_action = Action::new(move |(target, status): &(UserId, UserStatus)| {
+        let target = *target;
+        let status = *status;
+        let csrf_token = csrf.token();
+        async move { set_user_status(target, status, csrf_token).await }
+    });
+    let unlock_action = Action::new(move |target: &UserId| {
+        let target = *target;
+        let csrf_token = csrf.token();
+        async move { admin_unlock_user(target, csrf_token).await }
+    });
+    let impersonate_action = Action::new(move |target: &UserId| {
+        let target = *target;
+        let csrf_token = csrf.token();
+        async move { impersonate(target, csrf_token).await }
+    });
+    Effect::watch(
+        move || impersonate_action.value().get(),
+        move |result, _, _| {
+            // full reload, the whole page is now the user's
+            if matches!(result.as_ref(), Some(Ok(()))) {
+                let _ = window().location().set_href("/");
+            }
+        },
+        false,
+    );
+    Effect::watch(
+        move || unlock_action.value().get(),
+        move |result, _, _| {
+            if matches!(result.as_ref(), Some(Ok(_))) {
+                users.refetch();
+            }
+        },
+        false,
+    );
+
+    // Disabling signs the user out everywhere, so it is confirmed first
+    let (pending_disable, set_pending_disable) = signal(None::<UserSummary>);
+    Effect::watch(
+        move || status_action.value().get(),
+        move |result, _, _| {
+            if matches!(result.as_ref(), Some(Ok(_))) {
+                set_pending_disable.set(None);
+                users.refetch();
+            }
+        },
+        false,
+    );
+    let action_error = move || {
+        status_action
+            .value()
+            .get()
+            .and_then(Result::err)
+            .or_else(|| unlock_action.value().get().and_then(Result::err))
+            .or_else(|| impersonate_action.value().get().and_then(Result::err))
+            .map(|e| e.to_string())
+    };
+    let pending = Signal::derive(move || {
+        status_action.pending().get()
+            || unlock_action.pending().get()
+            || impersonate_action.pending().get()
+    });
+
+    let on_toggle = Callback::new(move |user: UserSummary| match user.status {
+        UserStatus::Active => set_pending_disable.set(Some(user)),
+        UserStatus::Disabled => {
+            status_action.dispatch((user.id, UserStatus::Active));
+        }
+    });
+
+    view! {
+        <section id="manage-users" class="mt-12">
+            <h2 class="text-xl font-bold mb-4 tracking-tight">"Manage Users"</h2>
+
+            <Show when=move || action_error().is_some()>
+                <p class="text-sm text-red-400 mb-4">{move || action_error().unwrap_or_default()}</p>
+            </Show>
+
+            <Suspense fallback=|| view! { <p class="text-gray-500 text-sm">"Loading users..."</p> }>
+                {move || {
+                    users.get().map(|result| match result {
+                        Ok(users) => view! {
+                            <div class="bg-[#1e1f25] border border-gray-800/60 rounded-2xl overflow-hidden">
+                                <table class="w-full text-sm text-left">
+                                    <thead class="text-gray-500 border-b border-gray-800/60">
+                                        <tr>
+                                            <th class="px-4 py-3 font-medium">"Username"</th>
+                                            <th class="px-4 py-3 font-medium">"Role"</th>
+                                            <th class="px-4 py-3 font-medium">"Status"</th>
+                                            <th class="px-4 py-3 font-medium"></th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {users.into_iter().map(|user| view! {
+                                            <UserRow
+                                                user=user
+                                                pending=pending
+                                                on_toggle=on_toggle
+                                                on_unlock=Callback::new(move |id| {
+                                                    unlock_action.dispatch(id);
+                                                })
+                                                on_impersonate=Callback::new(move |id| {
+                                                    impersonate_action.dispatch(id);
+                                                })
+                                            />
+                                        }).collect_view()}
+                                    </tbody>
+                                </table>
+                            </div>
+                        }.into_any(),
+                        Err(e) => view! {
+                            <div class="bg-red-900/20 border border-red-800 rounded-2xl p-6 text-red-400">
+                                <p class="font-medium">"Failed to load users"</p>
+                                <p class="text-sm mt-1">{e.to_string()}</p>
+                            </div>
+                        }.into_any(),
+                    })
+                }}
+            </Suspense>
+
+            <ConfirmModal
+                open=Signal::derive(move || pending_disable.get().is_some())
+                title="Disable this user?"
+                body="They will be signed out everywhere and can't log in until re-enabled. Their projects and history are kept."
+                confirm_label="Disable"
+                pending_label="Disabling..."
+                pending=status_action.pending()
+                danger=true
+                on_confirm=move || {
+                    if let Some(user) = pending_disable.get_untracked() {
+                        status_action.dispatch((user.id, UserStatus::Disabled));
+                    }
+                }
+                on_cancel=move || set_pending_disable.set(None)
+            />
+        </section>
+    }
+}
+
+#[component]
+fn UserRow(
+    user: UserSummary,
+    #[prop(into)] pending: Signal<bool>,
+    on_toggle: Callback<UserSummary>,
+    on_unlock: Callback<UserId>,
+    on_impersonate: Callback<UserId>,
+) -> impl IntoView {
+    let role = match user.role {
+        Role::Admin => "Admin",
+        Role::User => "User",
+    };
+    let (status_class, toggle_label) = match user.status {
+        UserStatus::Active => ("text-green-400", "Disable"),
+        UserStatus::Disabled => ("text-red-400", "Enable"),
+    };
+    let status = user.status.label();
+    let username = user.username.clone();
+    let id = user.id;
+    let locked_until = user.locked_until.map(|until| {
+        format!(
+            "Locked until {:04}-{:02}-{:02} {:02}:{:02} UTC",
+            until.year(),
+            until.month() as u8,
+            until.day(),
+            until.hour(),
+            until.minute()
+        )
+    });
+    let is_locked = locked_until.is_some();
+    // admins can't be impersonated, and disabled users can't have sessions
+    let can_impersonate = user.role == Role::User && user.status == UserStatus::Active;
+
+    view! {
+        <tr class="border-b border-gray-800/40 last:border-0">
+            <td class="px-4 py-2 text-gray-200">{username}</td>
+            <td class="px-4 py-2 text-gray-400">{role}</td>
+            <td class=format!("px-4 py-2 {status_class}")>
+                {status}
+                {locked_until.map(|locked| view! {
+                    <span class="block text-xs text-amber-400">{locked}</span>
+                })}
+            </td>
+            <td class="px-4 py-2 text-right space-x-2">
+                {is_locked.then(|| view! {
+                    <button
+                        type="button"
+                        class="text-xs font-medium text-amber-300 hover:text-white bg-gray-700 hover:bg-gray-600 py-1 px-3 rounded-lg transition disabled:opacity-50"
+                        disabled=move || pending.get()
+                        on:click=move |_| on_unlock.run(id)
+                    >
+                        "Unlock"
+                    </button>
+                })}
+                {can_impersonate.then(|| view! {
+                    <button
+                        type="button"
+                        class="text-xs font-medium text-fuchsia-300 hover:text-white bg-gray-700 hover:bg-gray-600 py-1 px-3 rounded-lg transition disabled:opacity-50"
+                        disabled=move || pending.get()
+                        on:click=move |_| on_impersonate.run(id)
+                    >
+                        "Impersonate"
+                    </button>
+                })}
+                <button
+                    type="button"
+                    class="text-xs font-medium text-gray-300 hover:text-white bg-gray-700 hover:bg-gray-600 py-1 px-3 rounded-lg transition disabled:opacity-50"
+                    disabled=move || pending.get()
+                    on:click=move |_| on_toggle.run(user.clone())
+                >
+                    {toggle_label}
+                </button>
+            </td>
+        </tr>
+    }
+}